@@ -0,0 +1,156 @@
+// In-memory cache of rendered plots for servers that re-render the same chart
+// (enabled with the `cache` feature)
+
+use anyhow::{anyhow, Context, Result};
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+use crate::data::PlotData;
+use crate::{csv_reader, parser, preprocessor, runtime, RenderOptions};
+
+/// Default number of rendered outputs kept by `RenderCache::default()`
+pub const DEFAULT_CAPACITY: usize = 100;
+
+/// SHA-256 of the DSL, CSV bytes, render options (as JSON) and variables
+pub type CacheKey = [u8; 32];
+
+struct CacheEntry {
+    bytes: Vec<u8>,
+    created: Instant,
+}
+
+/// Least-recently-used cache of rendered PNG/SVG bytes keyed by everything that affects the output
+pub struct RenderCache {
+    inner: LruCache<CacheKey, CacheEntry>,
+    ttl: Option<Duration>,
+}
+
+impl Default for RenderCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl RenderCache {
+    /// A cache holding at most `capacity` outputs (at least 1); entries never expire
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self { inner: LruCache::new(capacity), ttl: None }
+    }
+
+    /// Re-render entries older than `ttl` (for data that changes behind the same request)
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Number of cached outputs, including expired ones not yet evicted
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Render `dsl` against `csv`, returning the cached bytes for an identical earlier request.
+    /// Failed renders are not cached.
+    pub fn render_cached(
+        &mut self,
+        dsl: &str,
+        csv: &[u8],
+        options: RenderOptions,
+        variables: &HashMap<String, String>,
+    ) -> Result<Vec<u8>> {
+        let key = cache_key(dsl, csv, &options, variables)?;
+        let ttl = self.ttl;
+        if let Some(entry) = self.inner.get(&key) {
+            if ttl.is_none_or(|ttl| entry.created.elapsed() < ttl) {
+                return Ok(entry.bytes.clone());
+            }
+        }
+
+        let bytes = render(dsl, csv, options, variables)?;
+        self.inner.put(key, CacheEntry { bytes: bytes.clone(), created: Instant::now() });
+        Ok(bytes)
+    }
+}
+
+/// Hash each input with a length prefix so that shifting bytes between fields changes the key
+fn cache_key(dsl: &str, csv: &[u8], options: &RenderOptions, variables: &HashMap<String, String>) -> Result<CacheKey> {
+    let mut hasher = Sha256::new();
+    let mut field = |bytes: &[u8]| {
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(bytes);
+    };
+    field(dsl.as_bytes());
+    field(csv);
+    field(&serde_json::to_vec(options).context("Failed to serialize render options")?);
+
+    let mut names: Vec<&String> = variables.keys().collect();
+    names.sort();
+    for name in names {
+        field(name.as_bytes());
+        field(variables[name].as_bytes());
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// The uncached pipeline: expand variables, parse, read the CSV and render
+fn render(dsl: &str, csv: &[u8], options: RenderOptions, variables: &HashMap<String, String>) -> Result<Vec<u8>> {
+    let dsl = preprocessor::expand_variables(dsl, variables).context("Failed to expand variables")?;
+    let (spec, _warnings) = parser::parse_plot_spec(&dsl).map_err(|errors| {
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        anyhow!("Parse error {}", messages.join("\nParse error "))
+    })?;
+    let data = csv_reader::read_csv_from_reader(csv)?;
+    runtime::render_plot(spec, PlotData::from_csv(data), options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV: &[u8] = b"x,y\n1,10\n2,20\n3,15\n";
+
+    #[test]
+    fn test_render_cached_reuses_identical_requests() {
+        let mut cache = RenderCache::default();
+        let vars = HashMap::new();
+        let first = cache.render_cached("aes(x: x, y: y) | line()", CSV, RenderOptions::default(), &vars).unwrap();
+        let second = cache.render_cached("aes(x: x, y: y) | line()", CSV, RenderOptions::default(), &vars).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(cache.len(), 1);
+
+        // Any input that affects the output is part of the key
+        cache.render_cached("aes(x: x, y: y) | point()", CSV, RenderOptions::default(), &vars).unwrap();
+        let wide = RenderOptions { width: 400, ..RenderOptions::default() };
+        cache.render_cached("aes(x: x, y: y) | line()", CSV, wide, &vars).unwrap();
+        let vars = HashMap::from([("c".to_string(), "red".to_string())]);
+        cache.render_cached("aes(x: x, y: y) | line()", CSV, RenderOptions::default(), &vars).unwrap();
+        assert_eq!(cache.len(), 4);
+
+        assert!(cache.render_cached("aes(x: x, y: y) | nope()", CSV, RenderOptions::default(), &vars).is_err());
+        assert_eq!(cache.len(), 4);
+    }
+
+    #[test]
+    fn test_render_cache_evicts_least_recently_used_and_expires() {
+        let mut cache = RenderCache::new(1);
+        let vars = HashMap::new();
+        cache.render_cached("aes(x: x, y: y) | line()", CSV, RenderOptions::default(), &vars).unwrap();
+        cache.render_cached("aes(x: x, y: y) | point()", CSV, RenderOptions::default(), &vars).unwrap();
+        assert_eq!(cache.len(), 1);
+
+        let key = cache_key("aes(x: x, y: y) | point()", CSV, &RenderOptions::default(), &vars).unwrap();
+        cache.inner.get_mut(&key).unwrap().bytes = b"stale".to_vec();
+        let cached = cache.render_cached("aes(x: x, y: y) | point()", CSV, RenderOptions::default(), &vars).unwrap();
+        assert_eq!(cached, b"stale");
+
+        let mut cache = cache.with_ttl(Duration::ZERO);
+        let fresh = cache.render_cached("aes(x: x, y: y) | point()", CSV, RenderOptions::default(), &vars).unwrap();
+        assert_ne!(fresh, b"stale");
+    }
+}
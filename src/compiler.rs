@@ -1,10 +1,11 @@
 use anyhow::Result;
-use crate::ir::{RenderData, ScaleSystem, ResolvedSpec, SceneGraph, PanelScene, DrawCommand, RenderStyle};
-use crate::parser::ast::{Layer, BarPosition};
+use crate::ir::{RenderData, ScaleSystem, ResolvedSpec, SceneGraph, PanelScene, DrawCommand, RenderStyle, LegendEntry, FacetLayout, GroupData, PanelScales, Scale};
+use crate::parser::ast::{Layer, ArrowEnds, ArrowType, LayerPosition, BeeswarmPriority, FacetSpace, LineLayer, OutOfBounds, PointLayer, RibbonLayer, SeType, Stat, StepDirection, ViolinSide};
 use crate::graph::{LineStyle, PointStyle, BarStyle, BoxplotStyle, RibbonStyle};
 use crate::RenderOptions;
+use crate::runtime::{report, Progress};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 // =============================================================================
 // Boxplot Geometry Helpers
@@ -18,11 +19,19 @@ struct BoxplotGeometry {
     max_cap: Vec<(f64, f64)>,
     box_tl: (f64, f64),
     box_br: (f64, f64),
+    box_outline: Option<Vec<(f64, f64)>>, // Notched box, drawn as a polygon instead of the rect
     median_line: Vec<(f64, f64)>,
     outlier_points: Vec<(f64, f64)>,
 }
 
+/// Default notch waist width, as a fraction of the box width
+const DEFAULT_NOTCH_WIDTH: f64 = 0.5;
+
+/// Default whisker cap width, as a fraction of the box width
+const DEFAULT_STAPLE_WIDTH: f64 = 0.5;
+
 /// Calculates boxplot primitive geometry for a single boxplot
+#[allow(clippy::too_many_arguments)]
 fn compute_boxplot_geometry(
     x: f64,
     width: f64,
@@ -32,11 +41,30 @@ fn compute_boxplot_geometry(
     q3: f64,
     max: f64,
     outliers: &[f64],
+    notch: Option<(f64, f64, f64)>, // (low, high, waist width as a fraction of the box width)
+    staplewidth: f64,               // Whisker cap width as a fraction of the box width
     is_vertical: bool,
 ) -> BoxplotGeometry {
     let half_width = width / 2.0;
-    let cap_width = width * 0.4;
+    let cap_width = width * staplewidth;
     let cap_half = cap_width / 2.0;
+    let median_half = notch.map_or(half_width, |(_, _, waist)| half_width * waist);
+
+    // Notched outline, counter-clockwise from the bottom-left corner, as (x, value) pairs
+    let outline = notch.map(|(low, high, _)| {
+        vec![
+            (x - half_width, q1),
+            (x + half_width, q1),
+            (x + half_width, low),
+            (x + median_half, median),
+            (x + half_width, high),
+            (x + half_width, q3),
+            (x - half_width, q3),
+            (x - half_width, high),
+            (x - median_half, median),
+            (x - half_width, low),
+        ]
+    });
 
     if is_vertical {
         BoxplotGeometry {
@@ -46,7 +74,8 @@ fn compute_boxplot_geometry(
             max_cap: vec![(x - cap_half, max), (x + cap_half, max)],
             box_tl: (x - half_width, q3),
             box_br: (x + half_width, q1),
-            median_line: vec![(x - half_width, median), (x + half_width, median)],
+            box_outline: outline,
+            median_line: vec![(x - median_half, median), (x + median_half, median)],
             outlier_points: outliers.iter().map(|&v| (x, v)).collect(),
         }
     } else {
@@ -58,7 +87,8 @@ fn compute_boxplot_geometry(
             max_cap: vec![(max, x - cap_half), (max, x + cap_half)],
             box_tl: (q1, x - half_width),
             box_br: (q3, x + half_width),
-            median_line: vec![(median, x - half_width), (median, x + half_width)],
+            box_outline: outline.map(|points| points.into_iter().map(|(a, v)| (v, a)).collect()),
+            median_line: vec![(median, x - median_half), (median, x + median_half)],
             outlier_points: outliers.iter().map(|&v| (v, x)).collect(),
         }
     }
@@ -71,6 +101,7 @@ fn boxplot_component_styles(style: &BoxplotStyle) -> (LineStyle, BarStyle, LineS
         color: style.color.clone(),
         width: Some(2.0),
         alpha: style.alpha,
+        linetype: None,
     };
 
     // Box fill
@@ -78,6 +109,9 @@ fn boxplot_component_styles(style: &BoxplotStyle) -> (LineStyle, BarStyle, LineS
         color: style.color.clone(),
         alpha: style.alpha,
         width: None,
+        border_color: None,
+        border_width: None,
+        hollow: false,
     };
 
     // Median line - white for contrast
@@ -85,6 +119,7 @@ fn boxplot_component_styles(style: &BoxplotStyle) -> (LineStyle, BarStyle, LineS
         color: Some("white".to_string()),
         width: Some(2.0),
         alpha: Some(0.9),
+        linetype: None,
     };
 
     // Outliers - use outlier-specific style or fallback to main color
@@ -93,6 +128,7 @@ fn boxplot_component_styles(style: &BoxplotStyle) -> (LineStyle, BarStyle, LineS
         size: style.outlier_size,
         shape: style.outlier_shape.clone(),
         alpha: style.alpha,
+        fill: None,
     };
 
     (whisker_style, box_style, median_style, outlier_style)
@@ -124,69 +160,342 @@ fn interpolate_density_at_y(target_y: f64, density: &[f64], density_y: &[f64]) -
     }
 }
 
+/// Pick the legend title from `labs()` based on which aesthetic drives the legend.
+/// Bars, ribbons and violins use `color` as their fill, so `fill:` is preferred there.
+fn legend_title(spec: &ResolvedSpec) -> Option<String> {
+    let labels = &spec.labels;
+    for layer in &spec.layers {
+        let aes = &layer.aesthetics;
+        let fill_mapped = matches!(&layer.original_layer, Layer::Boxplot(b) if matches!(b.fill, Some(crate::parser::ast::AestheticValue::Mapped(_))));
+        if aes.color.is_some() {
+            let title = match &layer.original_layer {
+                Layer::Bar(_) | Layer::Ribbon(_) | Layer::Violin(_) => labels.fill_title.clone().or_else(|| labels.color_title.clone()),
+                _ => labels.color_title.clone().or_else(|| labels.fill_title.clone()),
+            };
+            if title.is_some() {
+                return title;
+            }
+        }
+        if fill_mapped && labels.fill_title.is_some() {
+            return labels.fill_title.clone();
+        }
+        if aes.size.is_some() && labels.size_title.is_some() {
+            return labels.size_title.clone();
+        }
+        if aes.shape.is_some() && labels.shape_title.is_some() {
+            return labels.shape_title.clone();
+        }
+        if aes.linetype.is_some() && labels.linetype_title.is_some() {
+            return labels.linetype_title.clone();
+        }
+    }
+    None
+}
+
 /// Compile data and scales into a SceneGraph of drawing commands
 pub fn compile_geometry(
     data: RenderData, 
     scales: ScaleSystem, 
     spec: &ResolvedSpec,
     options: &RenderOptions,
+) -> Result<SceneGraph> {
+    compile_geometry_with_progress(data, scales, spec, options, None)
+}
+
+/// `compile_geometry`, reporting each panel to a --progress callback
+pub(crate) fn compile_geometry_with_progress(
+    data: RenderData,
+    scales: ScaleSystem,
+    spec: &ResolvedSpec,
+    options: &RenderOptions,
+    progress: Progress,
 ) -> Result<SceneGraph> {
     let mut panels = Vec::new();
+    let panel_count = data.panels.len();
     let is_flipped = matches!(spec.coord, Some(crate::parser::ast::CoordSystem::Flip));
+    let legend_title = legend_title(spec);
+    let plot_area = approx_plot_area(options, &data.facet_layout);
 
     // Iterate panels (zipped with scales)
-    for (panel_data, panel_scales) in data.panels.into_iter().zip(scales.panels.into_iter()) {
+    for (panel_idx, (panel_data, panel_scales)) in data.panels.into_iter().zip(scales.panels).enumerate() {
+        report(progress, || format!("Compiling geometry [{}/{}]...", panel_idx + 1, panel_count));
         let mut commands = Vec::new();
+        let mut legend_items = Vec::new();
+
+        // Smart Dodging: occupancy per X coordinate for each dodged layer. Layers grouped by the same
+        // column share it, so e.g. boxplot(color: g) | pointrange(color: g, position: "dodge") line
+        // up per group; any other layer (e.g. an ungrouped boxplot over grouped violins) dodges alone.
+        // Horizontal ranges dodge along y, together with other horizontal ranges only
+        let group_cols: Vec<Option<&String>> = spec.layers.iter().map(|l| crate::transform::group_column(&l.aesthetics)).collect();
+        let dodged: Vec<bool> = spec.layers.iter().map(|l| matches!(layer_position(&l.original_layer), LayerPosition::Dodge)).collect();
+        let horizontal_layers: Vec<bool> = spec.layers.iter().map(|l| l.aesthetics.xmin_col.is_some()).collect();
+        let occupancies: Vec<XOccupancy> = (0..panel_data.layers.len())
+            .map(|layer_idx| {
+                if !dodged[layer_idx] {
+                    return XOccupancy::new();
+                }
+                let sharing = (0..panel_data.layers.len()).filter(|&other| {
+                    other == layer_idx
+                        || (dodged[other] && horizontal_layers[other] == horizontal_layers[layer_idx]
+                            && group_cols[layer_idx].is_some() && group_cols[other] == group_cols[layer_idx])
+                });
+                x_occupancy(sharing.flat_map(|other| &panel_data.layers[other].groups), horizontal_layers[layer_idx])
+            })
+            .collect();
 
         // Iterate layers
         for (layer_idx, layer_data) in panel_data.layers.into_iter().enumerate() {
             // Retrieve original layer spec for metadata (position, etc.)
             let layer_spec = &spec.layers[layer_idx];
-            
-            // Handle Positioning Logic
-            let (_is_bar, position) = match &layer_spec.original_layer {
-                Layer::Bar(b) => (true, b.position.clone()),
-                Layer::Boxplot(_) => (true, BarPosition::Dodge),
-                Layer::Violin(_) => (true, BarPosition::Dodge),
-                _ => (false, BarPosition::Identity),
+            let position = layer_position(&layer_spec.original_layer);
+            let x_occupancy = &occupancies[layer_idx];
+            let horizontal = horizontal_layers[layer_idx];
+
+            // Largest category size in the layer, for varwidth boxplots
+            let max_count = layer_data.groups.iter().flat_map(|g| g.counts.iter().copied()).max().unwrap_or(0);
+
+            // abline(legend: ...)/crosshair(legend: ...) name their entry, listed even if nothing is drawn
+            let group_count = layer_data.groups.len();
+            let legend_label = match &layer_spec.original_layer {
+                Layer::Line(LineLayer { legend: Some(label), .. }) => Some(label.clone()),
+                _ => None,
             };
 
-            // Smart Dodging: Calculate occupancy per X coordinate
-            // Map: Quantized X -> List of Group Indices present at that X
-            let mut x_occupancy: HashMap<i64, Vec<usize>> = HashMap::new();
-            
-            if matches!(position, BarPosition::Dodge) {
-                for (g_idx, group) in layer_data.groups.iter().enumerate() {
-                    for &x in &group.x {
-                        // Quantize X to integer for categorical grouping logic
-                        // (Use round() to handle float imprecision)
-                        let key = x.round() as i64; 
-                        x_occupancy.entry(key).or_default().push(g_idx);
-                    }
-                }
-                // Sort groups at each X to ensure deterministic order (usually sorted by group key anyway)
-                for groups_at_x in x_occupancy.values_mut() {
-                    groups_at_x.sort(); 
-                    groups_at_x.dedup(); // Handle multiple points per group at same X (if any)
-                }
-            }
+            // beeswarm(): sideways offset of each point, per group
+            let swarm = match layer_spec.original_layer.stat() {
+                Stat::Beeswarm { priority, cex } => beeswarm_offsets(&layer_data.groups, *priority, *cex, &panel_scales, is_flipped, plot_area),
+                _ => Vec::new(),
+            };
+
+            // text(check_overlap: true): boxes of the labels placed so far in this layer
+            let mut placed_labels: Vec<TextBounds> = Vec::new();
 
             for (group_idx, group) in layer_data.groups.into_iter().enumerate() {
                 match &group.style {
+                    // abline(): each (slope, intercept) runs across the panel, clipped to its y range
+                    RenderStyle::Line(style) if !group.ablines.is_empty() => {
+                        let mut legend = reference_legend(&legend_label, &group.key, group_count, style, &mut legend_items);
+                        for &(slope, intercept) in &group.ablines {
+                            let Some((x0, x1)) = abline_span(slope, intercept, panel_scales.x.domain, panel_scales.y.domain) else { continue };
+                            let points = [x0, x1].iter()
+                                .map(|&x| (x, intercept + slope * x))
+                                .map(|(x, y)| if is_flipped { (y, x) } else { (x, y) })
+                                .collect();
+                            commands.push(DrawCommand::DrawLine { points, style: style.clone(), legend: legend.take() });
+                        }
+                    }
+                    // crosshair(): a vertical and a horizontal line through each point, across the panel
+                    RenderStyle::Line(style) if matches!(layer_spec.original_layer.stat(), Stat::Crosshair) => {
+                        let ((x_lo, x_hi), (y_lo, y_hi)) = (panel_scales.x.domain, panel_scales.y.domain);
+                        let mut legend = reference_legend(&legend_label, &group.key, group_count, style, &mut legend_items);
+                        for (&x, &y) in group.x.iter().zip(&group.y) {
+                            for line in [[(x, y_lo), (x, y_hi)], [(x_lo, y), (x_hi, y)]] {
+                                let points = line.iter().map(|&(x, y)| if is_flipped { (y, x) } else { (x, y) }).collect();
+                                commands.push(DrawCommand::DrawLine { points, style: style.clone(), legend: legend.take() });
+                            }
+                        }
+                    }
+                    // segment(): a line per row from (x, y) to (xend, yend), with arrow heads at the chosen ends
+                    RenderStyle::Line(style) if !group.segment_ends.is_empty() => {
+                        let arrow = match layer_spec.original_layer.stat() {
+                            Stat::Segment { arrow, .. } => *arrow,
+                            _ => None,
+                        };
+                        let flip = |(x, y): (f64, f64)| if is_flipped { (y, x) } else { (x, y) };
+                        let mut legend = Some(group.key.clone());
+                        for (start, &end) in group.x.iter().copied().zip(group.y.iter().copied()).zip(&group.segment_ends) {
+                            commands.push(DrawCommand::DrawLine { points: vec![flip(start), flip(end)], style: style.clone(), legend: legend.take() });
+                            let Some(arrow) = arrow else { continue };
+                            let tips = match arrow.ends {
+                                ArrowEnds::First => vec![(start, end)],
+                                ArrowEnds::Last => vec![(end, start)],
+                                ArrowEnds::Both => vec![(start, end), (end, start)],
+                            };
+                            for (tip, from) in tips {
+                                let Some([left, right]) = arrow_barbs(tip, from, &panel_scales, is_flipped, plot_area) else { continue };
+                                match arrow.head {
+                                    ArrowType::Closed => commands.push(DrawCommand::DrawPolygon {
+                                        points: vec![flip(tip), flip(left), flip(right)],
+                                        style: RibbonStyle { color: style.color.clone(), alpha: Some(style.alpha.unwrap_or(1.0)) },
+                                        legend: None,
+                                    }),
+                                    ArrowType::Open => {
+                                        for barb in [left, right] {
+                                            let barb_style = LineStyle { linetype: None, ..style.clone() };
+                                            commands.push(DrawCommand::DrawLine { points: vec![flip(barb), flip(tip)], style: barb_style, legend: None });
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    // rug(): a short tick per x value against the bottom of the panel
+                    RenderStyle::Line(style) if matches!(layer_spec.original_layer.stat(), Stat::Rug { .. }) => {
+                        let Stat::Rug { length, jitter } = *layer_spec.original_layer.stat() else { unreachable!() };
+                        let mut legend = Some(group.key.clone());
+                        for tick in rug_ticks(&group.x, &panel_scales.y, length, jitter.then_some(group_idx)) {
+                            let points = tick.iter().map(|&(x, y)| if is_flipped { (y, x) } else { (x, y) }).collect();
+                            commands.push(DrawCommand::DrawLine { points, style: style.clone(), legend: legend.take() });
+                        }
+                    }
+                    // contour(): one line per level path, or one polygon per band piece with filled: true
+                    RenderStyle::Line(style) if !group.contours.is_empty() => {
+                        let filled = matches!(layer_spec.original_layer.stat(), Stat::Contour { filled: true, .. });
+                        for (i, (_, path)) in group.contours.iter().enumerate() {
+                            let points = path.iter().map(|&(x, y)| if is_flipped { (y, x) } else { (x, y) }).collect();
+                            let legend = (i == 0).then(|| group.key.clone());
+                            if filled {
+                                commands.push(DrawCommand::DrawPolygon {
+                                    points,
+                                    style: RibbonStyle {
+                                        color: group.fills.get(i).cloned().or_else(|| style.color.clone()),
+                                        alpha: Some(style.alpha.unwrap_or(1.0)),
+                                    },
+                                    legend,
+                                });
+                            } else {
+                                commands.push(DrawCommand::DrawLine { points, style: style.clone(), legend });
+                            }
+                        }
+                    }
                     RenderStyle::Line(style) => {
-                        let points: Vec<(f64, f64)> = group.x.iter().zip(group.y.iter())
-                            .map(|(&x, &y)| if is_flipped { (y, x) } else { (x, y) })
+                        let mut points: Vec<(f64, f64)> = group.x.iter().copied().zip(group.y.iter().copied()).collect();
+                        if let Layer::Line(LineLayer { step: Some(direction), .. }) = &layer_spec.original_layer {
+                            points = step_points(&points, *direction);
+                        }
+                        let points: Vec<(f64, f64)> = points.into_iter()
+                            .map(|(x, y)| if is_flipped { (y, x) } else { (x, y) })
                             .collect();
+
+                        // density(fill: true): shade under the curve, closed along the zero baseline
+                        if matches!(layer_spec.original_layer.stat(), Stat::Density { fill: true, .. }) {
+                            if let (Some(&first), Some(&last)) = (group.x.first(), group.x.last()) {
+                                let baseline = [(last, 0.0), (first, 0.0)]
+                                    .map(|(x, y)| if is_flipped { (y, x) } else { (x, y) });
+                                commands.push(DrawCommand::DrawPolygon {
+                                    points: points.iter().copied().chain(baseline).collect(),
+                                    style: RibbonStyle { color: style.color.clone(), alpha: Some(0.3) },
+                                    legend: None,
+                                });
+                            }
+                        }
+
+                        // smooth(se: ...): interval band behind the line, y_max forward then y_min back
+                        if matches!(layer_spec.original_layer.stat(), Stat::Smooth { se: SeType::Confidence(_) | SeType::Prediction(_), .. }) {
+                            let upper = group.x.iter().zip(&group.y_max);
+                            let lower = group.x.iter().zip(&group.y_min).rev();
+                            commands.push(DrawCommand::DrawPolygon {
+                                points: upper.chain(lower)
+                                    .map(|(&x, &y)| if is_flipped { (y, x) } else { (x, y) })
+                                    .collect(),
+                                style: RibbonStyle { color: style.color.clone(), alpha: Some(0.3) },
+                                legend: None,
+                            });
+                        }
+
                         commands.push(DrawCommand::DrawLine {
                             points,
                             style: style.clone(),
                             legend: Some(group.key.clone()),
                         });
+
+                        // smooth(residuals: true): observed - fitted around a dashed zero line
+                        if !group.residuals.is_empty() {
+                            let flip = |(x, y): (f64, f64)| if is_flipped { (y, x) } else { (x, y) };
+                            let (min_x, max_x) = group.residuals.iter()
+                                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &(x, _)| (lo.min(x), hi.max(x)));
+                            commands.push(DrawCommand::DrawLine {
+                                points: vec![flip((min_x, 0.0)), flip((max_x, 0.0))],
+                                style: LineStyle {
+                                    color: Some("grey".to_string()),
+                                    width: Some(1.0),
+                                    alpha: None,
+                                    linetype: Some("dashed".to_string()),
+                                },
+                                legend: None,
+                            });
+                            commands.push(DrawCommand::DrawPoint {
+                                points: group.residuals.iter().map(|&p| flip(p)).collect(),
+                                style: PointStyle {
+                                    color: style.color.clone(),
+                                    size: Some(2.0),
+                                    shape: None,
+                                    alpha: Some(0.5),
+                                    fill: None,
+                                },
+                                legend: None,
+                            });
+                        }
                     }
                     RenderStyle::Point(style) => {
-                        let points: Vec<(f64, f64)> = group.x.iter().zip(group.y.iter())
-                            .map(|(&x, &y)| if is_flipped { (y, x) } else { (x, y) })
+                        // A horizontal range (xmin:, xmax:) sits at its y and runs along x: `at` places a
+                        // (category position, value) pair on the right axes
+                        let (along, values, lows, highs) = if horizontal {
+                            (&group.y, &group.x, &group.x_min, &group.x_max)
+                        } else {
+                            (&group.x, &group.y, &group.y_min, &group.y_max)
+                        };
+                        let at = |center: f64, value: f64| {
+                            let (x, y) = if horizontal { (value, center) } else { (center, value) };
+                            if is_flipped { (y, x) } else { (x, y) }
+                        };
+                        let (crossbar, width) = match &layer_spec.original_layer {
+                            Layer::Point(p) => (p.crossbar, p.width.unwrap_or(PointLayer::DEFAULT_CROSSBAR_WIDTH)),
+                            _ => (false, PointLayer::DEFAULT_CROSSBAR_WIDTH),
+                        };
+                        // (slot width, center) per point: swarm offsets, or the dodge slot among the groups at its position
+                        let offsets = swarm.get(group_idx);
+                        let slots: Vec<(f64, f64)> = along.iter().enumerate()
+                            .map(|(i, &c)| {
+                                let (slot, offset) = dodge_slot(x_occupancy, &position, c, &group.key, width);
+                                (slot, c + offset + offsets.map_or(0.0, |o| o[i]))
+                            })
                             .collect();
+
+                        // crossbar(): a box from ymin to ymax across the slot, with a line at y; the box
+                        // carries the legend entry in place of a point
+                        if crossbar {
+                            let line_style = LineStyle { color: style.color.clone(), width: Some(2.0), alpha: style.alpha, linetype: None };
+                            for (i, &(slot, c)) in slots.iter().enumerate() {
+                                let half = slot / 2.0;
+                                commands.push(DrawCommand::DrawRect {
+                                    tl: at(c - half, highs[i]),
+                                    br: at(c + half, lows[i]),
+                                    style: BarStyle {
+                                        color: style.color.clone(),
+                                        alpha: style.alpha,
+                                        width: None,
+                                        border_color: style.color.clone(),
+                                        border_width: Some(1.5),
+                                        hollow: true,
+                                    },
+                                    legend: (i == 0).then(|| group.key.clone()),
+                                });
+                                commands.push(DrawCommand::DrawLine {
+                                    points: vec![at(c - half, values[i]), at(c + half, values[i])],
+                                    style: line_style.clone(),
+                                    legend: None,
+                                });
+                            }
+                            continue;
+                        }
+
+                        let points: Vec<(f64, f64)> = slots.iter().zip(values).map(|(&(_, c), &v)| at(c, v)).collect();
+
+                        // stat_summary() and pointrange(): a range from ymin to ymax (or xmin to xmax)
+                        // behind each point
+                        let has_range = matches!(layer_spec.original_layer.stat(), Stat::Summary { .. })
+                            || matches!(layer_spec.original_layer, Layer::Point(PointLayer { range: Some(_), .. }));
+                        if has_range {
+                            for ((&(_, c), &lo), &hi) in slots.iter().zip(lows).zip(highs) {
+                                commands.push(DrawCommand::DrawLine {
+                                    points: vec![at(c, lo), at(c, hi)],
+                                    style: LineStyle { color: style.color.clone(), width: Some(1.5), alpha: style.alpha, linetype: None },
+                                    legend: None,
+                                });
+                            }
+                        }
+
                         commands.push(DrawCommand::DrawPoint {
                             points,
                             style: style.clone(),
@@ -202,22 +511,10 @@ pub fn compile_geometry(
                             let y_bottom = group.y_start[i];
                             
                             // Calculate Dodge Offset for this specific point
-                            let (slot_width, x_offset) = if matches!(position, BarPosition::Dodge) {
-                                let key = x_center.round() as i64;
-                                if let Some(occupants) = x_occupancy.get(&key) {
-                                    let num_at_x = occupants.len();
-                                    if let Some(rank) = occupants.iter().position(|&g| g == group_idx) {
-                                        let slot = bar_width_ratio / num_at_x as f64;
-                                        let offset = (rank as f64 - (num_at_x as f64 - 1.0) / 2.0) * slot;
-                                        (slot, offset)
-                                    } else {
-                                        (bar_width_ratio, 0.0) // Should not happen
-                                    }
-                                } else {
-                                    (bar_width_ratio, 0.0)
-                                }
+                            let (slot_width, x_offset) = if let Some(&width) = group.widths.get(i) {
+                                (width, 0.0) // Mosaic bars fill their whole slot and are never dodged
                             } else {
-                                (bar_width_ratio, 0.0)
+                                dodge_slot(x_occupancy, &position, x_center, &group.key, bar_width_ratio)
                             };
 
                             let x_final = x_center + x_offset;
@@ -232,53 +529,48 @@ pub fn compile_geometry(
                                 (tl, br)
                             };
 
-                            commands.push(DrawCommand::DrawRect {
-                                tl,
-                                br,
-                                style: style.clone(),
-                                legend: if i == 0 { Some(group.key.clone()) } else { None },
-                            });
+                            // bar(fill: col) of literal colors: each bar its own color, without a legend
+                            // key (the colors are the data, not a mapping to explain)
+                            let (style, legend) = match group.fills.get(i) {
+                                Some(fill) => (BarStyle { color: Some(fill.clone()), ..style.clone() }, None),
+                                None => (style.clone(), (i == 0).then(|| group.key.clone())),
+                            };
+                            commands.push(DrawCommand::DrawRect { tl, br, style, legend });
                         }
                     }
                     RenderStyle::Boxplot(style) => {
                         let width_ratio = style.width.unwrap_or(0.5);
                         let (whisker_style, box_style, median_style, outlier_style) =
                             boxplot_component_styles(style);
+                        let staplewidth = style.staplewidth.unwrap_or(DEFAULT_STAPLE_WIDTH).max(0.0);
 
                         for i in 0..group.x.len() {
                             let x_center = group.x[i];
 
                             // Calculate Dodge Offset for this specific point
-                            let (slot_width, x_offset) = if matches!(position, BarPosition::Dodge) {
-                                let key = x_center.round() as i64;
-                                if let Some(occupants) = x_occupancy.get(&key) {
-                                    let num_at_x = occupants.len();
-                                    if let Some(rank) = occupants.iter().position(|&g| g == group_idx) {
-                                        let slot = width_ratio / num_at_x as f64;
-                                        let offset = (rank as f64 - (num_at_x as f64 - 1.0) / 2.0) * slot;
-                                        (slot, offset)
-                                    } else {
-                                        (width_ratio, 0.0)
-                                    }
-                                } else {
-                                    (width_ratio, 0.0)
-                                }
-                            } else {
-                                (width_ratio, 0.0)
-                            };
+                            let (slot_width, x_offset) = dodge_slot(x_occupancy, &position, x_center, &group.key, width_ratio);
 
                             let x_final = x_center + x_offset;
                             let is_vertical = !is_flipped;
 
+                            // varwidth: shrink the box (not its dodge slot) by sqrt(n / n_max)
+                            let box_width = if style.varwidth && max_count > 0 {
+                                slot_width * (group.counts[i] as f64 / max_count as f64).sqrt()
+                            } else {
+                                slot_width
+                            };
+
                             let geom = compute_boxplot_geometry(
                                 x_final,
-                                slot_width,
+                                box_width,
                                 group.y_min[i],
                                 group.y_q1[i],
                                 group.y_median[i],
                                 group.y_q3[i],
                                 group.y_max[i],
                                 &group.outliers[i],
+                                style.notch.then(|| (group.y_notch_low[i], group.y_notch_high[i], style.notchwidth.unwrap_or(DEFAULT_NOTCH_WIDTH))),
+                                staplewidth,
                                 is_vertical,
                             );
 
@@ -296,25 +588,36 @@ pub fn compile_geometry(
                                 legend: None,
                             });
 
-                            // 2. Whisker caps
-                            commands.push(DrawCommand::DrawLine {
-                                points: geom.min_cap,
-                                style: whisker_style.clone(),
-                                legend: None,
-                            });
-                            commands.push(DrawCommand::DrawLine {
-                                points: geom.max_cap,
-                                style: whisker_style.clone(),
-                                legend: None,
-                            });
+                            // 2. Whisker caps (none with staplewidth: 0)
+                            if staplewidth > 0.0 {
+                                commands.push(DrawCommand::DrawLine {
+                                    points: geom.min_cap,
+                                    style: whisker_style.clone(),
+                                    legend: None,
+                                });
+                                commands.push(DrawCommand::DrawLine {
+                                    points: geom.max_cap,
+                                    style: whisker_style.clone(),
+                                    legend: None,
+                                });
+                            }
 
-                            // 3. Box (rectangle) - legend attached here
-                            commands.push(DrawCommand::DrawRect {
-                                tl: geom.box_tl,
-                                br: geom.box_br,
-                                style: box_style.clone(),
-                                legend: if i == 0 { Some(group.key.clone()) } else { None },
-                            });
+                            // 3. Box (rectangle, or polygon when notched) - legend attached here
+                            let legend = if i == 0 { Some(group.key.clone()) } else { None };
+                            match geom.box_outline {
+                                Some(points) => commands.push(DrawCommand::DrawPolygon {
+                                    points,
+                                    // Opaque like the rect, not the translucent ribbon default
+                                    style: RibbonStyle { color: box_style.color.clone(), alpha: Some(box_style.alpha.unwrap_or(1.0)) },
+                                    legend,
+                                }),
+                                None => commands.push(DrawCommand::DrawRect {
+                                    tl: geom.box_tl,
+                                    br: geom.box_br,
+                                    style: box_style.clone(),
+                                    legend,
+                                }),
+                            }
 
                             // 4. Median line (white for contrast)
                             commands.push(DrawCommand::DrawLine {
@@ -333,6 +636,111 @@ pub fn compile_geometry(
                             }
                         }
                     }
+                    RenderStyle::Tile(style) => {
+                        // One rect per tile, centered on (x, y); colors come from the fill scale.
+                        // No legend entries: a continuous gradient has no discrete keys.
+                        for i in 0..group.x.len() {
+                            let (x, y) = (group.x[i], group.y[i]);
+                            let tl = (x - style.width / 2.0, y + style.height / 2.0);
+                            let br = (x + style.width / 2.0, y - style.height / 2.0);
+                            let (tl, br) = if is_flipped { ((tl.1, tl.0), (br.1, br.0)) } else { (tl, br) };
+
+                            commands.push(DrawCommand::DrawRect {
+                                tl,
+                                br,
+                                style: BarStyle {
+                                    color: group.fills.get(i).cloned().or_else(|| style.fill.clone()),
+                                    alpha: style.alpha,
+                                    width: None,
+                                    border_color: style.border.clone(),
+                                    border_width: None,
+                                    hollow: false,
+                                },
+                                legend: None,
+                            });
+                        }
+                    }
+                    RenderStyle::Hex(style) => {
+                        // Pointy-top hexagon around each cell center, filled by its fill summary
+                        let size_by_count = matches!(&layer_spec.original_layer, Layer::Hexbin(h) if h.size_by_count);
+                        for i in 0..group.x.len() {
+                            let (x, y) = (group.x[i], group.y[i]);
+                            // hexbin(size: "count"): area proportional to the count, the fullest cell at full size
+                            let shrink = match group.counts.get(i) {
+                                Some(&count) if size_by_count && max_count > 0 => (count as f64 / max_count as f64).sqrt(),
+                                _ => 1.0,
+                            };
+                            let (half_w, r) = (style.width / 2.0 * shrink, style.radius * shrink);
+                            let mut points: Vec<(f64, f64)> = [
+                                (0.0, r), (half_w, r / 2.0), (half_w, -r / 2.0),
+                                (0.0, -r), (-half_w, -r / 2.0), (-half_w, r / 2.0),
+                            ].iter()
+                                .map(|&(dx, dy)| if is_flipped { (y + dy, x + dx) } else { (x + dx, y + dy) })
+                                .collect();
+
+                            commands.push(DrawCommand::DrawPolygon {
+                                points: points.clone(),
+                                style: RibbonStyle {
+                                    color: group.fills.get(i).cloned(),
+                                    alpha: Some(style.alpha.unwrap_or(1.0)),
+                                },
+                                legend: None,
+                            });
+                            if let Some(border) = &style.border {
+                                points.push(points[0]);
+                                commands.push(DrawCommand::DrawLine {
+                                    points,
+                                    style: LineStyle { color: Some(border.clone()), width: Some(1.0), ..Default::default() },
+                                    legend: None,
+                                });
+                            }
+                        }
+                    }
+                    RenderStyle::Text(style) => {
+                        let Layer::Text(text) = &layer_spec.original_layer else { continue };
+                        let mut legend = Some(group.key.clone());
+                        for (i, label) in group.labels.iter().enumerate() {
+                            let (x, y) = (group.x[i] + text.nudge_x, group.y[i] + text.nudge_y);
+                            let pos = if is_flipped { (y, x) } else { (x, y) };
+
+                            let scales = if is_flipped { (&panel_scales.y, &panel_scales.x) } else { (&panel_scales.x, &panel_scales.y) };
+                            let padding = text.label_box.as_ref().map_or(0.0, |b| b.padding);
+                            let bounds = TextBounds::around(pos, label, style.size, padding, scales, plot_area);
+
+                            // Greedy, in data order: skip a label that overlaps one already placed
+                            if text.check_overlap {
+                                if placed_labels.iter().any(|b| b.overlaps(&bounds)) {
+                                    continue;
+                                }
+                                placed_labels.push(bounds);
+                            }
+
+                            // label(): the box goes underneath its text
+                            if let Some(label_box) = &text.label_box {
+                                let (tl, br) = bounds.corners(scales);
+                                commands.push(DrawCommand::DrawRect {
+                                    tl,
+                                    br,
+                                    style: BarStyle {
+                                        color: Some(label_box.fill.clone()),
+                                        alpha: style.alpha,
+                                        width: None,
+                                        border_color: (label_box.border > 0.0).then(|| style.color.clone().unwrap_or_else(|| "black".to_string())),
+                                        border_width: Some(label_box.border),
+                                        hollow: false,
+                                    },
+                                    legend: None,
+                                });
+                            }
+
+                            commands.push(DrawCommand::DrawText {
+                                pos,
+                                text: label.clone(),
+                                style: style.clone(),
+                                legend: legend.take(),
+                            });
+                        }
+                    }
                     RenderStyle::Ribbon(style) => {
                         // Construct Polygon: Trace y_max forward, then y_min backward
                         let mut points = Vec::with_capacity(group.x.len() * 2);
@@ -356,32 +764,33 @@ pub fn compile_geometry(
                             style: style.clone(),
                             legend: Some(group.key.clone()),
                         });
+
+                        // ribbon(border: ...): the upper and lower edges only, leaving the ends open
+                        if let Layer::Ribbon(RibbonLayer { border: Some(border), linetype, .. }) = &layer_spec.original_layer {
+                            let line_style = LineStyle { color: Some(border.clone()), linetype: linetype.clone(), ..Default::default() };
+                            for edge in [&group.y_max, &group.y_min] {
+                                let points = group.x.iter().zip(edge)
+                                    .map(|(&x, &y)| if is_flipped { (y, x) } else { (x, y) })
+                                    .collect();
+                                commands.push(DrawCommand::DrawLine { points, style: line_style.clone(), legend: None });
+                            }
+                        }
                     }
                     RenderStyle::Violin(style) => {
                         let width_ratio = style.width.unwrap_or(0.8);
                         let is_vertical = !is_flipped;
+                        // Share of the density drawn on each side of the center (half violins collapse one side)
+                        let (right_share, left_share) = match style.side {
+                            ViolinSide::Both => (1.0, 1.0),
+                            ViolinSide::Left => (0.0, 1.0),
+                            ViolinSide::Right => (1.0, 0.0),
+                        };
 
                         for i in 0..group.x.len() {
                             let x_center = group.x[i];
 
                             // Calculate Dodge Offset (same as boxplot)
-                            let (slot_width, x_offset) = if matches!(position, BarPosition::Dodge) {
-                                let key = x_center.round() as i64;
-                                if let Some(occupants) = x_occupancy.get(&key) {
-                                    let num_at_x = occupants.len();
-                                    if let Some(rank) = occupants.iter().position(|&g| g == group_idx) {
-                                        let slot = width_ratio / num_at_x as f64;
-                                        let offset = (rank as f64 - (num_at_x as f64 - 1.0) / 2.0) * slot;
-                                        (slot, offset)
-                                    } else {
-                                        (width_ratio, 0.0)
-                                    }
-                                } else {
-                                    (width_ratio, 0.0)
-                                }
-                            } else {
-                                (width_ratio, 0.0)
-                            };
+                            let (slot_width, x_offset) = dodge_slot(x_occupancy, &position, x_center, &group.key, width_ratio);
 
                             let x_final = x_center + x_offset;
                             let half_width = slot_width / 2.0;
@@ -394,7 +803,7 @@ pub fn compile_geometry(
                                 continue;
                             }
 
-                            // Build violin polygon: trimmed at data min/max with flat caps (like ggplot2)
+                            // Build violin polygon: cut at y_min/y_max (the data range unless trim: false) with flat caps (like ggplot2)
                             let data_min = group.y_min[i];
                             let data_max = group.y_max[i];
 
@@ -409,60 +818,60 @@ pub fn compile_geometry(
                             if is_vertical {
                                 // Start with flat bottom cap at data_min
                                 let width_at_min = density_at_min * half_width;
-                                right_side.push((x_final + width_at_min, data_min));
+                                right_side.push((x_final + width_at_min * right_share, data_min));
 
                                 // Add points within data range (bottom to top)
                                 for j in 0..density.len() {
                                     let y = density_y[j];
                                     if y > data_min && y < data_max {
                                         let x_offset_density = density[j] * half_width;
-                                        right_side.push((x_final + x_offset_density, y));
+                                        right_side.push((x_final + x_offset_density * right_share, y));
                                     }
                                 }
 
                                 // End with flat top cap at data_max
                                 let width_at_max = density_at_max * half_width;
-                                right_side.push((x_final + width_at_max, data_max));
+                                right_side.push((x_final + width_at_max * right_share, data_max));
 
                                 // Mirror for left side (top to bottom)
-                                left_side.push((x_final - width_at_max, data_max));
+                                left_side.push((x_final - width_at_max * left_share, data_max));
                                 for j in (0..density.len()).rev() {
                                     let y = density_y[j];
                                     if y > data_min && y < data_max {
                                         let x_offset_density = density[j] * half_width;
-                                        left_side.push((x_final - x_offset_density, y));
+                                        left_side.push((x_final - x_offset_density * left_share, y));
                                     }
                                 }
-                                left_side.push((x_final - width_at_min, data_min));
+                                left_side.push((x_final - width_at_min * left_share, data_min));
                             } else {
                                 // Horizontal orientation (coord_flip)
                                 // Start with flat left cap at data_min
                                 let width_at_min = density_at_min * half_width;
-                                right_side.push((data_min, x_final + width_at_min));
+                                right_side.push((data_min, x_final + width_at_min * right_share));
 
                                 // Add points within data range (left to right)
                                 for j in 0..density.len() {
                                     let y_coord = density_y[j];
                                     if y_coord > data_min && y_coord < data_max {
                                         let offset = density[j] * half_width;
-                                        right_side.push((y_coord, x_final + offset));
+                                        right_side.push((y_coord, x_final + offset * right_share));
                                     }
                                 }
 
                                 // End with flat right cap at data_max
                                 let width_at_max = density_at_max * half_width;
-                                right_side.push((data_max, x_final + width_at_max));
+                                right_side.push((data_max, x_final + width_at_max * right_share));
 
                                 // Mirror for bottom side (right to left)
-                                left_side.push((data_max, x_final - width_at_max));
+                                left_side.push((data_max, x_final - width_at_max * left_share));
                                 for j in (0..density.len()).rev() {
                                     let y_coord = density_y[j];
                                     if y_coord > data_min && y_coord < data_max {
                                         let offset = density[j] * half_width;
-                                        left_side.push((y_coord, x_final - offset));
+                                        left_side.push((y_coord, x_final - offset * left_share));
                                     }
                                 }
-                                left_side.push((data_min, x_final - width_at_min));
+                                left_side.push((data_min, x_final - width_at_min * left_share));
                             }
 
                             // Combine into closed polygon
@@ -491,9 +900,9 @@ pub fn compile_geometry(
                                 let half_width_at_q = interpolate_density_at_y(q_y, density, density_y) * half_width;
 
                                 let line_points = if is_vertical {
-                                    vec![(x_final - half_width_at_q, q_y), (x_final + half_width_at_q, q_y)]
+                                    vec![(x_final - half_width_at_q * left_share, q_y), (x_final + half_width_at_q * right_share, q_y)]
                                 } else {
-                                    vec![(q_y, x_final - half_width_at_q), (q_y, x_final + half_width_at_q)]
+                                    vec![(q_y, x_final - half_width_at_q * left_share), (q_y, x_final + half_width_at_q * right_share)]
                                 };
 
                                 commands.push(DrawCommand::DrawLine {
@@ -502,6 +911,7 @@ pub fn compile_geometry(
                                         color: Some("white".to_string()),
                                         width: Some(1.5),
                                         alpha: Some(0.9),
+                                        linetype: None,
                                     },
                                     legend: None,
                                 });
@@ -521,33 +931,475 @@ pub fn compile_geometry(
         let row = panel_data.index / data.facet_layout.ncol;
         let col = panel_data.index % data.facet_layout.ncol;
         
-        let (x_scale, y_scale) = if is_flipped {
-            (panel_scales.y, panel_scales.x)
+        // Axis titles follow their aesthetic, so labs(x: ...) names the vertical axis when flipped
+        let (x_scale, y_scale, x_label, y_label) = if is_flipped {
+            (panel_scales.y, panel_scales.x, spec.labels.y.clone(), spec.labels.x.clone())
         } else {
-            (panel_scales.x, panel_scales.y)
+            (panel_scales.x, panel_scales.y, spec.labels.x.clone(), spec.labels.y.clone())
         };
 
+        // oob: follows its aesthetic too, so apply it in drawn coordinates after the flip
+        let oob = |s: &Option<crate::parser::ast::AxisScale>| s.as_ref().map(|s| s.oob).unwrap_or_default();
+        let (x_oob, y_oob) = if is_flipped {
+            (oob(&spec.y_scale_spec), oob(&spec.x_scale_spec))
+        } else {
+            (oob(&spec.x_scale_spec), oob(&spec.y_scale_spec))
+        };
+        let bounds = OobBounds { x: AxisBounds::new(&x_scale, x_oob), y: AxisBounds::new(&y_scale, y_oob) };
+        let commands = if bounds.is_noop() { commands } else { bounds.apply(commands, &mut legend_items) };
+
         panels.push(PanelScene {
             row,
             col,
             title,
-            x_label: spec.labels.x.clone(),
-            y_label: spec.labels.y.clone(),
+            strip_position: spec.facet.as_ref().map(|f| f.strip_position).unwrap_or_default(),
+            x_label,
+            y_label,
+            legend_title: legend_title.clone(),
             x_scale,
             y_scale,
             commands,
+            legend_items,
         });
     }
 
+    // Faceted plots share one legend outside the panel grid instead of repeating it per panel
+    let mut legend = Vec::new();
+    if panels.len() > 1 {
+        let mut seen = HashSet::new();
+        for panel in &mut panels {
+            panel.legend_title = None;
+            let items = std::mem::take(&mut panel.legend_items);
+            for entry in panel.commands.iter_mut().filter_map(take_legend_entry).chain(items) {
+                if seen.insert(entry.label.clone()) {
+                    legend.push(entry);
+                }
+            }
+        }
+    }
+    let legend_title = if legend.is_empty() { None } else { legend_title };
+
+    // labs(fill: ...) names the continuous fill legend
+    let colorbar = data.colorbar.map(|mut bar| {
+        if let Some(title) = &spec.labels.fill_title {
+            bar.title = Some(title.clone());
+        }
+        bar
+    });
+
+    let row_heights = match &spec.facet {
+        Some(facet) if facet.space == FacetSpace::FreeY => free_row_heights(&panels),
+        _ => Vec::new(),
+    };
+
     Ok(SceneGraph {
         width: options.width,
         height: options.height,
         panels,
         labels: spec.labels.clone(),
         theme: spec.theme.clone(),
+        legend,
+        legend_title,
+        colorbar,
+        row_heights,
     })
 }
 
+/// Each panel row's share of the grid height: the widest vertical-axis span among its panels
+/// over the sum across rows (empty, i.e. equal rows, if any row has no usable span)
+fn free_row_heights(panels: &[PanelScene]) -> Vec<f64> {
+    let rows = panels.iter().map(|p| p.row + 1).max().unwrap_or(0);
+    let mut spans = vec![0.0f64; rows];
+    for panel in panels {
+        let (lo, hi) = panel.y_scale.range;
+        let span = if panel.y_scale.is_log && lo > 0.0 && hi > 0.0 { (hi / lo).log10() } else { hi - lo };
+        spans[panel.row] = spans[panel.row].max(span.abs());
+    }
+
+    let total: f64 = spans.iter().sum();
+    if spans.iter().any(|s| !s.is_finite() || *s <= 0.0) {
+        return Vec::new();
+    }
+    spans.iter().map(|s| s / total).collect()
+}
+
+/// Rough pixel size of one panel's plotting area (output split by the facet grid, less the
+/// axis label areas and margins), for sizing text labels in data units
+fn approx_plot_area(options: &RenderOptions, layout: &FacetLayout) -> (f64, f64) {
+    let width = options.width as f64 / layout.ncol.max(1) as f64 - 70.0;
+    let height = options.height as f64 / layout.nrow.max(1) as f64 - 80.0;
+    (width.max(1.0), height.max(1.0))
+}
+
+/// Position along an axis in its drawing space (decades on a log axis)
+fn axis_value(scale: &Scale, v: f64) -> f64 {
+    if scale.is_log { v.max(f64::MIN_POSITIVE).log10() } else { v }
+}
+
+/// Axis-space box of a text label (log axes measured in decades)
+#[derive(Debug, Clone, Copy)]
+struct TextBounds {
+    x: (f64, f64),
+    y: (f64, f64),
+}
+
+impl TextBounds {
+    /// Box of `text` centered on `pos`, sized from its length and font size plus `padding` (px)
+    fn around(pos: (f64, f64), text: &str, size: f64, padding: f64, (h_scale, v_scale): (&Scale, &Scale), (plot_w, plot_h): (f64, f64)) -> Self {
+        let per_px = |scale: &Scale, px: f64| (axis_value(scale, scale.range.1) - axis_value(scale, scale.range.0)).abs() / px;
+        let half_w = (text.chars().count() as f64 * size * 0.6 / 2.0 + padding) * per_px(h_scale, plot_w);
+        let half_h = (size / 2.0 + padding) * per_px(v_scale, plot_h);
+        let (x, y) = (axis_value(h_scale, pos.0), axis_value(v_scale, pos.1));
+        TextBounds { x: (x - half_w, x + half_w), y: (y - half_h, y + half_h) }
+    }
+
+    /// Top-left and bottom-right corners in data units
+    fn corners(&self, (h_scale, v_scale): (&Scale, &Scale)) -> ((f64, f64), (f64, f64)) {
+        let data = |scale: &Scale, v: f64| if scale.is_log { 10f64.powf(v) } else { v };
+        ((data(h_scale, self.x.0), data(v_scale, self.y.1)), (data(h_scale, self.x.1), data(v_scale, self.y.0)))
+    }
+
+    fn overlaps(&self, other: &TextBounds) -> bool {
+        self.x.0 < other.x.1 && other.x.0 < self.x.1 && self.y.0 < other.y.1 && other.y.0 < self.y.1
+    }
+}
+
+/// Remove the legend label from a command, returning it as a shared legend entry
+fn take_legend_entry(cmd: &mut DrawCommand) -> Option<LegendEntry> {
+    match cmd {
+        DrawCommand::DrawLine { style, legend, .. } => legend.take()
+            .map(|label| LegendEntry { label, style: RenderStyle::Line(style.clone()) }),
+        DrawCommand::DrawPoint { style, legend, .. } => legend.take()
+            .map(|label| LegendEntry { label, style: RenderStyle::Point(style.clone()) }),
+        DrawCommand::DrawRect { style, legend, .. } => legend.take()
+            .map(|label| LegendEntry { label, style: RenderStyle::Bar(style.clone()) }),
+        DrawCommand::DrawPolygon { style, legend, .. } => legend.take()
+            .map(|label| LegendEntry { label, style: RenderStyle::Ribbon(style.clone()) }),
+        DrawCommand::DrawText { style, legend, .. } => legend.take()
+            .map(|label| LegendEntry { label, style: RenderStyle::Text(style.clone()) }),
+    }
+}
+
+/// Legend label for a reference line group's first command. With abline(legend: ...) the entry goes
+/// to the panel's `legend_items` instead (so it survives clipping), suffixed by the group key when
+/// the layer is grouped.
+fn reference_legend(label: &Option<String>, key: &str, group_count: usize, style: &LineStyle, items: &mut Vec<LegendEntry>) -> Option<String> {
+    let Some(label) = label else { return Some(key.to_string()) };
+    let label = if group_count > 1 { format!("{} ({})", label, key) } else { label.clone() };
+    items.push(LegendEntry { label, style: RenderStyle::Line(style.clone()) });
+    None
+}
+
+/// How a layer's groups sharing an x are arranged (boxplots and violins always dodge)
+fn layer_position(layer: &Layer) -> LayerPosition {
+    match layer {
+        Layer::Bar(b) => b.position.clone(),
+        Layer::Boxplot(_) | Layer::Violin(_) => LayerPosition::Dodge,
+        Layer::Point(p) if p.range.is_some() => p.position.clone(),
+        _ => LayerPosition::Identity,
+    }
+}
+
+/// Quantized X (y for horizontal ranges) -> Keys of the groups present at that X, sorted
+type XOccupancy = HashMap<i64, Vec<String>>;
+
+/// Occupancy of the given groups (of one layer, or of the layers dodging together)
+fn x_occupancy<'a>(groups: impl Iterator<Item = &'a GroupData>, horizontal: bool) -> XOccupancy {
+    let mut occupancy = XOccupancy::new();
+    for group in groups {
+        for &x in if horizontal { &group.y } else { &group.x } {
+            // Quantize X to integer for categorical grouping logic
+            // (Use round() to handle float imprecision)
+            occupancy.entry(x.round() as i64).or_default().push(group.key.clone());
+        }
+    }
+    // Sort groups at each X by key, the order layers list their groups in
+    for groups_at_x in occupancy.values_mut() {
+        groups_at_x.sort();
+        groups_at_x.dedup(); // Handle multiple points (or layers) per group at same X
+    }
+    occupancy
+}
+
+/// (slot width, x offset) of group `key` at `x`: with position "dodge", `width` is split evenly
+/// between the groups in `x_occupancy` at that x (in key order, centered on x); otherwise the
+/// group keeps the whole width, centered
+fn dodge_slot(x_occupancy: &XOccupancy, position: &LayerPosition, x: f64, key: &str, width: f64) -> (f64, f64) {
+    if !matches!(position, LayerPosition::Dodge) {
+        return (width, 0.0);
+    }
+    let Some(occupants) = x_occupancy.get(&(x.round() as i64)) else { return (width, 0.0) };
+    let Some(rank) = occupants.iter().position(|g| g == key) else { return (width, 0.0) };
+    let num_at_x = occupants.len() as f64;
+    let slot = width / num_at_x;
+    (slot, (rank as f64 - (num_at_x - 1.0) / 2.0) * slot)
+}
+
+/// x interval over which y = intercept + slope * x stays inside the y domain, within the x domain
+fn abline_span(slope: f64, intercept: f64, (x_lo, x_hi): (f64, f64), (y_lo, y_hi): (f64, f64)) -> Option<(f64, f64)> {
+    let (lo, hi) = if slope == 0.0 {
+        if intercept < y_lo || intercept > y_hi {
+            return None;
+        }
+        (x_lo, x_hi)
+    } else {
+        let (a, b) = ((y_lo - intercept) / slope, (y_hi - intercept) / slope);
+        (x_lo.max(a.min(b)), x_hi.min(a.max(b)))
+    };
+    (lo < hi).then_some((lo, hi))
+}
+
+/// Stair path through `points` for step(): each pair (x1, y1), (x2, y2) gets corner
+/// (x2, y1) for "hv", (x1, y2) for "vh", or (x_mid, y1), (x_mid, y2) for "mid"
+fn step_points(points: &[(f64, f64)], direction: StepDirection) -> Vec<(f64, f64)> {
+    let mut stairs = Vec::with_capacity(points.len() * 3);
+    for pair in points.windows(2) {
+        let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+        stairs.push((x1, y1));
+        match direction {
+            StepDirection::Hv => stairs.push((x2, y1)),
+            StepDirection::Vh => stairs.push((x1, y2)),
+            StepDirection::Mid => {
+                let x_mid = (x1 + x2) / 2.0;
+                stairs.extend([(x_mid, y1), (x_mid, y2)]);
+            }
+        }
+    }
+    stairs.extend(points.last());
+    stairs
+}
+
+/// Length of a segment(arrow: true) head, in pixels
+const ARROW_HEAD_LENGTH: f64 = 10.0;
+
+/// segment(arrow: true): the two barb ends, in data units, of an arrow head pointing at `tip` along
+/// the line from `from`, sized and angled in pixels (30° either side) so the axes' units don't skew
+/// it. None for a zero-length segment, which has no direction
+fn arrow_barbs(tip: (f64, f64), from: (f64, f64), scales: &PanelScales, is_flipped: bool, (plot_w, plot_h): (f64, f64)) -> Option<[(f64, f64); 2]> {
+    let (x_px, y_px) = if is_flipped { (plot_h, plot_w) } else { (plot_w, plot_h) };
+    let per_unit = |scale: &Scale, px: f64| px / (axis_value(scale, scale.range.1) - axis_value(scale, scale.range.0)).abs().max(f64::MIN_POSITIVE);
+    let (sx, sy) = (per_unit(&scales.x, x_px), per_unit(&scales.y, y_px));
+    let to_px = |(x, y): (f64, f64)| (axis_value(&scales.x, x) * sx, axis_value(&scales.y, y) * sy);
+    let from_px = |scale: &Scale, v: f64| if scale.is_log { 10f64.powf(v) } else { v };
+
+    let ((tx, ty), (fx, fy)) = (to_px(tip), to_px(from));
+    let len = (tx - fx).hypot(ty - fy);
+    if len == 0.0 || !len.is_finite() {
+        return None;
+    }
+    let (ux, uy) = ((fx - tx) / len, (fy - ty) / len); // Unit vector back along the line
+    let (sin, cos) = std::f64::consts::FRAC_PI_6.sin_cos();
+    Some([sin, -sin].map(|sin| {
+        let (bx, by) = (ux * cos - uy * sin, ux * sin + uy * cos);
+        (from_px(&scales.x, (tx + bx * ARROW_HEAD_LENGTH) / sx), from_px(&scales.y, (ty + by * ARROW_HEAD_LENGTH) / sy))
+    }))
+}
+
+/// rug(): (bottom, top) of the tick at each x. The band is `length` of the y range tall, hanging
+/// below y = 0 when the range spans zero (as under a density() curve, clamped to the room left
+/// there) and standing on the panel bottom otherwise. `jitter` (seeded by the group index) makes
+/// the ticks a third of the band tall, each at a reproducible pseudo-random height within it
+fn rug_ticks(xs: &[f64], y: &Scale, length: f64, jitter: Option<usize>) -> Vec<[(f64, f64); 2]> {
+    let (lo, hi) = (axis_value(y, y.domain.0), axis_value(y, y.domain.1));
+    let band = length * (hi - lo);
+    let (base, dir, band) = if !y.is_log && lo < 0.0 && hi > 0.0 { (0.0, -1.0, band.min(-lo)) } else { (lo, 1.0, band) };
+    let at = |t: f64| {
+        let v = base + dir * t;
+        if y.is_log { 10f64.powf(v) } else { v }
+    };
+
+    xs.iter().enumerate().map(|(i, &x)| {
+        let (from, to) = match jitter {
+            Some(seed) => {
+                let start = jitter_unit(((seed as u64) << 32) | i as u64) * band * 2.0 / 3.0;
+                (start, start + band / 3.0)
+            }
+            None => (0.0, band),
+        };
+        [(x, at(from)), (x, at(to))]
+    }).collect()
+}
+
+/// Pseudo-random value in [0, 1) from a seed (splitmix64), so jittered renders are reproducible
+fn jitter_unit(seed: u64) -> f64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    ((z ^ (z >> 31)) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// beeswarm(): sideways offset (x units) of every point of every group, so that no two dots of
+/// an x category overlap on screen. Dots are placed in `priority` order, measured in pixels of
+/// the approximate plot area; with coord_flip() the swarm spreads vertically instead
+fn beeswarm_offsets(groups: &[GroupData], priority: BeeswarmPriority, cex: f64, scales: &PanelScales, is_flipped: bool, (plot_w, plot_h): (f64, f64)) -> Vec<Vec<f64>> {
+    let (value_px, spread_px) = if is_flipped { (plot_w, plot_h) } else { (plot_h, plot_w) };
+    let span = |scale: &Scale| (axis_value(scale, scale.range.1) - axis_value(scale, scale.range.0)).abs().max(f64::MIN_POSITIVE);
+    let (px_per_y, x_per_px) = (value_px / span(&scales.y), span(&scales.x) / spread_px);
+
+    // (group, row) of each point, per x category, in group then row order
+    let mut categories: HashMap<u64, Vec<(usize, usize)>> = HashMap::new();
+    for (g, group) in groups.iter().enumerate() {
+        for (i, x) in group.x.iter().enumerate() {
+            categories.entry(x.to_bits()).or_default().push((g, i));
+        }
+    }
+
+    let mut offsets: Vec<Vec<f64>> = groups.iter().map(|g| vec![0.0; g.x.len()]).collect();
+    for mut members in categories.into_values() {
+        let y = |&(g, i): &(usize, usize)| groups[g].y[i];
+        match priority {
+            BeeswarmPriority::Ascending => members.sort_by(|a, b| y(a).total_cmp(&y(b))),
+            BeeswarmPriority::Descending => members.sort_by(|a, b| y(b).total_cmp(&y(a))),
+            BeeswarmPriority::DataOrder => {}
+        }
+        let dots: Vec<(f64, f64)> = members.iter().map(|m| {
+            let radius = match &groups[m.0].style {
+                RenderStyle::Point(style) => style.size.unwrap_or(3.0),
+                _ => 3.0,
+            };
+            (axis_value(&scales.y, y(m)) * px_per_y, radius * cex)
+        }).collect();
+        for (&(g, i), dx) in members.iter().zip(swarm_offsets(&dots)) {
+            offsets[g][i] = dx * x_per_px;
+        }
+    }
+    offsets
+}
+
+/// Sideways offset (px) of each (y px, radius px) dot, placed in order: each takes the position
+/// nearest the center, among the center and the spots touching a placed dot, that clears all
+/// dots placed before it
+fn swarm_offsets(dots: &[(f64, f64)]) -> Vec<f64> {
+    let mut offsets = vec![0.0; dots.len()];
+    // (y, offset, radius) of the dots placed so far, sorted by y: only those within reach in y
+    // (the two radii) can touch the next dot, found by binary search
+    let mut placed: Vec<(f64, f64, f64)> = Vec::with_capacity(dots.len());
+    let mut max_radius = 0.0f64;
+    for (&(y, r), offset) in dots.iter().zip(&mut offsets) {
+        let lo = placed.partition_point(|p| p.0 <= y - r - max_radius);
+        let hi = placed.partition_point(|p| p.0 < y + r + max_radius);
+        let near = &placed[lo..hi];
+
+        let mut candidates = vec![0.0];
+        for &(py, px, pr) in near {
+            let (reach, dy) = (r + pr, y - py);
+            if dy.abs() < reach {
+                let dx = (reach * reach - dy * dy).sqrt();
+                candidates.extend([px - dx, px + dx]);
+            }
+        }
+        candidates.sort_by(|a, b| a.abs().total_cmp(&b.abs()).then(a.total_cmp(b)));
+
+        // The outermost touching spot always clears, so the search cannot come up empty
+        let clears = |dx: f64| near.iter().all(|&(py, px, pr)| (dx - px).hypot(y - py) >= r + pr - 1e-6);
+        let dx = candidates.into_iter().find(|&dx| clears(dx)).unwrap_or(0.0);
+        *offset = dx;
+        let at = placed.partition_point(|p| p.0 < y);
+        placed.insert(at, (y, dx, r));
+        max_radius = max_radius.max(r);
+    }
+    offsets
+}
+
+/// A continuous axis' limits with its out-of-bounds treatment (None = keep everything)
+#[derive(Clone, Copy)]
+struct AxisBounds(Option<(f64, f64, OutOfBounds)>);
+
+impl AxisBounds {
+    fn new(scale: &Scale, oob: OutOfBounds) -> Self {
+        let (a, b) = scale.domain;
+        AxisBounds((!scale.is_categorical && oob != OutOfBounds::Keep).then(|| (a.min(b), a.max(b), oob)))
+    }
+
+    /// The value squished into the limits, or None when it is censored
+    fn fix(&self, v: f64) -> Option<f64> {
+        match self.0 {
+            Some((lo, hi, OutOfBounds::Squish)) => Some(v.clamp(lo, hi)),
+            Some((lo, hi, OutOfBounds::Censor)) => (lo..=hi).contains(&v).then_some(v),
+            _ => Some(v),
+        }
+    }
+}
+
+/// scale_*_continuous(oob: ...) for both drawn axes of a panel
+struct OobBounds {
+    x: AxisBounds,
+    y: AxisBounds,
+}
+
+impl OobBounds {
+    fn is_noop(&self) -> bool {
+        self.x.0.is_none() && self.y.0.is_none()
+    }
+
+    fn fix(&self, (x, y): (f64, f64)) -> Option<(f64, f64)> {
+        Some((self.x.fix(x)?, self.y.fix(y)?))
+    }
+
+    /// Squish or censor every drawn coordinate. Censored points are dropped, lines break
+    /// into the runs between them, and rects or text with a censored anchor disappear.
+    /// The legend entry of a dropped command goes to `legend_items`, keeping its own style.
+    fn apply(&self, commands: Vec<DrawCommand>, legend_items: &mut Vec<LegendEntry>) -> Vec<DrawCommand> {
+        let mut out = Vec::with_capacity(commands.len());
+
+        for mut cmd in commands {
+            let entry = take_legend_entry(&mut cmd);
+
+            let mut fixed: Vec<DrawCommand> = match cmd {
+                DrawCommand::DrawLine { points, style, legend } => {
+                    let mut runs: Vec<Vec<(f64, f64)>> = vec![Vec::new()];
+                    for p in points {
+                        match self.fix(p) {
+                            Some(p) => runs.last_mut().unwrap().push(p),
+                            None if !runs.last().unwrap().is_empty() => runs.push(Vec::new()),
+                            None => {}
+                        }
+                    }
+                    runs.into_iter()
+                        .filter(|run| run.len() >= 2)
+                        .map(|points| DrawCommand::DrawLine { points, style: style.clone(), legend: legend.clone() })
+                        .collect()
+                }
+                DrawCommand::DrawPoint { points, style, legend } => {
+                    let points: Vec<(f64, f64)> = points.into_iter().filter_map(|p| self.fix(p)).collect();
+                    if points.is_empty() { vec![] } else { vec![DrawCommand::DrawPoint { points, style, legend }] }
+                }
+                DrawCommand::DrawRect { tl, br, style, legend } => match (self.fix(tl), self.fix(br)) {
+                    (Some(tl), Some(br)) => vec![DrawCommand::DrawRect { tl, br, style, legend }],
+                    _ => vec![],
+                },
+                DrawCommand::DrawPolygon { points, style, legend } => {
+                    let points: Vec<(f64, f64)> = points.into_iter().filter_map(|p| self.fix(p)).collect();
+                    if points.len() < 3 { vec![] } else { vec![DrawCommand::DrawPolygon { points, style, legend }] }
+                }
+                DrawCommand::DrawText { pos, text, style, legend } => match self.fix(pos) {
+                    Some(pos) => vec![DrawCommand::DrawText { pos, text, style, legend }],
+                    None => vec![],
+                },
+            };
+
+            match (fixed.first_mut(), entry) {
+                (Some(first), Some(entry)) => *command_legend(first) = Some(entry.label),
+                (None, Some(entry)) => legend_items.push(entry),
+                (_, None) => {}
+            }
+            out.extend(fixed);
+        }
+        out
+    }
+}
+
+/// The legend label slot of any draw command
+fn command_legend(cmd: &mut DrawCommand) -> &mut Option<String> {
+    match cmd {
+        DrawCommand::DrawLine { legend, .. }
+        | DrawCommand::DrawPoint { legend, .. }
+        | DrawCommand::DrawRect { legend, .. }
+        | DrawCommand::DrawPolygon { legend, .. }
+        | DrawCommand::DrawText { legend, .. } => legend,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -567,36 +1419,53 @@ mod tests {
                         y_start: vec![0.0, 0.0],
                         y_min: vec![0.0, 0.0],
                         y_max: vec![10.0, 20.0],
+                        x_min: vec![],
+                        x_max: vec![],
                         y_q1: vec![],
                         y_median: vec![],
                         y_q3: vec![],
+                        y_notch_low: vec![],
+                        y_notch_high: vec![],
                         outliers: vec![],
+                        counts: vec![],
+                        cell_values: vec![],
                         violin_density: vec![],
                         violin_density_y: vec![],
                         violin_quantile_values: vec![],
+                        fills: vec![],
+                        residuals: vec![],
+                        contours: vec![],
+                        widths: vec![],
+                        labels: vec![],
+                        ablines: vec![],
+                        segment_ends: vec![],
                         x_categories: None,
+                        x_category_positions: vec![],
+                        y_categories: None,
                         style: RenderStyle::Line(LineStyle::default()),
                     }],
                 }],
             }],
             facet_layout: FacetLayout { nrow: 1, ncol: 1, panel_titles: vec![] },
+            colorbar: None,
         };
 
         let scales = ScaleSystem {
             panels: vec![PanelScales {
-                x: Scale { domain: (0.0, 1.0), range: (0.0, 1.0), is_categorical: false, categories: vec![] },
-                y: Scale { domain: (0.0, 20.0), range: (0.0, 20.0), is_categorical: false, categories: vec![] },
+                x: Scale { domain: (0.0, 1.0), range: (0.0, 1.0), is_categorical: false, is_log: false, categories: vec![], category_positions: vec![], is_percent: false, breaks_n: None, breaks_width: None, labels: Default::default() },
+                y: Scale { domain: (0.0, 20.0), range: (0.0, 20.0), is_categorical: false, is_log: false, categories: vec![], category_positions: vec![], is_percent: false, breaks_n: None, breaks_width: None, labels: Default::default() },
             }],
         };
 
         let spec = ResolvedSpec {
             layers: vec![ResolvedLayer {
                 original_layer: Layer::Line(LineLayer::default()),
+                inherits_binned: false,
                 aesthetics: ResolvedAesthetics {
                     x_col: "x".to_string(),
                     y_col: Some("y".to_string()),
-                    ymin_col: None, ymax_col: None,
-                    color: None, size: None, shape: None, alpha: None
+                    ymin_col: None, ymax_col: None, xmin_col: None, xmax_col: None,
+                    color: None, size: None, shape: None, alpha: None, linetype: None, fill: None, label: None, colors: None
                 },
             }],
             facet: None,
@@ -605,11 +1474,792 @@ mod tests {
             theme: crate::parser::ast::Theme::default(),
             x_scale_spec: None,
             y_scale_spec: None,
+            color_scale: None,
+            linetype_values: None,
+            fill_scale: None,
+            identity_scales: Default::default(),
         };
         
         (render_data, scales, spec)
     }
 
+    #[test]
+    fn test_legend_title_from_labels() {
+        let (data, scales, mut spec) = make_test_data();
+        spec.layers[0].aesthetics.color = Some("region".to_string());
+        spec.labels.color_title = Some("Region".to_string());
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        assert_eq!(scene.panels[0].legend_title, Some("Region".to_string()));
+    }
+
+    #[test]
+    fn test_legend_title_requires_mapping() {
+        let (data, scales, mut spec) = make_test_data();
+        spec.labels.color_title = Some("Region".to_string());
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        assert_eq!(scene.panels[0].legend_title, None);
+    }
+
+    #[test]
+    fn test_shared_legend_for_facets() {
+        let (mut data, scales, mut spec) = make_test_data();
+        let mut second = data.panels[0].clone();
+        second.index = 1;
+        data.panels.push(second);
+        data.facet_layout = FacetLayout { nrow: 1, ncol: 2, panel_titles: vec!["a".to_string(), "b".to_string()] };
+        let scales = ScaleSystem { panels: vec![scales.panels[0].clone(), scales.panels[0].clone()] };
+        spec.facet = Some(crate::ir::ResolvedFacet { col: "f".to_string(), ncol: None, scales: crate::parser::ast::FacetScales::Fixed, strip_position: crate::parser::ast::StripPosition::Top, space: crate::parser::ast::FacetSpace::Fixed });
+
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+
+        // One entry for group "A", and no per-panel legends
+        assert_eq!(scene.legend.len(), 1);
+        assert_eq!(scene.legend[0].label, "A");
+        for panel in &scene.panels {
+            for cmd in &panel.commands {
+                if let DrawCommand::DrawLine { legend, .. } = cmd {
+                    assert!(legend.is_none());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_free_y_space_sizes_rows_by_y_range() {
+        let (mut data, scales, mut spec) = make_test_data();
+        let mut second = data.panels[0].clone();
+        second.index = 1;
+        data.panels.push(second);
+        data.facet_layout = FacetLayout { nrow: 2, ncol: 1, panel_titles: vec!["a".to_string(), "b".to_string()] };
+        let mut tall = scales.panels[0].clone();
+        tall.y.range = (0.0, 60.0);
+        let scales = ScaleSystem { panels: vec![scales.panels[0].clone(), tall] };
+        spec.facet = Some(crate::ir::ResolvedFacet { col: "f".to_string(), ncol: Some(1), scales: crate::parser::ast::FacetScales::FreeY, strip_position: crate::parser::ast::StripPosition::Top, space: FacetSpace::FreeY });
+
+        let scene = compile_geometry(data.clone(), scales.clone(), &spec, &RenderOptions::default()).unwrap();
+        // y spans of 20 and 60
+        assert_eq!(scene.row_heights, vec![0.25, 0.75]);
+
+        spec.facet.as_mut().unwrap().space = FacetSpace::Fixed;
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        assert!(scene.row_heights.is_empty());
+    }
+
+    #[test]
+    fn test_strip_position_reaches_panels() {
+        let (mut data, scales, mut spec) = make_test_data();
+        data.facet_layout = FacetLayout { nrow: 1, ncol: 1, panel_titles: vec!["a".to_string()] };
+        spec.facet = Some(crate::ir::ResolvedFacet { col: "f".to_string(), ncol: None, scales: crate::parser::ast::FacetScales::Fixed, strip_position: crate::parser::ast::StripPosition::Bottom, space: crate::parser::ast::FacetSpace::Fixed });
+
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        assert_eq!(scene.panels[0].title.as_deref(), Some("f = a"));
+        assert_eq!(scene.panels[0].strip_position, crate::parser::ast::StripPosition::Bottom);
+    }
+
+    #[test]
+    fn test_reference_line_legend_items() {
+        let style = LineStyle::default();
+        let mut items = Vec::new();
+        assert_eq!(reference_legend(&None, "default", 1, &style, &mut items), Some("default".to_string()));
+        assert!(items.is_empty());
+
+        let label = Some("Target".to_string());
+        assert_eq!(reference_legend(&label, "default", 1, &style, &mut items), None);
+        assert_eq!(reference_legend(&label, "B", 2, &style, &mut items), None);
+        let labels: Vec<&str> = items.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, vec!["Target", "Target (B)"]);
+    }
+
+    #[test]
+    fn test_step_points_directions() {
+        let points = [(0.0, 1.0), (2.0, 3.0), (4.0, 2.0)];
+        assert_eq!(step_points(&points, StepDirection::Hv), vec![(0.0, 1.0), (2.0, 1.0), (2.0, 3.0), (4.0, 3.0), (4.0, 2.0)]);
+        assert_eq!(step_points(&points, StepDirection::Vh), vec![(0.0, 1.0), (0.0, 3.0), (2.0, 3.0), (2.0, 2.0), (4.0, 2.0)]);
+        assert_eq!(
+            step_points(&points, StepDirection::Mid),
+            vec![(0.0, 1.0), (1.0, 1.0), (1.0, 3.0), (2.0, 3.0), (3.0, 3.0), (3.0, 2.0), (4.0, 2.0)]
+        );
+        assert_eq!(step_points(&[(1.0, 1.0)], StepDirection::Mid), vec![(1.0, 1.0)]);
+        assert!(step_points(&[], StepDirection::Hv).is_empty());
+    }
+
+    #[test]
+    fn test_rug_ticks_hang_below_zero() {
+        let scale = |domain: (f64, f64)| Scale {
+            domain,
+            range: domain,
+            is_categorical: false,
+            is_log: false,
+            categories: vec![],
+            category_positions: vec![],
+            is_percent: false,
+            breaks_n: None,
+            breaks_width: None,
+            labels: crate::parser::ast::TickFormat::Auto,
+        };
+        // Under a density curve: from y = 0 down, clamped to the padding below zero
+        assert_eq!(rug_ticks(&[1.0, 2.0], &scale((-0.05, 1.05)), 0.5, None), vec![[(1.0, 0.0), (1.0, -0.05)], [(2.0, 0.0), (2.0, -0.05)]]);
+        // Elsewhere: standing on the panel bottom
+        assert_eq!(rug_ticks(&[1.0], &scale((10.0, 20.0)), 0.1, None), vec![[(1.0, 10.0), (1.0, 11.0)]]);
+
+        let xs: Vec<f64> = (0..50).map(f64::from).collect();
+        let ticks = rug_ticks(&xs, &scale((10.0, 20.0)), 0.3, Some(1));
+        assert!(ticks.iter().all(|[(_, a), (_, b)]| *a >= 10.0 && *b <= 13.0 + 1e-9 && (b - a - 1.0).abs() < 1e-9));
+        let starts: HashSet<u64> = ticks.iter().map(|t| t[0].1.to_bits()).collect();
+        assert!(starts.len() > 40, "jitter should stagger the ticks");
+        assert_eq!(ticks, rug_ticks(&xs, &scale((10.0, 20.0)), 0.3, Some(1)));
+        assert!((0..1000).map(jitter_unit).all(|u| (0.0..1.0).contains(&u)));
+    }
+
+    #[test]
+    fn test_arrow_barbs_point_back_along_the_line() {
+        let scale = |domain: (f64, f64)| Scale {
+            domain,
+            range: domain,
+            is_categorical: false,
+            is_log: false,
+            categories: vec![],
+            category_positions: vec![],
+            is_percent: false,
+            breaks_n: None,
+            breaks_width: None,
+            labels: crate::parser::ast::TickFormat::Auto,
+        };
+        // 1 px per x unit, 0.1 px per y unit: the head is 10 px long whatever the units
+        let scales = PanelScales { x: scale((0.0, 100.0)), y: scale((0.0, 1000.0)) };
+        let [left, right] = arrow_barbs((50.0, 500.0), (0.0, 500.0), &scales, false, (100.0, 100.0)).unwrap();
+        let (sin, cos) = std::f64::consts::FRAC_PI_6.sin_cos();
+        assert!((left.0 - (50.0 - 10.0 * cos)).abs() < 1e-9 && (right.0 - left.0).abs() < 1e-9);
+        assert!((left.1 - (500.0 - 100.0 * sin)).abs() < 1e-9 && (right.1 - (500.0 + 100.0 * sin)).abs() < 1e-9);
+        assert_eq!(arrow_barbs((1.0, 1.0), (1.0, 1.0), &scales, false, (100.0, 100.0)), None);
+    }
+
+    #[test]
+    fn test_segment_arrow_commands() {
+        let (mut data, scales, mut spec) = make_test_data();
+        let mut commands = |arrow: Option<crate::parser::ast::Arrow>| {
+            spec.layers[0].original_layer = Layer::Line(LineLayer {
+                stat: Stat::Segment { xend: Some("x2".to_string()), yend: Some("y2".to_string()), arrow },
+                ..Default::default()
+            });
+            data.panels[0].layers[0].groups[0].segment_ends = vec![(0.5, 15.0), (1.0, 10.0)];
+            let scene = compile_geometry(data.clone(), scales.clone(), &spec, &RenderOptions::default()).unwrap();
+            let count = |f: fn(&DrawCommand) -> bool| scene.panels[0].commands.iter().filter(|c| f(c)).count();
+            (count(|c| matches!(c, DrawCommand::DrawLine { .. })), count(|c| matches!(c, DrawCommand::DrawPolygon { .. })))
+        };
+        use crate::parser::ast::Arrow;
+        assert_eq!(commands(None), (2, 0));
+        // A filled triangle per head, or two barb lines with open heads
+        assert_eq!(commands(Some(Arrow { head: ArrowType::Closed, ends: ArrowEnds::Last })), (2, 2));
+        assert_eq!(commands(Some(Arrow { head: ArrowType::Open, ends: ArrowEnds::Both })), (10, 0));
+    }
+
+    #[test]
+    fn test_crossbar_dodges_groups_at_the_same_x() {
+        let (mut data, scales, mut spec) = make_test_data();
+        let mut b = data.panels[0].layers[0].groups[0].clone();
+        b.key = "B".to_string();
+        data.panels[0].layers[0].groups.push(b);
+        for group in &mut data.panels[0].layers[0].groups {
+            group.style = RenderStyle::Point(PointStyle::default());
+        }
+        let mut boxes = |position| {
+            spec.layers[0].original_layer = Layer::Point(PointLayer {
+                range: Some(Default::default()),
+                crossbar: true,
+                position,
+                ..Default::default()
+            });
+            let scene = compile_geometry(data.clone(), scales.clone(), &spec, &RenderOptions::default()).unwrap();
+            scene.panels[0].commands.iter().filter_map(|c| match c {
+                DrawCommand::DrawRect { tl, br, .. } => Some((tl.0, br.0, tl.1, br.1)),
+                _ => None,
+            }).collect::<Vec<_>>()
+        };
+
+        // Each group takes half of the 0.8 width at x = 0, boxes spanning ymin to ymax
+        let dodged = boxes(LayerPosition::Dodge);
+        assert_eq!(dodged.len(), 4);
+        assert!((dodged[0].0 + 0.4).abs() < 1e-9 && dodged[0].1.abs() < 1e-9);
+        assert!(dodged[2].0.abs() < 1e-9 && (dodged[2].1 - 0.4).abs() < 1e-9);
+        assert_eq!((dodged[0].2, dodged[0].3), (10.0, 0.0));
+
+        // Identity overlays them across the full width
+        let overlaid = boxes(LayerPosition::Identity);
+        assert!(overlaid.iter().step_by(2).all(|b| (b.0 + 0.4).abs() < 1e-9 && (b.1 - 0.4).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_ungrouped_boxplot_stays_centered_over_dodged_violins() {
+        let rows = [("a", "A"), ("a", "B")].iter()
+            .flat_map(|(c, g)| (0..5).map(move |i| vec![c.to_string(), g.to_string(), i.to_string()]))
+            .collect();
+        let data = crate::data::PlotData { headers: ["c", "g", "y"].map(String::from).to_vec(), rows };
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: c, y: y) | violin(color: g) | boxplot(width: 0.1)").unwrap();
+        let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+        let render_data = crate::transform::apply_transformations(&resolved, &data).unwrap();
+        let scales = crate::scale::build_scales(&render_data, &resolved).unwrap();
+        let scene = compile_geometry(render_data, scales, &resolved, &RenderOptions::default()).unwrap();
+
+        // The two violins split the category; the box (no group column) is not given a third slot
+        let violins: Vec<f64> = scene.panels[0].commands.iter().filter_map(|c| match c {
+            DrawCommand::DrawPolygon { points, .. } => Some(points.iter().map(|p| p.0).sum::<f64>() / points.len() as f64),
+            _ => None,
+        }).collect();
+        assert_eq!(violins.len(), 2);
+        assert!((violins[0] + 0.2).abs() < 1e-9 && (violins[1] - 0.2).abs() < 1e-9, "{:?}", violins);
+        let boxes: Vec<(f64, f64)> = scene.panels[0].commands.iter().filter_map(|c| match c {
+            DrawCommand::DrawRect { tl, br, .. } => Some((tl.0, br.0)),
+            _ => None,
+        }).collect();
+        assert_eq!(boxes.len(), 1);
+        assert!((boxes[0].0 + boxes[0].1).abs() < 1e-9, "{:?}", boxes);
+    }
+
+    #[test]
+    fn test_notched_box_is_opaque() {
+        let rows = (0..10).map(|i| vec!["a".to_string(), i.to_string()]).collect();
+        let data = crate::data::PlotData { headers: ["c", "y"].map(String::from).to_vec(), rows };
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: c, y: y) | boxplot(notch: true)").unwrap();
+        let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+        let render_data = crate::transform::apply_transformations(&resolved, &data).unwrap();
+        let scales = crate::scale::build_scales(&render_data, &resolved).unwrap();
+        let scene = compile_geometry(render_data, scales, &resolved, &RenderOptions::default()).unwrap();
+        let alphas: Vec<Option<f64>> = scene.panels[0].commands.iter().filter_map(|c| match c {
+            DrawCommand::DrawPolygon { style, .. } => Some(style.alpha),
+            _ => None,
+        }).collect();
+        assert_eq!(alphas, vec![Some(1.0)]);
+    }
+
+    #[test]
+    fn test_horizontal_pointrange_keeps_axes() {
+        let rows = [["t1", "1", "3"], ["t2", "2", "5"]].iter().map(|r| r.map(String::from).to_vec()).collect();
+        let data = crate::data::PlotData { headers: ["task", "start", "end"].map(String::from).to_vec(), rows };
+        let (spec, _) = crate::parser::parse_plot_spec(r#"aes(y: task, x: start, xmin: start, xmax: end) | pointrange() | point() | labs(x: "Time")"#).unwrap();
+        let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+        assert_eq!(resolved.coord, None);
+        let render_data = crate::transform::apply_transformations(&resolved, &data).unwrap();
+        let scales = crate::scale::build_scales(&render_data, &resolved).unwrap();
+        let scene = compile_geometry(render_data, scales, &resolved, &RenderOptions::default()).unwrap();
+
+        let panel = &scene.panels[0];
+        assert_eq!(panel.x_label.as_deref(), Some("Time"));
+        assert_eq!(panel.y_scale.categories, vec!["t1", "t2"]);
+        // Each range runs along x at its task's y
+        let ranges: Vec<&Vec<(f64, f64)>> = panel.commands.iter().filter_map(|c| match c {
+            DrawCommand::DrawLine { points, .. } => Some(points),
+            _ => None,
+        }).collect();
+        assert_eq!(ranges, vec![&vec![(1.0, 0.0), (3.0, 0.0)], &vec![(2.0, 1.0), (5.0, 1.0)]]);
+        // The second layer's points sit on the same categorical y
+        let points: Vec<(f64, f64)> = panel.commands.iter().filter_map(|c| match c {
+            DrawCommand::DrawPoint { points, .. } => Some(points.clone()),
+            _ => None,
+        }).flatten().collect();
+        assert!(points.contains(&(1.0, 0.0)) && points.contains(&(2.0, 1.0)), "{:?}", points);
+    }
+
+    #[test]
+    fn test_dodge_slots_shared_across_layers() {
+        let (mut data, scales, mut spec) = make_test_data();
+        data.panels[0].layers[0].groups[0].style = RenderStyle::Point(PointStyle::default());
+        let mut b = data.panels[0].layers[0].clone();
+        b.groups[0].key = "B".to_string();
+        data.panels[0].layers.push(b);
+        spec.layers[0].original_layer = Layer::Point(PointLayer {
+            range: Some(Default::default()),
+            crossbar: true,
+            position: LayerPosition::Dodge,
+            ..Default::default()
+        });
+        spec.layers[0].aesthetics.color = Some("g".to_string());
+        spec.layers.push(spec.layers[0].clone());
+
+        // Both layers group by g: group A of the first and group B of the second split x = 0 between them
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let boxes: Vec<_> = scene.panels[0].commands.iter().filter_map(|c| match c {
+            DrawCommand::DrawRect { tl, br, .. } => Some((tl.0, br.0)),
+            _ => None,
+        }).collect();
+        assert_eq!(boxes.len(), 4);
+        assert!((boxes[0].0 + 0.4).abs() < 1e-9 && boxes[0].1.abs() < 1e-9);
+        assert!(boxes[2].0.abs() < 1e-9 && (boxes[2].1 - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_smooth_se_band_behind_line() {
+        let (data, scales, mut spec) = make_test_data();
+        spec.layers[0].original_layer = Layer::Line(LineLayer {
+            stat: Stat::Smooth { method: Default::default(), n: 80, residuals: false, formula: None, fullrange: false, se: SeType::Prediction(0.9) },
+            ..Default::default()
+        });
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let commands = &scene.panels[0].commands;
+        match &commands[0] {
+            // y_max left to right, then y_min back
+            DrawCommand::DrawPolygon { points, legend, .. } => {
+                assert_eq!(points, &vec![(0.0, 10.0), (1.0, 20.0), (1.0, 0.0), (0.0, 0.0)]);
+                assert!(legend.is_none());
+            }
+            other => panic!("Expected the band first, got {:?}", other),
+        }
+        assert!(matches!(commands[1], DrawCommand::DrawLine { .. }));
+    }
+
+    #[test]
+    fn test_ribbon_border_traces_both_edges() {
+        let (mut data, scales, mut spec) = make_test_data();
+        spec.layers[0].original_layer = Layer::Ribbon(RibbonLayer {
+            border: Some("black".to_string()),
+            linetype: Some("dashed".to_string()),
+            ..Default::default()
+        });
+        let group = &mut data.panels[0].layers[0].groups[0];
+        group.style = RenderStyle::Ribbon(RibbonStyle::default());
+        group.y_min = vec![1.0, 2.0];
+        group.y_max = vec![3.0, 4.0];
+
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let lines: Vec<_> = scene.panels[0].commands.iter().filter_map(|c| match c {
+            DrawCommand::DrawLine { points, style, .. } => Some((points.clone(), style.clone())),
+            _ => None,
+        }).collect();
+        // ymax then ymin, each left to right, with no segments closing the ends
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].0, vec![(0.0, 3.0), (1.0, 4.0)]);
+        assert_eq!(lines[1].0, vec![(0.0, 1.0), (1.0, 2.0)]);
+        assert_eq!(lines[0].1.linetype.as_deref(), Some("dashed"));
+    }
+
+    #[test]
+    fn test_swarm_offsets_clear_each_other() {
+        // Equal values fan out center, left, right, ...
+        let offsets = swarm_offsets(&[(10.0, 3.0); 4]);
+        assert_eq!(offsets, vec![0.0, -6.0, 6.0, -12.0]);
+
+        // Far apart values all stay on the center line
+        assert_eq!(swarm_offsets(&[(0.0, 3.0), (10.0, 3.0), (20.0, 3.0)]), vec![0.0; 3]);
+
+        let dots: Vec<(f64, f64)> = (0..60).map(|i| (((i * 37) % 23) as f64 * 0.9, 2.0 + (i % 3) as f64)).collect();
+        let offsets = swarm_offsets(&dots);
+        for i in 0..dots.len() {
+            for j in 0..i {
+                let dist = (offsets[i] - offsets[j]).hypot(dots[i].0 - dots[j].0);
+                assert!(dist >= dots[i].1 + dots[j].1 - 1e-6, "dots {} and {} overlap", j, i);
+            }
+        }
+        // A dot touching only the first one sits just beside it
+        let offsets = swarm_offsets(&[(0.0, 3.0), (3.0, 3.0)]);
+        assert!((offsets[1].abs() - 27f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_swarm_offsets_large_category() {
+        // 3000 dots packed into one 400px tall category only check their neighbours in y
+        let dots: Vec<(f64, f64)> = (0..3000).map(|i| (((i * 7919) % 3000) as f64 * 400.0 / 3000.0, 1.5)).collect();
+        let offsets = swarm_offsets(&dots);
+        assert_eq!(offsets.len(), dots.len());
+        let mut order: Vec<usize> = (0..dots.len()).collect();
+        order.sort_by(|&a, &b| dots[a].0.total_cmp(&dots[b].0));
+        for (k, &i) in order.iter().enumerate() {
+            for &j in order[k + 1..].iter().take_while(|&&j| dots[j].0 - dots[i].0 < 3.0) {
+                let dist = (offsets[i] - offsets[j]).hypot(dots[i].0 - dots[j].0);
+                assert!(dist >= 3.0 - 1e-6, "dots {} and {} overlap", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compute_boxplot_geometry() {
+        let geom = compute_boxplot_geometry(1.0, 0.5, 0.0, 2.0, 3.0, 5.0, 8.0, &[12.0], None, 0.4, true);
+        assert_eq!(geom.lower_whisker, vec![(1.0, 0.0), (1.0, 2.0)]);
+        assert_eq!(geom.upper_whisker, vec![(1.0, 5.0), (1.0, 8.0)]);
+        assert_eq!(geom.min_cap, vec![(0.9, 0.0), (1.1, 0.0)]);
+        assert_eq!(geom.box_tl, (0.75, 5.0));
+        assert_eq!(geom.box_br, (1.25, 2.0));
+        assert_eq!(geom.median_line, vec![(0.75, 3.0), (1.25, 3.0)]);
+        assert_eq!(geom.outlier_points, vec![(1.0, 12.0)]);
+
+        // coord_flip swaps the axes
+        let flipped = compute_boxplot_geometry(1.0, 0.5, 0.0, 2.0, 3.0, 5.0, 8.0, &[12.0], None, 0.4, false);
+        assert_eq!(flipped.max_cap, vec![(8.0, 0.9), (8.0, 1.1)]);
+        assert_eq!(flipped.box_tl, (2.0, 0.75));
+        assert_eq!(flipped.box_br, (5.0, 1.25));
+        assert_eq!(flipped.outlier_points, vec![(12.0, 1.0)]);
+    }
+
+    #[test]
+    fn test_compute_boxplot_geometry_staplewidth() {
+        // Caps as wide as the box with staplewidth: 1
+        let geom = compute_boxplot_geometry(1.0, 0.5, 0.0, 2.0, 3.0, 5.0, 8.0, &[], None, 1.0, true);
+        assert_eq!(geom.min_cap, vec![(0.75, 0.0), (1.25, 0.0)]);
+        assert_eq!(geom.max_cap, vec![(0.75, 8.0), (1.25, 8.0)]);
+    }
+
+    #[test]
+    fn test_boxplot_staplewidth_zero_drops_caps() {
+        let (mut data, scales, mut spec) = make_test_data();
+        spec.layers[0].original_layer = Layer::Boxplot(crate::parser::ast::BoxplotLayer::default());
+        let mut lines = |staplewidth: Option<f64>| {
+            let group = &mut data.panels[0].layers[0].groups[0];
+            group.y_q1 = vec![5.0, 5.0];
+            group.y_median = vec![8.0, 8.0];
+            group.y_q3 = vec![12.0, 12.0];
+            group.outliers = vec![vec![], vec![]];
+            group.style = RenderStyle::Boxplot(crate::graph::BoxplotStyle { staplewidth, ..Default::default() });
+            let scene = compile_geometry(data.clone(), scales.clone(), &spec, &RenderOptions::default()).unwrap();
+            scene.panels[0].commands.iter().filter(|c| matches!(c, DrawCommand::DrawLine { .. })).count()
+        };
+        // Two whiskers, two caps and the median per box; no caps with staplewidth: 0
+        assert_eq!(lines(None), 10);
+        assert_eq!(lines(Some(0.0)), 6);
+    }
+
+    #[test]
+    fn test_compute_boxplot_geometry_notched() {
+        let geom = compute_boxplot_geometry(1.0, 0.5, 0.0, 2.0, 3.0, 5.0, 8.0, &[], Some((2.5, 3.5, 0.5)), 0.5, true);
+        assert_eq!(geom.box_outline, Some(vec![
+            (0.75, 2.0), (1.25, 2.0), (1.25, 2.5), (1.125, 3.0), (1.25, 3.5),
+            (1.25, 5.0), (0.75, 5.0), (0.75, 3.5), (0.875, 3.0), (0.75, 2.5),
+        ]));
+        // The median line spans the waist only
+        assert_eq!(geom.median_line, vec![(0.875, 3.0), (1.125, 3.0)]);
+
+        let flipped = compute_boxplot_geometry(1.0, 0.5, 0.0, 2.0, 3.0, 5.0, 8.0, &[], Some((2.5, 3.5, 0.5)), 0.5, false);
+        assert_eq!(flipped.box_outline.unwrap()[3], (3.0, 1.125));
+        assert_eq!(flipped.median_line, vec![(3.0, 0.875), (3.0, 1.125)]);
+    }
+
+    #[test]
+    fn test_boxplot_varwidth_scales_by_sqrt_count() {
+        let (mut data, scales, mut spec) = make_test_data();
+        let style = crate::graph::BoxplotStyle { width: Some(0.8), varwidth: true, ..Default::default() };
+        {
+            let group = &mut data.panels[0].layers[0].groups[0];
+            group.y_q1 = vec![5.0, 5.0];
+            group.y_median = vec![8.0, 8.0];
+            group.y_q3 = vec![12.0, 12.0];
+            group.outliers = vec![vec![], vec![]];
+            group.counts = vec![100, 25];
+            group.style = RenderStyle::Boxplot(style);
+        }
+        spec.layers[0].original_layer = Layer::Boxplot(crate::parser::ast::BoxplotLayer { varwidth: true, ..Default::default() });
+
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let widths: Vec<f64> = scene.panels[0].commands.iter()
+            .filter_map(|cmd| match cmd {
+                DrawCommand::DrawRect { tl, br, .. } => Some(br.0 - tl.0),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(widths.len(), 2);
+        assert!((widths[0] - 0.8).abs() < 1e-9);
+        assert!((widths[1] - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hexbin_size_by_count_shrinks_sparse_cells() {
+        let (mut data, scales, mut spec) = make_test_data();
+        {
+            let group = &mut data.panels[0].layers[0].groups[0];
+            group.counts = vec![16, 4];
+            group.fills = vec!["#000000".to_string(), "#ffffff".to_string()];
+            group.style = RenderStyle::Hex(crate::graph::HexStyle { border: None, alpha: None, width: 1.0, radius: 0.6 });
+        }
+        let widths = |spec: &ResolvedSpec| -> Vec<f64> {
+            let scene = compile_geometry(data.clone(), scales.clone(), spec, &RenderOptions::default()).unwrap();
+            scene.panels[0].commands.iter()
+                .filter_map(|cmd| match cmd {
+                    DrawCommand::DrawPolygon { points, .. } => {
+                        let xs = points.iter().map(|p| p.0);
+                        Some(xs.clone().fold(f64::NEG_INFINITY, f64::max) - xs.fold(f64::INFINITY, f64::min))
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+
+        spec.layers[0].original_layer = Layer::Hexbin(crate::parser::ast::HexbinLayer::default());
+        assert_eq!(widths(&spec), vec![1.0, 1.0]);
+
+        // A quarter of the count: a quarter of the area, half the width
+        spec.layers[0].original_layer = Layer::Hexbin(crate::parser::ast::HexbinLayer { size_by_count: true, ..Default::default() });
+        let sized = widths(&spec);
+        assert!((sized[0] - 1.0).abs() < 1e-9);
+        assert!((sized[1] - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_violin_polygon_and_quantile_lines_follow_density() {
+        let (mut data, scales, mut spec) = make_test_data();
+        let style = crate::graph::ViolinStyle { width: Some(0.8), draw_quantiles: vec![0.5], ..Default::default() };
+        {
+            let group = &mut data.panels[0].layers[0].groups[0];
+            group.y_min = vec![0.0, 0.0];
+            group.y_max = vec![2.0, 2.0];
+            group.violin_density = vec![vec![0.0, 1.0, 0.0]; 2];
+            group.violin_density_y = vec![vec![0.0, 1.0, 2.0]; 2];
+            group.violin_quantile_values = vec![vec![0.5]; 2];
+            group.style = RenderStyle::Violin(style);
+        }
+        spec.layers[0].original_layer = Layer::Violin(crate::parser::ast::ViolinLayer { draw_quantiles: vec![0.5], ..Default::default() });
+
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let commands = &scene.panels[0].commands;
+
+        // Right side traced bottom to top, left side mirrored top to bottom, widest at the density peak
+        let polygons: Vec<&Vec<(f64, f64)>> = commands.iter()
+            .filter_map(|cmd| match cmd { DrawCommand::DrawPolygon { points, .. } => Some(points), _ => None })
+            .collect();
+        assert_eq!(polygons.len(), 2);
+        assert_eq!(polygons[0], &vec![(0.0, 0.0), (0.4, 1.0), (0.0, 2.0), (0.0, 2.0), (-0.4, 1.0), (0.0, 0.0)]);
+
+        // Median line at y = 0.5 spans the violin's width there (half the peak width)
+        let lines: Vec<&Vec<(f64, f64)>> = commands.iter()
+            .filter_map(|cmd| match cmd { DrawCommand::DrawLine { points, .. } => Some(points), _ => None })
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1], &vec![(0.8, 0.5), (1.2, 0.5)]);
+    }
+
+    #[test]
+    fn test_half_violin_traces_one_side() {
+        let (mut data, scales, mut spec) = make_test_data();
+        let style = crate::graph::ViolinStyle { width: Some(0.8), draw_quantiles: vec![0.5], side: ViolinSide::Right, ..Default::default() };
+        {
+            let group = &mut data.panels[0].layers[0].groups[0];
+            group.x = vec![1.0];
+            group.y_min = vec![0.0];
+            group.y_max = vec![2.0];
+            group.violin_density = vec![vec![0.0, 1.0, 0.0]];
+            group.violin_density_y = vec![vec![0.0, 1.0, 2.0]];
+            group.violin_quantile_values = vec![vec![0.5]];
+            group.style = RenderStyle::Violin(style);
+        }
+        spec.layers[0].original_layer = Layer::Violin(crate::parser::ast::ViolinLayer { side: ViolinSide::Right, ..Default::default() });
+
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let commands = &scene.panels[0].commands;
+
+        // The left side collapses onto the center line
+        let DrawCommand::DrawPolygon { points, .. } = &commands[0] else { panic!("Expected DrawPolygon") };
+        assert_eq!(points, &vec![(1.0, 0.0), (1.4, 1.0), (1.0, 2.0), (1.0, 2.0), (1.0, 1.0), (1.0, 0.0)]);
+        let DrawCommand::DrawLine { points, .. } = &commands[1] else { panic!("Expected DrawLine") };
+        assert_eq!(points, &vec![(1.0, 0.5), (1.2, 0.5)]);
+    }
+
+    #[test]
+    fn test_coord_flip_moves_categories_and_labels_to_y() {
+        let (data, mut scales, mut spec) = make_test_data();
+        scales.panels[0].x = Scale { domain: (-0.5, 1.5), range: (-0.5, 1.5), is_categorical: true, is_log: false, categories: vec!["a".into(), "b".into()], category_positions: vec![], is_percent: false, breaks_n: None, breaks_width: None, labels: Default::default() };
+        spec.coord = Some(crate::parser::ast::CoordSystem::Flip);
+        spec.labels.x = Some("Category".to_string());
+        spec.labels.y = Some("Value".to_string());
+
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let panel = &scene.panels[0];
+        assert!(panel.y_scale.is_categorical && !panel.x_scale.is_categorical);
+        assert_eq!(panel.y_scale.categories, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(panel.y_label.as_deref(), Some("Category"));
+        assert_eq!(panel.x_label.as_deref(), Some("Value"));
+    }
+
+    #[test]
+    fn test_single_panel_keeps_own_legend() {
+        let (data, scales, spec) = make_test_data();
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        assert!(scene.legend.is_empty());
+    }
+
+    #[test]
+    fn test_density_fill_adds_polygon_under_curve() {
+        let (data, scales, mut spec) = make_test_data();
+        spec.layers[0].original_layer = Layer::Line(LineLayer {
+            stat: Stat::Density { bw: Default::default(), n: 2, fill: true },
+            ..Default::default()
+        });
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let commands = &scene.panels[0].commands;
+        assert_eq!(commands.len(), 2);
+
+        let DrawCommand::DrawPolygon { points, legend, .. } = &commands[0] else { panic!("Expected DrawPolygon") };
+        assert_eq!(points, &vec![(0.0, 10.0), (1.0, 20.0), (1.0, 0.0), (0.0, 0.0)]);
+        assert!(legend.is_none());
+        assert!(matches!(&commands[1], DrawCommand::DrawLine { legend: Some(_), .. }));
+    }
+
+    #[test]
+    fn test_text_check_overlap_skips_later_labels() {
+        let (mut data, scales, mut spec) = make_test_data();
+        let group = &mut data.panels[0].layers[0].groups[0];
+        group.x = vec![0.0, 0.005, 0.5];
+        group.y = vec![10.0, 10.0, 10.0];
+        group.labels = vec!["first".to_string(), "second".to_string(), "third".to_string()];
+        group.style = RenderStyle::Text(crate::graph::TextGeomStyle { color: None, size: 12.0, alpha: None });
+
+        let text = |check_overlap| Layer::Text(crate::parser::ast::TextLayer { check_overlap, nudge_y: 1.0, ..Default::default() });
+        let texts = |scene: &SceneGraph| -> Vec<(String, (f64, f64))> {
+            scene.panels[0].commands.iter().filter_map(|c| match c {
+                DrawCommand::DrawText { text, pos, .. } => Some((text.clone(), *pos)),
+                _ => None,
+            }).collect()
+        };
+
+        spec.layers[0].original_layer = text(false);
+        let scene = compile_geometry(data.clone(), scales.clone(), &spec, &RenderOptions::default()).unwrap();
+        assert_eq!(texts(&scene).len(), 3);
+        assert_eq!(texts(&scene)[0].1, (0.0, 11.0)); // nudged up
+
+        // The second label sits on top of the first, which was placed first
+        spec.layers[0].original_layer = text(true);
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let placed: Vec<String> = texts(&scene).into_iter().map(|(t, _)| t).collect();
+        assert_eq!(placed, vec!["first", "third"]);
+    }
+
+    #[test]
+    fn test_label_draws_box_under_text() {
+        let (mut data, scales, mut spec) = make_test_data();
+        let group = &mut data.panels[0].layers[0].groups[0];
+        group.x = vec![0.5];
+        group.y = vec![10.0];
+        group.labels = vec!["peak".to_string()];
+        group.style = RenderStyle::Text(crate::graph::TextGeomStyle { color: Some("red".to_string()), size: 12.0, alpha: None });
+        spec.layers[0].original_layer = Layer::Text(crate::parser::ast::TextLayer {
+            label_box: Some(crate::parser::ast::LabelBox::default()),
+            ..Default::default()
+        });
+
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let commands = &scene.panels[0].commands;
+        assert_eq!(commands.len(), 2);
+
+        let DrawCommand::DrawRect { tl, br, style, legend } = &commands[0] else { panic!("Expected DrawRect") };
+        assert!(tl.0 < 0.5 && br.0 > 0.5);
+        assert!(br.1 < 10.0 && tl.1 > 10.0);
+        assert_eq!(style.color.as_deref(), Some("white"));
+        assert_eq!(style.border_color.as_deref(), Some("red"));
+        assert!(legend.is_none());
+        assert!(matches!(&commands[1], DrawCommand::DrawText { pos: (0.5, 10.0), legend: Some(_), .. }));
+    }
+
+    #[test]
+    fn test_pointrange_draws_range_along_flipped_axis() {
+        let (mut data, scales, mut spec) = make_test_data();
+        let group = &mut data.panels[0].layers[0].groups[0];
+        group.x = vec![1.0];
+        group.y = vec![5.0];
+        group.y_min = vec![2.0];
+        group.y_max = vec![8.0];
+        group.style = RenderStyle::Point(PointStyle { color: Some("blue".to_string()), size: None, shape: None, alpha: None, fill: None });
+        spec.layers[0].original_layer = Layer::Point(PointLayer { range: Some(Default::default()), ..Default::default() });
+        spec.coord = Some(crate::parser::ast::CoordSystem::Flip);
+
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let commands = &scene.panels[0].commands;
+        let DrawCommand::DrawLine { points, legend, .. } = &commands[0] else { panic!("Expected DrawLine") };
+        assert_eq!(points, &vec![(2.0, 1.0), (8.0, 1.0)]);
+        assert!(legend.is_none());
+        assert!(matches!(&commands[1], DrawCommand::DrawPoint { points, .. } if points == &vec![(5.0, 1.0)]));
+    }
+
+    #[test]
+    fn test_oob_squish_clamps_to_the_limits() {
+        let (data, mut scales, mut spec) = make_test_data();
+        scales.panels[0].y.domain = (0.0, 15.0);
+        spec.y_scale_spec = Some(crate::parser::ast::AxisScale { limits: Some((0.0, 15.0)), oob: OutOfBounds::Squish, ..Default::default() });
+
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let DrawCommand::DrawLine { points, .. } = &scene.panels[0].commands[0] else { panic!("Expected DrawLine") };
+        assert_eq!(points, &vec![(0.0, 10.0), (1.0, 15.0)]);
+    }
+
+    #[test]
+    fn test_oob_censor_breaks_lines_and_keeps_legends() {
+        let bounds = OobBounds {
+            x: AxisBounds(None),
+            y: AxisBounds(Some((0.0, 15.0, OutOfBounds::Censor))),
+        };
+        let rect = |tl, br, legend: Option<&str>| DrawCommand::DrawRect { tl, br, style: BarStyle::default(), legend: legend.map(String::from) };
+        let mut legend_items = Vec::new();
+        let commands = bounds.apply(vec![
+            DrawCommand::DrawLine {
+                points: vec![(0.0, 10.0), (1.0, 20.0), (2.0, 12.0), (3.0, 14.0), (4.0, 16.0), (5.0, 1.0), (6.0, 2.0)],
+                style: LineStyle::default(),
+                legend: Some("line".to_string()),
+            },
+            rect((0.0, 20.0), (1.0, 0.0), Some("bars")),
+            rect((1.0, 10.0), (2.0, 0.0), None),
+        ], &mut legend_items);
+
+        // The single point before the first gap draws nothing; the label stays on the first run
+        let lines: Vec<_> = commands.iter()
+            .filter_map(|cmd| match cmd { DrawCommand::DrawLine { points, legend, .. } => Some((points.clone(), legend.clone())), _ => None })
+            .collect();
+        assert_eq!(lines, vec![
+            (vec![(2.0, 12.0), (3.0, 14.0)], Some("line".to_string())),
+            (vec![(5.0, 1.0), (6.0, 2.0)], None),
+        ]);
+
+        // The censored bar is dropped and its entry becomes a legend item; the next bar stays unlabeled
+        let rects: Vec<&DrawCommand> = commands.iter().filter(|cmd| matches!(cmd, DrawCommand::DrawRect { .. })).collect();
+        assert_eq!(rects.len(), 1);
+        let DrawCommand::DrawRect { tl, legend, .. } = rects[0] else { unreachable!() };
+        assert_eq!((*tl, legend.as_deref()), ((1.0, 10.0), None));
+        assert_eq!(legend_items.len(), 1);
+        assert_eq!(legend_items[0].label, "bars");
+        assert!(matches!(legend_items[0].style, RenderStyle::Bar(_)));
+    }
+
+    #[test]
+    fn test_abline_spans_the_panel() {
+        assert_eq!(abline_span(0.0, 5.0, (0.0, 10.0), (0.0, 10.0)), Some((0.0, 10.0)));
+        assert_eq!(abline_span(0.0, 12.0, (0.0, 10.0), (0.0, 10.0)), None);
+        // Clipped where it leaves the top of the panel
+        assert_eq!(abline_span(2.0, 0.0, (0.0, 10.0), (0.0, 10.0)), Some((0.0, 5.0)));
+        assert_eq!(abline_span(-1.0, 10.0, (0.0, 10.0), (2.0, 10.0)), Some((0.0, 8.0)));
+
+        let (mut data, scales, mut spec) = make_test_data();
+        let group = &mut data.panels[0].layers[0].groups[0];
+        group.x = vec![];
+        group.y = vec![];
+        group.ablines = vec![(0.0, 15.0), (0.0, 20.0)];
+        spec.layers[0].original_layer = Layer::Line(LineLayer {
+            stat: Stat::Abline { slope: crate::parser::ast::AestheticValue::Fixed(0.0), intercept: crate::parser::ast::AestheticValue::Fixed(15.0) },
+            ..Default::default()
+        });
+        let (x_lo, x_hi) = scales.panels[0].x.domain;
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let commands = &scene.panels[0].commands;
+        let lines: Vec<(&Vec<(f64, f64)>, bool)> = commands.iter()
+            .map(|c| match c { DrawCommand::DrawLine { points, legend, .. } => (points, legend.is_some()), _ => panic!("Expected DrawLine") })
+            .collect();
+        // Only the first line carries the legend entry
+        assert_eq!(lines, vec![(&vec![(x_lo, 15.0), (x_hi, 15.0)], true), (&vec![(x_lo, 20.0), (x_hi, 20.0)], false)]);
+    }
+
+    #[test]
+    fn test_crosshair_draws_two_lines_per_point() {
+        let (mut data, scales, mut spec) = make_test_data();
+        let group = &mut data.panels[0].layers[0].groups[0];
+        group.x = vec![0.25, 0.75];
+        group.y = vec![5.0, 15.0];
+        spec.layers[0].original_layer = Layer::Line(LineLayer { stat: Stat::Crosshair, ..Default::default() });
+
+        let scene = compile_geometry(data, scales, &spec, &RenderOptions::default()).unwrap();
+        let lines: Vec<&Vec<(f64, f64)>> = scene.panels[0].commands.iter()
+            .map(|c| match c { DrawCommand::DrawLine { points, .. } => points, _ => panic!("Expected DrawLine") })
+            .collect();
+        assert_eq!(lines, vec![
+            &vec![(0.25, 0.0), (0.25, 20.0)],
+            &vec![(0.0, 5.0), (1.0, 5.0)],
+            &vec![(0.75, 0.0), (0.75, 20.0)],
+            &vec![(0.0, 15.0), (1.0, 15.0)],
+        ]);
+        assert_eq!(scene.panels[0].commands.iter().filter(|c| matches!(c, DrawCommand::DrawLine { legend: Some(_), .. })).count(), 1);
+    }
+
     #[test]
     fn test_compile_line() {
         let (data, scales, spec) = make_test_data();
@@ -1,6 +1,8 @@
 use anyhow::{anyhow, Context, Result};
 use csv::ReaderBuilder;
+use std::fs::File;
 use std::io;
+use std::path::Path;
 
 #[derive(Debug, Clone)]
 pub struct CsvData {
@@ -14,9 +16,18 @@ pub enum ColumnSelector {
 }
 
 pub fn read_csv_from_stdin() -> Result<CsvData> {
+    read_csv_from_reader(io::stdin())
+}
+
+pub fn read_csv_from_path(path: &Path) -> Result<CsvData> {
+    let file = File::open(path).with_context(|| format!("Failed to open CSV file '{}'", path.display()))?;
+    read_csv_from_reader(file)
+}
+
+pub fn read_csv_from_reader(input: impl io::Read) -> Result<CsvData> {
     let mut reader = ReaderBuilder::new()
         .has_headers(true)
-        .from_reader(io::stdin());
+        .from_reader(input);
 
     let headers = reader
         .headers()
@@ -100,6 +111,46 @@ pub fn extract_column(data: &CsvData, selector: ColumnSelector) -> Result<(Strin
     Ok((column_name, values))
 }
 
+/// Numeric values of each named column, in the order given
+pub fn extract_columns_by_names(data: &CsvData, names: &[&str]) -> Result<Vec<(String, Vec<f64>)>> {
+    names
+        .iter()
+        .map(|name| extract_column(data, ColumnSelector::Name(name.to_string())))
+        .collect()
+}
+
+/// Expand a `first:last` selector into the header names from `first` to `last` inclusive,
+/// in header order (reversed when `last` comes before `first`). A plain name selects itself.
+pub fn parse_column_range_selector(data: &CsvData, selector: &str) -> Result<Vec<String>> {
+    let position = |name: &str| {
+        data.headers
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Column '{}' not found. Available columns: {}",
+                    name,
+                    data.headers.join(", ")
+                )
+            })
+    };
+
+    let (first, last) = match selector.split_once(':') {
+        Some((first, last)) => (position(first.trim())?, position(last.trim())?),
+        None => {
+            let idx = position(selector.trim())?;
+            (idx, idx)
+        }
+    };
+
+    let names = if first <= last {
+        data.headers[first..=last].to_vec()
+    } else {
+        data.headers[last..=first].iter().rev().cloned().collect()
+    };
+    Ok(names)
+}
+
 pub fn extract_column_as_string(data: &CsvData, selector: ColumnSelector) -> Result<(String, Vec<String>)> {
     let (column_index, column_name) = match selector {
         ColumnSelector::Index(idx) => {
@@ -306,6 +357,28 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("at least one data row"));
     }
 
+    // Multiple columns
+
+    #[test]
+    fn test_extract_columns_by_names() {
+        let csv = csv_from_string("x,y,z\n1,10,100\n2,20,200").unwrap();
+        let columns = extract_columns_by_names(&csv, &["z", "X"]).unwrap();
+        assert_eq!(columns, vec![
+            ("z".to_string(), vec![100.0, 200.0]),
+            ("x".to_string(), vec![1.0, 2.0]),
+        ]);
+        assert!(extract_columns_by_names(&csv, &["x", "w"]).unwrap_err().to_string().contains("not found"));
+    }
+
+    #[test]
+    fn test_parse_column_range_selector() {
+        let csv = csv_from_string("id,value1,value2,value3,note\n1,2,3,4,a").unwrap();
+        assert_eq!(parse_column_range_selector(&csv, "value1:value3").unwrap(), vec!["value1", "value2", "value3"]);
+        assert_eq!(parse_column_range_selector(&csv, "value3:value2").unwrap(), vec!["value3", "value2"]);
+        assert_eq!(parse_column_range_selector(&csv, "note").unwrap(), vec!["note"]);
+        assert!(parse_column_range_selector(&csv, "value1:value9").unwrap_err().to_string().contains("not found"));
+    }
+
     // read_csv_from_stdin tests (6 tests using csv_from_string helper)
 
     #[test]
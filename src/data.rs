@@ -1,5 +1,6 @@
 use anyhow::{anyhow, Result};
 use serde_json::Value;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct PlotData {
@@ -59,4 +60,230 @@ impl PlotData {
 
         Ok(Self { headers, rows })
     }
+
+    /// Join with another table, keeping only rows whose `on` value appears in both
+    pub fn inner_join(&self, other: &PlotData, on: &str) -> Result<PlotData> {
+        self.join(other, on, false)
+    }
+
+    /// Join with another table, keeping every row of `self` (unmatched right values are empty)
+    pub fn left_join(&self, other: &PlotData, on: &str) -> Result<PlotData> {
+        self.join(other, on, true)
+    }
+
+    /// Match rows on the string value of the `on` column.
+    /// Right-hand columns that clash with left-hand names get a `_right` suffix.
+    fn join(&self, other: &PlotData, on: &str, keep_unmatched: bool) -> Result<PlotData> {
+        let left_idx = self.column_index(on)?;
+        let right_idx = other.column_index(on)?;
+
+        let mut right_rows: HashMap<&str, Vec<&Vec<String>>> = HashMap::new();
+        for row in &other.rows {
+            right_rows.entry(row[right_idx].as_str()).or_default().push(row);
+        }
+
+        let mut left_counts: HashMap<&str, usize> = HashMap::new();
+        for row in &self.rows {
+            *left_counts.entry(row[left_idx].as_str()).or_default() += 1;
+        }
+        if let Some((key, _)) = left_counts.iter()
+            .find(|(k, &n)| n > 1 && right_rows.get(*k).is_some_and(|r| r.len() > 1))
+        {
+            return Err(anyhow!("Many-to-many join on '{}': key '{}' is duplicated in both tables", on, key));
+        }
+
+        let mut headers = self.headers.clone();
+        for (i, h) in other.headers.iter().enumerate() {
+            if i == right_idx { continue; }
+            if self.headers.iter().any(|l| l.eq_ignore_ascii_case(h)) {
+                headers.push(format!("{}_right", h));
+            } else {
+                headers.push(h.clone());
+            }
+        }
+
+        let right_values = |row: &Vec<String>| -> Vec<String> {
+            row.iter().enumerate().filter(|(i, _)| *i != right_idx).map(|(_, v)| v.clone()).collect()
+        };
+
+        let mut rows = Vec::new();
+        for row in &self.rows {
+            match right_rows.get(row[left_idx].as_str()) {
+                Some(matches) => {
+                    for right in matches {
+                        let mut joined = row.clone();
+                        joined.extend(right_values(right));
+                        rows.push(joined);
+                    }
+                }
+                None if keep_unmatched => {
+                    let mut joined = row.clone();
+                    joined.resize(headers.len(), String::new());
+                    rows.push(joined);
+                }
+                None => {}
+            }
+        }
+
+        Ok(PlotData { headers, rows })
+    }
+
+    /// Reshape long-format data to wide format.
+    /// Each unique value of `names_from` becomes a column filled from `values_from`;
+    /// rows are identified by `id_cols` (all remaining columns when empty).
+    /// Missing combinations are left as empty strings.
+    pub fn pivot_wider(&self, id_cols: &[String], names_from: &str, values_from: &str) -> Result<PlotData> {
+        let names_idx = self.column_index(names_from)?;
+        let values_idx = self.column_index(values_from)?;
+
+        let id_idxs: Vec<usize> = if id_cols.is_empty() {
+            (0..self.headers.len()).filter(|&i| i != names_idx && i != values_idx).collect()
+        } else {
+            id_cols.iter().map(|c| self.column_index(c)).collect::<Result<_>>()?
+        };
+
+        // New columns and rows, both in order of first appearance
+        let mut names: Vec<String> = Vec::new();
+        let mut name_pos: HashMap<String, usize> = HashMap::new();
+        let mut ids: Vec<Vec<String>> = Vec::new();
+        let mut id_pos: HashMap<Vec<String>, usize> = HashMap::new();
+        let mut cells: HashMap<(usize, usize), String> = HashMap::new();
+
+        for row in &self.rows {
+            let id: Vec<String> = id_idxs.iter().map(|&i| row[i].clone()).collect();
+            let row_i = *id_pos.entry(id.clone()).or_insert_with(|| {
+                ids.push(id);
+                ids.len() - 1
+            });
+
+            let name = &row[names_idx];
+            let col_i = *name_pos.entry(name.clone()).or_insert_with(|| {
+                names.push(name.clone());
+                names.len() - 1
+            });
+
+            if cells.insert((row_i, col_i), row[values_idx].clone()).is_some() {
+                return Err(anyhow!(
+                    "pivot_wider: multiple '{}' values for '{}' = '{}' in the same row",
+                    values_from, names_from, name
+                ));
+            }
+        }
+
+        let mut headers: Vec<String> = id_idxs.iter().map(|&i| self.headers[i].clone()).collect();
+        headers.extend(names.iter().cloned());
+
+        let rows = ids.into_iter().enumerate().map(|(row_i, mut row)| {
+            for col_i in 0..names.len() {
+                row.push(cells.remove(&(row_i, col_i)).unwrap_or_default());
+            }
+            row
+        }).collect();
+
+        Ok(PlotData { headers, rows })
+    }
+
+    fn column_index(&self, name: &str) -> Result<usize> {
+        find_col_index(&self.headers, name)
+    }
+}
+
+/// Index of the header matching `name`, ignoring ASCII case
+pub(crate) fn find_col_index(headers: &[String], name: &str) -> Result<usize> {
+    headers.iter()
+        .position(|h| h.eq_ignore_ascii_case(name))
+        .ok_or_else(|| anyhow!("Column '{}' not found", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(headers: &[&str], rows: &[&[&str]]) -> PlotData {
+        PlotData::new(
+            headers.iter().map(|s| s.to_string()).collect(),
+            rows.iter().map(|r| r.iter().map(|s| s.to_string()).collect()).collect(),
+        )
+    }
+
+    fn make_long() -> PlotData {
+        table(&["date", "variable", "value"], &[
+            &["1", "A", "10"],
+            &["1", "B", "20"],
+            &["2", "A", "11"],
+            &["2", "B", "21"],
+            &["3", "A", "12"],
+        ])
+    }
+
+    #[test]
+    fn test_pivot_wider() {
+        let wide = make_long().pivot_wider(&["date".to_string()], "variable", "value").unwrap();
+        assert_eq!(wide.headers, vec!["date", "A", "B"]);
+        assert_eq!(wide.rows, vec![
+            vec!["1", "10", "20"],
+            vec!["2", "11", "21"],
+            vec!["3", "12", ""],
+        ]);
+    }
+
+    #[test]
+    fn test_pivot_wider_default_id_cols() {
+        let wide = make_long().pivot_wider(&[], "variable", "value").unwrap();
+        assert_eq!(wide.headers, vec!["date", "A", "B"]);
+        assert_eq!(wide.rows.len(), 3);
+    }
+
+    #[test]
+    fn test_pivot_wider_duplicate_values() {
+        let mut data = make_long();
+        data.rows.push(vec!["1".to_string(), "A".to_string(), "99".to_string()]);
+        assert!(data.pivot_wider(&["date".to_string()], "variable", "value").is_err());
+    }
+
+    #[test]
+    fn test_pivot_wider_missing_column() {
+        assert!(make_long().pivot_wider(&[], "nope", "value").is_err());
+    }
+
+    #[test]
+    fn test_inner_join() {
+        let sales = table(&["date", "value"], &[&["1", "10"], &["2", "20"], &["3", "30"]]);
+        let forecast = table(&["date", "value", "model"], &[&["1", "11", "a"], &["3", "29", "a"]]);
+        let joined = sales.inner_join(&forecast, "date").unwrap();
+        assert_eq!(joined.headers, vec!["date", "value", "value_right", "model"]);
+        assert_eq!(joined.rows, vec![
+            vec!["1", "10", "11", "a"],
+            vec!["3", "30", "29", "a"],
+        ]);
+    }
+
+    #[test]
+    fn test_left_join_keeps_unmatched() {
+        let sales = table(&["date", "value"], &[&["1", "10"], &["2", "20"]]);
+        let forecast = table(&["date", "forecast"], &[&["1", "11"]]);
+        let joined = sales.left_join(&forecast, "date").unwrap();
+        assert_eq!(joined.rows, vec![vec!["1", "10", "11"], vec!["2", "20", ""]]);
+    }
+
+    #[test]
+    fn test_join_one_to_many() {
+        let left = table(&["k", "a"], &[&["x", "1"]]);
+        let right = table(&["k", "b"], &[&["x", "2"], &["x", "3"]]);
+        assert_eq!(left.inner_join(&right, "k").unwrap().rows.len(), 2);
+    }
+
+    #[test]
+    fn test_join_many_to_many_errors() {
+        let left = table(&["k", "a"], &[&["x", "1"], &["x", "2"]]);
+        let right = table(&["k", "b"], &[&["x", "3"], &["x", "4"]]);
+        assert!(left.inner_join(&right, "k").is_err());
+    }
+
+    #[test]
+    fn test_join_missing_column() {
+        let left = table(&["k"], &[&["x"]]);
+        let right = table(&["j"], &[&["x"]]);
+        assert!(left.left_join(&right, "k").is_err());
+    }
 }
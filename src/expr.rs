@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+/// Arithmetic expression over the columns of a row, used by mutate()
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Expression {
+    Column(String),
+    Const(f64),
+    Log(Box<Expression>),  // Natural logarithm
+    Sqrt(Box<Expression>),
+    Add(Box<Expression>, Box<Expression>),
+    Sub(Box<Expression>, Box<Expression>),
+    Mul(Box<Expression>, Box<Expression>),
+    Div(Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// Evaluate the expression against one data row
+    pub fn eval(&self, headers: &[String], row: &[String]) -> Result<f64> {
+        Ok(match self {
+            Expression::Column(name) => {
+                let idx = headers.iter()
+                    .position(|h| h.eq_ignore_ascii_case(name))
+                    .ok_or_else(|| anyhow!("Column '{}' not found", name))?;
+                row[idx].trim().parse::<f64>()
+                    .map_err(|_| anyhow!("Column '{}' has non-numeric value '{}'", name, row[idx]))?
+            }
+            Expression::Const(v) => *v,
+            Expression::Log(e) => e.eval(headers, row)?.ln(),
+            Expression::Sqrt(e) => e.eval(headers, row)?.sqrt(),
+            Expression::Add(a, b) => a.eval(headers, row)? + b.eval(headers, row)?,
+            Expression::Sub(a, b) => a.eval(headers, row)? - b.eval(headers, row)?,
+            Expression::Mul(a, b) => a.eval(headers, row)? * b.eval(headers, row)?,
+            Expression::Div(a, b) => a.eval(headers, row)? / b.eval(headers, row)?,
+        })
+    }
+}
+
+/// Per-group aggregation used by group_by() | summarize()
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Aggregation {
+    Sum(String),
+    Mean(String),
+    Median(String),
+    Count,
+    Min(String),
+    Max(String),
+    Sd(String), // Sample standard deviation (0 for single-row groups)
+}
+
+impl Aggregation {
+    /// Column the aggregation reads (None for Count)
+    pub fn column(&self) -> Option<&str> {
+        match self {
+            Aggregation::Sum(c) | Aggregation::Mean(c) | Aggregation::Median(c)
+            | Aggregation::Min(c) | Aggregation::Max(c) | Aggregation::Sd(c) => Some(c),
+            Aggregation::Count => None,
+        }
+    }
+
+    /// Aggregate the (non-empty) values of one group
+    pub fn apply(&self, values: &[f64]) -> f64 {
+        let n = values.len() as f64;
+        match self {
+            Aggregation::Count => n,
+            Aggregation::Sum(_) => values.iter().sum(),
+            Aggregation::Mean(_) => values.iter().sum::<f64>() / n,
+            Aggregation::Min(_) => values.iter().cloned().fold(f64::INFINITY, f64::min),
+            Aggregation::Max(_) => values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            Aggregation::Median(_) => {
+                let mut sorted = values.to_vec();
+                sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+                let mid = sorted.len() / 2;
+                if sorted.len().is_multiple_of(2) { (sorted[mid - 1] + sorted[mid]) / 2.0 } else { sorted[mid] }
+            }
+            Aggregation::Sd(_) => {
+                if values.len() < 2 {
+                    return 0.0;
+                }
+                let mean = values.iter().sum::<f64>() / n;
+                (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn col(name: &str) -> Box<Expression> {
+        Box::new(Expression::Column(name.to_string()))
+    }
+
+    #[test]
+    fn test_eval_arithmetic() {
+        let headers = vec!["sales".to_string(), "target".to_string()];
+        let row = vec!["50".to_string(), "200".to_string()];
+        let ratio = Expression::Div(col("sales"), col("target"));
+        assert_eq!(ratio.eval(&headers, &row).unwrap(), 0.25);
+
+        let expr = Expression::Sub(
+            Box::new(Expression::Mul(col("sales"), Box::new(Expression::Const(2.0)))),
+            Box::new(Expression::Sqrt(Box::new(Expression::Const(16.0)))),
+        );
+        assert_eq!(expr.eval(&headers, &row).unwrap(), 96.0);
+    }
+
+    #[test]
+    fn test_eval_log() {
+        let headers = vec!["x".to_string()];
+        let row = vec![std::f64::consts::E.to_string()];
+        assert!((Expression::Log(col("x")).eval(&headers, &row).unwrap() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_aggregations() {
+        let values = [1.0, 3.0, 2.0, 6.0];
+        let c = || "v".to_string();
+        assert_eq!(Aggregation::Count.apply(&values), 4.0);
+        assert_eq!(Aggregation::Sum(c()).apply(&values), 12.0);
+        assert_eq!(Aggregation::Mean(c()).apply(&values), 3.0);
+        assert_eq!(Aggregation::Median(c()).apply(&values), 2.5);
+        assert_eq!(Aggregation::Min(c()).apply(&values), 1.0);
+        assert_eq!(Aggregation::Max(c()).apply(&values), 6.0);
+        assert!((Aggregation::Sd(c()).apply(&values) - (14.0f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert_eq!(Aggregation::Sd(c()).apply(&[5.0]), 0.0);
+    }
+
+    #[test]
+    fn test_eval_errors() {
+        let headers = vec!["x".to_string()];
+        let row = vec!["abc".to_string()];
+        assert!(Expression::Column("x".to_string()).eval(&headers, &row).is_err());
+        assert!(Expression::Column("y".to_string()).eval(&headers, &row).is_err());
+    }
+}
@@ -1,47 +1,63 @@
 use anyhow::{Context, Result};
-use image::ImageEncoder;
 use plotters::prelude::*;
-use crate::ir::{SceneGraph, PanelScene, DrawCommand};
-use crate::{OutputFormat, RenderOptions};
+use plotters::element::{Drawable, PointCollection};
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use plotters_backend::{BackendColor, BackendCoord, BackendTextStyle, DrawingErrorKind};
+use serde::Serialize;
+use crate::ir::{SceneGraph, PanelScene, DrawCommand, LegendEntry, RenderStyle, Scale, ColorBar};
+use plotters::coord::ranged1d::{KeyPointHint, NoDefaultFormatting, Ranged, ValueFormatter};
+use plotters::coord::types::RangedCoordf64;
+use crate::parser::ast::{LegendPosition, StripPosition, TickFormat};
+use crate::{OutputFormat, RenderOptions, WatermarkPosition};
+use crate::runtime::{report, Progress};
 use crate::theme_resolve::{ResolvedTheme, parse_color as resolve_color};
 
 /// Style configuration for line layers
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct LineStyle {
     pub color: Option<String>,
     pub width: Option<f64>,
     pub alpha: Option<f64>,
+    pub linetype: Option<String>, // solid (default), dashed, dotted, dotdash, longdash, twodash
 }
 
 /// Style configuration for point layers
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct PointStyle {
     pub color: Option<String>,
     pub size: Option<f64>,
     pub shape: Option<String>,
     pub alpha: Option<f64>,
+    pub fill: Option<String>, // Fill inside a `color` outline (None = solid `color`, or unfilled "_open" shapes)
 }
 
 /// Style configuration for bar layers
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct BarStyle {
-    pub color: Option<String>,
+    pub color: Option<String>,        // Fill color
     pub alpha: Option<f64>,
     pub width: Option<f64>,
+    pub border_color: Option<String>, // Outline drawn around the rect (None = no outline)
+    pub border_width: Option<f64>,    // Outline width in pixels (default 1)
+    pub hollow: bool,                 // Outline only, in border_color or else the fill color
 }
 
 /// Style configuration for ribbon layers
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct RibbonStyle {
     pub color: Option<String>,
     pub alpha: Option<f64>,
 }
 
 /// Style configuration for boxplot layers
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct BoxplotStyle {
     pub color: Option<String>,
     pub width: Option<f64>,
+    pub varwidth: bool,
+    pub notch: bool,
+    pub notchwidth: Option<f64>,
+    pub staplewidth: Option<f64>,
     pub alpha: Option<f64>,
     pub outlier_color: Option<String>,
     pub outlier_size: Option<f64>,
@@ -49,65 +65,340 @@ pub struct BoxplotStyle {
 }
 
 /// Style configuration for violin layers
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct ViolinStyle {
     pub color: Option<String>,
     pub width: Option<f64>,
     pub alpha: Option<f64>,
     pub draw_quantiles: Vec<f64>,
+    pub side: crate::parser::ast::ViolinSide,
+}
+
+/// Style configuration for tile layers
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TileStyle {
+    pub fill: Option<String>,   // Fixed fill (used when no fill column is mapped)
+    pub border: Option<String>,
+    pub alpha: Option<f64>,
+    pub width: f64,  // Tile size in data units
+    pub height: f64,
+}
+
+/// Style configuration for hexbin layers
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HexStyle {
+    pub border: Option<String>,
+    pub alpha: Option<f64>,
+    pub width: f64,  // Flat-to-flat width in x data units
+    pub radius: f64, // Center-to-vertex height in y data units
+}
+
+/// Style configuration for text layers
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TextGeomStyle {
+    pub color: Option<String>,
+    pub size: f64, // Font size in pixels
+    pub alpha: Option<f64>,
+}
+
+/// Font size (px) and opacity of the --watermark text
+const WATERMARK_FONT_SIZE: i32 = 11;
+const WATERMARK_ALPHA: f64 = 0.5;
+
+/// Drawing backend adapter for high-DPI output: reports the logical size to plotters and
+/// multiplies every coordinate, stroke width and font size by `scale` before drawing
+struct ScaledBackend<DB: DrawingBackend> {
+    inner: DB,
+    size: (u32, u32),
+    scale: f64,
+}
+
+/// A backend style whose stroke width has been multiplied by the output scale
+struct ScaledStyle {
+    color: BackendColor,
+    stroke_width: u32,
+}
+
+impl plotters_backend::BackendStyle for ScaledStyle {
+    fn color(&self) -> BackendColor {
+        self.color
+    }
+
+    fn stroke_width(&self) -> u32 {
+        self.stroke_width
+    }
+}
+
+impl<DB: DrawingBackend> ScaledBackend<DB> {
+    fn new(inner: DB, size: (u32, u32), scale: f64) -> Self {
+        Self { inner, size, scale }
+    }
+
+    fn coord(&self, (x, y): BackendCoord) -> BackendCoord {
+        ((x as f64 * self.scale).round() as i32, (y as f64 * self.scale).round() as i32)
+    }
+
+    fn length(&self, value: u32) -> u32 {
+        (value as f64 * self.scale).round() as u32
+    }
+
+    fn style<S: plotters_backend::BackendStyle>(&self, style: &S) -> ScaledStyle {
+        ScaledStyle { color: style.color(), stroke_width: self.length(style.stroke_width()).max(1) }
+    }
+
+    fn text_style<'a, T: BackendTextStyle>(&self, style: &'a T) -> TextStyle<'a> {
+        TextStyle {
+            font: FontDesc::new(style.family(), style.size() * self.scale, style.style()).transform(style.transform()),
+            color: style.color(),
+            pos: style.anchor(),
+        }
+    }
 }
 
-/// The Rendering Backend
+impl<DB: DrawingBackend> DrawingBackend for ScaledBackend<DB> {
+    type ErrorType = DB::ErrorType;
+
+    fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.inner.ensure_prepared()
+    }
+
+    fn present(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.inner.present()
+    }
+
+    fn draw_pixel(&mut self, point: BackendCoord, color: BackendColor) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let (x, y) = self.coord(point);
+        let side = self.length(1) as i32;
+        if side <= 1 {
+            return self.inner.draw_pixel((x, y), color);
+        }
+        // The bitmap backend fills rects up to, not including, the bottom-right corner
+        let style = ScaledStyle { color, stroke_width: 1 };
+        self.inner.draw_rect((x, y), (x + side, y + side), &style, true)
+    }
+
+    fn draw_line<S: plotters_backend::BackendStyle>(&mut self, from: BackendCoord, to: BackendCoord, style: &S) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let (from, to, style) = (self.coord(from), self.coord(to), self.style(style));
+        if style.stroke_width == 1 {
+            self.inner.draw_line(from, to, &style)
+        } else {
+            self.inner.draw_path([from, to], &style)
+        }
+    }
+
+    fn draw_rect<S: plotters_backend::BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let (upper_left, bottom_right, style) = (self.coord(upper_left), self.coord(bottom_right), self.style(style));
+        self.inner.draw_rect(upper_left, bottom_right, &style, fill)
+    }
+
+    fn draw_path<S: plotters_backend::BackendStyle, I: IntoIterator<Item = BackendCoord>>(&mut self, path: I, style: &S) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let path: Vec<_> = path.into_iter().map(|p| self.coord(p)).collect();
+        let style = self.style(style);
+        self.inner.draw_path(path, &style)
+    }
+
+    fn draw_circle<S: plotters_backend::BackendStyle>(
+        &mut self,
+        center: BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let (center, radius, style) = (self.coord(center), self.length(radius), self.style(style));
+        self.inner.draw_circle(center, radius, &style, fill)
+    }
+
+    fn fill_polygon<S: plotters_backend::BackendStyle, I: IntoIterator<Item = BackendCoord>>(&mut self, vert: I, style: &S) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let vert: Vec<_> = vert.into_iter().map(|p| self.coord(p)).collect();
+        let style = self.style(style);
+        self.inner.fill_polygon(vert, &style)
+    }
+
+    fn draw_text<T: BackendTextStyle>(&mut self, text: &str, style: &T, pos: BackendCoord) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let (pos, style) = (self.coord(pos), self.text_style(style));
+        self.inner.draw_text(text, &style, pos)
+    }
+
+    fn estimate_text_size<T: BackendTextStyle>(&self, text: &str, style: &T) -> Result<(u32, u32), DrawingErrorKind<Self::ErrorType>> {
+        let (w, h) = self.inner.estimate_text_size(text, &self.text_style(style))?;
+        Ok(((w as f64 / self.scale).round() as u32, (h as f64 / self.scale).round() as u32))
+    }
+}
+
+/// The Rendering Backend. Layers are compiled into DrawCommands up front; each panel builds
+/// its chart once and draws all of its commands onto it.
 pub struct Canvas;
 
 impl Canvas {
     /// Execute the SceneGraph and produce a byte vector (PNG or SVG)
     pub fn execute(scene: SceneGraph, options: &RenderOptions) -> Result<Vec<u8>> {
+        Self::execute_with_progress(scene, options, None)
+    }
+
+    /// `execute`, reporting each panel and the encoding step to a --progress callback
+    pub(crate) fn execute_with_progress(scene: SceneGraph, options: &RenderOptions, progress: Progress) -> Result<Vec<u8>> {
         match options.format {
-            OutputFormat::Png => Self::render_png(scene, options),
-            OutputFormat::Svg => Self::render_svg(scene, options),
+            OutputFormat::Png => Self::render_png(scene, options, progress),
+            OutputFormat::Svg => Self::render_svg(scene, options, progress),
         }
     }
 
-    fn render_png(scene: SceneGraph, _options: &RenderOptions) -> Result<Vec<u8>> {
-        let width = scene.width;
-        let height = scene.height;
+    fn render_png(scene: SceneGraph, options: &RenderOptions, progress: Progress) -> Result<Vec<u8>> {
+        let scale = options.scale;
+        if !(scale.is_finite() && scale > 0.0) {
+            anyhow::bail!("Invalid scale {}: must be a positive number", scale);
+        }
+        // Physical pixel dimensions; the scene is laid out in logical pixels
+        let width = ((scene.width as f64 * scale).round() as u32).max(1);
+        let height = ((scene.height as f64 * scale).round() as u32).max(1);
         let mut buffer = vec![0u8; (width * height * 3) as usize];
 
         {
-            let root = BitMapBackend::with_buffer(&mut buffer, (width, height))
-                .into_drawing_area();
-            Self::draw_scene(&root, &scene)?;
+            let backend = BitMapBackend::with_buffer(&mut buffer, (width, height));
+            let root = ScaledBackend::new(backend, (scene.width, scene.height), scale).into_drawing_area();
+            Self::draw_scene(&root, &scene, progress)?;
+        }
+
+        // Watermarks are stamped onto the finished image
+        if options.watermark.is_some() || options.watermark_image.is_some() {
+            let mut image = image::RgbImage::from_raw(width, height, buffer)
+                .context("Rendered buffer does not match the image size")?;
+            if let Some(path) = &options.watermark_image {
+                let bytes = std::fs::read(path)
+                    .with_context(|| format!("Failed to read watermark image {}", path.display()))?;
+                // Keep a bottom-right logo clear of the text watermark
+                let lift = if options.watermark.is_some() && options.watermark_position == WatermarkPosition::BottomRight {
+                    WATERMARK_FONT_SIZE as u32 + 5
+                } else {
+                    0
+                };
+                Self::add_watermark_image(&mut image, &bytes, options.watermark_position, options.watermark_size, lift, scale)?;
+            }
+            if let Some(text) = &options.watermark {
+                Self::add_watermark(&mut image, text, WATERMARK_ALPHA, RGBColor(128, 128, 128), scale)?;
+            }
+            buffer = image.into_raw();
         }
 
-        // Encode as PNG
+        report(progress, || "Encoding PNG...".to_string());
+        Self::encode_png(&buffer, width, height, scale)
+    }
+
+    /// Encode an RGB buffer as PNG, recording the pixel density (72 DPI x `scale`) in a pHYs chunk
+    fn encode_png(buffer: &[u8], width: u32, height: u32, scale: f64) -> Result<Vec<u8>> {
+        // pHYs counts pixels per metre
+        let pixels_per_metre = (72.0 * scale / 0.0254).round() as u32;
         let mut png_bytes = Vec::new();
         {
-            let encoder = image::codecs::png::PngEncoder::new(&mut png_bytes);
-            encoder
-                .write_image(
-                    &buffer,
-                    width,
-                    height,
-                    image::ColorType::Rgb8,
-                )
-                .context("Failed to encode PNG")?;
+            let mut encoder = png::Encoder::new(&mut png_bytes, width, height);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_pixel_dims(Some(png::PixelDimensions {
+                xppu: pixels_per_metre,
+                yppu: pixels_per_metre,
+                unit: png::Unit::Meter,
+            }));
+            let mut writer = encoder.write_header().context("Failed to encode PNG")?;
+            writer.write_image_data(buffer).context("Failed to encode PNG")?;
         }
 
         Ok(png_bytes)
     }
 
-    fn render_svg(scene: SceneGraph, _options: &RenderOptions) -> Result<Vec<u8>> {
+    fn render_svg(scene: SceneGraph, options: &RenderOptions, progress: Progress) -> Result<Vec<u8>> {
+        if options.watermark.is_some() || options.watermark_image.is_some() {
+            anyhow::bail!("Watermarks are only supported for PNG output");
+        }
+        if options.scale != 1.0 {
+            anyhow::bail!("--scale is only supported for PNG output");
+        }
+
         let mut buffer = String::new();
         {
             let root = SVGBackend::with_string(&mut buffer, (scene.width, scene.height))
                 .into_drawing_area();
-            Self::draw_scene(&root, &scene)?;
+            Self::draw_scene(&root, &scene, progress)?;
         }
+
+        // Expose theme values as CSS custom properties for external styling
+        let style = format!("<style>\n{}\n</style>\n", scene.theme.resolve().to_css_vars());
+        if let Some(pos) = buffer.find("<svg").and_then(|start| buffer[start..].find('>').map(|end| start + end + 1)) {
+            buffer.insert_str(pos, &format!("\n{}", style));
+        }
+
         Ok(buffer.into_bytes())
     }
 
-    fn draw_scene<DB: DrawingBackend>(root: &DrawingArea<DB, plotters::coord::Shift>, scene: &SceneGraph) -> Result<()>
+    /// Draw `text` in a small font, 10px from the right edge and 5px from the bottom
+    /// (logical pixels; the image is `scale` times larger)
+    fn add_watermark(image: &mut image::RgbImage, text: &str, alpha: f64, color: RGBColor, scale: f64) -> Result<()> {
+        let (physical_width, physical_height) = image.dimensions();
+        let width = (physical_width as f64 / scale).round() as u32;
+        let height = (physical_height as f64 / scale).round() as u32;
+        let backend = BitMapBackend::with_buffer(image, (physical_width, physical_height));
+        let root = ScaledBackend::new(backend, (width, height), scale).into_drawing_area();
+        let color = color.mix(alpha);
+        let style = TextStyle::from(("sans-serif", WATERMARK_FONT_SIZE).into_font())
+            .color(&color)
+            .pos(Pos::new(HPos::Right, VPos::Bottom));
+        root.draw_text(text, &style, (width as i32 - 10, height as i32 - 5))?;
+        root.present().context("Failed to draw watermark")?;
+        Ok(())
+    }
+
+    /// Composite a decoded image, scaled to `size` pixels wide, into a corner using its alpha channel.
+    /// `lift` moves a bottom-placed image up by that many pixels. Sizes are logical pixels.
+    fn add_watermark_image(
+        image: &mut image::RgbImage,
+        bytes: &[u8],
+        position: WatermarkPosition,
+        size: u32,
+        lift: u32,
+        scale: f64,
+    ) -> Result<()> {
+        let logo = image::load_from_memory(bytes).context("Failed to decode watermark image")?.to_rgba8();
+        let (logo_width, logo_height) = logo.dimensions();
+        let size = ((size as f64 * scale).round() as u32).max(1);
+        let lift = (lift as f64 * scale).round() as u32;
+        let scaled_height = ((logo_height as f64 * size as f64 / logo_width.max(1) as f64).round() as u32).max(1);
+        let logo = image::imageops::resize(&logo, size, scaled_height, image::imageops::FilterType::Triangle);
+
+        let (width, height) = image.dimensions();
+        let margin = (10.0 * scale).round() as i64;
+        let x0 = match position {
+            WatermarkPosition::TopLeft | WatermarkPosition::BottomLeft => margin,
+            _ => width as i64 - size as i64 - margin,
+        };
+        let y0 = match position {
+            WatermarkPosition::TopLeft | WatermarkPosition::TopRight => margin,
+            _ => height as i64 - scaled_height as i64 - margin - lift as i64,
+        };
+
+        for (x, y, pixel) in logo.enumerate_pixels() {
+            let (tx, ty) = (x0 + x as i64, y0 + y as i64);
+            if tx < 0 || ty < 0 || tx >= width as i64 || ty >= height as i64 {
+                continue;
+            }
+            let alpha = pixel[3] as f64 / 255.0;
+            let target = image.get_pixel_mut(tx as u32, ty as u32);
+            for c in 0..3 {
+                target[c] = (pixel[c] as f64 * alpha + target[c] as f64 * (1.0 - alpha)).round() as u8;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_scene<DB: DrawingBackend>(root: &DrawingArea<DB, plotters::coord::Shift>, scene: &SceneGraph, progress: Progress) -> Result<()>
     where DB::ErrorType: 'static {
         // Resolve theme once at the start
         let resolved_theme = scene.theme.resolve();
@@ -122,10 +413,51 @@ impl Canvas {
         let rows = max_row + 1;
         let cols = max_col + 1;
 
-        let areas = root.split_evenly((rows, cols));
+        // The continuous fill legend (tiles, hexbins) takes a column at the far right
+        let (plot_area, colorbar_area) = match &scene.colorbar {
+            Some(bar) if resolved_theme.legend_position != LegendPosition::None => {
+                let (rest, area) = root.split_horizontally(scene.width.saturating_sub(colorbar_width(bar)));
+                (rest, Some(area))
+            }
+            _ => (root.clone(), None),
+        };
+        let (width, _) = plot_area.dim_in_pixel();
+
+        // Reserve space for a shared legend (faceted plots) to the right of, or below, the grid
+        let show_shared_legend = !scene.legend.is_empty() && resolved_theme.legend_position != LegendPosition::None;
+        let legend_below = resolved_theme.legend_position == LegendPosition::LowerMiddle;
+        let (grid_area, legend_area) = if show_shared_legend {
+            if legend_below {
+                let (grid, legend) = plot_area.split_vertically(scene.height.saturating_sub(SHARED_LEGEND_HEIGHT));
+                (grid, Some(legend))
+            } else {
+                let legend_width = shared_legend_width(&scene.legend, &scene.legend_title, resolved_theme.legend_key_size);
+                let (grid, legend) = plot_area.split_horizontally(width.saturating_sub(legend_width));
+                (grid, Some(legend))
+            }
+        } else {
+            (plot_area, None)
+        };
+
+        let areas = if scene.row_heights.len() == rows {
+            // facet_wrap(space: "free_y"): rows sized by their share of the grid height
+            let (_, grid_height) = grid_area.dim_in_pixel();
+            let mut breaks = Vec::with_capacity(rows.saturating_sub(1));
+            let mut acc = 0.0;
+            for share in &scene.row_heights[..rows - 1] {
+                acc += share;
+                breaks.push((acc * grid_height as f64).round() as u32);
+            }
+            grid_area.split_by_breakpoints(Vec::<u32>::new(), breaks)
+                .iter()
+                .flat_map(|row| row.split_evenly((1, cols)))
+                .collect()
+        } else {
+            grid_area.split_evenly((rows, cols))
+        };
 
         // Draw Global Title using resolved theme
-        if let Some(title) = &scene.labels.title {
+        if let Some(title) = scene.labels.title.as_ref().filter(|_| !resolved_theme.plot_title_blank) {
             let title_style = TextStyle::from((
                 resolved_theme.plot_title.family.as_str(),
                 resolved_theme.plot_title.size as i32
@@ -133,7 +465,8 @@ impl Canvas {
             root.draw_text(title, &title_style, (10, 10))?;
         }
 
-        for panel in &scene.panels {
+        for (panel_idx, panel) in scene.panels.iter().enumerate() {
+            report(progress, || format!("Rendering panel [{}/{}]...", panel_idx + 1, scene.panels.len()));
             let area_idx = panel.row * cols + panel.col;
             if area_idx >= areas.len() { continue; }
 
@@ -141,6 +474,14 @@ impl Canvas {
             Canvas::draw_panel(area, panel, &resolved_theme)?;
         }
 
+        if let Some(area) = legend_area {
+            Canvas::draw_shared_legend(&area, &scene.legend, &scene.legend_title, &resolved_theme, legend_below)?;
+        }
+
+        if let (Some(area), Some(bar)) = (colorbar_area, &scene.colorbar) {
+            Canvas::draw_colorbar(&area, bar, &resolved_theme)?;
+        }
+
         root.present().context("Failed to present drawing")?;
         Ok(())
     }
@@ -152,21 +493,63 @@ impl Canvas {
     ) -> Result<()>
     where <DB as plotters::prelude::DrawingBackend>::ErrorType: 'static
     {
-        let x_range = panel.x_scale.range.0..panel.x_scale.range.1;
-        let y_range = panel.y_scale.range.0..panel.y_scale.range.1;
+        let x_range = AxisCoord::new(&panel.x_scale);
+        let y_range = AxisCoord::new(&panel.y_scale);
+
+        // Strips on any edge but the top take a band of the panel area instead of the chart caption
+        let (area, strip_area) = match (&panel.title, panel.strip_position) {
+            (None, _) | (_, StripPosition::Top) => (area.clone(), None),
+            (Some(_), position) => {
+                let strip_size = theme.strip_text_size as u32 + 10;
+                let (width, height) = area.dim_in_pixel();
+                let (chart, strip) = match position {
+                    StripPosition::Bottom => area.split_vertically(height.saturating_sub(strip_size)),
+                    StripPosition::Left => {
+                        let (strip, chart) = area.split_horizontally(strip_size);
+                        (chart, strip)
+                    }
+                    _ => area.split_horizontally(width.saturating_sub(strip_size)),
+                };
+                (chart, Some(strip))
+            }
+        };
+        let area = &area;
+
+        // Rotated category labels (theme(axis_text: element_text(angle: ...))) are drawn by hand below,
+        // since plotters always centers x tick labels on the tick
+        let x_label_rotation = if panel.x_scale.is_categorical && !theme.axis_text_blank { label_rotation(theme.axis_text.angle) } else { None };
+        // Mosaic bars put their categories off the integer ticks, so those labels are drawn by hand too
+        let x_positioned = panel.x_scale.is_categorical && !panel.x_scale.category_positions.is_empty() && !theme.axis_text_blank;
+        let y_positioned = panel.y_scale.is_categorical && !panel.y_scale.category_positions.is_empty() && !theme.axis_text_blank;
+        let x_label_area = x_label_area_size(panel, theme, x_label_rotation.as_ref(), area.dim_in_pixel().1);
+        let y_label_area = y_label_area_size(panel, theme, area.dim_in_pixel().0);
 
         let mut chart_builder = ChartBuilder::on(area);
 
+        chart_builder.margin(10);
+        if strip_area.is_none() {
+            chart_builder.caption(
+                panel.title.clone().unwrap_or_default(),
+                (theme.plot_title.family.as_str(), theme.strip_text_size as i32),
+            );
+        }
         chart_builder
-            .margin(10)
-            .caption(panel.title.clone().unwrap_or_default(), ("sans-serif", 15))
-            .x_label_area_size(30)
-            .y_label_area_size(40);
+            .x_label_area_size(x_label_area)
+            .y_label_area_size(y_label_area);
 
         let mut chart = chart_builder
             .build_cartesian_2d(x_range, y_range)
             .context("Failed to build chart")?;
 
+        // Panel background (only when it differs from the plot background)
+        if !theme.panel_background_blank && theme.panel_background.fill != theme.plot_background.fill {
+            chart.plotting_area().fill(&theme.panel_background.fill)
+                .context("Failed to fill panel background")?;
+        }
+
+        // Transparent color used to hide blank tick labels
+        let hidden_text_color = theme.axis_text.color.mix(0.0);
+
         // Configure Mesh & Labels
         let mut mesh = chart.configure_mesh();
 
@@ -208,12 +591,32 @@ impl Canvas {
                 }
             }
 
+            // Both grids blank - skip mesh lines entirely
+            if theme.panel_grid_major.is_none() && theme.panel_grid_minor.is_none() {
+                mesh.disable_mesh();
+            }
+
+            // Blank ticks
+            if theme.axis_ticks.is_none() {
+                mesh.set_all_tick_mark_size(0);
+            }
+
             // Axis text styling
             let axis_text_style = TextStyle::from((
                 theme.axis_text.family.as_str(),
                 theme.axis_text.size as i32
             ).into_font()).color(&theme.axis_text.color);
-            mesh.label_style(axis_text_style);
+            mesh.axis_desc_style(axis_text_style.clone());
+
+            if theme.axis_text_blank && theme.axis_line.is_none() {
+                // Nothing left to draw on the axes
+                mesh.disable_axes();
+            } else if theme.axis_text_blank {
+                // Keep the axis line, hide the tick labels
+                mesh.label_style(axis_text_style.color(&hidden_text_color));
+            } else {
+                mesh.label_style(axis_text_style);
+            }
         }
 
         if let Some(x_label) = &panel.x_label {
@@ -239,7 +642,10 @@ impl Canvas {
             }
         };
 
-        if panel.x_scale.is_categorical {
+        let formatter_blank = |_: &f64| String::new();
+        if x_label_rotation.is_some() || x_positioned {
+            mesh.x_label_formatter(&formatter_blank);
+        } else if panel.x_scale.is_categorical {
             mesh.x_label_formatter(&formatter_x);
         }
 
@@ -257,104 +663,851 @@ impl Canvas {
             }
         };
 
-        if panel.y_scale.is_categorical {
+        if y_positioned {
+            mesh.y_label_formatter(&formatter_blank);
+        } else if panel.y_scale.is_categorical {
             mesh.y_label_formatter(&formatter_y);
         }
+
+        // Continuous tick label formats; proportion stats default to percentages
+        let x_format = tick_format(&panel.x_scale);
+        let y_format = tick_format(&panel.y_scale);
+        let formatter_x_ticks = |v: &f64| format_tick(*v, x_format.as_ref().unwrap_or(&TickFormat::Auto));
+        let formatter_y_ticks = |v: &f64| format_tick(*v, y_format.as_ref().unwrap_or(&TickFormat::Auto));
+        if x_format.is_some() {
+            mesh.x_label_formatter(&formatter_x_ticks);
+        }
+        if y_format.is_some() {
+            mesh.y_label_formatter(&formatter_y_ticks);
+        }
         
         mesh.draw().context("Failed to draw mesh")?;
 
+        if x_label_rotation.is_some() || x_positioned {
+            // Anchor the end of the text nearest the axis at each tick
+            let transform = match &x_label_rotation {
+                Some(LabelRotation::Quarter(t)) => t.clone(),
+                _ => FontTransform::None,
+            };
+            let pos = match transform {
+                FontTransform::None => Pos::new(HPos::Center, VPos::Top),
+                FontTransform::Rotate90 => Pos::new(HPos::Left, VPos::Center),
+                FontTransform::Rotate180 => Pos::new(HPos::Center, VPos::Bottom),
+                _ => Pos::new(HPos::Right, VPos::Center),
+            };
+            let style = TextStyle::from((theme.axis_text.family.as_str(), theme.axis_text.size as i32).into_font())
+                .color(&theme.axis_text.color)
+                .transform(transform)
+                .pos(pos);
+            let (base_x, base_y) = area.get_base_pixel();
+            let axis_y = chart.plotting_area().get_pixel_range().1.end;
+            for (i, category) in panel.x_scale.categories.iter().enumerate() {
+                let x = panel.x_scale.category_positions.get(i).copied().unwrap_or(i as f64);
+                if x < panel.x_scale.range.0.min(panel.x_scale.range.1) || x > panel.x_scale.range.0.max(panel.x_scale.range.1) {
+                    continue;
+                }
+                let (px, _) = chart.backend_coord(&(x, panel.y_scale.range.0));
+                let anchor = (px - base_x, axis_y - base_y + 8);
+                if let Some(LabelRotation::Angle(angle)) = x_label_rotation {
+                    draw_angled_text(area, category, &style, anchor, angle)?;
+                } else {
+                    area.draw_text(category, &style, anchor)?;
+                }
+            }
+        }
+
+        if y_positioned {
+            let style = TextStyle::from((theme.axis_text.family.as_str(), theme.axis_text.size as i32).into_font())
+                .color(&theme.axis_text.color)
+                .pos(Pos::new(HPos::Right, VPos::Center));
+            let (base_x, base_y) = area.get_base_pixel();
+            let axis_x = chart.plotting_area().get_pixel_range().0.start;
+            for (category, &y) in panel.y_scale.categories.iter().zip(&panel.y_scale.category_positions) {
+                let (_, py) = chart.backend_coord(&(panel.x_scale.range.0, y));
+                area.draw_text(category, &style, (axis_x - base_x - 8, py - base_y))?;
+            }
+        }
+
+        // Strip label centered in its band, rotated on the side edges
+        if let (Some(strip), Some(title)) = (&strip_area, &panel.title) {
+            let transform = match panel.strip_position {
+                StripPosition::Left => FontTransform::Rotate270,
+                StripPosition::Right => FontTransform::Rotate90,
+                _ => FontTransform::None,
+            };
+            let style = TextStyle::from((theme.plot_title.family.as_str(), theme.strip_text_size as i32).into_font())
+                .color(&theme.plot_title.color)
+                .transform(transform)
+                .pos(Pos::new(HPos::Center, VPos::Center));
+            let (width, height) = strip.dim_in_pixel();
+            strip.draw_text(title, &style, (width as i32 / 2, height as i32 / 2))?;
+        }
+
         // Draw Commands
         for cmd in &panel.commands {
             match cmd {
-                DrawCommand::DrawLine { points, style, legend } => {
+                DrawCommand::DrawLine { points, style, .. } => {
                     let color = parse_color(&style.color, BLUE);
                     let stroke_width = style.width.unwrap_or(2.0).ceil() as u32;
-                    let alpha = style.alpha.unwrap_or(1.0);
-                    let color_style = color.mix(alpha).stroke_width(stroke_width);
-
-                    let series = chart.draw_series(LineSeries::new(points.iter().cloned(), color_style))
-                        .context("Failed to draw line")?;
+                    let color_style = color.mix(style.alpha.unwrap_or(1.0)).stroke_width(stroke_width);
+                    let pattern = dash_pattern(style.linetype.as_deref(), stroke_width);
 
-                    if let Some(label) = legend {
-                        series.label(label)
-                            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.mix(alpha).stroke_width(stroke_width)));
-                    }
+                    if pattern.is_empty() {
+                        chart.draw_series(LineSeries::new(points.iter().cloned(), color_style))
+                    } else {
+                        chart.draw_series(std::iter::once(PatternPathElement::new(points.clone(), pattern, color_style)))
+                    }.context("Failed to draw line")?;
                 }
-                DrawCommand::DrawPoint { points, style, legend } => {
-                    let color = parse_color(&style.color, BLUE);
+                DrawCommand::DrawPoint { points, style, .. } => {
                     let size = style.size.unwrap_or(3.0) as i32;
-                    let alpha = style.alpha.unwrap_or(1.0);
-                    let color_style = color.mix(alpha).filled();
-
-                    let series = chart.draw_series(points.iter().map(|(x, y)| {
-                        Circle::new((*x, *y), size, color_style)
-                    })).context("Failed to draw points")?;
+                    let (fill, border) = point_styles(style);
+                    let shape = style.shape.as_deref().unwrap_or("circle");
 
-                    if let Some(label) = legend {
-                        series.label(label)
-                            .legend(move |(x, y)| Circle::new((x + 10, y), size, color.mix(alpha).filled()));
+                    for (marker_style, filled) in [(fill, true), (border, false)] {
+                        let Some(marker_style) = marker_style else { continue };
+                        match marker_vertices(shape, size, !filled) {
+                            None => chart.draw_series(points.iter().map(|&p| Circle::new(p, size, marker_style))),
+                            Some(v) if filled => chart.draw_series(points.iter().map(|&p| EmptyElement::at(p) + Polygon::new(v.clone(), marker_style))),
+                            Some(v) => chart.draw_series(points.iter().map(|&p| EmptyElement::at(p) + PathElement::new(v.clone(), marker_style))),
+                        }.context("Failed to draw points")?;
                     }
                 }
-                DrawCommand::DrawRect { tl, br, style, legend } => {
+                DrawCommand::DrawRect { tl, br, style, .. } => {
                     let color = parse_color(&style.color, BLUE);
-                    let alpha = style.alpha.unwrap_or(1.0);
-                    let color_style = color.mix(alpha).filled();
+                    let color_style = color.mix(style.alpha.unwrap_or(1.0)).filled();
 
-                    let series = chart.draw_series(std::iter::once(Rectangle::new(
-                        [*tl, *br],
-                        color_style
-                    ))).context("Failed to draw rect")?;
-                    
-                    if let Some(label) = legend {
-                        series.label(label)
-                            .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 15, y + 5)], color.mix(alpha).filled()));
+                    if !style.hollow {
+                        chart.draw_series(std::iter::once(Rectangle::new(
+                            [*tl, *br],
+                            color_style
+                        ))).context("Failed to draw rect")?;
+                    }
+
+                    let border = match &style.border_color {
+                        Some(border) => Some(parse_color(&Some(border.clone()), BLACK)),
+                        None => style.hollow.then_some(color),
+                    };
+                    if let Some(border) = border {
+                        let stroke_width = style.border_width.unwrap_or(1.0).ceil() as u32;
+                        chart.draw_series(std::iter::once(Rectangle::new([*tl, *br], border.stroke_width(stroke_width))))
+                            .context("Failed to draw rect border")?;
                     }
                 }
-                DrawCommand::DrawPolygon { points, style, legend } => {
+                DrawCommand::DrawPolygon { points, style, .. } => {
                     let color = parse_color(&style.color, BLUE);
-                    let alpha = style.alpha.unwrap_or(0.5);
-                    let color_style = color.mix(alpha).filled();
+                    let color_style = color.mix(style.alpha.unwrap_or(0.5)).filled();
 
-                    let series = chart.draw_series(std::iter::once(Polygon::new(
+                    chart.draw_series(std::iter::once(Polygon::new(
                         points.clone(),
-                        color_style.clone()
+                        color_style
                     ))).context("Failed to draw polygon")?;
+                }
+                DrawCommand::DrawText { pos, text, style, .. } => {
+                    let color = parse_color(&style.color, BLACK).mix(style.alpha.unwrap_or(1.0));
+                    let font = TextStyle::from((theme.axis_text.family.as_str(), style.size).into_font())
+                        .color(&color)
+                        .pos(Pos::new(HPos::Center, VPos::Center));
 
-                    if let Some(label) = legend {
-                        series.label(label)
-                            .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 15, y + 5)], color_style.clone()));
-                    }
+                    chart.draw_series(std::iter::once(Text::new(text.clone(), *pos, font)))
+                        .context("Failed to draw text")?;
                 }
             }
         }
-        
-        // Draw Legend if any items (respecting theme legend_position)
-        // Note: Plotters draws legend only if series were labeled
-        use crate::parser::ast::LegendPosition;
-        use plotters::chart::SeriesLabelPosition;
-
-        if theme.legend_position != LegendPosition::None {
-            let position = match theme.legend_position {
-                LegendPosition::UpperLeft => SeriesLabelPosition::UpperLeft,
-                LegendPosition::UpperMiddle => SeriesLabelPosition::UpperMiddle,
-                LegendPosition::UpperRight => SeriesLabelPosition::UpperRight,
-                LegendPosition::MiddleLeft => SeriesLabelPosition::MiddleLeft,
-                LegendPosition::MiddleMiddle => SeriesLabelPosition::MiddleMiddle,
-                LegendPosition::MiddleRight => SeriesLabelPosition::MiddleRight,
-                LegendPosition::LowerLeft => SeriesLabelPosition::LowerLeft,
-                LegendPosition::LowerMiddle => SeriesLabelPosition::LowerMiddle,
-                LegendPosition::LowerRight => SeriesLabelPosition::LowerRight,
-                LegendPosition::None => unreachable!(), // handled above
-            };
 
-            chart.configure_series_labels()
-                .position(position)
-                .background_style(&WHITE.mix(0.8))
-                .border_style(&BLACK)
-                .draw()
-                .context("Failed to draw legend")?;
+        // Draw Legend if any commands are labeled (respecting theme legend_position)
+        let mut entries = panel_legend_entries(&panel.commands);
+        entries.extend(panel.legend_items.iter().cloned());
+        if theme.legend_position != LegendPosition::None && !entries.is_empty() {
+            Self::draw_legend(&chart.plotting_area().strip_coord_spec(), &entries, &panel.legend_title, theme)?;
+        }
+
+        Ok(())
+    }
+
+    /// Draw a panel's legend box inside its plotting area, at the theme's legend_position
+    fn draw_legend<DB: DrawingBackend>(
+        area: &DrawingArea<DB, plotters::coord::Shift>,
+        entries: &[LegendEntry],
+        title: &Option<String>,
+        theme: &ResolvedTheme,
+    ) -> Result<()>
+    where DB::ErrorType: 'static {
+        let font = if theme.has_customization {
+            TextStyle::from((theme.axis_text.family.as_str(), theme.axis_text.size as i32).into_font()).color(&theme.axis_text.color)
+        } else {
+            TextStyle::from(("sans-serif", 12).into_font())
+        };
+        let key_size = theme.legend_key_size;
+        let margin = 10;
+        // Room for the key left of the labels: the default key is 20px wide plus a 10px gap
+        let key_area = scaled(LEGEND_KEY_WIDTH, key_size) + 10;
+        // Rows are one text line apart, or further apart when the keys are taller
+        let row_height = (font.font.get_size() * 1.25).max(1.5 * scaled(LEGEND_KEY_HEIGHT, key_size) as f64);
+
+        // Title row first (no key), then one row per entry
+        let rows: Vec<(&str, Option<&RenderStyle>)> = title.iter().map(|t| (t.as_str(), None))
+            .chain(entries.iter().map(|e| (e.label.as_str(), Some(&e.style))))
+            .collect();
+        let mut layout = Vec::with_capacity(rows.len());
+        let (mut text_w, mut text_h) = (0, 0);
+        for (i, (label, _)) in rows.iter().enumerate() {
+            let (w, h) = area.estimate_text_size(label, &font)?;
+            let y = (i as f64 * row_height).round() as i32;
+            text_w = text_w.max(w as i32);
+            text_h = text_h.max(y + h as i32);
+            layout.push((y, h as i32));
+        }
+
+        let (w, h) = (text_w + key_area + margin * 2, text_h + margin * 2);
+        let (area_w, area_h) = area.dim_in_pixel();
+        let (area_w, area_h) = (area_w as i32, area_h as i32);
+        let x0 = match theme.legend_position {
+            LegendPosition::UpperLeft | LegendPosition::MiddleLeft | LegendPosition::LowerLeft => 5,
+            LegendPosition::UpperMiddle | LegendPosition::MiddleMiddle | LegendPosition::LowerMiddle => (area_w - w) / 2,
+            _ => area_w - w - 5,
+        };
+        let y0 = match theme.legend_position {
+            LegendPosition::UpperLeft | LegendPosition::UpperMiddle | LegendPosition::UpperRight => 5,
+            LegendPosition::MiddleLeft | LegendPosition::MiddleMiddle | LegendPosition::MiddleRight => (area_h - h) / 2,
+            _ => area_h - h - 5,
+        };
+
+        area.draw(&Rectangle::new([(x0, y0), (x0 + w, y0 + h)], WHITE.mix(0.8).filled()))?;
+        area.draw(&Rectangle::new([(x0, y0), (x0 + w, y0 + h)], BLACK))?;
+        for ((label, style), (y, h)) in rows.iter().zip(layout) {
+            let y = y0 + margin + y;
+            area.draw(&Text::new(label.to_string(), (x0 + margin + key_area, y), font.clone()))
+                .context("Failed to draw legend label")?;
+            if let Some(style) = style {
+                Self::draw_legend_glyph(area, style, (x0 + margin, y + h / 2), key_size)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Default legend key size in pixels, before theme(legend_key_size: ...) scaling
+const LEGEND_KEY_WIDTH: f64 = 20.0;
+const LEGEND_KEY_HEIGHT: f64 = 10.0;
+
+/// A pixel length scaled by the legend key size
+fn scaled(px: f64, key_size: f64) -> i32 {
+    (px * key_size).round() as i32
+}
+
+/// Legend entries of a panel: its labeled draw commands, in drawing order
+fn panel_legend_entries(commands: &[DrawCommand]) -> Vec<LegendEntry> {
+    commands.iter().filter_map(|cmd| {
+        let (label, style) = match cmd {
+            DrawCommand::DrawLine { legend, style, .. } => (legend, RenderStyle::Line(style.clone())),
+            DrawCommand::DrawPoint { legend, style, .. } => (legend, RenderStyle::Point(style.clone())),
+            DrawCommand::DrawRect { legend, style, .. } => (legend, RenderStyle::Bar(style.clone())),
+            DrawCommand::DrawPolygon { legend, style, .. } => (legend, RenderStyle::Ribbon(style.clone())),
+            DrawCommand::DrawText { legend, style, .. } => (legend, RenderStyle::Text(style.clone())),
+        };
+        label.as_ref().map(|label| LegendEntry { label: label.clone(), style })
+    }).collect()
+}
+
+/// Height of the shared legend strip when placed below the panel grid
+const SHARED_LEGEND_HEIGHT: u32 = 40;
+
+/// Width of the color bar column: the bar plus its longest tick label (or the title)
+fn colorbar_width(bar: &ColorBar) -> u32 {
+    let longest_tick = bar.ticks.iter().map(|(_, l)| l.chars().count()).max().unwrap_or(0) as u32;
+    let title = bar.title.as_ref().map_or(0, |t| t.chars().count()) as u32;
+    (longest_tick * 7 + 45).max(title * 7 + 20).max(70)
+}
+
+impl Canvas {
+    /// Draw the continuous fill legend: a vertical gradient (low at the bottom) with tick labels
+    fn draw_colorbar<DB: DrawingBackend>(
+        area: &DrawingArea<DB, plotters::coord::Shift>,
+        bar: &ColorBar,
+        theme: &ResolvedTheme,
+    ) -> Result<()>
+    where DB::ErrorType: 'static {
+        let text_style = TextStyle::from((
+            theme.axis_text.family.as_str(),
+            theme.axis_text.size as i32
+        ).into_font()).color(&theme.axis_text.color);
+        let (_, height) = area.dim_in_pixel();
+        let bar_height = (height as f64 * 0.5).min(200.0);
+        let (x, top) = (10, (height as i32 - bar_height as i32) / 2);
+        let bottom = top + bar_height as i32;
+
+        if let Some(title) = &bar.title {
+            area.draw(&Text::new(title.clone(), (x, top - 20), text_style.clone()))
+                .context("Failed to draw color bar title")?;
+        }
+
+        let n = bar.colors.len().max(1) as f64;
+        for (i, color) in bar.colors.iter().enumerate() {
+            let y_low = bottom - (i as f64 * bar_height / n).round() as i32;
+            let y_high = bottom - ((i + 1) as f64 * bar_height / n).round() as i32;
+            area.draw(&Rectangle::new([(x, y_high), (x + 15, y_low)], parse_color(&Some(color.clone()), BLUE).filled()))
+                .context("Failed to draw color bar")?;
+        }
+
+        for (pos, label) in &bar.ticks {
+            let y = bottom - (pos * bar_height).round() as i32;
+            area.draw(&PathElement::new(vec![(x + 11, y), (x + 15, y)], WHITE))
+                .context("Failed to draw color bar tick")?;
+            area.draw(&Text::new(label.clone(), (x + 20, y - 6), text_style.clone()))
+                .context("Failed to draw color bar label")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Tick count a date axis aims for without breaks_n (as many as plotters' default labels)
+const DATE_TICKS: usize = 10;
+
+/// Continuous axis coordinate with optional tick control
+/// (scale_x_continuous(breaks_n: ..., breaks_width: ...)); without either it
+/// behaves exactly like plotters' own f64 coordinate. Log axes map log10 of the
+/// value and tick at powers of ten; date axes tick on whole days, weeks, months or years.
+struct AxisCoord {
+    inner: RangedCoordf64,
+    breaks_n: Option<usize>,
+    breaks_width: Option<f64>,
+    log: Option<RangedCoordf64>, // log10 of the range, for scale_*_log10
+    date: bool,                  // Values are days since 1970-01-01 (scale_*_date)
+}
+
+impl AxisCoord {
+    fn new(scale: &Scale) -> Self {
+        let continuous = !scale.is_categorical;
+        let log = (continuous && scale.is_log && scale.range.0 > 0.0 && scale.range.1 > 0.0)
+            .then(|| (scale.range.0.log10()..scale.range.1.log10()).into());
+        AxisCoord {
+            inner: (scale.range.0..scale.range.1).into(),
+            breaks_n: scale.breaks_n.filter(|_| continuous),
+            breaks_width: scale.breaks_width.filter(|_| continuous),
+            log,
+            date: continuous && matches!(scale.labels, TickFormat::Date(_)),
+        }
+    }
+
+    /// Multiples of `step` inside the axis range (None if there would be unreasonably many)
+    fn multiples(&self, step: f64) -> Option<Vec<f64>> {
+        let range = self.inner.range();
+        let (lo, hi) = (range.start.min(range.end), range.start.max(range.end));
+        let first = (lo / step).ceil() as i64;
+        let last = (hi / step).floor() as i64;
+        if last - first > 1000 {
+            return None;
+        }
+        Some((first..=last).map(|i| i as f64 * step).collect())
+    }
+}
+
+impl Ranged for AxisCoord {
+    type FormatOption = NoDefaultFormatting;
+    type ValueType = f64;
+
+    fn map(&self, value: &f64, limit: (i32, i32)) -> i32 {
+        match &self.log {
+            Some(log) => {
+                // Non-positive values (e.g. a bar baseline) sit one axis length below the range
+                let range = log.range();
+                let floor = range.start.min(range.end) - (range.end - range.start).abs();
+                let v = if *value > 0.0 { value.log10().max(floor) } else { floor };
+                log.map(&v, limit)
+            }
+            None => self.inner.map(value, limit),
+        }
+    }
+
+    fn key_points<Hint: KeyPointHint>(&self, hint: Hint) -> Vec<f64> {
+        let light = hint.weight().allow_light_points();
+        if self.log.is_some() {
+            let range = self.inner.range();
+            let (lo, hi) = (range.start.min(range.end), range.start.max(range.end));
+            let mut points = if light { crate::scale::trans_minor_breaks(lo, hi) } else { crate::scale::log10_breaks(lo, hi) };
+            // Less than a 1-2-5 step across the axis: ordinary ticks at their log positions
+            if points.len() < 2 {
+                return self.inner.key_points(hint);
+            }
+            // Thin out to the hinted count, keeping every n-th break
+            let step = points.len().div_ceil(hint.max_num_points().max(1));
+            if step > 1 {
+                points = points.into_iter().step_by(step).collect();
+            }
+            return points;
+        }
+        if self.date && self.breaks_width.is_none() {
+            let range = self.inner.range();
+            let (lo, hi) = (range.start.min(range.end), range.start.max(range.end));
+            let n = self.breaks_n.unwrap_or(DATE_TICKS);
+            // Minor grid lines at a finer calendar step
+            return crate::scale::date_breaks(lo, hi, if light { n * 2 } else { n });
+        }
+        if let Some(width) = self.breaks_width {
+            // Minor grid lines halfway between the breaks
+            if let Some(points) = self.multiples(if light { width / 2.0 } else { width }) {
+                return points;
+            }
+        }
+        match self.breaks_n {
+            Some(n) if !light => self.inner.key_points(n),
+            _ => self.inner.key_points(hint),
+        }
+    }
+
+    fn range(&self) -> std::ops::Range<f64> {
+        self.inner.range()
+    }
+}
+
+impl ValueFormatter<f64> for AxisCoord {
+    fn format(value: &f64) -> String {
+        RangedCoordf64::format(value)
+    }
+}
+
+/// Rotation of the x category labels
+#[derive(Clone)]
+enum LabelRotation {
+    Quarter(FontTransform), // Glyphs rotated by plotters
+    Angle(f64),             // Radians counter-clockwise, not a quarter turn
+}
+
+/// Rotation for a theme angle (degrees counter-clockwise, as in ggplot2), None when unrotated.
+/// Plotters only rotates glyphs by quarter turns; other angles are laid out by `draw_angled_text`
+fn label_rotation(angle: f64) -> Option<LabelRotation> {
+    let turns = angle / 90.0;
+    if (turns - turns.round()).abs() > 1e-9 {
+        return Some(LabelRotation::Angle(angle.to_radians()));
+    }
+    match turns.round().rem_euclid(4.0) as u8 {
+        1 => Some(LabelRotation::Quarter(FontTransform::Rotate270)),
+        2 => Some(LabelRotation::Quarter(FontTransform::Rotate180)),
+        3 => Some(LabelRotation::Quarter(FontTransform::Rotate90)),
+        _ => None,
+    }
+}
+
+/// Draws `text` rotated by `angle`, the end nearest the axis at `anchor` (text rising away from
+/// the tick ends there, text falling away starts there). The glyphs are rasterized unrotated and
+/// each covered pixel of the rotated box is sampled back from them
+fn draw_angled_text<DB: DrawingBackend>(area: &DrawingArea<DB, plotters::coord::Shift>, text: &str, style: &TextStyle, anchor: (i32, i32), angle: f64) -> Result<()>
+where DB::ErrorType: 'static {
+    let mut coverage = std::collections::HashMap::new();
+    style.font.draw(text, (0, 0), |x, y, a| {
+        if a > 0.0 {
+            coverage.insert((x, y), a);
+        }
+        Ok::<(), std::convert::Infallible>(())
+    })
+    .map_err(|e| anyhow::anyhow!("Failed to draw label '{}': {:?}", text, e))?
+    .ok();
+    let Some(x_lo) = coverage.keys().map(|p| p.0).min() else { return Ok(()) };
+    let x_hi = coverage.keys().map(|p| p.0).max().unwrap_or(x_lo);
+    let (y_lo, y_hi) = (coverage.keys().map(|p| p.1).min().unwrap_or(0), coverage.keys().map(|p| p.1).max().unwrap_or(0));
+
+    // Text point placed at the anchor: its axis end, on the vertical center
+    let (sin, cos) = angle.sin_cos();
+    let origin = (if sin > 0.0 { x_hi } else { x_lo } as f64, (y_lo + y_hi) as f64 / 2.0);
+    let to_pixel = |(u, v): (f64, f64)| {
+        let (du, dv) = (u - origin.0, v - origin.1);
+        (anchor.0 as f64 + du * cos + dv * sin, anchor.1 as f64 - du * sin + dv * cos)
+    };
+    let corners = [(x_lo, y_lo), (x_hi, y_lo), (x_lo, y_hi), (x_hi, y_hi)].map(|(u, v)| to_pixel((u as f64, v as f64)));
+    let (px_lo, px_hi) = corners.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.0), hi.max(p.0)));
+    let (py_lo, py_hi) = corners.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.1), hi.max(p.1)));
+
+    let (r, g, b) = style.color.rgb;
+    for py in py_lo.floor() as i32..=py_hi.ceil() as i32 {
+        for px in px_lo.floor() as i32..=px_hi.ceil() as i32 {
+            let (dx, dy) = (px as f64 - anchor.0 as f64, py as f64 - anchor.1 as f64);
+            let (u, v) = (origin.0 + dx * cos - dy * sin, origin.1 + dx * sin + dy * cos);
+            if let Some(&a) = coverage.get(&(u.round() as i32, v.round() as i32)) {
+                area.draw_pixel((px, py), &RGBAColor(r, g, b, style.color.alpha * a as f64))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Height of the x label area: rotated labels need room for the longest one
+fn x_label_area_size(panel: &PanelScene, theme: &ResolvedTheme, rotation: Option<&LabelRotation>, area_height: u32) -> u32 {
+    let sin = match rotation {
+        Some(LabelRotation::Quarter(FontTransform::Rotate90 | FontTransform::Rotate270)) => 1.0,
+        Some(LabelRotation::Angle(angle)) => angle.sin().abs(),
+        _ => return 30,
+    };
+    let longest = panel.x_scale.categories.iter().map(|c| c.chars().count()).max().unwrap_or(0);
+    let text = (longest as f64 * theme.axis_text.size * 0.6 * sin).ceil() as u32 + 15;
+    text.clamp(30, (area_height / 3).max(30))
+}
+
+/// Width of the y label area: category names (e.g. after coord_flip()) and formatted
+/// tick labels (labels: "dollar", ...) need room for the longest one
+fn y_label_area_size(panel: &PanelScene, theme: &ResolvedTheme, area_width: u32) -> u32 {
+    let longest = if panel.y_scale.is_categorical {
+        panel.y_scale.categories.iter().map(|c| c.chars().count()).max().unwrap_or(0)
+    } else if let Some(format) = tick_format(&panel.y_scale) {
+        let (a, b) = panel.y_scale.range;
+        [a, b].iter().map(|v| format_tick(*v, &format).chars().count()).max().unwrap_or(0)
+    } else {
+        return 40;
+    };
+    let desc = if panel.y_label.is_some() { 20 } else { 0 };
+    let text = (longest as f64 * theme.axis_text.size * 0.6).ceil() as u32 + 15 + desc;
+    text.clamp(40, (area_width / 3).max(40))
+}
+
+/// Width of the shared legend column: room for the glyph plus the longest label
+fn shared_legend_width(entries: &[LegendEntry], title: &Option<String>, key_size: f64) -> u32 {
+    let longest = entries.iter().map(|e| e.label.chars().count())
+        .chain(title.iter().map(|t| t.chars().count()))
+        .max()
+        .unwrap_or(0) as u32;
+    (longest * 7 + scaled(LEGEND_KEY_WIDTH, key_size) as u32 + 30).max(80)
+}
+
+impl Canvas {
+    /// Draw the shared legend (title followed by one glyph + label per entry)
+    fn draw_shared_legend<DB: DrawingBackend>(
+        area: &DrawingArea<DB, plotters::coord::Shift>,
+        entries: &[LegendEntry],
+        title: &Option<String>,
+        theme: &ResolvedTheme,
+        horizontal: bool,
+    ) -> Result<()>
+    where DB::ErrorType: 'static {
+        let text_style = TextStyle::from((
+            theme.axis_text.family.as_str(),
+            theme.axis_text.size as i32
+        ).into_font()).color(&theme.axis_text.color);
+        let (width, height) = area.dim_in_pixel();
+        let key_size = theme.legend_key_size;
+        let row_height = 20.max(2 * scaled(LEGEND_KEY_HEIGHT, key_size));
+        let label_x = scaled(LEGEND_KEY_WIDTH, key_size) + 5;
+
+        // Vertical legends are centered in their column; horizontal ones start after the title
+        let (mut x, mut y) = if horizontal {
+            (10, (height / 2) as i32)
+        } else {
+            let rows = entries.len() as i32 + title.is_some() as i32;
+            (10, (height as i32 - rows * row_height) / 2 + row_height / 2)
+        };
+
+        if let Some(title) = title {
+            area.draw(&Text::new(title.clone(), (x, y - 6), text_style.clone()))
+                .context("Failed to draw legend title")?;
+            if horizontal {
+                x += title.chars().count() as i32 * 7 + 20;
+            } else {
+                y += row_height;
+            }
+        }
+
+        for entry in entries {
+            Self::draw_legend_glyph(area, &entry.style, (x, y), key_size)?;
+            area.draw(&Text::new(entry.label.clone(), (x + label_x, y - 6), text_style.clone()))
+                .context("Failed to draw legend label")?;
+            if horizontal {
+                x += entry.label.chars().count() as i32 * 7 + label_x + 15;
+                if x >= width as i32 { break; }
+            } else {
+                y += row_height;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Draw the legend key for a style, left edge at `x` and centered on `y`, scaled by `key_size`
+    fn draw_legend_glyph<DB: DrawingBackend>(
+        area: &DrawingArea<DB, plotters::coord::Shift>,
+        style: &RenderStyle,
+        (x, y): (i32, i32),
+        key_size: f64,
+    ) -> Result<()>
+    where DB::ErrorType: 'static {
+        // Filled keys are 15x10 at the default size
+        let fill_key = |color: RGBAColor| Rectangle::new(
+            [(x, y - scaled(LEGEND_KEY_HEIGHT / 2.0, key_size)), (x + scaled(15.0, key_size), y + scaled(LEGEND_KEY_HEIGHT / 2.0, key_size))],
+            color.filled(),
+        );
+        match style {
+            RenderStyle::Line(s) => {
+                let color = parse_color(&s.color, BLUE);
+                let stroke_width = s.width.unwrap_or(2.0).ceil() as u32;
+                let pattern = dash_pattern(s.linetype.as_deref(), stroke_width);
+                let end = x + scaled(LEGEND_KEY_WIDTH, key_size);
+                area.draw(&PatternPathElement::new(vec![(x, y), (end, y)], pattern, color.mix(s.alpha.unwrap_or(1.0)).stroke_width(stroke_width)))?;
+            }
+            RenderStyle::Point(s) => {
+                let radius = scaled(s.size.unwrap_or(3.0).trunc(), key_size);
+                let center = (x + scaled(LEGEND_KEY_WIDTH / 2.0, key_size), y);
+                let (fill, border) = point_styles(s);
+                let shape = s.shape.as_deref().unwrap_or("circle");
+                for (marker_style, filled) in [(fill, true), (border, false)] {
+                    let Some(marker_style) = marker_style else { continue };
+                    match marker_vertices(shape, radius, !filled) {
+                        None => area.draw(&Circle::new(center, radius, marker_style)),
+                        Some(v) if filled => area.draw(&(EmptyElement::at(center) + Polygon::new(v, marker_style))),
+                        Some(v) => area.draw(&(EmptyElement::at(center) + PathElement::new(v, marker_style))),
+                    }?;
+                }
+            }
+            RenderStyle::Bar(s) => {
+                let color = parse_color(&s.color, BLUE);
+                let key = fill_key(color.mix(s.alpha.unwrap_or(1.0)));
+                if !s.hollow {
+                    area.draw(&key)?;
+                }
+                let border = match &s.border_color {
+                    Some(border) => Some(parse_color(&Some(border.clone()), BLACK)),
+                    None => s.hollow.then_some(color),
+                };
+                if let Some(border) = border {
+                    let stroke_width = s.border_width.unwrap_or(1.0).ceil() as u32;
+                    let [tl, br] = [key.point_iter()[0], key.point_iter()[1]];
+                    area.draw(&Rectangle::new([tl, br], border.stroke_width(stroke_width)))?;
+                }
+            }
+            RenderStyle::Ribbon(s) => {
+                area.draw(&fill_key(parse_color(&s.color, BLUE).mix(s.alpha.unwrap_or(0.5))))?;
+            }
+            RenderStyle::Boxplot(s) => {
+                area.draw(&fill_key(parse_color(&s.color, BLUE).mix(s.alpha.unwrap_or(1.0))))?;
+            }
+            RenderStyle::Violin(s) => {
+                area.draw(&fill_key(parse_color(&s.color, BLUE).mix(s.alpha.unwrap_or(0.7))))?;
+            }
+            RenderStyle::Tile(s) => {
+                area.draw(&fill_key(parse_color(&s.fill, RGBColor(128, 128, 128)).mix(s.alpha.unwrap_or(1.0))))?;
+            }
+            RenderStyle::Hex(s) => {
+                area.draw(&fill_key(RGBColor(128, 128, 128).mix(s.alpha.unwrap_or(1.0))))?;
+            }
+            RenderStyle::Text(s) => {
+                let color = parse_color(&s.color, BLACK).mix(s.alpha.unwrap_or(1.0));
+                let style = TextStyle::from(("sans-serif", scaled(12.0, key_size)).into_font()).color(&color).pos(Pos::new(HPos::Center, VPos::Center));
+                area.draw_text("a", &style, (x + scaled(LEGEND_KEY_WIDTH / 2.0, key_size), y))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Format a 0-1 proportion as a percentage tick label ("0%", "50%", "100%")
+fn format_percent(v: f64) -> String {
+    let pct = v * 100.0;
+    if (pct - pct.round()).abs() < 1e-6 {
+        format!("{}%", pct.round())
+    } else {
+        format!("{:.1}%", pct)
+    }
+}
+
+/// Explicit tick label format for a continuous scale (None = plotters default)
+fn tick_format(scale: &Scale) -> Option<TickFormat> {
+    match &scale.labels {
+        _ if scale.is_categorical => None,
+        TickFormat::Auto if scale.is_percent => Some(TickFormat::Percent),
+        // Powers of ten read better as 10,000 than as 10000.0
+        TickFormat::Auto if scale.is_log => Some(TickFormat::Comma),
+        TickFormat::Auto => None,
+        format => Some(format.clone()),
+    }
+}
+
+/// Format a tick value according to scale_*_continuous(labels: ...)
+fn format_tick(v: f64, format: &TickFormat) -> String {
+    match format {
+        TickFormat::Auto => RangedCoordf64::format(&v),
+        TickFormat::Comma => format_number_with_commas(v, decimal_places(v)),
+        TickFormat::Scientific => format!("{:e}", v),
+        TickFormat::Percent => format_percent(v),
+        TickFormat::Dollar if v < 0.0 => format!("-${}", format_number_with_commas(-v, decimal_places(v))),
+        TickFormat::Dollar => format!("${}", format_number_with_commas(v, decimal_places(v))),
+        TickFormat::Custom(spec) => format_printf(spec, v),
+        TickFormat::Date(spec) => crate::scale::days_to_date(v).map_or_else(String::new, |d| d.format(spec).to_string()),
+    }
+}
+
+/// Decimals needed to show a tick value (at most 5, trailing zeros dropped)
+fn decimal_places(v: f64) -> usize {
+    let s = format!("{:.5}", v.abs());
+    s.trim_end_matches('0').split('.').nth(1).map_or(0, str::len)
+}
+
+/// Format a number with thousands separators: 1234567.5 -> "1,234,567.5"
+fn format_number_with_commas(v: f64, decimal_places: usize) -> String {
+    let s = format!("{:.*}", decimal_places, v.abs());
+    let (int_part, frac_part) = match s.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (s.as_str(), None),
+    };
+
+    let mut out = String::new();
+    for (i, c) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    if let Some(frac) = frac_part {
+        out.push('.');
+        out.push_str(frac);
+    }
+    if v < 0.0 && out.chars().any(|c| c != '0' && c != ',' && c != '.') {
+        out.insert(0, '-');
+    }
+    out
+}
+
+/// Minimal printf: `%f`, `%.Nf`, `%e`, `%.Ne`, `%d` and `%%`; other text is copied through
+fn format_printf(spec: &str, v: f64) -> String {
+    let mut out = String::new();
+    let mut chars = spec.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let mut precision = String::new();
+        if chars.peek() == Some(&'.') {
+            chars.next();
+            while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                precision.push(*d);
+                chars.next();
+            }
+        }
+        let precision: Option<usize> = precision.parse().ok();
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some('f') => out.push_str(&format!("{:.*}", precision.unwrap_or(6), v)),
+            Some('e') => out.push_str(&format!("{:.*e}", precision.unwrap_or(6), v)),
+            Some('d') => out.push_str(&format!("{}", v.round() as i64)),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+/// (fill, outline) styles of a point: solid `color` by default, `fill` inside a `color` outline,
+/// or only the outline for "_open" shapes
+fn point_styles(style: &PointStyle) -> (Option<ShapeStyle>, Option<ShapeStyle>) {
+    let alpha = style.alpha.unwrap_or(1.0);
+    let color = parse_color(&style.color, BLUE).mix(alpha);
+    let open = style.shape.as_deref().is_some_and(|s| crate::palette::split_open_shape(s).1);
+    match &style.fill {
+        Some(fill) => (Some(parse_color(&Some(fill.clone()), WHITE).mix(alpha).filled()), Some(color.stroke_width(1))),
+        None if open => (None, Some(color.stroke_width(1))),
+        None => (Some(color.filled()), None),
+    }
+}
+
+/// Pixel vertices around (0, 0) of a point shape of radius `size`, closed when `closed` is set.
+/// None for circles: shapes other than square, triangle and diamond (and their "_open"
+/// variants) are drawn as circles.
+fn marker_vertices(shape: &str, size: i32, closed: bool) -> Option<Vec<(i32, i32)>> {
+    let half = (size as f64 * 0.87).round() as i32;
+    let mut vertices = match crate::palette::split_open_shape(shape).0 {
+        "square" => vec![(-size, -size), (size, -size), (size, size), (-size, size)],
+        "triangle" => vec![(0, -size), (half, size / 2), (-half, size / 2)],
+        "diamond" => vec![(0, -size), (size, 0), (0, size), (-size, 0)],
+        _ => return None,
+    };
+    if closed {
+        vertices.push(vertices[0]);
+    }
+    Some(vertices)
+}
+
+/// Dash pattern (alternating on/off lengths in pixels) for a ggplot2 linetype name.
+/// Lengths are in units of the line width; an empty pattern means a solid line.
+fn dash_pattern(linetype: Option<&str>, stroke_width: u32) -> Vec<f32> {
+    let units: &[f32] = match linetype {
+        Some("dashed") => &[4.0, 4.0],
+        Some("dotted") => &[1.0, 3.0],
+        Some("dotdash") => &[1.0, 3.0, 4.0, 3.0],
+        Some("longdash") => &[7.0, 3.0],
+        Some("twodash") => &[2.0, 2.0, 6.0, 2.0],
+        _ => &[],
+    };
+    let unit = stroke_width.max(1) as f32;
+    units.iter().map(|u| u * unit).collect()
+}
+
+/// A path drawn with a repeating dash pattern (solid when the pattern is empty).
+/// Like plotters' `DashedPathElement`, but with multi-segment patterns such as twodash.
+struct PatternPathElement<Coord> {
+    points: Vec<Coord>,
+    pattern: Vec<f32>,
+    style: ShapeStyle,
+}
+
+impl<Coord> PatternPathElement<Coord> {
+    fn new<S: Into<ShapeStyle>>(points: Vec<Coord>, pattern: Vec<f32>, style: S) -> Self {
+        Self { points, pattern, style: style.into() }
+    }
+}
+
+impl<'a, Coord> PointCollection<'a, Coord> for &'a PatternPathElement<Coord> {
+    type Point = &'a Coord;
+    type IntoIter = &'a [Coord];
+    fn point_iter(self) -> &'a [Coord] {
+        &self.points
+    }
+}
+
+impl<Coord, DB: DrawingBackend> Drawable<DB> for PatternPathElement<Coord> {
+    fn draw<I: Iterator<Item = BackendCoord>>(
+        &self,
+        points: I,
+        backend: &mut DB,
+        _: (u32, u32),
+    ) -> Result<(), DrawingErrorKind<DB::ErrorType>> {
+        let points: Vec<(f32, f32)> = points.map(|(x, y)| (x as f32, y as f32)).collect();
+        let to_i = |(x, y): (f32, f32)| (x.round() as i32, y.round() as i32);
+        if self.pattern.iter().sum::<f32>() <= 0.0 {
+            return backend.draw_path(points.into_iter().map(to_i), &self.style);
         }
 
+        // Walk the path, toggling between "on" and "off" segments of the pattern
+        let mut segment = 0;
+        let mut left = self.pattern[0];
+        let mut dash = points.first().map(|&p| vec![to_i(p)]).unwrap_or_default();
+        for pair in points.windows(2) {
+            let (mut start, end) = (pair[0], pair[1]);
+            loop {
+                let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+                let d = dx.hypot(dy);
+                if d <= left {
+                    left -= d;
+                    if segment % 2 == 0 {
+                        dash.push(to_i(end));
+                    }
+                    break;
+                }
+                let t = left / d;
+                start = (start.0 + dx * t, start.1 + dy * t);
+                if segment % 2 == 0 {
+                    dash.push(to_i(start));
+                    backend.draw_path(dash.drain(..), &self.style)?;
+                } else {
+                    dash.push(to_i(start));
+                }
+                segment = (segment + 1) % self.pattern.len();
+                left = self.pattern[segment];
+            }
+        }
+        if segment % 2 == 0 && dash.len() > 1 {
+            backend.draw_path(dash, &self.style)?;
+        }
         Ok(())
     }
 }
@@ -366,3 +1519,254 @@ fn parse_color(color_str: &Option<String>, default_color: RGBColor) -> RGBColor
         None => default_color,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ImageEncoder;
+
+    #[test]
+    fn test_axis_coord_log_scale() {
+        let scale = Scale {
+            domain: (1.0, 1000.0), range: (1.0, 1000.0), is_categorical: false, is_log: true, categories: vec![], category_positions: vec![],
+            is_percent: false, breaks_n: None, breaks_width: None, labels: TickFormat::Auto,
+        };
+        let axis = AxisCoord::new(&scale);
+        // Equal ratios get equal distances
+        assert_eq!(axis.map(&1.0, (0, 300)), 0);
+        assert_eq!(axis.map(&10.0, (0, 300)), 100);
+        assert_eq!(axis.map(&100.0, (0, 300)), 200);
+        // Zero (a bar baseline) is pushed below the axis instead of producing -inf
+        assert!(axis.map(&0.0, (0, 300)) < 0);
+        assert_eq!(axis.key_points(plotters::coord::ranged1d::BoldPoints(10)), vec![1.0, 10.0, 100.0, 1000.0]);
+        assert_eq!(tick_format(&scale), Some(TickFormat::Comma));
+    }
+
+    #[test]
+    fn test_format_number_with_commas() {
+        assert_eq!(format_number_with_commas(0.0, 0), "0");
+        assert_eq!(format_number_with_commas(999.0, 0), "999");
+        assert_eq!(format_number_with_commas(10000.0, 0), "10,000");
+        assert_eq!(format_number_with_commas(1234567.25, 2), "1,234,567.25");
+        assert_eq!(format_number_with_commas(-200000.0, 0), "-200,000");
+    }
+
+    #[test]
+    fn test_format_tick() {
+        assert_eq!(format_tick(50000.0, &TickFormat::Comma), "50,000");
+        assert_eq!(format_tick(2.5, &TickFormat::Comma), "2.5");
+        assert_eq!(format_tick(12500.0, &TickFormat::Dollar), "$12,500");
+        assert_eq!(format_tick(-5.0, &TickFormat::Dollar), "-$5");
+        assert_eq!(format_tick(25000.0, &TickFormat::Scientific), "2.5e4");
+        assert_eq!(format_tick(0.25, &TickFormat::Percent), "25%");
+        assert_eq!(format_tick(12.345, &TickFormat::Custom("%.1f%%".to_string())), "12.3%");
+        assert_eq!(format_tick(7.6, &TickFormat::Custom("%d units".to_string())), "8 units");
+        assert_eq!(format_tick(19797.0, &TickFormat::Date("%b %d, %Y".to_string())), "Mar 15, 2024");
+    }
+
+    fn encode_png(image: &image::RgbaImage) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut bytes)
+            .write_image(image.as_raw(), image.width(), image.height(), image::ColorType::Rgba8)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_add_watermark_image_blends_into_corner() {
+        // Left half opaque red, right half fully transparent
+        let logo = image::RgbaImage::from_fn(4, 2, |x, _| if x < 2 { image::Rgba([255, 0, 0, 255]) } else { image::Rgba([0, 0, 255, 0]) });
+        let bytes = encode_png(&logo);
+
+        let mut target = image::RgbImage::from_pixel(100, 50, image::Rgb([255, 255, 255]));
+        Canvas::add_watermark_image(&mut target, &bytes, WatermarkPosition::BottomRight, 8, 0, 1.0).unwrap();
+        // Scaled to 8x4, placed 10px in from the bottom-right corner
+        assert_eq!(*target.get_pixel(82, 36), image::Rgb([255, 0, 0]));
+        assert_eq!(*target.get_pixel(89, 39), image::Rgb([255, 255, 255]));
+        assert_eq!(*target.get_pixel(81, 36), image::Rgb([255, 255, 255]));
+
+        let mut target = image::RgbImage::from_pixel(100, 50, image::Rgb([255, 255, 255]));
+        Canvas::add_watermark_image(&mut target, &bytes, WatermarkPosition::TopLeft, 8, 0, 1.0).unwrap();
+        assert_eq!(*target.get_pixel(10, 10), image::Rgb([255, 0, 0]));
+
+        assert!(Canvas::add_watermark_image(&mut target, b"not an image", WatermarkPosition::TopLeft, 8, 0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_layers_share_one_chart_per_panel() {
+        // Each panel builds its chart once and replays every layer's commands onto it, so a
+        // later layer never repaints the background over an earlier one
+        let axis = Scale {
+            domain: (0.0, 10.0), range: (0.0, 10.0), is_categorical: false, is_log: false, categories: vec![], category_positions: vec![],
+            is_percent: false, breaks_n: None, breaks_width: None, labels: TickFormat::Auto,
+        };
+        let panel = PanelScene {
+            row: 0, col: 0, title: None, strip_position: StripPosition::Top,
+            x_label: None, y_label: None, legend_title: None,
+            x_scale: axis.clone(), y_scale: axis,
+            commands: vec![
+                DrawCommand::DrawLine {
+                    points: vec![(1.0, 2.0), (9.0, 2.0)],
+                    style: LineStyle { color: Some("red".to_string()), width: Some(6.0), alpha: None, linetype: None },
+                    legend: None,
+                },
+                DrawCommand::DrawPoint {
+                    points: vec![(5.0, 8.0)],
+                    style: PointStyle { color: Some("blue".to_string()), size: Some(10.0), shape: None, alpha: None, fill: None },
+                    legend: None,
+                },
+            ],
+            legend_items: vec![],
+        };
+        let scene = SceneGraph {
+            width: 200, height: 150, panels: vec![panel],
+            labels: Default::default(), theme: Default::default(),
+            legend: vec![], legend_title: None, colorbar: None, row_heights: vec![],
+        };
+
+        let png = Canvas::execute(scene, &RenderOptions { width: 200, height: 150, ..RenderOptions::default() }).unwrap();
+        let image = image::load_from_memory(&png).unwrap().to_rgb8();
+        assert!(image.pixels().any(|p| p.0 == [255, 0, 0]));
+        assert!(image.pixels().any(|p| p.0 == [0, 0, 255]));
+    }
+
+    #[test]
+    fn test_legend_key_size_scales_keys() {
+        let axis = Scale {
+            domain: (0.0, 10.0), range: (0.0, 10.0), is_categorical: false, is_log: false, categories: vec![], category_positions: vec![],
+            is_percent: false, breaks_n: None, breaks_width: None, labels: TickFormat::Auto,
+        };
+        let commands = vec![
+            DrawCommand::DrawLine {
+                points: vec![(1.0, 1.0), (2.0, 1.0)],
+                style: LineStyle { color: Some("blue".to_string()), width: Some(2.0), alpha: None, linetype: None },
+                legend: None,
+            },
+            DrawCommand::DrawPoint {
+                points: vec![(1.0, 1.0)],
+                style: PointStyle { color: Some("red".to_string()), size: Some(3.0), shape: None, alpha: None, fill: None },
+                legend: Some("A".to_string()),
+            },
+        ];
+        let entries = panel_legend_entries(&commands);
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(entries[0].style, RenderStyle::Point(_)));
+
+        let red_pixels = |legend_key_size| {
+            let panel = PanelScene {
+                row: 0, col: 0, title: None, strip_position: StripPosition::Top,
+                x_label: None, y_label: None, legend_title: None,
+                x_scale: axis.clone(), y_scale: axis.clone(),
+                commands: commands.clone(),
+                legend_items: vec![],
+            };
+            let scene = SceneGraph {
+                width: 300, height: 200, panels: vec![panel],
+                labels: Default::default(), theme: crate::parser::ast::Theme { legend_key_size, ..Default::default() },
+                legend: vec![], legend_title: None, colorbar: None, row_heights: vec![],
+            };
+            let png = Canvas::execute(scene, &RenderOptions { width: 300, height: 200, ..RenderOptions::default() }).unwrap();
+            image::load_from_memory(&png).unwrap().to_rgb8().pixels().filter(|p| p.0 == [255, 0, 0]).count()
+        };
+        // The data point stays the same size; only the legend key grows
+        assert!(red_pixels(Some(2.0)) > red_pixels(None));
+    }
+
+    #[test]
+    fn test_legend_items_draw_without_commands() {
+        let axis = Scale {
+            domain: (0.0, 10.0), range: (0.0, 10.0), is_categorical: false, is_log: false, categories: vec![], category_positions: vec![],
+            is_percent: false, breaks_n: None, breaks_width: None, labels: TickFormat::Auto,
+        };
+        let green_pixels = |legend_items: Vec<LegendEntry>| {
+            let panel = PanelScene {
+                row: 0, col: 0, title: None, strip_position: StripPosition::Top,
+                x_label: None, y_label: None, legend_title: None,
+                x_scale: axis.clone(), y_scale: axis.clone(),
+                commands: vec![],
+                legend_items,
+            };
+            let scene = SceneGraph {
+                width: 300, height: 200, panels: vec![panel],
+                labels: Default::default(), theme: Default::default(),
+                legend: vec![], legend_title: None, colorbar: None, row_heights: vec![],
+            };
+            let png = Canvas::execute(scene, &RenderOptions { width: 300, height: 200, ..RenderOptions::default() }).unwrap();
+            image::load_from_memory(&png).unwrap().to_rgb8().pixels().filter(|p| p.0 == [0, 255, 0]).count()
+        };
+        let target = LegendEntry {
+            label: "Target".to_string(),
+            style: RenderStyle::Line(LineStyle { color: Some("#00ff00".to_string()), width: Some(4.0), ..Default::default() }),
+        };
+        assert_eq!(green_pixels(vec![]), 0);
+        assert!(green_pixels(vec![target]) > 0);
+    }
+
+    #[test]
+    fn test_label_angles_between_quarter_turns() {
+        assert!(matches!(label_rotation(90.0), Some(LabelRotation::Quarter(FontTransform::Rotate270))));
+        assert!(matches!(label_rotation(-90.0), Some(LabelRotation::Quarter(FontTransform::Rotate90))));
+        assert!(matches!(label_rotation(45.0), Some(LabelRotation::Angle(a)) if (a - std::f64::consts::FRAC_PI_4).abs() < 1e-12));
+        assert!(label_rotation(0.0).is_none() && label_rotation(360.0).is_none());
+
+        let render = |angle: f64| {
+            let x_scale = Scale {
+                domain: (0.0, 2.0), range: (-0.5, 1.5), is_categorical: true, is_log: false,
+                categories: vec!["first category".to_string(), "second".to_string()], category_positions: vec![],
+                is_percent: false, breaks_n: None, breaks_width: None, labels: TickFormat::Auto,
+            };
+            let y_scale = Scale { is_categorical: false, categories: vec![], domain: (0.0, 10.0), range: (0.0, 10.0), ..x_scale.clone() };
+            let panel = PanelScene {
+                row: 0, col: 0, title: None, strip_position: StripPosition::Top,
+                x_label: None, y_label: None, legend_title: None,
+                x_scale, y_scale, commands: vec![], legend_items: vec![],
+            };
+            let axis_text = crate::parser::ast::ThemeElement::Text(crate::parser::ast::ElementText { angle: Some(angle), ..Default::default() });
+            let scene = SceneGraph {
+                width: 300, height: 200, panels: vec![panel],
+                labels: Default::default(), theme: crate::parser::ast::Theme { axis_text, ..Default::default() },
+                legend: vec![], legend_title: None, colorbar: None, row_heights: vec![],
+            };
+            let png = Canvas::execute(scene, &RenderOptions { width: 300, height: 200, ..RenderOptions::default() }).unwrap();
+            image::load_from_memory(&png).unwrap().to_rgb8()
+        };
+        // Columns holding label ink below the axis: a 45 degree label spreads sideways, a vertical one does not
+        let label_columns = |image: &image::RgbImage| {
+            (0..image.width()).filter(|&x| (135..image.height()).any(|y| image.get_pixel(x, y).0[0] < 128)).count()
+        };
+        let (diagonal, vertical) = (render(45.0), render(90.0));
+        assert_ne!(diagonal, vertical);
+        assert!(label_columns(&diagonal) > 2 * label_columns(&vertical), "{} vs {}", label_columns(&diagonal), label_columns(&vertical));
+    }
+
+    #[test]
+    fn test_add_watermark_text_stays_in_bottom_right() {
+        let mut target = image::RgbImage::from_pixel(200, 100, image::Rgb([255, 255, 255]));
+        Canvas::add_watermark(&mut target, "draft", WATERMARK_ALPHA, RGBColor(0, 0, 0), 1.0).unwrap();
+        let changed: Vec<(u32, u32)> = target.enumerate_pixels()
+            .filter(|(_, _, p)| p.0 != [255, 255, 255])
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert!(!changed.is_empty());
+        assert!(changed.iter().all(|&(x, y)| (100..190).contains(&x) && (80..95).contains(&y)), "{:?}", changed);
+        // Half-transparent: no pixel is fully black
+        assert!(target.pixels().all(|p| p.0[0] > 100));
+    }
+
+    #[test]
+    fn test_point_styles_open_and_fill() {
+        let style = |shape: &str, fill: Option<&str>| {
+            let style = PointStyle { color: Some("black".to_string()), shape: Some(shape.to_string()), fill: fill.map(String::from), ..Default::default() };
+            let (fill, border) = point_styles(&style);
+            (fill.map(|s| (s.color.rgb(), s.filled)), border.map(|s| (s.color.rgb(), s.filled)))
+        };
+        // Solid by default, outline only when open, a fill inside the outline when given
+        assert_eq!(style("circle", None), (Some(((0, 0, 0), true)), None));
+        assert_eq!(style("circle_open", None), (None, Some(((0, 0, 0), false))));
+        assert_eq!(style("circle_open", Some("white")), (Some(((255, 255, 255), true)), Some(((0, 0, 0), false))));
+
+        assert_eq!(marker_vertices("circle_open", 4, true), None);
+        assert_eq!(marker_vertices("diamond_open", 4, true), Some(vec![(0, -4), (4, 0), (0, 4), (-4, 0), (0, -4)]));
+        assert_eq!(marker_vertices("square", 2, false).map(|v| v.len()), Some(4));
+    }
+}
@@ -1,12 +1,13 @@
 use crate::parser::ast::Layer;
-use crate::graph::{LineStyle, PointStyle, BarStyle, RibbonStyle, BoxplotStyle, ViolinStyle};
+use crate::graph::{LineStyle, PointStyle, BarStyle, RibbonStyle, BoxplotStyle, ViolinStyle, TileStyle, HexStyle, TextGeomStyle};
+use serde::Serialize;
 
 // =============================================================================
 // Phase 1: Resolution
 // =============================================================================
 
 /// Result of resolving aesthetics against the CSV headers (but not data values yet)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ResolvedSpec {
     pub layers: Vec<ResolvedLayer>,
     pub facet: Option<ResolvedFacet>,
@@ -15,33 +16,47 @@ pub struct ResolvedSpec {
     pub theme: crate::parser::ast::Theme,
     pub x_scale_spec: Option<crate::parser::ast::AxisScale>,
     pub y_scale_spec: Option<crate::parser::ast::AxisScale>,
+    pub color_scale: Option<crate::parser::ast::ColorScale>,
+    pub linetype_values: Option<Vec<String>>,
+    pub fill_scale: Option<crate::parser::ast::FillScale>,
+    pub identity_scales: crate::parser::ast::IdentityScales,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ResolvedLayer {
     pub original_layer: Layer,
+    // Takes the bins of an earlier histogram(stat_output_as: "binned") instead of the raw rows
+    pub inherits_binned: bool,
     pub aesthetics: ResolvedAesthetics,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ResolvedAesthetics {
     pub x_col: String,
     pub y_col: Option<String>,
     pub ymin_col: Option<String>,
     pub ymax_col: Option<String>,
+    pub xmin_col: Option<String>, // Horizontal pointrange()/crossbar() range along x
+    pub xmax_col: Option<String>,
     // Optional grouping columns
     pub color: Option<String>,
     pub size: Option<String>,
     pub shape: Option<String>,
     pub alpha: Option<String>,
+    pub linetype: Option<String>,
+    pub fill: Option<String>, // Numeric fill column (tiles), mapped through the fill scale
+    pub label: Option<String>, // Label text column (text)
+    pub colors: Option<String>, // Column of literal colors, one per bar (bar(fill: col) of names or hex values)
     // Fixed values (if not mapped) can be stored here or retrieved from Layer
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ResolvedFacet {
     pub col: String,
     pub ncol: Option<usize>,
     pub scales: crate::parser::ast::FacetScales,
+    pub strip_position: crate::parser::ast::StripPosition,
+    pub space: crate::parser::ast::FacetSpace,
 }
 
 // =============================================================================
@@ -50,13 +65,14 @@ pub struct ResolvedFacet {
 
 /// The normalized data ready for scaling and rendering.
 /// It is split into "Panels" (for faceting). If no faceting, there is 1 panel.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RenderData {
     pub panels: Vec<PanelData>,
     pub facet_layout: FacetLayout,
+    pub colorbar: Option<ColorBar>, // Legend for the continuous fill scale (tiles, hexbins)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct FacetLayout {
     pub nrow: usize,
     pub ncol: usize,
@@ -64,7 +80,7 @@ pub struct FacetLayout {
 }
 
 /// Data for a single plot panel (one facet)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PanelData {
     pub index: usize,
     pub layers: Vec<LayerData>, // Corresponds 1:1 with ResolvedSpec.layers
@@ -72,13 +88,13 @@ pub struct PanelData {
 
 /// Data for a single layer within a panel.
 /// Contains one or more "Groups" (e.g. different colored lines).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct LayerData {
     pub groups: Vec<GroupData>,
 }
 
 /// The atomic unit of rendering: a set of points sharing the same visual style.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct GroupData {
     pub key: String, // Legend key (e.g. "Region A")
     
@@ -93,26 +109,61 @@ pub struct GroupData {
     // New fields for ribbons/error bars calculated by stats
     pub y_min: Vec<f64>,
     pub y_max: Vec<f64>,
+    // Horizontal ranges along x (pointrange(xmin:, xmax:)); empty otherwise
+    pub x_min: Vec<f64>,
+    pub x_max: Vec<f64>,
     
     // Boxplot statistics
     pub y_q1: Vec<f64>,
     pub y_median: Vec<f64>,
     pub y_q3: Vec<f64>,
+    pub y_notch_low: Vec<f64>,  // Median confidence interval, for boxplot(notch: true)
+    pub y_notch_high: Vec<f64>,
     pub outliers: Vec<Vec<f64>>,
+    pub counts: Vec<usize>,  // Observations per x category (for varwidth) or per hexbin cell
+    pub cell_values: Vec<f64>, // hexbin(fill: ...) summary per cell, mapped to `fills`; empty otherwise
 
     // Violin statistics (KDE density curves)
     pub violin_density: Vec<Vec<f64>>,          // Normalized density values (0-1) per x category
     pub violin_density_y: Vec<Vec<f64>>,        // Y coordinates for density curve per x category
     pub violin_quantile_values: Vec<Vec<f64>>,  // Computed Y values at requested quantiles per x category
 
+    // Per-point fill colors from the continuous fill scale (tiles; empty otherwise)
+    pub fills: Vec<String>,
+
+    // (x, observed - fitted) per input point for smooth(residuals: true); empty otherwise
+    pub residuals: Vec<(f64, f64)>,
+
+    // (density level, path) per contour() line, or per band polygon with filled: true; empty otherwise
+    pub contours: Vec<ContourPath>,
+
+    // Per-bar widths in x units from bar(width: column) (mosaic layout); empty = style width ratio
+    pub widths: Vec<f64>,
+
+    // Label text per point (text); empty otherwise
+    pub labels: Vec<String>,
+
+    // (slope, intercept) per abline() line, drawn across the x scale; empty otherwise
+    pub ablines: Vec<(f64, f64)>,
+
+    // (xend, yend) per segment() line, which starts at the matching (x, y); empty otherwise
+    pub segment_ends: Vec<(f64, f64)>,
+
     // Original category names for x-axis (if categorical)
     pub x_categories: Option<Vec<String>>, 
+    // X position of each category (mosaic bars); empty = the category index
+    pub x_category_positions: Vec<f64>,
+    // Category names for the y axis when the layer's y column is not numeric (points), y = index
+    pub y_categories: Option<Vec<String>>,
     
     // Resolved Visual Style for this group
     pub style: RenderStyle,
 }
 
-#[derive(Debug, Clone)]
+/// (density level, points) of one contour() line or filled band polygon
+pub type ContourPath = (f64, Vec<(f64, f64)>);
+
+#[derive(Debug, Clone, Serialize)]
 pub enum RenderStyle {
     Line(LineStyle),
     Point(PointStyle),
@@ -120,6 +171,9 @@ pub enum RenderStyle {
     Ribbon(RibbonStyle),
     Boxplot(BoxplotStyle),
     Violin(ViolinStyle),
+    Tile(TileStyle),
+    Hex(HexStyle),
+    Text(TextGeomStyle),
 }
 
 // =============================================================================
@@ -127,24 +181,30 @@ pub enum RenderStyle {
 // =============================================================================
 
 /// Holds the scales for the entire plot (potentially multiple panels)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ScaleSystem {
     // One scale pair per panel
     pub panels: Vec<PanelScales>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PanelScales {
     pub x: Scale,
     pub y: Scale,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Scale {
     pub domain: (f64, f64), // Data min/max
     pub range: (f64, f64),  // Pixel/Coordinate min/max
     pub is_categorical: bool,
+    pub is_log: bool,            // log10 axis (scale_*_log10); domain and range stay in data units
     pub categories: Vec<String>, // If categorical, maps index -> label
+    pub category_positions: Vec<f64>, // Axis position per category (mosaic bars); empty = the index
+    pub is_percent: bool,        // Tick labels shown as percentages (proportion stats)
+    pub breaks_n: Option<usize>,     // Tick count hint for continuous axes
+    pub breaks_width: Option<f64>,   // Tick spacing for continuous axes
+    pub labels: crate::parser::ast::TickFormat, // Tick label format for continuous axes
 }
 
 // =============================================================================
@@ -153,28 +213,53 @@ pub struct Scale {
 
 /// A list of primitive drawing commands.
 /// The Backend just executes these blindly.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SceneGraph {
     pub width: u32,
     pub height: u32,
     pub panels: Vec<PanelScene>,
     pub labels: crate::parser::ast::Labels,
     pub theme: crate::parser::ast::Theme,
+    // Shared legend for faceted plots (empty = each panel draws its own)
+    pub legend: Vec<LegendEntry>,
+    pub legend_title: Option<String>,
+    pub colorbar: Option<ColorBar>,
+    // Relative height of each panel row (facet_wrap(space: "free_y")); empty = equal rows
+    pub row_heights: Vec<f64>,
 }
 
-#[derive(Debug, Clone)]
+/// Continuous fill legend: a gradient bar with tick labels
+#[derive(Debug, Clone, Serialize)]
+pub struct ColorBar {
+    pub title: Option<String>,
+    pub colors: Vec<String>,       // Evenly spaced samples of the gradient, low to high
+    pub ticks: Vec<(f64, String)>, // (position 0-1 along the bar, label in data units)
+}
+
+/// One entry of the shared legend drawn outside the panel grid
+#[derive(Debug, Clone, Serialize)]
+pub struct LegendEntry {
+    pub label: String,
+    pub style: RenderStyle,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct PanelScene {
     pub row: usize,
     pub col: usize,
     pub title: Option<String>,
+    pub strip_position: crate::parser::ast::StripPosition, // Panel edge the title strip sits on
     pub x_label: Option<String>,
     pub y_label: Option<String>,
+    pub legend_title: Option<String>,
     pub x_scale: Scale, // For drawing axes
     pub y_scale: Scale,
     pub commands: Vec<DrawCommand>,
+    // Legend entries not tied to a draw command (abline(legend: ...), censored shapes), listed after the commands' own
+    pub legend_items: Vec<LegendEntry>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum DrawCommand {
     DrawLine {
         points: Vec<(f64, f64)>,
@@ -198,4 +283,10 @@ pub enum DrawCommand {
         style: RibbonStyle,
         legend: Option<String>,
     },
+    DrawText {
+        pos: (f64, f64), // Center of the text
+        text: String,
+        style: TextGeomStyle,
+        legend: Option<String>,
+    },
 }
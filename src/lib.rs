@@ -1,11 +1,16 @@
 // Library exports for gramgraph
 
 pub mod csv_reader;
+#[cfg(feature = "parquet")]
+pub mod parquet_reader;
+#[cfg(feature = "cache")]
+pub mod cache;
 pub mod graph;
 pub mod palette;
 pub mod parser;
 pub mod runtime;
 pub mod data;
+pub mod expr;
 
 // New Architecture Modules
 pub mod ir;
@@ -15,10 +20,12 @@ pub mod scale;
 pub mod compiler;
 pub mod theme_resolve;
 pub mod preprocessor;
+pub mod reshape;
+pub mod validate;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub enum OutputFormat {
     #[serde(rename = "png")]
     #[default]
@@ -27,25 +34,90 @@ pub enum OutputFormat {
     Svg,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// Corner of the output a watermark image is placed in
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum WatermarkPosition {
+    #[serde(rename = "top-left")]
+    TopLeft,
+    #[serde(rename = "top-right")]
+    TopRight,
+    #[serde(rename = "bottom-left")]
+    BottomLeft,
+    #[serde(rename = "bottom-right")]
+    #[default]
+    BottomRight,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RenderOptions {
     #[serde(default = "default_width")]
     pub width: u32,
     #[serde(default = "default_height")]
     pub height: u32,
+    /// Device pixel ratio: PNGs are rasterized at `scale` x width/height physical pixels (PNG only)
+    #[serde(default = "default_scale")]
+    pub scale: f64,
     #[serde(default, rename = "type")]
     pub format: OutputFormat,
+    /// Directory that join(file: ...) paths are relative to (default: current directory)
+    #[serde(default)]
+    pub data_dir: Option<std::path::PathBuf>,
+    /// Debugging: print the parsed PlotSpec, RenderData and SceneGraph as JSON to stderr
+    #[serde(default)]
+    pub dump_ast: bool,
+    #[serde(default)]
+    pub dump_ir: bool,
+    #[serde(default)]
+    pub dump_scene: bool,
+    /// Treat validation warnings as errors
+    #[serde(default)]
+    pub strict: bool,
+    /// Refuse to render when the estimated cost exceeds `cost_threshold`
+    #[serde(default)]
+    pub fast: bool,
+    #[serde(default = "default_cost_threshold")]
+    pub cost_threshold: usize,
+    /// Print each pipeline step ("Computing statistics [1/3]...") to stderr
+    #[serde(default)]
+    pub progress: bool,
+    /// Semi-transparent text stamped in the bottom-right corner (PNG only)
+    #[serde(default)]
+    pub watermark: Option<String>,
+    /// Image (e.g. a PNG logo) composited onto the output (PNG only)
+    #[serde(default)]
+    pub watermark_image: Option<std::path::PathBuf>,
+    #[serde(default)]
+    pub watermark_position: WatermarkPosition,
+    /// Watermark image width in pixels; the height keeps its aspect ratio
+    #[serde(default = "default_watermark_size")]
+    pub watermark_size: u32,
 }
 
 fn default_width() -> u32 { 800 }
 fn default_height() -> u32 { 600 }
+fn default_scale() -> f64 { 1.0 }
+fn default_cost_threshold() -> usize { validate::DEFAULT_COST_THRESHOLD }
+fn default_watermark_size() -> u32 { 64 }
 
 impl Default for RenderOptions {
     fn default() -> Self {
         Self {
             width: 800,
             height: 600,
+            scale: 1.0,
             format: OutputFormat::Png,
+            data_dir: None,
+            dump_ast: false,
+            dump_ir: false,
+            dump_scene: false,
+            strict: false,
+            fast: false,
+            cost_threshold: validate::DEFAULT_COST_THRESHOLD,
+            progress: false,
+            watermark: None,
+            watermark_image: None,
+            watermark_position: WatermarkPosition::BottomRight,
+            watermark_size: default_watermark_size(),
         }
     }
 }
\ No newline at end of file
@@ -1,33 +1,175 @@
-use gramgraph::{csv_reader, parser, runtime, RenderOptions, OutputFormat, data::PlotData};
+use gramgraph::{csv_reader, parser, runtime, RenderOptions, OutputFormat, WatermarkPosition, data::PlotData};
+use gramgraph::parser::ast::{ElementText, Theme, ThemeElement};
 
 use anyhow::{anyhow, Context, Result};
 use clap::{Parser, ValueEnum};
 use csv::ReaderBuilder;
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::io::{self, Read, Write};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "gramgraph")]
 #[command(about = "Generate graphs from CSV data using GramGraph DSL", long_about = None)]
 struct Args {
     /// GramGraph DSL string (e.g., 'chart(x: time, y: temp) | layer_line(color: "red")')
-    dsl: String,
+    #[arg(required_unless_present_any = ["list_geoms", "list_stats", "list_themes"])]
+    dsl: Option<String>,
 
-    /// Output width in pixels
-    #[arg(long, default_value_t = 800)]
-    width: u32,
+    /// Output width in pixels [default: 800]
+    #[arg(long)]
+    width: Option<u32>,
 
-    /// Output height in pixels
-    #[arg(long, default_value_t = 600)]
-    height: u32,
+    /// Output height in pixels [default: 600]
+    #[arg(long)]
+    height: Option<u32>,
 
-    /// Output format (png, svg)
-    #[arg(long, value_enum, default_value_t = FormatArg::Png)]
-    format: FormatArg,
+    /// Pixel density multiplier for high-DPI PNGs, e.g. 2 for retina [default: 1]
+    #[arg(long)]
+    scale: Option<f64>,
+
+    /// Output format (png, svg) [default: png]; comma-separated to encode one render in several, e.g. png,svg
+    #[arg(long = "format", value_enum, value_delimiter = ',')]
+    formats: Vec<FormatArg>,
+
+    /// Write the plot to this file instead of stdout; with several formats, a path prefix
+    /// (--output chart writes chart.png and chart.svg)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Default theme applied to the plot (plot-level theme() calls are layered on top)
+    #[arg(long, value_enum)]
+    theme: Option<ThemeArg>,
+
+    /// Font family for all text (shorthand for theme(text: element_text(family: ...)))
+    #[arg(long)]
+    font: Option<String>,
+
+    /// Input data format
+    #[arg(long, value_enum, default_value_t = InputFormatArg::Csv)]
+    input_format: InputFormatArg,
+
+    /// Read input data from this file instead of stdin (required for parquet)
+    #[arg(long)]
+    input: Option<std::path::PathBuf>,
+
+    /// Directory that join(file: ...) paths are resolved against (default: current directory)
+    #[arg(long)]
+    data_dir: Option<std::path::PathBuf>,
 
     /// Define variables for DSL substitution (e.g., -D x=time -D color=red)
     #[arg(short = 'D', long = "define", value_parser = parse_key_val)]
     defines: Vec<(String, String)>,
+
+    /// Don't load .gramgraph.toml or ~/.config/gramgraph/config.toml
+    #[arg(long)]
+    no_config: bool,
+
+    /// Treat validation warnings (small samples, overlapping bars, ...) as errors
+    #[arg(long)]
+    strict: bool,
+
+    /// Refuse to render plots whose estimated cost exceeds --cost-threshold
+    #[arg(long)]
+    fast: bool,
+
+    /// Rows x groups budget for quadratic stats such as loess [default: 10000]
+    #[arg(long)]
+    cost_threshold: Option<usize>,
+
+    /// Print rendering progress (partitioning, per-panel stats, compilation and drawing) to stderr
+    #[arg(long)]
+    progress: bool,
+
+    /// Stamp this text, semi-transparent, in the bottom-right corner (PNG only)
+    #[arg(long)]
+    watermark: Option<String>,
+
+    /// Composite this image (e.g. a PNG logo) onto the plot (PNG only)
+    #[arg(long)]
+    watermark_image: Option<PathBuf>,
+
+    /// Corner for --watermark-image [default: bottom-right]
+    #[arg(long, value_enum)]
+    watermark_position: Option<WatermarkPositionArg>,
+
+    /// Width of --watermark-image in pixels, keeping its aspect ratio [default: 64]
+    #[arg(long)]
+    watermark_size: Option<u32>,
+
+    /// Print the parsed PlotSpec as JSON to stderr
+    #[arg(long)]
+    dump_ast: bool,
+
+    /// Print the transformed RenderData as JSON to stderr
+    #[arg(long)]
+    dump_ir: bool,
+
+    /// Print the compiled SceneGraph as JSON to stderr
+    #[arg(long)]
+    dump_scene: bool,
+
+    /// Print the available geometries and exit
+    #[arg(long)]
+    list_geoms: bool,
+
+    /// Print the available statistical transformations and exit
+    #[arg(long)]
+    list_stats: bool,
+
+    /// Print the built-in theme presets and exit
+    #[arg(long)]
+    list_themes: bool,
+}
+
+/// Human-readable geom listing: `line(x, y) [color, width, alpha, linetype]` plus parameter notes
+fn format_geom_list() -> String {
+    let mut out = String::new();
+    for geom in parser::geom::GEOMS {
+        let optional: Vec<&str> = geom.optional.iter().map(|(name, _)| *name).collect();
+        out.push_str(&format!("{}({}) [{}]\n", geom.name, geom.required.join(", "), optional.join(", ")));
+        for (name, description) in geom.optional {
+            out.push_str(&format!("    {:<16}{}\n", name, description));
+        }
+    }
+    out
+}
+
+/// Two-column listing of names and descriptions
+fn format_name_list(entries: &[(&str, &str)]) -> String {
+    entries.iter().map(|(name, description)| format!("{:<18}{}\n", name, description)).collect()
+}
+
+/// Settings loaded from a config file; CLI flags take precedence
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Config {
+    render: RenderOptions,
+    defaults: HashMap<String, String>, // Default -D variable values
+    theme: String,                     // Default theme name ("minimal", "classic")
+}
+
+/// Candidate config files, in priority order
+fn config_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(".gramgraph.toml")];
+    if let Some(home) = std::env::var_os("HOME") {
+        paths.push(PathBuf::from(home).join(".config/gramgraph/config.toml"));
+    }
+    paths
+}
+
+/// Load the first config file that exists (or defaults when there is none)
+fn load_config() -> Result<Config> {
+    for path in config_paths() {
+        if path.is_file() {
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read config file {}", path.display()))?;
+            return toml::from_str(&text)
+                .with_context(|| format!("Invalid config file {}", path.display()));
+        }
+    }
+    Ok(Config::default())
 }
 
 /// Helper parser for key=value pairs
@@ -53,6 +195,85 @@ impl From<FormatArg> for OutputFormat {
     }
 }
 
+/// File extension of an output format
+fn format_extension(format: &OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Png => "png",
+        OutputFormat::Svg => "svg",
+    }
+}
+
+/// Files `--output` writes: the path itself for a single format, or the path as a prefix
+/// with each format's extension appended (chart -> chart.png, chart.svg)
+fn output_paths(output: &std::path::Path, formats: &[OutputFormat]) -> Vec<PathBuf> {
+    if formats.len() == 1 {
+        return vec![output.to_path_buf()];
+    }
+    formats.iter()
+        .map(|format| {
+            let mut path = output.as_os_str().to_owned();
+            path.push(".");
+            path.push(format_extension(format));
+            PathBuf::from(path)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum WatermarkPositionArg {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl From<WatermarkPositionArg> for WatermarkPosition {
+    fn from(arg: WatermarkPositionArg) -> Self {
+        match arg {
+            WatermarkPositionArg::TopLeft => WatermarkPosition::TopLeft,
+            WatermarkPositionArg::TopRight => WatermarkPosition::TopRight,
+            WatermarkPositionArg::BottomLeft => WatermarkPosition::BottomLeft,
+            WatermarkPositionArg::BottomRight => WatermarkPosition::BottomRight,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
+enum InputFormatArg {
+    Csv,
+    Parquet,
+}
+
+/// Load input data from `--input` (or stdin for CSV) in the requested format
+fn read_input(format: &InputFormatArg, input: Option<&std::path::Path>) -> Result<PlotData> {
+    match (format, input) {
+        (InputFormatArg::Csv, Some(path)) => Ok(PlotData::from_csv(csv_reader::read_csv_from_path(path)?)),
+        (InputFormatArg::Csv, None) => Ok(PlotData::from_csv(csv_reader::read_csv_from_stdin()?)),
+        (InputFormatArg::Parquet, None) => Err(anyhow!("--input-format parquet requires --input <file>")),
+        #[cfg(feature = "parquet")]
+        (InputFormatArg::Parquet, Some(path)) => Ok(PlotData::from_csv(gramgraph::parquet_reader::read_parquet(path)?)),
+        #[cfg(not(feature = "parquet"))]
+        (InputFormatArg::Parquet, Some(_)) => Err(anyhow!(
+            "Parquet input is not available in this build (rebuild with --features parquet)"
+        )),
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum ThemeArg {
+    Minimal,
+    Classic,
+}
+
+impl From<ThemeArg> for Theme {
+    fn from(arg: ThemeArg) -> Self {
+        match arg {
+            ThemeArg::Minimal => Theme::minimal(),
+            ThemeArg::Classic => Theme::classic(),
+        }
+    }
+}
+
 /// Process DSL and CSV data to generate PNG bytes
 /// This function is extracted for testability
 pub fn process_dsl(
@@ -61,10 +282,11 @@ pub fn process_dsl(
     options: RenderOptions,
     variables: HashMap<String, String>,
 ) -> Result<Vec<u8>> {
-    // 1. Preprocess: Expand variables immediately
-    let expanded_dsl = gramgraph::preprocessor::expand_variables(dsl, &variables)
-        .context("Failed to expand variables")?;
+    process_data(dsl, read_csv(csv_content)?, options, variables)
+}
 
+/// Read CSV text (stdin) into plot data; it must have at least one data row
+fn read_csv(csv_content: impl Read) -> Result<PlotData> {
     // Read CSV
     let mut reader = ReaderBuilder::new().has_headers(true).from_reader(csv_content);
 
@@ -87,44 +309,157 @@ pub fn process_dsl(
     }
 
     let csv_data = csv_reader::CsvData { headers, rows };
-    let plot_data = PlotData::from_csv(csv_data);
+    Ok(PlotData::from_csv(csv_data))
+}
+
+/// Process DSL against already-loaded data (CSV from stdin, or an --input file)
+pub fn process_data(
+    dsl: &str,
+    plot_data: PlotData,
+    options: RenderOptions,
+    variables: HashMap<String, String>,
+) -> Result<Vec<u8>> {
+    let format = options.format.clone();
+    let mut outputs = process_data_formats(dsl, plot_data, options, variables, &[format])?;
+    Ok(outputs.remove(0))
+}
+
+/// Process DSL against already-loaded data, rendering once and encoding each of `formats`
+pub fn process_data_formats(
+    dsl: &str,
+    plot_data: PlotData,
+    options: RenderOptions,
+    variables: HashMap<String, String>,
+    formats: &[OutputFormat],
+) -> Result<Vec<Vec<u8>>> {
+    // 1. Preprocess: Expand variables immediately
+    let expanded_dsl = gramgraph::preprocessor::expand_variables(dsl, &variables)
+        .context("Failed to expand variables")?;
 
     // Parse the DSL string
     let plot_spec = match parser::parse_plot_spec(&expanded_dsl) {
-        Ok((remaining, plot_spec)) => {
-            if !remaining.trim().is_empty() {
-                eprintln!("Warning: unparsed input: '{}'", remaining);
+        Ok((plot_spec, warnings)) => {
+            for warning in warnings {
+                eprintln!("Warning: {}", warning);
             }
             plot_spec
         }
-        Err(e) => {
-            return Err(anyhow!("Parse error: {:?}", e));
+        Err(errors) => {
+            // Report every malformed component at once
+            let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            return Err(anyhow!("Parse error {}", messages.join("\nParse error ")));
         }
     };
+    if options.dump_ast {
+        runtime::dump_json("PlotSpec", &plot_spec)?;
+    }
+
+    let cost = gramgraph::validate::estimate_render_cost(&plot_spec, &plot_data);
+    if cost.exceeds(options.cost_threshold) {
+        let message = format!(
+            "{} rows x {} groups with a quadratic stat (loess/ucv); rendering may take >10 seconds",
+            cost.rows, cost.groups
+        );
+        if options.fast {
+            return Err(anyhow!("Refusing to render (--fast): {}", message));
+        }
+        eprintln!("Warning: {}", message);
+    }
 
     // Render the plot
-    runtime::render_plot(plot_spec, plot_data, options).context("Failed to render plot")
+    runtime::render_plot_formats(plot_spec, plot_data, options, formats).context("Failed to render plot")
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
+    if args.list_geoms || args.list_stats || args.list_themes {
+        if args.list_geoms {
+            print!("{}", format_geom_list());
+        }
+        if args.list_stats {
+            print!("{}", format_name_list(parser::geom::STATS));
+        }
+        if args.list_themes {
+            print!("{}", format_name_list(parser::theme::THEME_PRESETS));
+        }
+        return Ok(());
+    }
+    let dsl = args.dsl.expect("clap requires the DSL unless a --list flag is given");
+
+    let config = if args.no_config { Config::default() } else { load_config()? };
+
+    // --format png,svg: each format once, in the order given
+    let mut formats: Vec<OutputFormat> = Vec::new();
+    for format in args.formats.into_iter().map(OutputFormat::from) {
+        if !formats.iter().any(|f| format_extension(f) == format_extension(&format)) {
+            formats.push(format);
+        }
+    }
+    if formats.is_empty() {
+        formats.push(config.render.format.clone());
+    }
+    if args.output.is_none() && formats.len() > 1 {
+        eprintln!("Warning: several formats need --output; writing only {} to stdout", format_extension(&formats[0]));
+        formats.truncate(1);
+    }
+
     let options = RenderOptions {
-        width: args.width,
-        height: args.height,
-        format: args.format.into(),
+        width: args.width.unwrap_or(config.render.width),
+        height: args.height.unwrap_or(config.render.height),
+        scale: args.scale.unwrap_or(config.render.scale),
+        format: formats[0].clone(),
+        data_dir: args.data_dir.or(config.render.data_dir),
+        dump_ast: args.dump_ast,
+        dump_ir: args.dump_ir,
+        dump_scene: args.dump_scene,
+        strict: args.strict || config.render.strict,
+        fast: args.fast || config.render.fast,
+        progress: args.progress || config.render.progress,
+        cost_threshold: args.cost_threshold.unwrap_or(config.render.cost_threshold),
+        watermark: args.watermark.or(config.render.watermark),
+        watermark_image: args.watermark_image.or(config.render.watermark_image),
+        watermark_position: args.watermark_position.map(Into::into).unwrap_or(config.render.watermark_position),
+        watermark_size: args.watermark_size.unwrap_or(config.render.watermark_size),
     };
 
-    // Convert defines Vec to HashMap
-    let variables: HashMap<String, String> = args.defines.into_iter().collect();
+    // Config defaults first, then -D defines override them
+    let mut variables = config.defaults;
+    variables.extend(args.defines);
 
-    let bytes = process_dsl(&args.dsl, io::stdin(), options, variables)?;
+    let config_theme = match config.theme.as_str() {
+        "" => None,
+        name => Some(ThemeArg::from_str(name, true).map_err(|_| anyhow!("Unknown theme '{}' in config file", name))?),
+    };
+    if let Some(theme) = args.theme.or(config_theme) {
+        gramgraph::theme_resolve::theme_set(theme.into());
+    }
+    if let Some(family) = args.font {
+        gramgraph::theme_resolve::theme_update(Theme {
+            text: ThemeElement::Text(ElementText { family: Some(family), ..Default::default() }),
+            ..Default::default()
+        });
+    }
+
+    let plot_data = match (&args.input_format, &args.input) {
+        (InputFormatArg::Csv, None) => read_csv(io::stdin())?,
+        (format, input) => read_input(format, input.as_deref())?,
+    };
+    let outputs = process_data_formats(&dsl, plot_data, options, variables, &formats)?;
+
+    if let Some(output) = &args.output {
+        for (path, bytes) in output_paths(output, &formats).iter().zip(&outputs) {
+            std::fs::write(path, bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        return Ok(());
+    }
 
     // Write output to stdout
+    let bytes = &outputs[0];
     let stdout = io::stdout();
     let mut handle = stdout.lock();
     handle
-        .write_all(&bytes)
+        .write_all(bytes)
         .context("Failed to write output to stdout")?;
     handle.flush().context("Failed to flush stdout")?;
 
@@ -137,6 +472,82 @@ mod tests {
     use std::collections::HashMap;
     use std::io::Cursor;
 
+    #[test]
+    fn test_format_geom_list() {
+        let listing = format_geom_list();
+        assert!(listing.contains("line(x, y) [color, width, alpha, linetype]\n"));
+        assert!(listing.contains("ribbon(x, ymin, ymax) [color, alpha, border, linetype]\n"));
+        assert!(format_name_list(parser::theme::THEME_PRESETS).contains("theme_void()"));
+    }
+
+    #[test]
+    fn test_config_parsing() {
+        let config: Config = toml::from_str(r#"
+            theme = "minimal"
+
+            [render]
+            width = 1024
+            type = "svg"
+
+            [defaults]
+            color = "red"
+        "#).unwrap();
+        assert_eq!(config.render.width, 1024);
+        assert_eq!(config.render.height, 600);
+        assert!(matches!(config.render.format, OutputFormat::Svg));
+        assert_eq!(config.defaults.get("color").map(String::as_str), Some("red"));
+        assert_eq!(config.theme, "minimal");
+
+        // Missing sections fall back to defaults
+        let empty: Config = toml::from_str("").unwrap();
+        assert_eq!(empty.render.width, 800);
+        assert!(empty.defaults.is_empty());
+        assert!(empty.theme.is_empty());
+    }
+
+    #[test]
+    fn test_read_input_csv_file() {
+        let data = read_input(&InputFormatArg::Csv, Some(std::path::Path::new("fixtures/basic.csv"))).unwrap();
+        assert!(!data.headers.is_empty());
+        assert!(!data.rows.is_empty());
+    }
+
+    #[test]
+    fn test_read_input_parquet_requires_path() {
+        let err = read_input(&InputFormatArg::Parquet, None).unwrap_err();
+        assert!(err.to_string().contains("--input"));
+    }
+
+    #[test]
+    fn test_plot_spec_serializes_to_json() {
+        let (spec, _) = parser::parse_plot_spec(r#"aes(x: x, y: y) | line(color: "red") | smooth(method: "loess")"#).unwrap();
+        let json: serde_json::Value = serde_json::to_value(&spec).unwrap();
+        assert_eq!(json["aesthetics"]["x"], "x");
+        assert_eq!(json["layers"][0]["Line"]["color"]["Fixed"], "red");
+        assert_eq!(json["layers"][1]["Line"]["stat"]["Smooth"]["method"]["Loess"]["span"], 0.75);
+    }
+
+    #[test]
+    fn test_process_dsl_with_dumps() {
+        let options = RenderOptions { dump_ast: true, dump_ir: true, dump_scene: true, ..RenderOptions::default() };
+        let result = process_dsl("aes(x: x, y: y) | line()", Cursor::new("x,y\n1,2\n2,3\n"), options, HashMap::new());
+        assert!(result.is_ok(), "Failed: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_process_dsl_fast_refuses_expensive_render() {
+        let csv: String = std::iter::once("x,y\n".to_string())
+            .chain((0..50).map(|i| format!("{},{}\n", i, i % 7)))
+            .collect();
+        let dsl = r#"aes(x: x, y: y) | smooth(method: "loess")"#;
+        let options = RenderOptions { fast: true, cost_threshold: 10, ..RenderOptions::default() };
+        let err = process_dsl(dsl, Cursor::new(csv.clone()), options, HashMap::new()).unwrap_err();
+        assert!(err.to_string().contains("--fast"));
+
+        let options = RenderOptions { cost_threshold: 10, ..RenderOptions::default() };
+        assert!(process_dsl(dsl, Cursor::new(csv), options, HashMap::new()).is_ok());
+    }
+
     #[test]
     fn test_process_dsl_line_chart() {
         let csv = "x,y\n1,10\n2,20\n3,30\n";
@@ -238,6 +649,25 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_output_paths_prefix_for_several_formats() {
+        let output = std::path::Path::new("out/chart");
+        assert_eq!(output_paths(output, &[OutputFormat::Svg]), vec![PathBuf::from("out/chart")]);
+        assert_eq!(
+            output_paths(output, &[OutputFormat::Png, OutputFormat::Svg]),
+            vec![PathBuf::from("out/chart.png"), PathBuf::from("out/chart.svg")]
+        );
+    }
+
+    #[test]
+    fn test_process_data_formats_encodes_each_format() {
+        let data = read_csv(Cursor::new("x,y\n1,10\n2,20\n")).unwrap();
+        let outputs = process_data_formats("aes(x: x, y: y) | line()", data, RenderOptions::default(), HashMap::new(), &[OutputFormat::Png, OutputFormat::Svg]).unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert!(outputs[0].starts_with(b"\x89PNG"));
+        assert!(String::from_utf8_lossy(&outputs[1]).contains("<svg"));
+    }
+
     #[test]
     fn test_process_dsl_variable_in_geom() {
         // Test variable substitution in geometry
@@ -261,4 +691,72 @@ mod tests {
         let err_str = format!("{:?}", result.unwrap_err());
         assert!(err_str.contains("Variable '$undefined' not defined"));
     }
+
+    #[test]
+    fn test_process_dsl_with_global_theme() {
+        gramgraph::theme_resolve::theme_set(ThemeArg::Classic.into());
+        let csv = "x,y\n1,10\n2,20\n";
+        let result = process_dsl("aes(x: x, y: y) | line()", Cursor::new(csv), RenderOptions::default(), HashMap::new());
+        gramgraph::theme_resolve::theme_set(Theme::default());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_process_dsl_svg_css_vars() {
+        let csv = "x,y\n1,10\n2,20\n";
+        let options = RenderOptions { format: OutputFormat::Svg, ..RenderOptions::default() };
+        let bytes = process_dsl("aes(x: x, y: y) | line()", Cursor::new(csv), options, HashMap::new()).unwrap();
+        let svg = String::from_utf8(bytes).unwrap();
+        let svg_start = svg.find("<svg").unwrap();
+        let style_start = svg.find("<style>").unwrap();
+        assert!(style_start > svg_start);
+        assert!(svg.contains("--plot-bg: #ffffff;"));
+    }
+
+    #[test]
+    fn test_process_dsl_watermark() {
+        let csv = "x,y\n1,10\n2,20\n";
+        let options = RenderOptions { watermark: Some("draft".to_string()), ..RenderOptions::default() };
+        let bytes = process_dsl("aes(x: x, y: y) | line()", Cursor::new(csv), options, HashMap::new()).unwrap();
+        assert_eq!(&bytes[0..8], &[137, 80, 78, 71, 13, 10, 26, 10]);
+
+        let options = RenderOptions { format: OutputFormat::Svg, watermark: Some("draft".to_string()), ..RenderOptions::default() };
+        let err = process_dsl("aes(x: x, y: y) | line()", Cursor::new(csv), options, HashMap::new()).unwrap_err();
+        assert!(format!("{:?}", err).contains("only supported for PNG"));
+
+        let options = RenderOptions { watermark_image: Some(PathBuf::from("fixtures/missing_logo.png")), ..RenderOptions::default() };
+        let err = process_dsl("aes(x: x, y: y) | line()", Cursor::new(csv), options, HashMap::new()).unwrap_err();
+        assert!(format!("{:?}", err).contains("missing_logo.png"));
+    }
+
+    #[test]
+    fn test_process_dsl_scale() {
+        let csv = "x,y\n1,10\n2,20\n";
+        let options = RenderOptions { width: 200, height: 100, scale: 2.0, ..RenderOptions::default() };
+        let bytes = process_dsl("aes(x: x, y: y) | line() | labs(title: \"T\")", Cursor::new(csv), options, HashMap::new()).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((image.width(), image.height()), (400, 200));
+        // pHYs: 144 DPI = 5669 pixels per metre in both axes, unit 1 (metre)
+        let phys = bytes.windows(4).position(|w| w == b"pHYs").expect("missing pHYs chunk");
+        assert_eq!(&bytes[phys + 4..phys + 13], &[0, 0, 0x16, 0x25, 0, 0, 0x16, 0x25, 1]);
+
+        let options = RenderOptions { scale: 0.0, ..RenderOptions::default() };
+        let err = process_dsl("aes(x: x, y: y) | line()", Cursor::new(csv), options, HashMap::new()).unwrap_err();
+        assert!(format!("{:?}", err).contains("Invalid scale"));
+
+        let options = RenderOptions { format: OutputFormat::Svg, scale: 2.0, ..RenderOptions::default() };
+        let err = process_dsl("aes(x: x, y: y) | line()", Cursor::new(csv), options, HashMap::new()).unwrap_err();
+        assert!(format!("{:?}", err).contains("only supported for PNG"));
+    }
+
+    #[test]
+    fn test_process_dsl_svg_font_family() {
+        let csv = "x,y\n1,10\n2,20\n";
+        let options = RenderOptions { format: OutputFormat::Svg, ..RenderOptions::default() };
+        let dsl = r#"aes(x: x, y: y) | line() | labs(title: "T", x: "X") | theme(text: element_text(family: "monospace"))"#;
+        let bytes = process_dsl(dsl, Cursor::new(csv), options, HashMap::new()).unwrap();
+        let svg = String::from_utf8(bytes).unwrap();
+        assert!(svg.contains("font-family=\"monospace\""));
+        assert!(!svg.contains("font-family=\"sans-serif\""));
+    }
 }
@@ -1,6 +1,40 @@
 // Color and size palettes for data-driven aesthetics
 
+use anyhow::{anyhow, Result};
 use std::collections::HashMap;
+use crate::ir::ColorBar;
+use crate::parser::ast::{FillScale, ScaleType};
+use crate::theme_resolve::parse_color;
+
+// ColorBrewer palettes (colorbrewer2.org), largest variant of each
+const BREWER_BLUES: &[(u8, u8, u8)] = &[
+    (247, 251, 255), (222, 235, 247), (198, 219, 239), (158, 202, 225), (107, 174, 214),
+    (66, 146, 198), (33, 113, 181), (8, 81, 156), (8, 48, 107),
+];
+const BREWER_RDYLGN: &[(u8, u8, u8)] = &[
+    (165, 0, 38), (215, 48, 39), (244, 109, 67), (253, 174, 97), (254, 224, 139), (255, 255, 191),
+    (217, 239, 139), (166, 217, 106), (102, 189, 99), (26, 152, 80), (0, 104, 55),
+];
+const BREWER_SPECTRAL: &[(u8, u8, u8)] = &[
+    (158, 1, 66), (213, 62, 79), (244, 109, 67), (253, 174, 97), (254, 224, 139), (255, 255, 191),
+    (230, 245, 152), (171, 221, 164), (102, 194, 165), (50, 136, 189), (94, 79, 162),
+];
+const BREWER_SET1: &[(u8, u8, u8)] = &[
+    (228, 26, 28), (55, 126, 184), (77, 175, 74), (152, 78, 163), (255, 127, 0),
+    (255, 255, 51), (166, 86, 40), (247, 129, 191), (153, 153, 153),
+];
+const BREWER_SET2: &[(u8, u8, u8)] = &[
+    (102, 194, 165), (252, 141, 98), (141, 160, 203), (231, 138, 195),
+    (166, 216, 84), (255, 217, 47), (229, 196, 148), (179, 179, 179),
+];
+const BREWER_SET3: &[(u8, u8, u8)] = &[
+    (141, 211, 199), (255, 255, 179), (190, 186, 218), (251, 128, 114), (128, 177, 211), (253, 180, 98),
+    (179, 222, 105), (252, 205, 229), (217, 217, 217), (188, 128, 189), (204, 235, 197), (255, 237, 111),
+];
+const BREWER_PAIRED: &[(u8, u8, u8)] = &[
+    (166, 206, 227), (31, 120, 180), (178, 223, 138), (51, 160, 44), (251, 154, 153), (227, 26, 28),
+    (253, 191, 111), (255, 127, 0), (202, 178, 214), (106, 61, 154), (255, 255, 153), (177, 89, 40),
+];
 
 /// Color palette for categorical data
 pub struct ColorPalette {
@@ -27,6 +61,42 @@ impl ColorPalette {
         }
     }
 
+    /// Create a ColorBrewer palette with `n` colors.
+    /// Sequential and diverging palettes are sampled evenly across their range;
+    /// qualitative palettes use their first `n` colors.
+    pub fn brewer(name: &str, n: usize) -> Result<Self> {
+        let (colors, qualitative) = match name {
+            "Blues" => (BREWER_BLUES, false),
+            "RdYlGn" => (BREWER_RDYLGN, false),
+            "Spectral" => (BREWER_SPECTRAL, false),
+            "Set1" => (BREWER_SET1, true),
+            "Set2" => (BREWER_SET2, true),
+            "Set3" => (BREWER_SET3, true),
+            "Paired" => (BREWER_PAIRED, true),
+            _ => return Err(anyhow!(
+                "Unknown ColorBrewer palette '{}' (expected Blues, RdYlGn, Spectral, Set1, Set2, Set3 or Paired)", name
+            )),
+        };
+        if n > colors.len() {
+            return Err(anyhow!("ColorBrewer palette '{}' has at most {} colors, {} requested", name, colors.len(), n));
+        }
+
+        let n = n.max(1);
+        let picked: Vec<(u8, u8, u8)> = if qualitative || n == colors.len() {
+            colors[..n].to_vec()
+        } else if n == 1 {
+            vec![colors[colors.len() / 2]]
+        } else {
+            (0..n)
+                .map(|i| colors[(i as f64 * (colors.len() - 1) as f64 / (n - 1) as f64).round() as usize])
+                .collect()
+        };
+
+        Ok(ColorPalette {
+            colors: picked.into_iter().map(|(r, g, b)| format!("#{:02x}{:02x}{:02x}", r, g, b)).collect(),
+        })
+    }
+
     /// Get color for a specific index (wraps around if index > palette size)
     pub fn get_color(&self, index: usize) -> String {
         self.colors[index % self.colors.len()].clone()
@@ -43,6 +113,135 @@ impl ColorPalette {
     }
 }
 
+/// Continuous color gradient for numeric fill values (scale_fill_continuous)
+pub struct ColorGradient {
+    low: (u8, u8, u8),
+    high: (u8, u8, u8),
+    mid: Option<(u8, u8, u8)>,
+    midpoint: Option<f64>,
+    na_value: String,
+    log10: bool,
+}
+
+impl ColorGradient {
+    /// `counts` selects the log10 transform when the scale does not set `trans` (hexbin)
+    pub fn from_scale(scale: &FillScale, counts: bool) -> Result<Self> {
+        let FillScale::Continuous { low, high, mid, midpoint, na_value, trans } = scale;
+        let rgb = |name: &str| {
+            parse_color(name)
+                .map(|c| (c.0, c.1, c.2))
+                .ok_or_else(|| anyhow!("Unknown color '{}' in scale_fill_continuous", name))
+        };
+        Ok(ColorGradient {
+            low: rgb(low)?,
+            high: rgb(high)?,
+            mid: mid.as_deref().map(rgb).transpose()?,
+            midpoint: *midpoint,
+            na_value: na_value.clone(),
+            log10: match trans {
+                Some(t) => *t == ScaleType::Log10,
+                None => counts,
+            },
+        })
+    }
+
+    /// The log transform is log10(value + 1), so zero counts map to `low`
+    fn transform(&self, value: f64) -> f64 {
+        if self.log10 { (value + 1.0).log10() } else { value }
+    }
+
+    /// Map a value to a hex color, given the (min, max) range of all values.
+    /// With a mid color the gradient diverges symmetrically around the midpoint
+    /// (like ggplot2's scale_fill_gradient2). Non-finite values get `na_value`.
+    pub fn map(&self, value: f64, (min, max): (f64, f64)) -> String {
+        let value = self.transform(value);
+        if !value.is_finite() {
+            return self.na_value.clone();
+        }
+        self.color_at(value, (self.transform(min), self.transform(max)))
+    }
+
+    /// Interpolate the gradient at an already transformed value
+    fn color_at(&self, value: f64, (min, max): (f64, f64)) -> String {
+        let (from, to, t) = match self.mid {
+            None => {
+                let t = if max > min { (value - min) / (max - min) } else { 0.5 };
+                (self.low, self.high, t)
+            }
+            Some(mid) => {
+                let center = self.midpoint.map(|m| self.transform(m)).unwrap_or((min + max) / 2.0);
+                let span = (min - center).abs().max((max - center).abs());
+                let t = if span > 0.0 { 0.5 + (value - center) / (2.0 * span) } else { 0.5 };
+                if t < 0.5 { (self.low, mid, t * 2.0) } else { (mid, self.high, t * 2.0 - 1.0) }
+            }
+        };
+
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        format!("#{:02x}{:02x}{:02x}", lerp(from.0, to.0), lerp(from.1, to.1), lerp(from.2, to.2))
+    }
+
+    /// Legend color bar over `range`, with ticks labeled in data units (not log values)
+    pub fn colorbar(&self, (min, max): (f64, f64), title: Option<String>) -> ColorBar {
+        const STEPS: usize = 32;
+        let (t_min, t_max) = (self.transform(min), self.transform(max));
+        let colors = (0..STEPS)
+            .map(|i| self.color_at(t_min + (t_max - t_min) * i as f64 / (STEPS - 1) as f64, (t_min, t_max)))
+            .collect();
+
+        let breaks = if self.log10 { log_breaks(min, max) } else { linear_breaks(min, max) };
+        let ticks = breaks.into_iter()
+            .map(|v| {
+                let pos = if t_max > t_min { (self.transform(v) - t_min) / (t_max - t_min) } else { 0.5 };
+                (pos, format_break(v))
+            })
+            .collect();
+
+        ColorBar { title, colors, ticks }
+    }
+}
+
+/// About five evenly spaced breaks at multiples of 1, 2 or 5 x 10^k
+fn linear_breaks(min: f64, max: f64) -> Vec<f64> {
+    if max <= min {
+        return vec![min];
+    }
+    let ticks = |step: f64| ((min / step).ceil() as i64, (max / step).floor() as i64);
+    // 1/2/5 x 10^k step whose tick count is closest to 4 (larger step on ties)
+    let magnitude = 10f64.powf(((max - min) / 4.0).log10().floor());
+    let step = [0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0].iter().rev()
+        .map(|m| m * magnitude)
+        .min_by_key(|&s| {
+            let (first, last) = ticks(s);
+            (last - first + 1 - 4).abs()
+        })
+        .unwrap_or(magnitude);
+    let (first, last) = ticks(step);
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+/// Powers of ten inside the range, falling back to its end points
+fn log_breaks(min: f64, max: f64) -> Vec<f64> {
+    let mut breaks: Vec<f64> = (0..=15)
+        .map(|e| 10f64.powi(e))
+        .filter(|&v| v >= min && v <= max)
+        .collect();
+    if breaks.len() < 2 {
+        breaks = if max > min { vec![min, max] } else { vec![min] };
+    }
+    breaks
+}
+
+/// Whole numbers without decimals, others with at most two
+fn format_break(v: f64) -> String {
+    if v.fract() == 0.0 {
+        format!("{:.0}", v)
+    } else {
+        let s = format!("{:.2}", v);
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
 /// Size palette for categorical or continuous size mapping
 pub struct SizePalette {
     min_size: f64,
@@ -109,6 +308,24 @@ impl ShapePalette {
         }
     }
 
+    /// Create a palette of unfilled (outline-only) shapes
+    pub fn open_shapes() -> Self {
+        ShapePalette {
+            shapes: vec![
+                "circle_open".to_string(),
+                "square_open".to_string(),
+                "triangle_open".to_string(),
+                "diamond_open".to_string(),
+            ],
+        }
+    }
+
+    /// Whether `name` is one of the shapes points are drawn with (filled or "_open")
+    pub fn is_shape(name: &str) -> bool {
+        let (filled, open) = (Self::default_shapes(), Self::open_shapes());
+        filled.shapes.iter().chain(&open.shapes).any(|s| s == name)
+    }
+
     /// Get shape for a specific index (wraps around)
     pub fn get_shape(&self, index: usize) -> String {
         self.shapes[index % self.shapes.len()].clone()
@@ -124,6 +341,52 @@ impl ShapePalette {
     }
 }
 
+/// Split a shape name into its base shape and whether it is an open ("_open") variant
+pub fn split_open_shape(shape: &str) -> (&str, bool) {
+    match shape.strip_suffix("_open") {
+        Some(base) => (base, true),
+        None => (shape, false),
+    }
+}
+
+/// Linetype palette for categorical linetype mapping
+pub struct LinetypePalette {
+    linetypes: Vec<String>,
+}
+
+impl LinetypePalette {
+    /// Create a palette with the standard line types
+    pub fn default_types() -> Self {
+        LinetypePalette::new(
+            ["solid", "dashed", "dotted", "longdash", "twodash"].iter().map(|s| s.to_string()).collect(),
+        )
+    }
+
+    /// Whether `name` is a linetype lines are drawn with (the standard ones plus "dotdash")
+    pub fn is_linetype(name: &str) -> bool {
+        Self::default_types().linetypes.iter().any(|t| t == name) || name == "dotdash"
+    }
+
+    /// Create a palette from explicit values (scale_linetype_manual)
+    pub fn new(linetypes: Vec<String>) -> Self {
+        LinetypePalette { linetypes }
+    }
+
+    /// Get linetype for a specific index (wraps around)
+    pub fn get_linetype(&self, index: usize) -> String {
+        self.linetypes[index % self.linetypes.len()].clone()
+    }
+
+    /// Assign linetypes to a list of group keys
+    pub fn assign_linetypes(&self, group_keys: &[String]) -> HashMap<String, String> {
+        group_keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (key.clone(), self.get_linetype(i)))
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -151,6 +414,29 @@ mod tests {
         assert_eq!(colors.len(), 3);
     }
 
+    #[test]
+    fn test_color_palette_brewer_qualitative() {
+        let palette = ColorPalette::brewer("Set2", 3).unwrap();
+        assert_eq!(palette.get_color(0), "#66c2a5");
+        assert_eq!(palette.get_color(2), "#8da0cb");
+        assert_eq!(palette.get_color(3), "#66c2a5");
+    }
+
+    #[test]
+    fn test_color_palette_brewer_sequential_samples_evenly() {
+        let palette = ColorPalette::brewer("Blues", 3).unwrap();
+        assert_eq!(palette.get_color(0), "#f7fbff");
+        assert_eq!(palette.get_color(1), "#6baed6");
+        assert_eq!(palette.get_color(2), "#08306b");
+    }
+
+    #[test]
+    fn test_color_palette_brewer_errors() {
+        assert!(ColorPalette::brewer("Set2", 9).is_err());
+        assert!(ColorPalette::brewer("Nope", 3).is_err());
+        assert!(ColorPalette::brewer("Paired", 12).is_ok());
+    }
+
     #[test]
     fn test_size_palette_default_range() {
         let palette = SizePalette::default_range();
@@ -197,6 +483,62 @@ mod tests {
         assert_eq!(sizes.len(), 0);
     }
 
+    #[test]
+    fn test_color_gradient_interpolates() {
+        let scale = FillScale::Continuous {
+            low: "white".to_string(),
+            high: "black".to_string(),
+            mid: None,
+            midpoint: None,
+            na_value: "grey".to_string(),
+            trans: None,
+        };
+        let gradient = ColorGradient::from_scale(&scale, false).unwrap();
+        assert_eq!(gradient.map(0.0, (0.0, 10.0)), "#ffffff");
+        assert_eq!(gradient.map(5.0, (0.0, 10.0)), "#808080");
+        assert_eq!(gradient.map(10.0, (0.0, 10.0)), "#000000");
+        assert_eq!(gradient.map(f64::NAN, (0.0, 10.0)), "grey");
+    }
+
+    #[test]
+    fn test_color_gradient_diverges_around_midpoint() {
+        let scale = FillScale::Continuous {
+            low: "blue".to_string(),
+            high: "red".to_string(),
+            mid: Some("white".to_string()),
+            midpoint: Some(0.0),
+            na_value: "grey".to_string(),
+            trans: None,
+        };
+        let gradient = ColorGradient::from_scale(&scale, false).unwrap();
+        assert_eq!(gradient.map(0.0, (-2.0, 4.0)), "#ffffff");
+        assert_eq!(gradient.map(4.0, (-2.0, 4.0)), "#ff0000");
+        // Symmetric around the midpoint: -2 is halfway from white to blue
+        assert_eq!(gradient.map(-2.0, (-2.0, 4.0)), "#8080ff");
+    }
+
+    #[test]
+    fn test_color_gradient_log_colorbar() {
+        let gradient = ColorGradient::from_scale(&FillScale::default(), true).unwrap();
+        // log10(count + 1): 9 is halfway between 0 and 99
+        assert_eq!(gradient.map(9.0, (0.0, 99.0)), "#a3c1da");
+        assert_eq!(gradient.map(0.0, (0.0, 99.0)), "#ffffff");
+
+        let bar = ColorGradient::from_scale(&FillScale::default(), true).unwrap().colorbar((1.0, 500.0), Some("count".to_string()));
+        let labels: Vec<&str> = bar.ticks.iter().map(|(_, l)| l.as_str()).collect();
+        assert_eq!(labels, vec!["1", "10", "100"]);
+        assert_eq!(bar.colors.first().map(String::as_str), Some("#ffffff"));
+        assert_eq!(bar.colors.last().map(String::as_str), Some("#4682b4"));
+        assert!(bar.ticks.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn test_linear_breaks() {
+        assert_eq!(linear_breaks(0.0, 10.0), vec![0.0, 5.0, 10.0]);
+        assert_eq!(linear_breaks(3.0, 47.0), vec![10.0, 20.0, 30.0, 40.0]);
+        assert_eq!(format_break(2.5), "2.5");
+    }
+
     #[test]
     fn test_shape_palette_default_shapes() {
         let palette = ShapePalette::default_shapes();
@@ -207,6 +549,23 @@ mod tests {
         assert_eq!(palette.get_shape(6), "circle");
     }
 
+    #[test]
+    fn test_shape_palette_open_shapes() {
+        let palette = ShapePalette::open_shapes();
+        assert_eq!(palette.get_shape(0), "circle_open");
+        assert_eq!(palette.get_shape(3), "diamond_open");
+        assert_eq!(split_open_shape("triangle_open"), ("triangle", true));
+        assert_eq!(split_open_shape("square"), ("square", false));
+    }
+
+    #[test]
+    fn test_identity_value_names() {
+        assert!(ShapePalette::is_shape("triangle") && ShapePalette::is_shape("diamond_open"));
+        assert!(!ShapePalette::is_shape("bogus"));
+        assert!(LinetypePalette::is_linetype("dotdash") && LinetypePalette::is_linetype("solid"));
+        assert!(!LinetypePalette::is_linetype("wavy"));
+    }
+
     #[test]
     fn test_shape_palette_assign_shapes() {
         let palette = ShapePalette::default_shapes();
@@ -216,4 +575,22 @@ mod tests {
         assert_eq!(shapes.get("A"), Some(&"circle".to_string()));
         assert_eq!(shapes.get("B"), Some(&"square".to_string()));
     }
+
+    #[test]
+    fn test_linetype_palette_default() {
+        let palette = LinetypePalette::default_types();
+        assert_eq!(palette.get_linetype(0), "solid");
+        assert_eq!(palette.get_linetype(4), "twodash");
+        assert_eq!(palette.get_linetype(5), "solid");
+    }
+
+    #[test]
+    fn test_linetype_palette_manual() {
+        let palette = LinetypePalette::new(vec!["dotted".to_string(), "dashed".to_string()]);
+        let groups = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let types = palette.assign_linetypes(&groups);
+        assert_eq!(types.get("A"), Some(&"dotted".to_string()));
+        assert_eq!(types.get("B"), Some(&"dashed".to_string()));
+        assert_eq!(types.get("C"), Some(&"dotted".to_string()));
+    }
 }
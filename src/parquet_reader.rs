@@ -0,0 +1,91 @@
+use anyhow::{anyhow, Context, Result};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::Field;
+use std::fs::File;
+use std::path::Path;
+use crate::csv_reader::CsvData;
+
+/// Read a Parquet file into a CsvData struct, converting every value to a string.
+/// Nulls become empty strings, matching missing CSV cells.
+pub fn read_parquet(path: &Path) -> Result<CsvData> {
+    let file = File::open(path).with_context(|| format!("Failed to open Parquet file '{}'", path.display()))?;
+    let reader = SerializedFileReader::new(file).context("Failed to read Parquet metadata")?;
+
+    let headers: Vec<String> = reader.metadata().file_metadata().schema_descr().root_schema()
+        .get_fields()
+        .iter()
+        .map(|f| f.name().to_string())
+        .collect();
+
+    let mut rows = Vec::new();
+    for row in reader.get_row_iter(None).context("Failed to read Parquet rows")? {
+        let row = row.context("Failed to read Parquet row")?;
+        rows.push(row.get_column_iter().map(|(_, field)| field_to_string(field)).collect());
+    }
+
+    if rows.is_empty() {
+        return Err(anyhow!("Parquet file must contain at least one row"));
+    }
+
+    Ok(CsvData { headers, rows })
+}
+
+/// Render a Parquet value the way it would appear in a CSV cell
+fn field_to_string(field: &Field) -> String {
+    match field {
+        Field::Null => String::new(),
+        Field::Str(s) => s.clone(),
+        Field::Bytes(b) => String::from_utf8_lossy(b.data()).into_owned(),
+        Field::Float(v) => v.to_string(),
+        Field::Double(v) => v.to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::data_type::{ByteArray, ByteArrayType, DoubleType};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::sync::Arc;
+
+    fn write_sample(path: &Path) {
+        let schema = Arc::new(parse_message_type(
+            "message sample { REQUIRED BYTE_ARRAY name (UTF8); OPTIONAL DOUBLE value; }"
+        ).unwrap());
+        let file = File::create(path).unwrap();
+        let mut writer = SerializedFileWriter::new(file, schema, Arc::new(WriterProperties::builder().build())).unwrap();
+        let mut group = writer.next_row_group().unwrap();
+
+        let mut col = group.next_column().unwrap().unwrap();
+        col.typed::<ByteArrayType>()
+            .write_batch(&[ByteArray::from("a"), ByteArray::from("b")], None, None)
+            .unwrap();
+        col.close().unwrap();
+
+        let mut col = group.next_column().unwrap().unwrap();
+        col.typed::<DoubleType>().write_batch(&[1.5], Some(&[1, 0]), None).unwrap();
+        col.close().unwrap();
+
+        group.close().unwrap();
+        writer.close().unwrap();
+    }
+
+    #[test]
+    fn test_read_parquet() {
+        let path = std::env::temp_dir().join(format!("gramgraph_test_{}.parquet", std::process::id()));
+        write_sample(&path);
+        let data = read_parquet(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(data.headers, vec!["name", "value"]);
+        assert_eq!(data.rows, vec![vec!["a", "1.5"], vec!["b", ""]]);
+    }
+
+    #[test]
+    fn test_read_parquet_missing_file() {
+        assert!(read_parquet(Path::new("does/not/exist.parquet")).is_err());
+    }
+}
@@ -10,7 +10,7 @@ use nom::{
 };
 
 /// Parse aesthetics specification
-/// Format: aes(x: col, y: col[, color: col2][, size: col3][, shape: col4][, alpha: col5])
+/// Format: aes(x: col, y: col[, color: col2][, size: col3][, shape: col4][, alpha: col5][, linetype: col6][, ymin/ymax/xmin/xmax: col][, label: col7])
 pub fn parse_aesthetics(input: &str) -> IResult<&str, Aesthetics> {
     let (input, _) = ws(tag("aes"))(input)?;
     let (input, _) = ws(char('('))(input)?;
@@ -30,8 +30,12 @@ pub fn parse_aesthetics(input: &str) -> IResult<&str, Aesthetics> {
     let mut size = None;
     let mut shape = None;
     let mut alpha = None;
+    let mut linetype = None;
     let mut ymin = None;
     let mut ymax = None;
+    let mut xmin = None;
+    let mut xmax = None;
+    let mut label = None;
 
     for (key, value) in args {
         match key.as_str() {
@@ -41,8 +45,12 @@ pub fn parse_aesthetics(input: &str) -> IResult<&str, Aesthetics> {
             "size" => size = Some(value),
             "shape" => shape = Some(value),
             "alpha" => alpha = Some(value),
+            "linetype" => linetype = Some(value),
             "ymin" => ymin = Some(value),
             "ymax" => ymax = Some(value),
+            "xmin" => xmin = Some(value),
+            "xmax" => xmax = Some(value),
+            "label" => label = Some(value),
             _ => {} // Ignore unknown keys
         }
     }
@@ -57,7 +65,7 @@ pub fn parse_aesthetics(input: &str) -> IResult<&str, Aesthetics> {
     
     // y is now optional (e.g. for histogram)
 
-    Ok((input, Aesthetics { x, y, color, size, shape, alpha, ymin, ymax }))
+    Ok((input, Aesthetics { x, y, color, size, shape, alpha, linetype, ymin, ymax, xmin, xmax, label }))
 }
 
 /// Parse a single aesthetic argument (key: value)
@@ -1,15 +1,19 @@
 // Abstract Syntax Tree for Grammar of Graphics DSL
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum CoordSystem {
     Cartesian,
     Flip,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub enum LegendPosition {
     UpperLeft,
     UpperMiddle,
+    #[default]
     UpperRight,
     MiddleLeft,
     MiddleMiddle,
@@ -20,16 +24,10 @@ pub enum LegendPosition {
     None,
 }
 
-impl Default for LegendPosition {
-    fn default() -> Self {
-        LegendPosition::UpperRight
-    }
-}
-
 // === Theme Element Primitives ===
 
 /// Line element styling (for axis lines, grid lines, tick marks)
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct ElementLine {
     pub color: Option<String>,
     pub width: Option<f64>,
@@ -37,7 +35,7 @@ pub struct ElementLine {
 }
 
 /// Rectangle element styling (for backgrounds, borders)
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct ElementRect {
     pub fill: Option<String>,
     pub color: Option<String>,  // Border color
@@ -45,7 +43,7 @@ pub struct ElementRect {
 }
 
 /// Text element styling (for labels, titles)
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct ElementText {
     pub family: Option<String>,
     pub color: Option<String>,
@@ -57,25 +55,20 @@ pub struct ElementText {
 }
 
 /// Theme element wrapper - can be a specific element type, blank, or inherit from parent
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub enum ThemeElement {
     Line(ElementLine),
     Rect(ElementRect),
     Text(ElementText),
     Blank,   // Remove this element entirely
+    #[default]
     Inherit, // Inherit from parent element in hierarchy
 }
 
-impl Default for ThemeElement {
-    fn default() -> Self {
-        ThemeElement::Inherit
-    }
-}
-
 // === Hierarchical Theme ===
 
 /// Complete theme specification with hierarchical element inheritance
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Theme {
     // Root elements (base defaults for each type)
     pub line: ThemeElement,
@@ -97,7 +90,8 @@ pub struct Theme {
     pub axis_ticks: ThemeElement,
 
     // Legend
-    pub legend_position: LegendPosition,
+    pub legend_position: Option<LegendPosition>, // None = inherit (default upper-right)
+    pub legend_key_size: Option<f64>, // Legend key scale, in lines (None = inherit, default 1.0)
 }
 
 impl Default for Theme {
@@ -114,12 +108,13 @@ impl Default for Theme {
             axis_text: ThemeElement::Inherit,
             axis_line: ThemeElement::Inherit,
             axis_ticks: ThemeElement::Inherit,
-            legend_position: LegendPosition::UpperRight,
+            legend_position: None,
+            legend_key_size: None,
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum ScaleType {
     Linear,
     Log10,
@@ -127,10 +122,15 @@ pub enum ScaleType {
     Reverse,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct AxisScale {
     pub scale_type: ScaleType,
     pub limits: Option<(f64, f64)>, // Custom min/max
+    pub breaks_n: Option<usize>,    // Approximate number of ticks (None = chosen by plotters)
+    pub breaks_width: Option<f64>,  // Tick spacing: ticks at multiples of the width
+    pub labels: TickFormat,
+    pub oob: OutOfBounds,           // Treatment of drawn values outside the limits
+    pub discrete: Option<DiscreteScale>, // Category order and names (scale_x_discrete)
 }
 
 impl Default for AxisScale {
@@ -138,12 +138,53 @@ impl Default for AxisScale {
         AxisScale {
             scale_type: ScaleType::Linear,
             limits: None,
+            breaks_n: None,
+            breaks_width: None,
+            labels: TickFormat::Auto,
+            oob: OutOfBounds::Keep,
+            discrete: None,
         }
     }
 }
 
+/// Categorical axis options: scale_x_discrete(limits: [...], labels: {...}, drop: false)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiscreteScale {
+    pub limits: Option<Vec<String>>,     // Categories in display order (None = sorted)
+    pub labels: HashMap<String, String>, // Display name per category; the data values are unchanged
+    pub drop: bool,                      // Drop rows outside `limits` (false: show their categories after them)
+}
+
+impl Default for DiscreteScale {
+    fn default() -> Self {
+        DiscreteScale { limits: None, labels: HashMap::new(), drop: true }
+    }
+}
+
+/// Tick label format for continuous axes (scale_y_continuous(labels: "comma"))
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub enum TickFormat {
+    #[default]
+    Auto,           // Plotters' default number formatting
+    Comma,          // 12,500
+    Scientific,     // 1.25e4
+    Percent,        // 0.25 -> 25%
+    Dollar,         // $12,500
+    Custom(String), // printf-style, e.g. "%.1f%%"
+    Date(String),   // strftime-style, e.g. "%b %Y"; values are days since 1970-01-01 (scale_x_date)
+}
+
+/// What happens to values outside a continuous axis' limits (scale_y_continuous(oob: "squish"))
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum OutOfBounds {
+    #[default]
+    Keep,   // Drawn as-is (and cut off at the panel edge)
+    Squish, // Clamped to the nearest limit
+    Censor, // Dropped (lines break at them)
+}
+
 /// Complete plot specification
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct PlotSpec {
     pub aesthetics: Option<Aesthetics>,
     pub layers: Vec<Layer>,
@@ -153,6 +194,89 @@ pub struct PlotSpec {
     pub theme: Option<Theme>,
     pub x_scale: Option<AxisScale>,
     pub y_scale: Option<AxisScale>,
+    pub steps: Vec<PipelineStep>, // Data reshaping applied (in order) before resolution
+    pub color_scale: Option<ColorScale>,
+    pub linetype_values: Option<Vec<String>>, // scale_linetype_manual(values: [...])
+    pub fill_scale: Option<FillScale>,
+    pub identity_scales: IdentityScales,
+}
+
+/// Palette used for mapped color/fill aesthetics
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ColorScale {
+    /// scale_color_brewer(palette: "Set2", type: "qual")
+    Brewer { palette: String },
+    /// scale_color_identity(): the mapped column holds the colors themselves
+    Identity,
+}
+
+/// Shape and linetype scales whose mapped column holds the shape or linetype names themselves
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct IdentityScales {
+    pub shape: bool,    // scale_shape_identity()
+    pub linetype: bool, // scale_linetype_identity()
+}
+
+/// Scale for numeric fill values (tile heatmaps)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum FillScale {
+    /// scale_fill_continuous(low: "white", high: "steelblue", mid: "white", midpoint: 0, na_value: "grey", trans: "log10")
+    Continuous {
+        low: String,
+        high: String,
+        mid: Option<String>,   // Diverging: low -> mid -> high
+        midpoint: Option<f64>, // Value drawn in `mid` (default: middle of the data range)
+        na_value: String,      // Color for missing or non-numeric values
+        trans: Option<ScaleType>, // Linear or Log10 (None = log10 for hexbin counts, linear otherwise)
+    },
+}
+
+impl Default for FillScale {
+    fn default() -> Self {
+        // The documented scale_fill_continuous() defaults (white to steelblue)
+        FillScale::Continuous {
+            low: "white".to_string(),
+            high: "steelblue".to_string(),
+            mid: None,
+            midpoint: None,
+            na_value: "grey".to_string(),
+            trans: None,
+        }
+    }
+}
+
+/// Data reshaping step in the pipeline (applied to the input table before plotting)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum PipelineStep {
+    /// pivot_wider(id_cols: [date], names_from: variable, values_from: value)
+    PivotWider {
+        id_cols: Vec<String>,
+        names_from: String,
+        values_from: String,
+    },
+    /// join(file: "forecast.csv", on: "date", how: "left")
+    Join {
+        file: String,
+        on: String,
+        how: JoinType,
+    },
+    /// mutate(log_price = log(price), ratio = sales / target)
+    Mutate {
+        columns: Vec<(String, crate::expr::Expression)>,
+    },
+    /// group_by(region, month) | summarize(total = sum(sales), n = count())
+    GroupSummarize {
+        by: Vec<String>,
+        aggregations: Vec<(String, crate::expr::Aggregation)>,
+    },
+}
+
+/// Row matching strategy for join()
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub enum JoinType {
+    #[default]
+    Inner, // Keep only rows with a match on both sides
+    Left,  // Keep every left row, empty values where unmatched
 }
 
 impl PlotSpec {
@@ -163,7 +287,7 @@ impl PlotSpec {
 }
 
 /// Global aesthetic mappings (data columns → visual properties)
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Aesthetics {
     /// Column name for x-axis
     pub x: String,
@@ -177,14 +301,22 @@ pub struct Aesthetics {
     pub shape: Option<String>,
     /// Optional column name for alpha grouping
     pub alpha: Option<String>,
+    /// Optional column name for linetype grouping
+    pub linetype: Option<String>,
     /// Optional column name for ymin
     pub ymin: Option<String>,
     /// Optional column name for ymax
     pub ymax: Option<String>,
+    /// Optional column name for xmin (horizontal pointrange())
+    pub xmin: Option<String>,
+    /// Optional column name for xmax (horizontal pointrange())
+    pub xmax: Option<String>,
+    /// Optional column name for text labels (text())
+    pub label: Option<String>,
 }
 
 /// Represents either a fixed literal value or a data-driven column mapping
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum AestheticValue<T> {
     /// Fixed literal value (e.g., line(color: "red"))
     Fixed(T),
@@ -192,25 +324,150 @@ pub enum AestheticValue<T> {
     Mapped(String),
 }
 
+/// Fitting method for smooth()
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub enum SmoothMethod {
+    #[default]
+    Lm,                   // Linear regression
+    Poly,                 // Quadratic least squares
+    Loess { span: f64 },  // Local quadratic fits over the nearest `span` fraction of points
+}
+
+/// Interval band drawn around a smooth() line, at the given level (e.g. 0.95)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum SeType {
+    #[default]
+    None,
+    Confidence(f64), // Uncertainty of the fitted mean
+    Prediction(f64), // Range of new observations (wider: adds the residual variance)
+}
+
+impl SeType {
+    pub const DEFAULT_LEVEL: f64 = 0.95;
+}
+
+/// KDE bandwidth selection rule for violin()
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub enum BandwidthMethod {
+    #[default]
+    Nrd0,        // Silverman's rule of thumb
+    Nrd,         // Scott's rule: 1.06 * sd * n^(-1/5)
+    Ucv,         // Unbiased (least-squares) cross-validation
+    Fixed(f64),  // Explicit bandwidth
+}
+
+/// Evaluation points of a density() curve unless n: is given (as in ggplot2)
+pub const DEFAULT_DENSITY_POINTS: usize = 512;
+
+/// Density levels drawn by contour() unless bins: is given
+pub const DEFAULT_CONTOUR_BINS: usize = 10;
+
+/// Height of the rug() band as a fraction of the y range unless length: is given
+pub const DEFAULT_RUG_LENGTH: f64 = 0.03;
+
+/// DSL argument for a bandwidth rule (None for the default nrd0)
+fn bw_arg(bw: &BandwidthMethod) -> Option<String> {
+    match bw {
+        BandwidthMethod::Nrd0 => None,
+        BandwidthMethod::Nrd => Some("\"nrd\"".to_string()),
+        BandwidthMethod::Ucv => Some("\"ucv\"".to_string()),
+        BandwidthMethod::Fixed(b) => Some(b.to_string()),
+    }
+}
+
+/// Per-x summary of the y values, named in stat_summary() formulas
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum SummaryFun {
+    Mean,
+    Median,
+    Sd,  // Sample standard deviation (0 for a single value)
+    Se,  // Standard error of the mean: sd / sqrt(n)
+    Min,
+    Max,
+    Q25,
+    Q75,
+}
+
+impl SummaryFun {
+    pub const ALL: [SummaryFun; 8] = [
+        SummaryFun::Mean, SummaryFun::Median, SummaryFun::Sd, SummaryFun::Se,
+        SummaryFun::Min, SummaryFun::Max, SummaryFun::Q25, SummaryFun::Q75,
+    ];
+
+    /// Name used in formulas ("mean", "se", ...)
+    pub fn name(self) -> &'static str {
+        match self {
+            SummaryFun::Mean => "mean",
+            SummaryFun::Median => "median",
+            SummaryFun::Sd => "sd",
+            SummaryFun::Se => "se",
+            SummaryFun::Min => "min",
+            SummaryFun::Max => "max",
+            SummaryFun::Q25 => "q25",
+            SummaryFun::Q75 => "q75",
+        }
+    }
+}
+
+/// stat_summary() formula: summary functions joined by + and -, e.g. "mean - se"
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum SummaryExpr {
+    Fun(SummaryFun),
+    Add(Box<SummaryExpr>, Box<SummaryExpr>),
+    Sub(Box<SummaryExpr>, Box<SummaryExpr>),
+}
+
+impl SummaryExpr {
+    fn offset(fun: SummaryFun, sign: fn(Box<SummaryExpr>, Box<SummaryExpr>) -> SummaryExpr) -> SummaryExpr {
+        sign(Box::new(SummaryExpr::Fun(SummaryFun::Mean)), Box::new(SummaryExpr::Fun(fun)))
+    }
+
+    /// Defaults of stat_summary(): mean with a standard error range (ggplot2's mean_se)
+    pub fn default_fun() -> SummaryExpr { SummaryExpr::Fun(SummaryFun::Mean) }
+    pub fn default_ymin() -> SummaryExpr { SummaryExpr::offset(SummaryFun::Se, SummaryExpr::Sub) }
+    pub fn default_ymax() -> SummaryExpr { SummaryExpr::offset(SummaryFun::Se, SummaryExpr::Add) }
+}
+
+impl fmt::Display for SummaryExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SummaryExpr::Fun(fun) => write!(f, "{}", fun.name()),
+            SummaryExpr::Add(a, b) => write!(f, "{} + {}", a, b),
+            SummaryExpr::Sub(a, b) => write!(f, "{} - {}", a, b),
+        }
+    }
+}
+
 /// Statistical transformation to apply
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub enum Stat {
+    #[default]
     Identity,
     Bin { bins: usize },
     Count,
-    Smooth { method: String },
+    Prop,
+    Sum,  // Aggregates of the y values per x value (bar(stat: "sum" | "mean" | "max" | "min"))
+    Mean,
+    Max,
+    Min,
+    Smooth { method: SmoothMethod, n: usize, residuals: bool, formula: Option<String>, fullrange: bool, se: SeType }, // n = evaluation points for curved fits; formula overrides method; fullrange spans the plot's x range
+    Quantile { quantiles: Vec<f64> }, // One linear quantile regression line per requested quantile
+    Ecdf { pad: bool, n: Option<usize> }, // Empirical CDF steps; n = evaluation points (None = every unique x)
+    Density { bw: BandwidthMethod, n: usize, fill: bool }, // KDE of x per group; fill shades the area under the curve
+    Contour { bins: usize, filled: bool, bandwidth_x: Option<f64>, bandwidth_y: Option<f64> }, // 2D KDE level lines of (x, y); filled draws the bands between levels; None = Silverman's rule per axis
+    Summary { fun: SummaryExpr, fun_ymin: SummaryExpr, fun_ymax: SummaryExpr }, // Point range per x value
+    Beeswarm { priority: BeeswarmPriority, cex: f64 }, // Raw points, spread sideways within each x category so none overlap
     Boxplot,
-    Violin { draw_quantiles: Vec<f64> },
-}
-
-impl Default for Stat {
-    fn default() -> Self {
-        Stat::Identity
-    }
+    Violin { draw_quantiles: Vec<f64>, bw: BandwidthMethod, scale: ViolinScale, trim: bool },
+    Hexbin { bins: usize }, // Point counts per hexagonal cell, `bins` cells across the x range
+    Abline { slope: AestheticValue<f64>, intercept: AestheticValue<f64> }, // Lines across the x range; columns give one line per distinct row value
+    Crosshair, // Raw points, each drawn as a vertical and a horizontal line across the panel
+    Rug { length: f64, jitter: bool }, // x values drawn as short ticks at y = 0 or the panel bottom; jitter staggers them
+    Segment { xend: Option<String>, yend: Option<String>, arrow: Option<Arrow> }, // A line from (x, y) to the row's (xend, yend) columns (both required)
 }
 
 /// Individual visualization layer
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum Layer {
     Line(LineLayer),
     Point(PointLayer),
@@ -218,12 +475,16 @@ pub enum Layer {
     Ribbon(RibbonLayer),
     Boxplot(BoxplotLayer),
     Violin(ViolinLayer),
+    Tile(TileLayer),
+    Hexbin(HexbinLayer),
+    Text(TextLayer),
 }
 
 impl Layer {
     /// Returns true if this layer type requires a categorical x-axis (e.g., Bar charts)
     pub fn requires_categorical_x(&self) -> bool {
         matches!(self, Layer::Bar(_) | Layer::Boxplot(_) | Layer::Violin(_))
+            || matches!(self, Layer::Point(PointLayer { stat: Stat::Beeswarm { .. }, .. }))
     }
 
     pub fn stat(&self) -> &Stat {
@@ -234,12 +495,343 @@ impl Layer {
             Layer::Ribbon(r) => &r.stat,
             Layer::Boxplot(b) => &b.stat,
             Layer::Violin(v) => &v.stat,
+            Layer::Tile(t) => &t.stat,
+            Layer::Hexbin(h) => &h.stat,
+            Layer::Text(t) => &t.stat,
+        }
+    }
+}
+
+impl fmt::Display for AestheticValue<String> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AestheticValue::Fixed(v) => write!(f, "\"{}\"", v),
+            AestheticValue::Mapped(col) => write!(f, "{}", col),
+        }
+    }
+}
+
+impl fmt::Display for AestheticValue<f64> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AestheticValue::Fixed(v) => write!(f, "{}", v),
+            AestheticValue::Mapped(col) => write!(f, "{}", col),
         }
     }
 }
 
+/// Formats a layer back into DSL source that parses to the same layer
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut args: Vec<String> = Vec::new();
+        let mut push = |name: &str, value: Option<String>| {
+            if let Some(v) = value {
+                args.push(format!("{}: {}", name, v));
+            }
+        };
+        let quoted = |s: &str| format!("\"{}\"", s);
+
+        let name = match self {
+            Layer::Line(LineLayer { stat: Stat::Smooth { method, n, residuals, formula, fullrange, se }, .. }) => {
+                match method {
+                    SmoothMethod::Lm => push("method", Some(quoted("lm"))),
+                    SmoothMethod::Poly => push("method", Some(quoted("poly"))),
+                    SmoothMethod::Loess { span } => {
+                        push("method", Some(quoted("loess")));
+                        push("span", Some(span.to_string()));
+                    }
+                }
+                push("n", Some(n.to_string()));
+                push("residuals", residuals.then(|| "true".to_string()));
+                push("formula", formula.as_deref().map(quoted));
+                push("fullrange", fullrange.then(|| "true".to_string()));
+                let (se_name, level) = match se {
+                    SeType::None => (None, None),
+                    SeType::Confidence(level) => (Some("confidence"), Some(*level)),
+                    SeType::Prediction(level) => (Some("prediction"), Some(*level)),
+                };
+                push("se", se_name.map(quoted));
+                push("level", level.filter(|&l| l != SeType::DEFAULT_LEVEL).map(|l| l.to_string()));
+                "smooth"
+            }
+            Layer::Line(LineLayer { stat: Stat::Quantile { quantiles }, .. }) => {
+                let qs: Vec<String> = quantiles.iter().map(|q| q.to_string()).collect();
+                push("quantiles", Some(format!("[{}]", qs.join(", "))));
+                "quantile"
+            }
+            Layer::Line(LineLayer { stat: Stat::Density { bw, n, fill }, .. }) => {
+                push("bw", bw_arg(bw));
+                push("n", (*n != DEFAULT_DENSITY_POINTS).then(|| n.to_string()));
+                push("fill", fill.then(|| "true".to_string()));
+                "density"
+            }
+            Layer::Line(l @ LineLayer { stat: Stat::Contour { bins, filled, bandwidth_x, bandwidth_y }, .. }) => {
+                push("bins", (*bins != DEFAULT_CONTOUR_BINS).then(|| bins.to_string()));
+                push("filled", filled.then(|| "true".to_string()));
+                push("bandwidth_x", bandwidth_x.map(|h| h.to_string()));
+                push("bandwidth_y", bandwidth_y.map(|h| h.to_string()));
+                push("color", l.color.as_ref().map(|v| v.to_string()));
+                push("width", l.width.as_ref().map(|v| v.to_string()));
+                push("alpha", l.alpha.as_ref().map(|v| v.to_string()));
+                push("linetype", l.linetype.as_ref().map(|v| v.to_string()));
+                "contour"
+            }
+            Layer::Line(l @ LineLayer { stat: Stat::Abline { slope, intercept }, .. }) => {
+                push("slope", Some(slope.to_string()));
+                push("intercept", Some(intercept.to_string()));
+                push("color", l.color.as_ref().map(|v| v.to_string()));
+                push("width", l.width.as_ref().map(|v| v.to_string()));
+                push("alpha", l.alpha.as_ref().map(|v| v.to_string()));
+                push("linetype", l.linetype.as_ref().map(|v| v.to_string()));
+                push("legend", l.legend.as_deref().map(quoted));
+                "abline"
+            }
+            Layer::Line(l @ LineLayer { stat: Stat::Crosshair, .. }) => {
+                push("x", l.x.clone());
+                push("y", l.y.clone());
+                push("color", l.color.as_ref().map(|v| v.to_string()));
+                push("width", l.width.as_ref().map(|v| v.to_string()));
+                push("alpha", l.alpha.as_ref().map(|v| v.to_string()));
+                push("linetype", l.linetype.as_ref().map(|v| v.to_string()));
+                push("legend", l.legend.as_deref().map(quoted));
+                "crosshair"
+            }
+            Layer::Line(l @ LineLayer { stat: Stat::Rug { length, jitter }, .. }) => {
+                push("x", l.x.clone());
+                push("length", (*length != DEFAULT_RUG_LENGTH).then(|| length.to_string()));
+                push("jitter", jitter.then(|| "true".to_string()));
+                push("color", l.color.as_ref().map(|v| v.to_string()));
+                push("width", l.width.as_ref().map(|v| v.to_string()));
+                push("alpha", l.alpha.as_ref().map(|v| v.to_string()));
+                push("linetype", l.linetype.as_ref().map(|v| v.to_string()));
+                "rug"
+            }
+            Layer::Line(l @ LineLayer { stat: Stat::Segment { xend, yend, arrow }, .. }) => {
+                push("x", l.x.clone());
+                push("y", l.y.clone());
+                push("xend", xend.clone());
+                push("yend", yend.clone());
+                push("color", l.color.as_ref().map(|v| v.to_string()));
+                push("width", l.width.as_ref().map(|v| v.to_string()));
+                push("alpha", l.alpha.as_ref().map(|v| v.to_string()));
+                push("linetype", l.linetype.as_ref().map(|v| v.to_string()));
+                if let Some(arrow) = arrow {
+                    push("arrow", Some("true".to_string()));
+                    push("arrow_type", (arrow.head != ArrowType::default()).then(|| quoted(arrow.head.name())));
+                    push("arrow_ends", (arrow.ends != ArrowEnds::default()).then(|| quoted(arrow.ends.name())));
+                }
+                "segment"
+            }
+            Layer::Line(LineLayer { stat: Stat::Ecdf { pad, n }, .. }) => {
+                push("pad", (!pad).then(|| "false".to_string()));
+                push("n", n.map(|n| n.to_string()));
+                "ecdf"
+            }
+            Layer::Line(l) => {
+                push("x", l.x.clone());
+                push("y", l.y.clone());
+                push("color", l.color.as_ref().map(|v| v.to_string()));
+                push("width", l.width.as_ref().map(|v| v.to_string()));
+                push("alpha", l.alpha.as_ref().map(|v| v.to_string()));
+                push("linetype", l.linetype.as_ref().map(|v| v.to_string()));
+                match l.step {
+                    Some(direction) => {
+                        let direction = match direction {
+                            StepDirection::Hv => None,
+                            StepDirection::Vh => Some("vh"),
+                            StepDirection::Mid => Some("mid"),
+                        };
+                        push("direction", direction.map(quoted));
+                        "step"
+                    }
+                    None => "line",
+                }
+            }
+            Layer::Point(PointLayer { stat: Stat::Summary { fun, fun_ymin, fun_ymax }, .. }) => {
+                let formula = |expr: &SummaryExpr, default: SummaryExpr| (*expr != default).then(|| quoted(&expr.to_string()));
+                push("fun", formula(fun, SummaryExpr::default_fun()));
+                push("fun_ymin", formula(fun_ymin, SummaryExpr::default_ymin()));
+                push("fun_ymax", formula(fun_ymax, SummaryExpr::default_ymax()));
+                "stat_summary"
+            }
+            Layer::Point(p @ PointLayer { stat: Stat::Beeswarm { priority, cex }, .. }) => {
+                push("x", p.x.clone());
+                push("y", p.y.clone());
+                push("color", p.color.as_ref().map(|v| v.to_string()));
+                push("size", p.size.as_ref().map(|v| v.to_string()));
+                push("shape", p.shape.as_ref().map(|v| v.to_string()));
+                push("alpha", p.alpha.as_ref().map(|v| v.to_string()));
+                push("fill", p.fill.as_deref().map(quoted));
+                push("priority", (*priority != BeeswarmPriority::default()).then(|| quoted(priority.name())));
+                push("cex", (*cex != 1.0).then(|| cex.to_string()));
+                "beeswarm"
+            }
+            Layer::Point(p) => {
+                push("x", p.x.clone());
+                push("y", p.y.clone());
+                push("color", p.color.as_ref().map(|v| v.to_string()));
+                push("size", p.size.as_ref().map(|v| v.to_string()));
+                push("shape", p.shape.as_ref().map(|v| v.to_string()));
+                push("alpha", p.alpha.as_ref().map(|v| v.to_string()));
+                push("fill", p.fill.as_deref().map(quoted));
+                match &p.range {
+                    Some(range) => {
+                        push("ymin", range.ymin.clone());
+                        push("ymax", range.ymax.clone());
+                        push("xmin", range.xmin.clone());
+                        push("xmax", range.xmax.clone());
+                        push("width", p.width.map(|w| w.to_string()));
+                        push("position", matches!(p.position, LayerPosition::Dodge).then(|| quoted("dodge")));
+                        if p.crossbar { "crossbar" } else { "pointrange" }
+                    }
+                    None => "point",
+                }
+            }
+            Layer::Bar(b) => {
+                push("x", b.x.clone());
+                push("y", b.y.clone());
+                push("color", b.color.as_ref().map(|v| v.to_string()));
+                push("width", b.width.as_ref().map(|v| v.to_string()));
+                push("alpha", b.alpha.as_ref().map(|v| v.to_string()));
+                push("fill", b.hollow.then(|| quoted("none")));
+                push("border", b.border.as_deref().map(quoted));
+                push("border_width", b.border_width.map(|w| w.to_string()));
+                let position = match b.position {
+                    LayerPosition::Identity => None,
+                    LayerPosition::Dodge => Some("dodge"),
+                    LayerPosition::Stack => Some("stack"),
+                    LayerPosition::Fill => Some("fill"),
+                    LayerPosition::Percent => Some("percent"),
+                };
+                push("position", position.map(quoted));
+                match &b.stat {
+                    Stat::Bin { bins } => {
+                        push("bins", Some(bins.to_string()));
+                        push("stat_output_as", b.stat_output_as.map(|_| quoted("binned")));
+                        "histogram"
+                    }
+                    Stat::Count => {
+                        push("stat", Some(quoted("count")));
+                        "bar"
+                    }
+                    Stat::Prop => {
+                        push("stat", Some(quoted("prop")));
+                        "bar"
+                    }
+                    Stat::Sum | Stat::Mean | Stat::Max | Stat::Min => {
+                        let name = match b.stat {
+                            Stat::Sum => "sum",
+                            Stat::Mean => "mean",
+                            Stat::Max => "max",
+                            _ => "min",
+                        };
+                        push("stat", Some(quoted(name)));
+                        "bar"
+                    }
+                    _ if b.require_y => "col",
+                    _ => "bar",
+                }
+            }
+            Layer::Ribbon(r) => {
+                push("x", r.x.clone());
+                push("ymin", r.ymin.clone());
+                push("ymax", r.ymax.clone());
+                push("color", r.color.as_ref().map(|v| v.to_string()));
+                push("alpha", r.alpha.as_ref().map(|v| v.to_string()));
+                push("border", r.border.as_deref().map(quoted));
+                push("linetype", r.linetype.as_deref().map(quoted));
+                "ribbon"
+            }
+            Layer::Boxplot(b) => {
+                push("x", b.x.clone());
+                push("y", b.y.clone());
+                push("color", b.color.as_ref().map(|v| v.to_string()));
+                push("width", b.width.as_ref().map(|v| v.to_string()));
+                push("alpha", b.alpha.as_ref().map(|v| v.to_string()));
+                push("varwidth", b.varwidth.then(|| "true".to_string()));
+                push("notch", b.notch.then(|| "true".to_string()));
+                push("notchwidth", b.notchwidth.map(|w| w.to_string()));
+                push("staplewidth", b.staplewidth.map(|w| w.to_string()));
+                push("outlier_color", b.outlier_color.as_deref().map(quoted));
+                push("outlier_size", b.outlier_size.map(|s| s.to_string()));
+                push("outlier_shape", b.outlier_shape.as_deref().map(quoted));
+                "boxplot"
+            }
+            Layer::Violin(v) => {
+                push("x", v.x.clone());
+                push("y", v.y.clone());
+                push("color", v.color.as_ref().map(|c| c.to_string()));
+                push("width", v.width.as_ref().map(|w| w.to_string()));
+                push("alpha", v.alpha.as_ref().map(|a| a.to_string()));
+                if !v.draw_quantiles.is_empty() {
+                    let qs: Vec<String> = v.draw_quantiles.iter().map(|q| q.to_string()).collect();
+                    push("draw_quantiles", Some(format!("[{}]", qs.join(", "))));
+                }
+                if let Stat::Violin { bw, .. } = &v.stat {
+                    push("bw", bw_arg(bw));
+                }
+                push("scale", (v.scale == ViolinScale::Count).then(|| quoted("count")));
+                push("trim", (!v.trim).then(|| "false".to_string()));
+                let side = match v.side {
+                    ViolinSide::Both => None,
+                    ViolinSide::Left => Some("left"),
+                    ViolinSide::Right => Some("right"),
+                };
+                push("side", side.map(quoted));
+                "violin"
+            }
+            Layer::Tile(t) => {
+                push("x", t.x.clone());
+                push("y", t.y.clone());
+                push("fill", t.fill.as_ref().map(|v| v.to_string()));
+                push("color", t.color.as_deref().map(quoted));
+                push("alpha", t.alpha.map(|a| a.to_string()));
+                push("width", t.width.map(|w| w.to_string()));
+                push("height", t.height.map(|h| h.to_string()));
+                push("na_color", t.na_color.as_deref().map(quoted));
+                push("drop_na", t.drop_na.then(|| "true".to_string()));
+                "tile"
+            }
+            Layer::Hexbin(h) => {
+                push("x", h.x.clone());
+                push("y", h.y.clone());
+                if let Stat::Hexbin { bins } = h.stat {
+                    push("bins", (bins != HexbinLayer::DEFAULT_BINS).then(|| bins.to_string()));
+                }
+                push("color", h.color.as_deref().map(quoted));
+                push("alpha", h.alpha.map(|a| a.to_string()));
+                push("fill", (h.fill != HexSummary::Count).then(|| quoted(h.fill.name())));
+                push("size", h.size_by_count.then(|| quoted("count")));
+                "hexbin"
+            }
+            Layer::Text(t) => {
+                push("x", t.x.clone());
+                push("y", t.y.clone());
+                push("label", t.label.as_ref().map(|v| v.to_string()));
+                push("color", t.color.as_ref().map(|v| v.to_string()));
+                push("size", t.size.map(|s| s.to_string()));
+                push("alpha", t.alpha.map(|a| a.to_string()));
+                push("check_overlap", t.check_overlap.then(|| "true".to_string()));
+                push("nudge_x", (t.nudge_x != 0.0).then(|| t.nudge_x.to_string()));
+                push("nudge_y", (t.nudge_y != 0.0).then(|| t.nudge_y.to_string()));
+                match &t.label_box {
+                    Some(b) => {
+                        push("fill", (b.fill != LabelBox::DEFAULT_FILL).then(|| quoted(&b.fill)));
+                        push("label_padding", (b.padding != LabelBox::DEFAULT_PADDING).then(|| b.padding.to_string()));
+                        push("label_size", (b.border != LabelBox::DEFAULT_BORDER).then(|| b.border.to_string()));
+                        "label"
+                    }
+                    None => "text",
+                }
+            }
+        };
+
+        write!(f, "{}({})", name, args.join(", "))
+    }
+}
+
 /// Line geometry layer
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct LineLayer {
     pub stat: Stat,
     // Aesthetic overrides (None = inherit from global)
@@ -250,11 +842,93 @@ pub struct LineLayer {
     pub color: Option<AestheticValue<String>>,
     pub width: Option<AestheticValue<f64>>,
     pub alpha: Option<AestheticValue<f64>>,
-    // Future: linetype (solid, dashed, dotted)
+    pub linetype: Option<AestheticValue<String>>, // solid, dashed, dotted, dotdash, longdash, twodash
+    pub step: Option<StepDirection>, // step(): join the points with stairs instead of straight segments
+    pub legend: Option<String>,      // abline()/crosshair(): legend entry label (None = the group key)
+}
+
+/// Where step() moves between two consecutive points
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum StepDirection {
+    #[default]
+    Hv,  // Horizontal, then vertical at the next x
+    Vh,  // Vertical at this x, then horizontal
+    Mid, // Horizontal to halfway, vertical, horizontal again
+}
+
+/// Order beeswarm() places the dots of a category in; earlier dots sit closer to the center
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum BeeswarmPriority {
+    #[default]
+    Ascending,  // Lowest y first
+    Descending, // Highest y first
+    DataOrder,  // Row order ("none")
+}
+
+impl BeeswarmPriority {
+    pub const ALL: [BeeswarmPriority; 3] = [BeeswarmPriority::Ascending, BeeswarmPriority::Descending, BeeswarmPriority::DataOrder];
+
+    /// DSL name
+    pub fn name(self) -> &'static str {
+        match self {
+            BeeswarmPriority::Ascending => "ascending",
+            BeeswarmPriority::Descending => "descending",
+            BeeswarmPriority::DataOrder => "none",
+        }
+    }
+}
+
+/// Arrow heads of a segment()
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct Arrow {
+    pub head: ArrowType,
+    pub ends: ArrowEnds,
+}
+
+/// How a segment() arrow head is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum ArrowType {
+    #[default]
+    Closed, // Filled triangle
+    Open,   // Two lines in a V
+}
+
+impl ArrowType {
+    pub const ALL: [ArrowType; 2] = [ArrowType::Closed, ArrowType::Open];
+
+    /// DSL name
+    pub fn name(self) -> &'static str {
+        match self {
+            ArrowType::Closed => "closed",
+            ArrowType::Open => "open",
+        }
+    }
+}
+
+/// Which ends of a segment() carry an arrow head
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum ArrowEnds {
+    First, // At (x, y)
+    #[default]
+    Last,  // At (xend, yend)
+    Both,
+}
+
+impl ArrowEnds {
+    pub const ALL: [ArrowEnds; 3] = [ArrowEnds::First, ArrowEnds::Last, ArrowEnds::Both];
+
+    /// DSL name
+    pub fn name(self) -> &'static str {
+        match self {
+            ArrowEnds::First => "first",
+            ArrowEnds::Last => "last",
+            ArrowEnds::Both => "both",
+        }
+    }
 }
 
 /// Point geometry layer
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct PointLayer {
     pub stat: Stat,
     // Aesthetic overrides
@@ -266,10 +940,31 @@ pub struct PointLayer {
     pub size: Option<AestheticValue<f64>>,
     pub shape: Option<AestheticValue<String>>,
     pub alpha: Option<AestheticValue<f64>>,
+    pub fill: Option<String>, // Fill color inside a `color` outline
+
+    // pointrange(): an interval drawn through each point
+    pub range: Option<PointRange>,
+    // crossbar(): the interval as a box with a line at y, in place of the point and its line
+    pub crossbar: bool,
+    pub width: Option<f64>,        // Crossbar box (or dodging) width 0-1, like bar widths
+    pub position: LayerPosition,   // Identity or Dodge (pointrange/crossbar only)
+}
+
+impl PointLayer {
+    pub const DEFAULT_CROSSBAR_WIDTH: f64 = 0.8;
+}
+
+/// Interval columns of a pointrange(): ymin/ymax, or xmin/xmax for a horizontal range
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct PointRange {
+    pub ymin: Option<String>,
+    pub ymax: Option<String>,
+    pub xmin: Option<String>,
+    pub xmax: Option<String>,
 }
 
 /// Bar geometry layer
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct BarLayer {
     pub stat: Stat,
     // Aesthetic overrides
@@ -281,12 +976,29 @@ pub struct BarLayer {
     pub alpha: Option<AestheticValue<f64>>,
     pub width: Option<AestheticValue<f64>>, // Bar width (0.0-1.0, relative to category spacing)
 
+    // Outline color and width in pixels (None = no outline), and unfilled bars (fill: "none")
+    pub border: Option<String>,
+    pub border_width: Option<f64>,
+    pub hollow: bool,
+
     // Positioning strategy
-    pub position: BarPosition,
+    pub position: LayerPosition,
+
+    // col(): always uses raw y values, never falls back to counting
+    pub require_y: bool,
+
+    // histogram(stat_output_as: "binned"): later layers on the same x reuse the bins
+    pub stat_output_as: Option<StatOutput>,
+}
+
+/// How a layer's stat output is shared with the layers after it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum StatOutput {
+    Binned, // (bin, count) per histogram bar
 }
 
 /// Ribbon geometry layer
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct RibbonLayer {
     pub stat: Stat,
     // Aesthetic overrides
@@ -297,10 +1009,12 @@ pub struct RibbonLayer {
     // Visual properties
     pub color: Option<AestheticValue<String>>, // Used for fill
     pub alpha: Option<AestheticValue<f64>>,
+    pub border: Option<String>,   // Color of the ymin/ymax edge lines (None = no outline)
+    pub linetype: Option<String>, // Dash pattern of the edge lines
 }
 
 /// Boxplot geometry layer
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct BoxplotLayer {
     pub stat: Stat,
     // Aesthetic overrides
@@ -312,6 +1026,10 @@ pub struct BoxplotLayer {
     pub fill: Option<AestheticValue<String>>,  // Fill color
     pub alpha: Option<AestheticValue<f64>>,
     pub width: Option<AestheticValue<f64>>,    // Box width
+    pub varwidth: bool,                        // Scale box width by sqrt(group count)
+    pub notch: bool,                           // Narrow the box to a waist over the median's 95% interval
+    pub notchwidth: Option<f64>,               // Waist width as a fraction of the box width (default 0.5)
+    pub staplewidth: Option<f64>,              // Whisker cap width as a fraction of the box width (default 0.5, 0 = no caps)
 
     // Outlier properties
     pub outlier_color: Option<String>,
@@ -320,7 +1038,7 @@ pub struct BoxplotLayer {
 }
 
 /// Violin geometry layer
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ViolinLayer {
     pub stat: Stat,
     // Aesthetic overrides
@@ -334,34 +1052,211 @@ pub struct ViolinLayer {
 
     // Violin-specific options
     pub draw_quantiles: Vec<f64>,  // Quantile lines to draw inside violin (e.g., [0.25, 0.5, 0.75])
+    pub scale: ViolinScale,
+    pub trim: bool,                // Cut the density at the data range (false = show KDE tails)
+    pub side: ViolinSide,          // Half violins for raincloud plots
+}
+
+impl Default for ViolinLayer {
+    fn default() -> Self {
+        ViolinLayer {
+            stat: Stat::default(),
+            x: None,
+            y: None,
+            color: None,
+            alpha: None,
+            width: None,
+            draw_quantiles: Vec::new(),
+            scale: ViolinScale::default(),
+            trim: true,
+            side: ViolinSide::default(),
+        }
+    }
+}
+
+/// Tile geometry layer (heatmaps)
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct TileLayer {
+    pub stat: Stat,
+    // Aesthetic overrides
+    pub x: Option<String>,
+    pub y: Option<String>,
+
+    // Visual properties
+    pub fill: Option<AestheticValue<String>>, // Numeric column (through the fill scale) or fixed color
+    pub color: Option<String>,                // Border color (None = no border)
+    pub alpha: Option<f64>,
+    pub width: Option<f64>,  // Tile size in data units (default: spacing of the x values)
+    pub height: Option<f64>, // Default: spacing of the y values
+    pub na_color: Option<String>, // Color of x/y grid cells missing from the data (default TileLayer::DEFAULT_NA_COLOR)
+    pub drop_na: bool,            // Leave missing grid cells empty instead
+}
+
+impl TileLayer {
+    pub const DEFAULT_NA_COLOR: &'static str = "grey";
+}
+
+/// Text geometry layer (one label per row, centered on x/y)
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct TextLayer {
+    pub stat: Stat,
+    // Aesthetic overrides
+    pub x: Option<String>,
+    pub y: Option<String>,
+    pub label: Option<AestheticValue<String>>, // Column of label text, or the same text for every row
+
+    // Visual properties
+    pub color: Option<AestheticValue<String>>,
+    pub size: Option<f64>, // Font size in pixels (default TextLayer::DEFAULT_SIZE)
+    pub alpha: Option<f64>,
+    pub check_overlap: bool, // Skip labels that would overlap one already placed (in data order)
+    pub nudge_x: f64,        // Offset from the data point, in data units
+    pub nudge_y: f64,
+
+    // label(): background box behind each label (None = bare text)
+    pub label_box: Option<LabelBox>,
+}
+
+impl TextLayer {
+    pub const DEFAULT_SIZE: f64 = 12.0;
+}
+
+/// Background box drawn behind label() text
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LabelBox {
+    pub fill: String,
+    pub padding: f64, // Space between the text and the box edge, in pixels
+    pub border: f64,  // Border width in pixels (0 = no border), drawn in the text color
+}
+
+impl LabelBox {
+    pub const DEFAULT_FILL: &'static str = "white";
+    pub const DEFAULT_PADDING: f64 = 4.0;
+    pub const DEFAULT_BORDER: f64 = 1.0;
+}
+
+impl Default for LabelBox {
+    fn default() -> Self {
+        LabelBox {
+            fill: Self::DEFAULT_FILL.to_string(),
+            padding: Self::DEFAULT_PADDING,
+            border: Self::DEFAULT_BORDER,
+        }
+    }
+}
+
+/// Hexagonal binning layer (2D density of x/y points)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HexbinLayer {
+    pub stat: Stat, // Stat::Hexbin { bins }
+    // Aesthetic overrides
+    pub x: Option<String>,
+    pub y: Option<String>,
+
+    // Visual properties (cells are filled by `fill` through the fill scale)
+    pub color: Option<String>, // Cell border color (None = no border)
+    pub alpha: Option<f64>,
+    pub fill: HexSummary,      // Per-cell value the fill color shows
+    pub size_by_count: bool,   // hexbin(size: "count"): cell area proportional to its count
+}
+
+/// Per-cell summary a hexbin(fill: ...) maps through the fill scale
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum HexSummary {
+    #[default]
+    Count,
+    MeanY,
+    SumY,
+    MinY,
+    MaxY,
+}
+
+impl HexSummary {
+    pub const ALL: [HexSummary; 5] = [HexSummary::Count, HexSummary::MeanY, HexSummary::SumY, HexSummary::MinY, HexSummary::MaxY];
+
+    /// DSL name, also the default fill legend title
+    pub fn name(self) -> &'static str {
+        match self {
+            HexSummary::Count => "count",
+            HexSummary::MeanY => "mean_y",
+            HexSummary::SumY => "sum_y",
+            HexSummary::MinY => "min_y",
+            HexSummary::MaxY => "max_y",
+        }
+    }
 }
 
-/// Bar positioning modes (how bars are arranged)
-#[derive(Debug, Clone, PartialEq)]
-pub enum BarPosition {
-    Identity, // Bars overlap at same x position
+impl HexbinLayer {
+    pub const DEFAULT_BINS: usize = 30;
+}
+
+impl Default for HexbinLayer {
+    fn default() -> Self {
+        HexbinLayer {
+            stat: Stat::Hexbin { bins: Self::DEFAULT_BINS },
+            x: None,
+            y: None,
+            color: None,
+            alpha: None,
+            fill: HexSummary::default(),
+            size_by_count: false,
+        }
+    }
+}
+
+/// How violin widths are normalized against each other
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub enum ViolinScale {
+    #[default]
+    Width,  // Every violin has the same maximum width
+    Count,  // Widths proportional to group size (area ~ n)
+}
+
+/// Which half of the violin is drawn (from the category center outward)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum ViolinSide {
+    #[default]
+    Both,
+    Left,  // Below the center when flipped
+    Right, // Above the center when flipped
+}
+
+/// Positioning modes: how bars, or the intervals of crossbar()/pointrange(), sharing an x are arranged
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub enum LayerPosition {
+    #[default]
+    Identity, // Bars overlap at same x position, each at its own height (no stacking or normalization)
     Dodge,    // Bars side-by-side
     Stack,    // Bars stacked vertically
+    Fill,     // Bars stacked and normalized to proportions (0-1)
+    Percent,  // Alias for Fill
 }
 
-impl Default for BarPosition {
-    fn default() -> Self {
-        BarPosition::Identity
+impl LayerPosition {
+    /// Stacked and normalized to proportions (position "fill" or its alias "percent")
+    pub fn is_fill(&self) -> bool {
+        matches!(self, LayerPosition::Fill | LayerPosition::Percent)
     }
 }
 
 /// Plot labels (title, axes)
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub struct Labels {
     pub title: Option<String>,
     pub subtitle: Option<String>,
     pub x: Option<String>, // Renamed from x_label for ggplot2 parity
     pub y: Option<String>, // Renamed from y_label
     pub caption: Option<String>,
+    // Legend titles for mapped aesthetics
+    pub color_title: Option<String>,
+    pub fill_title: Option<String>,
+    pub size_title: Option<String>,
+    pub shape_title: Option<String>,
+    pub linetype_title: Option<String>,
 }
 
 /// Facet specification for creating subplot grids
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Facet {
     /// Column name to facet by (creates one subplot per unique value)
     pub by: String,
@@ -369,12 +1264,41 @@ pub struct Facet {
     pub ncol: Option<usize>,
     /// Axis scale sharing mode
     pub scales: FacetScales,
+    /// Panel edge the strip label is drawn on
+    pub strip_position: StripPosition,
+    /// Panel size allocation
+    pub space: FacetSpace,
+}
+
+/// Facet panel size allocation
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum FacetSpace {
+    /// Equal-sized panels (default)
+    #[default]
+    Fixed,
+    /// Row heights proportional to the y range of their panels (pair with scales: "free_y")
+    FreeY,
+}
+
+/// Facet strip label placement
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum StripPosition {
+    /// Above each panel (default)
+    #[default]
+    Top,
+    /// Below each panel
+    Bottom,
+    /// Left of each panel, text rotated to read bottom-to-top
+    Left,
+    /// Right of each panel, text rotated to read top-to-bottom
+    Right,
 }
 
 /// Facet axis scale sharing modes
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub enum FacetScales {
     /// All facets share the same x and y ranges (default)
+    #[default]
     Fixed,
     /// Independent x ranges, shared y range
     FreeX,
@@ -383,9 +1307,3 @@ pub enum FacetScales {
     /// Independent x and y ranges for each facet
     Free,
 }
-
-impl Default for FacetScales {
-    fn default() -> Self {
-        FacetScales::Fixed
-    }
-}
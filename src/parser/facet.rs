@@ -1,6 +1,6 @@
 // Facet parser for facet_wrap() syntax
 
-use super::ast::{Facet, FacetScales};
+use super::ast::{Facet, FacetScales, FacetSpace, StripPosition};
 use super::lexer::{identifier, ws};
 use nom::{
     bytes::complete::tag,
@@ -10,10 +10,12 @@ use nom::{
 };
 
 /// Parse facet_wrap specification
-/// Format: facet_wrap(by: column_name, ncol: 2, scales: "free_x")
+/// Format: facet_wrap(by: column_name, ncol: 2, scales: "free_x", strip_position: "bottom")
 /// - by: required (column name to facet by)
 /// - ncol: optional (number of columns in grid)
 /// - scales: optional (axis sharing mode: "fixed", "free_x", "free_y", "free")
+/// - strip_position: optional (strip label edge: "top", "bottom", "left", "right")
+/// - space: optional (panel sizes: "fixed", or "free_y" for row heights proportional to their y ranges)
 pub fn parse_facet_wrap(input: &str) -> IResult<&str, Facet> {
     // Parse function name
     let (input, _) = ws(tag("facet_wrap"))(input)?;
@@ -31,12 +33,16 @@ pub fn parse_facet_wrap(input: &str) -> IResult<&str, Facet> {
     let mut by = None;
     let mut ncol = None;
     let mut scales = FacetScales::default();
+    let mut strip_position = StripPosition::default();
+    let mut space = FacetSpace::default();
 
     for (key, value) in args {
         match key.as_str() {
             "by" => by = Some(value.column),
             "ncol" => ncol = value.ncol,
             "scales" => scales = value.scales.unwrap_or_default(),
+            "strip_position" => strip_position = value.strip_position.unwrap_or_default(),
+            "space" => space = value.space.unwrap_or_default(),
             _ => {}
         }
     }
@@ -49,7 +55,7 @@ pub fn parse_facet_wrap(input: &str) -> IResult<&str, Facet> {
         ))
     })?;
 
-    Ok((input, Facet { by, ncol, scales }))
+    Ok((input, Facet { by, ncol, scales, strip_position, space }))
 }
 
 /// Parse a single facet argument (key: value pair)
@@ -79,6 +85,38 @@ fn parse_facet_argument(input: &str) -> IResult<&str, (String, FacetArgValue)> {
             };
             (input, FacetArgValue::scales(scales))
         }
+        "strip_position" => {
+            let value_start = input;
+            let (input, _) = ws(char('"'))(input)?;
+            let (input, position_str) = nom::bytes::complete::take_while(|c: char| c != '"')(input)?;
+            let (input, _) = ws(char('"'))(input)?;
+            let position = match position_str {
+                "top" => StripPosition::Top,
+                "bottom" => StripPosition::Bottom,
+                "left" => StripPosition::Left,
+                "right" => StripPosition::Right,
+                _ => return Err(nom::Err::Failure(nom::error::Error::new(
+                    value_start,
+                    nom::error::ErrorKind::Verify,
+                ))),
+            };
+            (input, FacetArgValue::strip_position(position))
+        }
+        "space" => {
+            let value_start = input;
+            let (input, _) = ws(char('"'))(input)?;
+            let (input, space_str) = nom::bytes::complete::take_while(|c: char| c != '"')(input)?;
+            let (input, _) = ws(char('"'))(input)?;
+            let space = match space_str {
+                "fixed" => FacetSpace::Fixed,
+                "free_y" => FacetSpace::FreeY,
+                _ => return Err(nom::Err::Failure(nom::error::Error::new(
+                    value_start,
+                    nom::error::ErrorKind::Verify,
+                ))),
+            };
+            (input, FacetArgValue::space(space))
+        }
         _ => {
             // Unknown argument, skip it
             let (input, col) = ws(identifier)(input)?;
@@ -95,6 +133,8 @@ struct FacetArgValue {
     column: String,
     ncol: Option<usize>,
     scales: Option<FacetScales>,
+    strip_position: Option<StripPosition>,
+    space: Option<FacetSpace>,
 }
 
 impl FacetArgValue {
@@ -103,6 +143,8 @@ impl FacetArgValue {
             column: s,
             ncol: None,
             scales: None,
+            strip_position: None,
+            space: None,
         }
     }
 
@@ -111,6 +153,8 @@ impl FacetArgValue {
             column: String::new(),
             ncol: Some(n),
             scales: None,
+            strip_position: None,
+            space: None,
         }
     }
 
@@ -119,6 +163,28 @@ impl FacetArgValue {
             column: String::new(),
             ncol: None,
             scales: Some(s),
+            strip_position: None,
+            space: None,
+        }
+    }
+
+    fn strip_position(p: StripPosition) -> Self {
+        Self {
+            column: String::new(),
+            ncol: None,
+            scales: None,
+            strip_position: Some(p),
+            space: None,
+        }
+    }
+
+    fn space(s: FacetSpace) -> Self {
+        Self {
+            column: String::new(),
+            ncol: None,
+            scales: None,
+            strip_position: None,
+            space: Some(s),
         }
     }
 }
@@ -181,6 +247,33 @@ mod tests {
         assert_eq!(facet.scales, FacetScales::FreeX);
     }
 
+    #[test]
+    fn test_parse_facet_wrap_strip_position() {
+        let (_, facet) = parse_facet_wrap("facet_wrap(by: region)").unwrap();
+        assert_eq!(facet.strip_position, StripPosition::Top);
+
+        let (_, facet) = parse_facet_wrap(r#"facet_wrap(by: region, strip_position: "bottom")"#).unwrap();
+        assert_eq!(facet.strip_position, StripPosition::Bottom);
+
+        let (_, facet) = parse_facet_wrap(r#"facet_wrap(by: region, strip_position: "left")"#).unwrap();
+        assert_eq!(facet.strip_position, StripPosition::Left);
+
+        let result = parse_facet_wrap(r#"facet_wrap(by: region, strip_position: "middle")"#);
+        assert!(matches!(result, Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_facet_wrap_space() {
+        let (_, facet) = parse_facet_wrap("facet_wrap(by: region)").unwrap();
+        assert_eq!(facet.space, FacetSpace::Fixed);
+
+        let (_, facet) = parse_facet_wrap(r#"facet_wrap(by: region, scales: "free_y", space: "free_y")"#).unwrap();
+        assert_eq!(facet.space, FacetSpace::FreeY);
+
+        let result = parse_facet_wrap(r#"facet_wrap(by: region, space: "free_x")"#);
+        assert!(matches!(result, Err(nom::Err::Failure(_))));
+    }
+
     #[test]
     fn test_parse_facet_wrap_missing_by() {
         // Missing required "by" argument should fail
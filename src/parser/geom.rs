@@ -1,14 +1,15 @@
 // Geometry (geom) parser for Grammar of Graphics DSL
 
-use super::ast::{AestheticValue, BarLayer, BarPosition, BoxplotLayer, Layer, LineLayer, PointLayer, RibbonLayer, ViolinLayer};
+use super::ast::{AestheticValue, Arrow, ArrowEnds, ArrowType, BandwidthMethod, BarLayer, BeeswarmPriority, LayerPosition, BoxplotLayer, StatOutput, HexSummary, HexbinLayer, LabelBox, Layer, LineLayer, PointLayer, PointRange, RibbonLayer, SeType, SmoothMethod, StepDirection, SummaryExpr, SummaryFun, TextLayer, TileLayer, ViolinLayer, ViolinScale, ViolinSide};
 use super::lexer::{identifier, number_literal, string_literal, ws};
+use crate::theme_resolve::parse_color;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::char,
-    combinator::{map, opt},
-    multi::separated_list0,
-    sequence::preceded,
+    character::complete::{char, one_of},
+    combinator::{all_consuming, map},
+    multi::{many0, separated_list0},
+    sequence::{pair, preceded},
     IResult,
 };
 
@@ -22,6 +23,257 @@ enum ArgValue {
     NumberArray(Vec<f64>),     // draw_quantiles: [0.25, 0.5, 0.75]
 }
 
+/// Description of a geometry for --list-geoms
+pub struct GeomInfo {
+    pub name: &'static str,
+    pub required: &'static [&'static str],          // Required aesthetics
+    pub optional: &'static [(&'static str, &'static str)], // (parameter, description)
+}
+
+/// All geometries accepted by parse_geom
+pub const GEOMS: &[GeomInfo] = &[
+    GeomInfo { name: "line", required: &["x", "y"], optional: &[
+        ("color", "line color, literal or column"),
+        ("width", "line width, number or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("linetype", "\"solid\", \"dashed\", ... or column"),
+    ] },
+    GeomInfo { name: "step", required: &["x", "y"], optional: &[
+        ("direction", "\"hv\" (default), \"vh\" or \"mid\" (step halfway between x values)"),
+        ("color", "line color, literal or column"),
+        ("width", "line width, number or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("linetype", "\"solid\", \"dashed\", ... or column"),
+    ] },
+    GeomInfo { name: "point", required: &["x", "y"], optional: &[
+        ("color", "point color, literal or column"),
+        ("size", "point size, number or column"),
+        ("shape", "point shape (\"circle\", \"square\", \"triangle\", \"diamond\", or \"_open\" variants), literal or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("fill", "fill color inside a color outline"),
+    ] },
+    GeomInfo { name: "pointrange", required: &["x", "y", "ymin", "ymax"], optional: &[
+        ("xmin", "left end of a horizontal range, column (with xmax; draws the plot flipped)"),
+        ("xmax", "right end of a horizontal range, column"),
+        ("color", "point and range color, literal or column"),
+        ("size", "point size, number or column"),
+        ("shape", "point shape, literal or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("position", "\"identity\" (default) or \"dodge\": side by side per color group"),
+        ("width", "dodging width 0-1, shared by the groups at each x (default 0.8)"),
+    ] },
+    GeomInfo { name: "crossbar", required: &["x", "y", "ymin", "ymax"], optional: &[
+        ("xmin", "left end of a horizontal box, column (with xmax; draws the plot flipped)"),
+        ("xmax", "right end of a horizontal box, column"),
+        ("color", "box and middle line color, literal or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("width", "box width 0-1, split between the groups at each x when dodged (default 0.8)"),
+        ("position", "\"identity\" (default, overlaid) or \"dodge\": side by side per color group"),
+    ] },
+    GeomInfo { name: "beeswarm", required: &["x", "y"], optional: &[
+        ("priority", "placement order: \"ascending\" (default), \"descending\" or \"none\" (row order)"),
+        ("cex", "spacing between dots relative to their size (default 1)"),
+        ("groupOnX", "categories along x; only true is supported, use coord_flip() for horizontal swarms"),
+        ("color", "point color, literal or column"),
+        ("size", "point size, number or column"),
+        ("shape", "point shape, literal or column"),
+        ("alpha", "opacity 0-1, number or column"),
+    ] },
+    GeomInfo { name: "bar", required: &["x"], optional: &[
+        ("y", "bar height column (rows are counted without it)"),
+        ("color", "bar color, literal or column (alias: fill)"),
+        ("width", "bar width 0-1, number or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("border", "outline color (fill: \"none\" for unfilled bars)"),
+        ("border_width", "outline width in pixels"),
+        ("position", "\"identity\", \"dodge\", \"stack\" or \"fill\""),
+        ("stat", "\"identity\", \"count\" or \"prop\""),
+    ] },
+    GeomInfo { name: "col", required: &["x", "y"], optional: &[
+        ("color", "bar color, literal or column (alias: fill)"),
+        ("width", "bar width 0-1, number or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("border", "outline color (fill: \"none\" for unfilled bars)"),
+        ("border_width", "outline width in pixels"),
+        ("position", "\"identity\", \"dodge\", \"stack\" or \"fill\""),
+    ] },
+    GeomInfo { name: "ribbon", required: &["x", "ymin", "ymax"], optional: &[
+        ("color", "fill color, literal or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("border", "color of the upper and lower edge lines (default: none)"),
+        ("linetype", "edge line dash: \"solid\", \"dashed\", ..."),
+    ] },
+    GeomInfo { name: "histogram", required: &["x"], optional: &[
+        ("bins", "number of bins (default 30)"),
+        ("color", "bar color, literal or column (alias: fill)"),
+        ("width", "bar width 0-1, number or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("border", "outline color (fill: \"none\" for unfilled bars)"),
+        ("border_width", "outline width in pixels"),
+        ("position", "\"identity\", \"dodge\", \"stack\" or \"fill\""),
+    ] },
+    GeomInfo { name: "smooth", required: &["x", "y"], optional: &[
+        ("method", "\"lm\", \"poly\" or \"loess\""),
+        ("n", "number of evaluation points (default 80)"),
+        ("span", "loess neighborhood fraction, 0 < span <= 1 (default 0.75)"),
+        ("residuals", "also plot observed - fitted at each x (default false)"),
+        ("fullrange", "extend the fit across the whole plot's x range, not just the group's (default false)"),
+        ("se", "interval band: true or \"confidence\" (the mean), \"prediction\" (new observations); straight-line fits only"),
+        ("level", "interval level, 0 < level < 1 (default 0.95)"),
+    ] },
+    GeomInfo { name: "quantile", required: &["x", "y"], optional: &[
+        ("quantiles", "quantiles to fit, each 0 < q < 1 (default [0.25, 0.5, 0.75])"),
+        ("method", "\"rq\" (linear quantile regression, the only method)"),
+    ] },
+    GeomInfo { name: "ecdf", required: &["x"], optional: &[
+        ("pad", "extend the steps to 0 below and 1 above the data (default true)"),
+        ("n", "number of evaluation points (default: every unique x)"),
+    ] },
+    GeomInfo { name: "density", required: &["x"], optional: &[
+        ("bw", "bandwidth: \"nrd0\", \"nrd\", \"ucv\" or a number"),
+        ("n", "number of evaluation points (default 512)"),
+        ("fill", "shade the area under each curve (default false)"),
+    ] },
+    GeomInfo { name: "contour", required: &["x", "y"], optional: &[
+        ("bins", "number of equally spaced density levels (default 10)"),
+        ("filled", "fill the bands between levels instead of drawing lines (default false)"),
+        ("bandwidth_x", "kernel bandwidth along x (default: Silverman's rule)"),
+        ("bandwidth_y", "kernel bandwidth along y (default: Silverman's rule)"),
+        ("color", "line color, literal or column"),
+        ("width", "line width, number or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("linetype", "\"solid\", \"dashed\", \"dotted\", \"dashdot\" or column"),
+    ] },
+    GeomInfo { name: "contour_filled", required: &["x", "y"], optional: &[
+        ("bins", "number of equally spaced density levels (default 10)"),
+        ("alpha", "opacity 0-1 of the bands"),
+    ] },
+    GeomInfo { name: "density_2d", required: &["x", "y"], optional: &[
+        ("bins", "number of equally spaced density levels (default 10)"),
+        ("bandwidth_x", "kernel bandwidth along x (default: Silverman's rule)"),
+        ("bandwidth_y", "kernel bandwidth along y (default: Silverman's rule)"),
+        ("filled", "fill the bands between levels instead of drawing lines (default false)"),
+        ("color", "line color, literal or column"),
+        ("width", "line width, number or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("linetype", "\"solid\", \"dashed\", \"dotted\", \"dashdot\" or column"),
+    ] },
+    GeomInfo { name: "abline", required: &[], optional: &[
+        ("slope", "line slope, number or column (default 1)"),
+        ("intercept", "y at x = 0, number or column (default 0)"),
+        ("color", "line color, literal or column"),
+        ("width", "line width, number or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("linetype", "\"solid\", \"dashed\", ... or column"),
+        ("legend", "legend entry label, shown even if the line misses the panel"),
+    ] },
+    GeomInfo { name: "crosshair", required: &["x", "y"], optional: &[
+        ("color", "line color, literal or column"),
+        ("width", "line width, number or column"),
+        ("alpha", "opacity 0-1, number or column (e.g. 0.1 for a density crosshair)"),
+        ("linetype", "\"solid\", \"dashed\", ... or column"),
+        ("legend", "legend entry label"),
+    ] },
+    GeomInfo { name: "rug", required: &["x"], optional: &[
+        ("length", "tick band height as a fraction of the y range (default 0.03)"),
+        ("jitter", "stagger shorter ticks within the band so overlapping ones separate (default false)"),
+        ("color", "tick color, literal or column"),
+        ("width", "tick width, number or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("linetype", "\"solid\", \"dashed\", ... or column"),
+    ] },
+    GeomInfo { name: "segment", required: &["x", "y", "xend", "yend"], optional: &[
+        ("color", "line color, literal or column"),
+        ("width", "line width, number or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("linetype", "\"solid\", \"dashed\", ... or column"),
+        ("arrow", "draw an arrow head (default false)"),
+        ("arrow_type", "\"closed\" (filled triangle, default) or \"open\" (V-shaped), with arrow: true"),
+        ("arrow_ends", "\"last\" (default), \"first\" or \"both\", with arrow: true"),
+    ] },
+    GeomInfo { name: "stat_summary", required: &["x", "y"], optional: &[
+        ("fun", "point position, a formula such as \"mean\" or \"median\" (default \"mean\")"),
+        ("fun_ymin", "lower end of the range (default \"mean - se\")"),
+        ("fun_ymax", "upper end of the range (default \"mean + se\")"),
+    ] },
+    GeomInfo { name: "boxplot", required: &["x", "y"], optional: &[
+        ("color", "box color, literal or column"),
+        ("width", "box width 0-1, number or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("varwidth", "scale box widths by sqrt(group size)"),
+        ("notch", "narrow the box at the median's 95% confidence interval"),
+        ("notchwidth", "notch waist as a fraction of the box width (default 0.5)"),
+        ("staplewidth", "whisker cap width as a fraction of the box width (default 0.5, 0 = no caps)"),
+        ("outlier_color", "outlier point color"),
+        ("outlier_size", "outlier point size"),
+        ("outlier_shape", "outlier point shape"),
+    ] },
+    GeomInfo { name: "violin", required: &["x", "y"], optional: &[
+        ("color", "violin color, literal or column"),
+        ("width", "violin width 0-1, number or column"),
+        ("alpha", "opacity 0-1, number or column"),
+        ("draw_quantiles", "quantile lines, e.g. [0.25, 0.5, 0.75]"),
+        ("bw", "bandwidth: \"nrd0\", \"nrd\", \"ucv\" or a number"),
+        ("scale", "\"width\" or \"count\""),
+        ("trim", "cut the density at the data range (default true)"),
+        ("side", "\"both\", \"left\" or \"right\" (half violins for raincloud plots)"),
+    ] },
+    GeomInfo { name: "tile", required: &["x", "y"], optional: &[
+        ("fill", "numeric column (see scale_fill_continuous) or fixed color"),
+        ("color", "tile border color"),
+        ("alpha", "opacity 0-1"),
+        ("width", "tile width in data units (default: x spacing)"),
+        ("height", "tile height in data units (default: y spacing)"),
+        ("na_color", "color of x/y grid cells missing from the data (default \"grey\")"),
+        ("drop_na", "leave missing grid cells empty (default false)"),
+    ] },
+    GeomInfo { name: "hexbin", required: &["x", "y"], optional: &[
+        ("bins", "number of hexagons across the x range (default 30)"),
+        ("color", "hexagon border color"),
+        ("alpha", "opacity 0-1"),
+        ("fill", "\"count\" (default), \"mean_y\", \"sum_y\", \"min_y\" or \"max_y\" per cell"),
+        ("size", "\"count\": hexagon area proportional to the cell's count"),
+    ] },
+    GeomInfo { name: "text", required: &["x", "y", "label"], optional: &[
+        ("color", "text color, literal or column"),
+        ("size", "font size in pixels (default 12)"),
+        ("alpha", "opacity 0-1"),
+        ("check_overlap", "skip labels overlapping an earlier one (default false)"),
+        ("nudge_x", "horizontal offset in data units"),
+        ("nudge_y", "vertical offset in data units"),
+    ] },
+    GeomInfo { name: "label", required: &["x", "y", "label"], optional: &[
+        ("color", "text and border color, literal or column"),
+        ("fill", "box fill color (default \"white\")"),
+        ("label_padding", "space around the text in pixels (default 4)"),
+        ("label_size", "box border width in pixels, 0 for none (default 1)"),
+        ("size", "font size in pixels (default 12)"),
+        ("alpha", "opacity 0-1"),
+        ("check_overlap", "skip labels overlapping an earlier one (default false)"),
+        ("nudge_x", "horizontal offset in data units"),
+        ("nudge_y", "vertical offset in data units"),
+    ] },
+];
+
+/// Statistical transformations, with the geoms that select them
+pub const STATS: &[(&str, &str)] = &[
+    ("identity", "plot values as-is (default for most geoms)"),
+    ("count", "number of rows per x value: bar() without y, bar(stat: \"count\")"),
+    ("prop", "proportion of rows per x value: bar(stat: \"prop\")"),
+    ("sum", "sum of the y values per x value: bar(stat: \"sum\"), also \"mean\", \"max\", \"min\""),
+    ("bin", "counts per numeric bin: histogram(bins: n)"),
+    ("smooth", "fitted trend line: smooth(method: ...)"),
+    ("quantile", "quantile regression lines: quantile(quantiles: [...])"),
+    ("ecdf", "empirical cumulative distribution: ecdf(pad: true)"),
+    ("density", "kernel density estimate of x per group: density(bw: \"nrd0\")"),
+    ("contour", "2D kernel density level lines: contour(bins: n)"),
+    ("beeswarm", "points spread sideways within each x category: beeswarm(priority: \"ascending\")"),
+    ("summary", "point range per x value: stat_summary(fun_ymin: \"mean - sd\", fun_ymax: \"mean + sd\")"),
+    ("boxplot", "five-number summary and outliers: boxplot()"),
+    ("violin", "kernel density estimate: violin()"),
+    ("hexbin", "point counts per hexagonal cell: hexbin(bins: n)"),
+];
+
 /// Parse a number array like [0.25, 0.5, 0.75]
 fn parse_number_array(input: &str) -> IResult<&str, Vec<f64>> {
     let (input, _) = ws(char('['))(input)?;
@@ -34,6 +286,34 @@ fn parse_number_array(input: &str) -> IResult<&str, Vec<f64>> {
 /// Format: line() or line(color: "red", width: 2, ...) or line(color: region)
 pub fn parse_line(input: &str) -> IResult<&str, Layer> {
     let (input, _) = ws(tag("line"))(input)?;
+    let args_start = input;
+    let (input, (layer, direction)) = parse_line_args(input)?;
+
+    // Only step() has a direction
+    if direction.is_some() {
+        return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify)));
+    }
+    Ok((input, Layer::Line(layer)))
+}
+
+/// Parse a step geometry (a line drawn as stairs)
+/// Format: step() or step(direction: "vh") or step(direction: "mid", color: "red", ...)
+pub fn parse_step(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("step"))(input)?;
+    let args_start = input;
+    let (input, (mut layer, direction)) = parse_line_args(input)?;
+
+    layer.step = Some(match direction.as_deref() {
+        None | Some("hv") => StepDirection::Hv,
+        Some("vh") => StepDirection::Vh,
+        Some("mid") => StepDirection::Mid,
+        Some(_) => return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify))),
+    });
+    Ok((input, Layer::Line(layer)))
+}
+
+/// Parse the arguments shared by line() and step(), plus step()'s direction
+fn parse_line_args(input: &str) -> IResult<&str, (LineLayer, Option<String>)> {
     let (input, _) = ws(char('('))(input)?;
 
     // Parse optional named arguments
@@ -77,12 +357,26 @@ pub fn parse_line(input: &str) -> IResult<&str, Layer> {
                 preceded(ws(tag("alpha:")), ws(identifier)),
                 |a| ("alpha", ArgValue::NumericMapped(a)),
             ),
+            // linetype: can be "dashed" (literal), group_col (column)
+            map(
+                preceded(ws(tag("linetype:")), ws(string_literal)),
+                |t| ("linetype", ArgValue::ColorFixed(t)),
+            ),
+            map(
+                preceded(ws(tag("linetype:")), ws(identifier)),
+                |t| ("linetype", ArgValue::ColorMapped(t)),
+            ),
+            map(
+                preceded(ws(tag("direction:")), ws(string_literal)),
+                |d| ("direction", ArgValue::ColorFixed(d)),
+            ),
         )),
     )(input)?;
 
     let (input, _) = ws(char(')'))(input)?;
 
     let mut layer = LineLayer::default();
+    let mut direction = None;
 
     for (key, val) in args {
         match (key, val) {
@@ -94,17 +388,110 @@ pub fn parse_line(input: &str) -> IResult<&str, Layer> {
             ("width", ArgValue::NumericMapped(w)) => layer.width = Some(AestheticValue::Mapped(w)),
             ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(AestheticValue::Fixed(a)),
             ("alpha", ArgValue::NumericMapped(a)) => layer.alpha = Some(AestheticValue::Mapped(a)),
+            ("linetype", ArgValue::ColorFixed(t)) => layer.linetype = Some(AestheticValue::Fixed(t)),
+            ("linetype", ArgValue::ColorMapped(t)) => layer.linetype = Some(AestheticValue::Mapped(t)),
+            ("direction", ArgValue::ColorFixed(d)) => direction = Some(d),
             _ => {}
         }
     }
 
-    Ok((input, Layer::Line(layer)))
+    Ok((input, (layer, direction)))
 }
 
 /// Parse a point geometry
 /// Format: point() or point(size: 5, color: "blue", ...) or point(color: region, size: metric)
 pub fn parse_point(input: &str) -> IResult<&str, Layer> {
     let (input, _) = ws(tag("point"))(input)?;
+    let args_start = input;
+    let (rest, PointArgs { layer, range, swarm, position }) = parse_point_args(input)?;
+
+    // Interval columns only make sense for pointrange(), placement ones for beeswarm()
+    if range != PointRange::default() || swarm != SwarmArgs::default() || position.is_some() || layer.width.is_some() {
+        return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify)));
+    }
+    Ok((rest, Layer::Point(layer)))
+}
+
+/// Parse a pointrange geometry (a point with an interval through it)
+/// Format: pointrange(ymin: lo, ymax: hi) or pointrange(xmin: start, xmax: end) for a horizontal range
+pub fn parse_pointrange(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("pointrange"))(input)?;
+    parse_range_args(input, false)
+}
+
+/// Parse a crossbar geometry: a box from ymin to ymax with a line across it at y
+/// Format: crossbar(ymin: lo, ymax: hi) or crossbar(color: group, position: "dodge", width: 0.6)
+pub fn parse_crossbar(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("crossbar"))(input)?;
+    parse_range_args(input, true)
+}
+
+/// pointrange()/crossbar() arguments: the point arguments plus the interval and its position
+fn parse_range_args(input: &str, crossbar: bool) -> IResult<&str, Layer> {
+    let (rest, PointArgs { mut layer, range, swarm, position }) = parse_point_args(input)?;
+    let fail = || nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Verify));
+
+    // A horizontal range needs both ends
+    if range.xmin.is_some() != range.xmax.is_some() || swarm != SwarmArgs::default() {
+        return Err(fail());
+    }
+    layer.position = match position.as_deref() {
+        None | Some("identity") => LayerPosition::Identity,
+        Some("dodge") => LayerPosition::Dodge,
+        Some(_) => return Err(fail()),
+    };
+    if layer.width.is_some_and(|w| w <= 0.0) {
+        return Err(fail());
+    }
+    layer.range = Some(range);
+    layer.crossbar = crossbar;
+    Ok((rest, Layer::Point(layer)))
+}
+
+/// Parse a beeswarm geometry: points spread sideways within each x category so none overlap,
+/// placed in `priority` order ("ascending", "descending" or "none" for row order) as close to
+/// the category center as they fit. `cex` scales the spacing relative to the point size.
+/// Format: beeswarm() or beeswarm(priority: "descending", cex: 1.5, color: species)
+pub fn parse_beeswarm(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("beeswarm"))(input)?;
+    let args_start = input;
+    let (rest, PointArgs { mut layer, range, swarm, position }) = parse_point_args(input)?;
+    let fail = || nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify));
+
+    // Categories always run along x; coord_flip() gives horizontal swarms
+    if range != PointRange::default() || swarm.group_on_x == Some(false) || position.is_some() || layer.width.is_some() {
+        return Err(fail());
+    }
+    let priority = match swarm.priority {
+        Some(name) => BeeswarmPriority::ALL.into_iter().find(|p| p.name() == name).ok_or_else(fail)?,
+        None => BeeswarmPriority::default(),
+    };
+    let cex = swarm.cex.unwrap_or(1.0);
+    if cex <= 0.0 {
+        return Err(fail());
+    }
+    layer.stat = crate::parser::ast::Stat::Beeswarm { priority, cex };
+    Ok((rest, Layer::Point(layer)))
+}
+
+/// Arguments shared by point(), pointrange(), crossbar() and beeswarm()
+struct PointArgs {
+    layer: PointLayer,
+    range: PointRange, // Interval columns (pointrange/crossbar only)
+    swarm: SwarmArgs,  // Raw placement arguments (beeswarm only)
+    position: Option<String>, // Raw `position:` value (pointrange/crossbar only)
+}
+
+/// Raw beeswarm() arguments, validated by parse_beeswarm
+#[derive(Debug, Default, PartialEq)]
+struct SwarmArgs {
+    group_on_x: Option<bool>,
+    priority: Option<String>,
+    cex: Option<f64>,
+}
+
+/// Parse the parenthesized point arguments
+fn parse_point_args(input: &str) -> IResult<&str, PointArgs> {
     let (input, _) = ws(char('('))(input)?;
 
     // Parse optional named arguments
@@ -157,12 +544,29 @@ pub fn parse_point(input: &str) -> IResult<&str, Layer> {
                 preceded(ws(tag("alpha:")), ws(identifier)),
                 |a| ("alpha", ArgValue::NumericMapped(a)),
             ),
+            // fill: "white" fills the shape inside a color outline
+            map(preceded(ws(tag("fill:")), ws(string_literal)), |f| ("fill", ArgValue::ColorFixed(f))),
+            // ymin/ymax/xmin/xmax: interval columns (pointrange)
+            map(preceded(ws(tag("ymin:")), ws(identifier)), |c| ("ymin", ArgValue::ColumnName(c))),
+            map(preceded(ws(tag("ymax:")), ws(identifier)), |c| ("ymax", ArgValue::ColumnName(c))),
+            map(preceded(ws(tag("xmin:")), ws(identifier)), |c| ("xmin", ArgValue::ColumnName(c))),
+            map(preceded(ws(tag("xmax:")), ws(identifier)), |c| ("xmax", ArgValue::ColumnName(c))),
+            // groupOnX/priority/cex: dot placement (beeswarm)
+            map(preceded(ws(tag("groupOnX:")), ws(alt((tag("true"), tag("false"))))), |g| ("groupOnX", ArgValue::ColorFixed(g.to_string()))),
+            map(preceded(ws(tag("priority:")), ws(string_literal)), |p| ("priority", ArgValue::ColorFixed(p))),
+            map(preceded(ws(tag("cex:")), ws(number_literal)), |c| ("cex", ArgValue::NumericFixed(c))),
+            // width/position: box width and dodging (pointrange/crossbar)
+            map(preceded(ws(tag("width:")), ws(number_literal)), |w| ("width", ArgValue::NumericFixed(w))),
+            map(preceded(ws(tag("position:")), ws(string_literal)), |p| ("position", ArgValue::ColorFixed(p))),
         )),
     )(input)?;
 
     let (input, _) = ws(char(')'))(input)?;
 
     let mut layer = PointLayer::default();
+    let mut range = PointRange::default();
+    let mut swarm = SwarmArgs::default();
+    let mut position = None;
 
     for (key, val) in args {
         match (key, val) {
@@ -176,17 +580,63 @@ pub fn parse_point(input: &str) -> IResult<&str, Layer> {
             ("shape", ArgValue::ColorMapped(sh)) => layer.shape = Some(AestheticValue::Mapped(sh)),
             ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(AestheticValue::Fixed(a)),
             ("alpha", ArgValue::NumericMapped(a)) => layer.alpha = Some(AestheticValue::Mapped(a)),
+            ("fill", ArgValue::ColorFixed(f)) => layer.fill = Some(f),
+            ("ymin", ArgValue::ColumnName(c)) => range.ymin = Some(c),
+            ("ymax", ArgValue::ColumnName(c)) => range.ymax = Some(c),
+            ("xmin", ArgValue::ColumnName(c)) => range.xmin = Some(c),
+            ("xmax", ArgValue::ColumnName(c)) => range.xmax = Some(c),
+            ("groupOnX", ArgValue::ColorFixed(g)) => swarm.group_on_x = Some(g == "true"),
+            ("priority", ArgValue::ColorFixed(p)) => swarm.priority = Some(p),
+            ("cex", ArgValue::NumericFixed(c)) => swarm.cex = Some(c),
+            ("width", ArgValue::NumericFixed(w)) => layer.width = Some(w),
+            ("position", ArgValue::ColorFixed(p)) => position = Some(p),
             _ => {}
         }
     }
 
-    Ok((input, Layer::Point(layer)))
+    Ok((input, PointArgs { layer, range, swarm, position }))
 }
 
 /// Parse a bar geometry
 /// Format: bar() or bar(color: "red", position: "dodge", ...) or bar(color: region)
 pub fn parse_bar(input: &str) -> IResult<&str, Layer> {
     let (input, _) = ws(tag("bar"))(input)?;
+    let (rest, args) = parse_bar_args(input)?;
+
+    // bins and stat_output_as only make sense for histogram()
+    let known_stat = |s: &str| matches!(s, "identity" | "count" | "prop" | "sum" | "mean" | "max" | "min");
+    if args.stat.as_deref().is_some_and(|s| !known_stat(s)) || args.bins.is_some() || args.stat_output_as.is_some() {
+        return Err(nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Verify)));
+    }
+    Ok((rest, Layer::Bar(args.layer)))
+}
+
+/// Parse a column geometry (bar with identity stat, like ggplot2's geom_col)
+/// Format: col() or col(y: value, color: "red", position: "dodge", ...)
+pub fn parse_col(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("col"))(input)?;
+    let (rest, BarArgs { mut layer, stat, bins, stat_output_as }) = parse_bar_args(input)?;
+
+    // col always plots raw y values
+    if stat.is_some_and(|s| s != "identity") || bins.is_some() || stat_output_as.is_some() {
+        return Err(nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Verify)));
+    }
+    layer.stat = crate::parser::ast::Stat::Identity;
+    layer.require_y = true;
+
+    Ok((rest, Layer::Bar(layer)))
+}
+
+/// Arguments shared by bar(), col() and histogram()
+struct BarArgs {
+    layer: BarLayer,
+    stat: Option<String>, // Raw `stat:` value, for geoms that restrict it
+    bins: Option<usize>,
+    stat_output_as: Option<String>, // Raw `stat_output_as:` value (histogram only)
+}
+
+/// Parse the parenthesized bar arguments
+fn parse_bar_args(input: &str) -> IResult<&str, BarArgs> {
     let (input, _) = ws(char('('))(input)?;
 
     // Parse optional named arguments
@@ -212,6 +662,15 @@ pub fn parse_bar(input: &str) -> IResult<&str, Layer> {
                 preceded(ws(tag("color:")), ws(identifier)),
                 |c| ("color", ArgValue::ColorMapped(c)),
             ),
+            // fill: alias for color (bars are drawn filled with their color)
+            map(
+                preceded(ws(tag("fill:")), ws(string_literal)),
+                |c| ("color", ArgValue::ColorFixed(c)),
+            ),
+            map(
+                preceded(ws(tag("fill:")), ws(identifier)),
+                |c| ("color", ArgValue::ColorMapped(c)),
+            ),
             // width: can be 0.8 (literal), width_col (column)
             map(
                 preceded(ws(tag("width:")), ws(number_literal)),
@@ -235,17 +694,40 @@ pub fn parse_bar(input: &str) -> IResult<&str, Layer> {
                 preceded(ws(tag("position:")), ws(string_literal)),
                 |p| ("position", ArgValue::ColorFixed(p)),
             ),
+            // stat: "count" | "prop" | "sum" | "mean" | "max" | "min" | "identity"
+            map(
+                preceded(ws(tag("stat:")), ws(string_literal)),
+                |s| ("stat", ArgValue::ColorFixed(s)),
+            ),
+            // bins: histogram bin count
+            map(
+                preceded(ws(tag("bins:")), ws(number_literal)),
+                |b| ("bins", ArgValue::NumericFixed(b)),
+            ),
+            // stat_output_as: "binned" shares the histogram's bins with later layers
+            map(
+                preceded(ws(tag("stat_output_as:")), ws(string_literal)),
+                |s| ("stat_output_as", ArgValue::ColorFixed(s)),
+            ),
+            // border: outline color, border_width: its width in pixels
+            map(preceded(ws(tag("border:")), ws(string_literal)), |c| ("border", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("border_width:")), ws(number_literal)), |w| ("border_width", ArgValue::NumericFixed(w))),
         )),
     )(input)?;
 
     let (input, _) = ws(char(')'))(input)?;
 
     let mut layer = BarLayer::default();
+    let mut stat_arg = None;
+    let mut bins = None;
+    let mut stat_output_as = None;
 
     for (key, val) in args {
         match (key, val) {
             ("x", ArgValue::ColumnName(x)) => layer.x = Some(x),
             ("y", ArgValue::ColumnName(y)) => layer.y = Some(y),
+            // fill: "none" leaves the bars unfilled, outlined in their color
+            ("color", ArgValue::ColorFixed(c)) if c == "none" => layer.hollow = true,
             ("color", ArgValue::ColorFixed(c)) => layer.color = Some(AestheticValue::Fixed(c)),
             ("color", ArgValue::ColorMapped(c)) => layer.color = Some(AestheticValue::Mapped(c)),
             ("width", ArgValue::NumericFixed(w)) => layer.width = Some(AestheticValue::Fixed(w)),
@@ -254,17 +736,35 @@ pub fn parse_bar(input: &str) -> IResult<&str, Layer> {
             ("alpha", ArgValue::NumericMapped(a)) => layer.alpha = Some(AestheticValue::Mapped(a)),
             ("position", ArgValue::ColorFixed(p)) => {
                 layer.position = match p.as_str() {
-                    "dodge" => BarPosition::Dodge,
-                    "stack" => BarPosition::Stack,
-                    "identity" => BarPosition::Identity,
-                    _ => BarPosition::Identity, // default for unknown values
+                    "dodge" => LayerPosition::Dodge,
+                    "stack" => LayerPosition::Stack,
+                    "fill" => LayerPosition::Fill,
+                    "percent" => LayerPosition::Percent,
+                    "identity" => LayerPosition::Identity,
+                    _ => LayerPosition::Identity, // default for unknown values
                 };
             }
+            ("stat", ArgValue::ColorFixed(s)) => {
+                layer.stat = match s.as_str() {
+                    "count" => crate::parser::ast::Stat::Count,
+                    "prop" => crate::parser::ast::Stat::Prop,
+                    "sum" => crate::parser::ast::Stat::Sum,
+                    "mean" => crate::parser::ast::Stat::Mean,
+                    "max" => crate::parser::ast::Stat::Max,
+                    "min" => crate::parser::ast::Stat::Min,
+                    _ => crate::parser::ast::Stat::Identity, // "identity"; each geom rejects names it doesn't take
+                };
+                stat_arg = Some(s);
+            }
+            ("bins", ArgValue::NumericFixed(b)) => bins = Some(b as usize),
+            ("stat_output_as", ArgValue::ColorFixed(s)) => stat_output_as = Some(s),
+            ("border", ArgValue::ColorFixed(c)) => layer.border = Some(c),
+            ("border_width", ArgValue::NumericFixed(w)) => layer.border_width = Some(w.max(0.0)),
             _ => {}
         }
     }
 
-    Ok((input, Layer::Bar(layer)))
+    Ok((input, BarArgs { layer, stat: stat_arg, bins, stat_output_as }))
 }
 
 /// Parse a ribbon geometry
@@ -289,6 +789,10 @@ pub fn parse_ribbon(input: &str) -> IResult<&str, Layer> {
             // alpha: can be number, column
             map(preceded(ws(tag("alpha:")), ws(number_literal)), |a| ("alpha", ArgValue::NumericFixed(a))),
             map(preceded(ws(tag("alpha:")), ws(identifier)), |a| ("alpha", ArgValue::NumericMapped(a))),
+
+            // border: "black", linetype: "dashed" (outline of the upper and lower edges)
+            map(preceded(ws(tag("border:")), ws(string_literal)), |c| ("border", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("linetype:")), ws(string_literal)), |t| ("linetype", ArgValue::ColorFixed(t))),
         ))
     )(input)?;
 
@@ -305,6 +809,8 @@ pub fn parse_ribbon(input: &str) -> IResult<&str, Layer> {
             ("color", ArgValue::ColorMapped(c)) => layer.color = Some(AestheticValue::Mapped(c)),
             ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(AestheticValue::Fixed(a)),
             ("alpha", ArgValue::NumericMapped(a)) => layer.alpha = Some(AestheticValue::Mapped(a)),
+            ("border", ArgValue::ColorFixed(c)) => layer.border = Some(c),
+            ("linetype", ArgValue::ColorFixed(t)) => layer.linetype = Some(t),
             _ => {}
         }
     }
@@ -313,179 +819,1001 @@ pub fn parse_ribbon(input: &str) -> IResult<&str, Layer> {
 }
 
 /// Parse a histogram geometry (sugar for bar(stat: "bin"))
+/// Format: histogram(bins: 30) or histogram(bins: 30, fill: group, alpha: 0.5, position: "identity")
 pub fn parse_histogram(input: &str) -> IResult<&str, Layer> {
     let (input, _) = ws(tag("histogram"))(input)?;
-    let (input, _) = ws(char('('))(input)?;
-    // Optional bins argument
-    let (input, bins) = opt(preceded(ws(tag("bins:")), ws(number_literal)))(input)?;
-    let (input, _) = ws(char(')'))(input)?;
+    let (rest, BarArgs { mut layer, stat, bins, stat_output_as }) = parse_bar_args(input)?;
 
-    let mut layer = BarLayer::default();
-    layer.stat = crate::parser::ast::Stat::Bin { bins: bins.unwrap_or(30.0) as usize };
-    Ok((input, Layer::Bar(layer)))
+    // The stat is always binning
+    let invalid = || nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Verify));
+    if stat.is_some_and(|s| s != "bin") {
+        return Err(invalid());
+    }
+    layer.stat = crate::parser::ast::Stat::Bin { bins: bins.unwrap_or(30) };
+    layer.stat_output_as = match stat_output_as.as_deref() {
+        None => None,
+        Some("binned") => Some(StatOutput::Binned),
+        Some(_) => return Err(invalid()),
+    };
+    Ok((rest, Layer::Bar(layer)))
 }
 
 /// Parse a smooth geometry (sugar for line(stat: "smooth"))
+/// Format: smooth() or smooth(method: "poly", n: 80) or smooth(method: "loess", span: 0.5, residuals: true)
+/// or smooth(fullrange: true) to extend the fit across the whole plot's x range
 pub fn parse_smooth(input: &str) -> IResult<&str, Layer> {
     let (input, _) = ws(tag("smooth"))(input)?;
     let (input, _) = ws(char('('))(input)?;
+    let args_start = input;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            // method: "lm" | "poly" | "loess"
+            map(preceded(ws(tag("method:")), ws(string_literal)), |m| ("method", ArgValue::ColorFixed(m))),
+            // n: number of evaluation points
+            map(preceded(ws(tag("n:")), ws(number_literal)), |n| ("n", ArgValue::NumericFixed(n))),
+            // span: LOESS neighborhood fraction
+            map(preceded(ws(tag("span:")), ws(number_literal)), |s| ("span", ArgValue::NumericFixed(s))),
+            // residuals: true | false
+            map(preceded(ws(tag("residuals:")), ws(alt((tag("true"), tag("false"))))), |r| ("residuals", ArgValue::ColorFixed(r.to_string()))),
+            // formula: R-style model ("y ~ x", "y ~ poly(x, 2)", "y ~ log(x)"), checked when fitting
+            map(preceded(ws(tag("formula:")), ws(string_literal)), |f| ("formula", ArgValue::ColorFixed(f))),
+            // fullrange: true | false
+            map(preceded(ws(tag("fullrange:")), ws(alt((tag("true"), tag("false"))))), |f| ("fullrange", ArgValue::ColorFixed(f.to_string()))),
+            // se: true (= "confidence") | false | "confidence" | "prediction", at level: 0.95
+            map(preceded(ws(tag("se:")), ws(alt((tag("true"), tag("false"))))), |s| ("se", ArgValue::ColorFixed(s.to_string()))),
+            map(preceded(ws(tag("se:")), ws(string_literal)), |s| ("se", ArgValue::ColorFixed(s))),
+            map(preceded(ws(tag("level:")), ws(number_literal)), |l| ("level", ArgValue::NumericFixed(l))),
+        ))
+    )(input)?;
+
     let (input, _) = ws(char(')'))(input)?;
 
-    let mut layer = LineLayer::default();
-    layer.stat = crate::parser::ast::Stat::Smooth { method: "lm".to_string() };
+    let mut method = "lm".to_string();
+    let mut n = 80;
+    let mut span = 0.75;
+    let mut residuals = false;
+    let mut formula = None;
+    let mut fullrange = false;
+    let mut se = "false".to_string();
+    let mut level = None;
+    for (key, val) in args {
+        match (key, val) {
+            ("method", ArgValue::ColorFixed(m)) => method = m,
+            ("n", ArgValue::NumericFixed(v)) => n = v as usize,
+            ("span", ArgValue::NumericFixed(s)) => span = s,
+            ("residuals", ArgValue::ColorFixed(r)) => residuals = r == "true",
+            ("formula", ArgValue::ColorFixed(f)) => formula = Some(f),
+            ("fullrange", ArgValue::ColorFixed(f)) => fullrange = f == "true",
+            ("se", ArgValue::ColorFixed(s)) => se = s,
+            ("level", ArgValue::NumericFixed(l)) => level = Some(l),
+            _ => {}
+        }
+    }
+
+    let invalid = || nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify));
+    let method = match method.as_str() {
+        "lm" => SmoothMethod::Lm,
+        "poly" => SmoothMethod::Poly,
+        "loess" if span > 0.0 && span <= 1.0 => SmoothMethod::Loess { span },
+        _ => return Err(invalid()),
+    };
+    let level = match level {
+        Some(l) if l > 0.0 && l < 1.0 => l,
+        None => SeType::DEFAULT_LEVEL,
+        Some(_) => return Err(invalid()),
+    };
+    let se = match se.as_str() {
+        "false" if level == SeType::DEFAULT_LEVEL => SeType::None,
+        "true" | "confidence" => SeType::Confidence(level),
+        "prediction" => SeType::Prediction(level),
+        // level: without a band to apply it to
+        _ => return Err(invalid()),
+    };
+
+    let layer = LineLayer {
+        stat: crate::parser::ast::Stat::Smooth { method, n, residuals, formula, fullrange, se },
+        ..Default::default()
+    };
     Ok((input, Layer::Line(layer)))
 }
 
-/// Parse a boxplot geometry
-pub fn parse_boxplot(input: &str) -> IResult<&str, Layer> {
-    let (input, _) = ws(tag("boxplot"))(input)?;
+/// Parse a quantile regression geometry (one line per quantile)
+/// Format: quantile() or quantile(quantiles: [0.1, 0.5, 0.9], method: "rq")
+pub fn parse_quantile(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("quantile"))(input)?;
     let (input, _) = ws(char('('))(input)?;
+    let args_start = input;
 
     let (input, args) = separated_list0(
         ws(char(',')),
         alt((
-            // x: can be column
-            map(preceded(ws(tag("x:")), ws(identifier)), |x| ("x", ArgValue::ColumnName(x))),
-            // y: can be column
-            map(preceded(ws(tag("y:")), ws(identifier)), |y| ("y", ArgValue::ColumnName(y))),
+            map(preceded(ws(tag("quantiles:")), ws(parse_number_array)), |q| ("quantiles", ArgValue::NumberArray(q))),
+            map(preceded(ws(tag("method:")), ws(string_literal)), |m| ("method", ArgValue::ColorFixed(m))),
+        ))
+    )(input)?;
 
-            // color: can be "literal", column
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut quantiles = vec![0.25, 0.5, 0.75];
+    let mut method = "rq".to_string();
+    for (key, val) in args {
+        match (key, val) {
+            ("quantiles", ArgValue::NumberArray(q)) => quantiles = q,
+            ("method", ArgValue::ColorFixed(m)) => method = m,
+            _ => {}
+        }
+    }
+
+    if method != "rq" || quantiles.is_empty() || quantiles.iter().any(|&q| q <= 0.0 || q >= 1.0) {
+        return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify)));
+    }
+
+    let layer = LineLayer {
+        stat: crate::parser::ast::Stat::Quantile { quantiles },
+        ..Default::default()
+    };
+    Ok((input, Layer::Line(layer)))
+}
+
+/// Parse an empirical cumulative distribution geometry (a step line from 0 to 1)
+/// Format: ecdf() or ecdf(pad: false, n: 100)
+pub fn parse_ecdf(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("ecdf"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let args_start = input;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("pad:")), ws(alt((tag("true"), tag("false"))))), |p| ("pad", ArgValue::ColorFixed(p.to_string()))),
+            map(preceded(ws(tag("n:")), ws(number_literal)), |n| ("n", ArgValue::NumericFixed(n))),
+        ))
+    )(input)?;
+
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut pad = true;
+    let mut n = None;
+    for (key, val) in args {
+        match (key, val) {
+            ("pad", ArgValue::ColorFixed(p)) => pad = p == "true",
+            ("n", ArgValue::NumericFixed(v)) => n = Some(v),
+            _ => {}
+        }
+    }
+
+    // A step function needs at least its two end points
+    if n.is_some_and(|n| n < 2.0 || n.fract() != 0.0) {
+        return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify)));
+    }
+
+    let layer = LineLayer {
+        stat: crate::parser::ast::Stat::Ecdf { pad, n: n.map(|n| n as usize) },
+        ..Default::default()
+    };
+    Ok((input, Layer::Line(layer)))
+}
+
+/// Parse a density geometry (KDE curve of x, one per color group; sugar for a line layer)
+/// Format: density() or density(bw: "nrd", n: 256, fill: true)
+pub fn parse_density(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("density"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let args_start = input;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("bw:")), ws(string_literal)), |b| ("bw", ArgValue::ColorFixed(b))),
+            map(preceded(ws(tag("bw:")), ws(number_literal)), |b| ("bw", ArgValue::NumericFixed(b))),
+            map(preceded(ws(tag("n:")), ws(number_literal)), |n| ("n", ArgValue::NumericFixed(n))),
+            map(preceded(ws(tag("fill:")), ws(alt((tag("true"), tag("false"))))), |f| ("fill", ArgValue::ColorFixed(f.to_string()))),
+        ))
+    )(input)?;
+
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut bw = BandwidthMethod::default();
+    let mut n = crate::parser::ast::DEFAULT_DENSITY_POINTS;
+    let mut fill = false;
+    let invalid = || nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify));
+
+    for (key, val) in args {
+        match (key, val) {
+            ("bw", val) => bw = bandwidth_method(val).ok_or_else(invalid)?,
+            // A curve needs at least its two end points
+            ("n", ArgValue::NumericFixed(v)) if v >= 2.0 && v.fract() == 0.0 => n = v as usize,
+            ("n", _) => return Err(invalid()),
+            ("fill", ArgValue::ColorFixed(f)) => fill = f == "true",
+            _ => {}
+        }
+    }
+
+    let layer = LineLayer {
+        stat: crate::parser::ast::Stat::Density { bw, n, fill },
+        ..Default::default()
+    };
+    Ok((input, Layer::Line(layer)))
+}
+
+/// Bandwidth rule from a bw: argument ("nrd0", "nrd", "ucv" or a positive number)
+fn bandwidth_method(val: ArgValue) -> Option<BandwidthMethod> {
+    match val {
+        ArgValue::ColorFixed(b) => match b.as_str() {
+            "nrd0" => Some(BandwidthMethod::Nrd0),
+            "nrd" => Some(BandwidthMethod::Nrd),
+            "ucv" => Some(BandwidthMethod::Ucv),
+            _ => None,
+        },
+        ArgValue::NumericFixed(b) if b > 0.0 => Some(BandwidthMethod::Fixed(b)),
+        _ => None,
+    }
+}
+
+/// Parse a contour geometry (density level lines of x and y; sugar for a line layer)
+/// Format: contour() or contour(bins: 5, filled: true, color: "black", alpha: 0.8, linetype: "dashed")
+/// or contour_filled(bins: 5), the same as contour(bins: 5, filled: true)
+/// or density_2d(bins: 8, bandwidth_x: 0.5), an alias of contour()
+pub fn parse_contour(input: &str) -> IResult<&str, Layer> {
+    let (input, name) = ws(alt((tag("contour_filled"), tag("contour"), tag("density_2d"))))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let args_start = input;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("bins:")), ws(number_literal)), |b| ("bins", ArgValue::NumericFixed(b))),
+            map(preceded(ws(tag("filled:")), ws(alt((tag("true"), tag("false"))))), |f| ("filled", ArgValue::ColorFixed(f.to_string()))),
+            map(preceded(ws(tag("bandwidth_x:")), ws(number_literal)), |h| ("bandwidth_x", ArgValue::NumericFixed(h))),
+            map(preceded(ws(tag("bandwidth_y:")), ws(number_literal)), |h| ("bandwidth_y", ArgValue::NumericFixed(h))),
             map(preceded(ws(tag("color:")), ws(string_literal)), |c| ("color", ArgValue::ColorFixed(c))),
             map(preceded(ws(tag("color:")), ws(identifier)), |c| ("color", ArgValue::ColorMapped(c))),
-
-            // width: can be number, column
             map(preceded(ws(tag("width:")), ws(number_literal)), |w| ("width", ArgValue::NumericFixed(w))),
             map(preceded(ws(tag("width:")), ws(identifier)), |w| ("width", ArgValue::NumericMapped(w))),
-
-            // alpha: can be number, column
             map(preceded(ws(tag("alpha:")), ws(number_literal)), |a| ("alpha", ArgValue::NumericFixed(a))),
             map(preceded(ws(tag("alpha:")), ws(identifier)), |a| ("alpha", ArgValue::NumericMapped(a))),
-
-            // Outlier specific args (keep as fixed for simplicity)
-            map(preceded(ws(tag("outlier_color:")), ws(string_literal)), |c| ("outlier_color", ArgValue::ColorFixed(c))),
-            map(preceded(ws(tag("outlier_size:")), ws(number_literal)), |s| ("outlier_size", ArgValue::NumericFixed(s))),
-            map(preceded(ws(tag("outlier_shape:")), ws(string_literal)), |sh| ("outlier_shape", ArgValue::ColorFixed(sh))),
+            map(preceded(ws(tag("linetype:")), ws(string_literal)), |t| ("linetype", ArgValue::ColorFixed(t))),
+            map(preceded(ws(tag("linetype:")), ws(identifier)), |t| ("linetype", ArgValue::ColorMapped(t))),
         ))
     )(input)?;
 
     let (input, _) = ws(char(')'))(input)?;
 
-    let mut layer = BoxplotLayer::default();
-    layer.stat = crate::parser::ast::Stat::Boxplot;
+    let mut layer = LineLayer::default();
+    let mut bins = crate::parser::ast::DEFAULT_CONTOUR_BINS;
+    let mut filled = name == "contour_filled";
+    let (mut bandwidth_x, mut bandwidth_y) = (None, None);
 
     for (key, val) in args {
         match (key, val) {
-            ("x", ArgValue::ColumnName(x)) => layer.x = Some(x),
-            ("y", ArgValue::ColumnName(y)) => layer.y = Some(y),
+            ("bins", ArgValue::NumericFixed(b)) if b >= 1.0 && b.fract() == 0.0 => bins = b as usize,
+            ("bins", _) => return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify))),
+            // contour_filled() is always filled
+            ("filled", _) if name == "contour_filled" => {
+                return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify)));
+            }
+            ("filled", ArgValue::ColorFixed(f)) => filled = f == "true",
+            ("bandwidth_x" | "bandwidth_y", ArgValue::NumericFixed(h)) if h <= 0.0 => {
+                return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify)));
+            }
+            ("bandwidth_x", ArgValue::NumericFixed(h)) => bandwidth_x = Some(h),
+            ("bandwidth_y", ArgValue::NumericFixed(h)) => bandwidth_y = Some(h),
             ("color", ArgValue::ColorFixed(c)) => layer.color = Some(AestheticValue::Fixed(c)),
             ("color", ArgValue::ColorMapped(c)) => layer.color = Some(AestheticValue::Mapped(c)),
             ("width", ArgValue::NumericFixed(w)) => layer.width = Some(AestheticValue::Fixed(w)),
             ("width", ArgValue::NumericMapped(w)) => layer.width = Some(AestheticValue::Mapped(w)),
             ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(AestheticValue::Fixed(a)),
             ("alpha", ArgValue::NumericMapped(a)) => layer.alpha = Some(AestheticValue::Mapped(a)),
-            ("outlier_color", ArgValue::ColorFixed(c)) => layer.outlier_color = Some(c),
-            ("outlier_size", ArgValue::NumericFixed(s)) => layer.outlier_size = Some(s),
-            ("outlier_shape", ArgValue::ColorFixed(sh)) => layer.outlier_shape = Some(sh),
+            ("linetype", ArgValue::ColorFixed(t)) => layer.linetype = Some(AestheticValue::Fixed(t)),
+            ("linetype", ArgValue::ColorMapped(t)) => layer.linetype = Some(AestheticValue::Mapped(t)),
             _ => {}
         }
     }
 
-    Ok((input, Layer::Boxplot(layer)))
+    layer.stat = crate::parser::ast::Stat::Contour { bins, filled, bandwidth_x, bandwidth_y };
+    Ok((input, Layer::Line(layer)))
 }
 
-/// Parse a violin geometry
-/// Format: violin() or violin(color: "blue", alpha: 0.7, width: 0.8, draw_quantiles: [0.25, 0.5, 0.75])
-pub fn parse_violin(input: &str) -> IResult<&str, Layer> {
-    let (input, _) = ws(tag("violin"))(input)?;
+/// Parse an abline() layer: straight lines y = intercept + slope * x across the x range.
+/// Each of slope and intercept is a number or a column (one line per distinct row value).
+/// Format: abline(slope: 2, intercept: 1) or abline(slope: slope_col, intercept: intercept_col, color: model)
+pub fn parse_abline(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("abline"))(input)?;
     let (input, _) = ws(char('('))(input)?;
 
     let (input, args) = separated_list0(
         ws(char(',')),
         alt((
-            // x: can be column
-            map(preceded(ws(tag("x:")), ws(identifier)), |x| ("x", ArgValue::ColumnName(x))),
-            // y: can be column
-            map(preceded(ws(tag("y:")), ws(identifier)), |y| ("y", ArgValue::ColumnName(y))),
-
-            // color: can be "literal", column
+            map(preceded(ws(tag("slope:")), ws(number_literal)), |v| ("slope", ArgValue::NumericFixed(v))),
+            map(preceded(ws(tag("slope:")), ws(identifier)), |v| ("slope", ArgValue::NumericMapped(v))),
+            map(preceded(ws(tag("intercept:")), ws(number_literal)), |v| ("intercept", ArgValue::NumericFixed(v))),
+            map(preceded(ws(tag("intercept:")), ws(identifier)), |v| ("intercept", ArgValue::NumericMapped(v))),
             map(preceded(ws(tag("color:")), ws(string_literal)), |c| ("color", ArgValue::ColorFixed(c))),
             map(preceded(ws(tag("color:")), ws(identifier)), |c| ("color", ArgValue::ColorMapped(c))),
-
-            // width: can be number, column
             map(preceded(ws(tag("width:")), ws(number_literal)), |w| ("width", ArgValue::NumericFixed(w))),
             map(preceded(ws(tag("width:")), ws(identifier)), |w| ("width", ArgValue::NumericMapped(w))),
-
-            // alpha: can be number, column
             map(preceded(ws(tag("alpha:")), ws(number_literal)), |a| ("alpha", ArgValue::NumericFixed(a))),
             map(preceded(ws(tag("alpha:")), ws(identifier)), |a| ("alpha", ArgValue::NumericMapped(a))),
-
-            // Violin-specific: draw_quantiles array
-            map(preceded(ws(tag("draw_quantiles:")), ws(parse_number_array)), |q| ("draw_quantiles", ArgValue::NumberArray(q))),
+            map(preceded(ws(tag("linetype:")), ws(string_literal)), |t| ("linetype", ArgValue::ColorFixed(t))),
+            map(preceded(ws(tag("linetype:")), ws(identifier)), |t| ("linetype", ArgValue::ColorMapped(t))),
+            map(preceded(ws(tag("legend:")), ws(string_literal)), |l| ("legend", ArgValue::ColorFixed(l))),
         ))
     )(input)?;
 
     let (input, _) = ws(char(')'))(input)?;
 
-    let mut layer = ViolinLayer::default();
+    let mut layer = LineLayer::default();
+    let mut slope = AestheticValue::Fixed(1.0);
+    let mut intercept = AestheticValue::Fixed(0.0);
 
     for (key, val) in args {
         match (key, val) {
-            ("x", ArgValue::ColumnName(x)) => layer.x = Some(x),
-            ("y", ArgValue::ColumnName(y)) => layer.y = Some(y),
+            ("slope", ArgValue::NumericFixed(v)) => slope = AestheticValue::Fixed(v),
+            ("slope", ArgValue::NumericMapped(c)) => slope = AestheticValue::Mapped(c),
+            ("intercept", ArgValue::NumericFixed(v)) => intercept = AestheticValue::Fixed(v),
+            ("intercept", ArgValue::NumericMapped(c)) => intercept = AestheticValue::Mapped(c),
             ("color", ArgValue::ColorFixed(c)) => layer.color = Some(AestheticValue::Fixed(c)),
             ("color", ArgValue::ColorMapped(c)) => layer.color = Some(AestheticValue::Mapped(c)),
             ("width", ArgValue::NumericFixed(w)) => layer.width = Some(AestheticValue::Fixed(w)),
             ("width", ArgValue::NumericMapped(w)) => layer.width = Some(AestheticValue::Mapped(w)),
             ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(AestheticValue::Fixed(a)),
             ("alpha", ArgValue::NumericMapped(a)) => layer.alpha = Some(AestheticValue::Mapped(a)),
-            ("draw_quantiles", ArgValue::NumberArray(q)) => layer.draw_quantiles = q,
+            ("linetype", ArgValue::ColorFixed(t)) => layer.linetype = Some(AestheticValue::Fixed(t)),
+            ("linetype", ArgValue::ColorMapped(t)) => layer.linetype = Some(AestheticValue::Mapped(t)),
+            ("legend", ArgValue::ColorFixed(l)) => layer.legend = Some(l),
             _ => {}
         }
     }
 
-    // Set stat with draw_quantiles for transform phase
-    layer.stat = crate::parser::ast::Stat::Violin { draw_quantiles: layer.draw_quantiles.clone() };
-
-    Ok((input, Layer::Violin(layer)))
+    layer.stat = crate::parser::ast::Stat::Abline { slope, intercept };
+    Ok((input, Layer::Line(layer)))
 }
 
-/// Parse any geometry layer
-pub fn parse_geom(input: &str) -> IResult<&str, Layer> {
-    alt((parse_line, parse_point, parse_bar, parse_ribbon, parse_histogram, parse_smooth, parse_boxplot, parse_violin))(input)
-}
+/// Parse a crosshair geometry: a vertical and a horizontal line through each point, across the panel
+/// Format: crosshair() or crosshair(x: col, y: col, color: "red", alpha: 0.1)
+pub fn parse_crosshair(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("crosshair"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("x:")), ws(identifier)), |x| ("x", ArgValue::ColumnName(x))),
+            map(preceded(ws(tag("y:")), ws(identifier)), |y| ("y", ArgValue::ColumnName(y))),
+            map(preceded(ws(tag("color:")), ws(string_literal)), |c| ("color", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("color:")), ws(identifier)), |c| ("color", ArgValue::ColorMapped(c))),
+            map(preceded(ws(tag("width:")), ws(number_literal)), |w| ("width", ArgValue::NumericFixed(w))),
+            map(preceded(ws(tag("width:")), ws(identifier)), |w| ("width", ArgValue::NumericMapped(w))),
+            map(preceded(ws(tag("alpha:")), ws(number_literal)), |a| ("alpha", ArgValue::NumericFixed(a))),
+            map(preceded(ws(tag("alpha:")), ws(identifier)), |a| ("alpha", ArgValue::NumericMapped(a))),
+            map(preceded(ws(tag("linetype:")), ws(string_literal)), |t| ("linetype", ArgValue::ColorFixed(t))),
+            map(preceded(ws(tag("linetype:")), ws(identifier)), |t| ("linetype", ArgValue::ColorMapped(t))),
+            map(preceded(ws(tag("legend:")), ws(string_literal)), |l| ("legend", ArgValue::ColorFixed(l))),
+        ))
+    )(input)?;
 
-    #[test]
-    fn test_parse_line_empty() {
-        let result = parse_line("line()");
-        assert!(result.is_ok());
-        let (_, layer) = result.unwrap();
-        match layer {
-            Layer::Line(l) => {
-                assert_eq!(l.color, None);
-                assert_eq!(l.width, None);
-            }
-            _ => panic!("Expected Line layer"),
-        }
-    }
+    let (input, _) = ws(char(')'))(input)?;
 
-    #[test]
-    fn test_parse_line_with_color() {
-        let result = parse_line(r#"line(color: "red")"#);
-        assert!(result.is_ok());
-        let (_, layer) = result.unwrap();
-        match layer {
-            Layer::Line(l) => {
-                assert_eq!(l.color, Some(AestheticValue::Fixed("red".to_string())));
-            }
-            _ => panic!("Expected Line layer"),
+    let mut layer = LineLayer { stat: crate::parser::ast::Stat::Crosshair, ..Default::default() };
+    for (key, val) in args {
+        match (key, val) {
+            ("x", ArgValue::ColumnName(x)) => layer.x = Some(x),
+            ("y", ArgValue::ColumnName(y)) => layer.y = Some(y),
+            ("color", ArgValue::ColorFixed(c)) => layer.color = Some(AestheticValue::Fixed(c)),
+            ("color", ArgValue::ColorMapped(c)) => layer.color = Some(AestheticValue::Mapped(c)),
+            ("width", ArgValue::NumericFixed(w)) => layer.width = Some(AestheticValue::Fixed(w)),
+            ("width", ArgValue::NumericMapped(w)) => layer.width = Some(AestheticValue::Mapped(w)),
+            ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(AestheticValue::Fixed(a)),
+            ("alpha", ArgValue::NumericMapped(a)) => layer.alpha = Some(AestheticValue::Mapped(a)),
+            ("linetype", ArgValue::ColorFixed(t)) => layer.linetype = Some(AestheticValue::Fixed(t)),
+            ("linetype", ArgValue::ColorMapped(t)) => layer.linetype = Some(AestheticValue::Mapped(t)),
+            ("legend", ArgValue::ColorFixed(l)) => layer.legend = Some(l),
+            _ => {}
         }
     }
 
-    #[test]
+    Ok((input, Layer::Line(layer)))
+}
+
+/// Parse a rug geometry: a short vertical tick per x value, hanging below y = 0 when the y range
+/// spans zero (under a density() curve) and standing on the panel bottom otherwise. `length` is the
+/// band height as a fraction of the y range; `jitter: true` staggers shorter ticks within the band.
+/// Format: rug() or rug(x: col, length: 0.05, jitter: true, color: "black", alpha: 0.5)
+pub fn parse_rug(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("rug"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let args_start = input;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("x:")), ws(identifier)), |x| ("x", ArgValue::ColumnName(x))),
+            map(preceded(ws(tag("length:")), ws(number_literal)), |l| ("length", ArgValue::NumericFixed(l))),
+            map(preceded(ws(tag("jitter:")), ws(alt((tag("true"), tag("false"))))), |j| ("jitter", ArgValue::ColorFixed(j.to_string()))),
+            map(preceded(ws(tag("color:")), ws(string_literal)), |c| ("color", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("color:")), ws(identifier)), |c| ("color", ArgValue::ColorMapped(c))),
+            map(preceded(ws(tag("width:")), ws(number_literal)), |w| ("width", ArgValue::NumericFixed(w))),
+            map(preceded(ws(tag("width:")), ws(identifier)), |w| ("width", ArgValue::NumericMapped(w))),
+            map(preceded(ws(tag("alpha:")), ws(number_literal)), |a| ("alpha", ArgValue::NumericFixed(a))),
+            map(preceded(ws(tag("alpha:")), ws(identifier)), |a| ("alpha", ArgValue::NumericMapped(a))),
+            map(preceded(ws(tag("linetype:")), ws(string_literal)), |t| ("linetype", ArgValue::ColorFixed(t))),
+            map(preceded(ws(tag("linetype:")), ws(identifier)), |t| ("linetype", ArgValue::ColorMapped(t))),
+        ))
+    )(input)?;
+
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut layer = LineLayer::default();
+    let (mut length, mut jitter) = (crate::parser::ast::DEFAULT_RUG_LENGTH, false);
+    for (key, val) in args {
+        match (key, val) {
+            ("x", ArgValue::ColumnName(x)) => layer.x = Some(x),
+            ("length", ArgValue::NumericFixed(l)) if l > 0.0 && l <= 1.0 => length = l,
+            ("length", _) => return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify))),
+            ("jitter", ArgValue::ColorFixed(j)) => jitter = j == "true",
+            ("color", ArgValue::ColorFixed(c)) => layer.color = Some(AestheticValue::Fixed(c)),
+            ("color", ArgValue::ColorMapped(c)) => layer.color = Some(AestheticValue::Mapped(c)),
+            ("width", ArgValue::NumericFixed(w)) => layer.width = Some(AestheticValue::Fixed(w)),
+            ("width", ArgValue::NumericMapped(w)) => layer.width = Some(AestheticValue::Mapped(w)),
+            ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(AestheticValue::Fixed(a)),
+            ("alpha", ArgValue::NumericMapped(a)) => layer.alpha = Some(AestheticValue::Mapped(a)),
+            ("linetype", ArgValue::ColorFixed(t)) => layer.linetype = Some(AestheticValue::Fixed(t)),
+            ("linetype", ArgValue::ColorMapped(t)) => layer.linetype = Some(AestheticValue::Mapped(t)),
+            _ => {}
+        }
+    }
+
+    layer.stat = crate::parser::ast::Stat::Rug { length, jitter };
+    Ok((input, Layer::Line(layer)))
+}
+
+/// Parse a segment() layer: a line from (x, y) to (xend, yend) per row, optionally with arrow heads
+/// Format: segment(xend: x2, yend: y2) or segment(xend: x2, yend: y2, arrow: true, arrow_type: "open", arrow_ends: "both")
+pub fn parse_segment(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("segment"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let args_start = input;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("xend:")), ws(identifier)), |x| ("xend", ArgValue::ColumnName(x))),
+            map(preceded(ws(tag("yend:")), ws(identifier)), |y| ("yend", ArgValue::ColumnName(y))),
+            map(preceded(ws(tag("x:")), ws(identifier)), |x| ("x", ArgValue::ColumnName(x))),
+            map(preceded(ws(tag("y:")), ws(identifier)), |y| ("y", ArgValue::ColumnName(y))),
+            map(preceded(ws(tag("color:")), ws(string_literal)), |c| ("color", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("color:")), ws(identifier)), |c| ("color", ArgValue::ColorMapped(c))),
+            map(preceded(ws(tag("width:")), ws(number_literal)), |w| ("width", ArgValue::NumericFixed(w))),
+            map(preceded(ws(tag("width:")), ws(identifier)), |w| ("width", ArgValue::NumericMapped(w))),
+            map(preceded(ws(tag("alpha:")), ws(number_literal)), |a| ("alpha", ArgValue::NumericFixed(a))),
+            map(preceded(ws(tag("alpha:")), ws(identifier)), |a| ("alpha", ArgValue::NumericMapped(a))),
+            map(preceded(ws(tag("linetype:")), ws(string_literal)), |t| ("linetype", ArgValue::ColorFixed(t))),
+            map(preceded(ws(tag("linetype:")), ws(identifier)), |t| ("linetype", ArgValue::ColorMapped(t))),
+            map(preceded(ws(tag("arrow:")), ws(alt((tag("true"), tag("false"))))), |a| ("arrow", ArgValue::ColorFixed(a.to_string()))),
+            map(preceded(ws(tag("arrow_type:")), ws(string_literal)), |t| ("arrow_type", ArgValue::ColorFixed(t))),
+            map(preceded(ws(tag("arrow_ends:")), ws(string_literal)), |e| ("arrow_ends", ArgValue::ColorFixed(e))),
+        ))
+    )(input)?;
+
+    let (input, _) = ws(char(')'))(input)?;
+
+    let invalid = || nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify));
+    let mut layer = LineLayer::default();
+    let (mut xend, mut yend) = (None, None);
+    let (mut arrow, mut head, mut ends) = (false, None, None);
+    for (key, val) in args {
+        match (key, val) {
+            ("x", ArgValue::ColumnName(x)) => layer.x = Some(x),
+            ("y", ArgValue::ColumnName(y)) => layer.y = Some(y),
+            ("xend", ArgValue::ColumnName(x)) => xend = Some(x),
+            ("yend", ArgValue::ColumnName(y)) => yend = Some(y),
+            ("color", ArgValue::ColorFixed(c)) => layer.color = Some(AestheticValue::Fixed(c)),
+            ("color", ArgValue::ColorMapped(c)) => layer.color = Some(AestheticValue::Mapped(c)),
+            ("width", ArgValue::NumericFixed(w)) => layer.width = Some(AestheticValue::Fixed(w)),
+            ("width", ArgValue::NumericMapped(w)) => layer.width = Some(AestheticValue::Mapped(w)),
+            ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(AestheticValue::Fixed(a)),
+            ("alpha", ArgValue::NumericMapped(a)) => layer.alpha = Some(AestheticValue::Mapped(a)),
+            ("linetype", ArgValue::ColorFixed(t)) => layer.linetype = Some(AestheticValue::Fixed(t)),
+            ("linetype", ArgValue::ColorMapped(t)) => layer.linetype = Some(AestheticValue::Mapped(t)),
+            ("arrow", ArgValue::ColorFixed(a)) => arrow = a == "true",
+            ("arrow_type", ArgValue::ColorFixed(t)) => {
+                head = Some(ArrowType::ALL.into_iter().find(|h| h.name() == t).ok_or_else(invalid)?);
+            }
+            ("arrow_ends", ArgValue::ColorFixed(e)) => {
+                ends = Some(ArrowEnds::ALL.into_iter().find(|end| end.name() == e).ok_or_else(invalid)?);
+            }
+            _ => {}
+        }
+    }
+
+    // arrow_type/arrow_ends only style an arrow: true head
+    if !arrow && (head.is_some() || ends.is_some()) {
+        return Err(invalid());
+    }
+    let arrow = arrow.then(|| Arrow { head: head.unwrap_or_default(), ends: ends.unwrap_or_default() });
+    layer.stat = crate::parser::ast::Stat::Segment { xend, yend, arrow };
+    Ok((input, Layer::Line(layer)))
+}
+
+/// Parse a stat_summary() layer: a point at `fun` with a vertical range from `fun_ymin` to `fun_ymax`
+/// for each x value. Formulas combine mean, median, sd, se, min, max, q25 and q75 with + and -.
+/// Format: stat_summary() or stat_summary(fun: "median", fun_ymin: "q25", fun_ymax: "q75")
+pub fn parse_stat_summary(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("stat_summary"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let args_start = input;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("fun:")), ws(string_literal)), |f| ("fun", f)),
+            map(preceded(ws(tag("fun_ymin:")), ws(string_literal)), |f| ("fun_ymin", f)),
+            map(preceded(ws(tag("fun_ymax:")), ws(string_literal)), |f| ("fun_ymax", f)),
+        ))
+    )(input)?;
+
+    let (input, _) = ws(char(')'))(input)?;
+
+    let (mut fun, mut fun_ymin, mut fun_ymax) = (SummaryExpr::default_fun(), SummaryExpr::default_ymin(), SummaryExpr::default_ymax());
+    for (key, formula) in args {
+        let (_, expr) = all_consuming(ws(summary_formula))(formula.as_str())
+            .map_err(|_| nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify)))?;
+        match key {
+            "fun" => fun = expr,
+            "fun_ymin" => fun_ymin = expr,
+            _ => fun_ymax = expr,
+        }
+    }
+
+    let layer = PointLayer {
+        stat: crate::parser::ast::Stat::Summary { fun, fun_ymin, fun_ymax },
+        ..Default::default()
+    };
+    Ok((input, Layer::Point(layer)))
+}
+
+/// `<fun> [(+|-) <fun>]...`, folded left to right
+fn summary_formula(input: &str) -> IResult<&str, SummaryExpr> {
+    let fun = |input| {
+        let (input, name) = ws(identifier)(input)?;
+        match SummaryFun::ALL.into_iter().find(|f| f.name() == name) {
+            Some(f) => Ok((input, SummaryExpr::Fun(f))),
+            None => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Tag))),
+        }
+    };
+    let (input, first) = fun(input)?;
+    let (input, rest) = many0(pair(ws(one_of("+-")), fun))(input)?;
+    let expr = rest.into_iter().fold(first, |acc, (op, term)| match op {
+        '+' => SummaryExpr::Add(Box::new(acc), Box::new(term)),
+        _ => SummaryExpr::Sub(Box::new(acc), Box::new(term)),
+    });
+    Ok((input, expr))
+}
+
+/// Parse a boxplot geometry
+pub fn parse_boxplot(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("boxplot"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            // x: can be column
+            map(preceded(ws(tag("x:")), ws(identifier)), |x| ("x", ArgValue::ColumnName(x))),
+            // y: can be column
+            map(preceded(ws(tag("y:")), ws(identifier)), |y| ("y", ArgValue::ColumnName(y))),
+
+            // color: can be "literal", column
+            map(preceded(ws(tag("color:")), ws(string_literal)), |c| ("color", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("color:")), ws(identifier)), |c| ("color", ArgValue::ColorMapped(c))),
+
+            // width: can be number, column
+            map(preceded(ws(tag("width:")), ws(number_literal)), |w| ("width", ArgValue::NumericFixed(w))),
+            map(preceded(ws(tag("width:")), ws(identifier)), |w| ("width", ArgValue::NumericMapped(w))),
+
+            // alpha: can be number, column
+            map(preceded(ws(tag("alpha:")), ws(number_literal)), |a| ("alpha", ArgValue::NumericFixed(a))),
+            map(preceded(ws(tag("alpha:")), ws(identifier)), |a| ("alpha", ArgValue::NumericMapped(a))),
+
+            // Outlier specific args (keep as fixed for simplicity)
+            map(preceded(ws(tag("outlier_color:")), ws(string_literal)), |c| ("outlier_color", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("outlier_size:")), ws(number_literal)), |s| ("outlier_size", ArgValue::NumericFixed(s))),
+            map(preceded(ws(tag("outlier_shape:")), ws(string_literal)), |sh| ("outlier_shape", ArgValue::ColorFixed(sh))),
+
+            // varwidth: true | false
+            map(preceded(ws(tag("varwidth:")), ws(alt((tag("true"), tag("false"))))), |v| ("varwidth", ArgValue::ColorFixed(v.to_string()))),
+
+            // notch: true | false, notchwidth: waist fraction
+            map(preceded(ws(tag("notch:")), ws(alt((tag("true"), tag("false"))))), |v| ("notch", ArgValue::ColorFixed(v.to_string()))),
+            map(preceded(ws(tag("notchwidth:")), ws(number_literal)), |w| ("notchwidth", ArgValue::NumericFixed(w))),
+
+            // staplewidth: whisker cap fraction (0 = no caps)
+            map(preceded(ws(tag("staplewidth:")), ws(number_literal)), |w| ("staplewidth", ArgValue::NumericFixed(w))),
+        ))
+    )(input)?;
+
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut layer = BoxplotLayer {
+        stat: crate::parser::ast::Stat::Boxplot,
+        ..Default::default()
+    };
+
+    for (key, val) in args {
+        match (key, val) {
+            ("x", ArgValue::ColumnName(x)) => layer.x = Some(x),
+            ("y", ArgValue::ColumnName(y)) => layer.y = Some(y),
+            ("color", ArgValue::ColorFixed(c)) => layer.color = Some(AestheticValue::Fixed(c)),
+            ("color", ArgValue::ColorMapped(c)) => layer.color = Some(AestheticValue::Mapped(c)),
+            ("width", ArgValue::NumericFixed(w)) => layer.width = Some(AestheticValue::Fixed(w)),
+            ("width", ArgValue::NumericMapped(w)) => layer.width = Some(AestheticValue::Mapped(w)),
+            ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(AestheticValue::Fixed(a)),
+            ("alpha", ArgValue::NumericMapped(a)) => layer.alpha = Some(AestheticValue::Mapped(a)),
+            ("outlier_color", ArgValue::ColorFixed(c)) => layer.outlier_color = Some(c),
+            ("outlier_size", ArgValue::NumericFixed(s)) => layer.outlier_size = Some(s),
+            ("outlier_shape", ArgValue::ColorFixed(sh)) => layer.outlier_shape = Some(sh),
+            ("varwidth", ArgValue::ColorFixed(v)) => layer.varwidth = v == "true",
+            ("notch", ArgValue::ColorFixed(v)) => layer.notch = v == "true",
+            ("notchwidth", ArgValue::NumericFixed(w)) => layer.notchwidth = Some(w),
+            ("staplewidth", ArgValue::NumericFixed(w)) => layer.staplewidth = Some(w),
+            _ => {}
+        }
+    }
+
+    Ok((input, Layer::Boxplot(layer)))
+}
+
+/// Parse a violin geometry
+/// Format: violin() or violin(color: "blue", alpha: 0.7, width: 0.8, draw_quantiles: [0.25, 0.5, 0.75], bw: "nrd", scale: "count", trim: false)
+pub fn parse_violin(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("violin"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let args_start = input;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            // x: can be column
+            map(preceded(ws(tag("x:")), ws(identifier)), |x| ("x", ArgValue::ColumnName(x))),
+            // y: can be column
+            map(preceded(ws(tag("y:")), ws(identifier)), |y| ("y", ArgValue::ColumnName(y))),
+
+            // color: can be "literal", column
+            map(preceded(ws(tag("color:")), ws(string_literal)), |c| ("color", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("color:")), ws(identifier)), |c| ("color", ArgValue::ColorMapped(c))),
+
+            // width: can be number, column
+            map(preceded(ws(tag("width:")), ws(number_literal)), |w| ("width", ArgValue::NumericFixed(w))),
+            map(preceded(ws(tag("width:")), ws(identifier)), |w| ("width", ArgValue::NumericMapped(w))),
+
+            // alpha: can be number, column
+            map(preceded(ws(tag("alpha:")), ws(number_literal)), |a| ("alpha", ArgValue::NumericFixed(a))),
+            map(preceded(ws(tag("alpha:")), ws(identifier)), |a| ("alpha", ArgValue::NumericMapped(a))),
+
+            // Violin-specific: draw_quantiles array
+            map(preceded(ws(tag("draw_quantiles:")), ws(parse_number_array)), |q| ("draw_quantiles", ArgValue::NumberArray(q))),
+
+            // bw: "nrd0" | "nrd" | "ucv" (rule), or a fixed number
+            map(preceded(ws(tag("bw:")), ws(string_literal)), |b| ("bw", ArgValue::ColorFixed(b))),
+            map(preceded(ws(tag("bw:")), ws(number_literal)), |b| ("bw", ArgValue::NumericFixed(b))),
+
+            // scale: "width" | "count"
+            map(preceded(ws(tag("scale:")), ws(string_literal)), |s| ("scale", ArgValue::ColorFixed(s))),
+
+            // trim: true | false
+            map(preceded(ws(tag("trim:")), ws(alt((tag("true"), tag("false"))))), |t| ("trim", ArgValue::ColorFixed(t.to_string()))),
+
+            // side: "both" | "left" | "right"
+            map(preceded(ws(tag("side:")), ws(string_literal)), |s| ("side", ArgValue::ColorFixed(s))),
+        ))
+    )(input)?;
+
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut layer = ViolinLayer::default();
+    let mut bw = BandwidthMethod::default();
+    let invalid = || nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify));
+
+    for (key, val) in args {
+        match (key, val) {
+            ("x", ArgValue::ColumnName(x)) => layer.x = Some(x),
+            ("y", ArgValue::ColumnName(y)) => layer.y = Some(y),
+            ("color", ArgValue::ColorFixed(c)) => layer.color = Some(AestheticValue::Fixed(c)),
+            ("color", ArgValue::ColorMapped(c)) => layer.color = Some(AestheticValue::Mapped(c)),
+            ("width", ArgValue::NumericFixed(w)) => layer.width = Some(AestheticValue::Fixed(w)),
+            ("width", ArgValue::NumericMapped(w)) => layer.width = Some(AestheticValue::Mapped(w)),
+            ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(AestheticValue::Fixed(a)),
+            ("alpha", ArgValue::NumericMapped(a)) => layer.alpha = Some(AestheticValue::Mapped(a)),
+            ("draw_quantiles", ArgValue::NumberArray(q)) => layer.draw_quantiles = q,
+            ("bw", val) => bw = bandwidth_method(val).ok_or_else(invalid)?,
+            ("scale", ArgValue::ColorFixed(s)) => {
+                layer.scale = match s.as_str() {
+                    "width" => ViolinScale::Width,
+                    "count" => ViolinScale::Count,
+                    _ => return Err(invalid()),
+                }
+            }
+            ("trim", ArgValue::ColorFixed(t)) => layer.trim = t == "true",
+            ("side", ArgValue::ColorFixed(s)) => {
+                layer.side = match s.as_str() {
+                    "both" => ViolinSide::Both,
+                    "left" => ViolinSide::Left,
+                    "right" => ViolinSide::Right,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Set stat with draw_quantiles, bandwidth rule, scaling and trimming for transform phase
+    layer.stat = crate::parser::ast::Stat::Violin {
+        draw_quantiles: layer.draw_quantiles.clone(),
+        bw,
+        scale: layer.scale.clone(),
+        trim: layer.trim,
+    };
+
+    Ok((input, Layer::Violin(layer)))
+}
+
+/// Parse a tile geometry (heatmap cells centered on x/y)
+/// Format: tile(fill: value) or tile(fill: value, color: "white", width: 1, height: 1)
+pub fn parse_tile(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("tile"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let args_start = input;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("x:")), ws(identifier)), |x| ("x", ArgValue::ColumnName(x))),
+            map(preceded(ws(tag("y:")), ws(identifier)), |y| ("y", ArgValue::ColumnName(y))),
+            // fill: "red" (literal) or value (column, mapped through the fill scale)
+            map(preceded(ws(tag("fill:")), ws(string_literal)), |c| ("fill", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("fill:")), ws(identifier)), |c| ("fill", ArgValue::ColorMapped(c))),
+            map(preceded(ws(tag("color:")), ws(string_literal)), |c| ("color", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("alpha:")), ws(number_literal)), |a| ("alpha", ArgValue::NumericFixed(a))),
+            map(preceded(ws(tag("width:")), ws(number_literal)), |w| ("width", ArgValue::NumericFixed(w))),
+            map(preceded(ws(tag("height:")), ws(number_literal)), |h| ("height", ArgValue::NumericFixed(h))),
+            map(preceded(ws(tag("na_color:")), ws(string_literal)), |c| ("na_color", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("drop_na:")), ws(alt((tag("true"), tag("false"))))), |b: &str| ("drop_na", ArgValue::ColorFixed(b.to_string()))),
+        )),
+    )(input)?;
+
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut layer = TileLayer::default();
+
+    for (key, val) in args {
+        match (key, val) {
+            ("x", ArgValue::ColumnName(x)) => layer.x = Some(x),
+            ("y", ArgValue::ColumnName(y)) => layer.y = Some(y),
+            ("fill", ArgValue::ColorFixed(c)) => layer.fill = Some(AestheticValue::Fixed(c)),
+            ("fill", ArgValue::ColorMapped(c)) => layer.fill = Some(AestheticValue::Mapped(c)),
+            ("color", ArgValue::ColorFixed(c)) => layer.color = Some(c),
+            ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(a),
+            ("width", ArgValue::NumericFixed(w)) => layer.width = Some(w),
+            ("height", ArgValue::NumericFixed(h)) => layer.height = Some(h),
+            ("na_color", ArgValue::ColorFixed(c)) => {
+                if parse_color(&c).is_none() {
+                    return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify)));
+                }
+                layer.na_color = Some(c);
+            }
+            ("drop_na", ArgValue::ColorFixed(b)) => layer.drop_na = b == "true",
+            _ => {}
+        }
+    }
+
+    Ok((input, Layer::Tile(layer)))
+}
+
+/// Parse a hexagonal binning geometry (cells filled by point count)
+/// Format: hexbin() or hexbin(bins: 20, color: "white", alpha: 0.9)
+pub fn parse_hexbin(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("hexbin"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let args_start = input;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("x:")), ws(identifier)), |x| ("x", ArgValue::ColumnName(x))),
+            map(preceded(ws(tag("y:")), ws(identifier)), |y| ("y", ArgValue::ColumnName(y))),
+            map(preceded(ws(tag("bins:")), ws(number_literal)), |b| ("bins", ArgValue::NumericFixed(b))),
+            map(preceded(ws(tag("color:")), ws(string_literal)), |c| ("color", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("alpha:")), ws(number_literal)), |a| ("alpha", ArgValue::NumericFixed(a))),
+            map(preceded(ws(tag("fill:")), ws(string_literal)), |f| ("fill", ArgValue::ColorFixed(f))),
+            map(preceded(ws(tag("size:")), ws(string_literal)), |s| ("size", ArgValue::ColorFixed(s))),
+        )),
+    )(input)?;
+
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut layer = HexbinLayer::default();
+
+    for (key, val) in args {
+        match (key, val) {
+            ("x", ArgValue::ColumnName(x)) => layer.x = Some(x),
+            ("y", ArgValue::ColumnName(y)) => layer.y = Some(y),
+            ("bins", ArgValue::NumericFixed(b)) => {
+                if b < 1.0 || b.fract() != 0.0 {
+                    return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify)));
+                }
+                layer.stat = crate::parser::ast::Stat::Hexbin { bins: b as usize };
+            }
+            ("color", ArgValue::ColorFixed(c)) => layer.color = Some(c),
+            ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(a),
+            ("fill", ArgValue::ColorFixed(f)) => {
+                let Some(fill) = HexSummary::ALL.into_iter().find(|s| s.name() == f) else {
+                    return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify)));
+                };
+                layer.fill = fill;
+            }
+            // Only the count can size the cells
+            ("size", ArgValue::ColorFixed(s)) if s == "count" => layer.size_by_count = true,
+            ("size", _) => return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify))),
+            _ => {}
+        }
+    }
+
+    Ok((input, Layer::Hexbin(layer)))
+}
+
+/// Parse a text geometry (one label per row)
+/// Format: text(label: name) or text(label: name, color: "red", size: 10, check_overlap: true, nudge_y: 0.5)
+pub fn parse_text(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("text"))(input)?;
+    let args_start = input;
+    let (rest, TextArgs { layer, fill, padding, border }) = parse_text_args(input)?;
+
+    // Box options only make sense for label()
+    if fill.is_some() || padding.is_some() || border.is_some() {
+        return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify)));
+    }
+    Ok((rest, Layer::Text(layer)))
+}
+
+/// Parse a label geometry (text on a filled background box)
+/// Format: label(label: name) or label(label: name, fill: "lightyellow", label_padding: 6, label_size: 0)
+pub fn parse_label(input: &str) -> IResult<&str, Layer> {
+    let (input, _) = ws(tag("label"))(input)?;
+    let (rest, TextArgs { mut layer, fill, padding, border }) = parse_text_args(input)?;
+
+    if padding.is_some_and(|p| p < 0.0) || border.is_some_and(|b| b < 0.0) {
+        return Err(nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Verify)));
+    }
+    let defaults = LabelBox::default();
+    layer.label_box = Some(LabelBox {
+        fill: fill.unwrap_or(defaults.fill),
+        padding: padding.unwrap_or(defaults.padding),
+        border: border.unwrap_or(defaults.border),
+    });
+    Ok((rest, Layer::Text(layer)))
+}
+
+/// Arguments shared by text() and label()
+struct TextArgs {
+    layer: TextLayer,
+    // label() box options
+    fill: Option<String>,
+    padding: Option<f64>,
+    border: Option<f64>,
+}
+
+/// Parse the parenthesized text arguments
+fn parse_text_args(input: &str) -> IResult<&str, TextArgs> {
+    let (input, _) = ws(char('('))(input)?;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("x:")), ws(identifier)), |x| ("x", ArgValue::ColumnName(x))),
+            map(preceded(ws(tag("y:")), ws(identifier)), |y| ("y", ArgValue::ColumnName(y))),
+            // label: "text" (same for every row) or name (column)
+            map(preceded(ws(tag("label:")), ws(string_literal)), |l| ("label", ArgValue::ColorFixed(l))),
+            map(preceded(ws(tag("label:")), ws(identifier)), |l| ("label", ArgValue::ColorMapped(l))),
+            map(preceded(ws(tag("color:")), ws(string_literal)), |c| ("color", ArgValue::ColorFixed(c))),
+            map(preceded(ws(tag("color:")), ws(identifier)), |c| ("color", ArgValue::ColorMapped(c))),
+            map(preceded(ws(tag("size:")), ws(number_literal)), |s| ("size", ArgValue::NumericFixed(s))),
+            map(preceded(ws(tag("alpha:")), ws(number_literal)), |a| ("alpha", ArgValue::NumericFixed(a))),
+            map(preceded(ws(tag("check_overlap:")), ws(alt((tag("true"), tag("false"))))), |c| ("check_overlap", ArgValue::ColorFixed(c.to_string()))),
+            map(preceded(ws(tag("nudge_x:")), ws(number_literal)), |n| ("nudge_x", ArgValue::NumericFixed(n))),
+            map(preceded(ws(tag("nudge_y:")), ws(number_literal)), |n| ("nudge_y", ArgValue::NumericFixed(n))),
+            map(preceded(ws(tag("fill:")), ws(string_literal)), |f| ("fill", ArgValue::ColorFixed(f))),
+            map(preceded(ws(tag("label_padding:")), ws(number_literal)), |p| ("label_padding", ArgValue::NumericFixed(p))),
+            map(preceded(ws(tag("label_size:")), ws(number_literal)), |s| ("label_size", ArgValue::NumericFixed(s))),
+        )),
+    )(input)?;
+
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut layer = TextLayer::default();
+    let (mut fill, mut padding, mut border) = (None, None, None);
+
+    for (key, val) in args {
+        match (key, val) {
+            ("x", ArgValue::ColumnName(x)) => layer.x = Some(x),
+            ("y", ArgValue::ColumnName(y)) => layer.y = Some(y),
+            ("label", ArgValue::ColorFixed(l)) => layer.label = Some(AestheticValue::Fixed(l)),
+            ("label", ArgValue::ColorMapped(l)) => layer.label = Some(AestheticValue::Mapped(l)),
+            ("color", ArgValue::ColorFixed(c)) => layer.color = Some(AestheticValue::Fixed(c)),
+            ("color", ArgValue::ColorMapped(c)) => layer.color = Some(AestheticValue::Mapped(c)),
+            ("size", ArgValue::NumericFixed(s)) => layer.size = Some(s),
+            ("alpha", ArgValue::NumericFixed(a)) => layer.alpha = Some(a),
+            ("check_overlap", ArgValue::ColorFixed(c)) => layer.check_overlap = c == "true",
+            ("nudge_x", ArgValue::NumericFixed(n)) => layer.nudge_x = n,
+            ("nudge_y", ArgValue::NumericFixed(n)) => layer.nudge_y = n,
+            ("fill", ArgValue::ColorFixed(f)) => fill = Some(f),
+            ("label_padding", ArgValue::NumericFixed(p)) => padding = Some(p),
+            ("label_size", ArgValue::NumericFixed(s)) => border = Some(s),
+            _ => {}
+        }
+    }
+
+    Ok((input, TextArgs { layer, fill, padding, border }))
+}
+
+/// Parse any geometry layer
+pub fn parse_geom(input: &str) -> IResult<&str, Layer> {
+    alt((
+        parse_line,
+        parse_step,
+        parse_point,
+        parse_pointrange,
+        parse_crossbar,
+        parse_beeswarm,
+        parse_bar,
+        parse_col,
+        parse_ribbon,
+        parse_histogram,
+        // Line layers with their own stat (nested: alt takes at most 21 branches)
+        alt((parse_smooth, parse_quantile, parse_ecdf, parse_density, parse_contour, parse_abline, parse_crosshair, parse_rug, parse_segment)),
+        parse_stat_summary,
+        parse_boxplot,
+        parse_violin,
+        parse_tile,
+        parse_hexbin,
+        parse_text,
+        parse_label,
+    ))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_empty() {
+        let result = parse_line("line()");
+        assert!(result.is_ok());
+        let (_, layer) = result.unwrap();
+        match layer {
+            Layer::Line(l) => {
+                assert_eq!(l.color, None);
+                assert_eq!(l.width, None);
+            }
+            _ => panic!("Expected Line layer"),
+        }
+    }
+
+    #[test]
+    fn test_parse_line_with_color() {
+        let result = parse_line(r#"line(color: "red")"#);
+        assert!(result.is_ok());
+        let (_, layer) = result.unwrap();
+        match layer {
+            Layer::Line(l) => {
+                assert_eq!(l.color, Some(AestheticValue::Fixed("red".to_string())));
+            }
+            _ => panic!("Expected Line layer"),
+        }
+    }
+
+    #[test]
     fn test_parse_point_with_size() {
         let result = parse_point("point(size: 5)");
         assert!(result.is_ok());
@@ -499,43 +1827,561 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_bar_empty() {
-        let result = parse_bar("bar()");
-        assert!(result.is_ok());
-        let (_, layer) = result.unwrap();
-        match layer {
-            Layer::Bar(b) => {
-                assert_eq!(b.color, None);
-                assert_eq!(b.alpha, None);
-                assert_eq!(b.position, BarPosition::Identity);
-            }
-            _ => panic!("Expected Bar layer"),
+    fn test_parse_bar_empty() {
+        let result = parse_bar("bar()");
+        assert!(result.is_ok());
+        let (_, layer) = result.unwrap();
+        match layer {
+            Layer::Bar(b) => {
+                assert_eq!(b.color, None);
+                assert_eq!(b.alpha, None);
+                assert_eq!(b.position, LayerPosition::Identity);
+            }
+            _ => panic!("Expected Bar layer"),
+        }
+    }
+
+    #[test]
+    fn test_parse_bar_with_position() {
+        let result = parse_bar(r#"bar(position: "dodge")"#);
+        assert!(result.is_ok());
+        let (_, layer) = result.unwrap();
+        match layer {
+            Layer::Bar(b) => {
+                assert_eq!(b.position, LayerPosition::Dodge);
+            }
+            _ => panic!("Expected Bar layer"),
+        }
+    }
+
+    #[test]
+    fn test_parse_bar_with_stack_position() {
+        let result = parse_bar(r#"bar(position: "stack")"#);
+        assert!(result.is_ok());
+        let (_, layer) = result.unwrap();
+        match layer {
+            Layer::Bar(b) => {
+                assert_eq!(b.position, LayerPosition::Stack);
+            }
+            _ => panic!("Expected Bar layer"),
+        }
+    }
+
+    #[test]
+    fn test_parse_step_directions() {
+        let (rest, layer) = parse_step("step()").unwrap();
+        assert_eq!(rest, "");
+        assert!(matches!(layer, Layer::Line(LineLayer { step: Some(StepDirection::Hv), .. })));
+        assert_eq!(layer.to_string(), "step()");
+
+        let (_, layer) = parse_step(r#"step(direction: "vh", color: "red")"#).unwrap();
+        assert!(matches!(&layer, Layer::Line(LineLayer { step: Some(StepDirection::Vh), color: Some(AestheticValue::Fixed(c)), .. }) if c == "red"));
+
+        let (_, layer) = parse_geom(r#"step(direction: "mid")"#).unwrap();
+        assert!(matches!(layer, Layer::Line(LineLayer { step: Some(StepDirection::Mid), .. })));
+        assert_eq!(layer.to_string(), r#"step(direction: "mid")"#);
+
+        assert!(matches!(parse_step(r#"step(direction: "diagonal")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_line(r#"line(direction: "hv")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_line("line()"), Ok((_, Layer::Line(LineLayer { step: None, .. })))));
+    }
+
+    #[test]
+    fn test_parse_line_linetype() {
+        let (_, layer) = parse_line(r#"line(linetype: "dashed")"#).unwrap();
+        if let Layer::Line(l) = layer {
+            assert_eq!(l.linetype, Some(AestheticValue::Fixed("dashed".to_string())));
+        } else {
+            panic!("Expected Line layer");
+        }
+        let (_, layer) = parse_line("line(linetype: region)").unwrap();
+        if let Layer::Line(l) = layer {
+            assert_eq!(l.linetype, Some(AestheticValue::Mapped("region".to_string())));
+        } else {
+            panic!("Expected Line layer");
+        }
+    }
+
+    #[test]
+    fn test_parse_bar_with_count_stat() {
+        let (_, layer) = parse_bar(r#"bar(stat: "count")"#).unwrap();
+        if let Layer::Bar(b) = layer {
+            assert_eq!(b.stat, crate::parser::ast::Stat::Count);
+        } else {
+            panic!("Expected Bar layer");
+        }
+    }
+
+    #[test]
+    fn test_parse_bar_with_prop_stat() {
+        let (_, layer) = parse_bar(r#"bar(stat: "prop", position: "fill")"#).unwrap();
+        if let Layer::Bar(b) = layer {
+            assert_eq!(b.stat, crate::parser::ast::Stat::Prop);
+            assert_eq!(b.position, LayerPosition::Fill);
+        } else {
+            panic!("Expected Bar layer");
+        }
+
+        let (_, layer) = parse_bar(r#"bar(position: "percent")"#).unwrap();
+        if let Layer::Bar(b) = layer {
+            assert_eq!(b.position, LayerPosition::Percent);
+            assert!(b.position.is_fill());
+        } else {
+            panic!("Expected Bar layer");
+        }
+    }
+
+    #[test]
+    fn test_parse_bar_with_aggregate_stats() {
+        use crate::parser::ast::Stat;
+        for (name, stat) in [("sum", Stat::Sum), ("mean", Stat::Mean), ("max", Stat::Max), ("min", Stat::Min)] {
+            let (_, layer) = parse_bar(&format!(r#"bar(stat: "{}")"#, name)).unwrap();
+            assert_eq!(layer.stat(), &stat);
+        }
+        assert!(parse_col(r#"col(stat: "sum")"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_bar_rejects_unknown_stat() {
+        use crate::parser::ast::Stat;
+        assert_eq!(parse_bar(r#"bar(stat: "identity")"#).unwrap().1.stat(), &Stat::Identity);
+        assert!(matches!(parse_bar(r#"bar(stat: "bogus")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_bar(r#"bar(stat: "bin")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_col() {
+        let (_, layer) = parse_col(r#"col(y: value, position: "dodge")"#).unwrap();
+        if let Layer::Bar(b) = layer {
+            assert_eq!(b.stat, crate::parser::ast::Stat::Identity);
+            assert_eq!(b.y, Some("value".to_string()));
+            assert_eq!(b.position, LayerPosition::Dodge);
+            assert!(b.require_y);
+        } else {
+            panic!("Expected Bar layer");
+        }
+        assert!(parse_col(r#"col(stat: "identity")"#).is_ok());
+    }
+
+    #[test]
+    fn test_parse_col_rejects_non_identity_stat() {
+        assert!(matches!(parse_col(r#"col(stat: "count")"#), Err(nom::Err::Failure(_))));
+        assert!(parse_geom(r#"col(stat: "prop")"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_smooth_args() {
+        let (_, layer) = parse_smooth("smooth()").unwrap();
+        if let Layer::Line(l) = layer {
+            assert_eq!(l.stat, crate::parser::ast::Stat::Smooth { method: SmoothMethod::Lm, n: 80, residuals: false, formula: None, fullrange: false, se: SeType::None });
+        } else {
+            panic!("Expected Line layer");
+        }
+
+        let (_, layer) = parse_smooth(r#"smooth(method: "poly", n: 200)"#).unwrap();
+        if let Layer::Line(l) = layer {
+            assert_eq!(l.stat, crate::parser::ast::Stat::Smooth { method: SmoothMethod::Poly, n: 200, residuals: false, formula: None, fullrange: false, se: SeType::None });
+        } else {
+            panic!("Expected Line layer");
+        }
+
+        let (_, layer) = parse_smooth("smooth(residuals: true)").unwrap();
+        if let Layer::Line(l) = layer {
+            assert_eq!(l.stat, crate::parser::ast::Stat::Smooth { method: SmoothMethod::Lm, n: 80, residuals: true, formula: None, fullrange: false, se: SeType::None });
+        } else {
+            panic!("Expected Line layer");
+        }
+
+        let (_, layer) = parse_smooth(r#"smooth(method: "loess", formula: "y ~ poly(x, 3)")"#).unwrap();
+        let Layer::Line(l) = layer else { panic!("Expected Line layer") };
+        let crate::parser::ast::Stat::Smooth { formula, .. } = l.stat else { panic!("Expected smooth stat") };
+        assert_eq!(formula.as_deref(), Some("y ~ poly(x, 3)"));
+    }
+
+    #[test]
+    fn test_parse_smooth_se() {
+        let se = |dsl: &str| match parse_smooth(dsl) {
+            Ok((_, Layer::Line(LineLayer { stat: crate::parser::ast::Stat::Smooth { se, .. }, .. }))) => Some(se),
+            _ => None,
+        };
+        assert_eq!(se("smooth(se: true)"), Some(SeType::Confidence(0.95)));
+        assert_eq!(se("smooth(se: false)"), Some(SeType::None));
+        assert_eq!(se(r#"smooth(se: "prediction", level: 0.9)"#), Some(SeType::Prediction(0.9)));
+        assert_eq!(se(r#"smooth(level: 0.8, se: "confidence")"#), Some(SeType::Confidence(0.8)));
+        // Unknown interval types, levels outside (0, 1), and a level with no band are rejected
+        assert!(matches!(parse_smooth(r#"smooth(se: "tolerance")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_smooth("smooth(se: true, level: 1.5)"), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_smooth("smooth(level: 0.9)"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_quantile() {
+        let (_, layer) = parse_quantile("quantile()").unwrap();
+        if let Layer::Line(l) = layer {
+            assert_eq!(l.stat, crate::parser::ast::Stat::Quantile { quantiles: vec![0.25, 0.5, 0.75] });
+        } else {
+            panic!("Expected Line layer");
+        }
+
+        let (_, layer) = parse_quantile(r#"quantile(quantiles: [0.1, 0.9], method: "rq")"#).unwrap();
+        if let Layer::Line(l) = layer {
+            assert_eq!(l.stat, crate::parser::ast::Stat::Quantile { quantiles: vec![0.1, 0.9] });
+        } else {
+            panic!("Expected Line layer");
+        }
+
+        assert!(matches!(parse_quantile("quantile(quantiles: [0.5, 1])"), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_quantile("quantile(quantiles: [])"), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_quantile(r#"quantile(method: "lm")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_ecdf() {
+        let (_, layer) = parse_ecdf("ecdf()").unwrap();
+        assert!(matches!(layer, Layer::Line(LineLayer { stat: crate::parser::ast::Stat::Ecdf { pad: true, n: None }, .. })));
+
+        let (_, layer) = parse_ecdf("ecdf(pad: false, n: 50)").unwrap();
+        assert!(matches!(layer, Layer::Line(LineLayer { stat: crate::parser::ast::Stat::Ecdf { pad: false, n: Some(50) }, .. })));
+
+        assert!(matches!(parse_ecdf("ecdf(n: 1)"), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_ecdf("ecdf(n: 2.5)"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_stat_summary_formulas() {
+        use crate::parser::ast::Stat;
+        use SummaryExpr::{Add, Fun, Sub};
+
+        let (_, layer) = parse_stat_summary("stat_summary()").unwrap();
+        assert_eq!(layer.stat(), &Stat::Summary {
+            fun: Fun(SummaryFun::Mean),
+            fun_ymin: Sub(Box::new(Fun(SummaryFun::Mean)), Box::new(Fun(SummaryFun::Se))),
+            fun_ymax: Add(Box::new(Fun(SummaryFun::Mean)), Box::new(Fun(SummaryFun::Se))),
+        });
+
+        let (_, layer) = parse_stat_summary(r#"stat_summary(fun: "median", fun_ymin: "q25", fun_ymax: "max - sd + se")"#).unwrap();
+        let Stat::Summary { fun, fun_ymin, fun_ymax } = layer.stat() else { panic!("Expected Stat::Summary") };
+        assert_eq!(fun, &Fun(SummaryFun::Median));
+        assert_eq!(fun_ymin, &Fun(SummaryFun::Q25));
+        // Left-associative: (max - sd) + se
+        assert_eq!(fun_ymax, &Add(Box::new(Sub(Box::new(Fun(SummaryFun::Max)), Box::new(Fun(SummaryFun::Sd)))), Box::new(Fun(SummaryFun::Se))));
+
+        assert!(matches!(parse_stat_summary(r#"stat_summary(fun: "mode")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_stat_summary(r#"stat_summary(fun_ymin: "mean -")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_stat_summary(r#"stat_summary(fun_ymax: "mean * 2")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_beeswarm() {
+        use crate::parser::ast::Stat;
+
+        let (_, layer) = parse_beeswarm("beeswarm()").unwrap();
+        assert_eq!(layer.stat(), &Stat::Beeswarm { priority: BeeswarmPriority::Ascending, cex: 1.0 });
+        assert!(layer.requires_categorical_x());
+
+        let (_, layer) = parse_geom(r#"beeswarm(groupOnX: true, priority: "none", cex: 1.5, color: species, size: 2)"#).unwrap();
+        let Layer::Point(p) = layer else { panic!("Expected Point layer") };
+        assert_eq!(p.stat, Stat::Beeswarm { priority: BeeswarmPriority::DataOrder, cex: 1.5 });
+        assert_eq!(p.color, Some(AestheticValue::Mapped("species".to_string())));
+        assert_eq!(p.size, Some(AestheticValue::Fixed(2.0)));
+
+        // Horizontal swarms come from coord_flip(), and placement arguments belong to beeswarm() alone
+        assert!(parse_beeswarm("beeswarm(groupOnX: false)").is_err());
+        assert!(parse_beeswarm(r#"beeswarm(priority: "random")"#).is_err());
+        assert!(parse_beeswarm("beeswarm(cex: 0)").is_err());
+        assert!(parse_point("point(cex: 2)").is_err());
+        assert!(parse_pointrange(r#"pointrange(ymin: lo, ymax: hi, priority: "ascending")"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_contour() {
+        use crate::parser::ast::Stat;
+
+        let (_, layer) = parse_contour("contour()").unwrap();
+        assert_eq!(layer.stat(), &Stat::Contour { bins: 10, filled: false, bandwidth_x: None, bandwidth_y: None });
+
+        let (_, layer) = parse_contour(r#"contour(bins: 5, filled: true, color: "black", alpha: 0.5, linetype: "dashed")"#).unwrap();
+        let Layer::Line(l) = layer else { panic!("Expected Layer::Line") };
+        assert_eq!(l.stat, Stat::Contour { bins: 5, filled: true, bandwidth_x: None, bandwidth_y: None });
+        assert_eq!(l.color, Some(AestheticValue::Fixed("black".to_string())));
+        assert_eq!(l.alpha, Some(AestheticValue::Fixed(0.5)));
+        assert_eq!(l.linetype, Some(AestheticValue::Fixed("dashed".to_string())));
+
+        assert!(matches!(parse_contour("contour(bins: 0)"), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_contour("contour(bins: 2.5)"), Err(nom::Err::Failure(_))));
+
+        let (rest, layer) = parse_geom("contour_filled(bins: 4, alpha: 0.7)").unwrap();
+        assert_eq!(rest, "");
+        let Layer::Line(l) = &layer else { panic!("Expected Layer::Line") };
+        assert_eq!(l.stat, Stat::Contour { bins: 4, filled: true, bandwidth_x: None, bandwidth_y: None });
+        assert_eq!(l.alpha, Some(AestheticValue::Fixed(0.7)));
+        assert_eq!(layer.to_string(), "contour(bins: 4, filled: true, alpha: 0.7)");
+        assert!(matches!(parse_contour("contour_filled(filled: false)"), Err(nom::Err::Failure(_))));
+
+        let (rest, layer) = parse_geom(r#"density_2d(bins: 8, bandwidth_x: 0.5, color: "black")"#).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(layer.stat(), &Stat::Contour { bins: 8, filled: false, bandwidth_x: Some(0.5), bandwidth_y: None });
+        assert_eq!(layer.to_string(), r#"contour(bins: 8, bandwidth_x: 0.5, color: "black")"#);
+        assert!(matches!(parse_contour("density_2d(bandwidth_y: 0)"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_crosshair() {
+        use crate::parser::ast::Stat;
+
+        let (_, layer) = parse_geom(r#"crosshair(x: a, alpha: 0.1, color: "red")"#).unwrap();
+        let Layer::Line(l) = layer else { panic!("Expected Layer::Line") };
+        assert_eq!(l.stat, Stat::Crosshair);
+        assert_eq!(l.x.as_deref(), Some("a"));
+        assert_eq!(l.alpha, Some(AestheticValue::Fixed(0.1)));
+        assert_eq!(l.color, Some(AestheticValue::Fixed("red".to_string())));
+    }
+
+    #[test]
+    fn test_parse_rug() {
+        use crate::parser::ast::Stat;
+
+        let (_, layer) = parse_rug("rug()").unwrap();
+        assert_eq!(layer.stat(), &Stat::Rug { length: 0.03, jitter: false });
+
+        let (rest, layer) = parse_geom(r#"rug(x: height, length: 0.05, jitter: true, color: "black", alpha: 0.5)"#).unwrap();
+        assert_eq!(rest, "");
+        let Layer::Line(l) = &layer else { panic!("Expected Layer::Line") };
+        assert_eq!(l.stat, Stat::Rug { length: 0.05, jitter: true });
+        assert_eq!(l.x, Some("height".to_string()));
+        assert_eq!(l.color, Some(AestheticValue::Fixed("black".to_string())));
+        assert_eq!(layer.to_string(), r#"rug(x: height, length: 0.05, jitter: true, color: "black", alpha: 0.5)"#);
+
+        assert!(matches!(parse_rug("rug(length: 0)"), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_rug("rug(length: 2)"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_segment() {
+        use crate::parser::ast::Stat;
+
+        let (rest, layer) = parse_geom("segment(xend: x2, yend: y2)").unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(layer.stat(), &Stat::Segment { xend: Some("x2".to_string()), yend: Some("y2".to_string()), arrow: None });
+
+        let (_, layer) = parse_segment(r#"segment(xend: x2, yend: y2, color: "red", arrow: true, arrow_type: "open", arrow_ends: "both")"#).unwrap();
+        let Layer::Line(l) = &layer else { panic!("Expected Layer::Line") };
+        assert_eq!(l.stat, Stat::Segment {
+            xend: Some("x2".to_string()),
+            yend: Some("y2".to_string()),
+            arrow: Some(Arrow { head: ArrowType::Open, ends: ArrowEnds::Both }),
+        });
+        assert_eq!(l.color, Some(AestheticValue::Fixed("red".to_string())));
+
+        let (_, layer) = parse_segment("segment(xend: x2, yend: y2, arrow: true)").unwrap();
+        assert_eq!(layer.stat(), &Stat::Segment { xend: Some("x2".to_string()), yend: Some("y2".to_string()), arrow: Some(Arrow::default()) });
+        assert_eq!(layer.to_string(), "segment(xend: x2, yend: y2, arrow: true)");
+
+        // Styles need arrow: true, and names must be known
+        assert!(matches!(parse_segment(r#"segment(xend: x2, yend: y2, arrow_type: "open")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_segment(r#"segment(xend: x2, yend: y2, arrow: true, arrow_ends: "middle")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_abline() {
+        use crate::parser::ast::Stat;
+
+        let (_, layer) = parse_geom("abline()").unwrap();
+        assert_eq!(layer.stat(), &Stat::Abline { slope: AestheticValue::Fixed(1.0), intercept: AestheticValue::Fixed(0.0) });
+
+        let (_, layer) = parse_geom(r#"abline(slope: slope, intercept: -2.5, color: model, linetype: "dashed")"#).unwrap();
+        let Layer::Line(l) = layer else { panic!("Expected Layer::Line") };
+        assert_eq!(l.stat, Stat::Abline { slope: AestheticValue::Mapped("slope".to_string()), intercept: AestheticValue::Fixed(-2.5) });
+        assert_eq!(l.color, Some(AestheticValue::Mapped("model".to_string())));
+        assert_eq!(l.linetype, Some(AestheticValue::Fixed("dashed".to_string())));
+        assert_eq!(l.legend, None);
+
+        let (_, layer) = parse_geom(r#"abline(slope: 0, intercept: 5, legend: "Target")"#).unwrap();
+        let Layer::Line(l) = &layer else { panic!("Expected Layer::Line") };
+        assert_eq!(l.legend.as_deref(), Some("Target"));
+        assert_eq!(layer.to_string(), r#"abline(slope: 0, intercept: 5, legend: "Target")"#);
+        assert!(parse_line(r#"line(legend: "Target")"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_density() {
+        let (_, layer) = parse_density("density()").unwrap();
+        assert!(matches!(layer, Layer::Line(LineLayer { stat: crate::parser::ast::Stat::Density { bw: BandwidthMethod::Nrd0, n: 512, fill: false }, .. })));
+
+        let (_, layer) = parse_density(r#"density(bw: "ucv", n: 64, fill: true)"#).unwrap();
+        assert!(matches!(layer, Layer::Line(LineLayer { stat: crate::parser::ast::Stat::Density { bw: BandwidthMethod::Ucv, n: 64, fill: true }, .. })));
+
+        assert!(matches!(parse_density("density(bw: -1)"), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_density("density(n: 1)"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_geom_registry_matches_parser() {
+        for geom in GEOMS {
+            let call = format!("{}()", geom.name);
+            assert!(parse_geom(&call).is_ok(), "{} is listed but does not parse", call);
+        }
+    }
+
+    #[test]
+    fn test_parse_histogram_stat_output_as() {
+        let (_, layer) = parse_geom(r#"histogram(bins: 20, stat_output_as: "binned")"#).unwrap();
+        let Layer::Bar(b) = &layer else { panic!("Expected Bar layer") };
+        assert_eq!(b.stat_output_as, Some(StatOutput::Binned));
+        assert_eq!(layer.to_string(), r#"histogram(bins: 20, stat_output_as: "binned")"#);
+
+        assert!(matches!(parse_histogram(r#"histogram(stat_output_as: "raw")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_bar(r#"bar(stat_output_as: "binned")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_col(r#"col(stat_output_as: "binned")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_histogram_with_fill() {
+        let (_, layer) = parse_histogram(r#"histogram(bins: 20, fill: group, alpha: 0.5, position: "identity")"#).unwrap();
+        if let Layer::Bar(b) = layer {
+            assert_eq!(b.stat, crate::parser::ast::Stat::Bin { bins: 20 });
+            assert_eq!(b.color, Some(AestheticValue::Mapped("group".to_string())));
+            assert_eq!(b.alpha, Some(AestheticValue::Fixed(0.5)));
+            assert_eq!(b.position, LayerPosition::Identity);
+        } else {
+            panic!("Expected Bar layer");
         }
+
+        let (_, layer) = parse_histogram(r#"histogram(fill: "steelblue", position: "stack")"#).unwrap();
+        if let Layer::Bar(b) = layer {
+            assert_eq!(b.stat, crate::parser::ast::Stat::Bin { bins: 30 });
+            assert_eq!(b.color, Some(AestheticValue::Fixed("steelblue".to_string())));
+            assert_eq!(b.position, LayerPosition::Stack);
+        } else {
+            panic!("Expected Bar layer");
+        }
+
+        assert!(matches!(parse_histogram(r#"histogram(stat: "count")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_bar("bar(bins: 5)"), Err(nom::Err::Failure(_))));
     }
 
     #[test]
-    fn test_parse_bar_with_position() {
-        let result = parse_bar(r#"bar(position: "dodge")"#);
-        assert!(result.is_ok());
-        let (_, layer) = result.unwrap();
-        match layer {
-            Layer::Bar(b) => {
-                assert_eq!(b.position, BarPosition::Dodge);
-            }
-            _ => panic!("Expected Bar layer"),
+    fn test_parse_boxplot_varwidth() {
+        let (_, layer) = parse_boxplot("boxplot(varwidth: true, width: 0.6)").unwrap();
+        if let Layer::Boxplot(b) = layer {
+            assert!(b.varwidth);
+            assert_eq!(b.width, Some(AestheticValue::Fixed(0.6)));
+        } else {
+            panic!("Expected Boxplot layer");
+        }
+
+        let (_, layer) = parse_boxplot("boxplot()").unwrap();
+        if let Layer::Boxplot(b) = layer {
+            assert!(!b.varwidth);
+        } else {
+            panic!("Expected Boxplot layer");
         }
     }
 
     #[test]
-    fn test_parse_bar_with_stack_position() {
-        let result = parse_bar(r#"bar(position: "stack")"#);
-        assert!(result.is_ok());
-        let (_, layer) = result.unwrap();
-        match layer {
-            Layer::Bar(b) => {
-                assert_eq!(b.position, BarPosition::Stack);
-            }
-            _ => panic!("Expected Bar layer"),
+    fn test_parse_boxplot_notch() {
+        let (_, layer) = parse_boxplot("boxplot(notch: true, notchwidth: 0.3)").unwrap();
+        let Layer::Boxplot(b) = layer else { panic!("Expected Boxplot layer") };
+        assert!(b.notch);
+        assert_eq!(b.notchwidth, Some(0.3));
+        let (_, layer) = parse_boxplot("boxplot(staplewidth: 0)").unwrap();
+        let Layer::Boxplot(b) = layer else { panic!("Expected Boxplot layer") };
+        assert_eq!(b.staplewidth, Some(0.0));
+
+        let (_, layer) = parse_boxplot("boxplot()").unwrap();
+        let Layer::Boxplot(b) = layer else { panic!("Expected Boxplot layer") };
+        assert!(!b.notch);
+        assert_eq!(b.notchwidth, None);
+    }
+
+    #[test]
+    fn test_parse_violin_bandwidth() {
+        let stat_of = |input: &str| match parse_violin(input).unwrap().1 {
+            Layer::Violin(v) => v.stat,
+            _ => panic!("Expected Violin layer"),
+        };
+        assert_eq!(stat_of("violin()"), crate::parser::ast::Stat::Violin { draw_quantiles: vec![], bw: BandwidthMethod::Nrd0, scale: ViolinScale::Width, trim: true });
+        assert_eq!(
+            stat_of(r#"violin(draw_quantiles: [0.5], bw: "nrd")"#),
+            crate::parser::ast::Stat::Violin { draw_quantiles: vec![0.5], bw: BandwidthMethod::Nrd, scale: ViolinScale::Width, trim: true }
+        );
+        assert_eq!(stat_of(r#"violin(bw: "ucv")"#), crate::parser::ast::Stat::Violin { draw_quantiles: vec![], bw: BandwidthMethod::Ucv, scale: ViolinScale::Width, trim: true });
+        assert_eq!(stat_of("violin(bw: 0.5)"), crate::parser::ast::Stat::Violin { draw_quantiles: vec![], bw: BandwidthMethod::Fixed(0.5), scale: ViolinScale::Width, trim: true });
+
+        assert!(matches!(parse_violin(r#"violin(bw: "sj")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_violin("violin(bw: 0)"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_violin_scale() {
+        let (_, layer) = parse_violin(r#"violin(scale: "count")"#).unwrap();
+        if let Layer::Violin(v) = layer {
+            assert_eq!(v.scale, ViolinScale::Count);
+            assert!(matches!(v.stat, crate::parser::ast::Stat::Violin { scale: ViolinScale::Count, .. }));
+        } else {
+            panic!("Expected Violin layer");
+        }
+        assert!(matches!(parse_violin(r#"violin(scale: "area")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_violin_side() {
+        let side_of = |input: &str| match parse_violin(input).unwrap().1 {
+            Layer::Violin(v) => v.side,
+            _ => panic!("Expected Violin layer"),
+        };
+        assert_eq!(side_of("violin()"), ViolinSide::Both);
+        assert_eq!(side_of(r#"violin(side: "left")"#), ViolinSide::Left);
+        assert_eq!(side_of(r#"violin(side: "right", trim: false)"#), ViolinSide::Right);
+        assert!(matches!(parse_violin(r#"violin(side: "top")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_violin_trim() {
+        let (_, layer) = parse_violin("violin()").unwrap();
+        if let Layer::Violin(v) = layer {
+            assert!(v.trim);
+        } else {
+            panic!("Expected Violin layer");
+        }
+
+        let (_, layer) = parse_violin("violin(trim: false)").unwrap();
+        if let Layer::Violin(v) = layer {
+            assert!(!v.trim);
+            assert!(matches!(v.stat, crate::parser::ast::Stat::Violin { trim: false, .. }));
+        } else {
+            panic!("Expected Violin layer");
+        }
+    }
+
+    #[test]
+    fn test_parse_smooth_loess_span() {
+        let (_, layer) = parse_smooth(r#"smooth(method: "loess", span: 0.5)"#).unwrap();
+        if let Layer::Line(l) = layer {
+            assert_eq!(l.stat, crate::parser::ast::Stat::Smooth { method: SmoothMethod::Loess { span: 0.5 }, n: 80, residuals: false, formula: None, fullrange: false, se: SeType::None });
+        } else {
+            panic!("Expected Line layer");
+        }
+
+        let (_, layer) = parse_smooth(r#"smooth(method: "loess")"#).unwrap();
+        if let Layer::Line(l) = layer {
+            assert_eq!(l.stat, crate::parser::ast::Stat::Smooth { method: SmoothMethod::Loess { span: 0.75 }, n: 80, residuals: false, formula: None, fullrange: false, se: SeType::None });
+        } else {
+            panic!("Expected Line layer");
+        }
+
+        assert!(matches!(parse_smooth(r#"smooth(method: "loess", span: 0)"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_smooth(r#"smooth(method: "loess", span: 1.5)"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_smooth(r#"smooth(method: "spline")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_bar_with_fill_position() {
+        let (_, layer) = parse_bar(r#"bar(position: "fill")"#).unwrap();
+        if let Layer::Bar(b) = layer {
+            assert_eq!(b.position, LayerPosition::Fill);
+        } else {
+            panic!("Expected Bar layer");
         }
     }
 
@@ -559,7 +2405,7 @@ mod tests {
         let (_, layer) = result.unwrap();
         match layer {
             Layer::Bar(b) => {
-                assert_eq!(b.position, BarPosition::Stack);
+                assert_eq!(b.position, LayerPosition::Stack);
                 assert_eq!(b.color, Some(AestheticValue::Fixed("blue".to_string())));
                 assert_eq!(b.alpha, Some(AestheticValue::Fixed(0.7)));
                 assert_eq!(b.width, Some(AestheticValue::Fixed(0.6)));
@@ -568,6 +2414,166 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_bar_with_border() {
+        let (_, layer) = parse_bar(r#"bar(fill: "none", border: "black", border_width: 1.5)"#).unwrap();
+        match layer {
+            Layer::Bar(b) => {
+                assert!(b.hollow);
+                assert_eq!(b.color, None);
+                assert_eq!(b.border, Some("black".to_string()));
+                assert_eq!(b.border_width, Some(1.5));
+            }
+            _ => panic!("Expected Bar layer"),
+        }
+        let (_, layer) = parse_bar(r#"bar(color: "red", border: "navy")"#).unwrap();
+        match layer {
+            Layer::Bar(b) => {
+                assert!(!b.hollow);
+                assert_eq!(b.color, Some(AestheticValue::Fixed("red".to_string())));
+                assert_eq!(b.border, Some("navy".to_string()));
+            }
+            _ => panic!("Expected Bar layer"),
+        }
+    }
+
+    #[test]
+    fn test_parse_tile() {
+        let (rest, layer) = parse_tile(r#"tile(fill: value, color: "white", width: 0.9)"#).unwrap();
+        assert_eq!(rest, "");
+        match layer {
+            Layer::Tile(t) => {
+                assert_eq!(t.fill, Some(AestheticValue::Mapped("value".to_string())));
+                assert_eq!(t.color, Some("white".to_string()));
+                assert_eq!(t.width, Some(0.9));
+                assert_eq!(t.height, None);
+            }
+            _ => panic!("Expected Tile layer"),
+        }
+    }
+
+    #[test]
+    fn test_parse_ribbon_border() {
+        let (rest, layer) = parse_ribbon(r#"ribbon(color: "blue", border: "navy", linetype: "dashed")"#).unwrap();
+        assert_eq!(rest, "");
+        match layer {
+            Layer::Ribbon(r) => {
+                assert_eq!(r.color, Some(AestheticValue::Fixed("blue".to_string())));
+                assert_eq!(r.border, Some("navy".to_string()));
+                assert_eq!(r.linetype, Some("dashed".to_string()));
+            }
+            _ => panic!("Expected Ribbon layer"),
+        }
+    }
+
+    #[test]
+    fn test_parse_tile_na_color() {
+        let (_, layer) = parse_tile(r##"tile(fill: value, na_color: "#eeeeee", drop_na: true)"##).unwrap();
+        match layer {
+            Layer::Tile(t) => {
+                assert_eq!(t.na_color, Some("#eeeeee".to_string()));
+                assert!(t.drop_na);
+            }
+            _ => panic!("Expected Tile layer"),
+        }
+        assert!(matches!(parse_tile(r#"tile(na_color: "nocolor")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_text() {
+        let (rest, layer) = parse_text(r#"text(label: name, color: "red", size: 10, check_overlap: true, nudge_y: -0.5)"#).unwrap();
+        assert_eq!(rest, "");
+        match layer {
+            Layer::Text(t) => {
+                assert_eq!(t.label, Some(AestheticValue::Mapped("name".to_string())));
+                assert_eq!(t.color, Some(AestheticValue::Fixed("red".to_string())));
+                assert_eq!(t.size, Some(10.0));
+                assert!(t.check_overlap);
+                assert_eq!((t.nudge_x, t.nudge_y), (0.0, -0.5));
+            }
+            _ => panic!("Expected Text layer"),
+        }
+        assert_eq!(parse_geom(r#"text(label: "x")"#).unwrap().1, Layer::Text(TextLayer {
+            label: Some(AestheticValue::Fixed("x".to_string())),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_parse_label() {
+        let (_, layer) = parse_label("label(label: name)").unwrap();
+        let Layer::Text(t) = layer else { panic!("Expected Text layer") };
+        assert_eq!(t.label_box, Some(LabelBox::default()));
+
+        let (_, layer) = parse_geom(r#"label(label: name, fill: "lightyellow", label_padding: 6, label_size: 0)"#).unwrap();
+        let Layer::Text(t) = layer else { panic!("Expected Text layer") };
+        assert_eq!(t.label_box, Some(LabelBox { fill: "lightyellow".to_string(), padding: 6.0, border: 0.0 }));
+
+        assert!(matches!(parse_label("label(label_padding: -1)"), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_text(r#"text(fill: "white")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_pointrange() {
+        let (_, layer) = parse_geom("pointrange(ymin: lo, ymax: hi, size: 3)").unwrap();
+        let Layer::Point(p) = layer else { panic!("Expected Point layer") };
+        assert_eq!(p.range, Some(PointRange { ymin: Some("lo".to_string()), ymax: Some("hi".to_string()), ..Default::default() }));
+        assert_eq!(p.size, Some(AestheticValue::Fixed(3.0)));
+
+        let (_, layer) = parse_geom("pointrange(xmin: start, xmax: end)").unwrap();
+        let Layer::Point(p) = layer else { panic!("Expected Point layer") };
+        assert_eq!(p.range, Some(PointRange { xmin: Some("start".to_string()), xmax: Some("end".to_string()), ..Default::default() }));
+
+        // Column-less pointrange() takes its interval from aes()
+        let (_, layer) = parse_geom("pointrange()").unwrap();
+        assert!(matches!(layer, Layer::Point(PointLayer { range: Some(_), .. })));
+
+        assert!(matches!(parse_pointrange("pointrange(xmin: start)"), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_point("point(ymin: lo)"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_crossbar() {
+        let (_, layer) = parse_geom(r#"crossbar(ymin: lo, ymax: hi, color: g, width: 0.6, position: "dodge")"#).unwrap();
+        let Layer::Point(p) = layer else { panic!("Expected Point layer") };
+        assert!(p.crossbar);
+        assert_eq!(p.range, Some(PointRange { ymin: Some("lo".to_string()), ymax: Some("hi".to_string()), ..Default::default() }));
+        assert_eq!((p.width, p.position), (Some(0.6), LayerPosition::Dodge));
+
+        // pointrange() dodges the same way; other positions are bar-only
+        let (_, layer) = parse_geom(r#"pointrange(position: "dodge")"#).unwrap();
+        assert!(matches!(layer, Layer::Point(PointLayer { crossbar: false, position: LayerPosition::Dodge, .. })));
+        assert!(matches!(parse_crossbar(r#"crossbar(position: "stack")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_point(r#"point(position: "dodge")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_hexbin() {
+        let (_, layer) = parse_hexbin("hexbin()").unwrap();
+        assert_eq!(layer, Layer::Hexbin(HexbinLayer::default()));
+
+        let (_, layer) = parse_hexbin(r#"hexbin(bins: 12, color: "white")"#).unwrap();
+        match layer {
+            Layer::Hexbin(h) => {
+                assert_eq!(h.stat, crate::parser::ast::Stat::Hexbin { bins: 12 });
+                assert_eq!(h.color, Some("white".to_string()));
+            }
+            _ => panic!("Expected Hexbin layer"),
+        }
+
+        assert!(matches!(parse_hexbin("hexbin(bins: 0)"), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_hexbin("hexbin(bins: 2.5)"), Err(nom::Err::Failure(_))));
+
+        let (_, layer) = parse_hexbin(r#"hexbin(bins: 20, fill: "mean_y", size: "count")"#).unwrap();
+        let Layer::Hexbin(h) = &layer else { panic!("Expected Hexbin layer") };
+        assert_eq!(h.fill, HexSummary::MeanY);
+        assert!(h.size_by_count);
+        assert_eq!(layer.to_string(), r#"hexbin(bins: 20, fill: "mean_y", size: "count")"#);
+
+        assert!(matches!(parse_hexbin(r#"hexbin(fill: "median_y")"#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_hexbin(r#"hexbin(size: "mean_y")"#), Err(nom::Err::Failure(_))));
+    }
+
     #[test]
     fn test_parse_line_all_params() {
         // Test line with all parameters: x, y, color, width, alpha
@@ -586,6 +2592,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_point_open_shape_with_fill() {
+        let (_, layer) = parse_point(r#"point(color: "black", fill: "white", shape: "circle_open")"#).unwrap();
+        match layer {
+            Layer::Point(p) => {
+                assert_eq!(p.shape, Some(AestheticValue::Fixed("circle_open".to_string())));
+                assert_eq!(p.fill, Some("white".to_string()));
+            }
+            _ => panic!("Expected Point layer"),
+        }
+    }
+
     #[test]
     fn test_parse_point_all_params() {
         // Test point with all parameters: x, y, color, size, alpha
@@ -627,7 +2645,7 @@ mod tests {
         let (_, layer) = result.unwrap();
         match layer {
             Layer::Bar(b) => {
-                assert_eq!(b.position, BarPosition::Identity); // Should default
+                assert_eq!(b.position, LayerPosition::Identity); // Should default
             }
             _ => panic!("Expected Bar layer"),
         }
@@ -647,4 +2665,518 @@ mod tests {
             _ => panic!("Expected Line layer"),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use crate::parser::ast::Stat;
+    use proptest::prelude::*;
+
+    fn column() -> impl Strategy<Value = String> {
+        "[a-zA-Z_][a-zA-Z0-9_]{0,8}"
+    }
+
+    fn literal() -> impl Strategy<Value = String> {
+        "[a-zA-Z0-9#_ -]{1,10}"
+    }
+
+    fn number() -> impl Strategy<Value = f64> {
+        prop_oneof![
+            (-100i32..100).prop_map(f64::from),
+            proptest::num::f64::NORMAL | proptest::num::f64::ZERO,
+        ]
+    }
+
+    // Variants are ordered simplest first so failures shrink towards fixed values
+    fn aes_string() -> impl Strategy<Value = AestheticValue<String>> {
+        prop_oneof![literal().prop_map(AestheticValue::Fixed), column().prop_map(AestheticValue::Mapped)]
+    }
+
+    fn aes_number() -> impl Strategy<Value = AestheticValue<f64>> {
+        prop_oneof![number().prop_map(AestheticValue::Fixed), column().prop_map(AestheticValue::Mapped)]
+    }
+
+    fn line() -> impl Strategy<Value = Layer> {
+        (
+            proptest::option::of(column()),
+            proptest::option::of(column()),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_string()),
+        )
+            .prop_map(|(x, y, color, width, alpha, linetype)| {
+                Layer::Line(LineLayer { x, y, color, width, alpha, linetype, ..Default::default() })
+            })
+    }
+
+    fn step() -> impl Strategy<Value = Layer> {
+        let direction = prop_oneof![Just(StepDirection::Hv), Just(StepDirection::Vh), Just(StepDirection::Mid)];
+        (line(), direction).prop_map(|(layer, direction)| match layer {
+            Layer::Line(l) => Layer::Line(LineLayer { step: Some(direction), ..l }),
+            other => other,
+        })
+    }
+
+    fn smooth() -> impl Strategy<Value = Layer> {
+        let method = prop_oneof![
+            Just(SmoothMethod::Lm),
+            Just(SmoothMethod::Poly),
+            (1u32..=100).prop_map(|s| SmoothMethod::Loess { span: f64::from(s) / 100.0 }),
+        ];
+        let formula = proptest::option::of(prop_oneof![
+            Just("y ~ x".to_string()),
+            (1usize..5).prop_map(|d| format!("y ~ poly(x,{})", d)),
+            Just("y ~ log(x)".to_string()),
+        ]);
+        let se = prop_oneof![
+            Just(SeType::None),
+            (1u32..100).prop_map(|l| SeType::Confidence(f64::from(l) / 100.0)),
+            (1u32..100).prop_map(|l| SeType::Prediction(f64::from(l) / 100.0)),
+        ];
+        (method, 1usize..500, any::<bool>(), formula, any::<bool>(), se).prop_map(|(method, n, residuals, formula, fullrange, se)| {
+            Layer::Line(LineLayer { stat: Stat::Smooth { method, n, residuals, formula, fullrange, se }, ..Default::default() })
+        })
+    }
+
+    fn quantile() -> impl Strategy<Value = Layer> {
+        proptest::collection::vec((1u32..100).prop_map(|q| f64::from(q) / 100.0), 1..4).prop_map(|quantiles| {
+            Layer::Line(LineLayer { stat: Stat::Quantile { quantiles }, ..Default::default() })
+        })
+    }
+
+    fn ecdf() -> impl Strategy<Value = Layer> {
+        (any::<bool>(), proptest::option::of(2usize..1000)).prop_map(|(pad, n)| {
+            Layer::Line(LineLayer { stat: Stat::Ecdf { pad, n }, ..Default::default() })
+        })
+    }
+
+    fn density() -> impl Strategy<Value = Layer> {
+        let bw = prop_oneof![
+            Just(BandwidthMethod::Nrd0),
+            Just(BandwidthMethod::Nrd),
+            Just(BandwidthMethod::Ucv),
+            (1u32..1000).prop_map(|b| BandwidthMethod::Fixed(f64::from(b) / 100.0)),
+        ];
+        (bw, 2usize..2000, any::<bool>()).prop_map(|(bw, n, fill)| {
+            Layer::Line(LineLayer { stat: Stat::Density { bw, n, fill }, ..Default::default() })
+        })
+    }
+
+    fn summary_expr() -> impl Strategy<Value = SummaryExpr> {
+        let fun = proptest::sample::select(SummaryFun::ALL.to_vec());
+        (fun.clone(), proptest::collection::vec((any::<bool>(), fun), 0..3)).prop_map(|(first, rest)| {
+            rest.into_iter().fold(SummaryExpr::Fun(first), |acc, (plus, f)| {
+                let (a, b) = (Box::new(acc), Box::new(SummaryExpr::Fun(f)));
+                if plus { SummaryExpr::Add(a, b) } else { SummaryExpr::Sub(a, b) }
+            })
+        })
+    }
+
+    fn contour() -> impl Strategy<Value = Layer> {
+        (
+            (1usize..100, any::<bool>()),
+            proptest::option::of((1u32..1000).prop_map(|h| f64::from(h) / 100.0)),
+            proptest::option::of((1u32..1000).prop_map(|h| f64::from(h) / 100.0)),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_string()),
+        )
+            .prop_map(|((bins, filled), bandwidth_x, bandwidth_y, color, width, alpha, linetype)| {
+                Layer::Line(LineLayer { stat: Stat::Contour { bins, filled, bandwidth_x, bandwidth_y }, color, width, alpha, linetype, ..Default::default() })
+            })
+    }
+
+    fn rug() -> impl Strategy<Value = Layer> {
+        (
+            proptest::option::of(column()),
+            (1u32..=100).prop_map(|l| f64::from(l) / 100.0),
+            any::<bool>(),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_string()),
+        )
+            .prop_map(|(x, length, jitter, color, width, alpha, linetype)| {
+                Layer::Line(LineLayer { stat: Stat::Rug { length, jitter }, x, color, width, alpha, linetype, ..Default::default() })
+            })
+    }
+
+    fn segment() -> impl Strategy<Value = Layer> {
+        let arrow = (proptest::sample::select(ArrowType::ALL.to_vec()), proptest::sample::select(ArrowEnds::ALL.to_vec()))
+            .prop_map(|(head, ends)| Arrow { head, ends });
+        (
+            (proptest::option::of(column()), proptest::option::of(column()), proptest::option::of(column()), proptest::option::of(column())),
+            proptest::option::of(arrow),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_string()),
+        )
+            .prop_map(|((x, y, xend, yend), arrow, color, width, alpha, linetype)| {
+                Layer::Line(LineLayer { stat: Stat::Segment { xend, yend, arrow }, x, y, color, width, alpha, linetype, ..Default::default() })
+            })
+    }
+
+    fn abline() -> impl Strategy<Value = Layer> {
+        (
+            aes_number(),
+            aes_number(),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_string()),
+            proptest::option::of(literal()),
+        )
+            .prop_map(|(slope, intercept, color, width, alpha, linetype, legend)| {
+                Layer::Line(LineLayer { stat: Stat::Abline { slope, intercept }, color, width, alpha, linetype, legend, ..Default::default() })
+            })
+    }
+
+    fn crosshair() -> impl Strategy<Value = Layer> {
+        (
+            proptest::option::of(column()),
+            proptest::option::of(column()),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_string()),
+            proptest::option::of(literal()),
+        )
+            .prop_map(|(x, y, color, width, alpha, linetype, legend)| {
+                Layer::Line(LineLayer { stat: Stat::Crosshair, x, y, color, width, alpha, linetype, step: None, legend })
+            })
+    }
+
+    fn stat_summary() -> impl Strategy<Value = Layer> {
+        (summary_expr(), summary_expr(), summary_expr()).prop_map(|(fun, fun_ymin, fun_ymax)| {
+            Layer::Point(PointLayer { stat: Stat::Summary { fun, fun_ymin, fun_ymax }, ..Default::default() })
+        })
+    }
+
+    fn point() -> impl Strategy<Value = Layer> {
+        (
+            proptest::option::of(column()),
+            proptest::option::of(column()),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(literal()),
+        )
+            .prop_map(|(x, y, color, size, shape, alpha, fill)| {
+                Layer::Point(PointLayer { x, y, color, size, shape, alpha, fill, ..Default::default() })
+            })
+    }
+
+    fn pointrange() -> impl Strategy<Value = Layer> {
+        (
+            point(),
+            proptest::option::of(column()),
+            proptest::option::of(column()),
+            proptest::option::of((column(), column())),
+            (any::<bool>(), proptest::option::of((1u32..20).prop_map(|w| f64::from(w) / 10.0)), any::<bool>()),
+        )
+            .prop_map(|(layer, ymin, ymax, horizontal, (crossbar, width, dodge))| {
+                let Layer::Point(point) = layer else { unreachable!() };
+                let (xmin, xmax) = horizontal.unzip();
+                let position = if dodge { LayerPosition::Dodge } else { LayerPosition::Identity };
+                Layer::Point(PointLayer { range: Some(PointRange { ymin, ymax, xmin, xmax }), crossbar, width, position, ..point })
+            })
+    }
+
+    fn beeswarm() -> impl Strategy<Value = Layer> {
+        (point(), proptest::sample::select(BeeswarmPriority::ALL.to_vec()), 1u32..50).prop_map(|(layer, priority, cex)| {
+            let Layer::Point(point) = layer else { unreachable!() };
+            Layer::Point(PointLayer { stat: Stat::Beeswarm { priority, cex: f64::from(cex) / 10.0 }, ..point })
+        })
+    }
+
+    /// bar(), col() and histogram() layers
+    fn bar() -> impl Strategy<Value = Layer> {
+        let position = prop_oneof![
+            Just(LayerPosition::Identity),
+            Just(LayerPosition::Dodge),
+            Just(LayerPosition::Stack),
+            Just(LayerPosition::Fill),
+            Just(LayerPosition::Percent),
+        ];
+        // (stat, require_y, stat_output_as): col() is the identity stat with require_y set
+        let kind = prop_oneof![
+            Just((Stat::Identity, false, None)),
+            Just((Stat::Identity, true, None)),
+            Just((Stat::Count, false, None)),
+            Just((Stat::Prop, false, None)),
+            Just((Stat::Sum, false, None)),
+            Just((Stat::Mean, false, None)),
+            Just((Stat::Max, false, None)),
+            Just((Stat::Min, false, None)),
+            (1usize..100, proptest::option::of(Just(StatOutput::Binned)))
+                .prop_map(|(bins, stat_output_as)| (Stat::Bin { bins }, false, stat_output_as)),
+        ];
+        (
+            proptest::option::of(column()),
+            proptest::option::of(column()),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_number()),
+            position,
+            kind,
+            (proptest::option::of(literal()), proptest::option::of((0u32..20).prop_map(f64::from)), any::<bool>()),
+        )
+            .prop_map(|(x, y, color, width, alpha, position, (stat, require_y, stat_output_as), (border, border_width, hollow))| {
+                Layer::Bar(BarLayer { stat, x, y, color, alpha, width, border, border_width, hollow, position, require_y, stat_output_as })
+            })
+    }
+
+    fn ribbon() -> impl Strategy<Value = Layer> {
+        (
+            proptest::option::of(column()),
+            proptest::option::of(column()),
+            proptest::option::of(column()),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_number()),
+            (proptest::option::of(literal()), proptest::option::of(literal())),
+        )
+            .prop_map(|(x, ymin, ymax, color, alpha, (border, linetype))| {
+                Layer::Ribbon(RibbonLayer { x, ymin, ymax, color, alpha, border, linetype, ..Default::default() })
+            })
+    }
+
+    fn boxplot() -> impl Strategy<Value = Layer> {
+        (
+            (proptest::option::of(column()), proptest::option::of(column())),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_number()),
+            (any::<bool>(), any::<bool>(), proptest::option::of(number()), proptest::option::of(number())),
+            (proptest::option::of(literal()), proptest::option::of(number()), proptest::option::of(literal())),
+        )
+            .prop_map(|((x, y), color, width, alpha, (varwidth, notch, notchwidth, staplewidth), (outlier_color, outlier_size, outlier_shape))| {
+                Layer::Boxplot(BoxplotLayer {
+                    stat: Stat::Boxplot,
+                    x, y, color, width, alpha, varwidth, notch, notchwidth, staplewidth,
+                    outlier_color, outlier_size, outlier_shape,
+                    ..Default::default()
+                })
+            })
+    }
+
+    fn violin() -> impl Strategy<Value = Layer> {
+        let bw = prop_oneof![
+            Just(BandwidthMethod::Nrd0),
+            Just(BandwidthMethod::Nrd),
+            Just(BandwidthMethod::Ucv),
+            (1u32..1000).prop_map(|b| BandwidthMethod::Fixed(f64::from(b) / 10.0)),
+        ];
+        let scale = prop_oneof![Just(ViolinScale::Width), Just(ViolinScale::Count)];
+        let side = prop_oneof![Just(ViolinSide::Both), Just(ViolinSide::Left), Just(ViolinSide::Right)];
+        (
+            (proptest::option::of(column()), proptest::option::of(column())),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_number()),
+            proptest::option::of(aes_number()),
+            proptest::collection::vec(0.0..1.0f64, 0..4),
+            bw,
+            scale,
+            (any::<bool>(), side),
+        )
+            .prop_map(|((x, y), color, width, alpha, draw_quantiles, bw, scale, (trim, side))| {
+                Layer::Violin(ViolinLayer {
+                    stat: Stat::Violin { draw_quantiles: draw_quantiles.clone(), bw, scale: scale.clone(), trim },
+                    x, y, color, alpha, width, draw_quantiles, scale, trim, side,
+                })
+            })
+    }
+
+    fn tile() -> impl Strategy<Value = Layer> {
+        (
+            (proptest::option::of(column()), proptest::option::of(column())),
+            proptest::option::of(aes_string()),
+            proptest::option::of(literal()),
+            proptest::option::of(number()),
+            (proptest::option::of(number()), proptest::option::of(number())),
+            (proptest::option::of(prop_oneof![Just("grey".to_string()), Just("#ff8800".to_string())]), any::<bool>()),
+        )
+            .prop_map(|((x, y), fill, color, alpha, (width, height), (na_color, drop_na))| {
+                Layer::Tile(TileLayer { x, y, fill, color, alpha, width, height, na_color, drop_na, ..Default::default() })
+            })
+    }
+
+    fn hexbin() -> impl Strategy<Value = Layer> {
+        (
+            (proptest::option::of(column()), proptest::option::of(column())),
+            1usize..200,
+            proptest::option::of(literal()),
+            proptest::option::of(number()),
+            proptest::sample::select(HexSummary::ALL.to_vec()),
+            any::<bool>(),
+        )
+            .prop_map(|((x, y), bins, color, alpha, fill, size_by_count)| {
+                Layer::Hexbin(HexbinLayer { stat: Stat::Hexbin { bins }, x, y, color, alpha, fill, size_by_count })
+            })
+    }
+
+    fn text() -> impl Strategy<Value = Layer> {
+        (
+            (proptest::option::of(column()), proptest::option::of(column())),
+            proptest::option::of(aes_string()),
+            proptest::option::of(aes_string()),
+            (proptest::option::of(number()), proptest::option::of(number())),
+            (any::<bool>(), number(), number()),
+            proptest::option::of((literal(), 0i32..20, 0i32..5)),
+        )
+            .prop_map(|((x, y), label, color, (size, alpha), (check_overlap, nudge_x, nudge_y), label_box)| {
+                let label_box = label_box.map(|(fill, padding, border)| LabelBox { fill, padding: padding.into(), border: border.into() });
+                Layer::Text(TextLayer { x, y, label, color, size, alpha, check_overlap, nudge_x, nudge_y, label_box, ..Default::default() })
+            })
+    }
+
+    fn layer() -> impl Strategy<Value = Layer> {
+        prop_oneof![line(), point(), pointrange(), beeswarm(), bar(), ribbon(), boxplot(), violin(), smooth(), quantile(), abline(), crosshair(), rug(), segment(), tile(), hexbin(), text()]
+    }
+
+    proptest! {
+        #[test]
+        fn roundtrip_line(layer in line()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_line(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_step(layer in step()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_step(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_smooth(layer in smooth()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_smooth(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_quantile(layer in quantile()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_quantile(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_ecdf(layer in ecdf()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_ecdf(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_density(layer in density()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_density(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_contour(layer in contour()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_contour(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_abline(layer in abline()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_abline(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_crosshair(layer in crosshair()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_geom(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_stat_summary(layer in stat_summary()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_stat_summary(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_rug(layer in rug()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_rug(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_segment(layer in segment()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_segment(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_beeswarm(layer in beeswarm()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_beeswarm(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_point(layer in point()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_point(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_pointrange(layer in pointrange()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_geom(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_bar(layer in bar()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_geom(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_ribbon(layer in ribbon()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_ribbon(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_boxplot(layer in boxplot()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_boxplot(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_violin(layer in violin()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_violin(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_tile(layer in tile()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_tile(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_hexbin(layer in hexbin()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_hexbin(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_text(layer in text()) {
+            let dsl = layer.to_string();
+            prop_assert_eq!(parse_geom(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+
+        #[test]
+        fn roundtrip_any_layer_with_whitespace(layer in layer(), pad in "[ \t\n]{0,3}") {
+            let dsl = layer.to_string()
+                .replacen('(', &format!("{pad}({pad}"), 1)
+                .replace(", ", &format!("{pad},{pad}"));
+            let dsl = format!("{dsl}{pad}");
+            prop_assert_eq!(parse_geom(&dsl), Ok(("", layer)), "{}", dsl);
+        }
+    }
+}
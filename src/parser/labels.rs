@@ -10,41 +10,64 @@ use nom::{
 use crate::parser::ast::Labels;
 use crate::parser::lexer::{string_literal, ws};
 
+/// Parse `labels(...)`
+pub fn parse_labels(input: &str) -> IResult<&str, Labels> {
+    parse_labels_with_keyword("labels")(input)
+}
+
+/// Parse `labs(...)` (ggplot2-style alias for `labels(...)`)
 pub fn parse_labs(input: &str) -> IResult<&str, Labels> {
-    let (input, _) = ws(tag("labs"))(input)?;
-    let (input, _) = ws(char('('))(input)?;
-
-    let (input, args) = separated_list0(
-        ws(char(',')),
-        alt((
-            // title: always string literal
-            map(preceded(ws(tag("title:")), ws(string_literal)), |v| ("title", v)),
-            // subtitle: always string literal
-            map(preceded(ws(tag("subtitle:")), ws(string_literal)), |v| ("subtitle", v)),
-            // x: always string literal
-            map(preceded(ws(tag("x:")), ws(string_literal)), |v| ("x", v)),
-            // y: always string literal
-            map(preceded(ws(tag("y:")), ws(string_literal)), |v| ("y", v)),
-            // caption: always string literal
-            map(preceded(ws(tag("caption:")), ws(string_literal)), |v| ("caption", v)),
-        ))
-    )(input)?;
-
-    let (input, _) = ws(char(')'))(input)?;
-
-    let mut labels = Labels::default();
-    for (key, val) in args {
-        match key {
-            "title" => labels.title = Some(val),
-            "subtitle" => labels.subtitle = Some(val),
-            "x" => labels.x = Some(val),
-            "y" => labels.y = Some(val),
-            "caption" => labels.caption = Some(val),
-            _ => {}
+    parse_labels_with_keyword("labs")(input)
+}
+
+fn parse_labels_with_keyword(keyword: &'static str) -> impl Fn(&str) -> IResult<&str, Labels> {
+    move |input: &str| {
+        let (input, _) = ws(tag(keyword))(input)?;
+        let (input, _) = ws(char('('))(input)?;
+
+        let (input, args) = separated_list0(
+            ws(char(',')),
+            alt((
+                // title: always string literal
+                map(preceded(ws(tag("title:")), ws(string_literal)), |v| ("title", v)),
+                // subtitle: always string literal
+                map(preceded(ws(tag("subtitle:")), ws(string_literal)), |v| ("subtitle", v)),
+                // x: always string literal
+                map(preceded(ws(tag("x:")), ws(string_literal)), |v| ("x", v)),
+                // y: always string literal
+                map(preceded(ws(tag("y:")), ws(string_literal)), |v| ("y", v)),
+                // caption: always string literal
+                map(preceded(ws(tag("caption:")), ws(string_literal)), |v| ("caption", v)),
+                // Legend titles for mapped aesthetics
+                map(preceded(ws(tag("color:")), ws(string_literal)), |v| ("color", v)),
+                map(preceded(ws(tag("fill:")), ws(string_literal)), |v| ("fill", v)),
+                map(preceded(ws(tag("size:")), ws(string_literal)), |v| ("size", v)),
+                map(preceded(ws(tag("shape:")), ws(string_literal)), |v| ("shape", v)),
+                map(preceded(ws(tag("linetype:")), ws(string_literal)), |v| ("linetype", v)),
+            ))
+        )(input)?;
+
+        let (input, _) = ws(char(')'))(input)?;
+
+        let mut labels = Labels::default();
+        for (key, val) in args {
+            match key {
+                "title" => labels.title = Some(val),
+                "subtitle" => labels.subtitle = Some(val),
+                "x" => labels.x = Some(val),
+                "y" => labels.y = Some(val),
+                "caption" => labels.caption = Some(val),
+                "color" => labels.color_title = Some(val),
+                "fill" => labels.fill_title = Some(val),
+                "size" => labels.size_title = Some(val),
+                "shape" => labels.shape_title = Some(val),
+                "linetype" => labels.linetype_title = Some(val),
+                _ => {}
+            }
         }
-    }
 
-    Ok((input, labels))
+        Ok((input, labels))
+    }
 }
 
 #[cfg(test)]
@@ -59,4 +82,26 @@ mod tests {
         assert_eq!(labels.title, Some("My Chart".to_string()));
         assert_eq!(labels.x, Some("X Axis".to_string()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_labels_keyword() {
+        let (_, labels) = parse_labels(r#"labels(title: "My Chart", y: "Y Axis")"#).unwrap();
+        assert_eq!(labels.title, Some("My Chart".to_string()));
+        assert_eq!(labels.y, Some("Y Axis".to_string()));
+    }
+
+    #[test]
+    fn test_parse_labs_legend_titles() {
+        let (_, labels) = parse_labs(r#"labs(color: "Region", fill: "Group", size: "Weight", shape: "Kind", linetype: "Series")"#).unwrap();
+        assert_eq!(labels.color_title, Some("Region".to_string()));
+        assert_eq!(labels.fill_title, Some("Group".to_string()));
+        assert_eq!(labels.size_title, Some("Weight".to_string()));
+        assert_eq!(labels.shape_title, Some("Kind".to_string()));
+        assert_eq!(labels.linetype_title, Some("Series".to_string()));
+    }
+
+    #[test]
+    fn test_parse_labs_rejects_labels_keyword() {
+        assert!(parse_labs(r#"labels(title: "T")"#).is_err());
+    }
+}
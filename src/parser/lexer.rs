@@ -38,6 +38,10 @@ pub fn string_literal(input: &str) -> IResult<&str, String> {
 }
 
 pub fn number_literal(input: &str) -> IResult<&str, f64> {
+    // `double` also accepts "inf"/"nan", which would swallow identifiers like `nan_count`
+    if input.starts_with(|c: char| c.is_alphabetic()) {
+        return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Float)));
+    }
     double(input)
 }
 
@@ -97,6 +101,13 @@ mod tests {
         assert!(string_literal(r#""hello"#).is_err());
     }
 
+    #[test]
+    fn test_number_literal_rejects_words() {
+        assert!(number_literal("inf").is_err());
+        assert!(number_literal("nan_count").is_err());
+        assert!(number_literal("Infinity").is_err());
+    }
+
     #[test]
     fn test_number_literal_negative() {
         assert_eq!(number_literal("-42"), Ok(("", -42.0)));
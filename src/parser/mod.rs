@@ -16,6 +16,8 @@ pub mod lexer;
 
 pub mod pipeline;
 
+pub mod reshape;
+
 pub mod scale;
 
 pub mod theme;
@@ -27,4 +29,4 @@ pub mod theme;
 // Public API re-exports
 pub use ast::{Aesthetics, Facet, FacetScales, Layer, LineLayer, PlotSpec, PointLayer};
 pub use facet::parse_facet_wrap;
-pub use pipeline::parse_plot_spec;
+pub use pipeline::{parse_plot_spec, ParseError, ParseWarning};
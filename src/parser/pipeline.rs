@@ -1,14 +1,17 @@
 // Pipeline parser for Grammar of Graphics DSL
 
 use super::aesthetics::parse_aesthetics;
-use super::ast::{Aesthetics, AxisScale, CoordSystem, Facet, Labels, Layer, PlotSpec, Theme, ThemeElement};
+use super::ast::{Aesthetics, AxisScale, ColorScale, CoordSystem, Facet, FillScale, IdentityScales, Labels, Layer, PipelineStep, PlotSpec, Theme};
 use super::coord::parse_coord_flip;
 use super::facet::parse_facet_wrap;
 use super::geom::parse_geom;
-use super::labels::parse_labs;
-use super::scale::parse_scale_command;
+use super::labels::{parse_labels, parse_labs};
+use super::reshape::parse_pipeline_step;
+use super::scale::{parse_scale_color_brewer, parse_scale_color_identity, parse_scale_command, parse_scale_fill_continuous, parse_scale_identity, parse_scale_linetype_manual};
 use super::theme::parse_theme_command;
 use super::lexer::ws;
+use crate::theme_resolve::merge_themes;
+use std::fmt;
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -18,26 +21,6 @@ use nom::{
     IResult,
 };
 
-/// Merge two themes together (ggplot2-style).
-/// Fields from `overlay` override `base` unless they are `Inherit`.
-fn merge_themes(base: Theme, overlay: Theme) -> Theme {
-    Theme {
-        line: if overlay.line != ThemeElement::Inherit { overlay.line } else { base.line },
-        rect: if overlay.rect != ThemeElement::Inherit { overlay.rect } else { base.rect },
-        text: if overlay.text != ThemeElement::Inherit { overlay.text } else { base.text },
-        plot_background: if overlay.plot_background != ThemeElement::Inherit { overlay.plot_background } else { base.plot_background },
-        plot_title: if overlay.plot_title != ThemeElement::Inherit { overlay.plot_title } else { base.plot_title },
-        panel_background: if overlay.panel_background != ThemeElement::Inherit { overlay.panel_background } else { base.panel_background },
-        panel_grid_major: if overlay.panel_grid_major != ThemeElement::Inherit { overlay.panel_grid_major } else { base.panel_grid_major },
-        panel_grid_minor: if overlay.panel_grid_minor != ThemeElement::Inherit { overlay.panel_grid_minor } else { base.panel_grid_minor },
-        axis_text: if overlay.axis_text != ThemeElement::Inherit { overlay.axis_text } else { base.axis_text },
-        axis_line: if overlay.axis_line != ThemeElement::Inherit { overlay.axis_line } else { base.axis_line },
-        axis_ticks: if overlay.axis_ticks != ThemeElement::Inherit { overlay.axis_ticks } else { base.axis_ticks },
-        // legend_position always takes overlay (no Inherit concept for this field)
-        legend_position: overlay.legend_position,
-    }
-}
-
 #[derive(Debug)]
 enum PipelineComponent {
     Aes(Aesthetics),
@@ -45,8 +28,13 @@ enum PipelineComponent {
     Facet(Facet),
     Coord(CoordSystem),
     Labels(Labels),
-    Theme(Theme),
+    Theme(Box<Theme>),
     Scale(bool, AxisScale), // is_x, scale
+    Step(PipelineStep),
+    ColorScale(ColorScale),
+    LinetypeValues(Vec<String>),
+    FillScale(FillScale),
+    IdentityScale(IdentityScales),
 }
 
 fn parse_pipeline_component(input: &str) -> IResult<&str, PipelineComponent> {
@@ -55,15 +43,194 @@ fn parse_pipeline_component(input: &str) -> IResult<&str, PipelineComponent> {
         map(parse_geom, PipelineComponent::Layer),
         map(parse_facet_wrap, PipelineComponent::Facet),
         map(parse_coord_flip, PipelineComponent::Coord),
-        map(parse_labs, PipelineComponent::Labels),
-        map(parse_theme_command, PipelineComponent::Theme),
+        map(alt((parse_labels, parse_labs)), PipelineComponent::Labels),
+        map(parse_theme_command, |t| PipelineComponent::Theme(Box::new(t))),
         map(parse_scale_command, |(is_x, s)| PipelineComponent::Scale(is_x, s)),
+        map(parse_pipeline_step, PipelineComponent::Step),
+        map(alt((parse_scale_color_brewer, parse_scale_color_identity)), PipelineComponent::ColorScale),
+        map(parse_scale_identity, PipelineComponent::IdentityScale),
+        map(parse_scale_linetype_manual, PipelineComponent::LinetypeValues),
+        map(parse_scale_fill_continuous, PipelineComponent::FillScale),
     ))(input)
 }
 
+/// Parse error with its byte offset into the DSL string
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at position {}: {}", self.position, self.message)
+    }
+}
+
+/// Non-fatal issue in a DSL string that parsed successfully
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseWarning {
+    pub message: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
 /// Parse a complete plot specification
 /// Format: component | component | ...
-pub fn parse_plot_spec(input: &str) -> IResult<&str, PlotSpec> {
+///
+/// On failure every malformed component is reported, not just the first one.
+pub fn parse_plot_spec(input: &str) -> Result<(PlotSpec, Vec<ParseWarning>), Vec<ParseError>> {
+    match plot_spec(input) {
+        Ok((_, (spec, warnings))) => Ok((spec, warnings)),
+        Err(_) => Err(collect_errors(input)),
+    }
+}
+
+/// Recovery pass: split at top-level pipes and parse each component on its own
+fn collect_errors(input: &str) -> Vec<ParseError> {
+    let segments = split_components(input);
+    let mut errors = Vec::new();
+    let mut has_layer = false;
+
+    let mut i = 0;
+    while i < segments.len() {
+        let (start, segment) = segments[i];
+        if i == 0 && segment.trim() == "df" {
+            i += 1;
+            continue;
+        }
+        if segment.trim().is_empty() {
+            // A leading pipe is allowed; anything else is a dangling "|"
+            if !(i == 0 && segments.len() > 1) {
+                errors.push(ParseError { position: start, message: "empty pipeline component".to_string() });
+            }
+            i += 1;
+            continue;
+        }
+
+        match parse_component_exact(segment) {
+            Ok(component) => {
+                has_layer |= matches!(component, PipelineComponent::Layer(_));
+                i += 1;
+            }
+            // Steps such as group_by(...) | summarize(...) span two segments
+            Err(_) if i + 1 < segments.len()
+                && parse_component_exact(&input[start..segments[i + 1].0 + segments[i + 1].1.len()]).is_ok() =>
+            {
+                i += 2;
+            }
+            Err(offset) => {
+                let trimmed = segment.trim();
+                let name = trimmed.split('(').next().unwrap_or(trimmed).trim();
+                let name = if name.is_empty() { trimmed } else { name };
+                errors.push(match bad_argument(segment) {
+                    Some((arg_offset, arg)) => ParseError {
+                        position: start + arg_offset,
+                        message: format!("could not parse argument `{}` of `{}`", arg, name),
+                    },
+                    None => ParseError { position: start + offset, message: format!("could not parse `{}`", name) },
+                });
+                i += 1;
+            }
+        }
+    }
+
+    if errors.is_empty() && !has_layer {
+        errors.push(ParseError { position: input.len(), message: "plot needs at least one geometry layer".to_string() });
+    }
+    errors
+}
+
+/// Parse one whole component, returning the byte offset of the failure on error
+fn parse_component_exact(segment: &str) -> Result<PipelineComponent, usize> {
+    let offset_of = |rest: &str| {
+        let skipped = segment.len() - rest.len();
+        // Point at the first non-space character of the failing text
+        skipped + (rest.len() - rest.trim_start().len())
+    };
+    match parse_pipeline_component(segment) {
+        Ok((rest, component)) if rest.trim().is_empty() => Ok(component),
+        Ok((rest, _)) => Err(offset_of(rest)),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(offset_of(e.input)),
+        Err(nom::Err::Incomplete(_)) => Err(0),
+    }
+}
+
+/// Offset and name of the first argument whose removal lets a `name(arg, ...)` component parse
+/// (an unknown argument name or an invalid value), so errors point at it instead of the component
+fn bad_argument(segment: &str) -> Option<(usize, &str)> {
+    let open = segment.find('(')?;
+    let close = segment.rfind(')').filter(|&close| close > open)?;
+    let args = top_level_args(&segment[open + 1..close], open + 1);
+    for (k, &(arg_start, arg)) in args.iter().enumerate() {
+        // Drop the argument with the comma before it (or after it, for the first one)
+        let (from, to) = match (k, args.get(k + 1)) {
+            (0, Some(&(next, _))) => (arg_start, next),
+            (0, None) => (arg_start, arg_start + arg.len()),
+            _ => (args[k - 1].0 + args[k - 1].1.len(), arg_start + arg.len()),
+        };
+        let candidate = format!("{}{}", &segment[..from], &segment[to..]);
+        if parse_component_exact(&candidate).is_ok() {
+            let leading = arg.len() - arg.trim_start().len();
+            let arg = arg.trim();
+            return Some((arg_start + leading, arg.split(':').next().unwrap_or(arg).trim()));
+        }
+    }
+    None
+}
+
+/// Split an argument list at `,` outside parentheses, brackets and string literals, keeping byte offsets
+fn top_level_args(input: &str, offset: usize) -> Vec<(usize, &str)> {
+    let mut args = Vec::new();
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' | '[' if !in_string => depth += 1,
+            ')' | ']' if !in_string => depth -= 1,
+            ',' if !in_string && depth <= 0 => {
+                args.push((offset + start, &input[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !input[start..].trim().is_empty() {
+        args.push((offset + start, &input[start..]));
+    }
+    args
+}
+
+/// Split at `|` outside parentheses, brackets and string literals, keeping byte offsets
+fn split_components(input: &str) -> Vec<(usize, &str)> {
+    let mut segments = Vec::new();
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' | '[' if !in_string => depth += 1,
+            ')' | ']' if !in_string => depth -= 1,
+            '|' if !in_string && depth <= 0 => {
+                segments.push((start, &input[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    segments.push((start, &input[start..]));
+    segments
+}
+
+/// Single-pass nom parser for the whole pipeline
+fn plot_spec(input: &str) -> IResult<&str, (PlotSpec, Vec<ParseWarning>)> {
     // Optional: consume leading "df"
     let (input, _) = opt(ws(tag("df")))(input)?;
 
@@ -88,6 +255,34 @@ pub fn parse_plot_spec(input: &str) -> IResult<&str, PlotSpec> {
     let mut theme = None;
     let mut x_scale = None;
     let mut y_scale = None;
+    let mut steps = Vec::new();
+    let mut color_scale = None;
+    let mut linetype_values = None;
+    let mut fill_scale = None;
+    let mut identity_scales = IdentityScales::default();
+    let mut warnings = Vec::new();
+
+    // Components that can only appear once: later ones silently replace earlier ones
+    let mut seen = Vec::new();
+    for comp in &components {
+        let name = match comp {
+            PipelineComponent::Aes(_) => "aes()",
+            PipelineComponent::Facet(_) => "facet_wrap()",
+            PipelineComponent::Coord(_) => "coord_flip()",
+            PipelineComponent::Labels(_) => "labs()",
+            PipelineComponent::Scale(true, _) => "x scale",
+            PipelineComponent::Scale(false, _) => "y scale",
+            PipelineComponent::ColorScale(_) => "color scale",
+            PipelineComponent::LinetypeValues(_) => "scale_linetype_manual()",
+            PipelineComponent::FillScale(_) => "scale_fill_continuous()",
+            _ => continue,
+        };
+        if seen.contains(&name) {
+            warnings.push(ParseWarning { message: format!("{} given more than once; only the last one is used", name) });
+        } else {
+            seen.push(name);
+        }
+    }
 
     for comp in components {
         match comp {
@@ -103,13 +298,21 @@ pub fn parse_plot_spec(input: &str) -> IResult<&str, PlotSpec> {
             PipelineComponent::Theme(t) => {
                 // Merge themes (ggplot2-style: later values override earlier)
                 theme = Some(match theme {
-                    Some(base) => merge_themes(base, t),
-                    None => t,
+                    Some(base) => merge_themes(base, *t),
+                    None => *t,
                 });
             }
             PipelineComponent::Scale(is_x, s) => {
                 if is_x { x_scale = Some(s); } else { y_scale = Some(s); }
             }
+            PipelineComponent::Step(s) => steps.push(s),
+            PipelineComponent::ColorScale(c) => color_scale = Some(c),
+            PipelineComponent::LinetypeValues(v) => linetype_values = Some(v),
+            PipelineComponent::FillScale(f) => fill_scale = Some(f),
+            PipelineComponent::IdentityScale(i) => {
+                identity_scales.shape |= i.shape;
+                identity_scales.linetype |= i.linetype;
+            }
         }
     }
 
@@ -120,7 +323,7 @@ pub fn parse_plot_spec(input: &str) -> IResult<&str, PlotSpec> {
 
     Ok((
         input,
-        PlotSpec {
+        (PlotSpec {
             aesthetics,
             layers,
             labels,
@@ -129,7 +332,12 @@ pub fn parse_plot_spec(input: &str) -> IResult<&str, PlotSpec> {
             theme,
             x_scale,
             y_scale,
-        },
+            steps,
+            color_scale,
+            linetype_values,
+            fill_scale,
+            identity_scales,
+        }, warnings),
     ))
 }
 
@@ -141,7 +349,7 @@ mod tests {
     fn test_parse_aes_and_line() {
         let result = parse_plot_spec("aes(x: time, y: temp) | line()");
         assert!(result.is_ok());
-        let (_, spec) = result.unwrap();
+        let (spec, _) = result.unwrap();
         assert!(spec.aesthetics.is_some());
         assert_eq!(spec.layers.len(), 1);
     }
@@ -150,17 +358,26 @@ mod tests {
     fn test_parse_multiple_layers() {
         let result = parse_plot_spec(r#"aes(x: one, y: two) | line(color: "red") | point(size: 5)"#);
         assert!(result.is_ok());
-        let (_, spec) = result.unwrap();
+        let (spec, _) = result.unwrap();
         assert!(spec.aesthetics.is_some());
         assert_eq!(spec.layers.len(), 2);
     }
 
+    #[test]
+    fn test_parse_pivot_wider_step() {
+        let (spec, _) = parse_plot_spec(
+            "pivot_wider(id_cols: [date], names_from: variable, values_from: value) | aes(x: date, y: A) | line()"
+        ).unwrap();
+        assert_eq!(spec.steps.len(), 1);
+        assert_eq!(spec.layers.len(), 1);
+    }
+
     #[test]
     fn test_parse_no_aesthetics() {
         // Allow geoms without explicit aes for backward compat / convenience
         let result = parse_plot_spec("line()");
         assert!(result.is_ok());
-        let (_, spec) = result.unwrap();
+        let (spec, _) = result.unwrap();
         assert!(spec.aesthetics.is_none());
         assert_eq!(spec.layers.len(), 1);
     }
@@ -169,7 +386,7 @@ mod tests {
     fn test_parse_with_df_prefix() {
         let result = parse_plot_spec("df | aes(x: a, y: b) | line()");
         assert!(result.is_ok());
-        let (_, spec) = result.unwrap();
+        let (spec, _) = result.unwrap();
         assert!(spec.aesthetics.is_some());
         assert_eq!(spec.layers.len(), 1);
     }
@@ -197,7 +414,7 @@ mod tests {
         // Three layers: line + point + bar
         let result = parse_plot_spec(r#"aes(x: a, y: b) | line() | point() | bar()"#);
         assert!(result.is_ok());
-        let (_, spec) = result.unwrap();
+        let (spec, _) = result.unwrap();
         assert_eq!(spec.layers.len(), 3);
     }
 
@@ -206,7 +423,7 @@ mod tests {
         // df prefix without aesthetics should succeed
         let result = parse_plot_spec("df | line()");
         assert!(result.is_ok());
-        let (_, spec) = result.unwrap();
+        let (spec, _) = result.unwrap();
         assert!(spec.aesthetics.is_none());
         assert_eq!(spec.layers.len(), 1);
     }
@@ -215,7 +432,7 @@ mod tests {
     fn test_parse_plot_spec_with_facet_wrap() {
         let result = parse_plot_spec("aes(x: time, y: sales) | line() | facet_wrap(by: region)");
         assert!(result.is_ok());
-        let (_, spec) = result.unwrap();
+        let (spec, _) = result.unwrap();
         assert!(spec.facet.is_some());
         let facet = spec.facet.unwrap();
         assert_eq!(facet.by, "region");
@@ -225,7 +442,7 @@ mod tests {
     fn test_parse_plot_spec_with_facet_wrap_full() {
         let result = parse_plot_spec(r#"aes(x: time, y: sales) | line() | facet_wrap(by: region, ncol: 2, scales: "free_x")"#);
         assert!(result.is_ok());
-        let (_, spec) = result.unwrap();
+        let (spec, _) = result.unwrap();
         assert!(spec.facet.is_some());
         let facet = spec.facet.unwrap();
         assert_eq!(facet.by, "region");
@@ -236,7 +453,7 @@ mod tests {
     fn test_parse_plot_spec_without_facet() {
         let result = parse_plot_spec("aes(x: time, y: sales) | line()");
         assert!(result.is_ok());
-        let (_, spec) = result.unwrap();
+        let (spec, _) = result.unwrap();
         assert!(spec.facet.is_none());
     }
 
@@ -244,10 +461,68 @@ mod tests {
     fn test_parse_plot_spec_with_labs_and_theme() {
         let result = parse_plot_spec(r#"aes(x: x, y: y) | line() | labs(title: "My Plot", x: "Time") | theme(legend_position: "none")"#);
         assert!(result.is_ok());
-        let (_, spec) = result.unwrap();
+        let (spec, _) = result.unwrap();
         assert_eq!(spec.labels.as_ref().unwrap().title, Some("My Plot".to_string()));
         assert_eq!(spec.labels.as_ref().unwrap().x, Some("Time".to_string()));
-        assert_eq!(spec.theme.as_ref().unwrap().legend_position, crate::parser::ast::LegendPosition::None);
+        assert_eq!(spec.theme.as_ref().unwrap().legend_position, Some(crate::parser::ast::LegendPosition::None));
+    }
+
+    #[test]
+    fn test_parse_plot_spec_later_theme_keeps_legend_position() {
+        let (spec, _) = parse_plot_spec(r#"aes(x: x, y: y) | line() | theme(legend_position: "none") | theme(axis_text: element_text(size: 14))"#).unwrap();
+        let theme = spec.theme.unwrap();
+        assert_eq!(theme.legend_position, Some(crate::parser::ast::LegendPosition::None));
+        assert!(matches!(theme.axis_text, crate::parser::ast::ThemeElement::Text(_)));
+    }
+
+    #[test]
+    fn test_parse_plot_spec_collects_all_errors() {
+        let input = r#"aes(x: a, y: b) | line(colr: "red") | point() | bogus()"#;
+        let errors = parse_plot_spec(input).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].position, input.find("colr").unwrap());
+        assert_eq!(errors[0].message, "could not parse argument `colr` of `line`");
+        assert_eq!(errors[1].position, input.find("bogus").unwrap());
+    }
+
+    #[test]
+    fn test_parse_plot_spec_error_points_at_bad_argument() {
+        let input = r#"aes(x: a, y: b) | point(size: 3, shape: 7, alpha: 0.5)"#;
+        let errors = parse_plot_spec(input).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position, input.find("shape").unwrap());
+        assert!(errors[0].message.contains("`shape` of `point`"));
+
+        // Without a single culprit argument, the error stays at the component
+        let input = "aes(x: a, y: b) | line(";
+        let errors = parse_plot_spec(input).unwrap_err();
+        assert_eq!(errors[0].position, input.find("line").unwrap());
+    }
+
+    #[test]
+    fn test_parse_plot_spec_error_kinds() {
+        let errors = parse_plot_spec("aes(x: a, y: b) | line() |").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("empty"));
+
+        let errors = parse_plot_spec("aes(x: a, y: b)").unwrap_err();
+        assert!(errors[0].message.contains("geometry"));
+
+        // Multi-segment steps are not reported as errors during recovery
+        let errors = parse_plot_spec("group_by(a) | summarize(n = count()) | aes(x: a, y: n) | bar(bogus: 1)").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("bar"));
+    }
+
+    #[test]
+    fn test_parse_plot_spec_warnings() {
+        let (spec, warnings) = parse_plot_spec(r#"aes(x: a, y: b) | line() | labs(title: "A") | labs(title: "B")"#).unwrap();
+        assert_eq!(spec.labels.unwrap().title, Some("B".to_string()));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("labs()"));
+
+        let (_, warnings) = parse_plot_spec("aes(x: a, y: b) | line() | point()").unwrap();
+        assert!(warnings.is_empty());
     }
 
     #[test]
@@ -259,7 +534,7 @@ mod tests {
             Err(e) => println!("Parse error: {:?}", e),
         }
         assert!(result.is_ok());
-        let (_, spec) = result.unwrap();
+        let (spec, _) = result.unwrap();
         assert!(spec.aesthetics.is_some());
         assert_eq!(spec.layers.len(), 1);
         if let crate::parser::ast::Layer::Bar(b) = &spec.layers[0] {
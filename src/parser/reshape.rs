@@ -0,0 +1,335 @@
+// Data reshaping step parser (pivot_wider, join, mutate, group_by/summarize)
+
+use super::ast::{JoinType, PipelineStep};
+use super::lexer::{identifier, number_literal, string_literal, ws};
+use crate::expr::{Aggregation, Expression};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::map,
+    multi::{fold_many0, separated_list0},
+    sequence::{delimited, pair, preceded, separated_pair},
+    IResult,
+};
+
+/// Parse any data reshaping step
+pub fn parse_pipeline_step(input: &str) -> IResult<&str, PipelineStep> {
+    alt((parse_pivot_wider, parse_join, parse_mutate, parse_group_summarize))(input)
+}
+
+/// Parse a column name list like [date, region]
+fn parse_column_list(input: &str) -> IResult<&str, Vec<String>> {
+    let (input, _) = ws(char('['))(input)?;
+    let (input, cols) = separated_list0(ws(char(',')), ws(identifier))(input)?;
+    let (input, _) = ws(char(']'))(input)?;
+    Ok((input, cols))
+}
+
+/// Parse pivot_wider specification
+/// Format: pivot_wider(id_cols: [date], names_from: variable, values_from: value)
+/// - names_from, values_from: required
+/// - id_cols: optional (defaults to every column other than names_from/values_from)
+pub fn parse_pivot_wider(input: &str) -> IResult<&str, PipelineStep> {
+    let (input, _) = ws(tag("pivot_wider"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("id_cols:")), parse_column_list), |c| ("id_cols", c)),
+            map(preceded(ws(tag("names_from:")), ws(identifier)), |c| ("names_from", vec![c])),
+            map(preceded(ws(tag("values_from:")), ws(identifier)), |c| ("values_from", vec![c])),
+        )),
+    )(input)?;
+
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut id_cols = Vec::new();
+    let mut names_from = None;
+    let mut values_from = None;
+
+    for (key, mut value) in args {
+        match key {
+            "id_cols" => id_cols = value,
+            "names_from" => names_from = value.pop(),
+            "values_from" => values_from = value.pop(),
+            _ => {}
+        }
+    }
+
+    // Validate: names_from and values_from are required
+    match (names_from, values_from) {
+        (Some(names_from), Some(values_from)) => Ok((
+            input,
+            PipelineStep::PivotWider { id_cols, names_from, values_from },
+        )),
+        _ => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Tag))),
+    }
+}
+
+/// Parse join specification
+/// Format: join(file: "forecast.csv", on: "date", how: "left")
+/// - file, on: required
+/// - how: optional ("inner" (default) or "left")
+pub fn parse_join(input: &str) -> IResult<&str, PipelineStep> {
+    let (input, _) = ws(tag("join"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("file:")), ws(string_literal)), |v| ("file", v)),
+            map(preceded(ws(tag("on:")), ws(string_literal)), |v| ("on", v)),
+            map(preceded(ws(tag("on:")), ws(identifier)), |v| ("on", v)),
+            map(preceded(ws(tag("how:")), ws(string_literal)), |v| ("how", v)),
+        )),
+    )(input)?;
+
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut file = None;
+    let mut on = None;
+    let mut how = JoinType::default();
+
+    for (key, value) in args {
+        match key {
+            "file" => file = Some(value),
+            "on" => on = Some(value),
+            "how" => {
+                how = match value.as_str() {
+                    "left" => JoinType::Left,
+                    "inner" => JoinType::Inner,
+                    _ => return Err(nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Verify))),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match (file, on) {
+        (Some(file), Some(on)) => Ok((input, PipelineStep::Join { file, on, how })),
+        _ => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Tag))),
+    }
+}
+
+/// Parse mutate specification
+/// Format: mutate(log_price = log(price), ratio = sales / target)
+/// New columns are computed in order, so later expressions can use earlier ones.
+pub fn parse_mutate(input: &str) -> IResult<&str, PipelineStep> {
+    let (input, _) = ws(tag("mutate"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let (input, columns) = separated_list0(
+        ws(char(',')),
+        separated_pair(ws(identifier), ws(char('=')), parse_expression),
+    )(input)?;
+    let (input, _) = ws(char(')'))(input)?;
+
+    if columns.is_empty() {
+        return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::SeparatedList)));
+    }
+
+    Ok((input, PipelineStep::Mutate { columns }))
+}
+
+/// Parse a grouped aggregation (two pipeline components read as one step)
+/// Format: group_by(region, month) | summarize(total = sum(sales), n = count())
+/// Aggregations: sum, mean, median, min, max, sd (of a column), count() / n()
+pub fn parse_group_summarize(input: &str) -> IResult<&str, PipelineStep> {
+    let (input, _) = ws(tag("group_by"))(input)?;
+    let (input, by) = delimited(
+        ws(char('(')),
+        separated_list0(ws(char(',')), ws(identifier)),
+        ws(char(')')),
+    )(input)?;
+    let (input, _) = ws(char('|'))(input)?;
+    let (input, _) = ws(alt((tag("summarize"), tag("summarise"))))(input)?;
+    let (input, aggregations) = delimited(
+        ws(char('(')),
+        separated_list0(
+            ws(char(',')),
+            separated_pair(ws(identifier), ws(char('=')), parse_aggregation),
+        ),
+        ws(char(')')),
+    )(input)?;
+
+    if by.is_empty() || aggregations.is_empty() {
+        return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::SeparatedList)));
+    }
+
+    Ok((input, PipelineStep::GroupSummarize { by, aggregations }))
+}
+
+/// Parse one aggregation call like sum(sales) or count()
+fn parse_aggregation(input: &str) -> IResult<&str, Aggregation> {
+    let call = |name: &'static str| {
+        preceded(ws(tag(name)), delimited(ws(char('(')), ws(identifier), ws(char(')'))))
+    };
+    alt((
+        map(call("sum"), Aggregation::Sum),
+        map(call("mean"), Aggregation::Mean),
+        map(call("median"), Aggregation::Median),
+        map(call("min"), Aggregation::Min),
+        map(call("max"), Aggregation::Max),
+        map(call("sd"), Aggregation::Sd),
+        map(
+            preceded(ws(alt((tag("count"), tag("n")))), pair(ws(char('(')), ws(char(')')))),
+            |_| Aggregation::Count,
+        ),
+    ))(input)
+}
+
+/// Parse an arithmetic expression: term (('+' | '-') term)*
+pub fn parse_expression(input: &str) -> IResult<&str, Expression> {
+    let (input, first) = parse_term(input)?;
+    fold_many0(
+        pair(ws(alt((char('+'), char('-')))), parse_term),
+        move || first.clone(),
+        |acc, (op, rhs)| match op {
+            '+' => Expression::Add(Box::new(acc), Box::new(rhs)),
+            _ => Expression::Sub(Box::new(acc), Box::new(rhs)),
+        },
+    )(input)
+}
+
+/// term: factor (('*' | '/') factor)*
+fn parse_term(input: &str) -> IResult<&str, Expression> {
+    let (input, first) = parse_factor(input)?;
+    fold_many0(
+        pair(ws(alt((char('*'), char('/')))), parse_factor),
+        move || first.clone(),
+        |acc, (op, rhs)| match op {
+            '*' => Expression::Mul(Box::new(acc), Box::new(rhs)),
+            _ => Expression::Div(Box::new(acc), Box::new(rhs)),
+        },
+    )(input)
+}
+
+/// factor: log(expr) | sqrt(expr) | column | number | (expr)
+fn parse_factor(input: &str) -> IResult<&str, Expression> {
+    let call = |name: &'static str| {
+        preceded(ws(tag(name)), delimited(ws(char('(')), parse_expression, ws(char(')'))))
+    };
+    alt((
+        map(call("log"), |e| Expression::Log(Box::new(e))),
+        map(call("sqrt"), |e| Expression::Sqrt(Box::new(e))),
+        map(ws(identifier), Expression::Column),
+        map(ws(number_literal), Expression::Const),
+        delimited(ws(char('(')), parse_expression, ws(char(')'))),
+    ))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pivot_wider() {
+        let (rest, step) = parse_pivot_wider(
+            "pivot_wider(id_cols: [date, site], names_from: variable, values_from: value)"
+        ).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(step, PipelineStep::PivotWider {
+            id_cols: vec!["date".to_string(), "site".to_string()],
+            names_from: "variable".to_string(),
+            values_from: "value".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_parse_pivot_wider_without_id_cols() {
+        let (_, step) = parse_pivot_wider("pivot_wider(names_from: k, values_from: v)").unwrap();
+        assert_eq!(step, PipelineStep::PivotWider {
+            id_cols: vec![],
+            names_from: "k".to_string(),
+            values_from: "v".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_parse_pivot_wider_missing_required() {
+        assert!(parse_pivot_wider("pivot_wider(names_from: k)").is_err());
+    }
+
+    #[test]
+    fn test_parse_join() {
+        let (rest, step) = parse_join(r#"join(file: "forecast.csv", on: "date", how: "left")"#).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(step, PipelineStep::Join {
+            file: "forecast.csv".to_string(),
+            on: "date".to_string(),
+            how: JoinType::Left,
+        });
+    }
+
+    #[test]
+    fn test_parse_join_defaults_to_inner() {
+        let (_, step) = parse_pipeline_step(r#"join(file: "f.csv", on: date)"#).unwrap();
+        assert!(matches!(step, PipelineStep::Join { how: JoinType::Inner, .. }));
+    }
+
+    #[test]
+    fn test_parse_join_invalid() {
+        assert!(parse_join(r#"join(on: "date")"#).is_err());
+        assert!(parse_join(r#"join(file: "f.csv", on: "date", how: "outer")"#).is_err());
+    }
+
+    fn col(name: &str) -> Box<Expression> {
+        Box::new(Expression::Column(name.to_string()))
+    }
+
+    #[test]
+    fn test_parse_mutate() {
+        let (rest, step) = parse_mutate("mutate(log_price = log(price), ratio = sales / target)").unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(step, PipelineStep::Mutate {
+            columns: vec![
+                ("log_price".to_string(), Expression::Log(col("price"))),
+                ("ratio".to_string(), Expression::Div(col("sales"), col("target"))),
+            ],
+        });
+    }
+
+    #[test]
+    fn test_parse_expression_precedence() {
+        let (_, e) = parse_expression("a + b * 2 - (c - 1)").unwrap();
+        assert_eq!(e, Expression::Sub(
+            Box::new(Expression::Add(col("a"), Box::new(Expression::Mul(col("b"), Box::new(Expression::Const(2.0)))))),
+            Box::new(Expression::Sub(col("c"), Box::new(Expression::Const(1.0)))),
+        ));
+    }
+
+    #[test]
+    fn test_parse_expression_function_prefixed_column() {
+        // Columns starting with a function name are not calls
+        let (_, e) = parse_expression("sqrt(area) / logins").unwrap();
+        assert_eq!(e, Expression::Div(Box::new(Expression::Sqrt(col("area"))), col("logins")));
+    }
+
+    #[test]
+    fn test_parse_mutate_empty() {
+        assert!(parse_mutate("mutate()").is_err());
+    }
+
+    #[test]
+    fn test_parse_group_summarize() {
+        let (rest, step) = parse_group_summarize(
+            "group_by(region, month) | summarize(total = sum(sales), avg = mean(profit), n = count())"
+        ).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(step, PipelineStep::GroupSummarize {
+            by: vec!["region".to_string(), "month".to_string()],
+            aggregations: vec![
+                ("total".to_string(), Aggregation::Sum("sales".to_string())),
+                ("avg".to_string(), Aggregation::Mean("profit".to_string())),
+                ("n".to_string(), Aggregation::Count),
+            ],
+        });
+    }
+
+    #[test]
+    fn test_parse_group_summarize_requires_summarize() {
+        assert!(parse_group_summarize("group_by(region)").is_err());
+        assert!(parse_group_summarize("group_by(region) | summarize(x = total(sales))").is_err());
+    }
+}
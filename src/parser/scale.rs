@@ -2,55 +2,324 @@ use nom::{
     bytes::complete::tag,
     character::complete::char,
     branch::alt,
-    combinator::{map},
-    sequence::{delimited},
+    combinator::{map, opt},
+    multi::separated_list0,
+    sequence::{delimited, preceded, separated_pair},
     IResult,
 };
-use crate::parser::ast::{AxisScale, ScaleType};
-use crate::parser::lexer::{number_literal, ws};
+use crate::parser::ast::{AxisScale, ColorScale, DiscreteScale, FillScale, IdentityScales, OutOfBounds, ScaleType, TickFormat};
+use crate::parser::lexer::{number_literal, string_literal, ws};
+use crate::theme_resolve::parse_color;
+use chrono::format::{Item, StrftimeItems};
 
 pub fn parse_scale_x_log10(input: &str) -> IResult<&str, AxisScale> {
     let (input, _) = ws(tag("scale_x_log10"))(input)?;
     let (input, _) = delimited(tag("("), ws(tag("")), tag(")"))(input)?;
-    Ok((input, AxisScale { scale_type: ScaleType::Log10, limits: None }))
+    Ok((input, AxisScale { scale_type: ScaleType::Log10, ..Default::default() }))
 }
 
 pub fn parse_scale_y_log10(input: &str) -> IResult<&str, AxisScale> {
     let (input, _) = ws(tag("scale_y_log10"))(input)?;
     let (input, _) = delimited(tag("("), ws(tag("")), tag(")"))(input)?;
-    Ok((input, AxisScale { scale_type: ScaleType::Log10, limits: None }))
+    Ok((input, AxisScale { scale_type: ScaleType::Log10, ..Default::default() }))
 }
 
 pub fn parse_scale_x_reverse(input: &str) -> IResult<&str, AxisScale> {
     let (input, _) = ws(tag("scale_x_reverse"))(input)?;
     let (input, _) = delimited(tag("("), ws(tag("")), tag(")"))(input)?;
-    Ok((input, AxisScale { scale_type: ScaleType::Reverse, limits: None }))
+    Ok((input, AxisScale { scale_type: ScaleType::Reverse, ..Default::default() }))
 }
 
 pub fn parse_scale_y_reverse(input: &str) -> IResult<&str, AxisScale> {
     let (input, _) = ws(tag("scale_y_reverse"))(input)?;
     let (input, _) = delimited(tag("("), ws(tag("")), tag(")"))(input)?;
-    Ok((input, AxisScale { scale_type: ScaleType::Reverse, limits: None }))
+    Ok((input, AxisScale { scale_type: ScaleType::Reverse, ..Default::default() }))
 }
 
+/// Parse the `lo, hi` arguments of xlim()/ylim(), either positional or named (`lo: 0, hi: 100`)
+fn parse_lim_args(input: &str) -> IResult<&str, (f64, f64)> {
+    separated_pair(
+        preceded(opt(ws(tag("lo:"))), ws(number_literal)),
+        ws(char(',')),
+        preceded(opt(ws(tag("hi:"))), ws(number_literal)),
+    )(input)
+}
+
+/// Parse a `[lo, hi]` limits pair
+fn parse_limits_pair(input: &str) -> IResult<&str, (f64, f64)> {
+    delimited(
+        ws(char('[')),
+        separated_pair(ws(number_literal), ws(char(',')), ws(number_literal)),
+        ws(char(']')),
+    )(input)
+}
+
+enum ContinuousArg {
+    Limits((f64, f64)),
+    BreaksN(f64),
+    BreaksWidth(f64),
+    Labels(String),
+    Oob(String),
+}
+
+/// Parse `scale_x_continuous(...)` / `scale_y_continuous(...)` with optional
+/// `limits: [lo, hi]`, `breaks_n: 5` (tick count), `breaks_width: 10` (tick spacing)
+/// `labels: "comma"` (tick label format) and `oob: "squish"` (values outside the limits)
+fn parse_scale_continuous<'a>(keyword: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, AxisScale> {
+    move |input: &'a str| {
+        let (input, _) = ws(tag(keyword))(input)?;
+        let (input, _) = ws(char('('))(input)?;
+        let args_start = input;
+        let (input, args) = separated_list0(
+            ws(char(',')),
+            alt((
+                map(preceded(ws(tag("limits:")), parse_limits_pair), ContinuousArg::Limits),
+                map(preceded(ws(tag("breaks_n:")), ws(number_literal)), ContinuousArg::BreaksN),
+                map(preceded(ws(tag("breaks_width:")), ws(number_literal)), ContinuousArg::BreaksWidth),
+                map(preceded(ws(tag("labels:")), ws(string_literal)), ContinuousArg::Labels),
+                map(preceded(ws(tag("oob:")), ws(string_literal)), ContinuousArg::Oob),
+            )),
+        )(input)?;
+        let (input, _) = ws(char(')'))(input)?;
+
+        let invalid = || nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify));
+        let mut scale = AxisScale::default();
+        for arg in args {
+            match arg {
+                ContinuousArg::Limits(limits) => scale.limits = Some(limits),
+                ContinuousArg::BreaksN(n) if n >= 1.0 => scale.breaks_n = Some(n as usize),
+                ContinuousArg::BreaksWidth(w) if w > 0.0 && w.is_finite() => scale.breaks_width = Some(w),
+                ContinuousArg::Labels(l) => {
+                    scale.labels = match l.as_str() {
+                        "auto" => TickFormat::Auto,
+                        "comma" => TickFormat::Comma,
+                        "scientific" => TickFormat::Scientific,
+                        "percent" => TickFormat::Percent,
+                        "dollar" => TickFormat::Dollar,
+                        _ if l.contains('%') => TickFormat::Custom(l),
+                        _ => return Err(invalid()),
+                    }
+                }
+                ContinuousArg::Oob(o) => {
+                    scale.oob = match o.as_str() {
+                        "keep" => OutOfBounds::Keep,
+                        "squish" => OutOfBounds::Squish,
+                        "censor" => OutOfBounds::Censor,
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok((input, scale))
+    }
+}
+
+pub fn parse_scale_x_continuous(input: &str) -> IResult<&str, AxisScale> {
+    parse_scale_continuous("scale_x_continuous")(input)
+}
+
+pub fn parse_scale_y_continuous(input: &str) -> IResult<&str, AxisScale> {
+    parse_scale_continuous("scale_y_continuous")(input)
+}
+
+/// Parse `scale_x_date(...)` / `scale_y_date(...)` with optional `date_labels: "%b %Y"`
+/// (strftime-style tick format, default "%Y-%m-%d")
+fn parse_scale_date<'a>(keyword: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, AxisScale> {
+    move |input: &'a str| {
+        let (input, _) = ws(tag(keyword))(input)?;
+        let (input, _) = ws(char('('))(input)?;
+        let args_start = input;
+        let (input, labels) = opt(preceded(ws(tag("date_labels:")), ws(string_literal)))(input)?;
+        let (input, _) = ws(char(')'))(input)?;
+
+        let labels = labels.unwrap_or_else(|| "%Y-%m-%d".to_string());
+        if StrftimeItems::new(&labels).any(|item| item == Item::Error) {
+            return Err(nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify)));
+        }
+        Ok((input, AxisScale { labels: TickFormat::Date(labels), ..Default::default() }))
+    }
+}
+
+pub fn parse_scale_x_date(input: &str) -> IResult<&str, AxisScale> {
+    parse_scale_date("scale_x_date")(input)
+}
+
+pub fn parse_scale_y_date(input: &str) -> IResult<&str, AxisScale> {
+    parse_scale_date("scale_y_date")(input)
+}
+
+/// Parse `xlim(lo, hi)` or `xlim(lo: lo, hi: hi)` (shorthand for `scale_x_continuous(limits: [lo, hi])`)
 pub fn parse_xlim(input: &str) -> IResult<&str, AxisScale> {
     let (input, _) = ws(tag("xlim"))(input)?;
     let (input, _) = ws(char('('))(input)?;
-    let (input, min) = ws(number_literal)(input)?;
-    let (input, _) = ws(char(','))(input)?;
-    let (input, max) = ws(number_literal)(input)?;
+    let (input, (min, max)) = parse_lim_args(input)?;
     let (input, _) = ws(char(')'))(input)?;
-    Ok((input, AxisScale { scale_type: ScaleType::Linear, limits: Some((min, max)) }))
+    Ok((input, AxisScale { limits: Some((min, max)), ..Default::default() }))
 }
 
+/// Parse `ylim(lo, hi)` or `ylim(lo: lo, hi: hi)` (shorthand for `scale_y_continuous(limits: [lo, hi])`)
 pub fn parse_ylim(input: &str) -> IResult<&str, AxisScale> {
     let (input, _) = ws(tag("ylim"))(input)?;
     let (input, _) = ws(char('('))(input)?;
-    let (input, min) = ws(number_literal)(input)?;
-    let (input, _) = ws(char(','))(input)?;
-    let (input, max) = ws(number_literal)(input)?;
+    let (input, (min, max)) = parse_lim_args(input)?;
     let (input, _) = ws(char(')'))(input)?;
-    Ok((input, AxisScale { scale_type: ScaleType::Linear, limits: Some((min, max)) }))
+    Ok((input, AxisScale { limits: Some((min, max)), ..Default::default() }))
+}
+
+enum DiscreteArg {
+    Limits(Vec<String>),
+    Labels(Vec<(String, String)>),
+    Drop(bool),
+}
+
+/// Parse `scale_x_discrete(limits: ["Q4", "Q3"], labels: {"Q4": "Fourth"}, drop: false)`:
+/// category order, display names, and whether rows outside `limits` are dropped (default true)
+pub fn parse_scale_x_discrete(input: &str) -> IResult<&str, AxisScale> {
+    let (input, _) = ws(tag("scale_x_discrete"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(
+                preceded(ws(tag("limits:")), delimited(ws(char('[')), separated_list0(ws(char(',')), ws(string_literal)), ws(char(']')))),
+                DiscreteArg::Limits,
+            ),
+            map(
+                preceded(ws(tag("labels:")), delimited(
+                    ws(char('{')),
+                    separated_list0(ws(char(',')), separated_pair(ws(string_literal), ws(char(':')), ws(string_literal))),
+                    ws(char('}')),
+                )),
+                DiscreteArg::Labels,
+            ),
+            map(preceded(ws(tag("drop:")), ws(alt((tag("true"), tag("false"))))), |d| DiscreteArg::Drop(d == "true")),
+        )),
+    )(input)?;
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut discrete = DiscreteScale::default();
+    for arg in args {
+        match arg {
+            DiscreteArg::Limits(limits) => discrete.limits = Some(limits),
+            DiscreteArg::Labels(labels) => discrete.labels = labels.into_iter().collect(),
+            DiscreteArg::Drop(drop) => discrete.drop = drop,
+        }
+    }
+    Ok((input, AxisScale { discrete: Some(discrete), ..Default::default() }))
+}
+
+/// Parse `scale_color_brewer(palette: "Set2", type: "qual")` (also `scale_fill_brewer`).
+/// Without a palette, `type` picks a default: "seq" -> Blues, "div" -> RdYlGn, "qual" -> Set2.
+pub fn parse_scale_color_brewer(input: &str) -> IResult<&str, ColorScale> {
+    let (input, _) = ws(alt((tag("scale_color_brewer"), tag("scale_colour_brewer"), tag("scale_fill_brewer"))))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("palette:")), ws(string_literal)), |v| ("palette", v)),
+            map(preceded(ws(tag("type:")), ws(string_literal)), |v| ("type", v)),
+        )),
+    )(input)?;
+    let (input, _) = ws(char(')'))(input)?;
+
+    let mut palette = None;
+    let mut kind = None;
+    for (key, value) in args {
+        match key {
+            "palette" => palette = Some(value),
+            _ => kind = Some(value),
+        }
+    }
+
+    let palette = match (palette, kind.as_deref()) {
+        (Some(p), _) => p,
+        (None, None | Some("seq")) => "Blues".to_string(),
+        (None, Some("div")) => "RdYlGn".to_string(),
+        (None, Some("qual")) => "Set2".to_string(),
+        (None, Some(_)) => return Err(nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Verify))),
+    };
+
+    Ok((input, ColorScale::Brewer { palette }))
+}
+
+/// Parse `scale_fill_continuous(low: "white", high: "steelblue", mid: "white", midpoint: 0, na_value: "grey", trans: "log10")`.
+/// Omitted colors keep ggplot2's defaults; unknown color names and transforms are rejected.
+pub fn parse_scale_fill_continuous(input: &str) -> IResult<&str, FillScale> {
+    let (input, _) = ws(tag("scale_fill_continuous"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let args_start = input;
+    let (input, args) = separated_list0(
+        ws(char(',')),
+        alt((
+            map(preceded(ws(tag("low:")), ws(string_literal)), |v| ("low", v)),
+            map(preceded(ws(tag("high:")), ws(string_literal)), |v| ("high", v)),
+            map(preceded(ws(tag("midpoint:")), ws(number_literal)), |v| ("midpoint", v.to_string())),
+            map(preceded(ws(tag("mid:")), ws(string_literal)), |v| ("mid", v)),
+            map(preceded(ws(tag("na_value:")), ws(string_literal)), |v| ("na_value", v)),
+            map(preceded(ws(tag("trans:")), ws(string_literal)), |v| ("trans", v)),
+        )),
+    )(input)?;
+    let (input, _) = ws(char(')'))(input)?;
+
+    let FillScale::Continuous { mut low, mut high, mut mid, mut midpoint, mut na_value, mut trans } = FillScale::default();
+    for (key, value) in args {
+        let invalid = || nom::Err::Failure(nom::error::Error::new(args_start, nom::error::ErrorKind::Verify));
+        if !matches!(key, "midpoint" | "trans") && parse_color(&value).is_none() {
+            return Err(invalid());
+        }
+        match key {
+            "low" => low = value,
+            "high" => high = value,
+            "mid" => mid = Some(value),
+            "midpoint" => midpoint = Some(value.parse().map_err(|_| invalid())?),
+            "trans" => trans = Some(match value.as_str() {
+                "identity" => ScaleType::Linear,
+                "log10" => ScaleType::Log10,
+                _ => return Err(invalid()),
+            }),
+            _ => na_value = value,
+        }
+    }
+
+    Ok((input, FillScale::Continuous { low, high, mid, midpoint, na_value, trans }))
+}
+
+/// Parse `scale_linetype_manual(values: ["solid", "dashed", ...])`
+pub fn parse_scale_linetype_manual(input: &str) -> IResult<&str, Vec<String>> {
+    let (input, _) = ws(tag("scale_linetype_manual"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let (input, _) = ws(tag("values:"))(input)?;
+    let (input, values) = delimited(
+        ws(char('[')),
+        separated_list0(ws(char(',')), ws(string_literal)),
+        ws(char(']')),
+    )(input)?;
+    let (input, _) = ws(char(')'))(input)?;
+
+    if values.is_empty() {
+        return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::SeparatedList)));
+    }
+    Ok((input, values))
+}
+
+/// Parse `scale_color_identity()` (also `scale_colour_identity`, `scale_fill_identity`)
+pub fn parse_scale_color_identity(input: &str) -> IResult<&str, ColorScale> {
+    let (input, _) = ws(alt((tag("scale_color_identity"), tag("scale_colour_identity"), tag("scale_fill_identity"))))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let (input, _) = ws(char(')'))(input)?;
+    Ok((input, ColorScale::Identity))
+}
+
+/// Parse `scale_shape_identity()` or `scale_linetype_identity()`
+pub fn parse_scale_identity(input: &str) -> IResult<&str, IdentityScales> {
+    let (input, scale) = alt((
+        map(ws(tag("scale_shape_identity")), |_| IdentityScales { shape: true, ..Default::default() }),
+        map(ws(tag("scale_linetype_identity")), |_| IdentityScales { linetype: true, ..Default::default() }),
+    ))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let (input, _) = ws(char(')'))(input)?;
+    Ok((input, scale))
 }
 
 pub fn parse_scale_command(input: &str) -> IResult<&str, (bool, AxisScale)> {
@@ -58,8 +327,162 @@ pub fn parse_scale_command(input: &str) -> IResult<&str, (bool, AxisScale)> {
         map(parse_scale_x_log10, |s| (true, s)),
         map(parse_scale_y_log10, |s| (false, s)),
         map(parse_scale_x_reverse, |s| (true, s)),
+        map(parse_scale_x_discrete, |s| (true, s)),
         map(parse_scale_y_reverse, |s| (false, s)),
-        map(parse_xlim, |s| (true, s)),
-        map(parse_ylim, |s| (false, s)),
+        map(parse_scale_x_date, |s| (true, s)),
+        map(parse_scale_y_date, |s| (false, s)),
+        map(alt((parse_scale_x_continuous, parse_xlim)), |s| (true, s)),
+        map(alt((parse_scale_y_continuous, parse_ylim)), |s| (false, s)),
     ))(input)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_identity_scales() {
+        assert_eq!(parse_scale_color_identity("scale_colour_identity()"), Ok(("", ColorScale::Identity)));
+        assert_eq!(parse_scale_color_identity("scale_fill_identity( )"), Ok(("", ColorScale::Identity)));
+        assert_eq!(parse_scale_identity("scale_shape_identity()"), Ok(("", IdentityScales { shape: true, linetype: false })));
+        assert_eq!(parse_scale_identity("scale_linetype_identity()"), Ok(("", IdentityScales { shape: false, linetype: true })));
+        assert!(parse_scale_identity("scale_shape_identity(values: 1)").is_err());
+    }
+
+    #[test]
+    fn test_parse_scale_continuous_breaks() {
+        let (_, scale) = parse_scale_x_continuous("scale_x_continuous(breaks_n: 5)").unwrap();
+        assert_eq!(scale.breaks_n, Some(5));
+        assert_eq!(scale.breaks_width, None);
+
+        let (_, scale) = parse_scale_y_continuous("scale_y_continuous(breaks_width: 10, limits: [0, 100])").unwrap();
+        assert_eq!(scale.breaks_width, Some(10.0));
+        assert_eq!(scale.limits, Some((0.0, 100.0)));
+
+        assert!(matches!(parse_scale_x_continuous("scale_x_continuous(breaks_n: 0)"), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_scale_x_continuous("scale_x_continuous(breaks_width: -1)"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_scale_continuous_labels() {
+        let (_, scale) = parse_scale_y_continuous("scale_y_continuous(labels: \"comma\")").unwrap();
+        assert_eq!(scale.labels, TickFormat::Comma);
+
+        let (_, scale) = parse_scale_y_continuous("scale_y_continuous(labels: \"%.1f%%\", breaks_n: 4)").unwrap();
+        assert_eq!(scale.labels, TickFormat::Custom("%.1f%%".to_string()));
+        assert_eq!(scale.breaks_n, Some(4));
+
+        assert!(matches!(parse_scale_y_continuous("scale_y_continuous(labels: \"roman\")"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_scale_continuous_oob() {
+        let (_, scale) = parse_scale_y_continuous(r#"scale_y_continuous(limits: [0, 10], oob: "squish")"#).unwrap();
+        assert_eq!(scale.oob, OutOfBounds::Squish);
+        let (_, scale) = parse_scale_x_continuous(r#"scale_x_continuous(oob: "censor")"#).unwrap();
+        assert_eq!(scale.oob, OutOfBounds::Censor);
+        assert_eq!(parse_ylim("ylim(0, 1)").unwrap().1.oob, OutOfBounds::Keep);
+
+        assert!(matches!(parse_scale_y_continuous(r#"scale_y_continuous(oob: "wrap")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_scale_date() {
+        let (_, scale) = parse_scale_x_date("scale_x_date()").unwrap();
+        assert_eq!(scale.labels, TickFormat::Date("%Y-%m-%d".to_string()));
+        let (_, (is_x, scale)) = parse_scale_command(r#"scale_y_date(date_labels: "%b %Y")"#).unwrap();
+        assert!(!is_x);
+        assert_eq!(scale.labels, TickFormat::Date("%b %Y".to_string()));
+
+        assert!(matches!(parse_scale_x_date(r#"scale_x_date(date_labels: "%Q")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_xlim_positional() {
+        let (_, scale) = parse_xlim("xlim(0, 100)").unwrap();
+        assert_eq!(scale.limits, Some((0.0, 100.0)));
+        assert_eq!(scale.scale_type, ScaleType::Linear);
+    }
+
+    #[test]
+    fn test_parse_ylim_named() {
+        let (_, scale) = parse_ylim("ylim(lo: -50, hi: 50)").unwrap();
+        assert_eq!(scale.limits, Some((-50.0, 50.0)));
+    }
+
+    #[test]
+    fn test_lim_matches_long_form() {
+        let (_, short) = parse_xlim("xlim(0, 100)").unwrap();
+        let (_, long) = parse_scale_x_continuous("scale_x_continuous(limits: [0, 100])").unwrap();
+        assert_eq!(short, long);
+    }
+
+    #[test]
+    fn test_parse_scale_continuous_without_limits() {
+        let (_, scale) = parse_scale_y_continuous("scale_y_continuous()").unwrap();
+        assert_eq!(scale.limits, None);
+    }
+
+    #[test]
+    fn test_parse_scale_command_axis() {
+        let (_, (is_x, _)) = parse_scale_command("ylim(1, 2)").unwrap();
+        assert!(!is_x);
+        let (_, (is_x, _)) = parse_scale_command("scale_x_continuous(limits: [1, 2])").unwrap();
+        assert!(is_x);
+    }
+
+    #[test]
+    fn test_parse_scale_x_discrete() {
+        let (_, (is_x, scale)) = parse_scale_command(r#"scale_x_discrete(limits: ["Q4", "Q1"], labels: {"Q1": "First"}, drop: false)"#).unwrap();
+        assert!(is_x);
+        let discrete = scale.discrete.unwrap();
+        assert_eq!(discrete.limits, Some(vec!["Q4".to_string(), "Q1".to_string()]));
+        assert_eq!(discrete.labels.get("Q1").map(String::as_str), Some("First"));
+        assert!(!discrete.drop);
+
+        let (_, scale) = parse_scale_x_discrete("scale_x_discrete()").unwrap();
+        assert_eq!(scale.discrete, Some(DiscreteScale::default()));
+        assert!(scale.discrete.unwrap().drop);
+    }
+
+    #[test]
+    fn test_parse_scale_color_brewer() {
+        let (_, scale) = parse_scale_color_brewer(r#"scale_color_brewer(palette: "Set2", type: "qual")"#).unwrap();
+        assert_eq!(scale, ColorScale::Brewer { palette: "Set2".to_string() });
+        let (_, scale) = parse_scale_color_brewer(r#"scale_fill_brewer(type: "div")"#).unwrap();
+        assert_eq!(scale, ColorScale::Brewer { palette: "RdYlGn".to_string() });
+        let (_, scale) = parse_scale_color_brewer("scale_color_brewer()").unwrap();
+        assert_eq!(scale, ColorScale::Brewer { palette: "Blues".to_string() });
+        assert!(parse_scale_color_brewer(r#"scale_color_brewer(type: "other")"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_scale_fill_continuous() {
+        let (_, scale) = parse_scale_fill_continuous(r#"scale_fill_continuous(low: "white", high: "steelblue")"#).unwrap();
+        assert_eq!(scale, FillScale::Continuous {
+            low: "white".to_string(),
+            high: "steelblue".to_string(),
+            mid: None,
+            midpoint: None,
+            na_value: "grey".to_string(),
+            trans: None,
+        });
+
+        let (_, scale) = parse_scale_fill_continuous(r#"scale_fill_continuous(mid: "white", midpoint: 0, na_value: "grey")"#).unwrap();
+        assert!(matches!(scale, FillScale::Continuous { mid: Some(_), midpoint: Some(m), .. } if m == 0.0));
+
+        let (_, scale) = parse_scale_fill_continuous(r#"scale_fill_continuous(trans: "log10")"#).unwrap();
+        assert!(matches!(scale, FillScale::Continuous { trans: Some(ScaleType::Log10), .. }));
+        assert!(matches!(parse_scale_fill_continuous(r#"scale_fill_continuous(trans: "sqrt")"#), Err(nom::Err::Failure(_))));
+
+        assert_eq!(parse_scale_fill_continuous("scale_fill_continuous()").unwrap().1, FillScale::default());
+        assert!(matches!(parse_scale_fill_continuous(r#"scale_fill_continuous(low: "notacolor")"#), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_scale_linetype_manual() {
+        let (_, values) = parse_scale_linetype_manual(r#"scale_linetype_manual(values: ["solid", "dashed"])"#).unwrap();
+        assert_eq!(values, vec!["solid", "dashed"]);
+        assert!(parse_scale_linetype_manual("scale_linetype_manual(values: [])").is_err());
+    }
+}
@@ -140,6 +140,7 @@ enum ArgValue {
 #[derive(Debug)]
 enum ThemeArg {
     LegendPosition(LegendPosition),
+    LegendKeySize(f64),
     PlotBackground(ThemeElement),
     PlotTitle(ThemeElement),
     PanelBackground(ThemeElement),
@@ -172,9 +173,20 @@ fn parse_legend_position_arg(input: &str) -> IResult<&str, ThemeArg> {
     Ok((input, ThemeArg::LegendPosition(pos)))
 }
 
+/// legend_key_size: multiplier on the default key size (must be positive)
+fn parse_legend_key_size_arg(input: &str) -> IResult<&str, ThemeArg> {
+    let (input, _) = ws(tag("legend_key_size:"))(input)?;
+    let (rest, size) = ws(number_literal)(input)?;
+    if size <= 0.0 {
+        return Err(nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Verify)));
+    }
+    Ok((rest, ThemeArg::LegendKeySize(size)))
+}
+
 fn parse_theme_arg(input: &str) -> IResult<&str, ThemeArg> {
     alt((
         parse_legend_position_arg,
+        parse_legend_key_size_arg,
         map(preceded(ws(tag("plot_background:")), ws(parse_theme_element)), ThemeArg::PlotBackground),
         map(preceded(ws(tag("plot_title:")), ws(parse_theme_element)), ThemeArg::PlotTitle),
         map(preceded(ws(tag("panel_background:")), ws(parse_theme_element)), ThemeArg::PanelBackground),
@@ -191,36 +203,38 @@ fn parse_theme_arg(input: &str) -> IResult<&str, ThemeArg> {
 
 // === Main Theme Parsers ===
 
+/// Built-in theme presets, for --list-themes
+pub const THEME_PRESETS: &[(&str, &str)] = &[
+    ("theme_minimal()", "no background, light grid lines"),
+    ("theme_classic()", "axis lines, no grid"),
+    ("theme_void()", "no axes, grid or background"),
+];
+
 /// Parse theme_minimal() - returns a preset minimal theme
 pub fn parse_theme_minimal(input: &str) -> IResult<&str, Theme> {
     let (input, _) = ws(tag("theme_minimal"))(input)?;
     let (input, _) = ws(char('('))(input)?;
     let (input, _) = ws(char(')'))(input)?;
 
-    Ok((input, Theme {
-        line: ThemeElement::Inherit,
-        rect: ThemeElement::Inherit,
-        text: ThemeElement::Inherit,
-        plot_background: ThemeElement::Rect(ElementRect {
-            fill: Some("white".to_string()),
-            ..Default::default()
-        }),
-        plot_title: ThemeElement::Inherit,
-        panel_background: ThemeElement::Rect(ElementRect {
-            fill: Some("white".to_string()),
-            ..Default::default()
-        }),
-        panel_grid_major: ThemeElement::Line(ElementLine {
-            color: Some("#CCCCCC".to_string()),
-            width: Some(0.5),
-            ..Default::default()
-        }),
-        panel_grid_minor: ThemeElement::Blank,
-        axis_text: ThemeElement::Inherit,
-        axis_line: ThemeElement::Blank,
-        axis_ticks: ThemeElement::Blank,
-        legend_position: LegendPosition::UpperRight,
-    }))
+    Ok((input, Theme::minimal()))
+}
+
+/// Parse theme_classic() preset
+pub fn parse_theme_classic(input: &str) -> IResult<&str, Theme> {
+    let (input, _) = ws(tag("theme_classic"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let (input, _) = ws(char(')'))(input)?;
+
+    Ok((input, Theme::classic()))
+}
+
+/// Parse theme_void() preset
+pub fn parse_theme_void(input: &str) -> IResult<&str, Theme> {
+    let (input, _) = ws(tag("theme_void"))(input)?;
+    let (input, _) = ws(char('('))(input)?;
+    let (input, _) = ws(char(')'))(input)?;
+
+    Ok((input, Theme::void()))
 }
 
 /// Parse theme(...) with hierarchical element arguments
@@ -238,7 +252,8 @@ pub fn parse_theme(input: &str) -> IResult<&str, Theme> {
     let mut theme = Theme::default();
     for arg in args {
         match arg {
-            ThemeArg::LegendPosition(pos) => theme.legend_position = pos,
+            ThemeArg::LegendPosition(pos) => theme.legend_position = Some(pos),
+            ThemeArg::LegendKeySize(size) => theme.legend_key_size = Some(size),
             ThemeArg::PlotBackground(elem) => theme.plot_background = elem,
             ThemeArg::PlotTitle(elem) => theme.plot_title = elem,
             ThemeArg::PanelBackground(elem) => theme.panel_background = elem,
@@ -256,9 +271,9 @@ pub fn parse_theme(input: &str) -> IResult<&str, Theme> {
     Ok((input, theme))
 }
 
-/// Parse any theme command (theme_minimal or theme)
+/// Parse any theme command (theme_minimal, theme_classic, theme_void or theme)
 pub fn parse_theme_command(input: &str) -> IResult<&str, Theme> {
-    alt((parse_theme_minimal, parse_theme))(input)
+    alt((parse_theme_minimal, parse_theme_classic, parse_theme_void, parse_theme))(input)
 }
 
 #[cfg(test)]
@@ -338,7 +353,16 @@ mod tests {
         let result = parse_theme("theme(legend_position: \"bottom\")");
         assert!(result.is_ok());
         let (_, theme) = result.unwrap();
-        assert_eq!(theme.legend_position, LegendPosition::LowerMiddle);
+        assert_eq!(theme.legend_position, Some(LegendPosition::LowerMiddle));
+    }
+
+    #[test]
+    fn test_parse_theme_legend_key_size() {
+        let (_, theme) = parse_theme("theme(legend_key_size: 1.5, legend_position: \"left\")").unwrap();
+        assert_eq!(theme.legend_key_size, Some(1.5));
+        assert_eq!(theme.legend_position, Some(LegendPosition::MiddleLeft));
+        assert_eq!(parse_theme("theme()").unwrap().1.legend_key_size, None);
+        assert!(matches!(parse_theme("theme(legend_key_size: 0)"), Err(nom::Err::Failure(_))));
     }
 
     #[test]
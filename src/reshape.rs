@@ -0,0 +1,221 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use crate::csv_reader;
+use crate::data::{find_col_index, PlotData};
+use crate::expr::{Aggregation, Expression};
+use crate::parser::ast::{JoinType, PipelineStep};
+
+/// Apply the pipeline's data reshaping steps, in order.
+/// join() files are read relative to `data_dir` (or the current directory).
+pub fn apply_steps(data: PlotData, steps: &[PipelineStep], data_dir: Option<&Path>) -> Result<PlotData> {
+    steps.iter().try_fold(data, |data, step| match step {
+        PipelineStep::PivotWider { id_cols, names_from, values_from } => {
+            data.pivot_wider(id_cols, names_from, values_from)
+        }
+        PipelineStep::Join { file, on, how } => {
+            let path = match data_dir {
+                Some(dir) => dir.join(file),
+                None => Path::new(file).to_path_buf(),
+            };
+            let other = PlotData::from_csv(csv_reader::read_csv_from_path(&path)?);
+            match how {
+                JoinType::Inner => data.inner_join(&other, on),
+                JoinType::Left => data.left_join(&other, on),
+            }
+        }
+        PipelineStep::Mutate { columns } => mutate(data, columns),
+        PipelineStep::GroupSummarize { by, aggregations } => group_summarize(&data, by, aggregations),
+    })
+}
+
+/// Collapse the table to one row per unique combination of `by` values (in order of
+/// first appearance), with one column per aggregation. Empty cells are skipped.
+pub fn group_summarize(
+    data: &PlotData,
+    by: &[String],
+    aggregations: &[(String, Aggregation)],
+) -> Result<PlotData> {
+    let by_idxs: Vec<usize> = by.iter().map(|c| find_col_index(&data.headers, c)).collect::<Result<_>>()?;
+    let agg_idxs: Vec<Option<usize>> = aggregations.iter()
+        .map(|(_, agg)| agg.column().map(|c| find_col_index(&data.headers, c)).transpose())
+        .collect::<Result<_>>()?;
+
+    // Group key -> per-aggregation value lists
+    let mut keys: Vec<Vec<String>> = Vec::new();
+    let mut key_pos: HashMap<Vec<String>, usize> = HashMap::new();
+    let mut values: Vec<Vec<Vec<f64>>> = Vec::new();
+
+    for row in &data.rows {
+        let key: Vec<String> = by_idxs.iter().map(|&i| row[i].clone()).collect();
+        let group_i = *key_pos.entry(key.clone()).or_insert_with(|| {
+            keys.push(key);
+            values.push(vec![Vec::new(); aggregations.len()]);
+            keys.len() - 1
+        });
+
+        for (agg_i, idx) in agg_idxs.iter().enumerate() {
+            let v = match idx {
+                None => 1.0, // count(): every row counts
+                Some(idx) if row[*idx].trim().is_empty() => continue,
+                Some(idx) => row[*idx].trim().parse::<f64>()
+                    .map_err(|_| anyhow!("summarize: non-numeric value '{}' in column '{}'", row[*idx], data.headers[*idx]))?,
+            };
+            values[group_i][agg_i].push(v);
+        }
+    }
+
+    let mut headers: Vec<String> = by_idxs.iter().map(|&i| data.headers[i].clone()).collect();
+    headers.extend(aggregations.iter().map(|(name, _)| name.clone()));
+
+    let rows = keys.into_iter().zip(values).map(|(mut row, group_values)| {
+        for ((_, agg), vals) in aggregations.iter().zip(group_values) {
+            row.push(if vals.is_empty() && *agg != Aggregation::Count {
+                String::new()
+            } else {
+                agg.apply(&vals).to_string()
+            });
+        }
+        row
+    }).collect();
+
+    Ok(PlotData::new(headers, rows))
+}
+
+/// Append computed columns, evaluated per row in order (replacing same-named columns)
+pub fn mutate(mut data: PlotData, columns: &[(String, Expression)]) -> Result<PlotData> {
+    for (name, expr) in columns {
+        let values = data.rows.iter().enumerate().map(|(i, row)| {
+            let v = expr.eval(&data.headers, row)
+                .with_context(|| format!("mutate: failed to compute '{}' for row {}", name, i + 1))?;
+            if !v.is_finite() {
+                anyhow::bail!("mutate: '{}' is not a finite number for row {} ({})", name, i + 1, v);
+            }
+            Ok(v.to_string())
+        }).collect::<Result<Vec<_>>>()?;
+
+        match data.headers.iter().position(|h| h.eq_ignore_ascii_case(name)) {
+            Some(idx) => {
+                for (row, v) in data.rows.iter_mut().zip(values) { row[idx] = v; }
+            }
+            None => {
+                data.headers.push(name.clone());
+                for (row, v) in data.rows.iter_mut().zip(values) { row.push(v); }
+            }
+        }
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_long() -> PlotData {
+        let row = |d: &str, k: &str, v: &str| vec![d.to_string(), k.to_string(), v.to_string()];
+        PlotData::new(
+            vec!["date".to_string(), "variable".to_string(), "value".to_string()],
+            vec![
+                row("1", "A", "10"),
+                row("1", "B", "20"),
+                row("2", "A", "11"),
+                row("2", "B", "21"),
+                row("3", "A", "12"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_apply_steps() {
+        let steps = vec![PipelineStep::PivotWider {
+            id_cols: vec!["date".to_string()],
+            names_from: "variable".to_string(),
+            values_from: "value".to_string(),
+        }];
+        let wide = apply_steps(make_long(), &steps, None).unwrap();
+        assert_eq!(wide.headers.len(), 3);
+        assert_eq!(apply_steps(make_long(), &[], None).unwrap().headers.len(), 3);
+    }
+
+    #[test]
+    fn test_apply_join_step_from_data_dir() {
+        let data = PlotData::new(
+            vec!["category".to_string(), "extra".to_string()],
+            vec![vec!["A".to_string(), "1".to_string()], vec!["Z".to_string(), "2".to_string()]],
+        );
+        let steps = vec![PipelineStep::Join {
+            file: "stacked_data.csv".to_string(),
+            on: "category".to_string(),
+            how: JoinType::Left,
+        }];
+        let joined = apply_steps(data, &steps, Some(Path::new("fixtures"))).unwrap();
+        assert_eq!(joined.headers, vec!["category", "extra", "value", "segment"]);
+        // A matches two rows (S1, S2); Z is kept unmatched
+        assert_eq!(joined.rows.len(), 3);
+        assert_eq!(joined.rows[2], vec!["Z", "2", "", ""]);
+    }
+
+    #[test]
+    fn test_mutate() {
+        let data = PlotData::new(
+            vec!["sales".to_string(), "target".to_string()],
+            vec![vec!["50".to_string(), "100".to_string()], vec!["30".to_string(), "60".to_string()]],
+        );
+        let columns = vec![
+            ("ratio".to_string(), Expression::Div(
+                Box::new(Expression::Column("sales".to_string())),
+                Box::new(Expression::Column("target".to_string())),
+            )),
+            // Later expressions see earlier columns; existing names are replaced
+            ("sales".to_string(), Expression::Mul(
+                Box::new(Expression::Column("ratio".to_string())),
+                Box::new(Expression::Const(10.0)),
+            )),
+        ];
+        let out = mutate(data, &columns).unwrap();
+        assert_eq!(out.headers, vec!["sales", "target", "ratio"]);
+        assert_eq!(out.rows[0], vec!["5", "100", "0.5"]);
+        assert_eq!(out.rows[1], vec!["5", "60", "0.5"]);
+    }
+
+    #[test]
+    fn test_mutate_non_finite_errors() {
+        let data = PlotData::new(vec!["x".to_string()], vec![vec!["0".to_string()]]);
+        let columns = vec![("lx".to_string(), Expression::Log(Box::new(Expression::Column("x".to_string()))))];
+        assert!(mutate(data, &columns).is_err());
+    }
+
+    #[test]
+    fn test_group_summarize() {
+        let row = |r: &str, m: &str, s: &str| vec![r.to_string(), m.to_string(), s.to_string()];
+        let data = PlotData::new(
+            vec!["region".to_string(), "month".to_string(), "sales".to_string()],
+            vec![
+                row("N", "1", "10"),
+                row("S", "1", "5"),
+                row("N", "1", "20"),
+                row("N", "2", "7"),
+                row("S", "1", ""),
+            ],
+        );
+        let aggs = vec![
+            ("total".to_string(), Aggregation::Sum("sales".to_string())),
+            ("n".to_string(), Aggregation::Count),
+        ];
+        let out = group_summarize(&data, &["region".to_string(), "month".to_string()], &aggs).unwrap();
+        assert_eq!(out.headers, vec!["region", "month", "total", "n"]);
+        assert_eq!(out.rows, vec![
+            vec!["N", "1", "30", "2"],
+            vec!["S", "1", "5", "2"],
+            vec!["N", "2", "7", "1"],
+        ]);
+    }
+
+    #[test]
+    fn test_group_summarize_errors() {
+        let data = PlotData::new(vec!["g".to_string(), "v".to_string()], vec![vec!["a".to_string(), "x".to_string()]]);
+        let aggs = vec![("m".to_string(), Aggregation::Mean("v".to_string()))];
+        assert!(group_summarize(&data, &["g".to_string()], &aggs).is_err());
+        assert!(group_summarize(&data, &["missing".to_string()], &aggs).is_err());
+    }
+}
@@ -1,35 +1,46 @@
 use anyhow::Result;
-use crate::parser::ast::{PlotSpec, Layer, Aesthetics, AestheticValue};
-use crate::data::PlotData;
+use crate::parser::ast::{PlotSpec, Layer, Aesthetics, AestheticValue, BarLayer, ColorScale, PointLayer, Stat, StatOutput};
+use crate::data::{find_col_index, PlotData};
 use crate::ir::{ResolvedSpec, ResolvedLayer, ResolvedAesthetics, ResolvedFacet};
+use crate::theme_resolve::{merge_themes, parse_color, theme_get};
 
 /// Resolve all aesthetic mappings for the entire plot
 pub fn resolve_plot_aesthetics(
     spec: &PlotSpec,
-    _data: &PlotData,
+    data: &PlotData,
 ) -> Result<ResolvedSpec> {
     // 0. Resolve global aesthetics (simple clone now)
     let resolved_aes = spec.aesthetics.clone();
 
     // 1. Resolve Facet (if any)
-    let facet = if let Some(f) = &spec.facet {
-        Some(ResolvedFacet {
-            col: f.by.clone(),
-            ncol: f.ncol,
-            scales: f.scales.clone(),
-        })
-    } else {
-        None
-    };
+    let facet = spec.facet.as_ref().map(|f| ResolvedFacet {
+        col: f.by.clone(),
+        ncol: f.ncol,
+        scales: f.scales.clone(),
+        strip_position: f.strip_position,
+        space: f.space,
+    });
 
     // 2. Resolve layers
     let mut layers = Vec::new();
+    // x column of the latest histogram(stat_output_as: "binned"), whose bins later layers reuse
+    let mut binned_x: Option<String> = None;
     for layer in &spec.layers {
         // Layer variables are already resolved by preprocessor
         // Just resolve aesthetics
-        let aesthetics = resolve_layer_aesthetics(layer, &resolved_aes)?;
+        let layer = default_bar_stat(layer, &resolved_aes);
+        let mut aesthetics = resolve_layer_aesthetics(&layer, &resolved_aes, binned_x.as_deref())?;
+        if let Some(col) = literal_color_column(&layer, aesthetics.color.as_deref(), spec, data)? {
+            aesthetics.color = None;
+            aesthetics.colors = Some(col);
+        }
+        let inherits_binned = inherits_binned(&layer, &aesthetics.x_col, binned_x.as_deref());
+        if let Layer::Bar(BarLayer { stat_output_as: Some(StatOutput::Binned), .. }) = &layer {
+            binned_x = Some(aesthetics.x_col.clone());
+        }
         layers.push(ResolvedLayer {
-            original_layer: layer.clone(),
+            original_layer: layer,
+            inherits_binned,
             aesthetics,
         });
     }
@@ -37,24 +48,80 @@ pub fn resolve_plot_aesthetics(
     // 3. Resolve labels (simple clone now)
     let labels = spec.labels.clone().unwrap_or_default();
 
+    let coord = spec.coord.clone();
+
     Ok(ResolvedSpec {
         layers,
         facet,
-        coord: spec.coord.clone(),
+        coord,
         labels,
-        theme: spec.theme.clone().unwrap_or_default(),
+        // Plot-specific theme is layered over the global default (see theme_set)
+        theme: match &spec.theme {
+            Some(t) => merge_themes(theme_get(), t.clone()),
+            None => theme_get(),
+        },
         x_scale_spec: spec.x_scale.clone(),
         y_scale_spec: spec.y_scale.clone(),
+        color_scale: spec.color_scale.clone(),
+        linetype_values: spec.linetype_values.clone(),
+        fill_scale: spec.fill_scale.clone(),
+        identity_scales: spec.identity_scales,
     })
 }
 
+/// A bar without a y aesthetic counts rows per x value (like ggplot2's geom_bar)
+fn default_bar_stat(layer: &Layer, global_aes: &Option<Aesthetics>) -> Layer {
+    match layer {
+        Layer::Bar(b) if b.stat == Stat::Identity
+            && !b.require_y
+            && b.y.is_none()
+            && global_aes.as_ref().and_then(|a| a.y.as_ref()).is_none() =>
+        {
+            Layer::Bar(BarLayer { stat: Stat::Count, ..b.clone() })
+        }
+        _ => layer.clone(),
+    }
+}
+
+/// bar(fill: col) | scale_fill_identity(): each bar takes its row's color from the column
+/// instead of grouping by it (identity bars only, where rows map 1:1 to bars). `color` is the
+/// layer's resolved color column, falling back to the global aes(color: ...).
+fn literal_color_column(layer: &Layer, color: Option<&str>, spec: &PlotSpec, data: &PlotData) -> Result<Option<String>> {
+    let (Layer::Bar(BarLayer { stat: Stat::Identity, .. }), Some(col)) = (layer, color) else {
+        return Ok(None);
+    };
+    if spec.color_scale != Some(ColorScale::Identity) {
+        return Ok(None);
+    }
+    let Ok(idx) = find_col_index(&data.headers, col) else { return Ok(None) };
+    if let Some(bad) = data.rows.iter().filter_map(|row| row.get(idx)).find(|v| parse_color(v.trim()).is_none()) {
+        anyhow::bail!("scale_fill_identity(): '{}' in column '{}' is not a color name or hex value", bad, col);
+    }
+    Ok(Some(col.to_string()))
+}
+
+/// Whether a layer takes the shared histogram bins: any non-bar layer on the binned x column
+fn inherits_binned(layer: &Layer, x_col: &str, binned_x: Option<&str>) -> bool {
+    binned_x == Some(x_col) && !matches!(layer, Layer::Bar(_))
+}
+
+/// (xmin, xmax) columns of a horizontal pointrange(), from the layer or the global aes()
+fn horizontal_range(layer: &Layer, global_aes: &Option<Aesthetics>) -> Option<(String, String)> {
+    let Layer::Point(PointLayer { range: Some(range), .. }) = layer else { return None };
+    let global = global_aes.as_ref();
+    let xmin = range.xmin.clone().or_else(|| global.and_then(|a| a.xmin.clone()))?;
+    let xmax = range.xmax.clone().or_else(|| global.and_then(|a| a.xmax.clone()))?;
+    Some((xmin, xmax))
+}
+
 /// Resolve all aesthetic mappings for a single layer (layer-specific + global)
 fn resolve_layer_aesthetics(
     layer: &Layer,
     global_aes: &Option<Aesthetics>,
+    binned_x: Option<&str>,
 ) -> Result<ResolvedAesthetics> {
     // Resolve x and y (required)
-    let (x_col, y_col) = resolve_positional(layer, global_aes)?;
+    let (x_col, y_col) = resolve_positional(layer, global_aes, binned_x)?;
 
     // Resolve color mapping
     let color = match layer {
@@ -64,6 +131,8 @@ fn resolve_layer_aesthetics(
         Layer::Ribbon(r) => extract_mapped_string(&r.color),
         Layer::Boxplot(b) => extract_mapped_string(&b.color),
         Layer::Violin(v) => extract_mapped_string(&v.color),
+        Layer::Text(t) => extract_mapped_string(&t.color),
+        Layer::Tile(_) | Layer::Hexbin(_) => None, // Colored by fill, through the fill scale
     }
     .or_else(|| global_aes.as_ref().and_then(|a| a.color.clone()));
 
@@ -75,6 +144,7 @@ fn resolve_layer_aesthetics(
         Layer::Ribbon(_) => None,
         Layer::Boxplot(b) => extract_mapped_string_from_f64(&b.width),
         Layer::Violin(v) => extract_mapped_string_from_f64(&v.width),
+        Layer::Tile(_) | Layer::Hexbin(_) | Layer::Text(_) => None,
     }
     .or_else(|| global_aes.as_ref().and_then(|a| a.size.clone()));
 
@@ -93,36 +163,88 @@ fn resolve_layer_aesthetics(
         Layer::Ribbon(r) => extract_mapped_string_from_f64(&r.alpha),
         Layer::Boxplot(b) => extract_mapped_string_from_f64(&b.alpha),
         Layer::Violin(v) => extract_mapped_string_from_f64(&v.alpha),
+        Layer::Tile(_) | Layer::Hexbin(_) | Layer::Text(_) => None,
     }
     .or_else(|| global_aes.as_ref().and_then(|a| a.alpha.clone()));
 
+    // Resolve linetype mapping (line only)
+    let linetype = match layer {
+        Layer::Line(l) => extract_mapped_string(&l.linetype),
+        _ => None,
+    }
+    .or_else(|| global_aes.as_ref().and_then(|a| a.linetype.clone()));
+
     // Resolve ymin/ymax
     let ymin_col = match layer {
         Layer::Ribbon(r) => r.ymin.clone(),
+        Layer::Point(PointLayer { range: Some(range), .. }) => range.ymin.clone(),
         _ => None,
     }
     .or_else(|| global_aes.as_ref().and_then(|a| a.ymin.clone()));
 
     let ymax_col = match layer {
         Layer::Ribbon(r) => r.ymax.clone(),
+        Layer::Point(PointLayer { range: Some(range), .. }) => range.ymax.clone(),
         _ => None,
     }
     .or_else(|| global_aes.as_ref().and_then(|a| a.ymax.clone()));
 
+    // Horizontal pointrange()/crossbar(): the range runs along x at each y; the compiler draws it sideways
+    let geom = if matches!(layer, Layer::Point(PointLayer { crossbar: true, .. })) { "crossbar" } else { "pointrange" };
+    let (xmin_col, xmax_col) = match horizontal_range(layer, global_aes) {
+        Some((xmin, xmax)) => {
+            if y_col.is_none() {
+                anyhow::bail!("{}(xmin:, xmax:) requires a y aesthetic", geom);
+            }
+            (Some(xmin), Some(xmax))
+        }
+        None => (None, None),
+    };
+    if matches!(layer, Layer::Point(PointLayer { range: Some(_), .. })) && xmin_col.is_none() && (ymin_col.is_none() || ymax_col.is_none()) {
+        anyhow::bail!("{}() requires ymin and ymax (or xmin and xmax) aesthetics", geom);
+    }
+    if matches!(layer.stat(), Stat::Segment { xend: None, .. } | Stat::Segment { yend: None, .. }) {
+        anyhow::bail!("segment() requires xend and yend columns");
+    }
+
+    let fill = match layer {
+        Layer::Tile(t) => extract_mapped_string(&t.fill),
+        _ => None,
+    };
+
+    // Label column (text only); a fixed label is read from the layer itself
+    let label = match layer {
+        Layer::Text(t) => match &t.label {
+            Some(AestheticValue::Mapped(col)) => Some(col.clone()),
+            Some(AestheticValue::Fixed(_)) => None,
+            None => match global_aes.as_ref().and_then(|a| a.label.clone()) {
+                Some(col) => Some(col),
+                None => anyhow::bail!("text() requires a label (use aes(label: ...) or text(label: ...))"),
+            },
+        },
+        _ => None,
+    };
+
     Ok(ResolvedAesthetics {
         x_col,
         y_col,
         ymin_col,
         ymax_col,
+        xmin_col,
+        xmax_col,
         color,
         size,
         shape,
         alpha,
+        linetype,
+        fill,
+        label,
+        colors: None,
     })
 }
 
 /// Resolve x and y aesthetics
-fn resolve_positional(layer: &Layer, global_aes: &Option<Aesthetics>) -> Result<(String, Option<String>)> {
+fn resolve_positional(layer: &Layer, global_aes: &Option<Aesthetics>, binned_x: Option<&str>) -> Result<(String, Option<String>)> {
     let (x_override, y_override) = match layer {
         Layer::Line(l) => (l.x.as_ref(), l.y.as_ref()),
         Layer::Point(p) => (p.x.as_ref(), p.y.as_ref()),
@@ -130,6 +252,9 @@ fn resolve_positional(layer: &Layer, global_aes: &Option<Aesthetics>) -> Result<
         Layer::Ribbon(r) => (r.x.as_ref(), None), // Ribbon uses ymin/ymax primarily
         Layer::Boxplot(b) => (b.x.as_ref(), b.y.as_ref()),
         Layer::Violin(v) => (v.x.as_ref(), v.y.as_ref()),
+        Layer::Tile(t) => (t.x.as_ref(), t.y.as_ref()),
+        Layer::Hexbin(h) => (h.x.as_ref(), h.y.as_ref()),
+        Layer::Text(t) => (t.x.as_ref(), t.y.as_ref()),
     };
 
     // Get x column
@@ -137,6 +262,8 @@ fn resolve_positional(layer: &Layer, global_aes: &Option<Aesthetics>) -> Result<
         x.clone()
     } else if let Some(ref aes) = global_aes {
         aes.x.clone()
+    } else if matches!(layer.stat(), Stat::Abline { .. }) {
+        String::new() // abline() takes its x range from the other layers
     } else {
         anyhow::bail!("No x aesthetic specified (use aes(x: ..., y: ...) or layer-level x: ...)");
     };
@@ -154,12 +281,30 @@ fn resolve_positional(layer: &Layer, global_aes: &Option<Aesthetics>) -> Result<
     // Validation: Check if y is required but missing
     if y_col.is_none() {
         match layer {
-            Layer::Bar(b) if matches!(b.stat, crate::parser::ast::Stat::Bin { .. } | crate::parser::ast::Stat::Count) => {
+            _ if inherits_binned(layer, &x_col, binned_x) => {
+                // Allowed (y is the bin count)
+            },
+            Layer::Bar(b) if b.require_y => {
+                anyhow::bail!("col() requires a y aesthetic (use bar() to count rows per x value)");
+            },
+            Layer::Bar(b) if matches!(b.stat, Stat::Bin { .. } | Stat::Count | Stat::Prop) => {
                 // Allowed
             },
             Layer::Ribbon(_) => {
                 // Allowed (uses ymin/ymax)
             },
+            Layer::Line(l) if matches!(l.stat, Stat::Ecdf { .. }) => {
+                // Allowed (y is the cumulative proportion)
+            },
+            Layer::Line(l) if matches!(l.stat, Stat::Density { .. }) => {
+                // Allowed (y is the estimated density)
+            },
+            Layer::Line(l) if matches!(l.stat, Stat::Abline { .. }) => {
+                // Allowed (y comes from slope and intercept)
+            },
+            Layer::Line(l) if matches!(l.stat, Stat::Rug { .. }) => {
+                // Allowed (ticks only use x)
+            },
             _ => {
                  anyhow::bail!("No y aesthetic specified (use aes(x: ..., y: ...) or layer-level y: ...)");
             }
@@ -208,8 +353,12 @@ mod tests {
                 size: None,
                 shape: None,
                 alpha: None,
+                linetype: None,
                 ymin: None,
                 ymax: None,
+                xmin: None,
+                xmax: None,
+                label: None,
             }),
             layers: vec![Layer::Line(LineLayer::default())],
             labels: Some(crate::parser::ast::Labels::default()),
@@ -218,6 +367,11 @@ mod tests {
             theme: None,
             x_scale: None,
             y_scale: None,
+            steps: vec![],
+            color_scale: None,
+            linetype_values: None,
+            fill_scale: None,
+            identity_scales: Default::default(),
         };
         let data = make_data();
         let resolved = resolve_plot_aesthetics(&spec, &data).unwrap();
@@ -236,8 +390,12 @@ mod tests {
                 size: None,
                 shape: None,
                 alpha: None,
+                linetype: None,
                 ymin: None,
                 ymax: None,
+                xmin: None,
+                xmax: None,
+                label: None,
             }),
             layers: vec![Layer::Point(PointLayer {
                 x: None,
@@ -250,6 +408,11 @@ mod tests {
             theme: None,
             x_scale: None,
             y_scale: None,
+            steps: vec![],
+            color_scale: None,
+            linetype_values: None,
+            fill_scale: None,
+            identity_scales: Default::default(),
         };
         let data = make_data();
         let resolved = resolve_plot_aesthetics(&spec, &data).unwrap();
@@ -267,12 +430,217 @@ mod tests {
             theme: None,
             x_scale: None,
             y_scale: None,
+            steps: vec![],
+            color_scale: None,
+            linetype_values: None,
+            fill_scale: None,
+            identity_scales: Default::default(),
         };
         let data = make_data();
         let res = resolve_plot_aesthetics(&spec, &data);
         assert!(res.is_err());
     }
 
+    #[test]
+    fn test_resolve_bar_without_y_defaults_to_count() {
+        use crate::parser::ast::BarLayer;
+        let spec = PlotSpec {
+            aesthetics: Some(Aesthetics {
+                x: "g".to_string(),
+                y: None,
+                color: None,
+                size: None,
+                shape: None,
+                alpha: None,
+                linetype: None,
+                ymin: None,
+                ymax: None,
+                xmin: None,
+                xmax: None,
+                label: None,
+            }),
+            layers: vec![Layer::Bar(BarLayer::default())],
+            labels: None,
+            facet: None,
+            coord: None,
+            theme: None,
+            x_scale: None,
+            y_scale: None,
+            steps: vec![],
+            color_scale: None,
+            linetype_values: None,
+            fill_scale: None,
+            identity_scales: Default::default(),
+        };
+        let resolved = resolve_plot_aesthetics(&spec, &make_data()).unwrap();
+        assert_eq!(resolved.layers[0].original_layer.stat(), &Stat::Count);
+        assert_eq!(resolved.layers[0].aesthetics.y_col, None);
+    }
+
+    #[test]
+    fn test_resolve_col_requires_y() {
+        use crate::parser::ast::BarLayer;
+        let spec = PlotSpec {
+            aesthetics: Some(Aesthetics {
+                x: "g".to_string(),
+                y: None,
+                color: None,
+                size: None,
+                shape: None,
+                alpha: None,
+                linetype: None,
+                ymin: None,
+                ymax: None,
+                xmin: None,
+                xmax: None,
+                label: None,
+            }),
+            layers: vec![Layer::Bar(BarLayer { require_y: true, ..Default::default() })],
+            labels: None,
+            facet: None,
+            coord: None,
+            theme: None,
+            x_scale: None,
+            y_scale: None,
+            steps: vec![],
+            color_scale: None,
+            linetype_values: None,
+            fill_scale: None,
+            identity_scales: Default::default(),
+        };
+        let err = resolve_plot_aesthetics(&spec, &make_data()).unwrap_err();
+        assert!(err.to_string().contains("col()"));
+    }
+
+    #[test]
+    fn test_resolve_binned_histogram_supplies_y() {
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: x) | histogram(bins: 5) | smooth()").unwrap();
+        let err = resolve_plot_aesthetics(&spec, &make_data()).unwrap_err();
+        assert!(err.to_string().contains("No y aesthetic"));
+
+        let (spec, _) = crate::parser::parse_plot_spec(r#"aes(x: x) | histogram(bins: 5, stat_output_as: "binned") | smooth()"#).unwrap();
+        let resolved = resolve_plot_aesthetics(&spec, &make_data()).unwrap();
+        assert!(!resolved.layers[0].inherits_binned);
+        assert!(resolved.layers[1].inherits_binned);
+        assert_eq!(resolved.layers[1].aesthetics.y_col, None);
+    }
+
+    #[test]
+    fn test_resolve_bar_fill_literal_colors() {
+        let data = |g: [&str; 2]| PlotData {
+            headers: vec!["x".to_string(), "y".to_string(), "g".to_string()],
+            rows: g.iter().enumerate().map(|(i, g)| vec![i.to_string(), "1".to_string(), g.to_string()]).collect(),
+        };
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: x, y: y) | bar(fill: g) | point(color: g) | scale_fill_identity()").unwrap();
+
+        let resolved = resolve_plot_aesthetics(&spec, &data(["red", "#00ff00"])).unwrap();
+        assert_eq!(resolved.layers[0].aesthetics.colors.as_deref(), Some("g"));
+        assert_eq!(resolved.layers[0].aesthetics.color, None);
+        // Only bars take literal colors; points still group by the column
+        assert_eq!(resolved.layers[1].aesthetics.color.as_deref(), Some("g"));
+
+        let err = resolve_plot_aesthetics(&spec, &data(["red", "south"])).unwrap_err();
+        assert!(err.to_string().contains("'south'"));
+
+        // Without the identity scale, color-like values still group through the palette
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: x, y: y) | bar(fill: g)").unwrap();
+        let resolved = resolve_plot_aesthetics(&spec, &data(["red", "blue"])).unwrap();
+        assert_eq!(resolved.layers[0].aesthetics.colors, None);
+        assert_eq!(resolved.layers[0].aesthetics.color.as_deref(), Some("g"));
+
+        // The column matches case-insensitively, also through the global aes(color: ...)
+        for src in ["aes(x: x, y: y) | bar(fill: G) | scale_fill_identity()", "aes(x: x, y: y, color: G) | bar() | scale_fill_identity()"] {
+            let (spec, _) = crate::parser::parse_plot_spec(src).unwrap();
+            let resolved = resolve_plot_aesthetics(&spec, &data(["red", "blue"])).unwrap();
+            assert_eq!(resolved.layers[0].aesthetics.colors.as_deref(), Some("G"), "{}", src);
+            assert_eq!(resolved.layers[0].aesthetics.color, None, "{}", src);
+            assert!(resolve_plot_aesthetics(&spec, &data(["red", "south"])).is_err(), "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_resolve_text_label() {
+        use crate::parser::ast::TextLayer;
+        let (mut spec, _) = crate::parser::parse_plot_spec("aes(x: x, y: y, label: g) | text() | text(label: y) | text(label: \"fixed\")").unwrap();
+        let resolved = resolve_plot_aesthetics(&spec, &make_data()).unwrap();
+        let labels: Vec<Option<&str>> = resolved.layers.iter().map(|l| l.aesthetics.label.as_deref()).collect();
+        assert_eq!(labels, vec![Some("g"), Some("y"), None]);
+
+        spec.aesthetics.as_mut().unwrap().label = None;
+        spec.layers = vec![Layer::Text(TextLayer::default())];
+        let err = resolve_plot_aesthetics(&spec, &make_data()).unwrap_err();
+        assert!(err.to_string().contains("label"));
+    }
+
+    #[test]
+    fn test_resolve_pointrange() {
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: x, y: y) | pointrange(ymin: g, ymax: y)").unwrap();
+        let resolved = resolve_plot_aesthetics(&spec, &make_data()).unwrap();
+        let aes = &resolved.layers[0].aesthetics;
+        assert_eq!((aes.ymin_col.as_deref(), aes.ymax_col.as_deref()), (Some("g"), Some("y")));
+        assert_eq!(resolved.coord, None);
+
+        // Horizontal: the range runs along x, the axes and coord are left alone
+        let (spec, _) = crate::parser::parse_plot_spec("aes(y: g, x: x, xmin: x, xmax: y) | pointrange()").unwrap();
+        let resolved = resolve_plot_aesthetics(&spec, &make_data()).unwrap();
+        let aes = &resolved.layers[0].aesthetics;
+        assert_eq!((aes.x_col.as_str(), aes.y_col.as_deref()), ("x", Some("g")));
+        assert_eq!((aes.xmin_col.as_deref(), aes.xmax_col.as_deref()), (Some("x"), Some("y")));
+        assert_eq!(resolved.coord, None);
+
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: x, y: y) | pointrange()").unwrap();
+        let err = resolve_plot_aesthetics(&spec, &make_data()).unwrap_err();
+        assert!(err.to_string().contains("ymin and ymax"));
+    }
+
+    #[test]
+    fn test_resolve_ribbon_uses_global_ymin_ymax() {
+        use crate::parser::ast::RibbonLayer;
+        let spec = PlotSpec {
+            aesthetics: Some(Aesthetics {
+                x: "x".to_string(),
+                y: Some("y".to_string()),
+                color: None,
+                size: None,
+                shape: None,
+                alpha: None,
+                linetype: None,
+                ymin: Some("lo".to_string()),
+                ymax: Some("hi".to_string()),
+                xmin: None,
+                xmax: None,
+                label: None,
+            }),
+            layers: vec![
+                Layer::Ribbon(RibbonLayer::default()),
+                Layer::Ribbon(RibbonLayer { ymin: Some("g".to_string()), ..Default::default() }),
+                Layer::Line(LineLayer::default()),
+            ],
+            labels: Some(crate::parser::ast::Labels::default()),
+            facet: None,
+            coord: None,
+            theme: None,
+            x_scale: None,
+            y_scale: None,
+            steps: vec![],
+            color_scale: None,
+            linetype_values: None,
+            fill_scale: None,
+            identity_scales: Default::default(),
+        };
+        let data = PlotData {
+            headers: vec!["x".to_string(), "y".to_string(), "g".to_string(), "lo".to_string(), "hi".to_string()],
+            rows: vec![],
+        };
+        let resolved = resolve_plot_aesthetics(&spec, &data).unwrap();
+        let cols: Vec<_> = resolved.layers.iter()
+            .map(|l| (l.aesthetics.x_col.as_str(), l.aesthetics.ymin_col.as_deref(), l.aesthetics.ymax_col.as_deref()))
+            .collect();
+        // Layer-level ymin wins over the global one; the line shares x with the ribbons
+        assert_eq!(cols, vec![("x", Some("lo"), Some("hi")), ("x", Some("g"), Some("hi")), ("x", Some("lo"), Some("hi"))]);
+        assert_eq!(resolved.layers[2].aesthetics.y_col.as_deref(), Some("y"));
+    }
+
     #[test]
     fn test_resolve_facet() {
         let spec = PlotSpec {
@@ -283,8 +651,12 @@ mod tests {
                 size: None,
                 shape: None,
                 alpha: None,
+                linetype: None,
                 ymin: None,
                 ymax: None,
+                xmin: None,
+                xmax: None,
+                label: None,
             }),
             layers: vec![],
             labels: Some(crate::parser::ast::Labels::default()),
@@ -292,15 +664,61 @@ mod tests {
                 by: "g".to_string(),
                 ncol: None,
                 scales: crate::parser::ast::FacetScales::Fixed,
+                strip_position: crate::parser::ast::StripPosition::Top,
+                space: crate::parser::ast::FacetSpace::Fixed,
             }),
             coord: None,
             theme: None,
             x_scale: None,
             y_scale: None,
+            steps: vec![],
+            color_scale: None,
+            linetype_values: None,
+            fill_scale: None,
+            identity_scales: Default::default(),
         };
         let data = make_data();
         let resolved = resolve_plot_aesthetics(&spec, &data).unwrap();
         assert!(resolved.facet.is_some());
         assert_eq!(resolved.facet.unwrap().col, "g");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_resolve_merges_global_theme() {
+        use crate::parser::ast::{Theme, ThemeElement};
+        crate::theme_resolve::theme_set(Theme::classic());
+        let spec = PlotSpec {
+            aesthetics: Some(Aesthetics {
+                x: "x".to_string(),
+                y: Some("y".to_string()),
+                color: None,
+                size: None,
+                shape: None,
+                alpha: None,
+                linetype: None,
+                ymin: None,
+                ymax: None,
+                xmin: None,
+                xmax: None,
+                label: None,
+            }),
+            layers: vec![Layer::Line(LineLayer::default())],
+            labels: None,
+            facet: None,
+            coord: None,
+            theme: Some(Theme { panel_grid_major: ThemeElement::Inherit, axis_line: ThemeElement::Blank, ..Default::default() }),
+            x_scale: None,
+            y_scale: None,
+            steps: vec![],
+            color_scale: None,
+            linetype_values: None,
+            fill_scale: None,
+            identity_scales: Default::default(),
+        };
+        let resolved = resolve_plot_aesthetics(&spec, &make_data()).unwrap();
+        crate::theme_resolve::theme_set(Theme::default());
+        // Plot theme overrides axis_line; global classic theme supplies the blank grid
+        assert_eq!(resolved.theme.axis_line, ThemeElement::Blank);
+        assert_eq!(resolved.theme.panel_grid_major, ThemeElement::Blank);
+    }
+}
@@ -1,7 +1,17 @@
 use anyhow::Result;
 use crate::parser::ast::PlotSpec;
 use crate::data::PlotData;
-use crate::{resolve, transform, scale, compiler, graph, RenderOptions};
+use crate::{resolve, transform, scale, compiler, graph, reshape, validate, OutputFormat, RenderOptions};
+
+/// Callback receiving --progress messages; `None` turns every report into a no-op
+pub(crate) type Progress<'a> = Option<&'a dyn Fn(String)>;
+
+/// Send `message` to the progress callback, if there is one
+pub(crate) fn report(progress: Progress, message: impl FnOnce() -> String) {
+    if let Some(callback) = progress {
+        callback(message());
+    }
+}
 
 /// Render a plot specification to PNG bytes using the Ideal GoG Pipeline
 pub fn render_plot(
@@ -9,29 +19,83 @@ pub fn render_plot(
     data: PlotData,
     options: RenderOptions,
 ) -> Result<Vec<u8>> {
+    let format = options.format.clone();
+    let mut outputs = render_plot_formats(spec, data, options, &[format])?;
+    Ok(outputs.remove(0))
+}
+
+/// Render a plot specification once and encode the scene in each of `formats` (e.g. a PNG
+/// preview and an SVG), returning the bytes in the same order
+pub fn render_plot_formats(
+    spec: PlotSpec,
+    data: PlotData,
+    options: RenderOptions,
+    formats: &[OutputFormat],
+) -> Result<Vec<Vec<u8>>> {
     // Check for empty data (maintain legacy behavior for tests)
     if data.rows.is_empty() {
         anyhow::bail!("Plot requires at least one data row");
     }
 
+    let print_progress = |message: String| eprintln!("{}", message);
+    let progress: Progress = if options.progress { Some(&print_progress) } else { None };
+
+    // PHASE 0: RESHAPING
+    // Apply data pipeline steps (pivot_wider, join, ...) to the input table.
+    let data = reshape::apply_steps(data, &spec.steps, options.data_dir.as_deref())?;
+
     // PHASE 1: RESOLUTION
     // Resolve all aesthetics for all layers once.
     // Variables are substituted during resolution.
     let resolved_spec = resolve::resolve_plot_aesthetics(&spec, &data)?;
 
+    // Date axes (scale_x_date) hold days since 1970-01-01
+    let data = scale::dates_to_days(&resolved_spec, data)?;
+
+    // Log axes cannot show values <= 0
+    validate::check_log_scales(&resolved_spec, &data)?;
+
+    // Non-fatal checks (small samples, crowded axes, ...) before any heavy lifting
+    let warnings = validate::validate_spec(&resolved_spec, &data);
+    if options.strict && !warnings.is_empty() {
+        let messages: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+        anyhow::bail!("Validation failed (--strict):\n  {}", messages.join("\n  "));
+    }
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
     // PHASE 2: TRANSFORMATION
     // Apply stats (binning) and positions (stacking/dodging).
     // Returns RenderData with normalized geometry points.
-    let render_data = transform::apply_transformations(&resolved_spec, &data)?;
+    let render_data = transform::apply_transformations_with_progress(&resolved_spec, &data, progress)?;
+    if options.dump_ir {
+        dump_json("RenderData", &render_data)?;
+    }
 
     // 3. Scaling
     let scales = scale::build_scales(&render_data, &resolved_spec)?;
 
     // PHASE 4: COMPILATION (MAPPING)
     // Convert data units to drawing commands.
-    let scene = compiler::compile_geometry(render_data, scales, &resolved_spec, &options)?;
+    let scene = compiler::compile_geometry_with_progress(render_data, scales, &resolved_spec, &options, progress)?;
+    if options.dump_scene {
+        dump_json("SceneGraph", &scene)?;
+    }
 
     // PHASE 5: RENDERING
-    // Execute drawing commands on the canvas.
-    graph::Canvas::execute(scene, &options)
+    // Execute drawing commands on the canvas, once per output format.
+    formats.iter()
+        .map(|format| {
+            let options = RenderOptions { format: format.clone(), ..options.clone() };
+            graph::Canvas::execute_with_progress(scene.clone(), &options, progress)
+        })
+        .collect()
+}
+
+/// Print a pipeline stage as pretty JSON to stderr (--dump-ast / --dump-ir / --dump-scene)
+pub fn dump_json(stage: &str, value: &impl serde::Serialize) -> Result<()> {
+    let json = serde_json::to_string_pretty(value)?;
+    eprintln!("=== {} ===\n{}", stage, json);
+    Ok(())
 }
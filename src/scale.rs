@@ -1,6 +1,9 @@
-use anyhow::Result;
+use std::collections::HashMap;
+use anyhow::{bail, Result};
+use chrono::{Datelike, NaiveDate};
+use crate::data::PlotData;
 use crate::ir::{RenderData, ScaleSystem, PanelScales, Scale, ResolvedSpec};
-use crate::parser::ast::{FacetScales, ScaleType};
+use crate::parser::ast::{AxisScale, FacetScales, Layer, ScaleType, Stat, TickFormat};
 
 /// Build the scale system for the plot
 pub fn build_scales(data: &RenderData, spec: &ResolvedSpec) -> Result<ScaleSystem> {
@@ -17,6 +20,16 @@ pub fn build_scales(data: &RenderData, spec: &ResolvedSpec) -> Result<ScaleSyste
 
     // 3. Resolve final domains
     let mut final_scales = Vec::new();
+    // position "fill", stat "prop" and ecdf() produce proportions; the fixed 0-1 axis (and the
+    // percent labels) only apply when every layer does, raw layers keep their own range
+    let is_proportion = |l: &Layer| match l {
+        Layer::Bar(b) => b.position.is_fill() || matches!(b.stat, Stat::Prop),
+        _ => matches!(l.stat(), Stat::Ecdf { .. }),
+    };
+    let proportions_only = !spec.layers.is_empty() && spec.layers.iter().all(|l| is_proportion(&l.original_layer));
+    let has_prop_bars = spec.layers.iter().any(|l| {
+        matches!(&l.original_layer, Layer::Bar(b) if matches!(b.stat, Stat::Prop))
+    });
     
     // Pre-calculate globals if needed
     let global_x = if matches!(scales_mode, FacetScales::Fixed | FacetScales::FreeY) {
@@ -53,14 +66,25 @@ pub fn build_scales(data: &RenderData, spec: &ResolvedSpec) -> Result<ScaleSyste
                     if matches!(s.scale_type, ScaleType::Reverse) { (n - 0.5, -0.5) } else { (-0.5, n - 0.5) }
                 } else { (-0.5, n - 0.5) },
                 is_categorical: true,
-                categories: x_mm.categories,
+                is_log: false,
+                categories: display_categories(x_mm.categories, spec.x_scale_spec.as_ref()),
+                category_positions: x_mm.category_positions,
+                is_percent: false,
+                breaks_n: None,
+                breaks_width: None,
+                labels: TickFormat::Auto,
             }
         } else {
             // Continuous Scale
+            let x_log = is_log(spec.x_scale_spec.as_ref());
             let (min, max) = if let Some(s) = &spec.x_scale_spec {
                 if let Some((lmin, lmax)) = s.limits { (lmin, lmax) }
+                else if x_log { pad_log_range(x_mm.min_positive, x_mm.max) }
                 else { pad_range(x_mm.min, x_mm.max) }
             } else { pad_range(x_mm.min, x_mm.max) };
+            if x_log && min <= 0.0 {
+                bail!("scale_x_log10() limits must be positive, got ({}, {})", min, max);
+            }
 
             Scale {
                 domain: (min, max),
@@ -68,15 +92,52 @@ pub fn build_scales(data: &RenderData, spec: &ResolvedSpec) -> Result<ScaleSyste
                     if matches!(s.scale_type, ScaleType::Reverse) { (max, min) } else { (min, max) }
                 } else { (min, max) },
                 is_categorical: false,
+                is_log: x_log,
                 categories: Vec::new(),
+                category_positions: Vec::new(),
+                is_percent: false,
+                breaks_n: spec.x_scale_spec.as_ref().and_then(|s| s.breaks_n),
+                breaks_width: spec.x_scale_spec.as_ref().and_then(|s| s.breaks_width),
+                labels: spec.x_scale_spec.as_ref().map(|s| s.labels.clone()).unwrap_or_default(),
             }
         };
 
         // Y-Axis
-        let (min, max) = if let Some(s) = &spec.y_scale_spec {
-            if let Some((lmin, lmax)) = s.limits { (lmin, lmax) }
-            else { pad_range(y_mm.min, y_mm.max) }
-        } else { pad_range(y_mm.min, y_mm.max) };
+        if y_mm.is_categorical {
+            // Categorical y, e.g. the tasks of a horizontal pointrange()
+            let n = y_mm.categories.len() as f64;
+            let reverse = spec.y_scale_spec.as_ref().is_some_and(|s| matches!(s.scale_type, ScaleType::Reverse));
+            final_scales.push(PanelScales {
+                x: x_scale,
+                y: Scale {
+                    domain: (0.0, n),
+                    range: if reverse { (n - 0.5, -0.5) } else { (-0.5, n - 0.5) },
+                    is_categorical: true,
+                    is_log: false,
+                    categories: display_categories(y_mm.categories, spec.y_scale_spec.as_ref()),
+                    category_positions: Vec::new(),
+                    is_percent: false,
+                    breaks_n: None,
+                    breaks_width: None,
+                    labels: TickFormat::Auto,
+                },
+            });
+            continue;
+        }
+        let y_log = is_log(spec.y_scale_spec.as_ref());
+        let (min, max) = if let Some((lmin, lmax)) = spec.y_scale_spec.as_ref().and_then(|s| s.limits) {
+            (lmin, lmax)
+        } else if y_log {
+            // Bars' zero baseline has no place on a log axis
+            pad_log_range(y_mm.min_positive, y_mm.max)
+        } else if proportions_only {
+            (0.0, 1.0)
+        } else {
+            pad_range(y_mm.min, y_mm.max)
+        };
+        if y_log && min <= 0.0 {
+            bail!("scale_y_log10() limits must be positive, got ({}, {})", min, max);
+        }
 
         let y_scale = Scale {
             domain: (min, max),
@@ -84,7 +145,13 @@ pub fn build_scales(data: &RenderData, spec: &ResolvedSpec) -> Result<ScaleSyste
                 if matches!(s.scale_type, ScaleType::Reverse) { (max, min) } else { (min, max) }
             } else { (min, max) },
             is_categorical: false,
+            is_log: y_log,
             categories: Vec::new(),
+            category_positions: Vec::new(),
+            is_percent: has_prop_bars && proportions_only,
+            breaks_n: spec.y_scale_spec.as_ref().and_then(|s| s.breaks_n),
+            breaks_width: spec.y_scale_spec.as_ref().and_then(|s| s.breaks_width),
+            labels: spec.y_scale_spec.as_ref().map(|s| s.labels.clone()).unwrap_or_default(),
         };
 
         final_scales.push(PanelScales {
@@ -100,14 +167,18 @@ pub fn build_scales(data: &RenderData, spec: &ResolvedSpec) -> Result<ScaleSyste
 struct MinMax {
     min: f64,
     max: f64,
+    min_positive: f64, // Smallest value > 0 (lower bound of a log axis), infinite if none
     is_categorical: bool,
     categories: Vec<String>,
+    category_positions: Vec<f64>, // Mosaic bar positions per category; empty = the index
 }
 
 fn calculate_min_max_x(panel: &crate::ir::PanelData) -> MinMax {
     let mut min = f64::INFINITY;
     let mut max = f64::NEG_INFINITY;
+    let mut min_positive = f64::INFINITY;
     let mut categories = Vec::new();
+    let mut category_positions = Vec::new();
     let mut is_cat = false;
 
     for layer in &panel.layers {
@@ -118,11 +189,22 @@ fn calculate_min_max_x(panel: &crate::ir::PanelData) -> MinMax {
                 if categories.is_empty() {
                     categories = cats.clone();
                 }
+                if category_positions.is_empty() {
+                    category_positions = group.x_category_positions.clone();
+                }
             }
             
-            for &val in &group.x {
-                if val < min { min = val; }
-                if val > max { max = val; }
+            // Tiles and hexagons extend half their width either side of x
+            let half_width = match &group.style {
+                crate::ir::RenderStyle::Tile(t) => t.width / 2.0,
+                crate::ir::RenderStyle::Hex(h) => h.width / 2.0,
+                _ => 0.0,
+            };
+            // segment() lines also reach their xend, horizontal ranges their xmin and xmax
+            for val in group.x.iter().chain(group.segment_ends.iter().map(|(x, _)| x)).chain(&group.x_min).chain(&group.x_max).copied() {
+                if val - half_width < min { min = val - half_width; }
+                if val + half_width > max { max = val + half_width; }
+                if val > 0.0 && val < min_positive { min_positive = val; }
             }
         }
     }
@@ -134,66 +216,73 @@ fn calculate_min_max_x(panel: &crate::ir::PanelData) -> MinMax {
         max = (categories.len().max(1) - 1) as f64;
     }
 
-    MinMax { min, max, is_categorical: is_cat, categories }
+    MinMax { min, max, min_positive, is_categorical: is_cat, categories, category_positions }
 }
 
 fn calculate_min_max_y(panel: &crate::ir::PanelData) -> MinMax {
     let mut min = f64::INFINITY;
     let mut max = f64::NEG_INFINITY;
-    
+    let mut min_positive = f64::INFINITY;
+    let mut include = |val: f64| {
+        if val < min { min = val; }
+        if val > max { max = val; }
+        if val > 0.0 && val < min_positive { min_positive = val; }
+    };
+
     // Helper to include 0 for bar charts
     let mut has_bars = false;
+    let mut categories = None;
 
     for layer in &panel.layers {
         for group in &layer.groups {
+            if categories.is_none() {
+                categories = group.y_categories.clone();
+            }
             // Check if bar layer
             if matches!(group.style, crate::ir::RenderStyle::Bar(_)) {
                 has_bars = true;
             }
 
-            // Check y (and y_start for stacked)
-            for &val in &group.y {
-                if val < min { min = val; }
-                if val > max { max = val; }
-            }
-            for &val in &group.y_start {
-                if val < min { min = val; }
-                if val > max { max = val; }
-            }
-            for &val in &group.y_min {
-                if val < min { min = val; }
-                if val > max { max = val; }
-            }
-            for &val in &group.y_max {
-                if val < min { min = val; }
-                if val > max { max = val; }
-            }
-            for outlier_set in &group.outliers {
-                for &val in outlier_set {
-                    if val < min { min = val; }
-                    if val > max { max = val; }
-                }
+            // Check y (and y_start for stacked), ranges and outliers
+            group.y.iter()
+                .chain(&group.y_start)
+                .chain(&group.y_min)
+                .chain(&group.y_max)
+                .chain(group.outliers.iter().flatten())
+                .chain(group.segment_ends.iter().map(|(_, y)| y))
+                .for_each(|&val| include(val));
+            // Residuals are drawn around a zero line
+            if !group.residuals.is_empty() {
+                include(0.0);
             }
+            group.residuals.iter().for_each(|&(_, val)| include(val));
         }
     }
-    
+
     if has_bars {
         // Bar charts always include 0
-        if min > 0.0 { min = 0.0; }
-        if max < 0.0 { max = 0.0; }
+        include(0.0);
     }
 
-    MinMax { min, max, is_categorical: false, categories: Vec::new() }
+    if let Some(categories) = categories {
+        let max = (categories.len().max(1) - 1) as f64;
+        return MinMax { min: 0.0, max, min_positive, is_categorical: true, categories, category_positions: Vec::new() };
+    }
+
+    MinMax { min, max, min_positive, is_categorical: false, categories: Vec::new(), category_positions: Vec::new() }
 }
 
 fn merge_ranges<'a, I>(iter: I) -> MinMax 
 where I: Iterator<Item = &'a MinMax> 
 {
-    let mut global = MinMax { min: f64::INFINITY, max: f64::NEG_INFINITY, is_categorical: false, categories: Vec::new() };
+    let mut global = MinMax { min: f64::INFINITY, max: f64::NEG_INFINITY, min_positive: f64::INFINITY, is_categorical: false, categories: Vec::new(), category_positions: Vec::new() };
+    // Category -> (sum, count) of its mosaic positions over the panels that place it
+    let mut positions: HashMap<&str, (f64, f64)> = HashMap::new();
     
     for local in iter {
         if local.min < global.min { global.min = local.min; }
         if local.max > global.max { global.max = local.max; }
+        if local.min_positive < global.min_positive { global.min_positive = local.min_positive; }
         if local.is_categorical {
             global.is_categorical = true;
             // Naive merge: if one has categories, take them.
@@ -201,8 +290,18 @@ where I: Iterator<Item = &'a MinMax>
             if global.categories.is_empty() {
                 global.categories = local.categories.clone();
             }
+            for (category, &position) in local.categories.iter().zip(&local.category_positions) {
+                let entry = positions.entry(category).or_default();
+                *entry = (entry.0 + position, entry.1 + 1.0);
+            }
         }
     }
+    // Each category's label sits at its mean position across panels (at its index where no panel places it)
+    if !positions.is_empty() {
+        global.category_positions = global.categories.iter().enumerate()
+            .map(|(i, c)| positions.get(c.as_str()).map_or(i as f64, |(sum, n)| sum / n))
+            .collect();
+    }
     
     // Handle empty case
     if global.min == f64::INFINITY { global.min = 0.0; global.max = 1.0; }
@@ -210,6 +309,161 @@ where I: Iterator<Item = &'a MinMax>
     global
 }
 
+fn is_log(scale: Option<&crate::parser::ast::AxisScale>) -> bool {
+    scale.is_some_and(|s| s.scale_type == ScaleType::Log10)
+}
+
+/// Pad a log axis by 5% of its width in decades (one decade either side of a single value)
+fn pad_log_range(min_positive: f64, max: f64) -> (f64, f64) {
+    if !min_positive.is_finite() || max <= 0.0 {
+        return (1.0, 10.0);
+    }
+    let (lo, hi) = (min_positive.log10(), max.log10());
+    if lo == hi {
+        (10f64.powf(lo - 1.0), 10f64.powf(hi + 1.0))
+    } else {
+        let padding = (hi - lo) * 0.05;
+        (10f64.powf(lo - padding), 10f64.powf(hi + padding))
+    }
+}
+
+/// Major ticks for a log10 axis: the powers of ten inside [min, max], or 1, 2, 5 x 10^k
+/// when the range spans less than two decades
+pub fn log10_breaks(min: f64, max: f64) -> Vec<f64> {
+    let powers: Vec<f64> = log_multiples(min, max, &[1.0]);
+    if powers.len() >= 2 { powers } else { trans_minor_breaks(min, max) }
+}
+
+/// Minor grid lines for a log10 axis at 1, 2, 5, 10, 20, 50, ... inside [min, max]
+pub fn trans_minor_breaks(min: f64, max: f64) -> Vec<f64> {
+    log_multiples(min, max, &[1.0, 2.0, 5.0])
+}
+
+/// `m x 10^k` for each multiplier m and every decade k overlapping [min, max]
+fn log_multiples(min: f64, max: f64, multipliers: &[f64]) -> Vec<f64> {
+    if !(min > 0.0 && max >= min && max.is_finite()) {
+        return Vec::new();
+    }
+    let (first, last) = (min.log10().floor() as i32, max.log10().ceil() as i32);
+    (first..=last.min(first + 300))
+        .flat_map(|k| multipliers.iter().map(move |m| m * 10f64.powi(k)))
+        // Tolerate rounding in 10^k at the range ends
+        .filter(|&v| v >= min * (1.0 - 1e-9) && v <= max * (1.0 + 1e-9))
+        .collect()
+}
+
+// =============================================================================
+// Date axes (scale_x_date / scale_y_date)
+// =============================================================================
+
+const EPOCH: NaiveDate = match NaiveDate::from_ymd_opt(1970, 1, 1) {
+    Some(date) => date,
+    None => unreachable!(),
+};
+
+/// Parse an ISO date ("2024-03-15"); a time part after 'T' or ' ' is ignored
+pub fn parse_date(s: &str) -> Option<NaiveDate> {
+    let s = s.trim();
+    let date = match s.char_indices().nth(10) {
+        Some((i, 'T' | ' ')) => &s[..i],
+        _ => s,
+    };
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Days since 1970-01-01: how a date axis stores its values
+pub fn date_to_days(date: NaiveDate) -> f64 {
+    (date - EPOCH).num_days() as f64
+}
+
+/// The date a (rounded) day count stands for
+pub fn days_to_date(days: f64) -> Option<NaiveDate> {
+    if !days.is_finite() || days.abs() > 1e8 {
+        return None;
+    }
+    EPOCH.checked_add_signed(chrono::Duration::days(days.round() as i64))
+}
+
+/// A date axis stores days since 1970-01-01, so the dates in its mapped columns are
+/// replaced by day counts before anything else reads them. Empty cells stay empty;
+/// any other value that is not a date is an error.
+pub fn dates_to_days(spec: &ResolvedSpec, mut data: PlotData) -> Result<PlotData> {
+    let is_date = |s: &Option<AxisScale>| s.as_ref().is_some_and(|s| matches!(s.labels, TickFormat::Date(_)));
+    let mut cols: Vec<(&str, &str)> = Vec::new();
+    if is_date(&spec.x_scale_spec) {
+        cols.extend(spec.layers.iter().map(|l| ("x", l.aesthetics.x_col.as_str())));
+    }
+    if is_date(&spec.y_scale_spec) {
+        cols.extend(spec.layers.iter()
+            .flat_map(|l| [&l.aesthetics.y_col, &l.aesthetics.ymin_col, &l.aesthetics.ymax_col])
+            .filter_map(|c| c.as_deref())
+            .map(|c| ("y", c)));
+    }
+
+    let mut converted = Vec::new();
+    for (axis, col) in cols {
+        let Some(idx) = data.headers.iter().position(|h| h == col) else { continue };
+        if converted.contains(&idx) {
+            continue;
+        }
+        converted.push(idx);
+        for (row, values) in data.rows.iter_mut().enumerate() {
+            let Some(value) = values.get_mut(idx) else { continue };
+            if value.trim().is_empty() {
+                continue;
+            }
+            let Some(date) = parse_date(value) else {
+                bail!("scale_{axis}_date() expects dates like 2024-03-15; found {:?} at row {} in column '{}'.", value, row + 1, col);
+            };
+            *value = date_to_days(date).to_string();
+        }
+    }
+    Ok(data)
+}
+
+/// Ticks for a date axis (in days since 1970-01-01): the finest of 1, 2, 7 or 14 days,
+/// or 1, 2, 3, 6 months, or 1, 2, 5, 10, ... years, that leaves at most `max_points`
+/// ticks inside [min, max]. Month and year steps tick on the first of the month.
+pub fn date_breaks(min: f64, max: f64, max_points: usize) -> Vec<f64> {
+    let (Some(first), Some(last)) = (days_to_date(min.ceil()), days_to_date(max.floor())) else { return Vec::new() };
+    if last < first {
+        return Vec::new();
+    }
+    let max_points = max_points.max(2) as f64;
+    let span = max - min;
+
+    for step in [1.0, 2.0, 7.0, 14.0] {
+        if span / step <= max_points {
+            let start = (min / step).ceil() as i64;
+            let end = (max / step).floor() as i64;
+            return (start..=end).map(|i| i as f64 * step).collect();
+        }
+    }
+
+    let month_steps = [1, 2, 3, 6].into_iter()
+        .chain([1, 2, 5].into_iter().cycle().zip(0..).map(|(m, k)| m * 12 * 10i32.pow(k / 3)).take(15));
+    for months in month_steps {
+        if span / (30.44 * months as f64) > max_points {
+            continue;
+        }
+        // Month index since year 0, snapped up to a multiple of the step
+        let index = |d: NaiveDate| d.year() * 12 + d.month0() as i32;
+        let mut m = index(first).div_euclid(months) * months;
+        let mut ticks = Vec::new();
+        while let Some(date) = NaiveDate::from_ymd_opt(m.div_euclid(12), m.rem_euclid(12) as u32 + 1, 1) {
+            if date > last {
+                break;
+            }
+            if date >= first {
+                ticks.push(date_to_days(date));
+            }
+            m += months;
+        }
+        return ticks;
+    }
+    Vec::new()
+}
+
 fn pad_range(min: f64, max: f64) -> (f64, f64) {
     if min == max {
         (min - 1.0, max + 1.0)
@@ -219,6 +473,16 @@ fn pad_range(min: f64, max: f64) -> (f64, f64) {
     }
 }
 
+/// Category tick labels, renamed through scale_x_discrete(labels: {...})
+fn display_categories(categories: Vec<String>, spec: Option<&AxisScale>) -> Vec<String> {
+    match spec.and_then(|s| s.discrete.as_ref()) {
+        Some(d) if !d.labels.is_empty() => {
+            categories.into_iter().map(|c| d.labels.get(&c).cloned().unwrap_or(c)).collect()
+        }
+        _ => categories,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,23 +497,39 @@ mod tests {
                     groups: vec![GroupData {
                         key: "A".to_string(),
                         x,
-                        y: y,
+                        y,
                         y_start: vec![],
                         y_min: vec![],
                         y_max: vec![],
+                        x_min: vec![],
+                        x_max: vec![],
                         y_q1: vec![],
                         y_median: vec![],
                         y_q3: vec![],
+                        y_notch_low: vec![],
+                        y_notch_high: vec![],
                         outliers: vec![],
+                        counts: vec![],
+                        cell_values: vec![],
                         violin_density: vec![],
                         violin_density_y: vec![],
                         violin_quantile_values: vec![],
+                        fills: vec![],
+                        residuals: vec![],
+                        contours: vec![],
+                        widths: vec![],
+                        labels: vec![],
+                        ablines: vec![],
+                        segment_ends: vec![],
                         x_categories: None,
+                        x_category_positions: vec![],
+                        y_categories: None,
                         style: RenderStyle::Line(LineStyle::default()),
                     }],
                 }],
             }],
             facet_layout: FacetLayout { nrow: 1, ncol: 1, panel_titles: vec![] },
+            colorbar: None,
         }
     }
 
@@ -262,6 +542,10 @@ mod tests {
             theme: crate::parser::ast::Theme::default(),
             x_scale_spec: None,
             y_scale_spec: None,
+            color_scale: None,
+            linetype_values: None,
+            fill_scale: None,
+            identity_scales: Default::default(),
         }
     }
 
@@ -305,4 +589,182 @@ mod tests {
         assert_eq!(panel.x.categories, vec!["A", "B"]);
         assert_eq!(panel.x.range, (-0.5, 1.5));
     }
+
+    #[test]
+    fn test_scale_merges_category_positions_across_panels() {
+        let mut data = make_render_data(vec![0.0, 1.0], vec![10.0, 20.0]);
+        let group = &mut data.panels[0].layers[0].groups[0];
+        group.x_categories = Some(vec!["A".to_string(), "B".to_string()]);
+        group.x_category_positions = vec![-0.25, 1.25];
+        let mut second = data.panels[0].clone();
+        second.index = 1;
+        let group = &mut second.layers[0].groups[0];
+        group.x_categories = Some(vec!["B".to_string(), "C".to_string()]);
+        group.x_category_positions = vec![0.75, 1.5];
+        data.panels.push(second);
+
+        let scales = build_scales(&data, &make_resolved_spec()).unwrap();
+        // Positions follow the category names, not the index in each panel
+        for panel in &scales.panels {
+            assert_eq!(panel.x.categories, vec!["A", "B"]);
+            assert_eq!(panel.x.category_positions, vec![-0.25, 1.0]);
+        }
+    }
+
+    #[test]
+    fn test_scale_categorical_discrete_labels() {
+        let mut data = make_render_data(vec![0.0, 1.0], vec![10.0, 20.0]);
+        data.panels[0].layers[0].groups[0].x_categories = Some(vec!["A".to_string(), "B".to_string()]);
+
+        let mut spec = make_resolved_spec();
+        let mut discrete = crate::parser::ast::DiscreteScale::default();
+        discrete.labels.insert("B".to_string(), "Bravo".to_string());
+        spec.x_scale_spec = Some(AxisScale { discrete: Some(discrete), ..Default::default() });
+        let scales = build_scales(&data, &spec).unwrap();
+        assert_eq!(scales.panels[0].x.categories, vec!["A", "Bravo"]);
+    }
+
+    #[test]
+    fn test_scale_bar_fill_fixed_unit_range() {
+        use crate::ir::{ResolvedLayer, ResolvedAesthetics};
+        use crate::parser::ast::{BarLayer, LayerPosition};
+        let data = make_render_data(vec![0.0, 1.0], vec![0.4, 1.0]);
+        let mut spec = make_resolved_spec();
+        spec.layers.push(ResolvedLayer {
+            original_layer: Layer::Bar(BarLayer { position: LayerPosition::Fill, ..Default::default() }),
+            inherits_binned: false,
+            aesthetics: ResolvedAesthetics {
+                x_col: "x".to_string(),
+                y_col: Some("y".to_string()),
+                ymin_col: None,
+                ymax_col: None,
+                xmin_col: None,
+                xmax_col: None,
+                color: None,
+                size: None,
+                shape: None,
+                alpha: None,
+                linetype: None,
+                fill: None,
+                label: None,
+                colors: None,
+            },
+        });
+        let scales = build_scales(&data, &spec).unwrap();
+        assert_eq!(scales.panels[0].y.domain, (0.0, 1.0));
+        assert!(!scales.panels[0].y.is_percent);
+
+        // A raw layer alongside keeps its own values on the axis instead of being clipped
+        let mut data = data;
+        data.panels[0].layers.push(make_render_data(vec![0.0, 1.0], vec![20.0, 50.0]).panels.remove(0).layers.remove(0));
+        let mut line = spec.layers[0].clone();
+        line.original_layer = Layer::Line(Default::default());
+        spec.layers.push(line);
+        let (min, max) = build_scales(&data, &spec).unwrap().panels[0].y.domain;
+        assert!(min <= 0.4 && max >= 50.0);
+    }
+
+    #[test]
+    fn test_scale_bar_prop_percent_axis() {
+        use crate::ir::{ResolvedLayer, ResolvedAesthetics};
+        use crate::parser::ast::BarLayer;
+        let data = make_render_data(vec![0.0, 1.0], vec![0.25, 0.75]);
+        let mut spec = make_resolved_spec();
+        spec.layers.push(ResolvedLayer {
+            original_layer: Layer::Bar(BarLayer { stat: Stat::Prop, ..Default::default() }),
+            inherits_binned: false,
+            aesthetics: ResolvedAesthetics {
+                x_col: "x".to_string(),
+                y_col: None,
+                ymin_col: None,
+                ymax_col: None,
+                xmin_col: None,
+                xmax_col: None,
+                color: None,
+                size: None,
+                shape: None,
+                alpha: None,
+                linetype: None,
+                fill: None,
+                label: None,
+                colors: None,
+            },
+        });
+        let scales = build_scales(&data, &spec).unwrap();
+        assert_eq!(scales.panels[0].y.domain, (0.0, 1.0));
+        assert!(scales.panels[0].y.is_percent);
+        assert!(!scales.panels[0].x.is_percent);
+    }
+
+    #[test]
+    fn test_log_breaks() {
+        assert_eq!(log10_breaks(3.0, 52000.0), vec![10.0, 100.0, 1000.0, 10000.0]);
+        // Less than two decades: 1-2-5 steps
+        assert_eq!(log10_breaks(15.0, 120.0), vec![20.0, 50.0, 100.0]);
+        assert_eq!(trans_minor_breaks(1.0, 60.0), vec![1.0, 2.0, 5.0, 10.0, 20.0, 50.0]);
+        assert!(trans_minor_breaks(0.0, 10.0).is_empty());
+    }
+
+    #[test]
+    fn test_scale_log10_y_range() {
+        use crate::parser::ast::AxisScale;
+        let mut data = make_render_data(vec![0.0, 1.0], vec![10.0, 1000.0]);
+        // A bar baseline at 0 does not pull a log axis down
+        data.panels[0].layers[0].groups[0].y_start = vec![0.0, 0.0];
+        let mut spec = make_resolved_spec();
+        spec.y_scale_spec = Some(AxisScale { scale_type: ScaleType::Log10, ..Default::default() });
+        let y = &build_scales(&data, &spec).unwrap().panels[0].y;
+        assert!(y.is_log);
+        assert!((y.range.0.log10() - 0.9).abs() < 1e-9);
+        assert!((y.range.1.log10() - 3.1).abs() < 1e-9);
+
+        spec.y_scale_spec = Some(AxisScale { scale_type: ScaleType::Log10, limits: Some((0.0, 10.0)), ..Default::default() });
+        assert!(build_scales(&data, &spec).is_err());
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        assert_eq!(parse_date("2024-03-15"), Some(date));
+        assert_eq!(parse_date("2024-03-15T08:30:00Z"), Some(date));
+        assert_eq!(parse_date("2024-03-15 08:30"), Some(date));
+        assert_eq!(parse_date("15/03/2024"), None);
+        assert_eq!(date_to_days(NaiveDate::from_ymd_opt(1970, 1, 2).unwrap()), 1.0);
+        assert_eq!(days_to_date(date_to_days(date)), Some(date));
+    }
+
+    #[test]
+    fn test_date_breaks() {
+        let day = |y, m, d| date_to_days(NaiveDate::from_ymd_opt(y, m, d).unwrap());
+        // A week: daily ticks
+        assert_eq!(date_breaks(day(2024, 3, 1), day(2024, 3, 7), 10).len(), 7);
+        // Half a year: monthly ticks on the first
+        let ticks = date_breaks(day(2024, 1, 1), day(2024, 7, 15), 10);
+        assert_eq!(ticks.first(), Some(&day(2024, 1, 1)));
+        assert_eq!(ticks.last(), Some(&day(2024, 7, 1)));
+        assert_eq!(ticks.len(), 7);
+        // Decades: yearly steps aligned to multiples of the step
+        let ticks = date_breaks(day(1990, 6, 1), day(2020, 6, 1), 10);
+        assert_eq!(ticks.first(), Some(&day(1995, 1, 1)));
+        assert!(ticks.windows(2).all(|w| days_to_date(w[1]).unwrap().year() - days_to_date(w[0]).unwrap().year() == 5));
+    }
+
+    #[test]
+    fn test_dates_to_days() {
+        use crate::parser::ast::AxisScale;
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: day, y: value) | line()").unwrap();
+        let data = PlotData::new(
+            vec!["day".to_string(), "value".to_string()],
+            vec![vec!["1970-01-11".to_string(), "3".to_string()], vec![String::new(), "4".to_string()]],
+        );
+        let mut resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+        resolved.x_scale_spec = Some(AxisScale { labels: TickFormat::Date("%Y".to_string()), ..Default::default() });
+        let converted = dates_to_days(&resolved, data.clone()).unwrap();
+        assert_eq!(converted.rows[0], vec!["10", "3"]);
+        assert_eq!(converted.rows[1][0], "");
+
+        let bad = PlotData::new(data.headers.clone(), vec![vec!["soon".to_string(), "1".to_string()]]);
+        let err = dates_to_days(&resolved, bad).unwrap_err().to_string();
+        assert!(err.contains("scale_x_date()") && err.contains("\"soon\" at row 1"), "{}", err);
+    }
 }
@@ -22,6 +22,52 @@
 
 use crate::parser::ast::{Theme, ThemeElement, ElementLine, ElementRect, ElementText, LegendPosition};
 use plotters::style::RGBColor;
+use std::cell::RefCell;
+
+// === Global Theme State ===
+
+thread_local! {
+    /// Default theme applied beneath every plot's own theme (see `theme_set`)
+    static GLOBAL_THEME: RefCell<Theme> = RefCell::new(Theme::default());
+}
+
+/// Return a copy of the current global theme
+pub fn theme_get() -> Theme {
+    GLOBAL_THEME.with(|t| t.borrow().clone())
+}
+
+/// Replace the global theme
+pub fn theme_set(theme: Theme) {
+    GLOBAL_THEME.with(|t| *t.borrow_mut() = theme);
+}
+
+/// Merge a partial theme into the global theme (non-`Inherit` fields win)
+pub fn theme_update(partial: Theme) {
+    GLOBAL_THEME.with(|t| {
+        let base = t.borrow().clone();
+        *t.borrow_mut() = merge_themes(base, partial);
+    });
+}
+
+/// Merge two themes together (ggplot2-style).
+/// Fields from `overlay` override `base` unless they are `Inherit`.
+pub fn merge_themes(base: Theme, overlay: Theme) -> Theme {
+    Theme {
+        line: if overlay.line != ThemeElement::Inherit { overlay.line } else { base.line },
+        rect: if overlay.rect != ThemeElement::Inherit { overlay.rect } else { base.rect },
+        text: if overlay.text != ThemeElement::Inherit { overlay.text } else { base.text },
+        plot_background: if overlay.plot_background != ThemeElement::Inherit { overlay.plot_background } else { base.plot_background },
+        plot_title: if overlay.plot_title != ThemeElement::Inherit { overlay.plot_title } else { base.plot_title },
+        panel_background: if overlay.panel_background != ThemeElement::Inherit { overlay.panel_background } else { base.panel_background },
+        panel_grid_major: if overlay.panel_grid_major != ThemeElement::Inherit { overlay.panel_grid_major } else { base.panel_grid_major },
+        panel_grid_minor: if overlay.panel_grid_minor != ThemeElement::Inherit { overlay.panel_grid_minor } else { base.panel_grid_minor },
+        axis_text: if overlay.axis_text != ThemeElement::Inherit { overlay.axis_text } else { base.axis_text },
+        axis_line: if overlay.axis_line != ThemeElement::Inherit { overlay.axis_line } else { base.axis_line },
+        axis_ticks: if overlay.axis_ticks != ThemeElement::Inherit { overlay.axis_ticks } else { base.axis_ticks },
+        legend_position: overlay.legend_position.or(base.legend_position),
+        legend_key_size: overlay.legend_key_size.or(base.legend_key_size),
+    }
+}
 
 // === Resolved Types (no Options - fully concrete) ===
 
@@ -78,16 +124,56 @@ pub struct ResolvedTheme {
     pub plot_background: ResolvedRect,
     pub panel_background: ResolvedRect,
     pub plot_title: ResolvedText,
+    pub strip_text_size: f64, // Facet panel titles: plot_title's size if the theme sets one, else 15
     pub panel_grid_major: Option<ResolvedLine>,  // None if Blank
     pub panel_grid_minor: Option<ResolvedLine>,  // None if Blank
     pub axis_text: ResolvedText,
     pub axis_line: Option<ResolvedLine>,         // None if Blank
     pub axis_ticks: Option<ResolvedLine>,        // None if Blank
+    // Text/rect elements always carry a concrete style; these flag element_blank()
+    pub plot_title_blank: bool,
+    pub axis_text_blank: bool,
+    pub panel_background_blank: bool,
     pub legend_position: LegendPosition,
+    pub legend_key_size: f64, // Multiplier on the default legend key size
     /// True if user explicitly customized theme (vs using all defaults)
     pub has_customization: bool,
 }
 
+impl ResolvedTheme {
+    /// Export theme colors and fonts as CSS custom properties in a `:root { ... }` block.
+    /// Blank line elements are emitted as `none`.
+    pub fn to_css_vars(&self) -> String {
+        let line_color = |l: &Option<ResolvedLine>| l.as_ref().map(|l| css_hex(&l.color)).unwrap_or_else(|| "none".to_string());
+        let vars = [
+            ("--plot-bg", css_hex(&self.plot_background.fill)),
+            ("--panel-bg", css_hex(&self.panel_background.fill)),
+            ("--title-color", css_hex(&self.plot_title.color)),
+            ("--title-font-family", self.plot_title.family.clone()),
+            ("--title-font-size", format!("{}px", self.plot_title.size)),
+            ("--axis-text-color", css_hex(&self.axis_text.color)),
+            ("--axis-text-font-family", self.axis_text.family.clone()),
+            ("--axis-text-font-size", format!("{}px", self.axis_text.size)),
+            ("--axis-color", line_color(&self.axis_line)),
+            ("--tick-color", line_color(&self.axis_ticks)),
+            ("--grid-major-color", line_color(&self.panel_grid_major)),
+            ("--grid-minor-color", line_color(&self.panel_grid_minor)),
+        ];
+
+        let mut css = String::from(":root {\n");
+        for (name, value) in vars {
+            css.push_str(&format!("  {}: {};\n", name, value));
+        }
+        css.push('}');
+        css
+    }
+}
+
+/// Format a color as a CSS hex string (#rrggbb)
+fn css_hex(color: &RGBColor) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.0, color.1, color.2)
+}
+
 // === Default Values ===
 
 impl Default for ResolvedText {
@@ -135,39 +221,183 @@ pub fn parse_color(color_str: &str) -> Option<RGBColor> {
         return parse_hex_color(color_str);
     }
 
-    // Named colors (ggplot2-style gray scale + basic colors)
-    match color_str.to_lowercase().as_str() {
-        "white" => Some(RGBColor(255, 255, 255)),
-        "black" => Some(RGBColor(0, 0, 0)),
-        "red" => Some(RGBColor(255, 0, 0)),
-        "green" => Some(RGBColor(0, 128, 0)),
-        "blue" => Some(RGBColor(0, 0, 255)),
-        "yellow" => Some(RGBColor(255, 255, 0)),
-        "cyan" => Some(RGBColor(0, 255, 255)),
-        "magenta" => Some(RGBColor(255, 0, 255)),
-        "orange" => Some(RGBColor(255, 165, 0)),
-        "purple" => Some(RGBColor(128, 0, 128)),
-        "pink" => Some(RGBColor(255, 192, 203)),
-        "brown" => Some(RGBColor(139, 69, 19)),
-        "gray" | "grey" => Some(RGBColor(128, 128, 128)),
-        "darkgray" | "darkgrey" => Some(RGBColor(64, 64, 64)),
-        "lightgray" | "lightgrey" => Some(RGBColor(192, 192, 192)),
-        // ggplot2-style grayscale (gray0 to gray100)
-        s if s.starts_with("gray") || s.starts_with("grey") => {
-            let num_str = &s[4..];
-            if let Ok(n) = num_str.parse::<u8>() {
-                // gray0 = black, gray100 = white
-                // Use round() for correct conversion
-                let v = (n as f64 * 2.55).round() as u8;
-                Some(RGBColor(v, v, v))
-            } else {
-                None
-            }
-        }
-        _ => None,
+    let name = color_str.to_lowercase();
+    if let Ok(i) = NAMED_COLORS.binary_search_by(|(n, _)| (*n).cmp(name.as_str())) {
+        let (r, g, b) = NAMED_COLORS[i].1;
+        return Some(RGBColor(r, g, b));
     }
+
+    // ggplot2-style grayscale (gray0 to gray100)
+    let level = name.strip_prefix("gray").or_else(|| name.strip_prefix("grey"))?;
+    let n = level.parse::<u8>().ok()?;
+    // gray0 = black, gray100 = white
+    let v = (n as f64 * 2.55).round() as u8;
+    Some(RGBColor(v, v, v))
 }
 
+/// CSS Color Level 4 named colors, plus the category10 palette as `ggplot_<name>`.
+/// Sorted by name for binary search.
+static NAMED_COLORS: &[(&str, (u8, u8, u8))] = &[
+    ("aliceblue", (240, 248, 255)),
+    ("antiquewhite", (250, 235, 215)),
+    ("aqua", (0, 255, 255)),
+    ("aquamarine", (127, 255, 212)),
+    ("azure", (240, 255, 255)),
+    ("beige", (245, 245, 220)),
+    ("bisque", (255, 228, 196)),
+    ("black", (0, 0, 0)),
+    ("blanchedalmond", (255, 235, 205)),
+    ("blue", (0, 0, 255)),
+    ("blueviolet", (138, 43, 226)),
+    ("brown", (165, 42, 42)),
+    ("burlywood", (222, 184, 135)),
+    ("cadetblue", (95, 158, 160)),
+    ("chartreuse", (127, 255, 0)),
+    ("chocolate", (210, 105, 30)),
+    ("coral", (255, 127, 80)),
+    ("cornflowerblue", (100, 149, 237)),
+    ("cornsilk", (255, 248, 220)),
+    ("crimson", (220, 20, 60)),
+    ("cyan", (0, 255, 255)),
+    ("darkblue", (0, 0, 139)),
+    ("darkcyan", (0, 139, 139)),
+    ("darkgoldenrod", (184, 134, 11)),
+    ("darkgray", (169, 169, 169)),
+    ("darkgreen", (0, 100, 0)),
+    ("darkgrey", (169, 169, 169)),
+    ("darkkhaki", (189, 183, 107)),
+    ("darkmagenta", (139, 0, 139)),
+    ("darkolivegreen", (85, 107, 47)),
+    ("darkorange", (255, 140, 0)),
+    ("darkorchid", (153, 50, 204)),
+    ("darkred", (139, 0, 0)),
+    ("darksalmon", (233, 150, 122)),
+    ("darkseagreen", (143, 188, 143)),
+    ("darkslateblue", (72, 61, 139)),
+    ("darkslategray", (47, 79, 79)),
+    ("darkslategrey", (47, 79, 79)),
+    ("darkturquoise", (0, 206, 209)),
+    ("darkviolet", (148, 0, 211)),
+    ("deeppink", (255, 20, 147)),
+    ("deepskyblue", (0, 191, 255)),
+    ("dimgray", (105, 105, 105)),
+    ("dimgrey", (105, 105, 105)),
+    ("dodgerblue", (30, 144, 255)),
+    ("firebrick", (178, 34, 34)),
+    ("floralwhite", (255, 250, 240)),
+    ("forestgreen", (34, 139, 34)),
+    ("fuchsia", (255, 0, 255)),
+    ("gainsboro", (220, 220, 220)),
+    ("ggplot_blue", (31, 119, 180)),
+    ("ggplot_brown", (140, 86, 75)),
+    ("ggplot_cyan", (23, 190, 207)),
+    ("ggplot_gray", (127, 127, 127)),
+    ("ggplot_green", (44, 160, 44)),
+    ("ggplot_olive", (188, 189, 34)),
+    ("ggplot_orange", (255, 127, 14)),
+    ("ggplot_pink", (227, 119, 194)),
+    ("ggplot_purple", (148, 103, 189)),
+    ("ggplot_red", (214, 39, 40)),
+    ("ghostwhite", (248, 248, 255)),
+    ("gold", (255, 215, 0)),
+    ("goldenrod", (218, 165, 32)),
+    ("gray", (128, 128, 128)),
+    ("green", (0, 128, 0)),
+    ("greenyellow", (173, 255, 47)),
+    ("grey", (128, 128, 128)),
+    ("honeydew", (240, 255, 240)),
+    ("hotpink", (255, 105, 180)),
+    ("indianred", (205, 92, 92)),
+    ("indigo", (75, 0, 130)),
+    ("ivory", (255, 255, 240)),
+    ("khaki", (240, 230, 140)),
+    ("lavender", (230, 230, 250)),
+    ("lavenderblush", (255, 240, 245)),
+    ("lawngreen", (124, 252, 0)),
+    ("lemonchiffon", (255, 250, 205)),
+    ("lightblue", (173, 216, 230)),
+    ("lightcoral", (240, 128, 128)),
+    ("lightcyan", (224, 255, 255)),
+    ("lightgoldenrodyellow", (250, 250, 210)),
+    ("lightgray", (211, 211, 211)),
+    ("lightgreen", (144, 238, 144)),
+    ("lightgrey", (211, 211, 211)),
+    ("lightpink", (255, 182, 193)),
+    ("lightsalmon", (255, 160, 122)),
+    ("lightseagreen", (32, 178, 170)),
+    ("lightskyblue", (135, 206, 250)),
+    ("lightslategray", (119, 136, 153)),
+    ("lightslategrey", (119, 136, 153)),
+    ("lightsteelblue", (176, 196, 222)),
+    ("lightyellow", (255, 255, 224)),
+    ("lime", (0, 255, 0)),
+    ("limegreen", (50, 205, 50)),
+    ("linen", (250, 240, 230)),
+    ("magenta", (255, 0, 255)),
+    ("maroon", (128, 0, 0)),
+    ("mediumaquamarine", (102, 205, 170)),
+    ("mediumblue", (0, 0, 205)),
+    ("mediumorchid", (186, 85, 211)),
+    ("mediumpurple", (147, 112, 219)),
+    ("mediumseagreen", (60, 179, 113)),
+    ("mediumslateblue", (123, 104, 238)),
+    ("mediumspringgreen", (0, 250, 154)),
+    ("mediumturquoise", (72, 209, 204)),
+    ("mediumvioletred", (199, 21, 133)),
+    ("midnightblue", (25, 25, 112)),
+    ("mintcream", (245, 255, 250)),
+    ("mistyrose", (255, 228, 225)),
+    ("moccasin", (255, 228, 181)),
+    ("navajowhite", (255, 222, 173)),
+    ("navy", (0, 0, 128)),
+    ("oldlace", (253, 245, 230)),
+    ("olive", (128, 128, 0)),
+    ("olivedrab", (107, 142, 35)),
+    ("orange", (255, 165, 0)),
+    ("orangered", (255, 69, 0)),
+    ("orchid", (218, 112, 214)),
+    ("palegoldenrod", (238, 232, 170)),
+    ("palegreen", (152, 251, 152)),
+    ("paleturquoise", (175, 238, 238)),
+    ("palevioletred", (219, 112, 147)),
+    ("papayawhip", (255, 239, 213)),
+    ("peachpuff", (255, 218, 185)),
+    ("peru", (205, 133, 63)),
+    ("pink", (255, 192, 203)),
+    ("plum", (221, 160, 221)),
+    ("powderblue", (176, 224, 230)),
+    ("purple", (128, 0, 128)),
+    ("rebeccapurple", (102, 51, 153)),
+    ("red", (255, 0, 0)),
+    ("rosybrown", (188, 143, 143)),
+    ("royalblue", (65, 105, 225)),
+    ("saddlebrown", (139, 69, 19)),
+    ("salmon", (250, 128, 114)),
+    ("sandybrown", (244, 164, 96)),
+    ("seagreen", (46, 139, 87)),
+    ("seashell", (255, 245, 238)),
+    ("sienna", (160, 82, 45)),
+    ("silver", (192, 192, 192)),
+    ("skyblue", (135, 206, 235)),
+    ("slateblue", (106, 90, 205)),
+    ("slategray", (112, 128, 144)),
+    ("slategrey", (112, 128, 144)),
+    ("snow", (255, 250, 250)),
+    ("springgreen", (0, 255, 127)),
+    ("steelblue", (70, 130, 180)),
+    ("tan", (210, 180, 140)),
+    ("teal", (0, 128, 128)),
+    ("thistle", (216, 191, 216)),
+    ("tomato", (255, 99, 71)),
+    ("turquoise", (64, 224, 208)),
+    ("violet", (238, 130, 238)),
+    ("wheat", (245, 222, 179)),
+    ("white", (255, 255, 255)),
+    ("whitesmoke", (245, 245, 245)),
+    ("yellow", (255, 255, 0)),
+    ("yellowgreen", (154, 205, 50)),
+];
+
 /// Parse hex color (#RRGGBB or #RGB)
 fn parse_hex_color(hex: &str) -> Option<RGBColor> {
     let hex = hex.trim_start_matches('#');
@@ -210,6 +440,73 @@ fn parse_linetype(linetype: &str) -> LineType {
 // === Resolution Logic ===
 
 impl Theme {
+    /// Preset: clean white background, light major grid, no axis lines or ticks
+    pub fn minimal() -> Self {
+        Theme {
+            plot_background: ThemeElement::Rect(ElementRect {
+                fill: Some("white".to_string()),
+                ..Default::default()
+            }),
+            panel_background: ThemeElement::Rect(ElementRect {
+                fill: Some("white".to_string()),
+                ..Default::default()
+            }),
+            panel_grid_major: ThemeElement::Line(ElementLine {
+                color: Some("#CCCCCC".to_string()),
+                width: Some(0.5),
+                ..Default::default()
+            }),
+            panel_grid_minor: ThemeElement::Blank,
+            axis_line: ThemeElement::Blank,
+            axis_ticks: ThemeElement::Blank,
+            ..Default::default()
+        }
+    }
+
+    /// Preset: white background, black axis lines, no grid
+    pub fn classic() -> Self {
+        Theme {
+            plot_background: ThemeElement::Rect(ElementRect {
+                fill: Some("white".to_string()),
+                ..Default::default()
+            }),
+            panel_background: ThemeElement::Rect(ElementRect {
+                fill: Some("white".to_string()),
+                ..Default::default()
+            }),
+            panel_grid_major: ThemeElement::Blank,
+            panel_grid_minor: ThemeElement::Blank,
+            axis_line: ThemeElement::Line(ElementLine {
+                color: Some("black".to_string()),
+                width: Some(1.0),
+                ..Default::default()
+            }),
+            axis_ticks: ThemeElement::Line(ElementLine {
+                color: Some("black".to_string()),
+                width: Some(1.0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Preset: nothing but the data (no grid, axes, axis text or panel background)
+    pub fn void() -> Self {
+        Theme {
+            plot_background: ThemeElement::Rect(ElementRect {
+                fill: Some("white".to_string()),
+                ..Default::default()
+            }),
+            panel_background: ThemeElement::Blank,
+            panel_grid_major: ThemeElement::Blank,
+            panel_grid_minor: ThemeElement::Blank,
+            axis_text: ThemeElement::Blank,
+            axis_line: ThemeElement::Blank,
+            axis_ticks: ThemeElement::Blank,
+            ..Default::default()
+        }
+    }
+
     /// Resolve the theme into concrete styles using the inheritance hierarchy.
     ///
     /// Resolution order for each element:
@@ -239,6 +536,9 @@ impl Theme {
         let plot_background = self.resolve_rect_element(&self.plot_background, &base_rect);
         let panel_background = self.resolve_rect_element(&self.panel_background, &base_rect);
         let plot_title = self.resolve_text_element(&self.plot_title, &base_text);
+        let title_size_set = [&self.plot_title, &self.text].iter()
+            .any(|e| matches!(e, ThemeElement::Text(ElementText { size: Some(_), .. })));
+        let strip_text_size = if title_size_set { plot_title.size } else { 15.0 };
         let axis_text = self.resolve_text_element(&self.axis_text, &base_text);
 
         // Resolve line elements (can be Blank)
@@ -253,16 +553,30 @@ impl Theme {
             plot_background,
             panel_background,
             plot_title,
+            strip_text_size,
             panel_grid_major,
             panel_grid_minor,
             axis_text,
             axis_line,
             axis_ticks,
-            legend_position: self.legend_position.clone(),
+            plot_title_blank: self.is_blank(&self.plot_title, &self.text),
+            axis_text_blank: self.is_blank(&self.axis_text, &self.text),
+            panel_background_blank: self.is_blank(&self.panel_background, &self.rect),
+            legend_position: self.legend_position.clone().unwrap_or(LegendPosition::UpperRight),
+            legend_key_size: self.legend_key_size.unwrap_or(1.0),
             has_customization,
         }
     }
 
+    /// True if the element is blank, either directly or by inheriting a blank parent
+    fn is_blank(&self, element: &ThemeElement, parent: &ThemeElement) -> bool {
+        match element {
+            ThemeElement::Blank => true,
+            ThemeElement::Inherit => *parent == ThemeElement::Blank,
+            _ => false,
+        }
+    }
+
     /// Resolve base text style from root `text` element
     fn resolve_base_text(&self) -> ResolvedText {
         let mut base = ResolvedText::default();
@@ -324,6 +638,7 @@ impl Theme {
                 apply_line_overrides(&mut resolved, l);
                 Some(resolved)
             }
+            ThemeElement::Inherit if self.line == ThemeElement::Blank => None,
             ThemeElement::Inherit => Some(base.clone()),
             ThemeElement::Blank => None,
             _ => Some(base.clone()),
@@ -340,7 +655,7 @@ impl Theme {
                 apply_line_overrides(&mut resolved, l);
                 // Minor grid typically thinner
                 if l.width.is_none() {
-                    resolved.width = resolved.width * 0.5;
+                    resolved.width *= 0.5;
                 }
                 Some(resolved)
             }
@@ -435,6 +750,18 @@ mod tests {
         assert_eq!(parse_color("red"), Some(RGBColor(255, 0, 0)));
     }
 
+    #[test]
+    fn test_parse_css_named_colors() {
+        assert!(NAMED_COLORS.windows(2).all(|w| w[0].0 < w[1].0), "NAMED_COLORS must stay sorted");
+        assert_eq!(parse_color("aliceblue"), Some(RGBColor(240, 248, 255)));
+        assert_eq!(parse_color("CadetBlue"), Some(RGBColor(95, 158, 160)));
+        assert_eq!(parse_color("rebeccapurple"), Some(RGBColor(102, 51, 153)));
+        assert_eq!(parse_color("darkgrey"), parse_color("darkgray"));
+        assert_eq!(parse_color("ggplot_blue"), Some(RGBColor(0x1f, 0x77, 0xb4)));
+        assert_eq!(parse_color("ggplot_cyan"), Some(RGBColor(0x17, 0xbe, 0xcf)));
+        assert_eq!(parse_color("notacolor"), None);
+    }
+
     #[test]
     fn test_parse_gray_scale() {
         assert_eq!(parse_color("gray0"), Some(RGBColor(0, 0, 0)));
@@ -454,13 +781,17 @@ mod tests {
 
         // Default should have black text
         assert_eq!(resolved.axis_text.color, RGBColor(0, 0, 0));
+        // Facet panel titles keep their 15px size
+        assert_eq!(resolved.strip_text_size, 15.0);
     }
 
     #[test]
     fn test_resolve_with_blank_elements() {
-        let mut theme = Theme::default();
-        theme.axis_line = ThemeElement::Blank;
-        theme.axis_ticks = ThemeElement::Blank;
+        let theme = Theme {
+            axis_line: ThemeElement::Blank,
+            axis_ticks: ThemeElement::Blank,
+            ..Default::default()
+        };
 
         let resolved = theme.resolve();
 
@@ -470,29 +801,34 @@ mod tests {
 
     #[test]
     fn test_resolve_with_custom_text() {
-        let mut theme = Theme::default();
-        theme.plot_title = ThemeElement::Text(ElementText {
-            size: Some(24.0),
-            face: Some("bold".to_string()),
-            color: Some("#FF0000".to_string()),
+        let theme = Theme {
+            plot_title: ThemeElement::Text(ElementText {
+                size: Some(24.0),
+                face: Some("bold".to_string()),
+                color: Some("#FF0000".to_string()),
+                ..Default::default()
+            }),
             ..Default::default()
-        });
+        };
 
         let resolved = theme.resolve();
 
         assert_eq!(resolved.plot_title.size, 24.0);
+        assert_eq!(resolved.strip_text_size, 24.0);
         assert_eq!(resolved.plot_title.face, FontFace::Bold);
         assert_eq!(resolved.plot_title.color, RGBColor(255, 0, 0));
     }
 
     #[test]
     fn test_inheritance_from_root() {
-        let mut theme = Theme::default();
         // Set root text color
-        theme.text = ThemeElement::Text(ElementText {
-            color: Some("blue".to_string()),
+        let theme = Theme {
+            text: ThemeElement::Text(ElementText {
+                color: Some("blue".to_string()),
+                ..Default::default()
+            }),
             ..Default::default()
-        });
+        };
 
         let resolved = theme.resolve();
 
@@ -501,4 +837,76 @@ mod tests {
         // plot_title should also inherit
         assert_eq!(resolved.plot_title.color, RGBColor(0, 0, 255));
     }
+
+    #[test]
+    fn test_theme_get_set_update() {
+        theme_set(Theme::classic());
+        assert_eq!(theme_get(), Theme::classic());
+
+        theme_update(Theme { axis_line: ThemeElement::Blank, ..Default::default() });
+        let updated = theme_get();
+        assert_eq!(updated.axis_line, ThemeElement::Blank);
+        // Untouched fields keep the classic values
+        assert_eq!(updated.panel_grid_major, ThemeElement::Blank);
+        assert_eq!(updated.axis_ticks, Theme::classic().axis_ticks);
+
+        theme_set(Theme::default());
+        assert_eq!(theme_get(), Theme::default());
+    }
+
+    #[test]
+    fn test_to_css_vars() {
+        let theme = Theme {
+            plot_background: ThemeElement::Rect(ElementRect {
+                fill: Some("#1a1a2e".to_string()),
+                ..Default::default()
+            }),
+            axis_line: ThemeElement::Blank,
+            ..Default::default()
+        };
+        let css = theme.resolve().to_css_vars();
+        assert!(css.starts_with(":root {"));
+        assert!(css.contains("--plot-bg: #1a1a2e;"));
+        assert!(css.contains("--title-font-size: 12px;"));
+        assert!(css.contains("--axis-color: none;"));
+        assert!(css.ends_with('}'));
+    }
+
+    #[test]
+    fn test_resolve_blank_text_and_rect() {
+        let theme = Theme {
+            axis_text: ThemeElement::Blank,
+            panel_background: ThemeElement::Blank,
+            ..Default::default()
+        };
+        let resolved = theme.resolve();
+        assert!(resolved.axis_text_blank);
+        assert!(resolved.panel_background_blank);
+        assert!(!resolved.plot_title_blank);
+    }
+
+    #[test]
+    fn test_resolve_blank_root_elements_propagate() {
+        let theme = Theme {
+            line: ThemeElement::Blank,
+            text: ThemeElement::Blank,
+            ..Default::default()
+        };
+        let resolved = theme.resolve();
+        assert!(resolved.axis_line.is_none());
+        assert!(resolved.axis_ticks.is_none());
+        assert!(resolved.panel_grid_major.is_none());
+        assert!(resolved.panel_grid_minor.is_none());
+        assert!(resolved.plot_title_blank);
+        assert!(resolved.axis_text_blank);
+    }
+
+    #[test]
+    fn test_theme_void() {
+        let resolved = Theme::void().resolve();
+        assert!(resolved.panel_grid_major.is_none());
+        assert!(resolved.axis_line.is_none());
+        assert!(resolved.axis_text_blank);
+        assert!(resolved.has_customization);
+    }
 }
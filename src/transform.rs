@@ -1,15 +1,26 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use std::collections::{HashMap, HashSet};
-use crate::data::PlotData;
-use crate::ir::{RenderData, PanelData, LayerData, GroupData, FacetLayout, RenderStyle};
+use crate::data::{find_col_index, PlotData};
+use crate::ir::{RenderData, PanelData, LayerData, GroupData, FacetLayout, RenderStyle, ColorBar, ContourPath};
 use crate::ir::{ResolvedSpec, ResolvedLayer, ResolvedAesthetics, ResolvedFacet};
-use crate::parser::ast::{Layer, BarPosition, Stat};
-use crate::graph::{LineStyle, PointStyle, BarStyle, RibbonStyle, ViolinStyle};
-use crate::palette::{ColorPalette, SizePalette, ShapePalette};
+use crate::parser::ast::{Layer, AestheticValue, BandwidthMethod, BarLayer, LayerPosition, ColorScale, SeType, HexSummary, LineLayer, PointLayer, SmoothMethod, Stat, StatOutput, SummaryExpr, SummaryFun, TextLayer, TileLayer, ViolinScale};
+use crate::graph::{LineStyle, PointStyle, BarStyle, RibbonStyle, ViolinStyle, TileStyle, HexStyle, TextGeomStyle};
+use crate::palette::{ColorGradient, ColorPalette, SizePalette, ShapePalette, LinetypePalette};
+use crate::runtime::{report, Progress};
+use crate::theme_resolve::parse_color;
+
+/// Raw per-group columns collected from the data: (x strings, y, ymin, ymax)
+type RawGroups = HashMap<String, (Vec<String>, Vec<f64>, Vec<f64>, Vec<f64>)>;
 
 /// Main entry point: Transform resolved spec and CSV data into renderable data
 pub fn apply_transformations(spec: &ResolvedSpec, data: &PlotData) -> Result<RenderData> {
+    apply_transformations_with_progress(spec, data, None)
+}
+
+/// `apply_transformations`, reporting each step to a --progress callback
+pub(crate) fn apply_transformations_with_progress(spec: &ResolvedSpec, data: &PlotData, progress: Progress) -> Result<RenderData> {
     // 1. Partition Data (Faceting)
+    report(progress, || "Partitioning data...".to_string());
     let partitions = partition_data(spec, data)?;
     
     // 2. Calculate Layout info
@@ -20,19 +31,68 @@ pub fn apply_transformations(spec: &ResolvedSpec, data: &PlotData) -> Result<Ren
         panel_titles: partitions.iter().map(|p| p.title.clone()).collect(),
     };
 
+    // Fill ranges come from the whole data set so every facet shares one gradient; group keys
+    // too, so a group keeps its palette entry in facets that lack other groups
+    let plot_wide = spec.layers.iter()
+        .map(|l| Ok(PlotWide {
+            fill_range: fill_range(&l.aesthetics, data)?,
+            group_keys: plot_group_keys(&l.aesthetics, data)?,
+            y_categories: plot_y_categories(l, data)?,
+        }))
+        .collect::<Result<Vec<_>>>()?;
+    let x_range = plot_x_range(spec, data);
+
     // 3. Process each partition into a Panel
     let mut panels = Vec::new();
+    let panel_count = partitions.len();
     for (idx, partition) in partitions.into_iter().enumerate() {
-        let panel = process_partition(idx, partition, spec)?;
+        report(progress, || format!("Computing statistics [{}/{}]...", idx + 1, panel_count));
+        let panel = process_partition(idx, partition, spec, &plot_wide, x_range)?;
         panels.push(panel);
     }
 
+    // 4. Color hexbin cells by their fill summary (over every panel) and build the fill legend
+    let mut colorbar: Option<ColorBar> = None;
+    for (layer_idx, layer_spec) in spec.layers.iter().enumerate() {
+        if let Layer::Hexbin(h) = &layer_spec.original_layer {
+            let values: Vec<f64> = panels.iter()
+                .flat_map(|p| p.layers[layer_idx].groups.iter())
+                .flat_map(|g| g.cell_values.iter().copied())
+                .collect();
+            if values.is_empty() {
+                continue;
+            }
+            let range = (
+                values.iter().cloned().fold(f64::INFINITY, f64::min),
+                values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            );
+            // Counts get a log10 gradient by default; other summaries a linear one
+            let gradient = fill_gradient(spec, h.fill == HexSummary::Count)?;
+            for panel in &mut panels {
+                for group in &mut panel.layers[layer_idx].groups {
+                    group.fills = group.cell_values.iter().map(|&v| gradient.map(v, range)).collect();
+                }
+            }
+            colorbar.get_or_insert_with(|| gradient.colorbar(range, Some(h.fill.name().to_string())));
+        } else if let Some(range) = plot_wide[layer_idx].fill_range {
+            let gradient = fill_gradient(spec, false)?;
+            colorbar.get_or_insert_with(|| gradient.colorbar(range, layer_spec.aesthetics.fill.clone()));
+        }
+    }
+
     Ok(RenderData {
         panels,
         facet_layout,
+        colorbar,
     })
 }
 
+/// Gradient for the plot's fill scale (white to steelblue without scale_fill_continuous);
+/// hexbin counts default to a log10 transform
+fn fill_gradient(spec: &ResolvedSpec, counts: bool) -> Result<ColorGradient> {
+    ColorGradient::from_scale(&spec.fill_scale.clone().unwrap_or_default(), counts)
+}
+
 struct DataPartition {
     title: String,
     data: PlotData,
@@ -92,12 +152,82 @@ fn calculate_grid_dimensions(n_panels: usize, facet: Option<&ResolvedFacet>) ->
     (rows, cols)
 }
 
+/// Min/max of a layer's numeric fill column (None without a fill mapping or numeric values)
+fn fill_range(aes: &ResolvedAesthetics, data: &PlotData) -> Result<Option<(f64, f64)>> {
+    let Some(col) = &aes.fill else { return Ok(None) };
+    let idx = find_col_index(&data.headers, col)?;
+    let values: Vec<f64> = data.rows.iter()
+        .filter_map(|row| row[idx].parse::<f64>().ok())
+        .filter(|v| v.is_finite())
+        .collect();
+    if values.is_empty() {
+        return Ok(None);
+    }
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    Ok(Some((min, max)))
+}
+
+/// Sorted distinct y values of a point layer (points, pointrange(), crossbar()) whose y column is
+/// not numeric, e.g. the task axis of a horizontal pointrange(xmin:, xmax:); None otherwise
+fn plot_y_categories(layer: &ResolvedLayer, data: &PlotData) -> Result<Option<Vec<String>>> {
+    let (Layer::Point(PointLayer { stat: Stat::Identity, .. }), Some(col)) = (&layer.original_layer, &layer.aesthetics.y_col) else {
+        return Ok(None);
+    };
+    let idx = find_col_index(&data.headers, col)?;
+    if data.rows.iter().all(|row| row[idx].parse::<f64>().is_ok()) {
+        return Ok(None);
+    }
+    let categories: HashSet<&String> = data.rows.iter().map(|row| &row[idx]).collect();
+    let mut categories: Vec<String> = categories.into_iter().cloned().collect();
+    categories.sort();
+    Ok(Some(categories))
+}
+
+/// Sorted distinct values of a layer's grouping column over the whole data set (empty if ungrouped)
+fn plot_group_keys(aes: &ResolvedAesthetics, data: &PlotData) -> Result<Vec<String>> {
+    let Some(col) = group_column(aes) else { return Ok(Vec::new()) };
+    let idx = find_col_index(&data.headers, col)?;
+    let keys: HashSet<String> = data.rows.iter().map(|row| row[idx].clone()).collect();
+    let mut keys: Vec<String> = keys.into_iter().collect();
+    keys.sort();
+    Ok(keys)
+}
+
+/// x range of the whole plot, for smooth(fullrange: true): scale_x_continuous(limits: ...) when
+/// given, else the numeric x values of every layer over the whole data set
+fn plot_x_range(spec: &ResolvedSpec, data: &PlotData) -> Option<(f64, f64)> {
+    if let Some((lo, hi)) = spec.x_scale_spec.as_ref().and_then(|s| s.limits) {
+        return Some((lo.min(hi), lo.max(hi)));
+    }
+    let columns: HashSet<usize> = spec.layers.iter()
+        .filter_map(|l| data.headers.iter().position(|h| *h == l.aesthetics.x_col))
+        .collect();
+    let (lo, hi) = data.rows.iter()
+        .flat_map(|row| columns.iter().filter_map(|&idx| row[idx].parse::<f64>().ok()))
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    (lo <= hi).then_some((lo, hi))
+}
+
+/// Smallest gap between distinct values (ggplot2's resolution()), 1.0 if there is none
+fn resolution(values: &[f64]) -> f64 {
+    let mut sorted: Vec<f64> = values.iter().cloned().filter(|v| v.is_finite()).collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    sorted.dedup();
+    sorted.windows(2)
+        .map(|w| w[1] - w[0])
+        .fold(None, |acc: Option<f64>, d| Some(acc.map_or(d, |a| a.min(d))))
+        .unwrap_or(1.0)
+}
+
 /// Process a single data partition (Panel)
-fn process_partition(index: usize, partition: DataPartition, spec: &ResolvedSpec) -> Result<PanelData> {
+fn process_partition(index: usize, partition: DataPartition, spec: &ResolvedSpec, plot_wide: &[PlotWide], x_range: Option<(f64, f64)>) -> Result<PanelData> {
     let mut layers = Vec::new();
+    let mut context = LayerContext::default();
 
-    for layer_spec in &spec.layers {
-        let layer_data = process_layer(layer_spec, &partition.data)?;
+    for (layer_spec, wide) in spec.layers.iter().zip(plot_wide) {
+        let layer_data = process_layer(layer_spec, &partition.data, spec, wide, x_range, &mut context)?;
         layers.push(layer_data);
     }
 
@@ -107,36 +237,95 @@ fn process_partition(index: usize, partition: DataPartition, spec: &ResolvedSpec
     })
 }
 
+/// Values of a layer computed over the whole data set, shared by every panel
+struct PlotWide {
+    fill_range: Option<(f64, f64)>,
+    group_keys: Vec<String>,
+    y_categories: Option<Vec<String>>, // Non-numeric y of a point layer, in axis order
+}
+
+/// State a layer leaves for the layers after it in the same panel
+#[derive(Default)]
+struct LayerContext {
+    // Bins of the latest histogram(stat_output_as: "binned"), x as bar positions
+    binned: Option<RawGroups>,
+}
+
 /// Process a single layer: Extract, Group, Stack
-fn process_layer(layer_spec: &ResolvedLayer, data: &PlotData) -> Result<LayerData> {
+fn process_layer(
+    layer_spec: &ResolvedLayer,
+    data: &PlotData,
+    spec: &ResolvedSpec,
+    wide: &PlotWide,
+    x_range: Option<(f64, f64)>,
+    context: &mut LayerContext,
+) -> Result<LayerData> {
     let aes = &layer_spec.aesthetics;
+    let (fill_range, plot_keys) = (wide.fill_range, &wide.group_keys[..]);
+    if let Stat::Abline { slope, intercept } = layer_spec.original_layer.stat() {
+        return process_abline(layer_spec, data, spec, plot_keys, slope, intercept);
+    }
+    if let Stat::Segment { xend, yend, .. } = layer_spec.original_layer.stat() {
+        let (Some(xend), Some(yend)) = (xend, yend) else { unreachable!("resolve requires segment() xend and yend") };
+        return process_segment(layer_spec, data, spec, plot_keys, xend, yend);
+    }
     
     // 1. Identify Grouping Column
-    let group_col = aes.color.as_ref()
-        .or(aes.size.as_ref())
-        .or(aes.shape.as_ref())
-        .or(aes.alpha.as_ref());
+    let group_col = group_column(aes);
 
     // 2. Extract Data (Grouped)
     // We return a map: GroupKey -> (RawX, RawY, RawYMin, RawYMax)
     // RawX is String to handle both numeric and categorical initially
-    let mut raw_groups: HashMap<String, (Vec<String>, Vec<f64>, Vec<f64>, Vec<f64>)> = HashMap::new();
+    let mut raw_groups: RawGroups = HashMap::new();
     
     // Column Indices
     let x_idx = find_col_index(&data.headers, &aes.x_col)?;
     let y_idx = if let Some(y) = &aes.y_col { Some(find_col_index(&data.headers, y)?) } else { None };
-    let ymin_idx = if let Some(col) = &aes.ymin_col { Some(find_col_index(&data.headers, col)?) } else { None };
-    let ymax_idx = if let Some(col) = &aes.ymax_col { Some(find_col_index(&data.headers, col)?) } else { None };
+    let ymin_idx = if let Some(col) = aes.ymin_col.as_ref().or(aes.xmin_col.as_ref()) { Some(find_col_index(&data.headers, col)?) } else { None };
+    let ymax_idx = if let Some(col) = aes.ymax_col.as_ref().or(aes.xmax_col.as_ref()) { Some(find_col_index(&data.headers, col)?) } else { None };
 
     let group_idx = if let Some(g) = group_col {
         Some(find_col_index(&data.headers, g)?)
     } else {
         None
     };
+    let fill_idx = if let Some(col) = &aes.fill { Some(find_col_index(&data.headers, col)?) } else { None };
+    // Numeric fill values per group, in row order (tiles use the identity stat, which keeps it)
+    let mut raw_fills: HashMap<String, Vec<f64>> = HashMap::new();
+    let label_idx = if let Some(col) = &aes.label { Some(find_col_index(&data.headers, col)?) } else { None };
+    // Label text per group, in row order (text uses the identity stat, which keeps it)
+    let mut raw_labels: HashMap<String, Vec<String>> = HashMap::new();
+    let colors_idx = if let Some(col) = &aes.colors { Some(find_col_index(&data.headers, col)?) } else { None };
+    // Literal bar colors per group, in row order (identity bars only)
+    let mut raw_colors: HashMap<String, Vec<String>> = HashMap::new();
+    // scale_x_discrete(limits: [...]): category order, and (unless drop: false) the only categories kept
+    let discrete = spec.x_scale_spec.as_ref().and_then(|s| s.discrete.as_ref());
+    let kept_categories: Option<HashSet<&str>> = discrete
+        .filter(|d| d.drop)
+        .and_then(|d| d.limits.as_ref())
+        .map(|limits| limits.iter().map(String::as_str).collect());
+
+    // bar(width: column): one width per x category (its first row's value), for mosaic layouts
+    let mosaic_idx = match &layer_spec.original_layer {
+        Layer::Bar(BarLayer { width: Some(AestheticValue::Mapped(col)), .. }) => Some(find_col_index(&data.headers, col)?),
+        _ => None,
+    };
+    let mut raw_widths: HashMap<String, f64> = HashMap::new();
 
-    for row in &data.rows {
+    // Layers on a binned histogram's x start from its bins instead of the rows
+    let rows: &[Vec<String>] = if layer_spec.inherits_binned { &[] } else { &data.rows };
+    if layer_spec.inherits_binned {
+        raw_groups = context.binned.clone().unwrap_or_default();
+    }
+
+    for row in rows {
         let x_str = row[x_idx].clone();
-        let y_val = if let Some(idx) = y_idx { 
+        if kept_categories.as_ref().is_some_and(|kept| !kept.contains(x_str.as_str())) {
+            continue;
+        }
+        let y_val = if let (Some(idx), Some(categories)) = (y_idx, &wide.y_categories) {
+            categories.iter().position(|c| *c == row[idx]).unwrap_or(0) as f64
+        } else if let Some(idx) = y_idx {
             row[idx].parse::<f64>().context(format!("Failed to parse Y value '{}'", row[idx]))?
         } else { 
             0.0 // Default for histogram if not provided
@@ -150,6 +339,28 @@ fn process_layer(layer_spec: &ResolvedLayer, data: &PlotData) -> Result<LayerDat
             "default".to_string()
         };
 
+        if let Some(idx) = mosaic_idx {
+            if !raw_widths.contains_key(&x_str) {
+                let width = row[idx].parse::<f64>().context(format!("Failed to parse bar width '{}'", row[idx]))?;
+                if width < 0.0 || !width.is_finite() {
+                    bail!("bar(width: {}) values must be non-negative, got '{}'", data.headers[idx], row[idx]);
+                }
+                raw_widths.insert(x_str.clone(), width);
+            }
+        }
+
+        if let Some(idx) = label_idx {
+            raw_labels.entry(group_key.clone()).or_default().push(row[idx].clone());
+        }
+
+        if let Some(idx) = colors_idx {
+            raw_colors.entry(group_key.clone()).or_default().push(row[idx].trim().to_string());
+        }
+
+        if let Some(idx) = fill_idx {
+            raw_fills.entry(group_key.clone()).or_default().push(row[idx].parse::<f64>().unwrap_or(f64::NAN));
+        }
+
         let entry = raw_groups.entry(group_key).or_insert_with(|| (Vec::new(), Vec::new(), Vec::new(), Vec::new()));
         entry.0.push(x_str);
         entry.1.push(y_val);
@@ -157,8 +368,47 @@ fn process_layer(layer_spec: &ResolvedLayer, data: &PlotData) -> Result<LayerDat
         entry.3.push(ymax_val);
     }
 
+    // Hexbin lattice, fitted to the raw points before they are binned
+    let hex_grid = match (&layer_spec.original_layer, layer_spec.original_layer.stat()) {
+        (Layer::Hexbin(_), Stat::Hexbin { bins }) => Some(HexGrid::fit(&raw_groups, *bins)?),
+        _ => None,
+    };
+
     // Apply Statistics
-    let raw_groups = apply_statistics(raw_groups, layer_spec.original_layer.stat())?;
+    let raw_groups = match &layer_spec.original_layer {
+        // Proportions within each group, or of the grand total when filled/stacked
+        Layer::Bar(b) if matches!(b.stat, Stat::Prop) => {
+            compute_prop_stat(raw_groups, b.position.is_fill())?
+        }
+        // Summaries for hexbin(fill: ...) need the layer, and reuse the lattice fitted above
+        Layer::Hexbin(h) => match &hex_grid {
+            Some(grid) => compute_hexbin_stat(raw_groups, grid, h.fill)?,
+            None => apply_statistics(raw_groups, &h.stat)?,
+        },
+        // smooth(fullrange: true) is evaluated across the whole plot's x range
+        Layer::Line(LineLayer { stat: Stat::Smooth { method, n, residuals, formula, fullrange: true, se }, .. }) => {
+            compute_smooth_stat(raw_groups, method, *n, *residuals, formula.as_deref(), *se, x_range)?
+        }
+        layer => apply_statistics(raw_groups, layer.stat())?,
+    };
+
+    // tile(fill: column): cells of the full x × y grid absent from the data are appended to the
+    // first group and drawn in na_color (unless drop_na: true); value = index of its first such cell
+    let mut raw_groups = raw_groups;
+    let mut first_na_cell: HashMap<String, usize> = HashMap::new();
+    if let (Layer::Tile(TileLayer { drop_na: false, .. }), Some(_)) = (&layer_spec.original_layer, fill_idx) {
+        let missing = missing_tile_cells(&raw_groups);
+        if let Some(key) = get_sorted_keys(&raw_groups).into_iter().next() {
+            let group = raw_groups.get_mut(&key).unwrap();
+            first_na_cell.insert(key, group.x.len());
+            for (x, y) in missing {
+                group.x.push(x);
+                group.y.push(y);
+                group.ymin.push(0.0);
+                group.ymax.push(0.0);
+            }
+        }
+    }
 
     // 3. Determine X-Axis Type (Numeric vs Categorical)
     // Logic: If ALL x values in this layer can be parsed as float, it's numeric.
@@ -170,7 +420,7 @@ fn process_layer(layer_spec: &ResolvedLayer, data: &PlotData) -> Result<LayerDat
     let all_x_strings: Vec<&String> = raw_groups.values().flat_map(|d| d.x.iter()).collect();
     let all_numeric = all_x_strings.iter().all(|s| s.parse::<f64>().is_ok());
 
-    let use_categorical = is_bar || is_boxplot || is_violin || !all_numeric;
+    let use_categorical = is_bar || is_boxplot || is_violin || !all_numeric || discrete.is_some();
 
     // 4. Normalize X Values
     // If categorical, we need a unified mapping for stacking/grouping
@@ -198,28 +448,88 @@ fn process_layer(layer_spec: &ResolvedLayer, data: &PlotData) -> Result<LayerDat
         } else {
             category_order.sort();
         }
+
+        // Listed categories come first, in the given order (even when absent from this panel)
+        if let Some(limits) = discrete.and_then(|d| d.limits.as_ref()) {
+            let rest = category_order.into_iter().filter(|c| !limits.contains(c));
+            category_order = limits.iter().cloned().chain(rest).collect();
+        }
         
         for (i, cat) in category_order.iter().enumerate() {
             x_category_map.insert(cat.clone(), i as f64);
         }
     }
 
+    // histogram(stat_output_as: "binned"): share (bar position, count) per bin with later layers
+    if let Layer::Bar(BarLayer { stat_output_as: Some(StatOutput::Binned), .. }) = &layer_spec.original_layer {
+        context.binned = Some(raw_groups.iter().map(|(key, d)| {
+            let x = d.x.iter().map(|x| x_category_map[x].to_string()).collect();
+            (key.clone(), (x, d.y.clone(), d.ymin.clone(), d.ymax.clone()))
+        }).collect());
+    }
+
+    // Mosaic bars: widths normalized to fill all category slots, laid out contiguously from -0.5
+    let mut mosaic: HashMap<String, (f64, f64)> = HashMap::new(); // Category -> (center, width)
+    let mut x_category_positions = Vec::new();
+    if let Some(idx) = mosaic_idx {
+        let total: f64 = category_order.iter().filter_map(|c| raw_widths.get(c)).sum();
+        if total <= 0.0 {
+            bail!("bar(width: {}) needs at least one positive width", data.headers[idx]);
+        }
+        let slots = category_order.len() as f64;
+        let mut left = -0.5;
+        for cat in &category_order {
+            let width = raw_widths.get(cat).copied().unwrap_or(0.0) / total * slots;
+            mosaic.insert(cat.clone(), (left + width / 2.0, width));
+            x_category_positions.push(left + width / 2.0);
+            left += width;
+        }
+    }
+
     // 5. Build Groups (Styles & Coordinates)
     let mut groups = Vec::new();
     let sorted_group_keys = get_sorted_keys(&raw_groups);
     
     // Assign Palettes
-    let color_map = ColorPalette::category10().assign_colors(&sorted_group_keys);
-    let size_map = SizePalette::default_range().assign_sizes(&sorted_group_keys);
-    let shape_map = ShapePalette::default_shapes().assign_shapes(&sorted_group_keys);
+    let palettes = group_palettes(spec, aes, &sorted_group_keys, plot_keys, group_col)?;
+    let gradient = if fill_idx.is_some() { Some(fill_gradient(spec, false)?) } else { None };
+
+    // Tile size: explicit width/height, else the spacing of the x/y values
+    let tile_size = if let Layer::Tile(t) = &layer_spec.original_layer {
+        let width = t.width.unwrap_or_else(|| if use_categorical {
+            1.0
+        } else {
+            let xs: Vec<f64> = all_x_strings.iter().filter_map(|s| s.parse::<f64>().ok()).collect();
+            resolution(&xs)
+        });
+        let ys: Vec<f64> = raw_groups.values().flat_map(|d| d.y.iter().copied()).collect();
+        Some((width, t.height.unwrap_or_else(|| resolution(&ys))))
+    } else {
+        None
+    };
 
     // Prepare for Stacking (if needed)
     let mut stack_offsets: HashMap<String, f64> = HashMap::new(); // Map "X_Key" -> Current Height
-    let is_stacked = match &layer_spec.original_layer {
-        Layer::Bar(b) => matches!(b.position, BarPosition::Stack),
-        _ => false,
+    let (is_stacked, is_fill) = match &layer_spec.original_layer {
+        Layer::Bar(b) => (
+            matches!(b.position, LayerPosition::Stack) || b.position.is_fill(),
+            // Proportion bars are already normalized to the grand total
+            b.position.is_fill() && !matches!(b.stat, Stat::Prop),
+        ),
+        _ => (false, false),
     };
 
+    // Fill position: stack totals per x, used to normalize each stack to 1.0
+    let mut stack_totals: HashMap<String, f64> = HashMap::new();
+    if is_fill {
+        for stat_data in raw_groups.values() {
+            for (x_s, &y_val) in stat_data.x.iter().zip(stat_data.y.iter()) {
+                let stack_key = if use_categorical { x_s.clone() } else { x_s.parse::<f64>().unwrap().to_string() };
+                *stack_totals.entry(stack_key).or_default() += y_val;
+            }
+        }
+    }
+
     // Iterate groups in defined order (important for stacking order)
     for key in sorted_group_keys {
         let stat_data = raw_groups.get(&key).unwrap();
@@ -233,12 +543,18 @@ fn process_layer(layer_spec: &ResolvedLayer, data: &PlotData) -> Result<LayerDat
         let mut y_ends = Vec::with_capacity(raw_x.len());
         let mut y_mins = Vec::with_capacity(raw_x.len());
         let mut y_maxs = Vec::with_capacity(raw_x.len());
+        let mut widths = Vec::new();
         
         // Boxplot specific
         let mut y_q1s = Vec::new();
         let mut y_medians = Vec::new();
         let mut y_q3s = Vec::new();
+        let mut y_notch_lows = Vec::new();
+        let mut y_notch_highs = Vec::new();
         let mut outliers_vec = Vec::new();
+        let mut counts_vec = Vec::new();
+        let mut x_mins = Vec::new();
+        let mut x_maxs = Vec::new();
 
         // Violin specific
         let mut violin_density_vec: Vec<Vec<f64>> = Vec::new();
@@ -251,7 +567,10 @@ fn process_layer(layer_spec: &ResolvedLayer, data: &PlotData) -> Result<LayerDat
             let raw_max = raw_ymax[i];
             
             // Resolve X
-            let x_val = if use_categorical {
+            let x_val = if let Some(&(center, width)) = mosaic.get(x_s) {
+                widths.push(width);
+                center
+            } else if use_categorical {
                 *x_category_map.get(x_s).unwrap() // Should exist
             } else {
                 x_s.parse::<f64>().unwrap() // Verified numeric earlier
@@ -262,10 +581,38 @@ fn process_layer(layer_spec: &ResolvedLayer, data: &PlotData) -> Result<LayerDat
             let stack_key = if use_categorical { x_s.clone() } else { x_val.to_string() };
             
             let (y_start, y_end, y_min, y_max) = if is_stacked {
+                let y_val = if is_fill {
+                    match stack_totals.get(&stack_key) {
+                        Some(&total) if total != 0.0 => y_val / total,
+                        _ => 0.0,
+                    }
+                } else {
+                    y_val
+                };
                 let start = *stack_offsets.get(&stack_key).unwrap_or(&0.0);
                 let end = start + y_val;
                 stack_offsets.insert(stack_key, end);
                 (start, end, start, end)
+            } else if let Some((_, height)) = tile_size {
+                // Tiles span half their height around y
+                (y_val, y_val, y_val - height / 2.0, y_val + height / 2.0)
+            } else if hex_grid.is_some() {
+                // Hexagon centers, with their vertical extent from the stat
+                (y_val, y_val, raw_min, raw_max)
+            } else if matches!(layer_spec.original_layer.stat(), Stat::Summary { .. }) {
+                // Point at the summary, with its range from the stat
+                (0.0, y_val, raw_min, raw_max)
+            } else if matches!(layer_spec.original_layer.stat(), Stat::Smooth { se: SeType::Confidence(_) | SeType::Prediction(_), .. }) {
+                // smooth(se: ...): fitted line, with its interval band from the stat
+                (0.0, y_val, raw_min, raw_max)
+            } else if matches!(layer_spec.original_layer, Layer::Point(PointLayer { range: Some(_), .. })) && aes.xmin_col.is_some() {
+                // pointrange(xmin:, xmax:): point at (x, y), with its range along x
+                x_mins.push(raw_min);
+                x_maxs.push(raw_max);
+                (0.0, y_val, y_val, y_val)
+            } else if matches!(layer_spec.original_layer, Layer::Point(PointLayer { range: Some(_), .. })) {
+                // pointrange(): point at y, with its range from the ymin/ymax columns
+                (0.0, y_val, raw_min, raw_max)
             } else if matches!(layer_spec.original_layer, Layer::Ribbon(_)) || matches!(layer_spec.original_layer, Layer::Boxplot(_)) || matches!(layer_spec.original_layer, Layer::Violin(_)) {
                 // Ribbon, Boxplot, and Violin use raw ymin/ymax
                 (raw_min, raw_max, raw_min, raw_max)
@@ -284,13 +631,19 @@ fn process_layer(layer_spec: &ResolvedLayer, data: &PlotData) -> Result<LayerDat
                 y_q1s.push(bp.q1[i]);
                 y_medians.push(bp.median[i]);
                 y_q3s.push(bp.q3[i]);
+                y_notch_lows.push(bp.notch_low[i]);
+                y_notch_highs.push(bp.notch_high[i]);
                 outliers_vec.push(bp.outliers[i].clone());
+                counts_vec.push(bp.counts[i]);
             } else {
                  // Fill defaults to keep vectors aligned
                  y_q1s.push(0.0);
                  y_medians.push(0.0);
                  y_q3s.push(0.0);
+                 y_notch_lows.push(0.0);
+                 y_notch_highs.push(0.0);
                  outliers_vec.push(vec![]);
+                 counts_vec.push(stat_data.counts.as_ref().map_or(0, |c| c[i]));
             }
 
             // Collect violin stats if available
@@ -306,7 +659,42 @@ fn process_layer(layer_spec: &ResolvedLayer, data: &PlotData) -> Result<LayerDat
         }
 
         // Build Style
-        let style = build_style(key.clone(), &layer_spec.original_layer, aes, &color_map, &size_map, &shape_map);
+        let mut style = build_style(key.clone(), &layer_spec.original_layer, aes, &palettes);
+        if let (RenderStyle::Tile(t), Some((width, height))) = (&mut style, tile_size) {
+            t.width = width;
+            t.height = height;
+        }
+        if let (RenderStyle::Hex(h), Some(grid)) = (&mut style, &hex_grid) {
+            h.width = grid.sx;
+            h.radius = grid.radius();
+        }
+
+        let contours = stat_data.contours.clone().unwrap_or_default();
+        let fills = match (&gradient, raw_fills.get(&key)) {
+            (Some(gradient), Some(values)) => {
+                let mut fills: Vec<String> = values.iter()
+                    .map(|&v| gradient.map(v, fill_range.unwrap_or((0.0, 1.0))))
+                    .collect();
+                if let (Some(&first), Layer::Tile(t)) = (first_na_cell.get(&key), &layer_spec.original_layer) {
+                    fills.truncate(first);
+                    fills.resize(x_floats.len(), t.na_color.clone().unwrap_or_else(|| TileLayer::DEFAULT_NA_COLOR.to_string()));
+                }
+                fills
+            }
+            // contour(filled: true): bands shaded by their density level through the (linear) fill scale
+            _ if matches!(layer_spec.original_layer.stat(), Stat::Contour { filled: true, .. }) => {
+                let gradient = fill_gradient(spec, false)?;
+                let top = contours.iter().fold(0.0f64, |a, (level, _)| a.max(*level));
+                contours.iter().map(|(level, _)| gradient.map(*level, (0.0, top))).collect()
+            }
+            _ => raw_colors.get(&key).cloned().unwrap_or_default(),
+        };
+
+        let labels = match (&layer_spec.original_layer, raw_labels.remove(&key)) {
+            (_, Some(labels)) => labels,
+            (Layer::Text(TextLayer { label: Some(AestheticValue::Fixed(text)), .. }), None) => vec![text.clone(); x_floats.len()],
+            _ => Vec::new(),
+        };
 
         groups.push(GroupData {
             key: key.clone(),
@@ -315,27 +703,215 @@ fn process_layer(layer_spec: &ResolvedLayer, data: &PlotData) -> Result<LayerDat
             y_start: y_starts,
             y_min: y_mins,
             y_max: y_maxs,
+            x_min: x_mins,
+            x_max: x_maxs,
 
             y_q1: y_q1s,
             y_median: y_medians,
             y_q3: y_q3s,
+            y_notch_low: y_notch_lows,
+            y_notch_high: y_notch_highs,
             outliers: outliers_vec,
+            counts: counts_vec,
+            cell_values: stat_data.cell_values.clone().unwrap_or_default(),
 
             violin_density: violin_density_vec,
             violin_density_y: violin_density_y_vec,
             violin_quantile_values: violin_quantile_values_vec,
 
+            fills,
+            residuals: stat_data.residuals.clone().unwrap_or_default(),
+            contours,
+            widths,
+            labels,
+            ablines: vec![],
+            segment_ends: vec![],
+
             x_categories: if use_categorical { Some(category_order.clone()) } else { None },
+            x_category_positions: x_category_positions.clone(),
+            y_categories: wide.y_categories.clone(),
             style,
         });
     }
 
     Ok(LayerData { groups })
 }
-fn find_col_index(headers: &[String], name: &str) -> Result<usize> {
-    headers.iter()
-        .position(|h| h.eq_ignore_ascii_case(name))
-        .ok_or_else(|| anyhow!("Column '{}' not found", name))
+/// Column the layer's groups follow: the first mapped of color, size, shape, alpha and linetype
+pub(crate) fn group_column(aes: &ResolvedAesthetics) -> Option<&String> {
+    aes.color.as_ref()
+        .or(aes.size.as_ref())
+        .or(aes.shape.as_ref())
+        .or(aes.alpha.as_ref())
+        .or(aes.linetype.as_ref())
+}
+
+/// abline(): one group per group key, holding each distinct (slope, intercept) of its rows.
+/// The lines span the x scale, so the compiler computes their ends.
+fn process_abline(
+    layer_spec: &ResolvedLayer,
+    data: &PlotData,
+    spec: &ResolvedSpec,
+    plot_keys: &[String],
+    slope: &AestheticValue<f64>,
+    intercept: &AestheticValue<f64>,
+) -> Result<LayerData> {
+    let aes = &layer_spec.aesthetics;
+    let group_col = group_column(aes);
+    for value in [slope, intercept] {
+        if let AestheticValue::Mapped(col) = value {
+            find_col_index(&data.headers, col)?;
+        }
+    }
+    let group_idx = group_col.map(|g| find_col_index(&data.headers, g)).transpose()?;
+    let read = |value: &AestheticValue<f64>, row: &[String]| -> Result<f64> {
+        match value {
+            AestheticValue::Fixed(v) => Ok(*v),
+            AestheticValue::Mapped(col) => {
+                let cell = &row[find_col_index(&data.headers, col)?];
+                cell.trim().parse::<f64>().context(format!("Failed to parse abline() value '{}' in column '{}'", cell, col))
+            }
+        }
+    };
+
+    // Fixed slope and intercept: a single line, whatever the rows
+    let single = [Vec::new()];
+    let fixed = group_idx.is_none() && matches!((slope, intercept), (AestheticValue::Fixed(_), AestheticValue::Fixed(_)));
+    let rows: &[Vec<String>] = if fixed { &single } else { &data.rows };
+
+    let mut lines: HashMap<String, Vec<(f64, f64)>> = HashMap::new();
+    for row in rows {
+        let line = (read(slope, row)?, read(intercept, row)?);
+        let key = group_idx.map_or_else(|| "default".to_string(), |idx| row[idx].clone());
+        let group = lines.entry(key).or_default();
+        if !group.contains(&line) {
+            group.push(line);
+        }
+    }
+
+    let keys = get_sorted_keys(&lines);
+    let palettes = group_palettes(spec, aes, &keys, plot_keys, group_col)?;
+    let groups = keys.into_iter().map(|key| GroupData {
+        style: build_style(key.clone(), &layer_spec.original_layer, aes, &palettes),
+        ablines: lines.remove(&key).unwrap_or_default(),
+        segment_ends: vec![],
+        key,
+        x: vec![],
+        y: vec![],
+        y_start: vec![],
+        y_min: vec![],
+        y_max: vec![],
+        x_min: vec![],
+        x_max: vec![],
+        y_q1: vec![],
+        y_median: vec![],
+        y_q3: vec![],
+        y_notch_low: vec![],
+        y_notch_high: vec![],
+        outliers: vec![],
+        counts: vec![],
+        cell_values: vec![],
+        violin_density: vec![],
+        violin_density_y: vec![],
+        violin_quantile_values: vec![],
+        fills: vec![],
+        residuals: vec![],
+        contours: vec![],
+        widths: vec![],
+        labels: vec![],
+        x_categories: None,
+        x_category_positions: vec![],
+        y_categories: None,
+    }).collect();
+
+    Ok(LayerData { groups })
+}
+
+/// segment(): one line per row from (x, y) to (xend, yend), grouped like any other layer
+fn process_segment(layer_spec: &ResolvedLayer, data: &PlotData, spec: &ResolvedSpec, plot_keys: &[String], xend: &str, yend: &str) -> Result<LayerData> {
+    let aes = &layer_spec.aesthetics;
+    let group_col = group_column(aes);
+    let Some(y_col) = &aes.y_col else { bail!("segment() requires a y aesthetic") };
+    // (name, index) of the x, y, xend and yend columns
+    let cols = [aes.x_col.as_str(), y_col, xend, yend].into_iter()
+        .map(|col| Ok((col, find_col_index(&data.headers, col)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let group_idx = group_col.map(|g| find_col_index(&data.headers, g)).transpose()?;
+
+    // (start, end) of each segment per group key, in row order
+    let mut segments: HashMap<String, Vec<[(f64, f64); 2]>> = HashMap::new();
+    for row in &data.rows {
+        let mut values = [0.0; 4];
+        for (value, &(col, idx)) in values.iter_mut().zip(&cols) {
+            *value = row[idx].trim().parse::<f64>().context(format!("Failed to parse segment() value '{}' in column '{}'", row[idx], col))?;
+        }
+        let key = group_idx.map_or_else(|| "default".to_string(), |idx| row[idx].clone());
+        segments.entry(key).or_default().push([(values[0], values[1]), (values[2], values[3])]);
+    }
+
+    let keys = get_sorted_keys(&segments);
+    let palettes = group_palettes(spec, aes, &keys, plot_keys, group_col)?;
+    let groups = keys.into_iter().map(|key| {
+        let lines = segments.remove(&key).unwrap_or_default();
+        GroupData {
+            style: build_style(key.clone(), &layer_spec.original_layer, aes, &palettes),
+            x: lines.iter().map(|[start, _]| start.0).collect(),
+            y: lines.iter().map(|[start, _]| start.1).collect(),
+            segment_ends: lines.iter().map(|&[_, end]| end).collect(),
+            key,
+            y_start: vec![],
+            y_min: vec![],
+            y_max: vec![],
+            x_min: vec![],
+            x_max: vec![],
+            y_q1: vec![],
+            y_median: vec![],
+            y_q3: vec![],
+            y_notch_low: vec![],
+            y_notch_high: vec![],
+            outliers: vec![],
+            counts: vec![],
+            cell_values: vec![],
+            violin_density: vec![],
+            violin_density_y: vec![],
+            violin_quantile_values: vec![],
+            fills: vec![],
+            residuals: vec![],
+            contours: vec![],
+            widths: vec![],
+            labels: vec![],
+            ablines: vec![],
+            x_categories: None,
+            x_category_positions: vec![],
+            y_categories: None,
+        }
+    }).collect();
+
+    Ok(LayerData { groups })
+}
+
+/// (x, y) cells of the grid of all x values × all y values with no tile in any group
+fn missing_tile_cells(groups: &HashMap<String, StatData>) -> Vec<(String, f64)> {
+    // Values in order of first appearance, so the appended cells are deterministic
+    let (mut xs, mut ys) = (Vec::new(), Vec::new());
+    let (mut seen_x, mut seen_y) = (HashSet::new(), HashSet::new());
+    let mut present: HashSet<(&str, u64)> = HashSet::new();
+    for key in get_sorted_keys(groups) {
+        let group = &groups[&key];
+        for (x, &y) in group.x.iter().zip(&group.y) {
+            if seen_x.insert(x.as_str()) {
+                xs.push(x.as_str());
+            }
+            if seen_y.insert(y.to_bits()) {
+                ys.push(y);
+            }
+            present.insert((x.as_str(), y.to_bits()));
+        }
+    }
+    xs.iter()
+        .flat_map(|&x| ys.iter().map(move |&y| (x, y)))
+        .filter(|&(x, y)| !present.contains(&(x, y.to_bits())))
+        .map(|(x, y)| (x.to_string(), y))
+        .collect()
 }
 
 fn get_sorted_keys<V>(map: &HashMap<String, V>) -> Vec<String> {
@@ -344,17 +920,81 @@ fn get_sorted_keys<V>(map: &HashMap<String, V>) -> Vec<String> {
     keys
 }
 
+/// Palette value per group key for each grouping aesthetic
+struct GroupPalettes {
+    color: HashMap<String, String>,
+    size: HashMap<String, f64>,
+    shape: HashMap<String, String>,
+    linetype: HashMap<String, String>,
+}
+
+/// Palettes are assigned over the plot-wide keys (plus this panel's own, e.g. stat-made groups)
+/// so each group gets the same value in every facet
+fn group_palettes(spec: &ResolvedSpec, aes: &ResolvedAesthetics, keys: &[String], plot_keys: &[String], group_col: Option<&String>) -> Result<GroupPalettes> {
+    let mut all_keys: Vec<String> = plot_keys.iter().chain(keys).cloned().collect();
+    all_keys.sort();
+    all_keys.dedup();
+    let keys = &all_keys[..];
+    let color = match (&spec.color_scale, &aes.color) {
+        (Some(ColorScale::Identity), Some(col)) => {
+            identity_map(keys, "color", col, group_col, |v| parse_color(v).is_some(), "a color name or hex value")?
+        }
+        (Some(ColorScale::Brewer { palette }), _) => ColorPalette::brewer(palette, keys.len())?.assign_colors(keys),
+        _ => ColorPalette::category10().assign_colors(keys),
+    };
+    let size = SizePalette::default_range().assign_sizes(keys);
+    let shape = match &aes.shape {
+        Some(col) if spec.identity_scales.shape => identity_map(keys, "shape", col, group_col, ShapePalette::is_shape, "a shape name")?,
+        _ => ShapePalette::default_shapes().assign_shapes(keys),
+    };
+    let linetype = match (&aes.linetype, &spec.linetype_values) {
+        (Some(col), _) if spec.identity_scales.linetype => {
+            identity_map(keys, "linetype", col, group_col, LinetypePalette::is_linetype, "a linetype name")?
+        }
+        (_, Some(values)) => LinetypePalette::new(values.clone()).assign_linetypes(keys),
+        (_, None) => LinetypePalette::default_types().assign_linetypes(keys),
+    };
+    Ok(GroupPalettes { color, size, shape, linetype })
+}
+
+/// scale_*_identity(): each group key (a value of the mapped column) is its own color, shape or linetype.
+/// Groups follow a single column, so the aesthetic must be the one the layer groups by, and
+/// every value must pass `valid` (described as `expected` in the error).
+fn identity_map(
+    keys: &[String],
+    aesthetic: &str,
+    col: &str,
+    group_col: Option<&String>,
+    valid: fn(&str) -> bool,
+    expected: &str,
+) -> Result<HashMap<String, String>> {
+    if let Some(grouped_by) = group_col.filter(|g| *g != col) {
+        bail!(
+            "scale_{}_identity() needs the layer grouped by '{}', but it is grouped by '{}'; map the other aesthetics to the same column",
+            aesthetic, col, grouped_by
+        );
+    }
+    if let Some(bad) = keys.iter().find(|k| !valid(k.trim())) {
+        bail!("scale_{}_identity(): '{}' in column '{}' is not {}", aesthetic, bad, col, expected);
+    }
+    Ok(keys.iter().map(|k| (k.clone(), k.trim().to_string())).collect())
+}
+
+/// Ribbon opacity when alpha: is not given
+const DEFAULT_RIBBON_ALPHA: f64 = 0.3;
+
 fn build_style(
     group_key: String,
     layer: &Layer,
     aes: &ResolvedAesthetics,
-    color_map: &HashMap<String, String>,
-    size_map: &HashMap<String, f64>,
-    shape_map: &HashMap<String, String>,
+    palettes: &GroupPalettes,
 ) -> RenderStyle {
+    let GroupPalettes { color: color_map, size: size_map, shape: shape_map, linetype: linetype_map } = palettes;
     // Helper to pick color: GroupMapped ?? Fixed ?? Default
+    // Quantile lines are told apart by color even without a color aesthetic
+    let colored_by_group = aes.color.is_some() || matches!(layer.stat(), Stat::Quantile { .. });
     let pick_color = |l_color: &Option<crate::parser::ast::AestheticValue<String>>| -> Option<String> {
-        if aes.color.is_some() && color_map.contains_key(&group_key) {
+        if colored_by_group && color_map.contains_key(&group_key) {
              color_map.get(&group_key).cloned()
         } else {
             // Check fixed
@@ -392,6 +1032,14 @@ fn build_style(
             color: pick_color(&l.color),
             width: pick_size(&l.width),
             alpha: pick_alpha(&l.alpha),
+            linetype: if aes.linetype.is_some() && linetype_map.contains_key(&group_key) {
+                linetype_map.get(&group_key).cloned()
+            } else {
+                match &l.linetype {
+                    Some(crate::parser::ast::AestheticValue::Fixed(t)) => Some(t.clone()),
+                    _ => None,
+                }
+            },
         }),
         Layer::Point(p) => RenderStyle::Point(PointStyle {
             color: pick_color(&p.color),
@@ -405,19 +1053,32 @@ fn build_style(
                 }
             },
             alpha: pick_alpha(&p.alpha),
+            fill: p.fill.clone(),
         }),
         Layer::Bar(b) => RenderStyle::Bar(BarStyle {
             color: pick_color(&b.color),
             width: pick_size(&b.width),
             alpha: pick_alpha(&b.alpha),
+            // border_width: alone outlines in black; 0 turns the outline off
+            border_color: match b.border_width {
+                Some(w) if w <= 0.0 => None,
+                w => b.border.clone().or_else(|| w.map(|_| "black".to_string())),
+            },
+            border_width: b.border_width,
+            hollow: b.hollow,
         }),
         Layer::Ribbon(r) => RenderStyle::Ribbon(RibbonStyle {
             color: pick_color(&r.color),
-            alpha: pick_alpha(&r.alpha),
+            // Semi-transparent so overlapping bands (one per color group) stay readable
+            alpha: pick_alpha(&r.alpha).or(Some(DEFAULT_RIBBON_ALPHA)),
         }),
         Layer::Boxplot(b) => RenderStyle::Boxplot(crate::graph::BoxplotStyle {
             color: pick_color(&b.color),
             width: pick_size(&b.width),
+            varwidth: b.varwidth,
+            notch: b.notch,
+            notchwidth: b.notchwidth,
+            staplewidth: b.staplewidth,
             alpha: pick_alpha(&b.alpha),
             outlier_color: b.outlier_color.clone(),
             outlier_size: b.outlier_size,
@@ -428,6 +1089,29 @@ fn build_style(
             width: pick_size(&v.width),
             alpha: pick_alpha(&v.alpha),
             draw_quantiles: v.draw_quantiles.clone(),
+            side: v.side,
+        }),
+        Layer::Tile(t) => RenderStyle::Tile(TileStyle {
+            fill: match &t.fill {
+                Some(crate::parser::ast::AestheticValue::Fixed(c)) => Some(c.clone()),
+                _ => None,
+            },
+            border: t.color.clone(),
+            alpha: t.alpha,
+            width: t.width.unwrap_or(1.0),
+            height: t.height.unwrap_or(1.0),
+        }),
+        // Cell size comes from the hexbin lattice (see process_layer)
+        Layer::Hexbin(h) => RenderStyle::Hex(HexStyle {
+            border: h.color.clone(),
+            alpha: h.alpha,
+            width: 0.0,
+            radius: 0.0,
+        }),
+        Layer::Text(t) => RenderStyle::Text(TextGeomStyle {
+            color: pick_color(&t.color),
+            size: t.size.unwrap_or(TextLayer::DEFAULT_SIZE),
+            alpha: t.alpha,
         }),
     }
 }
@@ -437,7 +1121,10 @@ struct BoxplotData {
     q1: Vec<f64>,
     median: Vec<f64>,
     q3: Vec<f64>,
+    notch_low: Vec<f64>,
+    notch_high: Vec<f64>,
     outliers: Vec<Vec<f64>>,
+    counts: Vec<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -455,6 +1142,10 @@ struct StatData {
     ymax: Vec<f64>,
     boxplot: Option<BoxplotData>,
     violin: Option<ViolinData>,
+    counts: Option<Vec<usize>>, // Points per hexbin cell
+    cell_values: Option<Vec<f64>>, // hexbin(fill: ...) summary per cell
+    residuals: Option<Vec<(f64, f64)>>, // (x, observed - fitted) per input point, for smooth(residuals: true)
+    contours: Option<Vec<ContourPath>>, // (density level, path) per contour line or filled band polygon
 }
 
 impl StatData {
@@ -466,12 +1157,16 @@ impl StatData {
             ymax: t.3,
             boxplot: None,
             violin: None,
+            counts: None,
+            cell_values: None,
+            residuals: None,
+            contours: None,
         }
     }
 }
 
 fn compute_boxplot_stat(
-    groups: HashMap<String, (Vec<String>, Vec<f64>, Vec<f64>, Vec<f64>)>
+    groups: RawGroups
 ) -> Result<HashMap<String, StatData>> {
     let mut new_groups = HashMap::new();
 
@@ -495,7 +1190,10 @@ fn compute_boxplot_stat(
         let mut res_q1 = Vec::new();
         let mut res_median = Vec::new();
         let mut res_q3 = Vec::new();
+        let mut res_notch_low = Vec::new();
+        let mut res_notch_high = Vec::new();
         let mut res_outliers = Vec::new();
+        let mut res_counts = Vec::new();
 
         for x_val in unique_x {
             let mut ys = x_groups[&x_val].clone();
@@ -518,13 +1216,19 @@ fn compute_boxplot_stat(
             // Outliers
             let outliers: Vec<f64> = ys.iter().filter(|&&v| v < lower_fence || v > upper_fence).cloned().collect();
 
+            // Notches: ~95% confidence interval of the median (McGill et al. 1978)
+            let notch_half = 1.58 * iqr / (ys.len() as f64).sqrt();
+
             res_x.push(x_val);
             res_min.push(*lower_whisker);
             res_max.push(*upper_whisker);
             res_q1.push(q1);
             res_median.push(median);
             res_q3.push(q3);
+            res_notch_low.push(median - notch_half);
+            res_notch_high.push(median + notch_half);
             res_outliers.push(outliers);
+            res_counts.push(ys.len());
         }
 
         // Y in StatData usually represents the "main" value. For boxplot, maybe median?
@@ -541,9 +1245,16 @@ fn compute_boxplot_stat(
                 q1: res_q1,
                 median: res_median,
                 q3: res_q3,
+                notch_low: res_notch_low,
+                notch_high: res_notch_high,
                 outliers: res_outliers,
+                counts: res_counts,
             }),
             violin: None,
+            counts: None,
+            cell_values: None,
+            residuals: None,
+            contours: None,
         });
     }
 
@@ -572,13 +1283,70 @@ fn silverman_bandwidth(data: &[f64]) -> f64 {
     0.9 * scale * n.powf(-0.2)
 }
 
+/// Scott's rule of thumb: h = 1.06 * std * n^(-1/5)
+fn scott_bandwidth(data: &[f64]) -> f64 {
+    let n = data.len() as f64;
+    if n < 2.0 { return 1.0; }
+
+    let std_dev = sample_std_dev(data);
+    if std_dev <= 0.0 { return 1.0; }
+    1.06 * std_dev * n.powf(-0.2)
+}
+
+/// Unbiased cross-validation: minimize the UCV score over a grid of bandwidths
+fn ucv_bandwidth(data: &[f64]) -> f64 {
+    const CANDIDATES: usize = 100;
+
+    let n = data.len() as f64;
+    let std_dev = sample_std_dev(data);
+    if n < 2.0 || std_dev <= 0.0 { return silverman_bandwidth(data); }
+
+    // Same search interval as R's bw.ucv
+    let upper = 1.144 * std_dev * n.powf(-0.2);
+    let lower = 0.1 * upper;
+
+    let ucv = |h: f64| {
+        let mut sum = 0.0;
+        for (i, a) in data.iter().enumerate() {
+            for b in &data[i + 1..] {
+                let delta = ((a - b) / h).powi(2);
+                sum += (-delta / 4.0).exp() - 8f64.sqrt() * (-delta / 2.0).exp();
+            }
+        }
+        (0.5 + sum / n) / (n * h * std::f64::consts::PI.sqrt())
+    };
+
+    (0..CANDIDATES)
+        .map(|i| lower + (upper - lower) * i as f64 / (CANDIDATES - 1) as f64)
+        .map(|h| (h, ucv(h)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(h, _)| h)
+        .unwrap_or(upper)
+}
+
+fn sample_std_dev(data: &[f64]) -> f64 {
+    let n = data.len() as f64;
+    let mean = data.iter().sum::<f64>() / n;
+    (data.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+}
+
+/// Bandwidth for a group according to the requested rule
+fn select_bandwidth(data: &[f64], method: &BandwidthMethod) -> f64 {
+    match method {
+        BandwidthMethod::Nrd0 => silverman_bandwidth(data),
+        BandwidthMethod::Nrd => scott_bandwidth(data),
+        BandwidthMethod::Ucv => ucv_bandwidth(data),
+        BandwidthMethod::Fixed(h) => *h,
+    }
+}
+
 /// Gaussian kernel function
 fn gaussian_kernel(u: f64) -> f64 {
     const SQRT_2PI: f64 = 2.5066282746310002;
     (-0.5 * u * u).exp() / SQRT_2PI
 }
 
-/// Compute Gaussian KDE at grid points
+/// Compute Gaussian KDE at grid points (unnormalized density)
 fn compute_kde(data: &[f64], bandwidth: f64) -> (Vec<f64>, Vec<f64>) {
     const GRID_POINTS: usize = 128;  // Resolution of density curve
 
@@ -604,31 +1372,25 @@ fn compute_kde(data: &[f64], bandwidth: f64) -> (Vec<f64>, Vec<f64>) {
         let y = y_start + i as f64 * step;
         grid_y.push(y);
 
-        // Gaussian kernel density estimation
-        let mut d = 0.0;
-        for &xi in data {
-            let u = (y - xi) / bandwidth;
-            d += gaussian_kernel(u);
-        }
-        d /= n * bandwidth;
-        density.push(d);
-    }
-
-    // Normalize density to 0-1 range for rendering
-    let max_density = density.iter().fold(0.0f64, |a, &b| a.max(b));
-    if max_density > 0.0 {
-        for d in &mut density {
-            *d /= max_density;
-        }
+        density.push(kde_at(data, bandwidth, y));
     }
 
     (grid_y, density)
 }
 
+/// Gaussian kernel density estimate of `data` at `y`
+fn kde_at(data: &[f64], bandwidth: f64, y: f64) -> f64 {
+    let d: f64 = data.iter().map(|&xi| gaussian_kernel((y - xi) / bandwidth)).sum();
+    d / (data.len() as f64 * bandwidth)
+}
+
 /// Compute violin statistics using KDE
 fn compute_violin_stat(
-    groups: HashMap<String, (Vec<String>, Vec<f64>, Vec<f64>, Vec<f64>)>,
+    groups: RawGroups,
     draw_quantiles: &[f64],
+    bw: &BandwidthMethod,
+    scale: &ViolinScale,
+    trim: bool,
 ) -> Result<HashMap<String, StatData>> {
     let mut new_groups = HashMap::new();
 
@@ -661,11 +1423,16 @@ fn compute_violin_stat(
             let max_y = sorted_ys[sorted_ys.len() - 1];
             let median = percentile(&sorted_ys, 0.5);
 
-            // Compute bandwidth using Silverman's rule
-            let bandwidth = silverman_bandwidth(&sorted_ys);
+            let bandwidth = select_bandwidth(&sorted_ys, bw);
 
             // Compute KDE
-            let (grid_y, density) = compute_kde(&sorted_ys, bandwidth);
+            let (grid_y, mut density) = compute_kde(&sorted_ys, bandwidth);
+            match scale {
+                // Normalize each violin to 0-1 on its own
+                ViolinScale::Width => normalize_max(density.iter_mut()),
+                // Weight by the square root of the group size; normalized across all violins below
+                ViolinScale::Count => density.iter_mut().for_each(|d| *d *= (sorted_ys.len() as f64).sqrt()),
+            }
 
             // Compute actual data percentiles for requested quantiles
             let quantile_y_values: Vec<f64> = draw_quantiles
@@ -673,10 +1440,17 @@ fn compute_violin_stat(
                 .map(|&q| percentile(&sorted_ys, q))
                 .collect();
 
+            // The compiler cuts the violin outline at ymin/ymax: the data range when
+            // trimming, otherwise the full KDE grid so the tails are drawn
+            let (lower, upper) = match (trim, grid_y.first(), grid_y.last()) {
+                (false, Some(&lo), Some(&hi)) => (lo, hi),
+                _ => (min_y, max_y),
+            };
+
             res_x.push(x_val);
             res_y.push(median);
-            res_min.push(min_y);
-            res_max.push(max_y);
+            res_min.push(lower);
+            res_max.push(upper);
             density_vec.push(density);
             density_y_vec.push(grid_y);
             quantile_values_vec.push(quantile_y_values);
@@ -693,12 +1467,33 @@ fn compute_violin_stat(
                 density_y: density_y_vec,
                 quantile_values: quantile_values_vec,
             }),
+            counts: None,
+            cell_values: None,
+            residuals: None,
+            contours: None,
         });
     }
 
+    if *scale == ViolinScale::Count {
+        normalize_max(new_groups.values_mut()
+            .filter_map(|g| g.violin.as_mut())
+            .flat_map(|v| v.density.iter_mut().flatten()));
+    }
+
     Ok(new_groups)
 }
 
+/// Scale values in place so the largest becomes 1.0
+fn normalize_max<'a>(values: impl Iterator<Item = &'a mut f64>) {
+    let values: Vec<&mut f64> = values.collect();
+    let max = values.iter().fold(0.0f64, |a, b| a.max(**b));
+    if max > 0.0 {
+        for v in values {
+            *v /= max;
+        }
+    }
+}
+
 fn percentile(sorted_data: &[f64], p: f64) -> f64 {
     let n = sorted_data.len();
     if n == 0 { return 0.0; }
@@ -717,21 +1512,192 @@ fn percentile(sorted_data: &[f64], p: f64) -> f64 {
 }
 
 fn apply_statistics(
-    groups: HashMap<String, (Vec<String>, Vec<f64>, Vec<f64>, Vec<f64>)>,
+    groups: RawGroups,
     stat: &Stat
 ) -> Result<HashMap<String, StatData>> {
     match stat {
-        Stat::Identity => Ok(groups.into_iter().map(|(k, v)| (k, StatData::from_tuple(v))).collect()),
+        // beeswarm() keeps the raw rows; the compiler spreads them once pixel sizes are known
+        Stat::Identity | Stat::Crosshair | Stat::Beeswarm { .. } => Ok(groups.into_iter().map(|(k, v)| (k, StatData::from_tuple(v))).collect()),
         Stat::Bin { bins } => compute_bin_stat(groups, *bins),
         Stat::Count => compute_count_stat(groups),
-        Stat::Smooth { method } => compute_smooth_stat(groups, method),
+        Stat::Prop => compute_prop_stat(groups, false),
+        Stat::Sum => compute_sum_stat(groups),
+        Stat::Mean => compute_mean_stat(groups),
+        Stat::Max => compute_max_stat(groups),
+        Stat::Min => compute_min_stat(groups),
+        Stat::Smooth { method, n, residuals, formula, se, .. } => compute_smooth_stat(groups, method, *n, *residuals, formula.as_deref(), *se, None),
+        Stat::Quantile { quantiles } => compute_quantile_stat(groups, quantiles),
+        Stat::Ecdf { pad, n } => compute_ecdf_stat(groups, *pad, *n),
+        Stat::Rug { .. } => Ok(compute_rug_stat(groups)),
+        Stat::Density { bw, n, .. } => compute_density_stat(groups, bw, *n),
+        Stat::Contour { bins, filled, bandwidth_x, bandwidth_y } => compute_contour_stat(groups, *bins, *filled, (*bandwidth_x, *bandwidth_y)),
+        Stat::Summary { fun, fun_ymin, fun_ymax } => compute_summary_stat(groups, fun, fun_ymin, fun_ymax),
         Stat::Boxplot => compute_boxplot_stat(groups),
-        Stat::Violin { draw_quantiles } => compute_violin_stat(groups, draw_quantiles),
+        Stat::Violin { draw_quantiles, bw, scale, trim } => compute_violin_stat(groups, draw_quantiles, bw, scale, *trim),
+        Stat::Hexbin { bins } => {
+            let grid = HexGrid::fit(&groups, *bins)?;
+            compute_hexbin_stat(groups, &grid, HexSummary::Count)
+        }
+        Stat::Abline { .. } => unreachable!("abline() layers are built by process_abline"),
+        Stat::Segment { .. } => unreachable!("segment() layers are built by process_segment"),
+    }
+}
+
+/// Row spacing of the hexagon lattice, in units of the cell height `sy`
+const HEX_ROW_SPACING: f64 = 0.866_025_403_784_438_6; // sqrt(3) / 2
+
+/// Pointy-top hexagon lattice over the x/y range: `bins` columns span the x range
+/// and rows are spaced so the cells are regular hexagons when both axes are
+/// drawn with the same number of data units per `sx`/`sy`.
+#[derive(Debug, Clone, Copy)]
+struct HexGrid {
+    x0: f64,
+    y0: f64,
+    sx: f64, // Column spacing (flat-to-flat hexagon width) in x units
+    sy: f64, // Cell height scale in y units
+}
+
+impl HexGrid {
+    fn fit(groups: &RawGroups, bins: usize) -> Result<Self> {
+        let mut xs = Vec::new();
+        for (x_strs, _, _, _) in groups.values() {
+            for s in x_strs {
+                xs.push(s.parse::<f64>().map_err(|_| anyhow!("hexbin() needs a numeric x ('{}' is not a number)", s))?);
+            }
+        }
+        let ys: Vec<f64> = groups.values().flat_map(|g| g.1.iter().copied()).collect();
+        if xs.is_empty() {
+            return Ok(HexGrid { x0: 0.0, y0: 0.0, sx: 1.0, sy: 1.0 });
+        }
+
+        let bounds = |v: &[f64]| (
+            v.iter().cloned().fold(f64::INFINITY, f64::min),
+            v.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+        );
+        let step = |(lo, hi): (f64, f64)| if hi > lo { (hi - lo) / bins as f64 } else { 1.0 };
+        let (x_bounds, y_bounds) = (bounds(&xs), bounds(&ys));
+        Ok(HexGrid { x0: x_bounds.0, y0: y_bounds.0, sx: step(x_bounds), sy: step(y_bounds) })
+    }
+
+    /// (column, row) of the cell whose center is nearest to the point (as in d3-hexbin)
+    fn cell(&self, x: f64, y: f64) -> (i64, i64) {
+        let py = (y - self.y0) / self.sy / HEX_ROW_SPACING;
+        let mut pj = py.round();
+        let px = (x - self.x0) / self.sx - (pj as i64 & 1) as f64 / 2.0;
+        let mut pi = px.round();
+        let py1 = py - pj;
+
+        // Near a row boundary the point may be closer to a center in the neighboring row
+        if py1.abs() * 3.0 > 1.0 {
+            let px1 = px - pi;
+            let pi2 = pi + if px < pi { -0.5 } else { 0.5 };
+            let pj2 = pj + if py < pj { -1.0 } else { 1.0 };
+            let px2 = px - pi2;
+            let py2 = py - pj2;
+            let dist = |dx: f64, dy: f64| dx * dx + (dy * HEX_ROW_SPACING).powi(2);
+            if dist(px1, py1) > dist(px2, py2) {
+                pi = pi2 + if pj as i64 & 1 == 1 { 0.5 } else { -0.5 };
+                pj = pj2;
+            }
+        }
+        (pi as i64, pj as i64)
+    }
+
+    fn center(&self, (i, j): (i64, i64)) -> (f64, f64) {
+        (
+            self.x0 + (i as f64 + (j & 1) as f64 / 2.0) * self.sx,
+            self.y0 + j as f64 * HEX_ROW_SPACING * self.sy,
+        )
+    }
+
+    /// Center-to-vertex distance in y units
+    fn radius(&self) -> f64 {
+        self.sy / 3f64.sqrt()
+    }
+}
+
+/// Points and y values that fell into one hexbin cell
+#[derive(Default)]
+struct HexCell {
+    count: usize,
+    sum_y: f64,
+    min_y: f64,
+    max_y: f64,
+}
+
+impl HexCell {
+    fn add(&mut self, y: f64) {
+        if self.count == 0 {
+            (self.min_y, self.max_y) = (y, y);
+        }
+        self.count += 1;
+        self.sum_y += y;
+        self.min_y = self.min_y.min(y);
+        self.max_y = self.max_y.max(y);
+    }
+
+    fn value(&self, summary: HexSummary) -> f64 {
+        match summary {
+            HexSummary::Count => self.count as f64,
+            HexSummary::MeanY => self.sum_y / self.count as f64,
+            HexSummary::SumY => self.sum_y,
+            HexSummary::MinY => self.min_y,
+            HexSummary::MaxY => self.max_y,
+        }
+    }
+}
+
+/// Count points per hexagonal cell; x/y become cell centers, ymin/ymax their vertical extent,
+/// and each cell's `fill` summary of its y values is kept for the fill scale
+fn compute_hexbin_stat(groups: RawGroups, grid: &HexGrid, fill: HexSummary) -> Result<HashMap<String, StatData>> {
+    let mut new_groups = HashMap::new();
+
+    for (key, (x_strs, ys, _, _)) in groups {
+        let mut cells: HashMap<(i64, i64), HexCell> = HashMap::new();
+        for (x_s, &y) in x_strs.iter().zip(ys.iter()) {
+            let x = x_s.parse::<f64>().map_err(|_| anyhow!("hexbin() needs a numeric x ('{}' is not a number)", x_s))?;
+            cells.entry(grid.cell(x, y)).or_default().add(y);
+        }
+
+        // Row-major order keeps output deterministic
+        let mut keys: Vec<(i64, i64)> = cells.keys().copied().collect();
+        keys.sort_by_key(|&(i, j)| (j, i));
+
+        let mut res_x = Vec::new();
+        let mut res_y = Vec::new();
+        let mut res_min = Vec::new();
+        let mut res_max = Vec::new();
+        let mut res_counts = Vec::new();
+        let mut res_values = Vec::new();
+        for cell in keys {
+            let (cx, cy) = grid.center(cell);
+            res_x.push(cx.to_string());
+            res_y.push(cy);
+            res_min.push(cy - grid.radius());
+            res_max.push(cy + grid.radius());
+            res_counts.push(cells[&cell].count);
+            res_values.push(cells[&cell].value(fill));
+        }
+
+        new_groups.insert(key, StatData {
+            x: res_x,
+            y: res_y,
+            ymin: res_min,
+            ymax: res_max,
+            boxplot: None,
+            violin: None,
+            counts: Some(res_counts),
+            cell_values: Some(res_values),
+            residuals: None,
+            contours: None,
+        });
     }
+
+    Ok(new_groups)
 }
 
 fn compute_count_stat(
-    groups: HashMap<String, (Vec<String>, Vec<f64>, Vec<f64>, Vec<f64>)>
+    groups: RawGroups
 ) -> Result<HashMap<String, StatData>> {
     let mut new_groups = HashMap::new();
     
@@ -763,47 +1729,758 @@ fn compute_count_stat(
     Ok(new_groups)
 }
 
-fn compute_smooth_stat(
-    groups: HashMap<String, (Vec<String>, Vec<f64>, Vec<f64>, Vec<f64>)>,
-    _method: &str
+fn compute_sum_stat(groups: RawGroups) -> Result<HashMap<String, StatData>> {
+    compute_aggregate_stat(groups, |ys| ys.iter().sum())
+}
+
+fn compute_mean_stat(groups: RawGroups) -> Result<HashMap<String, StatData>> {
+    compute_aggregate_stat(groups, |ys| ys.iter().sum::<f64>() / ys.len() as f64)
+}
+
+fn compute_max_stat(groups: RawGroups) -> Result<HashMap<String, StatData>> {
+    compute_aggregate_stat(groups, |ys| ys.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+}
+
+fn compute_min_stat(groups: RawGroups) -> Result<HashMap<String, StatData>> {
+    compute_aggregate_stat(groups, |ys| ys.iter().copied().fold(f64::INFINITY, f64::min))
+}
+
+/// One value per x value: `aggregate` over the y values of that x's rows
+fn compute_aggregate_stat(
+    groups: RawGroups,
+    aggregate: fn(&[f64]) -> f64,
 ) -> Result<HashMap<String, StatData>> {
     let mut new_groups = HashMap::new();
-    
-    for (key, (x_strs, y_vals, _, _)) in groups {
-        // Simple Linear Regression
-        let mut x_floats = Vec::new();
-        for s in &x_strs {
-            x_floats.push(s.parse::<f64>().map_err(|_| anyhow!("Stat 'smooth' requires numeric x data"))?);
+
+    for (key, (x_strs, ys, _, _)) in groups {
+        let mut by_x: HashMap<String, Vec<f64>> = HashMap::new();
+        for (x, y) in x_strs.into_iter().zip(ys) {
+            by_x.entry(x).or_default().push(y);
         }
-        
-        if x_floats.len() < 2 { continue; }
-        
-        let n = x_floats.len() as f64;
-        let sum_x: f64 = x_floats.iter().sum();
-        let sum_y: f64 = y_vals.iter().sum();
-        let sum_xx: f64 = x_floats.iter().map(|&x| x * x).sum();
-        let sum_xy: f64 = x_floats.iter().zip(y_vals.iter()).map(|(&x, &y)| x * y).sum();
-        
-        let slope = (n * sum_xy - sum_x * sum_y) / (n * sum_xx - sum_x * sum_x);
-        let intercept = (sum_y - slope * sum_x) / n;
-        
-        // Generate trend line points (min and max X)
-        let min_x = x_floats.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-        let max_x = x_floats.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-        
-        let new_x = vec![min_x.to_string(), max_x.to_string()];
-        let new_y = vec![slope * min_x + intercept, slope * max_x + intercept];
-        let new_ymin = new_y.clone();
-        let new_ymax = new_y.clone();
-        
-        new_groups.insert(key, StatData::from_tuple((new_x, new_y, new_ymin, new_ymax)));
+
+        let mut keys: Vec<String> = by_x.keys().cloned().collect();
+        keys.sort();
+
+        let mut new_x = Vec::new();
+        let mut new_y = Vec::new();
+        let mut new_ymin = Vec::new();
+        let mut new_ymax = Vec::new();
+
+        for k in keys {
+            let value = aggregate(&by_x[&k]);
+            new_x.push(k);
+            new_y.push(value);
+            new_ymin.push(0.0);
+            new_ymax.push(value);
+        }
+
+        new_groups.insert(key, StatData::from_tuple((new_x, new_y, new_ymin, new_ymax)));
     }
-    
+
+    Ok(new_groups)
+}
+
+/// Counts per x value divided by the group total, or by the total across all groups
+fn compute_prop_stat(
+    groups: RawGroups,
+    across_groups: bool,
+) -> Result<HashMap<String, StatData>> {
+    let mut counted = compute_count_stat(groups)?;
+    let grand_total: f64 = counted.values().flat_map(|d| d.y.iter()).sum();
+
+    for data in counted.values_mut() {
+        let total = if across_groups { grand_total } else { data.y.iter().sum() };
+        if total > 0.0 {
+            for y in data.y.iter_mut().chain(data.ymax.iter_mut()) {
+                *y /= total;
+            }
+        }
+    }
+
+    Ok(counted)
+}
+
+fn compute_smooth_stat(
+    groups: RawGroups,
+    method: &SmoothMethod,
+    n_points: usize,
+    residuals: bool,
+    formula: Option<&str>,
+    se: SeType,
+    x_range: Option<(f64, f64)>, // Evaluation range instead of each group's own x range (fullrange)
+) -> Result<HashMap<String, StatData>> {
+    // (polynomial degree, fit against ln x); no degree = loess. formula: takes precedence over method:
+    let (degree, log_x) = match formula {
+        Some(formula) => {
+            let (degree, log_x) = parse_smooth_formula(formula)?;
+            (Some(degree), log_x)
+        }
+        None => match method {
+            SmoothMethod::Lm => (Some(1), false),
+            SmoothMethod::Poly => (Some(2), false),
+            SmoothMethod::Loess { .. } => (None, false),
+        },
+    };
+    // Interval bands have a closed form for straight-line fits only
+    if se != SeType::None && degree != Some(1) {
+        bail!("smooth(se: ...) needs a straight-line fit (method: \"lm\", or formula: \"y ~ x\" or \"y ~ log(x)\")");
+    }
+    // A straight line is fully described by its end points (but its interval band is curved)
+    let n_points = if degree == Some(1) && !log_x && se == SeType::None { 2 } else { n_points.max(2) };
+    let to_fit = |x: f64| if log_x { x.ln() } else { x };
+
+    let mut new_groups = HashMap::new();
+
+    for (key, (x_strs, y_vals, _, _)) in groups {
+        let mut x_floats = Vec::new();
+        for s in &x_strs {
+            x_floats.push(s.parse::<f64>().map_err(|_| anyhow!("Stat 'smooth' requires numeric x data"))?);
+        }
+        if log_x && x_floats.iter().any(|&x| x <= 0.0) {
+            bail!("smooth(formula: \"{}\") requires positive x values", formula.unwrap_or_default());
+        }
+
+        if x_floats.len() < 2 { continue; }
+
+        let min_x = x_floats.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+        let max_x = x_floats.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+        // Extrapolate out to the plot's range (a log fit only over positive x)
+        let (lo, hi) = match x_range {
+            Some((lo, hi)) => (if log_x && lo <= 0.0 { min_x } else { lo.min(min_x) }, hi.max(max_x)),
+            None => (min_x, max_x),
+        };
+        let step = (hi - lo) / (n_points - 1) as f64;
+        let eval_x: Vec<f64> = (0..n_points)
+            .map(|i| if i == n_points - 1 { hi } else { lo + step * i as f64 })
+            .collect();
+
+        // Center x before fitting to keep the normal equations well conditioned
+        let center = (to_fit(min_x) + to_fit(max_x)) / 2.0;
+        let coeffs = degree.and_then(|degree| {
+            let centered: Vec<f64> = x_floats.iter().map(|&x| to_fit(x) - center).collect();
+            let weights = vec![1.0; centered.len()];
+            fit_polynomial(&centered, &y_vals, &weights, degree)
+        });
+        let fit_at = |x: f64| match (degree, method) {
+            (None, SmoothMethod::Loess { span }) => loess_at(&x_floats, &y_vals, x, *span),
+            _ => coeffs.as_ref().map(|c| c.iter().rev().fold(0.0, |acc, c| acc * (to_fit(x) - center) + c)),
+        };
+        let fitted: Vec<Option<f64>> = eval_x.iter().map(|&x| fit_at(x)).collect();
+
+        let band = line_interval(&x_floats.iter().map(|&x| to_fit(x)).collect::<Vec<_>>(), &y_vals, se);
+
+        // Drop points where the fit is degenerate (e.g. all x values equal)
+        let mut new_x = Vec::with_capacity(n_points);
+        let mut new_y = Vec::with_capacity(n_points);
+        let mut new_ymin = Vec::with_capacity(n_points);
+        let mut new_ymax = Vec::with_capacity(n_points);
+        for (&x, y) in eval_x.iter().zip(fitted) {
+            if let Some(y) = y {
+                let half = band.as_ref().map_or(0.0, |half_width| half_width(to_fit(x)));
+                new_x.push(x.to_string());
+                new_y.push(y);
+                new_ymin.push(y - half);
+                new_ymax.push(y + half);
+            }
+        }
+        if new_x.is_empty() { continue; }
+
+        let mut stat_data = StatData::from_tuple((new_x, new_y, new_ymin, new_ymax));
+        if residuals {
+            stat_data.residuals = Some(x_floats.iter().zip(y_vals.iter())
+                .filter_map(|(&x, &y)| fit_at(x).map(|f| (x, y - f)))
+                .collect());
+        }
+        new_groups.insert(key, stat_data);
+    }
+
     Ok(new_groups)
 }
 
+/// Half-width at x of the `se` interval around the least-squares line through (xs, ys):
+/// t * s * sqrt(k + 1/n + (x - x_bar)^2 / Sxx), with k = 1 for prediction and 0 for confidence,
+/// s the residual standard error and t the Student t quantile at df = n - 2.
+/// None without a band, or with fewer than 3 points or no spread in x
+fn line_interval(xs: &[f64], ys: &[f64], se: SeType) -> Option<impl Fn(f64) -> f64> {
+    let (level, extra) = match se {
+        SeType::None => return None,
+        SeType::Confidence(level) => (level, 0.0),
+        SeType::Prediction(level) => (level, 1.0),
+    };
+    let n = xs.len() as f64;
+    if xs.len() < 3 {
+        return None;
+    }
+    let x_bar = xs.iter().sum::<f64>() / n;
+    let y_bar = ys.iter().sum::<f64>() / n;
+    let sxx: f64 = xs.iter().map(|x| (x - x_bar).powi(2)).sum();
+    if sxx <= 0.0 {
+        return None;
+    }
+    let slope = xs.iter().zip(ys).map(|(x, y)| (x - x_bar) * (y - y_bar)).sum::<f64>() / sxx;
+    let sse: f64 = xs.iter().zip(ys).map(|(x, y)| (y - y_bar - slope * (x - x_bar)).powi(2)).sum();
+    let df = n - 2.0;
+    let scale = student_t_quantile(0.5 + level / 2.0, df) * (sse / df).sqrt();
+    Some(move |x: f64| scale * (extra + 1.0 / n + (x - x_bar).powi(2) / sxx).sqrt())
+}
+
+/// Quantile of Student's t distribution with `df` degrees of freedom (p > 0.5), by bisection on the CDF
+fn student_t_quantile(p: f64, df: f64) -> f64 {
+    // Upper tail P(T > t) = I_{df/(df+t^2)}(df/2, 1/2) / 2
+    let upper = |t: f64| regularized_beta(df / (df + t * t), df / 2.0, 0.5) / 2.0;
+    let (mut lo, mut hi) = (0.0, 1.0);
+    while upper(hi) > 1.0 - p {
+        hi *= 2.0;
+    }
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        if upper(mid) > 1.0 - p { lo = mid } else { hi = mid }
+    }
+    (lo + hi) / 2.0
+}
+
+/// Regularized incomplete beta function I_x(a, b), by its continued fraction (Numerical Recipes betacf)
+fn regularized_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    // The continued fraction converges fastest for x below the mean; use the symmetry otherwise
+    if x > (a + 1.0) / (a + b + 2.0) {
+        return 1.0 - regularized_beta(1.0 - x, b, a);
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp() / a;
+
+    let tiny = 1e-300;
+    let (mut c, mut d) = (1.0, 1.0 - (a + b) * x / (a + 1.0));
+    d = 1.0 / if d.abs() < tiny { tiny } else { d };
+    let mut f = d;
+    for m in 1..300 {
+        let m = m as f64;
+        for numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            d = 1.0 / if d.abs() < tiny { tiny } else { d };
+            c = 1.0 + numerator / c;
+            if c.abs() < tiny { c = tiny; }
+            f *= c * d;
+        }
+        if (c * d - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+    front * f
+}
+
+/// ln Γ(x) for x > 0 (Lanczos approximation, g = 7)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 9] = [
+        0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8,
+        771.323_428_777_653_1, -176.615_029_162_140_6, 12.507_343_278_686_905,
+        -0.138_571_095_265_720_12, 9.984_369_578_019_572e-6, 1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection: Γ(x) Γ(1 - x) = π / sin(πx)
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFS.iter().enumerate().skip(1).fold(COEFFS[0], |acc, (i, c)| acc + c / (x + i as f64));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// Model of an R-style smooth() formula: (polynomial degree, whether x is log-transformed)
+fn parse_smooth_formula(formula: &str) -> Result<(usize, bool)> {
+    let compact: String = formula.chars().filter(|c| !c.is_whitespace()).collect();
+    match compact.as_str() {
+        "y~x" => Ok((1, false)),
+        "y~log(x)" => Ok((1, true)),
+        _ => compact.strip_prefix("y~poly(x,")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .and_then(|degree| degree.parse::<usize>().ok())
+            .filter(|&degree| degree >= 1)
+            .map(|degree| (degree, false))
+            .ok_or_else(|| anyhow!(
+                "Unsupported smooth formula '{}' (supported: \"y ~ x\", \"y ~ poly(x, n)\", \"y ~ log(x)\")",
+                formula
+            )),
+    }
+}
+
+/// One quantile regression line per (group, quantile); keys are the quantile, prefixed by the group when grouped
+fn compute_quantile_stat(groups: RawGroups, quantiles: &[f64]) -> Result<HashMap<String, StatData>> {
+    let mut new_groups = HashMap::new();
+
+    for (key, (x_strs, y_vals, _, _)) in groups {
+        let mut x_floats = Vec::with_capacity(x_strs.len());
+        for s in &x_strs {
+            x_floats.push(s.parse::<f64>().map_err(|_| anyhow!("Stat 'quantile' requires numeric x data"))?);
+        }
+
+        if x_floats.len() < 2 { continue; }
+
+        let min_x = x_floats.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+        let max_x = x_floats.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+        let center = (min_x + max_x) / 2.0;
+        let centered: Vec<f64> = x_floats.iter().map(|x| x - center).collect();
+
+        for &q in quantiles {
+            // Degenerate (e.g. all x equal) fits draw nothing
+            let Some(coeffs) = fit_quantile_line(&centered, &y_vals, q) else { continue };
+            let ys: Vec<f64> = [min_x, max_x].iter().map(|x| coeffs[0] + coeffs[1] * (x - center)).collect();
+            let line_key = if key == "default" { q.to_string() } else { format!("{} {}", key, q) };
+            new_groups.insert(line_key, StatData::from_tuple((
+                vec![min_x.to_string(), max_x.to_string()],
+                ys.clone(), ys.clone(), ys,
+            )));
+        }
+    }
+
+    Ok(new_groups)
+}
+
+/// rug(): the x values as they are, with NaN y (skipped by the y range) since the compiler
+/// places the ticks against the panel
+fn compute_rug_stat(groups: RawGroups) -> HashMap<String, StatData> {
+    groups.into_iter().map(|(key, (xs, _, _, _))| {
+        let nan = vec![f64::NAN; xs.len()];
+        (key, StatData::from_tuple((xs, nan.clone(), nan.clone(), nan)))
+    }).collect()
+}
+
+/// Empirical CDF per group as the vertices of a step line: F jumps at each unique x.
+/// `pad` extends the steps to (x_min - 0.1% of the range, 0) and (x_max + 0.1%, 1);
+/// `n` evaluates F at that many evenly chosen unique x values instead of all of them.
+fn compute_ecdf_stat(groups: RawGroups, pad: bool, n: Option<usize>) -> Result<HashMap<String, StatData>> {
+    let mut new_groups = HashMap::new();
+
+    for (key, (x_strs, _, _, _)) in groups {
+        let mut xs = Vec::with_capacity(x_strs.len());
+        for s in &x_strs {
+            xs.push(s.parse::<f64>().map_err(|_| anyhow!("Stat 'ecdf' requires numeric x data"))?);
+        }
+        xs.retain(|x| x.is_finite());
+        if xs.is_empty() { continue; }
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        // (unique x, fraction of observations <= x)
+        let total = xs.len() as f64;
+        let mut steps: Vec<(f64, f64)> = Vec::new();
+        for (i, &x) in xs.iter().enumerate() {
+            match steps.last_mut() {
+                Some(last) if last.0 == x => last.1 = (i + 1) as f64 / total,
+                _ => steps.push((x, (i + 1) as f64 / total)),
+            }
+        }
+        if let Some(n) = n.filter(|&n| n >= 2 && n < steps.len()) {
+            let last = steps.len() - 1;
+            steps = (0..n).map(|i| steps[(i * last + (n - 1) / 2) / (n - 1)]).collect();
+        }
+
+        let (x_min, x_max) = (xs[0], xs[xs.len() - 1]);
+        let margin = 0.001 * if x_max > x_min { x_max - x_min } else { 1.0 };
+        let mut points = Vec::with_capacity(2 * steps.len() + 2);
+        if pad {
+            points.push((x_min - margin, 0.0));
+        }
+        let mut previous = if pad { Some(0.0) } else { None };
+        for &(x, f) in &steps {
+            if let Some(p) = previous {
+                points.push((x, p));
+            }
+            points.push((x, f));
+            previous = Some(f);
+        }
+        if pad {
+            points.push((x_max + margin, 1.0));
+        }
+
+        let ys: Vec<f64> = points.iter().map(|p| p.1).collect();
+        new_groups.insert(key, StatData::from_tuple((
+            points.iter().map(|p| p.0.to_string()).collect(),
+            ys.clone(), ys.clone(), ys,
+        )));
+    }
+
+    Ok(new_groups)
+}
+
+/// KDE curve of x per group, all evaluated on one grid of `n` points spanning the union of the
+/// groups' supports (min - 3bw to max + 3bw) so the curves can be compared directly
+fn compute_density_stat(groups: RawGroups, bw: &BandwidthMethod, n: usize) -> Result<HashMap<String, StatData>> {
+    let mut samples = Vec::with_capacity(groups.len());
+    for (key, (x_strs, _, _, _)) in groups {
+        let mut xs = Vec::with_capacity(x_strs.len());
+        for s in &x_strs {
+            xs.push(s.parse::<f64>().map_err(|_| anyhow!("Stat 'density' requires numeric x data"))?);
+        }
+        xs.retain(|x| x.is_finite());
+        if xs.is_empty() { continue; }
+        let bandwidth = select_bandwidth(&xs, bw);
+        samples.push((key, xs, bandwidth));
+    }
+
+    let (lo, hi) = samples.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (_, xs, h)| {
+        let (min, max) = xs.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), &x| (a.min(x), b.max(x)));
+        (lo.min(min - 3.0 * h), hi.max(max + 3.0 * h))
+    });
+    let step = (hi - lo) / (n - 1) as f64;
+    let grid: Vec<f64> = (0..n).map(|i| lo + i as f64 * step).collect();
+
+    let mut new_groups = HashMap::new();
+    for (key, xs, bandwidth) in samples {
+        let density: Vec<f64> = grid.iter().map(|&x| kde_at(&xs, bandwidth, x)).collect();
+        // ymin of 0 keeps the baseline (and any fill) inside the y range
+        new_groups.insert(key, StatData::from_tuple((
+            grid.iter().map(|x| x.to_string()).collect(),
+            density.clone(), vec![0.0; n], density,
+        )));
+    }
+
+    Ok(new_groups)
+}
+
+/// Points per side of the regular grid the contour() density is estimated on
+const CONTOUR_GRID: usize = 50;
+
+/// contour(): 2D Gaussian KDE of (x, y) per group, traced at `bins` equally spaced density levels
+/// (max·k/(bins+1), k = 1..=bins) as level lines or, with `filled`, as the bands between them.
+/// `bandwidth` overrides Silverman's rule per axis
+fn compute_contour_stat(groups: RawGroups, bins: usize, filled: bool, bandwidth: (Option<f64>, Option<f64>)) -> Result<HashMap<String, StatData>> {
+    let mut new_groups = HashMap::new();
+
+    for (key, (x_strs, ys, _, _)) in groups {
+        let mut points = Vec::with_capacity(x_strs.len());
+        for (s, &y) in x_strs.iter().zip(&ys) {
+            let x = s.parse::<f64>().map_err(|_| anyhow!("Stat 'contour' requires numeric x data"))?;
+            if x.is_finite() && y.is_finite() {
+                points.push((x, y));
+            }
+        }
+        if points.len() < 2 { continue; }
+
+        let grid = DensityGrid::estimate(&points, CONTOUR_GRID, bandwidth);
+        let max = grid.z.iter().fold(0.0f64, |a, &b| a.max(b));
+        if max <= 0.0 { continue; }
+        let levels: Vec<f64> = (1..=bins).map(|k| max * k as f64 / (bins + 1) as f64).collect();
+
+        let contours = if filled {
+            grid.bands(&levels)
+        } else {
+            levels.iter().flat_map(|&level| grid.isolines(level).into_iter().map(move |path| (level, path))).collect()
+        };
+
+        let (xs, ys): (Vec<f64>, Vec<f64>) = contours.iter().flat_map(|(_, path)| path.iter().copied()).unzip();
+        let mut stat_data = StatData::from_tuple((xs.iter().map(|x| x.to_string()).collect(), ys.clone(), ys.clone(), ys));
+        stat_data.contours = Some(contours);
+        new_groups.insert(key, stat_data);
+    }
+
+    Ok(new_groups)
+}
+
+/// Gaussian product-kernel density estimate on a regular `xs` x `ys` grid spanning the data
+struct DensityGrid {
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+    z: Vec<f64>, // Row-major: z[j * xs.len() + i] is the density at (xs[i], ys[j])
+}
+
+impl DensityGrid {
+    fn estimate(points: &[(f64, f64)], size: usize, (bw_x, bw_y): (Option<f64>, Option<f64>)) -> Self {
+        let (px, py): (Vec<f64>, Vec<f64>) = points.iter().copied().unzip();
+        let (hx, hy) = (bw_x.unwrap_or_else(|| silverman_bandwidth(&px)), bw_y.unwrap_or_else(|| silverman_bandwidth(&py)));
+        let axis = |values: &[f64], h: f64| -> Vec<f64> {
+            let (mut lo, mut hi) = values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), &v| (a.min(v), b.max(v)));
+            if hi <= lo {
+                lo -= h;
+                hi += h;
+            }
+            (0..size).map(|i| lo + (hi - lo) * i as f64 / (size - 1) as f64).collect()
+        };
+        let (xs, ys) = (axis(&px, hx), axis(&py, hy));
+
+        // Kernel weights per grid line and point, so each grid value is one dot product
+        let weights = |grid: &[f64], values: &[f64], h: f64| -> Vec<Vec<f64>> {
+            grid.iter().map(|&g| values.iter().map(|&v| gaussian_kernel((g - v) / h) / h).collect()).collect()
+        };
+        let (kx, ky) = (weights(&xs, &px, hx), weights(&ys, &py, hy));
+        let n = points.len() as f64;
+        let z = ky.iter()
+            .flat_map(|wy| kx.iter().map(move |wx| wx.iter().zip(wy).map(|(a, b)| a * b).sum::<f64>() / n))
+            .collect();
+
+        DensityGrid { xs, ys, z }
+    }
+
+    fn point(&self, id: usize) -> (f64, f64, f64) {
+        let nx = self.xs.len();
+        (self.xs[id % nx], self.ys[id / nx], self.z[id])
+    }
+
+    /// Each grid cell as two triangles of vertex ids, so the density is linear within each one
+    fn triangles(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        let nx = self.xs.len();
+        (0..self.ys.len() - 1).flat_map(move |j| (0..nx - 1).flat_map(move |i| {
+            let a = j * nx + i;
+            let (b, c, d) = (a + 1, a + nx + 1, a + nx);
+            [[a, b, c], [a, c, d]]
+        }))
+    }
+
+    /// Level lines at `level` (marching triangles), joined into paths; closed loops repeat their first point
+    fn isolines(&self, level: f64) -> Vec<Vec<(f64, f64)>> {
+        // Segments between two crossed triangle edges, keyed by their (lower, higher) vertex ids
+        let mut segments: Vec<[(usize, usize); 2]> = Vec::new();
+        for tri in self.triangles() {
+            let crossed: Vec<(usize, usize)> = [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])].into_iter()
+                .filter(|&(a, b)| (self.z[a] >= level) != (self.z[b] >= level))
+                .map(|(a, b)| (a.min(b), a.max(b)))
+                .collect();
+            if let [e1, e2] = crossed[..] {
+                segments.push([e1, e2]);
+            }
+        }
+
+        let mut by_edge: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (i, seg) in segments.iter().enumerate() {
+            for &edge in seg {
+                by_edge.entry(edge).or_default().push(i);
+            }
+        }
+
+        let mut used = vec![false; segments.len()];
+        let next_from = |edge: (usize, usize), used: &mut Vec<bool>| -> Option<(usize, usize)> {
+            let &seg = by_edge.get(&edge)?.iter().find(|&&s| !used[s])?;
+            used[seg] = true;
+            let [a, b] = segments[seg];
+            Some(if a == edge { b } else { a })
+        };
+
+        let mut paths = Vec::new();
+        for start in 0..segments.len() {
+            if used[start] { continue; }
+            used[start] = true;
+            let mut path = std::collections::VecDeque::from(segments[start]);
+            while let Some(edge) = next_from(*path.back().unwrap(), &mut used) {
+                path.push_back(edge);
+            }
+            while let Some(edge) = next_from(*path.front().unwrap(), &mut used) {
+                path.push_front(edge);
+            }
+            paths.push(path.into_iter().map(|(a, b)| {
+                let ((xa, ya, za), (xb, yb, zb)) = (self.point(a), self.point(b));
+                let t = (level - za) / (zb - za);
+                (xa + t * (xb - xa), ya + t * (yb - ya))
+            }).collect());
+        }
+        paths
+    }
+
+    /// Polygons covering each band between consecutive levels (the last band is unbounded above),
+    /// tagged with the band's lower level; density below the first level is left unfilled
+    fn bands(&self, levels: &[f64]) -> Vec<ContourPath> {
+        let band = |z: f64| levels.iter().take_while(|&&l| l <= z).count();
+        let mut polygons = Vec::new();
+        let nx = self.xs.len();
+
+        for j in 0..self.ys.len() - 1 {
+            for i in 0..nx - 1 {
+                let a = j * nx + i;
+                let corners = [a, a + 1, a + nx + 1, a + nx];
+                let bands: Vec<usize> = corners.iter().map(|&id| band(self.z[id])).collect();
+                let (lo, hi) = (*bands.iter().min().unwrap(), *bands.iter().max().unwrap());
+                if hi == 0 { continue; }
+
+                // Whole cells within one band stay a single square
+                if lo == hi {
+                    polygons.push((levels[lo - 1], corners.iter().map(|&id| { let (x, y, _) = self.point(id); (x, y) }).collect()));
+                    continue;
+                }
+                for tri in [[corners[0], corners[1], corners[2]], [corners[0], corners[2], corners[3]]] {
+                    let tri: Vec<(f64, f64, f64)> = tri.iter().map(|&id| self.point(id)).collect();
+                    for b in lo.max(1)..=hi {
+                        let mut poly = clip_to_level(&tri, levels[b - 1], true);
+                        if let Some(&upper) = levels.get(b) {
+                            poly = clip_to_level(&poly, upper, false);
+                        }
+                        if poly.len() >= 3 {
+                            polygons.push((levels[b - 1], poly.into_iter().map(|(x, y, _)| (x, y)).collect()));
+                        }
+                    }
+                }
+            }
+        }
+        polygons
+    }
+}
+
+/// Clip a polygon whose third coordinate varies linearly to the part at or above / below `level`
+fn clip_to_level(poly: &[(f64, f64, f64)], level: f64, keep_above: bool) -> Vec<(f64, f64, f64)> {
+    let inside = |p: &(f64, f64, f64)| if keep_above { p.2 >= level } else { p.2 < level };
+    let mut out = Vec::with_capacity(poly.len() + 2);
+    for (k, cur) in poly.iter().enumerate() {
+        let next = &poly[(k + 1) % poly.len()];
+        if inside(cur) {
+            out.push(*cur);
+        }
+        if inside(cur) != inside(next) {
+            let t = (level - cur.2) / (next.2 - cur.2);
+            out.push((cur.0 + t * (next.0 - cur.0), cur.1 + t * (next.1 - cur.1), level));
+        }
+    }
+    out
+}
+
+/// stat_summary(): evaluate the three formulas over the y values at each x
+fn compute_summary_stat(groups: RawGroups, fun: &SummaryExpr, fun_ymin: &SummaryExpr, fun_ymax: &SummaryExpr) -> Result<HashMap<String, StatData>> {
+    let mut new_groups = HashMap::new();
+
+    for (key, (x_strs, ys, _, _)) in groups {
+        let mut by_x: HashMap<String, Vec<f64>> = HashMap::new();
+        for (x, y) in x_strs.into_iter().zip(ys) {
+            if y.is_finite() {
+                by_x.entry(x).or_default().push(y);
+            }
+        }
+
+        let mut keys: Vec<String> = by_x.keys().cloned().collect();
+        keys.sort();
+
+        let (mut new_y, mut new_ymin, mut new_ymax) = (Vec::new(), Vec::new(), Vec::new());
+        for k in &keys {
+            let values = by_x.get_mut(k).unwrap();
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            new_y.push(eval_summary(fun, values));
+            new_ymin.push(eval_summary(fun_ymin, values));
+            new_ymax.push(eval_summary(fun_ymax, values));
+        }
+
+        new_groups.insert(key, StatData::from_tuple((keys, new_y, new_ymin, new_ymax)));
+    }
+
+    Ok(new_groups)
+}
+
+/// Value of a stat_summary() formula over sorted, non-empty values
+fn eval_summary(expr: &SummaryExpr, sorted: &[f64]) -> f64 {
+    match expr {
+        SummaryExpr::Add(a, b) => eval_summary(a, sorted) + eval_summary(b, sorted),
+        SummaryExpr::Sub(a, b) => eval_summary(a, sorted) - eval_summary(b, sorted),
+        SummaryExpr::Fun(fun) => {
+            let n = sorted.len() as f64;
+            let mean = sorted.iter().sum::<f64>() / n;
+            let sd = || if sorted.len() < 2 {
+                0.0
+            } else {
+                (sorted.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+            };
+            match fun {
+                SummaryFun::Mean => mean,
+                SummaryFun::Median => percentile(sorted, 0.5),
+                SummaryFun::Sd => sd(),
+                SummaryFun::Se => sd() / n.sqrt(),
+                SummaryFun::Min => sorted[0],
+                SummaryFun::Max => sorted[sorted.len() - 1],
+                SummaryFun::Q25 => percentile(sorted, 0.25),
+                SummaryFun::Q75 => percentile(sorted, 0.75),
+            }
+        }
+    }
+}
+
+/// Linear quantile regression by iteratively reweighted least squares: weighting each residual r by
+/// |q - I(r < 0)| / |r| makes the weighted squared error equal the check loss ρ_q(r) = r(q - I(r < 0))
+fn fit_quantile_line(xs: &[f64], ys: &[f64], q: f64) -> Option<Vec<f64>> {
+    // Floor on |r| so points on the line do not get infinite weight
+    let scale = ys.iter().fold(0.0f64, |a, y| a.max(y.abs())).max(1.0);
+    let eps = 1e-6 * scale;
+
+    let mut weights = vec![1.0; xs.len()];
+    let mut coeffs = fit_polynomial(xs, ys, &weights, 1)?;
+    for _ in 0..200 {
+        for ((w, &x), &y) in weights.iter_mut().zip(xs).zip(ys) {
+            let r = y - (coeffs[0] + coeffs[1] * x);
+            *w = if r < 0.0 { 1.0 - q } else { q } / r.abs().max(eps);
+        }
+        let next = fit_polynomial(xs, ys, &weights, 1)?;
+        let converged = next.iter().zip(&coeffs).all(|(a, b)| (a - b).abs() <= 1e-9 * (1.0 + b.abs()));
+        coeffs = next;
+        if converged { break; }
+    }
+    Some(coeffs)
+}
+
+/// Local quadratic fit at `x0` over the `round(n * span)` nearest points, tricube-weighted by distance
+fn loess_at(xs: &[f64], ys: &[f64], x0: f64, span: f64) -> Option<f64> {
+    let k = ((xs.len() as f64 * span).round() as usize).clamp(2, xs.len());
+    let mut dists: Vec<f64> = xs.iter().map(|x| (x - x0).abs()).collect();
+    dists.sort_by(|a, b| a.total_cmp(b));
+    let max_dist = dists[k - 1];
+
+    let mut local_x = Vec::with_capacity(k);
+    let mut local_y = Vec::with_capacity(k);
+    let mut weights = Vec::with_capacity(k);
+    for (&x, &y) in xs.iter().zip(ys.iter()) {
+        let d = (x - x0).abs();
+        if d > max_dist { continue; }
+        let w = if max_dist > 0.0 { (1.0 - (d / max_dist).powi(3)).powi(3) } else { 1.0 };
+        if w > 0.0 {
+            local_x.push(x - x0);
+            local_y.push(y);
+            weights.push(w);
+        }
+    }
+
+    // Fall back to lower degrees when the neighborhood is too small for a quadratic
+    (0..=2).rev()
+        .filter(|&degree| local_x.len() > degree)
+        .find_map(|degree| fit_polynomial(&local_x, &local_y, &weights, degree))
+        .map(|coeffs| coeffs[0])
+}
+
+/// Weighted least-squares polynomial fit; returns coefficients from the constant term upward
+fn fit_polynomial(xs: &[f64], ys: &[f64], weights: &[f64], degree: usize) -> Option<Vec<f64>> {
+    let size = degree + 1;
+    // Normal equations (X^T X) c = X^T y as an augmented matrix
+    let mut m = vec![vec![0.0; size + 1]; size];
+    for ((&x, &y), &w) in xs.iter().zip(ys.iter()).zip(weights.iter()) {
+        let powers: Vec<f64> = (0..=2 * degree).map(|p| x.powi(p as i32)).collect();
+        for (r, row) in m.iter_mut().enumerate() {
+            for c in 0..size {
+                row[c] += w * powers[r + c];
+            }
+            row[size] += w * powers[r] * y;
+        }
+    }
+
+    // Gaussian elimination with partial pivoting
+    for col in 0..size {
+        let pivot = (col..size).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))?;
+        if m[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        m.swap(col, pivot);
+        let pivot_row = m[col].clone();
+        for (r, row) in m.iter_mut().enumerate() {
+            if r != col {
+                let factor = row[col] / pivot_row[col];
+                for (cell, p) in row.iter_mut().zip(pivot_row.iter()).skip(col) {
+                    *cell -= factor * p;
+                }
+            }
+        }
+    }
+
+    Some((0..size).map(|r| m[r][size] / m[r][r]).collect())
+}
+
 fn compute_bin_stat(
-    groups: HashMap<String, (Vec<String>, Vec<f64>, Vec<f64>, Vec<f64>)>,
+    groups: RawGroups,
     bin_count: usize
 ) -> Result<HashMap<String, StatData>> {
     // 1. Collect all X values to determine range
@@ -863,7 +2540,7 @@ fn compute_bin_stat(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parser::ast::{Layer, LineLayer};
+    use crate::parser::ast::{AxisScale, DiscreteScale, FillScale, Layer, LineLayer};
 
     fn make_data() -> PlotData {
         PlotData {
@@ -880,15 +2557,22 @@ mod tests {
         ResolvedSpec {
             layers: vec![ResolvedLayer {
                 original_layer: Layer::Line(LineLayer::default()),
+                inherits_binned: false,
                 aesthetics: ResolvedAesthetics {
                     x_col: "x".to_string(),
                     y_col: Some("y".to_string()),
                     ymin_col: None,
                     ymax_col: None,
+                    xmin_col: None,
+                    xmax_col: None,
                     color: Some("cat".to_string()),
                     size: None,
                     shape: None,
                     alpha: None,
+                    linetype: None,
+                    fill: None,
+                    label: None,
+                    colors: None,
                 },
             }],
             facet: None,
@@ -897,6 +2581,10 @@ mod tests {
             theme: crate::parser::ast::Theme::default(),
             x_scale_spec: None,
             y_scale_spec: None,
+            color_scale: None,
+            linetype_values: None,
+            fill_scale: None,
+            identity_scales: Default::default(),
         }
     }
 
@@ -918,6 +2606,927 @@ mod tests {
         assert_eq!(group_a.y, vec![10.0, 20.0]);
     }
 
+    #[test]
+    fn test_transform_bar_fill_normalizes() {
+        let mut spec = make_spec();
+        spec.layers[0].original_layer = Layer::Bar(crate::parser::ast::BarLayer {
+            position: LayerPosition::Fill,
+            ..Default::default()
+        });
+        let render_data = apply_transformations(&spec, &make_data()).unwrap();
+        let groups = &render_data.panels[0].layers[0].groups;
+        let a = groups.iter().find(|g| g.key == "A").unwrap();
+        let b = groups.iter().find(|g| g.key == "B").unwrap();
+
+        // x = 1.0: A = 10, B = 15 -> A covers 0.4, B stacks on top up to 1.0
+        assert!((a.y[0] - 0.4).abs() < 1e-9);
+        assert!((b.y_start[0] - 0.4).abs() < 1e-9);
+        assert!((b.y[0] - 1.0).abs() < 1e-9);
+        // x = 2.0: only A -> full height
+        assert!((a.y[1] - 1.0).abs() < 1e-9);
+
+        // "percent" is the same normalization
+        spec.layers[0].original_layer = Layer::Bar(crate::parser::ast::BarLayer {
+            position: LayerPosition::Percent,
+            ..Default::default()
+        });
+        let percent = apply_transformations(&spec, &make_data()).unwrap();
+        let percent_groups = &percent.panels[0].layers[0].groups;
+        for (fill, pct) in groups.iter().zip(percent_groups) {
+            assert_eq!((&fill.y, &fill.y_start), (&pct.y, &pct.y_start));
+        }
+    }
+
+    #[test]
+    fn test_boxplot_stat_notches() {
+        // 1..=9: median 5, IQR 4 (q1 3, q3 7), n 9 => median +- 1.58 * 4 / 3
+        let ys: Vec<f64> = (1..=9).map(f64::from).collect();
+        let mut groups = RawGroups::new();
+        groups.insert("g".to_string(), (vec!["a".to_string(); 9], ys, vec![0.0; 9], vec![0.0; 9]));
+
+        let stats = compute_boxplot_stat(groups).unwrap();
+        let bp = stats["g"].boxplot.as_ref().unwrap();
+        let half = 1.58 * 4.0 / 3.0;
+        assert!((bp.notch_low[0] - (5.0 - half)).abs() < 1e-12);
+        assert!((bp.notch_high[0] - (5.0 + half)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_transform_bar_aggregate_stats() {
+        let mut spec = make_spec();
+        spec.layers[0].aesthetics.x_col = "cat".to_string();
+        spec.layers[0].aesthetics.color = None;
+
+        // Category A has rows 10 and 20, B has 15
+        for (stat, expected) in [(Stat::Sum, [30.0, 15.0]), (Stat::Mean, [15.0, 15.0]), (Stat::Max, [20.0, 15.0]), (Stat::Min, [10.0, 15.0])] {
+            spec.layers[0].original_layer = Layer::Bar(crate::parser::ast::BarLayer { stat, ..Default::default() });
+            let render_data = apply_transformations(&spec, &make_data()).unwrap();
+            let group = &render_data.panels[0].layers[0].groups[0];
+            assert_eq!(group.x_categories, Some(vec!["A".to_string(), "B".to_string()]));
+            assert_eq!(group.x, vec![0.0, 1.0]);
+            assert_eq!(group.y, expected.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_transform_discrete_limits_order_and_drop() {
+        let mut spec = make_spec();
+        spec.layers[0].original_layer = Layer::Bar(crate::parser::ast::BarLayer::default());
+        spec.layers[0].aesthetics.x_col = "cat".to_string();
+        spec.layers[0].aesthetics.color = None;
+        spec.x_scale_spec = Some(AxisScale {
+            discrete: Some(DiscreteScale { limits: Some(vec!["B".to_string()]), ..Default::default() }),
+            ..Default::default()
+        });
+
+        // drop (default): only the listed category is kept
+        let render_data = apply_transformations(&spec, &make_data()).unwrap();
+        let group = &render_data.panels[0].layers[0].groups[0];
+        assert_eq!(group.x_categories, Some(vec!["B".to_string()]));
+        assert_eq!(group.y, vec![15.0]);
+
+        // drop: false keeps the rest, after the listed ones
+        spec.x_scale_spec.as_mut().unwrap().discrete.as_mut().unwrap().drop = false;
+        let render_data = apply_transformations(&spec, &make_data()).unwrap();
+        let group = &render_data.panels[0].layers[0].groups[0];
+        assert_eq!(group.x_categories, Some(vec!["B".to_string(), "A".to_string()]));
+        let mut bars: Vec<(f64, f64)> = group.x.iter().copied().zip(group.y.iter().copied()).collect();
+        bars.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(bars, vec![(0.0, 15.0), (1.0, 10.0), (1.0, 20.0)]);
+    }
+
+    #[test]
+    fn test_transform_bar_width_column_mosaic() {
+        let mut spec = make_spec();
+        spec.layers[0].original_layer = Layer::Bar(BarLayer {
+            width: Some(AestheticValue::Mapped("y".to_string())),
+            position: LayerPosition::Fill,
+            ..Default::default()
+        });
+        spec.layers[0].aesthetics.x_col = "cat".to_string();
+        spec.layers[0].aesthetics.color = None;
+        let render_data = apply_transformations(&spec, &make_data()).unwrap();
+        let group = &render_data.panels[0].layers[0].groups[0];
+
+        // A's width is its first row (10), B's is 15: shares 0.4 / 0.6 of the two slots
+        assert!((group.x_category_positions[0] - (-0.1)).abs() < 1e-9);
+        assert!((group.x_category_positions[1] - 0.9).abs() < 1e-9);
+        for (&x, &w) in group.x.iter().zip(&group.widths) {
+            let expected = if x < 0.0 { 0.8 } else { 1.2 };
+            assert!((w - expected).abs() < 1e-9);
+        }
+        assert_eq!(group.widths.len(), group.x.len());
+    }
+
+    #[test]
+    fn test_transform_text_labels_follow_rows() {
+        let mut spec = make_spec();
+        spec.layers[0].original_layer = Layer::Text(crate::parser::ast::TextLayer::default());
+        spec.layers[0].aesthetics.label = Some("y".to_string());
+        let render_data = apply_transformations(&spec, &make_data()).unwrap();
+        let a = render_data.panels[0].layers[0].groups.iter().find(|g| g.key == "A").unwrap();
+        assert_eq!(a.labels, vec!["10.0", "20.0"]);
+        assert!(matches!(a.style, RenderStyle::Text(_)));
+
+        // A fixed label repeats for every point
+        spec.layers[0].aesthetics.label = None;
+        spec.layers[0].original_layer = Layer::Text(crate::parser::ast::TextLayer {
+            label: Some(AestheticValue::Fixed("hi".to_string())),
+            ..Default::default()
+        });
+        let render_data = apply_transformations(&spec, &make_data()).unwrap();
+        let a = render_data.panels[0].layers[0].groups.iter().find(|g| g.key == "A").unwrap();
+        assert_eq!(a.labels, vec!["hi", "hi"]);
+    }
+
+    #[test]
+    fn test_transform_tile_fill_uses_global_range() {
+        let mut spec = make_spec();
+        spec.layers[0].original_layer = Layer::Tile(crate::parser::ast::TileLayer::default());
+        spec.layers[0].aesthetics.color = None;
+        spec.layers[0].aesthetics.fill = Some("y".to_string());
+        spec.fill_scale = Some(FillScale::Continuous {
+            low: "white".to_string(),
+            high: "black".to_string(),
+            mid: None,
+            midpoint: None,
+            na_value: "grey".to_string(),
+            trans: None,
+        });
+        spec.facet = Some(ResolvedFacet {
+            col: "cat".to_string(),
+            ncol: None,
+            scales: crate::parser::ast::FacetScales::Fixed,
+            strip_position: crate::parser::ast::StripPosition::Top,
+            space: crate::parser::ast::FacetSpace::Fixed,
+        });
+
+        let render_data = apply_transformations(&spec, &make_data()).unwrap();
+        let a = &render_data.panels[0].layers[0].groups[0];
+        let b = &render_data.panels[1].layers[0].groups[0];
+        // A's two rows lie on the diagonal of its 2x2 grid; the other two cells follow in na_color
+        assert_eq!(a.fills, vec!["#ffffff", "#000000", "grey", "grey"]);
+        // B's only value (15) sits mid-way through the range of the whole data set
+        assert_eq!(b.fills, vec!["#808080"]);
+
+        // Tiles span the spacing of the x/y values around their center
+        assert_eq!(a.y_min[..2], [5.0, 15.0]);
+        assert_eq!(a.y_max[..2], [15.0, 25.0]);
+        match &a.style {
+            RenderStyle::Tile(t) => assert_eq!(t.width, 1.0),
+            other => panic!("Expected tile style, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_transform_tile_fills_missing_cells_with_na_color() {
+        let mut spec = make_spec();
+        spec.layers[0].original_layer = Layer::Tile(TileLayer { na_color: Some("red".to_string()), ..Default::default() });
+        spec.layers[0].aesthetics.color = None;
+        spec.layers[0].aesthetics.fill = Some("y".to_string());
+
+        // x {1, 2} × y {10, 20, 15}: three of the six cells have no row
+        let render_data = apply_transformations(&spec, &make_data()).unwrap();
+        let group = &render_data.panels[0].layers[0].groups[0];
+        assert_eq!(group.x, vec![1.0, 2.0, 1.0, 1.0, 2.0, 2.0]);
+        assert_eq!(group.y, vec![10.0, 20.0, 15.0, 20.0, 10.0, 15.0]);
+        assert_eq!(&group.fills[3..], ["red", "red", "red"]);
+        assert!(group.fills[..3].iter().all(|f| f != "red"));
+
+        // drop_na: true leaves them out
+        spec.layers[0].original_layer = Layer::Tile(TileLayer { drop_na: true, ..Default::default() });
+        let render_data = apply_transformations(&spec, &make_data()).unwrap();
+        let group = &render_data.panels[0].layers[0].groups[0];
+        assert_eq!(group.x.len(), 3);
+        assert_eq!(group.fills.len(), 3);
+    }
+
+    #[test]
+    fn test_transform_hexbin_counts_and_colorbar() {
+        let mut spec = make_spec();
+        spec.layers[0].original_layer = Layer::Hexbin(crate::parser::ast::HexbinLayer {
+            stat: Stat::Hexbin { bins: 2 },
+            ..Default::default()
+        });
+        spec.layers[0].aesthetics.color = None;
+
+        let mut data = make_data();
+        data.rows.push(vec!["1.0".to_string(), "10.0".to_string(), "A".to_string()]);
+
+        let render_data = apply_transformations(&spec, &data).unwrap();
+        let group = &render_data.panels[0].layers[0].groups[0];
+        assert_eq!(group.counts.iter().sum::<usize>(), 4);
+        assert!(group.counts.contains(&2));
+        assert_eq!(group.fills.len(), group.counts.len());
+
+        // The fullest cell takes the high end of the default white -> steelblue gradient
+        let fullest = group.counts.iter().enumerate().max_by_key(|(_, c)| **c).unwrap().0;
+        assert_eq!(group.fills[fullest], "#4682b4");
+
+        let bar = render_data.colorbar.expect("hexbin should produce a color bar");
+        assert_eq!(bar.title.as_deref(), Some("count"));
+        match &group.style {
+            RenderStyle::Hex(h) => assert!(h.width > 0.0 && h.radius > 0.0),
+            other => panic!("Expected hex style, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_transform_hexbin_fill_by_mean_y() {
+        let mut spec = make_spec();
+        spec.layers[0].original_layer = Layer::Hexbin(crate::parser::ast::HexbinLayer {
+            stat: Stat::Hexbin { bins: 1 },
+            fill: HexSummary::MeanY,
+            ..Default::default()
+        });
+        spec.layers[0].aesthetics.color = None;
+
+        let render_data = apply_transformations(&spec, &make_data()).unwrap();
+        let group = &render_data.panels[0].layers[0].groups[0];
+        // Each cell's value is the mean of its y values; the counts are still kept
+        for (&value, &count) in group.cell_values.iter().zip(&group.counts) {
+            assert!((10.0..=20.0).contains(&value));
+            assert!(count >= 1);
+        }
+        assert_eq!(group.counts.iter().sum::<usize>(), 3);
+        let total: f64 = group.cell_values.iter().zip(&group.counts).map(|(v, &c)| v * c as f64).sum();
+        assert!((total - 45.0).abs() < 1e-9);
+        assert_eq!(render_data.colorbar.unwrap().title.as_deref(), Some("mean_y"));
+
+        let mut cell = HexCell::default();
+        for y in [4.0, -2.0, 7.0] {
+            cell.add(y);
+        }
+        let values: Vec<f64> = HexSummary::ALL.iter().map(|&s| cell.value(s)).collect();
+        assert_eq!(values, vec![3.0, 3.0, 9.0, -2.0, 7.0]);
+    }
+
+    fn smooth_groups(xs: &[f64], ys: &[f64]) -> RawGroups {
+        let mut groups: RawGroups = HashMap::new();
+        groups.insert("__all__".to_string(), (
+            xs.iter().map(|x| x.to_string()).collect(),
+            ys.to_vec(), ys.to_vec(), ys.to_vec(),
+        ));
+        groups
+    }
+
+    #[test]
+    fn test_smooth_fits_each_color_group_independently() {
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: x, y: y, color: g) | smooth()").unwrap();
+        let mut rows = Vec::new();
+        for x in 0..5 {
+            // A rises with slope 2, B falls with slope -1
+            rows.push(vec![x.to_string(), (2 * x).to_string(), "A".to_string()]);
+            rows.push(vec![x.to_string(), (10 - x).to_string(), "B".to_string()]);
+        }
+        let data = PlotData { headers: vec!["x".to_string(), "y".to_string(), "g".to_string()], rows };
+        let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+
+        let render_data = apply_transformations(&resolved, &data).unwrap();
+        let groups = &render_data.panels[0].layers[0].groups;
+        assert_eq!(groups.len(), 2);
+        let slope = |key: &str| {
+            let g = groups.iter().find(|g| g.key == key).unwrap();
+            let last = g.x.len() - 1;
+            (g.y[last] - g.y[0]) / (g.x[last] - g.x[0])
+        };
+        assert!((slope("A") - 2.0).abs() < 1e-9);
+        assert!((slope("B") + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_transform_bar_fill_literal_colors_per_row() {
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: x, y: y) | col(fill: status) | scale_fill_identity()").unwrap();
+        let rows = [("a", "red"), ("b", "green"), ("c", "red")].iter()
+            .map(|(x, status)| vec![x.to_string(), "2".to_string(), status.to_string()])
+            .collect();
+        let data = PlotData { headers: vec!["x".to_string(), "y".to_string(), "status".to_string()], rows };
+        let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+
+        let render_data = apply_transformations(&resolved, &data).unwrap();
+        let groups = &render_data.panels[0].layers[0].groups;
+        // One group: the colors are per bar, not a grouping
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].x, vec![0.0, 1.0, 2.0]);
+        assert_eq!(groups[0].fills, vec!["red", "green", "red"]);
+    }
+
+    #[test]
+    fn test_transform_bar_border_style() {
+        let data = PlotData { headers: vec!["x".to_string(), "y".to_string()], rows: vec![vec!["a".to_string(), "2".to_string()]] };
+        let style = |dsl: &str| {
+            let (spec, _) = crate::parser::parse_plot_spec(dsl).unwrap();
+            let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+            let render_data = apply_transformations(&resolved, &data).unwrap();
+            match &render_data.panels[0].layers[0].groups[0].style {
+                RenderStyle::Bar(style) => (style.border_color.clone(), style.border_width, style.hollow),
+                _ => panic!("Expected Bar style"),
+            }
+        };
+        assert_eq!(style("aes(x: x, y: y) | col()"), (None, None, false));
+        assert_eq!(style(r#"aes(x: x, y: y) | col(border: "navy")"#), (Some("navy".to_string()), None, false));
+        // A width alone outlines in black, and 0 turns the outline off
+        assert_eq!(style("aes(x: x, y: y) | col(border_width: 2)"), (Some("black".to_string()), Some(2.0), false));
+        assert_eq!(style(r#"aes(x: x, y: y) | col(border: "navy", border_width: 0)"#), (None, Some(0.0), false));
+        assert_eq!(style(r#"aes(x: x, y: y) | col(fill: "none")"#), (None, None, true));
+    }
+
+    #[test]
+    fn test_transform_ribbon_per_color_group() {
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: x, ymin: lo, ymax: hi, color: model) | ribbon() | ribbon(alpha: 0.8)").unwrap();
+        let rows = [("1", "0", "2", "a"), ("2", "1", "3", "a"), ("1", "5", "7", "b"), ("2", "6", "8", "b")].iter()
+            .map(|(x, lo, hi, model)| vec![x.to_string(), lo.to_string(), hi.to_string(), model.to_string()])
+            .collect();
+        let data = PlotData { headers: vec!["x".to_string(), "lo".to_string(), "hi".to_string(), "model".to_string()], rows };
+        let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+
+        let render_data = apply_transformations(&resolved, &data).unwrap();
+        let layers = &render_data.panels[0].layers;
+        // One band per model, each with its own fill color
+        let bands: Vec<(&str, &Vec<f64>, &Vec<f64>)> = layers[0].groups.iter().map(|g| (g.key.as_str(), &g.y_min, &g.y_max)).collect();
+        assert_eq!(bands, vec![("a", &vec![0.0, 1.0], &vec![2.0, 3.0]), ("b", &vec![5.0, 6.0], &vec![7.0, 8.0])]);
+        let style = |g: &GroupData| match &g.style {
+            RenderStyle::Ribbon(style) => (style.color.clone(), style.alpha),
+            _ => panic!("Expected Ribbon style"),
+        };
+        let (color_a, alpha_a) = style(&layers[0].groups[0]);
+        let (color_b, _) = style(&layers[0].groups[1]);
+        assert_ne!(color_a, color_b);
+        // Translucent by default; an explicit alpha wins
+        assert_eq!(alpha_a, Some(0.3));
+        assert_eq!(style(&layers[1].groups[0]).1, Some(0.8));
+    }
+
+    #[test]
+    fn test_transform_abline_lines_per_group() {
+        let rows = [("a", "1.5", "0.5"), ("a", "1.5", "0.5"), ("b", "0.5", "1")].iter()
+            .map(|(model, slope, intercept)| vec!["1".to_string(), model.to_string(), slope.to_string(), intercept.to_string()])
+            .collect();
+        let data = PlotData { headers: ["x", "model", "slope", "intercept"].map(String::from).to_vec(), rows };
+        let (spec, _) = crate::parser::parse_plot_spec("abline(slope: slope, intercept: intercept, color: model) | abline(slope: 2)").unwrap();
+        let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+
+        let render_data = apply_transformations(&resolved, &data).unwrap();
+        let layers = &render_data.panels[0].layers;
+        // One line per distinct row value in each group; none of them adds points to the scales
+        let lines: Vec<(&str, &Vec<(f64, f64)>)> = layers[0].groups.iter().map(|g| (g.key.as_str(), &g.ablines)).collect();
+        assert_eq!(lines, vec![("a", &vec![(1.5, 0.5)]), ("b", &vec![(0.5, 1.0)])]);
+        assert!(layers[0].groups.iter().all(|g| g.x.is_empty()));
+        assert_eq!(layers[1].groups[0].ablines, vec![(2.0, 0.0)]);
+
+        let (spec, _) = crate::parser::parse_plot_spec("abline(slope: model)").unwrap();
+        let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+        assert!(apply_transformations(&resolved, &data).unwrap_err().to_string().contains("abline() value 'a'"));
+    }
+
+    #[test]
+    fn test_transform_segment_ends_per_group() {
+        let rows = [("0", "1", "2", "3", "a"), ("1", "1", "1", "0", "b"), ("5", "5", "6", "4", "a")].iter()
+            .map(|(x, y, x2, y2, g)| [x, y, x2, y2, g].map(|v| v.to_string()).to_vec())
+            .collect();
+        let data = PlotData { headers: ["x", "y", "x2", "y2", "g"].map(String::from).to_vec(), rows };
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: x, y: y, color: g) | segment(xend: x2, yend: y2)").unwrap();
+        let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+
+        let render_data = apply_transformations(&resolved, &data).unwrap();
+        let groups = &render_data.panels[0].layers[0].groups;
+        let lines: Vec<_> = groups.iter().map(|g| (g.key.as_str(), &g.x, &g.y, &g.segment_ends)).collect();
+        assert_eq!(lines, vec![
+            ("a", &vec![0.0, 5.0], &vec![1.0, 5.0], &vec![(2.0, 3.0), (6.0, 4.0)]),
+            ("b", &vec![1.0], &vec![1.0], &vec![(1.0, 0.0)]),
+        ]);
+
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: x, y: y) | segment(xend: x2)").unwrap();
+        let err = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap_err();
+        assert!(err.to_string().contains("segment() requires xend and yend"));
+    }
+
+    #[test]
+    fn test_layers_inherit_binned_histogram() {
+        let (spec, _) = crate::parser::parse_plot_spec(r#"aes(x: x) | histogram(bins: 2, stat_output_as: "binned") | point() | point(x: y, y: y)"#).unwrap();
+        let rows = [1.0, 1.5, 2.0, 9.0, 10.0].iter().map(|x| vec![x.to_string(), "3".to_string()]).collect();
+        let data = PlotData { headers: vec!["x".to_string(), "y".to_string()], rows };
+        let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+        let inherits: Vec<bool> = resolved.layers.iter().map(|l| l.inherits_binned).collect();
+        assert_eq!(inherits, vec![false, true, false]);
+
+        let render_data = apply_transformations(&resolved, &data).unwrap();
+        let layers = &render_data.panels[0].layers;
+        let (bars, points, raw) = (&layers[0].groups[0], &layers[1].groups[0], &layers[2].groups[0]);
+        // One point per bin, on its bar
+        assert_eq!(points.x, bars.x);
+        assert_eq!(points.y, bars.y);
+        assert_eq!(points.y.iter().sum::<f64>(), 5.0);
+        // A layer on another x still sees the rows
+        assert_eq!(raw.x.len(), 5);
+    }
+
+    #[test]
+    fn test_compute_smooth_stat_lm_uses_two_points() {
+        let groups = smooth_groups(&[0.0, 1.0, 2.0, 3.0], &[1.0, 3.0, 5.0, 7.0]);
+        let result = compute_smooth_stat(groups, &SmoothMethod::Lm, 80, false, None, SeType::None, None).unwrap();
+        let line = &result["__all__"];
+        assert_eq!(line.x, vec!["0", "3"]);
+        assert!((line.y[0] - 1.0).abs() < 1e-9);
+        assert!((line.y[1] - 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_compute_smooth_stat_interval_bands() {
+        let xs = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let ys = [1.0, 2.5, 5.5, 6.5, 9.0];
+        let band = |se| {
+            let result = compute_smooth_stat(smooth_groups(&xs, &ys), &SmoothMethod::Lm, 5, false, None, se, None).unwrap();
+            let line = result["__all__"].clone();
+            assert_eq!(line.x.len(), 5);
+            line.ymin.iter().zip(&line.ymax).map(|(lo, hi)| hi - lo).collect::<Vec<f64>>()
+        };
+        let confidence = band(SeType::Confidence(0.95));
+        let prediction = band(SeType::Prediction(0.95));
+        // Narrowest at the mean of x, and prediction bands are wider everywhere
+        assert!(confidence[2] < confidence[0] && confidence[2] < confidence[4]);
+        assert!(prediction.iter().zip(&confidence).all(|(p, c)| p > c));
+
+        // At x_bar: residual SS 0.7 over df 3, t(0.975, 3) = 3.182446, so t * s * sqrt(1/5) and sqrt(1 + 1/5)
+        assert!((confidence[2] / 2.0 - 0.687487).abs() < 1e-5);
+        assert!((prediction[2] / 2.0 - 1.683992).abs() < 1e-5);
+
+        // Curved fits have no closed-form band
+        let err = compute_smooth_stat(smooth_groups(&xs, &ys), &SmoothMethod::Poly, 5, false, None, SeType::Confidence(0.95), None).unwrap_err();
+        assert!(err.to_string().contains("straight-line"));
+    }
+
+    #[test]
+    fn test_student_t_quantile() {
+        assert!((student_t_quantile(0.975, 1.0) - 12.706205).abs() < 1e-5);
+        assert!((student_t_quantile(0.975, 10.0) - 2.228139).abs() < 1e-5);
+        assert!((student_t_quantile(0.95, 5.0) - 2.015048).abs() < 1e-5);
+        assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_compute_smooth_stat_fullrange_extrapolates() {
+        let groups = smooth_groups(&[1.0, 2.0, 3.0], &[3.0, 5.0, 7.0]);
+        let result = compute_smooth_stat(groups, &SmoothMethod::Lm, 80, false, None, SeType::None, Some((-1.0, 10.0))).unwrap();
+        let line = &result["__all__"];
+        assert_eq!(line.x, vec!["-1", "10"]);
+        assert!((line.y[0] - -1.0).abs() < 1e-9);
+        assert!((line.y[1] - 21.0).abs() < 1e-9);
+
+        // A log fit cannot reach x <= 0, so it starts at the data instead
+        let groups = smooth_groups(&[1.0, 2.0, 4.0], &[0.0, 1.0, 2.0]);
+        let result = compute_smooth_stat(groups, &SmoothMethod::Lm, 5, false, Some("y ~ log(x)"), SeType::None, Some((-1.0, 8.0))).unwrap();
+        let xs = &result["__all__"].x;
+        assert_eq!((xs[0].as_str(), xs[4].as_str()), ("1", "8"));
+    }
+
+    #[test]
+    fn test_smooth_fullrange_spans_other_layers() {
+        let data = PlotData {
+            headers: vec!["x".to_string(), "y".to_string(), "g".to_string()],
+            rows: [("0", "0", "a"), ("1", "1", "a"), ("5", "2", "b"), ("10", "3", "b")].iter()
+                .map(|&(x, y, g)| vec![x.to_string(), y.to_string(), g.to_string()])
+                .collect(),
+        };
+        let x_starts = |dsl: &str| {
+            let (spec, _) = crate::parser::parse_plot_spec(dsl).unwrap();
+            let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+            let render = apply_transformations(&resolved, &data).unwrap();
+            let groups = &render.panels[0].layers[1].groups;
+            groups.iter().map(|g| (g.x[0], *g.x.last().unwrap())).collect::<Vec<_>>()
+        };
+        assert_eq!(x_starts("aes(x: x, y: y, color: g) | point() | smooth()"), vec![(0.0, 1.0), (5.0, 10.0)]);
+        assert_eq!(x_starts("aes(x: x, y: y, color: g) | point() | smooth(fullrange: true)"), vec![(0.0, 10.0); 2]);
+        assert_eq!(x_starts("aes(x: x, y: y) | point() | smooth(fullrange: true) | scale_x_continuous(limits: [-5, 20])"), vec![(-5.0, 20.0)]);
+    }
+
+    #[test]
+    fn test_compute_smooth_stat_residuals() {
+        // Fit through (0,0),(1,2),(2,2),(3,4) is y = 0.2 + 1.2x
+        let groups = smooth_groups(&[0.0, 1.0, 2.0, 3.0], &[0.0, 2.0, 2.0, 4.0]);
+        let result = compute_smooth_stat(groups.clone(), &SmoothMethod::Lm, 80, true, None, SeType::None, None).unwrap();
+        let residuals = result["__all__"].residuals.as_ref().unwrap();
+        let expected = [(0.0, -0.2), (1.0, 0.6), (2.0, -0.6), (3.0, 0.2)];
+        assert_eq!(residuals.len(), expected.len());
+        for (&(x, r), &(ex, er)) in residuals.iter().zip(expected.iter()) {
+            assert_eq!(x, ex);
+            assert!((r - er).abs() < 1e-9, "residual at {} was {}", x, r);
+        }
+
+        let result = compute_smooth_stat(groups, &SmoothMethod::Lm, 80, false, None, SeType::None, None).unwrap();
+        assert!(result["__all__"].residuals.is_none());
+    }
+
+    #[test]
+    fn test_compute_quantile_stat_fits_each_quantile() {
+        // Three points per x at y = 2x - 1, 2x, 2x + 1: the median is y = 2x, the 0.9 quantile the top row
+        let mut xs = Vec::new();
+        let mut ys = Vec::new();
+        for x in 0..10 {
+            for offset in [-1.0, 0.0, 1.0] {
+                xs.push(x as f64);
+                ys.push(2.0 * x as f64 + offset);
+            }
+        }
+        let mut groups = smooth_groups(&xs, &ys);
+        let all = groups.remove("__all__").unwrap();
+        groups.insert("default".to_string(), all);
+        let result = compute_quantile_stat(groups, &[0.5, 0.9]).unwrap();
+        let keys = get_sorted_keys(&result);
+        assert_eq!(keys, vec!["0.5", "0.9"]);
+
+        let median = &result["0.5"];
+        assert_eq!(median.x, vec!["0", "9"]);
+        assert!((median.y[0] - 0.0).abs() < 1e-3 && (median.y[1] - 18.0).abs() < 1e-3, "{:?}", median.y);
+        let upper = &result["0.9"];
+        assert!((upper.y[0] - 1.0).abs() < 1e-3 && (upper.y[1] - 19.0).abs() < 1e-3, "{:?}", upper.y);
+    }
+
+    #[test]
+    fn test_compute_ecdf_stat_steps_and_padding() {
+        let xs = [3.0, 1.0, 2.0, 2.0, 4.0];
+        let groups = smooth_groups(&xs, &[0.0; 5]);
+        let result = compute_ecdf_stat(groups.clone(), true, None).unwrap();
+        let ecdf = &result["__all__"];
+        // Padded by 0.1% of the 1..4 range, then a vertical jump at each unique x (2 is duplicated)
+        assert_eq!(ecdf.x, vec!["0.997", "1", "1", "2", "2", "3", "3", "4", "4", "4.003"]);
+        assert_eq!(ecdf.y, vec![0.0, 0.0, 0.2, 0.2, 0.6, 0.6, 0.8, 0.8, 1.0, 1.0]);
+
+        let result = compute_ecdf_stat(groups.clone(), false, None).unwrap();
+        let ecdf = &result["__all__"];
+        assert_eq!(ecdf.x, vec!["1", "2", "2", "3", "3", "4", "4"]);
+        assert_eq!(ecdf.y, vec![0.2, 0.2, 0.6, 0.6, 0.8, 0.8, 1.0]);
+
+        // n: 2 keeps only the end points, still reaching 1 at the largest x
+        let result = compute_ecdf_stat(groups, false, Some(2)).unwrap();
+        assert_eq!(result["__all__"].x, vec!["1", "4", "4"]);
+        assert_eq!(result["__all__"].y, vec![0.2, 0.2, 1.0]);
+    }
+
+    #[test]
+    fn test_compute_contour_stat_traces_closed_levels() {
+        // A symmetric cloud: every level is one closed loop around the center
+        let mut xs = Vec::new();
+        let mut ys = Vec::new();
+        for i in -4..=4 {
+            for j in -4..=4 {
+                let weight = 5 - i32::max(i32::abs(i), i32::abs(j));
+                for _ in 0..weight {
+                    xs.push(f64::from(i));
+                    ys.push(f64::from(j));
+                }
+            }
+        }
+        let result = compute_contour_stat(smooth_groups(&xs, &ys), 3, false, (None, None)).unwrap();
+        let contours = result["__all__"].contours.as_ref().unwrap();
+
+        assert_eq!(contours.len(), 3);
+        for (level, path) in contours {
+            assert!(*level > 0.0);
+            assert_eq!(path.first(), path.last());
+            assert!(path.iter().all(|&(x, y)| x.abs() <= 4.0 && y.abs() <= 4.0));
+        }
+        // Higher levels enclose smaller regions
+        let extent = |path: &Vec<(f64, f64)>| path.iter().fold(0.0f64, |a, p| a.max(p.0.abs()));
+        assert!(extent(&contours[0].1) > extent(&contours[2].1));
+
+        // Filled bands are tagged with the lower level of the band they cover
+        let result = compute_contour_stat(smooth_groups(&xs, &ys), 3, true, (None, None)).unwrap();
+        let bands = result["__all__"].contours.as_ref().unwrap();
+        let levels: Vec<f64> = contours.iter().map(|(l, _)| *l).collect();
+        assert!(!bands.is_empty());
+        assert!(bands.iter().all(|(l, poly)| levels.contains(l) && poly.len() >= 3));
+
+        // Bands tile the plane without overlapping, so a lower band leaves a hole where the
+        // next one starts: the peak is covered by the top band only
+        let contains = |poly: &[(f64, f64)], (px, py): (f64, f64)| {
+            let mut inside = false;
+            for (k, &(xa, ya)) in poly.iter().enumerate() {
+                let (xb, yb) = poly[(k + 1) % poly.len()];
+                if (ya > py) != (yb > py) && px < xa + (py - ya) * (xb - xa) / (yb - ya) {
+                    inside = !inside;
+                }
+            }
+            inside
+        };
+        let peak = (0.01, 0.02);
+        let covering: Vec<f64> = bands.iter().filter(|(_, poly)| contains(poly, peak)).map(|(l, _)| *l).collect();
+        assert_eq!(covering, vec![levels[2]]);
+
+        // A narrower x bandwidth (density_2d(bandwidth_x: ...)) pulls the outer level in along x only
+        let result = compute_contour_stat(smooth_groups(&xs, &ys), 3, false, (Some(0.3), None)).unwrap();
+        let narrow = &result["__all__"].contours.as_ref().unwrap()[0].1;
+        let y_extent = |path: &Vec<(f64, f64)>| path.iter().fold(0.0f64, |a, p| a.max(p.1.abs()));
+        assert!(extent(narrow) < extent(&contours[0].1));
+        assert!(extent(narrow) < y_extent(narrow));
+    }
+
+    #[test]
+    fn test_clip_to_level_splits_triangle() {
+        let tri = [(0.0, 0.0, 0.0), (2.0, 0.0, 2.0), (0.0, 2.0, 0.0)];
+        let above = clip_to_level(&tri, 1.0, true);
+        assert_eq!(above, vec![(1.0, 0.0, 1.0), (2.0, 0.0, 2.0), (1.0, 1.0, 1.0)]);
+        assert_eq!(clip_to_level(&tri, 1.0, false).len(), 4);
+    }
+
+    #[test]
+    fn test_compute_summary_stat_evaluates_formulas_per_x() {
+        let xs = ["a", "a", "a", "a", "b"];
+        let mut groups: RawGroups = HashMap::new();
+        groups.insert("__all__".to_string(), (xs.iter().map(|s| s.to_string()).collect(), vec![1.0, 2.0, 3.0, 6.0, 5.0], vec![], vec![]));
+        let mean = || Box::new(SummaryExpr::Fun(SummaryFun::Mean));
+        let sd = || Box::new(SummaryExpr::Fun(SummaryFun::Sd));
+        let result = compute_summary_stat(
+            groups,
+            &SummaryExpr::Fun(SummaryFun::Median),
+            &SummaryExpr::Sub(mean(), sd()),
+            &SummaryExpr::Add(Box::new(SummaryExpr::Add(mean(), sd())), Box::new(SummaryExpr::Fun(SummaryFun::Se))),
+        ).unwrap();
+        let summary = &result["__all__"];
+
+        // a: mean 3, sd sqrt(14/3), se sd/2; b: a single value has sd 0
+        let sd_a = (14.0f64 / 3.0).sqrt();
+        assert_eq!(summary.x, vec!["a", "b"]);
+        assert_eq!(summary.y, vec![2.5, 5.0]);
+        assert!((summary.ymin[0] - (3.0 - sd_a)).abs() < 1e-12);
+        assert!((summary.ymax[0] - (3.0 + sd_a + sd_a / 2.0)).abs() < 1e-12);
+        assert_eq!((summary.ymin[1], summary.ymax[1]), (5.0, 5.0));
+    }
+
+    #[test]
+    fn test_compute_density_stat_shares_grid_across_groups() {
+        let mut groups: RawGroups = HashMap::new();
+        groups.insert("a".to_string(), (vec!["0".into(), "1".into(), "2".into()], vec![], vec![], vec![]));
+        groups.insert("b".to_string(), (vec!["10".into(), "11".into(), "12".into()], vec![], vec![], vec![]));
+        let result = compute_density_stat(groups, &BandwidthMethod::Fixed(1.0), 101).unwrap();
+        let (a, b) = (&result["a"], &result["b"]);
+
+        // Both curves span -3..15 (the union of 0-3bw..2+3bw and 10-3bw..12+3bw)
+        assert_eq!(a.x, b.x);
+        assert_eq!(a.x.len(), 101);
+        assert_eq!(a.x[0], "-3");
+        assert_eq!(a.x[100], "15");
+        assert!(a.ymin.iter().all(|&y| y == 0.0));
+
+        // Each curve integrates to ~1 and peaks inside its own data
+        let step = 18.0 / 100.0;
+        assert!((a.y.iter().sum::<f64>() * step - 1.0).abs() < 0.01);
+        let peak = |d: &StatData| d.x[d.y.iter().enumerate().max_by(|p, q| p.1.total_cmp(q.1)).unwrap().0].parse::<f64>().unwrap();
+        assert!((peak(a) - 1.0).abs() < 0.2);
+        assert!((peak(b) - 11.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_compute_smooth_stat_poly_evaluates_n_points() {
+        let xs = [-2.0, -1.0, 0.0, 1.0, 2.0, 3.0];
+        let ys: Vec<f64> = xs.iter().map(|x| x * x).collect();
+        let result = compute_smooth_stat(smooth_groups(&xs, &ys), &SmoothMethod::Poly, 11, false, None, SeType::None, None).unwrap();
+        let curve = &result["__all__"];
+        assert_eq!(curve.x.len(), 11);
+        assert_eq!(curve.x[0], "-2");
+        assert_eq!(curve.x[10], "3");
+        // x = 0.5 is the sixth point; the quadratic is recovered exactly
+        assert!((curve.y[5] - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_compute_smooth_stat_formula() {
+        let xs = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let cubic: Vec<f64> = xs.iter().map(|x| x * x * x - 2.0 * x).collect();
+        // The formula wins over method:
+        let result = compute_smooth_stat(smooth_groups(&xs, &cubic), &SmoothMethod::Loess { span: 0.5 }, 11, false, Some("y ~ poly(x, 3)"), SeType::None, None).unwrap();
+        let curve = &result["__all__"];
+        assert_eq!(curve.x.len(), 11);
+        assert!((curve.y[1] - (1.5f64.powi(3) - 3.0)).abs() < 1e-6);
+
+        let log_linear: Vec<f64> = xs.iter().map(|x| 2.0 * x.ln() + 1.0).collect();
+        let result = compute_smooth_stat(smooth_groups(&xs, &log_linear), &SmoothMethod::Lm, 11, false, Some("y~log(x)"), SeType::None, None).unwrap();
+        let curve = &result["__all__"];
+        assert_eq!(curve.x.len(), 11);
+        assert!((curve.y[1] - (2.0 * 1.5f64.ln() + 1.0)).abs() < 1e-9);
+
+        let result = compute_smooth_stat(smooth_groups(&xs, &log_linear), &SmoothMethod::Poly, 11, false, Some("y ~ x"), SeType::None, None).unwrap();
+        assert_eq!(result["__all__"].x.len(), 2);
+
+        let err = compute_smooth_stat(smooth_groups(&xs, &cubic), &SmoothMethod::Lm, 11, false, Some("y ~ exp(x)"), SeType::None, None).unwrap_err();
+        assert!(err.to_string().contains("y ~ poly(x, n)"));
+        let err = compute_smooth_stat(smooth_groups(&[0.0, 1.0], &[1.0, 2.0]), &SmoothMethod::Lm, 11, false, Some("y ~ log(x)"), SeType::None, None).unwrap_err();
+        assert!(err.to_string().contains("positive"));
+    }
+
+    #[test]
+    fn test_compute_smooth_stat_loess() {
+        // Noisy sine: a small span tracks the wave, a large one flattens it
+        let xs: Vec<f64> = (0..40).map(|i| i as f64 * 0.25).collect();
+        let ys: Vec<f64> = xs.iter().enumerate()
+            .map(|(i, x)| x.sin() + if i % 2 == 0 { 0.05 } else { -0.05 })
+            .collect();
+        let eval = |span: f64| {
+            let result = compute_smooth_stat(smooth_groups(&xs, &ys), &SmoothMethod::Loess { span }, 40, false, None, SeType::None, None).unwrap();
+            result["__all__"].clone()
+        };
+
+        let wiggly = eval(0.2);
+        assert_eq!(wiggly.x.len(), 40);
+        let max_err = wiggly.x.iter().zip(wiggly.y.iter())
+            .map(|(x, y)| (x.parse::<f64>().unwrap().sin() - y).abs())
+            .fold(0.0, f64::max);
+        assert!(max_err < 0.1, "max error {}", max_err);
+
+        // An exact line is reproduced for any span
+        let line: Vec<f64> = xs.iter().map(|x| 2.0 * x + 1.0).collect();
+        let result = compute_smooth_stat(smooth_groups(&xs, &line), &SmoothMethod::Loess { span: 0.3 }, 5, false, None, SeType::None, None).unwrap();
+        for (x, y) in result["__all__"].x.iter().zip(result["__all__"].y.iter()) {
+            assert!((2.0 * x.parse::<f64>().unwrap() + 1.0 - y).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_violin_scale_count() {
+        let mut groups: RawGroups = HashMap::new();
+        let x: Vec<String> = ["a"; 4].iter().chain(["b"; 8].iter()).map(|s| s.to_string()).collect();
+        let y = vec![1.0, 2.0, 3.0, 4.0, 1.0, 2.0, 3.0, 4.0, 1.0, 2.0, 3.0, 4.0];
+        groups.insert("__all__".to_string(), (x, y.clone(), y.clone(), y));
+        let bw = BandwidthMethod::Fixed(1.0);
+        let max_of = |v: &[f64]| v.iter().fold(0.0f64, |a, &b| a.max(b));
+
+        let width = compute_violin_stat(groups.clone(), &[], &bw, &ViolinScale::Width, true).unwrap();
+        let density = &width["__all__"].violin.as_ref().unwrap().density;
+        assert!((max_of(&density[0]) - 1.0).abs() < 1e-9);
+        assert!((max_of(&density[1]) - 1.0).abs() < 1e-9);
+
+        // Same shape, twice the observations -> "a" peaks at 1/sqrt(2) of the width of "b"
+        let count = compute_violin_stat(groups, &[], &bw, &ViolinScale::Count, true).unwrap();
+        let density = &count["__all__"].violin.as_ref().unwrap().density;
+        assert!((max_of(&density[0]) - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-9);
+        assert!((max_of(&density[1]) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_violin_quantile_values_are_data_percentiles() {
+        let mut groups: RawGroups = HashMap::new();
+        let x: Vec<String> = ["a"; 4].iter().chain(["b"; 2].iter()).map(|s| s.to_string()).collect();
+        let y = vec![4.0, 1.0, 3.0, 2.0, 10.0, 20.0];
+        groups.insert("__all__".to_string(), (x, y.clone(), y.clone(), y));
+        let bw = BandwidthMethod::Fixed(0.5);
+
+        // Linear interpolation between order statistics, per category, whether or not the tails are trimmed
+        for trim in [true, false] {
+            let result = compute_violin_stat(groups.clone(), &[0.25, 0.5, 0.75], &bw, &ViolinScale::Width, trim).unwrap();
+            let quantiles = &result["__all__"].violin.as_ref().unwrap().quantile_values;
+            assert_eq!(quantiles, &vec![vec![1.75, 2.5, 3.25], vec![12.5, 15.0, 17.5]]);
+        }
+    }
+
+    #[test]
+    fn test_violin_trim() {
+        let mut groups: RawGroups = HashMap::new();
+        let y = vec![1.0, 2.0, 3.0, 4.0];
+        groups.insert("__all__".to_string(), (vec!["a".to_string(); 4], y.clone(), y.clone(), y));
+        let bw = BandwidthMethod::Fixed(0.5);
+
+        let trimmed = compute_violin_stat(groups.clone(), &[], &bw, &ViolinScale::Width, true).unwrap();
+        assert_eq!(trimmed["__all__"].ymin, vec![1.0]);
+        assert_eq!(trimmed["__all__"].ymax, vec![4.0]);
+
+        // Untrimmed violins extend 3 bandwidths past the data
+        let full = compute_violin_stat(groups, &[], &bw, &ViolinScale::Width, false).unwrap();
+        assert!((full["__all__"].ymin[0] - -0.5).abs() < 1e-9);
+        assert!((full["__all__"].ymax[0] - 5.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_bandwidth_rules() {
+        let data = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let sd = (6.0f64).sqrt();
+        let n_factor = 8f64.powf(-0.2);
+        assert!((scott_bandwidth(&data) - 1.06 * sd * n_factor).abs() < 1e-12);
+        assert_eq!(select_bandwidth(&data, &BandwidthMethod::Nrd0), silverman_bandwidth(&data));
+        assert_eq!(select_bandwidth(&data, &BandwidthMethod::Fixed(0.3)), 0.3);
+
+        let ucv = select_bandwidth(&data, &BandwidthMethod::Ucv);
+        let upper = 1.144 * sd * n_factor;
+        assert!(ucv >= 0.1 * upper - 1e-12 && ucv <= upper + 1e-12);
+
+        // Constant data falls back to a unit bandwidth
+        assert_eq!(scott_bandwidth(&[2.0, 2.0, 2.0]), 1.0);
+    }
+
+    #[test]
+    fn test_compute_prop_stat_within_and_across_groups() {
+        let mut groups: RawGroups = HashMap::new();
+        groups.insert("A".to_string(), (
+            vec!["a".to_string(), "a".to_string(), "b".to_string()],
+            vec![0.0; 3], vec![0.0; 3], vec![0.0; 3],
+        ));
+        groups.insert("B".to_string(), (vec!["a".to_string()], vec![0.0], vec![0.0], vec![0.0]));
+
+        let within = compute_prop_stat(groups.clone(), false).unwrap();
+        let a = &within["A"];
+        assert_eq!(a.x, vec!["a", "b"]);
+        assert!((a.y[0] - 2.0 / 3.0).abs() < 1e-9);
+        assert!((a.y[1] - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(within["B"].y, vec![1.0]);
+
+        let across = compute_prop_stat(groups, true).unwrap();
+        assert!((across["A"].y[0] - 0.5).abs() < 1e-9);
+        assert!((across["B"].y[0] - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_transform_brewer_colors() {
+        let mut spec = make_spec();
+        spec.color_scale = Some(ColorScale::Brewer { palette: "Set1".to_string() });
+        let render_data = apply_transformations(&spec, &make_data()).unwrap();
+        let group_b = render_data.panels[0].layers[0].groups.iter().find(|g| g.key == "B").unwrap();
+        match &group_b.style {
+            RenderStyle::Line(s) => assert_eq!(s.color.as_deref(), Some("#377eb8")),
+            _ => panic!("Expected line style"),
+        }
+
+        spec.color_scale = Some(ColorScale::Brewer { palette: "Unknown".to_string() });
+        assert!(apply_transformations(&spec, &make_data()).is_err());
+    }
+
+    #[test]
+    fn test_transform_brewer_colors_shared_across_facets() {
+        // Panel "p" has groups a, b, c; panel "q" only c
+        let rows = [("p", "a"), ("p", "b"), ("p", "c"), ("q", "c")].iter()
+            .map(|(f, g)| vec!["1".to_string(), "1".to_string(), f.to_string(), g.to_string()])
+            .collect();
+        let data = PlotData { headers: ["x", "y", "f", "g"].map(String::from).to_vec(), rows };
+        let (spec, _) = crate::parser::parse_plot_spec(r#"aes(x: x, y: y, color: g) | point() | facet_wrap(by: f) | scale_color_brewer(palette: "Blues")"#).unwrap();
+        let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+        let render_data = apply_transformations(&resolved, &data).unwrap();
+
+        let color_of = |panel: usize, key: &str| render_data.panels[panel].layers[0].groups.iter()
+            .find(|g| g.key == key)
+            .map(|g| match &g.style { RenderStyle::Point(s) => s.color.clone(), _ => panic!("Expected point style") })
+            .unwrap();
+        assert_eq!(color_of(1, "c"), color_of(0, "c"));
+        assert_ne!(color_of(0, "a"), color_of(0, "c"));
+    }
+
+    #[test]
+    fn test_transform_identity_scales_use_column_values() {
+        let rows = [("1", "red", "triangle", "dotted"), ("2", "#00ff00", "square", "dashed")].iter()
+            .map(|(x, color, shape, linetype)| vec![x.to_string(), "1".to_string(), color.to_string(), shape.to_string(), linetype.to_string()])
+            .collect();
+        let data = PlotData { headers: ["x", "y", "color", "shape", "linetype"].map(String::from).to_vec(), rows };
+        let render = |dsl: &str| {
+            let (spec, _) = crate::parser::parse_plot_spec(dsl).unwrap();
+            let resolved = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+            apply_transformations(&resolved, &data)
+        };
+
+        let render_data = render("aes(x: x, y: y) | point(color: color) | point(shape: shape) | line(linetype: linetype) | scale_color_identity() | scale_shape_identity() | scale_linetype_identity()").unwrap();
+        let layers = &render_data.panels[0].layers;
+        let points: Vec<(Option<String>, Option<String>)> = layers[0].groups.iter().chain(&layers[1].groups)
+            .map(|g| match &g.style {
+                RenderStyle::Point(s) => (s.color.clone(), s.shape.clone()),
+                _ => panic!("Expected point style"),
+            })
+            .collect();
+        assert_eq!(points, vec![
+            (Some("#00ff00".to_string()), None),
+            (Some("red".to_string()), None),
+            (None, Some("square".to_string())),
+            (None, Some("triangle".to_string())),
+        ]);
+        let linetypes: Vec<Option<String>> = layers[2].groups.iter()
+            .map(|g| match &g.style { RenderStyle::Line(s) => s.linetype.clone(), _ => panic!("Expected line style") })
+            .collect();
+        assert_eq!(linetypes, vec![Some("dashed".to_string()), Some("dotted".to_string())]);
+
+        // Without a color mapping the identity color scale has nothing to do
+        assert!(render("aes(x: x, y: y) | point(shape: linetype) | scale_color_identity()").is_ok());
+        let err = render("aes(x: x, y: y) | point(color: shape) | scale_color_identity()").unwrap_err();
+        assert!(err.to_string().contains("'square' in column 'shape' is not a color"));
+        let err = render("aes(x: x, y: y) | point(shape: color) | scale_shape_identity()").unwrap_err();
+        assert!(err.to_string().contains("'#00ff00' in column 'color' is not a shape name"));
+        let err = render("aes(x: x, y: y) | line(linetype: shape) | scale_linetype_identity()").unwrap_err();
+        assert!(err.to_string().contains("'square' in column 'shape' is not a linetype name"));
+        let err = render("aes(x: x, y: y) | point(color: color, shape: shape) | scale_shape_identity()").unwrap_err();
+        assert!(err.to_string().contains("grouped by 'color'"));
+    }
+
+    #[test]
+    fn test_transform_linetype_mapping() {
+        let mut spec = make_spec();
+        spec.layers[0].aesthetics.color = None;
+        spec.layers[0].aesthetics.linetype = Some("cat".to_string());
+        spec.linetype_values = Some(vec!["dotted".to_string(), "dashed".to_string()]);
+        let render_data = apply_transformations(&spec, &make_data()).unwrap();
+        let groups = &render_data.panels[0].layers[0].groups;
+        assert_eq!(groups.len(), 2);
+        let linetype = |key: &str| match &groups.iter().find(|g| g.key == key).unwrap().style {
+            RenderStyle::Line(s) => s.linetype.clone(),
+            _ => panic!("Expected line style"),
+        };
+        assert_eq!(linetype("A").as_deref(), Some("dotted"));
+        assert_eq!(linetype("B").as_deref(), Some("dashed"));
+    }
+
     #[test]
     fn test_transform_facet() {
         let mut spec = make_spec();
@@ -925,6 +3534,8 @@ mod tests {
             col: "cat".to_string(),
             ncol: None,
             scales: crate::parser::ast::FacetScales::Fixed,
+            strip_position: crate::parser::ast::StripPosition::Top,
+            space: crate::parser::ast::FacetSpace::Fixed,
         });
         
         let csv = make_data();
@@ -934,4 +3545,40 @@ mod tests {
         assert_eq!(render_data.facet_layout.panel_titles.len(), 2);
         assert!(render_data.facet_layout.panel_titles.contains(&"A".to_string()));
     }
+
+    #[test]
+    fn test_filled_contour_bands_use_a_linear_gradient() {
+        let rows = (0..30).map(|i| vec![(i % 6).to_string(), (i / 6).to_string()]).collect();
+        let data = PlotData { headers: vec!["x".to_string(), "y".to_string()], rows };
+        let (spec, _) = crate::parser::parse_plot_spec("aes(x: x, y: y) | contour_filled(bins: 4)").unwrap();
+        let spec = crate::resolve::resolve_plot_aesthetics(&spec, &data).unwrap();
+        let render_data = apply_transformations(&spec, &data).unwrap();
+        let group = &render_data.panels[0].layers[0].groups[0];
+        let top = group.contours.iter().fold(0.0f64, |a, (level, _)| a.max(*level));
+        // Hexbin counts default to a log10 gradient; density levels are mapped linearly
+        let linear = ColorGradient::from_scale(&FillScale::default(), false).unwrap();
+        let expected: Vec<String> = group.contours.iter().map(|(level, _)| linear.map(*level, (0.0, top))).collect();
+        assert!(!expected.is_empty());
+        assert_eq!(group.fills, expected);
+    }
+
+    #[test]
+    fn test_apply_transformations_reports_progress_per_panel() {
+        let mut spec = make_spec();
+        spec.facet = Some(ResolvedFacet {
+            col: "cat".to_string(),
+            ncol: None,
+            scales: crate::parser::ast::FacetScales::Fixed,
+            strip_position: crate::parser::ast::StripPosition::Top,
+            space: crate::parser::ast::FacetSpace::Fixed,
+        });
+
+        let messages = std::cell::RefCell::new(Vec::new());
+        let collect = |message: String| messages.borrow_mut().push(message);
+        apply_transformations_with_progress(&spec, &make_data(), Some(&collect)).unwrap();
+        assert_eq!(
+            messages.into_inner(),
+            vec!["Partitioning data...", "Computing statistics [1/2]...", "Computing statistics [2/2]..."]
+        );
+    }
 }
@@ -0,0 +1,446 @@
+use crate::data::PlotData;
+use crate::ir::{ResolvedLayer, ResolvedSpec};
+use crate::parser::ast::{AestheticValue, AxisScale, BandwidthMethod, Layer, LayerPosition, PlotSpec, ScaleType, SmoothMethod, Stat};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Categorical axes with more distinct values than this get crowded labels
+const MAX_CATEGORIES: usize = 30;
+
+/// Category of non-fatal problem found before rendering
+#[derive(Debug, Clone, PartialEq)]
+pub enum WarnKind {
+    SmallSampleSize,     // Too few rows for a statistic to be meaningful
+    TooManyCategories,   // Axis labels will overlap
+    DuplicateCategories, // Identity bars drawn on top of each other
+    MissingValues,       // Empty ymin/ymax cells are plotted as 0
+    ColumnNotFound,      // Mapped column is not in the data (usually a typo)
+}
+
+/// Non-fatal issue with a plot, reported on stderr (or as an error with --strict)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationWarning {
+    pub kind: WarnKind,
+    pub message: String,
+    pub suggestion: String,
+}
+
+impl fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.suggestion)
+    }
+}
+
+/// Default rows x groups budget for quadratic stats (about 10 seconds of rendering)
+pub const DEFAULT_COST_THRESHOLD: usize = 10_000;
+
+/// Per-row cost class of the most expensive stat in a plot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StatComplexity {
+    O1,     // Identity, counting, hexagonal binning
+    ONLogN, // Sorting: bins, boxplots, KDE, regression fits
+    ON2,    // Pairwise: loess, ucv bandwidth, beeswarm placement
+}
+
+/// Rough size of a render, estimated before any work is done
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderCost {
+    pub rows: usize,
+    pub groups: usize,
+    pub panels: usize,
+    pub stat: StatComplexity,
+}
+
+impl RenderCost {
+    /// True when a quadratic stat runs over more than `threshold` rows x groups
+    pub fn exceeds(&self, threshold: usize) -> bool {
+        self.stat == StatComplexity::ON2 && self.rows.saturating_mul(self.groups) > threshold
+    }
+}
+
+/// Estimate how expensive rendering `spec` over `data` will be
+pub fn estimate_render_cost(spec: &PlotSpec, data: &PlotData) -> RenderCost {
+    let distinct = |cols: &[&str]| -> usize {
+        let idx: Vec<usize> = cols.iter()
+            .filter_map(|c| data.headers.iter().position(|h| h.eq_ignore_ascii_case(c)))
+            .collect();
+        if idx.is_empty() {
+            return 1;
+        }
+        let keys: HashSet<Vec<&str>> = data.rows.iter()
+            .map(|row| idx.iter().map(|&i| row[i].as_str()).collect())
+            .collect();
+        keys.len().max(1)
+    };
+
+    // Grouping columns: global aesthetics plus per-layer color mappings
+    let mut group_cols: Vec<&str> = Vec::new();
+    if let Some(aes) = &spec.aesthetics {
+        group_cols.extend([&aes.color, &aes.shape, &aes.linetype].into_iter().flatten().map(String::as_str));
+    }
+    for layer in &spec.layers {
+        let color = match layer {
+            Layer::Line(l) => &l.color,
+            Layer::Point(p) => &p.color,
+            Layer::Bar(b) => &b.color,
+            Layer::Ribbon(r) => &r.color,
+            Layer::Boxplot(b) => &b.color,
+            Layer::Violin(v) => &v.color,
+            Layer::Text(t) => &t.color,
+            Layer::Tile(_) | Layer::Hexbin(_) => &None,
+        };
+        if let Some(AestheticValue::Mapped(c)) = color {
+            group_cols.push(c);
+        }
+    }
+
+    let stat = spec.layers.iter().map(|l| stat_complexity(l.stat())).max().unwrap_or(StatComplexity::O1);
+
+    RenderCost {
+        rows: data.rows.len(),
+        groups: distinct(&group_cols),
+        panels: spec.facet.as_ref().map_or(1, |f| distinct(&[f.by.as_str()])),
+        stat,
+    }
+}
+
+fn stat_complexity(stat: &Stat) -> StatComplexity {
+    match stat {
+        Stat::Identity | Stat::Count | Stat::Prop | Stat::Sum | Stat::Mean | Stat::Max | Stat::Min | Stat::Hexbin { .. } | Stat::Abline { .. } | Stat::Crosshair | Stat::Rug { .. } | Stat::Segment { .. } => StatComplexity::O1,
+        Stat::Smooth { method: SmoothMethod::Loess { .. }, .. } | Stat::Beeswarm { .. } => StatComplexity::ON2,
+        Stat::Violin { bw: BandwidthMethod::Ucv, .. } | Stat::Density { bw: BandwidthMethod::Ucv, .. } => StatComplexity::ON2,
+        Stat::Bin { .. } | Stat::Smooth { .. } | Stat::Quantile { .. } | Stat::Ecdf { .. } | Stat::Density { .. } | Stat::Contour { .. } | Stat::Summary { .. } | Stat::Boxplot | Stat::Violin { .. } => StatComplexity::ONLogN,
+    }
+}
+
+/// Check a resolved spec against the data for likely mistakes
+pub fn validate_spec(spec: &ResolvedSpec, data: &PlotData) -> Vec<ValidationWarning> {
+    let mut warnings = Vec::new();
+    check_columns(spec, data, &mut warnings);
+
+    for layer in &spec.layers {
+        check_sample_size(layer, data, &mut warnings);
+        check_categories(layer, spec, data, &mut warnings);
+        check_missing_ranges(layer, data, &mut warnings);
+    }
+
+    warnings
+}
+
+fn column<'a>(data: &'a PlotData, name: &str) -> Option<impl Iterator<Item = &'a str>> {
+    let idx = data.headers.iter().position(|h| h.eq_ignore_ascii_case(name))?;
+    Some(data.rows.iter().map(move |row| row[idx].as_str()))
+}
+
+/// Every mapped column must exist; suggest the closest header for likely typos
+fn check_columns(spec: &ResolvedSpec, data: &PlotData, warnings: &mut Vec<ValidationWarning>) {
+    let mut reported = HashSet::new();
+    let mapped = spec.layers.iter().flat_map(|l| {
+        let aes = &l.aesthetics;
+        std::iter::once(&aes.x_col)
+            .chain([&aes.y_col, &aes.ymin_col, &aes.ymax_col, &aes.xmin_col, &aes.xmax_col, &aes.color, &aes.size, &aes.shape, &aes.alpha, &aes.linetype, &aes.fill, &aes.label, &aes.colors].into_iter().flatten())
+    });
+    for col in mapped.chain(spec.facet.as_ref().map(|f| &f.col)) {
+        // abline() without an x aesthetic has no x column
+        if col.is_empty() {
+            continue;
+        }
+        if data.headers.iter().any(|h| h.eq_ignore_ascii_case(col)) || !reported.insert(col.to_lowercase()) {
+            continue;
+        }
+        let suggestion = match closest_column(&data.headers, col) {
+            Some(name) => format!("Did you mean '{}'?", name),
+            None => format!("available columns: {}", data.headers.join(", ")),
+        };
+        warnings.push(ValidationWarning {
+            kind: WarnKind::ColumnNotFound,
+            message: format!("column '{}' not found in the data", col),
+            suggestion,
+        });
+    }
+}
+
+/// Header nearest to `name` by case-insensitive edit distance, if close enough to be a typo
+fn closest_column<'a>(headers: &'a [String], name: &str) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(2);
+    headers.iter()
+        .map(|h| (levenshtein(&h.to_lowercase(), &name), h))
+        .filter(|(d, _)| *d <= max_distance)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, h)| h.as_str())
+}
+
+/// Edit distance (insertions, deletions, substitutions) between two strings
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { prev[j] } else { prev[j] + 1 };
+            row.push(cost.min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+fn check_sample_size(layer: &ResolvedLayer, data: &PlotData, warnings: &mut Vec<ValidationWarning>) {
+    let stat = match &layer.original_layer {
+        Layer::Line(l) => &l.stat,
+        _ => return,
+    };
+    let geom = match stat {
+        Stat::Smooth { .. } => "smooth",
+        Stat::Quantile { .. } => "quantile",
+        _ => return,
+    };
+    if data.rows.len() < 3 {
+        warnings.push(ValidationWarning {
+            kind: WarnKind::SmallSampleSize,
+            message: format!("{}() fitted to only {} row(s)", geom, data.rows.len()),
+            suggestion: "a trend needs at least 3 points to be meaningful".to_string(),
+        });
+    }
+}
+
+fn check_categories(layer: &ResolvedLayer, spec: &ResolvedSpec, data: &PlotData, warnings: &mut Vec<ValidationWarning>) {
+    let x_col = &layer.aesthetics.x_col;
+    let Some(values) = column(data, x_col) else { return };
+    let values: Vec<&str> = values.collect();
+    if values.iter().all(|v| v.parse::<f64>().is_ok()) {
+        return;
+    }
+
+    let distinct: HashSet<&str> = values.iter().copied().collect();
+    if distinct.len() > MAX_CATEGORIES {
+        warnings.push(ValidationWarning {
+            kind: WarnKind::TooManyCategories,
+            message: format!("x column '{}' has {} categories; axis labels will overlap", x_col, distinct.len()),
+            suggestion: "filter or aggregate the data, or use coord_flip()".to_string(),
+        });
+    }
+
+    // Identity bars with repeated x values (within one group and panel) are drawn over each other;
+    // stacked, filled and dodged bars are laid out apart
+    let Layer::Bar(bar) = &layer.original_layer else { return };
+    if bar.stat != Stat::Identity || bar.position != LayerPosition::Identity || layer.aesthetics.y_col.is_none() {
+        return;
+    }
+    let values_of = |col: Option<&str>| -> Vec<&str> {
+        match col.and_then(|c| column(data, c)) {
+            Some(v) => v.collect(),
+            None => vec![""; values.len()],
+        }
+    };
+    let groups = values_of(layer.aesthetics.color.as_deref());
+    let panels = values_of(spec.facet.as_ref().map(|f| f.col.as_str()));
+    let mut seen = HashSet::new();
+    if (0..values.len()).any(|i| !seen.insert((values[i], groups[i], panels[i]))) {
+        warnings.push(ValidationWarning {
+            kind: WarnKind::DuplicateCategories,
+            message: format!("x column '{}' repeats values within a bar group; bars will overlap", x_col),
+            suggestion: "summarize first (group_by() | summarize(...)), or use position: \"stack\"".to_string(),
+        });
+    }
+}
+
+fn check_missing_ranges(layer: &ResolvedLayer, data: &PlotData, warnings: &mut Vec<ValidationWarning>) {
+    for col in [&layer.aesthetics.ymin_col, &layer.aesthetics.ymax_col].into_iter().flatten() {
+        let Some(values) = column(data, col) else { continue };
+        let missing = values.filter(|v| v.trim().parse::<f64>().is_err()).count();
+        if missing > 0 {
+            warnings.push(ValidationWarning {
+                kind: WarnKind::MissingValues,
+                message: format!("column '{}' has {} missing or non-numeric value(s), plotted as 0", col, missing),
+                suggestion: "filter out incomplete rows before plotting".to_string(),
+            });
+        }
+    }
+}
+
+/// Log10 axes cannot show values <= 0: every column mapped to a log axis must be positive
+pub fn check_log_scales(spec: &ResolvedSpec, data: &PlotData) -> anyhow::Result<()> {
+    let x_cols: Vec<&str> = spec.layers.iter().map(|l| l.aesthetics.x_col.as_str()).collect();
+    let y_cols: Vec<&str> = spec.layers.iter()
+        .flat_map(|l| [&l.aesthetics.y_col, &l.aesthetics.ymin_col, &l.aesthetics.ymax_col])
+        .filter_map(|c| c.as_deref())
+        .collect();
+    check_log_scale("x", spec.x_scale_spec.as_ref(), &x_cols, data)?;
+    check_log_scale("y", spec.y_scale_spec.as_ref(), &y_cols, data)
+}
+
+fn check_log_scale(axis: &str, scale: Option<&AxisScale>, cols: &[&str], data: &PlotData) -> anyhow::Result<()> {
+    if !scale.is_some_and(|s| s.scale_type == ScaleType::Log10) {
+        return Ok(());
+    }
+    for col in cols {
+        let Some(values) = column(data, col) else { continue };
+        let first = values.enumerate()
+            .filter_map(|(row, v)| v.trim().parse::<f64>().ok().map(|v| (row, v)))
+            .find(|(_, v)| *v <= 0.0);
+        if let Some((row, value)) = first {
+            anyhow::bail!(
+                "scale_{axis}_log10() requires all {axis} values to be positive; found {axis} = {:?} at row {} in column '{}'.",
+                value,
+                row + 1,
+                col
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::ResolvedAesthetics;
+    use crate::parser::ast::{BarLayer, LineLayer, SmoothMethod};
+
+    fn make_spec(layer: Layer, x: &str, y: &str) -> ResolvedSpec {
+        ResolvedSpec {
+            layers: vec![ResolvedLayer {
+                original_layer: layer,
+                inherits_binned: false,
+                aesthetics: ResolvedAesthetics {
+                    x_col: x.to_string(),
+                    y_col: Some(y.to_string()),
+                    ymin_col: None,
+                    ymax_col: None,
+                    xmin_col: None,
+                    xmax_col: None,
+                    color: None,
+                    size: None,
+                    shape: None,
+                    alpha: None,
+                    linetype: None,
+                    fill: None,
+                    label: None,
+                    colors: None,
+                },
+            }],
+            facet: None,
+            coord: None,
+            labels: Default::default(),
+            theme: Default::default(),
+            x_scale_spec: None,
+            y_scale_spec: None,
+            color_scale: None,
+            linetype_values: None,
+            fill_scale: None,
+            identity_scales: Default::default(),
+        }
+    }
+
+    fn make_data(rows: &[(&str, &str)]) -> PlotData {
+        PlotData::new(
+            vec!["x".to_string(), "y".to_string()],
+            rows.iter().map(|(x, y)| vec![x.to_string(), y.to_string()]).collect(),
+        )
+    }
+
+    fn kinds(warnings: &[ValidationWarning]) -> Vec<WarnKind> {
+        warnings.iter().map(|w| w.kind.clone()).collect()
+    }
+
+    #[test]
+    fn test_clean_spec_has_no_warnings() {
+        let spec = make_spec(Layer::Line(LineLayer::default()), "x", "y");
+        let data = make_data(&[("1", "2"), ("2", "3"), ("3", "5")]);
+        assert!(validate_spec(&spec, &data).is_empty());
+    }
+
+    #[test]
+    fn test_smooth_small_sample() {
+        let layer = Layer::Line(LineLayer {
+            stat: Stat::Smooth { method: SmoothMethod::Lm, n: 80, residuals: false, formula: None, fullrange: false, se: Default::default() },
+            ..Default::default()
+        });
+        let spec = make_spec(layer, "x", "y");
+        let warnings = validate_spec(&spec, &make_data(&[("1", "2"), ("2", "3")]));
+        assert_eq!(kinds(&warnings), vec![WarnKind::SmallSampleSize]);
+    }
+
+    #[test]
+    fn test_too_many_and_duplicate_categories() {
+        let names: Vec<String> = (0..40).map(|i| format!("c{}", i)).collect();
+        let rows: Vec<(&str, &str)> = names.iter().map(|n| (n.as_str(), "1")).collect();
+        let spec = make_spec(Layer::Bar(BarLayer::default()), "x", "y");
+        assert_eq!(kinds(&validate_spec(&spec, &make_data(&rows))), vec![WarnKind::TooManyCategories]);
+
+        let warnings = validate_spec(&spec, &make_data(&[("a", "1"), ("a", "2"), ("b", "3")]));
+        assert_eq!(kinds(&warnings), vec![WarnKind::DuplicateCategories]);
+
+        // Stacked, filled and dodged bars don't overlap
+        for position in [LayerPosition::Stack, LayerPosition::Fill, LayerPosition::Dodge] {
+            let spec = make_spec(Layer::Bar(BarLayer { position, ..Default::default() }), "x", "y");
+            assert!(validate_spec(&spec, &make_data(&[("a", "1"), ("a", "2"), ("b", "3")])).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_column_not_found_suggests_closest() {
+        let spec = make_spec(Layer::Line(LineLayer::default()), "x", "temprature");
+        let data = PlotData::new(vec!["x".to_string(), "Temperature".to_string()], vec![vec!["1".to_string(), "2".to_string()]]);
+        let warnings = validate_spec(&spec, &data);
+        assert_eq!(kinds(&warnings), vec![WarnKind::ColumnNotFound]);
+        assert_eq!(warnings[0].suggestion, "Did you mean 'Temperature'?");
+
+        // Nothing close: list the columns instead
+        let spec = make_spec(Layer::Line(LineLayer::default()), "x", "pressure");
+        let warnings = validate_spec(&spec, &data);
+        assert_eq!(warnings[0].suggestion, "available columns: x, Temperature");
+
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_missing_range_values() {
+        let mut spec = make_spec(Layer::Line(LineLayer::default()), "x", "y");
+        spec.layers[0].aesthetics.ymin_col = Some("y".to_string());
+        let warnings = validate_spec(&spec, &make_data(&[("1", "10"), ("2", "")]));
+        assert_eq!(kinds(&warnings), vec![WarnKind::MissingValues]);
+    }
+
+    #[test]
+    fn test_estimate_render_cost() {
+        let (mut spec, _) = crate::parser::parse_plot_spec(
+            r#"aes(x: x, y: y, color: g) | point() | smooth(method: "loess") | facet_wrap(by: f)"#
+        ).unwrap();
+        let rows: Vec<Vec<String>> = (0..3000)
+            .map(|i| vec![i.to_string(), "1".to_string(), (i % 4).to_string(), (i % 2).to_string()])
+            .collect();
+        let data = PlotData::new(vec!["x".into(), "y".into(), "g".into(), "f".into()], rows);
+
+        let cost = estimate_render_cost(&spec, &data);
+        assert_eq!(cost, RenderCost { rows: 3000, groups: 4, panels: 2, stat: StatComplexity::ON2 });
+        assert!(cost.exceeds(DEFAULT_COST_THRESHOLD));
+        assert!(!cost.exceeds(20_000));
+
+        // Linear smoothing stays cheap regardless of size
+        spec.layers.pop();
+        let cost = estimate_render_cost(&spec, &data);
+        assert_eq!(cost.stat, StatComplexity::O1);
+        assert!(!cost.exceeds(DEFAULT_COST_THRESHOLD));
+    }
+
+    #[test]
+    fn test_log_of_negative() {
+        let mut spec = make_spec(Layer::Line(LineLayer::default()), "x", "y");
+        spec.y_scale_spec = Some(AxisScale { scale_type: ScaleType::Log10, ..Default::default() });
+        let data = make_data(&[("1", "10"), ("2", "0"), ("3", "-5")]);
+        assert!(validate_spec(&spec, &data).is_empty());
+        let err = check_log_scales(&spec, &data).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "scale_y_log10() requires all y values to be positive; found y = 0.0 at row 2 in column 'y'."
+        );
+
+        assert!(check_log_scales(&spec, &make_data(&[("1", "10"), ("2", "0.5")])).is_ok());
+        spec.x_scale_spec = spec.y_scale_spec.take();
+        let err = check_log_scales(&spec, &make_data(&[("-1", "10")])).unwrap_err();
+        assert!(err.to_string().starts_with("scale_x_log10() requires all x values"));
+    }
+}
@@ -0,0 +1,157 @@
+// Golden image tests: render known DSL + CSV combinations and compare the
+// decoded pixels against reference PNGs in tests/golden/.
+//
+// Set UPDATE_GOLDEN=1 to (re)write the reference images after an intended
+// visual change (or to create them for a new test). A missing reference fails.
+
+use gramgraph::csv_reader::read_csv_from_path;
+use gramgraph::data::PlotData;
+use gramgraph::parser::parse_plot_spec;
+use gramgraph::runtime::render_plot;
+use gramgraph::RenderOptions;
+use std::path::{Path, PathBuf};
+
+/// Fraction of pixels allowed to differ (anti-aliasing and font rasterization noise)
+const TOLERANCE: f64 = 0.01;
+
+/// Per-channel difference below which two pixels count as equal
+const CHANNEL_THRESHOLD: u8 = 16;
+
+fn render(dsl: &str, fixture: &str) -> Vec<u8> {
+    let csv = read_csv_from_path(Path::new(fixture)).expect("Failed to read fixture");
+    let (spec, _) = parse_plot_spec(dsl).expect("Failed to parse DSL");
+    let options = RenderOptions { width: 400, height: 300, ..RenderOptions::default() };
+    render_plot(spec, PlotData::from_csv(csv), options).expect("Failed to render")
+}
+
+/// Fraction of pixels that differ between two PNGs (1.0 if the sizes differ)
+fn diff_fraction(actual: &[u8], expected: &[u8]) -> f64 {
+    let actual = image::load_from_memory(actual).expect("Invalid rendered PNG").to_rgba8();
+    let expected = image::load_from_memory(expected).expect("Invalid golden PNG").to_rgba8();
+    if actual.dimensions() != expected.dimensions() {
+        return 1.0;
+    }
+
+    let differing = actual
+        .pixels()
+        .zip(expected.pixels())
+        .filter(|(a, e)| a.0.iter().zip(e.0.iter()).any(|(x, y)| x.abs_diff(*y) > CHANNEL_THRESHOLD))
+        .count();
+    differing as f64 / actual.pixels().len() as f64
+}
+
+fn assert_golden(name: &str, dsl: &str, fixture: &str) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", &format!("{}.png", name)].iter().collect();
+    let actual = render(dsl, fixture);
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &actual).expect("Failed to write golden image");
+        return;
+    }
+    assert!(path.exists(), "{}: missing golden image {}; rerun with UPDATE_GOLDEN=1 to create it", name, path.display());
+
+    let expected = std::fs::read(&path).expect("Failed to read golden image");
+    let diff = diff_fraction(&actual, &expected);
+    assert!(
+        diff <= TOLERANCE,
+        "{}: {:.2}% of pixels differ from {} (tolerance {:.2}%); rerun with UPDATE_GOLDEN=1 if the change is intended",
+        name,
+        diff * 100.0,
+        path.display(),
+        TOLERANCE * 100.0
+    );
+}
+
+#[test]
+fn golden_line_chart() {
+    assert_golden("line", "aes(x: date, y: temperature) | line() | point()", "fixtures/timeseries.csv");
+}
+
+#[test]
+fn golden_grouped_lines() {
+    assert_golden("grouped_lines", "aes(x: time, y: value, color: group) | line() | labs(title: \"Groups\")", "fixtures/simple_grouped.csv");
+}
+
+#[test]
+fn golden_dodged_bars() {
+    assert_golden("dodged_bars", "aes(x: region, y: sales, color: product) | bar(position: \"dodge\")", "fixtures/multiregion_sales.csv");
+}
+
+#[test]
+fn golden_boxplot() {
+    assert_golden("boxplot", "aes(x: species, y: sepal_length, color: species) | boxplot()", "fixtures/iris.csv");
+}
+
+#[test]
+fn golden_violin() {
+    assert_golden("violin", "aes(x: species, y: sepal_width) | violin(draw_quantiles: [0.5])", "fixtures/iris.csv");
+}
+
+#[test]
+fn golden_histogram_minimal_theme() {
+    assert_golden("histogram_minimal", "aes(x: sepal_length) | histogram(bins: 15) | theme_minimal()", "fixtures/iris.csv");
+}
+
+#[test]
+fn golden_facets() {
+    assert_golden("facets", "aes(x: sepal_length, y: sepal_width) | point() | facet_wrap(by: species)", "fixtures/iris.csv");
+}
+
+#[test]
+fn golden_rotated_category_labels() {
+    assert_golden("rotated_labels", "aes(x: species, y: sepal_length) | boxplot() | theme(axis_text: element_text(angle: 90))", "fixtures/iris.csv");
+}
+
+#[test]
+fn golden_flipped_bars() {
+    assert_golden("flipped_bars", "aes(x: species, y: sepal_width) | bar(stat: \"count\") | coord_flip() | labs(x: \"Species\")", "fixtures/iris.csv");
+}
+
+#[test]
+fn golden_flipped_boxplot() {
+    assert_golden("flipped_boxplot", "aes(x: species, y: sepal_length, color: species) | boxplot() | coord_flip()", "fixtures/iris.csv");
+}
+
+#[test]
+fn golden_flipped_violin() {
+    assert_golden("flipped_violin", "aes(x: species, y: sepal_width) | violin(draw_quantiles: [0.5]) | coord_flip()", "fixtures/iris.csv");
+}
+
+#[test]
+fn golden_axis_breaks() {
+    assert_golden("axis_breaks", "aes(x: sepal_length, y: sepal_width) | point() | scale_x_continuous(breaks_width: 0.25) | scale_y_continuous(breaks_n: 3)", "fixtures/iris.csv");
+}
+
+#[test]
+fn golden_comma_labels() {
+    assert_golden("comma_labels", "aes(x: date, y: temperature) | mutate(temperature = temperature * 1000) | line() | scale_y_continuous(labels: \"comma\")", "fixtures/timeseries.csv");
+}
+
+#[test]
+fn golden_ribbon() {
+    assert_golden("ribbon", "aes(x: date, y: temperature, ymin: lower, ymax: upper) | mutate(lower = temperature - 2, upper = temperature + 2) | ribbon(alpha: 0.3) | line()", "fixtures/timeseries.csv");
+}
+
+#[test]
+fn golden_heatmap() {
+    assert_golden("heatmap", "aes(x: day, y: hour) | tile(fill: visits, color: \"white\") | scale_fill_continuous(low: \"white\", high: \"steelblue\")", "fixtures/heatmap.csv");
+}
+
+#[test]
+fn golden_hexbin() {
+    assert_golden("hexbin", "aes(x: x, y: y) | hexbin(bins: 20)", "fixtures/bivariate.csv");
+}
+
+#[test]
+fn golden_contour() {
+    assert_golden("contour", "aes(x: x, y: y) | contour(bins: 6) | point(alpha: 0.2, size: 2)", "fixtures/bivariate.csv");
+}
+
+#[test]
+fn diff_fraction_detects_changes() {
+    let a = render("aes(x: height, y: weight) | point()", "fixtures/scatter.csv");
+    let b = render("aes(x: height, y: weight) | point(size: 8, color: \"red\")", "fixtures/scatter.csv");
+    assert_eq!(diff_fraction(&a, &a), 0.0);
+    assert!(diff_fraction(&a, &b) > 0.0);
+}
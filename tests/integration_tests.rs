@@ -5,7 +5,7 @@ use std::process::{Command, Stdio};
 /// Helper function to run gramgraph with DSL and CSV input
 fn run_gramgraph(dsl: &str, csv_content: &str) -> Result<Vec<u8>, String> {
     let mut child = Command::new("cargo")
-        .args(&["run", "--bin", "gramgraph", "--", dsl])
+        .args(["run", "--bin", "gramgraph", "--", dsl])
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -32,7 +32,7 @@ fn run_gramgraph(dsl: &str, csv_content: &str) -> Result<Vec<u8>, String> {
 
 /// Check if bytes are a valid PNG
 fn is_valid_png(bytes: &[u8]) -> bool {
-    bytes.len() > 8 && &bytes[0..8] == &[137, 80, 78, 71, 13, 10, 26, 10]
+    bytes.len() > 8 && bytes[0..8] == [137, 80, 78, 71, 13, 10, 26, 10]
 }
 
 #[test]
@@ -74,6 +74,50 @@ fn test_end_to_end_bar_chart() {
     assert!(is_valid_png(&png_bytes));
 }
 
+#[test]
+fn test_end_to_end_bar_count_without_y() {
+    let csv = fs::read_to_string("fixtures/bar_chart.csv").expect("Failed to read test CSV");
+    let result = run_gramgraph("aes(x: category) | bar()", &csv);
+    assert!(result.is_ok(), "Failed: {:?}", result.err());
+    let png_bytes = result.unwrap();
+    assert!(is_valid_png(&png_bytes));
+}
+
+#[test]
+fn test_end_to_end_grouped_histogram() {
+    let csv = fs::read_to_string("fixtures/iris.csv").expect("Failed to read test CSV");
+    for position in ["stack", "identity", "dodge"] {
+        let dsl = format!(r#"aes(x: sepal_length) | histogram(bins: 10, fill: species, alpha: 0.5, position: "{}")"#, position);
+        let result = run_gramgraph(&dsl, &csv);
+        assert!(result.is_ok(), "Failed ({}): {:?}", position, result.err());
+        assert!(is_valid_png(&result.unwrap()));
+    }
+}
+
+#[test]
+fn test_end_to_end_pivot_wider() {
+    let csv = fs::read_to_string("fixtures/stacked_data.csv").expect("Failed to read test CSV");
+    let result = run_gramgraph(
+        "pivot_wider(id_cols: [category], names_from: segment, values_from: value) | aes(x: category, y: S1) | bar() | point(y: S2)",
+        &csv,
+    );
+    assert!(result.is_ok(), "Failed: {:?}", result.err());
+    let png_bytes = result.unwrap();
+    assert!(is_valid_png(&png_bytes));
+}
+
+#[test]
+fn test_end_to_end_group_summarize() {
+    let csv = fs::read_to_string("fixtures/stacked_data.csv").expect("Failed to read test CSV");
+    let result = run_gramgraph(
+        "group_by(category) | summarize(total = sum(value)) | mutate(half = total / 2) | aes(x: category, y: total) | bar() | point(y: half)",
+        &csv,
+    );
+    assert!(result.is_ok(), "Failed: {:?}", result.err());
+    let png_bytes = result.unwrap();
+    assert!(is_valid_png(&png_bytes));
+}
+
 #[test]
 fn test_end_to_end_dodge_bars() {
     let csv = fs::read_to_string("fixtures/sales.csv").expect("Failed to read test CSV");
@@ -98,6 +142,16 @@ fn test_end_to_end_stack_bars() {
     assert!(is_valid_png(&png_bytes));
 }
 
+#[test]
+fn test_end_to_end_percent_bars_match_fill() {
+    let csv = fs::read_to_string("fixtures/stacked_data.csv").expect("Failed to read test CSV");
+    let dsl = |position: &str| format!("aes(x: category, y: value, color: segment) | bar(position: \"{}\")", position);
+    let fill = run_gramgraph(&dsl("fill"), &csv).expect("fill failed");
+    let percent = run_gramgraph(&dsl("percent"), &csv).expect("percent failed");
+    assert!(is_valid_png(&percent));
+    assert_eq!(fill, percent);
+}
+
 #[test]
 fn test_end_to_end_invalid_syntax() {
     let csv = "x,y\n1,10\n2,20\n";
@@ -268,3 +322,12 @@ fn test_end_to_end_boxplot() {
     let png_bytes = result.unwrap();
     assert!(is_valid_png(&png_bytes));
 }
+
+#[test]
+fn test_end_to_end_theme_void() {
+    let csv = fs::read_to_string("fixtures/timeseries.csv").expect("Failed to read test CSV");
+    let result = run_gramgraph("aes(x: date, y: temperature) | line() | theme_void()", &csv);
+    assert!(result.is_ok(), "Failed: {:?}", result.err());
+    let png_bytes = result.unwrap();
+    assert!(is_valid_png(&png_bytes));
+}